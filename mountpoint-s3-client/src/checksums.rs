@@ -1,6 +1,7 @@
 //! Provides base64 encoding/decoding for various checksums.
 pub use mountpoint_s3_crt::checksums::crc32::{self, Crc32};
 pub use mountpoint_s3_crt::checksums::crc32c::{self, Crc32c};
+pub use mountpoint_s3_crt::checksums::crc64::{self, Crc64};
 pub use mountpoint_s3_crt::checksums::sha1::{self, Sha1};
 pub use mountpoint_s3_crt::checksums::sha256::{self, Sha256};
 
@@ -32,6 +33,18 @@ pub fn crc32_from_base64(base64_str: &str) -> Result<Crc32, ParseError> {
     Ok(Crc32::new(u32::from_be_bytes(dec_buf)))
 }
 
+/// The base64 encoding for this CRC64NVME checksum value.
+pub fn crc64nvme_to_base64(checksum: &Crc64) -> String {
+    Base64::encode_string(&checksum.value().to_be_bytes())
+}
+
+/// Create a CRC64NVME checksum from a base64 encoding.
+pub fn crc64nvme_from_base64(base64_str: &str) -> Result<Crc64, ParseError> {
+    let mut dec_buf = [0u8; std::mem::size_of::<u64>()];
+    let _ = Base64::decode(base64_str, &mut dec_buf)?;
+    Ok(Crc64::new(u64::from_be_bytes(dec_buf)))
+}
+
 /// The base64 encoding for this SHA1 checksum value.
 pub fn sha1_to_base64(checksum: &Sha1) -> String {
     Base64::encode_string(checksum.value())
@@ -99,6 +112,28 @@ mod tests {
         assert!(matches!(err, ParseError::Base64ParseError(_)));
     }
 
+    #[test]
+    fn test_crc64nvme_to_base64() {
+        let crc = Crc64::new(1234);
+        let base64 = crc64nvme_to_base64(&crc);
+        assert_eq!(&base64, "AAAAAAAABNI=");
+    }
+
+    #[test]
+    fn test_crc64nvme_from_base64() {
+        let base64 = "AAAAAAAABNI=";
+        let crc = crc64nvme_from_base64(base64).expect("parsing should succeeed");
+        assert_eq!(crc.value(), 1234);
+    }
+
+    #[test_case("AAA")]
+    #[test_case("AAAAAAAABNI")]
+    #[test_case("AAAAAAAABNIAAA==")]
+    fn test_crc64nvme_from_base64_error(invalid_base64: &str) {
+        let err = crc64nvme_from_base64(invalid_base64).expect_err("parsing should fail");
+        assert!(matches!(err, ParseError::Base64ParseError(_)));
+    }
+
     #[test]
     fn test_sha1_to_base64() {
         let sha1 = Sha1::new([
@@ -14,11 +14,18 @@ use mountpoint_s3_crt::s3::client::BufferPoolUsageStats;
 use pin_project::pin_project;
 
 use crate::object_client::{
-    Checksum, CopyObjectError, CopyObjectParams, CopyObjectResult, DeleteObjectError, DeleteObjectResult, GetBodyPart,
+    AbortMultipartUploadError, AbortMultipartUploadResult, Checksum, CompleteMultipartUploadError,
+    CompleteMultipartUploadParams, CompleteMultipartUploadResult, CompletedPart, CopyObjectError, CopyObjectParams,
+    CopyObjectResult, CreateMultipartUploadError, CreateMultipartUploadParams, CreateMultipartUploadResult,
+    DeleteObjectError, DeleteObjectResult, DeleteObjectsError, DeleteObjectsResult, GetBodyPart,
     GetObjectAttributesError, GetObjectAttributesResult, GetObjectError, GetObjectParams, GetObjectResponse,
-    HeadObjectError, HeadObjectParams, HeadObjectResult, ListObjectsError, ListObjectsResult, ObjectAttribute,
-    ObjectChecksumError, ObjectClient, ObjectClientError, ObjectClientResult, ObjectMetadata, PutObjectError,
-    PutObjectParams, PutObjectRequest, PutObjectResult, PutObjectSingleParams, UploadReview,
+    GetObjectTaggingError, GetObjectTaggingResult, HeadObjectError, HeadObjectParams, HeadObjectResult,
+    ListMultipartUploadsError, ListMultipartUploadsResult, ListObjectVersionsError, ListObjectVersionsResult,
+    ListObjectsError, ListObjectsResult, ListPartsError, ListPartsResult, ObjectAttribute, ObjectChecksumError,
+    ObjectClient, ObjectClientError, ObjectClientResult, ObjectMetadata, ObjectTags, PutObjectError, PutObjectParams,
+    PutObjectRequest, PutObjectResult, PutObjectSingleParams, PutObjectTaggingError, PutObjectTaggingResult,
+    RestoreObjectError, RestoreObjectParams, RestoreObjectResult, UploadPartError, UploadPartParams, UploadPartResult,
+    UploadReview,
 };
 
 // Wrapper for injecting failures into a get stream or a put request
@@ -42,6 +49,8 @@ pub struct FailureClient<Client: ObjectClient, State, RequestWrapperState> {
     >,
     pub head_object_cb:
         fn(&mut State, &str, &str) -> Result<(), ObjectClientError<HeadObjectError, Client::ClientError>>,
+    pub get_object_tagging_cb:
+        fn(&mut State, &str, &str) -> Result<(), ObjectClientError<GetObjectTaggingError, Client::ClientError>>,
     pub list_objects_cb: fn(
         &mut State,
         &str,
@@ -104,6 +113,15 @@ where
         self.client.delete_object(bucket, key).await
     }
 
+    async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+    ) -> ObjectClientResult<DeleteObjectsResult, DeleteObjectsError, Self::ClientError> {
+        // TODO failure hook for delete_objects
+        self.client.delete_objects(bucket, keys).await
+    }
+
     async fn copy_object(
         &self,
         source_bucket: &str,
@@ -154,6 +172,21 @@ where
             .await
     }
 
+    async fn list_object_versions(
+        &self,
+        bucket: &str,
+        key_marker: Option<&str>,
+        version_id_marker: Option<&str>,
+        delimiter: &str,
+        max_keys: usize,
+        prefix: &str,
+    ) -> ObjectClientResult<ListObjectVersionsResult, ListObjectVersionsError, Self::ClientError> {
+        // TODO failure hook for list_object_versions
+        self.client
+            .list_object_versions(bucket, key_marker, version_id_marker, delimiter, max_keys, prefix)
+            .await
+    }
+
     async fn head_object(
         &self,
         bucket: &str,
@@ -203,6 +236,109 @@ where
             .get_object_attributes(bucket, key, max_parts, part_number_marker, object_attributes)
             .await
     }
+
+    async fn get_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> ObjectClientResult<GetObjectTaggingResult, GetObjectTaggingError, Self::ClientError> {
+        (self.get_object_tagging_cb)(&mut *self.state.lock().unwrap(), bucket, key)?;
+        self.client.get_object_tagging(bucket, key).await
+    }
+
+    async fn put_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+        tags: &ObjectTags,
+    ) -> ObjectClientResult<PutObjectTaggingResult, PutObjectTaggingError, Self::ClientError> {
+        // TODO failure hook for put_object_tagging
+        self.client.put_object_tagging(bucket, key, tags).await
+    }
+
+    async fn restore_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &RestoreObjectParams,
+    ) -> ObjectClientResult<RestoreObjectResult, RestoreObjectError, Self::ClientError> {
+        // TODO failure hook for restore_object
+        self.client.restore_object(bucket, key, params).await
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &CreateMultipartUploadParams,
+    ) -> ObjectClientResult<CreateMultipartUploadResult, CreateMultipartUploadError, Self::ClientError> {
+        // TODO failure hook for create_multipart_upload
+        self.client.create_multipart_upload(bucket, key, params).await
+    }
+
+    async fn upload_part<'a>(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: usize,
+        params: &UploadPartParams,
+        contents: impl AsRef<[u8]> + Send + 'a,
+    ) -> ObjectClientResult<UploadPartResult, UploadPartError, Self::ClientError> {
+        // TODO failure hook for upload_part
+        self.client
+            .upload_part(bucket, key, upload_id, part_number, params, contents)
+            .await
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: &[CompletedPart],
+        params: &CompleteMultipartUploadParams,
+    ) -> ObjectClientResult<CompleteMultipartUploadResult, CompleteMultipartUploadError, Self::ClientError> {
+        // TODO failure hook for complete_multipart_upload
+        self.client
+            .complete_multipart_upload(bucket, key, upload_id, parts, params)
+            .await
+    }
+
+    async fn list_parts(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number_marker: Option<usize>,
+    ) -> ObjectClientResult<ListPartsResult, ListPartsError, Self::ClientError> {
+        // TODO failure hook for list_parts
+        self.client.list_parts(bucket, key, upload_id, part_number_marker).await
+    }
+
+    async fn list_multipart_uploads(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        key_marker: Option<&str>,
+        upload_id_marker: Option<&str>,
+        max_uploads: usize,
+    ) -> ObjectClientResult<ListMultipartUploadsResult, ListMultipartUploadsError, Self::ClientError> {
+        // TODO failure hook for list_multipart_uploads
+        self.client
+            .list_multipart_uploads(bucket, prefix, key_marker, upload_id_marker, max_uploads)
+            .await
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> ObjectClientResult<AbortMultipartUploadResult, AbortMultipartUploadError, Self::ClientError> {
+        // TODO failure hook for abort_multipart_upload
+        self.client.abort_multipart_upload(bucket, key, upload_id).await
+    }
 }
 
 #[pin_project]
@@ -299,6 +435,9 @@ pub struct CountdownFailureConfig<ClientError> {
     /// For HEAD, map entries are interpreted as follows:
     ///   (k -> E) means inject error E on the k'th call to that operation
     pub head_failures: HashMap<usize, ObjectClientError<HeadObjectError, ClientError>>,
+    /// For GetObjectTagging, map entries are interpreted as follows:
+    ///   (k -> E) means inject error E on the k'th call to that operation
+    pub get_object_tagging_failures: HashMap<usize, ObjectClientError<GetObjectTaggingError, ClientError>>,
     /// For LIST, map entries are interpreted as follows:
     ///   (k -> E) means inject error E on the k'th call to that operation
     pub list_failures: HashMap<usize, ObjectClientError<ListObjectsError, ClientError>>,
@@ -319,6 +458,8 @@ pub struct CountdownFailureClientState<ClientError> {
     get_failures: RequestFailureMap<ClientError, GetObjectError>,
     head_count: usize,
     head_failures: HashMap<usize, ObjectClientError<HeadObjectError, ClientError>>,
+    get_object_tagging_count: usize,
+    get_object_tagging_failures: HashMap<usize, ObjectClientError<GetObjectTaggingError, ClientError>>,
     list_count: usize,
     list_failures: HashMap<usize, ObjectClientError<ListObjectsError, ClientError>>,
     put_single_count: usize,
@@ -344,6 +485,8 @@ pub fn countdown_failure_client<Client: ObjectClient>(
         get_failures: config.get_failures,
         head_count: 0usize,
         head_failures: config.head_failures,
+        get_object_tagging_count: 0usize,
+        get_object_tagging_failures: config.get_object_tagging_failures,
         list_count: 0usize,
         list_failures: config.list_failures,
         put_single_count: 0usize,
@@ -386,6 +529,17 @@ pub fn countdown_failure_client<Client: ObjectClient>(
                 Ok(())
             }
         },
+        get_object_tagging_cb: |state, _bucket, _key| {
+            state.get_object_tagging_count += 1;
+            if let Some(error) = state
+                .get_object_tagging_failures
+                .remove(&state.get_object_tagging_count)
+            {
+                Err(error)
+            } else {
+                Ok(())
+            }
+        },
         list_objects_cb: |state, _bucket, _ct, _delim, _max_keys, _prefix| {
             state.list_count += 1;
             if let Some(error) = state.list_failures.remove(&state.list_count) {
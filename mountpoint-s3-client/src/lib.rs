@@ -72,11 +72,17 @@ pub mod config {
 /// Types used by all object clients
 pub mod types {
     pub use super::object_client::{
-        Checksum, ChecksumAlgorithm, ChecksumMode, ClientBackpressureHandle, CopyObjectParams, CopyObjectResult,
-        DeleteObjectResult, ETag, GetBodyPart, GetObjectAttributesParts, GetObjectAttributesResult, GetObjectParams,
-        GetObjectResponse, HeadObjectParams, HeadObjectResult, ListObjectsResult, ObjectAttribute, ObjectClientResult,
-        ObjectInfo, ObjectPart, PutObjectParams, PutObjectResult, PutObjectSingleParams, PutObjectTrailingChecksums,
-        RestoreStatus, UploadChecksum, UploadReview, UploadReviewPart,
+        AbortMultipartUploadResult, Checksum, ChecksumAlgorithm, ChecksumMode, ChecksumType, ClientBackpressureHandle,
+        CompleteMultipartUploadParams, CompleteMultipartUploadResult, CompletedPart, CopyObjectParams,
+        CopyObjectResult, CreateMultipartUploadParams, CreateMultipartUploadResult, DeleteObjectResult,
+        DeleteObjectsKeyError, DeleteObjectsResult, ETag, GetBodyPart, GetObjectAttributesParts,
+        GetObjectAttributesResult, GetObjectParams, GetObjectResponse, GetObjectTaggingResult, HeadObjectParams,
+        HeadObjectResult, ListMultipartUploadsResult, ListObjectVersionsResult, ListObjectsResult, ListPartsResult,
+        MultipartUploadInfo, ObjectAttribute, ObjectClientResult, ObjectInfo, ObjectLock, ObjectPart, ObjectTags,
+        ObjectVersionInfo, PutObjectParams, PutObjectResult, PutObjectSingleParams, PutObjectTaggingResult,
+        PutObjectTrailingChecksums, RestoreObjectParams, RestoreObjectResult, RestoreStatus, RestoreTier,
+        SseCustomerKey, UploadChecksum, UploadPartParams, UploadPartResult, UploadReview, UploadReviewPart,
+        UploadedPart, MAX_DELETE_OBJECTS_KEYS,
     };
 }
 
@@ -87,8 +93,11 @@ pub mod types {
 /// client errors. See its documentation for more details.
 pub mod error {
     pub use super::object_client::{
-        CopyObjectError, DeleteObjectError, GetObjectAttributesError, GetObjectError, HeadObjectError,
-        ListObjectsError, ObjectClientError, PutObjectError,
+        AbortMultipartUploadError, CompleteMultipartUploadError, CopyObjectError, CreateMultipartUploadError,
+        DeleteObjectError, DeleteObjectsError, GetObjectAttributesError, GetObjectError, GetObjectTaggingError,
+        HeadObjectError, InvalidSseCustomerKeyError, ListMultipartUploadsError, ListObjectVersionsError,
+        ListObjectsError, ListPartsError, ObjectClientError, PutObjectError, PutObjectTaggingError, RestoreObjectError,
+        UploadPartError,
     };
     #[doc(hidden)]
     pub use super::s3_crt_client::HeadBucketError;
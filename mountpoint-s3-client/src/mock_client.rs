@@ -6,6 +6,7 @@ use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::Write;
 use std::pin::Pin;
+use std::slice;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 use std::task::{Context, Poll};
@@ -23,17 +24,26 @@ use time::OffsetDateTime;
 use tracing::trace;
 
 use crate::checksums::{
-    crc32, crc32_to_base64, crc32c, crc32c_to_base64, sha1, sha1_to_base64, sha256, sha256_to_base64,
+    crc32, crc32_to_base64, crc32c, crc32c_to_base64, crc64, crc64nvme_to_base64, sha1, sha1_to_base64, sha256,
+    sha256_to_base64,
 };
 use crate::error_metadata::{ClientErrorMetadata, ProvideErrorMetadata};
 use crate::object_client::{
-    Checksum, ChecksumAlgorithm, ChecksumMode, ClientBackpressureHandle, CopyObjectError, CopyObjectParams,
-    CopyObjectResult, DeleteObjectError, DeleteObjectResult, ETag, GetBodyPart, GetObjectAttributesError,
-    GetObjectAttributesParts, GetObjectAttributesResult, GetObjectError, GetObjectParams, GetObjectResponse,
-    HeadObjectError, HeadObjectParams, HeadObjectResult, ListObjectsError, ListObjectsResult, ObjectAttribute,
-    ObjectChecksumError, ObjectClient, ObjectClientError, ObjectClientResult, ObjectInfo, ObjectMetadata, ObjectPart,
-    PutObjectError, PutObjectParams, PutObjectRequest, PutObjectResult, PutObjectSingleParams,
-    PutObjectTrailingChecksums, RestoreStatus, UploadChecksum, UploadReview, UploadReviewPart,
+    AbortMultipartUploadError, AbortMultipartUploadResult, Checksum, ChecksumAlgorithm, ChecksumMode, ChecksumType,
+    ClientBackpressureHandle, CompleteMultipartUploadError, CompleteMultipartUploadParams,
+    CompleteMultipartUploadResult, CompletedPart, CopyObjectError, CopyObjectParams, CopyObjectResult,
+    CreateMultipartUploadError, CreateMultipartUploadParams, CreateMultipartUploadResult, DeleteObjectError,
+    DeleteObjectResult, DeleteObjectsError, DeleteObjectsKeyError, DeleteObjectsResult, ETag, GetBodyPart,
+    GetObjectAttributesError, GetObjectAttributesParts, GetObjectAttributesResult, GetObjectError, GetObjectParams,
+    GetObjectResponse, GetObjectTaggingError, GetObjectTaggingResult, HeadObjectError, HeadObjectParams,
+    HeadObjectResult, ListMultipartUploadsError, ListMultipartUploadsResult, ListObjectVersionsError,
+    ListObjectVersionsResult, ListObjectsError, ListObjectsResult, ListPartsError, ListPartsResult,
+    MultipartUploadInfo, ObjectAttribute, ObjectChecksumError, ObjectClient, ObjectClientError, ObjectClientResult,
+    ObjectInfo, ObjectLock, ObjectMetadata, ObjectPart, ObjectTags, ObjectVersionInfo, PutObjectError, PutObjectParams,
+    PutObjectRequest, PutObjectResult, PutObjectSingleParams, PutObjectTaggingError, PutObjectTaggingResult,
+    PutObjectTrailingChecksums, RestoreObjectError, RestoreObjectParams, RestoreObjectResult, RestoreStatus,
+    SseCustomerKey, UploadChecksum, UploadPartError, UploadPartParams, UploadPartResult, UploadReview,
+    UploadReviewPart, UploadedPart, MAX_DELETE_OBJECTS_KEYS,
 };
 
 mod leaky_bucket;
@@ -80,7 +90,10 @@ pub struct MockClientConfig {
 pub struct MockClient {
     config: MockClientConfig,
     objects: Arc<RwLock<BTreeMap<String, MockObject>>>,
+    versions: Arc<RwLock<BTreeMap<String, Vec<MockObjectVersion>>>>,
     in_progress_uploads: Arc<RwLock<BTreeSet<String>>>,
+    multipart_uploads: Arc<RwLock<BTreeMap<String, MockMultipartUpload>>>,
+    next_upload_id: Arc<AtomicU64>,
     operation_counts: Arc<RwLock<HashMap<Operation, u64>>>,
 }
 
@@ -88,13 +101,34 @@ fn add_object(objects: &Arc<RwLock<BTreeMap<String, MockObject>>>, key: &str, va
     objects.write().unwrap().insert(key.to_owned(), value);
 }
 
+/// Add an object only if the etag of the pre-existing object matches `if_match` (when provided).
+fn add_object_if_match(
+    objects: &Arc<RwLock<BTreeMap<String, MockObject>>>,
+    key: &str,
+    value: MockObject,
+    if_match: Option<&ETag>,
+) -> Result<(), PutObjectError> {
+    let mut objects = objects.write().unwrap();
+    if let Some(etag) = if_match {
+        match objects.get(key) {
+            Some(object) if object.etag == *etag => {}
+            _ => return Err(PutObjectError::PreconditionFailed),
+        }
+    }
+    objects.insert(key.to_owned(), value);
+    Ok(())
+}
+
 impl MockClient {
     /// Create a new [MockClient] with the given config
     pub fn new(config: MockClientConfig) -> Self {
         Self {
             config,
             objects: Default::default(),
+            versions: Default::default(),
             in_progress_uploads: Default::default(),
+            multipart_uploads: Default::default(),
+            next_upload_id: Default::default(),
             operation_counts: Default::default(),
         }
     }
@@ -104,6 +138,34 @@ impl MockClient {
         add_object(&self.objects, key, value);
     }
 
+    /// Add a version of an object to this mock client's bucket, visible to
+    /// [ObjectClient::list_object_versions] and to requests for a specific version. Versions are
+    /// kept separately from the objects added with [MockClient::add_object], and are ordered by
+    /// their last modified time (see [MockObject::set_last_modified]).
+    pub fn add_object_version(&self, key: &str, version_id: &str, value: MockObject) {
+        self.add_version(key, version_id, value.last_modified, Some(value));
+    }
+
+    /// Add a delete marker to the versions of an object in this mock client's bucket.
+    pub fn add_delete_marker(&self, key: &str, version_id: &str, last_modified: OffsetDateTime) {
+        self.add_version(key, version_id, last_modified, None);
+    }
+
+    fn add_version(&self, key: &str, version_id: &str, last_modified: OffsetDateTime, object: Option<MockObject>) {
+        let mut versions = self.versions.write().unwrap();
+        let key_versions = versions.entry(key.to_owned()).or_default();
+        // Most recent first, and the last one added first among versions with the same time.
+        key_versions.insert(
+            0,
+            MockObjectVersion {
+                version_id: version_id.to_owned(),
+                last_modified,
+                object,
+            },
+        );
+        key_versions.sort_by(|a, b| b.last_modified.cmp(&a.last_modified));
+    }
+
     /// Remove object for the mock client's bucket
     pub fn remove_object(&self, key: &str) {
         self.objects.write().unwrap().remove(key);
@@ -135,6 +197,28 @@ impl MockClient {
         self.in_progress_uploads.read().unwrap().contains(key)
     }
 
+    /// Number of multipart uploads created with [ObjectClient::create_multipart_upload] that have
+    /// not yet been completed or aborted
+    pub fn multipart_upload_count(&self) -> usize {
+        self.multipart_uploads.read().unwrap().len()
+    }
+
+    /// Set the time a multipart upload was initiated, as reported by
+    /// [ObjectClient::list_multipart_uploads]
+    pub fn set_multipart_upload_initiated(
+        &self,
+        upload_id: &str,
+        initiated: OffsetDateTime,
+    ) -> Result<(), MockClientError> {
+        match self.multipart_uploads.write().unwrap().get_mut(upload_id) {
+            Some(upload) => {
+                upload.initiated = initiated;
+                Ok(())
+            }
+            None => Err(MockClientError("upload not found".into())),
+        }
+    }
+
     /// Returns the objects storage class
     pub fn get_object_storage_class(&self, key: &str) -> Result<Option<String>, MockClientError> {
         if let Some(mock_object) = self.objects.read().unwrap().get(key) {
@@ -144,6 +228,33 @@ impl MockClient {
         }
     }
 
+    /// Returns the canned ACL the object was uploaded with
+    pub fn get_object_acl(&self, key: &str) -> Result<Option<String>, MockClientError> {
+        if let Some(mock_object) = self.objects.read().unwrap().get(key) {
+            Ok(mock_object.acl.to_owned())
+        } else {
+            Err(MockClientError("object not found".into()))
+        }
+    }
+
+    /// Returns the Object Lock retention and legal hold the object was uploaded with
+    pub fn get_object_lock(&self, key: &str) -> Result<ObjectLock, MockClientError> {
+        if let Some(mock_object) = self.objects.read().unwrap().get(key) {
+            Ok(mock_object.object_lock.clone())
+        } else {
+            Err(MockClientError("object not found".into()))
+        }
+    }
+
+    /// Returns the content type the object was uploaded with
+    pub fn get_object_content_type(&self, key: &str) -> Result<Option<String>, MockClientError> {
+        if let Some(mock_object) = self.objects.read().unwrap().get(key) {
+            Ok(mock_object.content_type.to_owned())
+        } else {
+            Err(MockClientError("object not found".into()))
+        }
+    }
+
     /// Returns error if object does not exist
     pub fn restore_object(&self, key: &str) -> Result<(), MockClientError> {
         match self.objects.write().unwrap().get_mut(key) {
@@ -196,11 +307,17 @@ impl MockClient {
 
         let mut object: MockObject = contents.into();
         object.set_storage_class(params.storage_class.clone());
+        object.set_acl(params.acl.clone());
+        object.set_content_type(params.content_type.clone());
+        object.set_sse_customer_key(params.sse_customer_key.clone());
+        object.set_object_lock(params.object_lock.clone());
         object.set_object_metadata(params.object_metadata.clone());
+        object.set_object_tags(params.object_tags.clone());
         object.set_checksum(checksum);
 
         let etag = object.etag.clone();
-        add_object(&self.objects, key, object);
+        add_object_if_match(&self.objects, key, object, params.if_match.as_ref())
+            .map_err(ObjectClientError::ServiceError)?;
         Ok(PutObjectResult {
             etag,
             sse_type: None,
@@ -375,6 +492,99 @@ impl MockClient {
         }
     }
 
+    fn list_object_versions_ordered(
+        &self,
+        key_marker: Option<&str>,
+        version_id_marker: Option<&str>,
+        delimiter: &str,
+        max_keys: usize,
+        prefix: &str,
+    ) -> ListObjectVersionsResult {
+        let delimiter = (!delimiter.is_empty()).then_some(delimiter);
+
+        let versions = self.versions.read().unwrap();
+
+        let mut version_vec: Vec<ObjectVersionInfo> = Vec::new();
+        let mut common_prefixes: Vec<String> = Vec::new();
+        // The key and version of the last entry we returned, in case we need to truncate the list.
+        let mut last_entry: Option<(String, Option<String>)> = None;
+        let mut truncated = false;
+
+        'keys: for (key, key_versions) in versions.range(key_marker.unwrap_or(prefix).to_string()..) {
+            let Some(no_prefix_key) = key.strip_prefix(prefix) else {
+                continue;
+            };
+
+            if let Some((pre, _)) = delimiter.and_then(|d| no_prefix_key.split_once(d)) {
+                let common_prefix = format!("{}{}{}", prefix, pre, delimiter.unwrap());
+                // Skip common prefixes that were already returned, either in this page or a previous one.
+                if common_prefixes.last() == Some(&common_prefix)
+                    || key_marker.is_some_and(|marker| common_prefix.as_str() <= marker)
+                {
+                    continue;
+                }
+                if version_vec.len() + common_prefixes.len() >= max_keys {
+                    truncated = true;
+                    break;
+                }
+                common_prefixes.push(common_prefix.clone());
+                last_entry = Some((common_prefix, None));
+                continue;
+            }
+
+            // Resume after the marker version, or after all the versions of the marker key.
+            let skip = match (key_marker, version_id_marker) {
+                (Some(marker), Some(version_id)) if marker == key => key_versions
+                    .iter()
+                    .position(|version| version.version_id == version_id)
+                    .map_or(0, |index| index + 1),
+                (Some(marker), None) if marker == key => key_versions.len(),
+                _ => 0,
+            };
+
+            for (index, version) in key_versions.iter().enumerate().skip(skip) {
+                if version_vec.len() + common_prefixes.len() >= max_keys {
+                    truncated = true;
+                    break 'keys;
+                }
+                version_vec.push(ObjectVersionInfo {
+                    key: key.to_string(),
+                    version_id: version.version_id.clone(),
+                    is_latest: index == 0,
+                    is_delete_marker: version.object.is_none(),
+                    last_modified: version.last_modified,
+                    size: version.object.as_ref().map_or(0, |object| object.len() as u64),
+                    etag: version.object.as_ref().map(|object| object.etag.as_str().to_string()),
+                    storage_class: version.object.as_ref().and_then(|object| object.storage_class.clone()),
+                });
+                last_entry = Some((key.to_string(), Some(version.version_id.clone())));
+            }
+        }
+
+        let (next_key_marker, next_version_id_marker) = match last_entry {
+            Some((key, version_id)) if truncated => (Some(key), version_id),
+            _ => (None, None),
+        };
+
+        ListObjectVersionsResult {
+            versions: version_vec,
+            common_prefixes,
+            next_key_marker,
+            next_version_id_marker,
+        }
+    }
+
+    /// Return the object stored for a specific version of a key, or `None` if the version does not
+    /// exist or is a delete marker.
+    fn get_object_version(&self, key: &str, version_id: &str) -> Option<MockObject> {
+        let versions = self.versions.read().unwrap();
+        let version = versions
+            .get(key)?
+            .iter()
+            .find(|version| version.version_id == version_id)?;
+        version.object.clone()
+    }
+
     // TODO: we may want to extend testing of failure conditions.
     fn append_object(
         &self,
@@ -398,12 +608,20 @@ impl MockClient {
 
                 let mut object = MockObject::from(contents);
                 object.set_storage_class(params.storage_class.clone());
+                object.set_acl(params.acl.clone());
+                object.set_content_type(params.content_type.clone());
+                object.set_sse_customer_key(params.sse_customer_key.clone());
+                object.set_object_lock(params.object_lock.clone());
                 object.set_object_metadata(params.object_metadata.clone());
+                object.set_object_tags(params.object_tags.clone());
                 object.set_checksum(checksum);
                 objects.insert(key.to_owned(), object);
                 objects.get_mut(key).unwrap()
             }
             Some(object) => {
+                object
+                    .check_sse_customer_key(params.sse_customer_key.as_ref())
+                    .map_err(ObjectClientError::ClientError)?;
                 if let Some(etag) = &params.if_match {
                     if object.etag != *etag {
                         return Err(ObjectClientError::ServiceError(PutObjectError::PreconditionFailed));
@@ -449,6 +667,7 @@ impl MockClient {
 #[derive(Debug, Eq, Hash, PartialEq)]
 pub enum Operation {
     DeleteObject,
+    DeleteObjects,
     HeadObject,
     GetObject,
     GetObjectAttributes,
@@ -456,6 +675,16 @@ pub enum Operation {
     PutObject,
     CopyObject,
     PutObjectSingle,
+    CreateMultipartUpload,
+    UploadPart,
+    CompleteMultipartUpload,
+    ListParts,
+    ListMultipartUploads,
+    AbortMultipartUpload,
+    GetObjectTagging,
+    PutObjectTagging,
+    ListObjectVersions,
+    RestoreObject,
 }
 
 /// Counter for a specific client [Operation].
@@ -483,11 +712,16 @@ pub struct MockObject {
     generator: Arc<dyn Fn(u64, usize) -> Box<[u8]> + Send + Sync>,
     size: usize,
     storage_class: Option<String>,
+    acl: Option<String>,
+    content_type: Option<String>,
+    sse_customer_key: Option<SseCustomerKey>,
+    object_lock: ObjectLock,
     restore_status: Option<RestoreStatus>,
     last_modified: OffsetDateTime,
     etag: ETag,
     parts: Option<MockObjectParts>,
     object_metadata: HashMap<String, String>,
+    object_tags: ObjectTags,
     /// S3 checksums associated with the object.
     ///
     /// Typically, at most one of the checksums should be set.
@@ -506,11 +740,16 @@ impl MockObject {
             size: bytes.len(),
             generator: Arc::new(move |offset, size| bytes[offset as usize..offset as usize + size].into()),
             storage_class: None,
+            acl: None,
+            content_type: None,
+            sse_customer_key: None,
+            object_lock: ObjectLock::new(),
             restore_status: None,
             last_modified: OffsetDateTime::now_utc(),
             etag,
             parts: None,
             object_metadata: HashMap::new(),
+            object_tags: ObjectTags::new(),
             checksum: Checksum::empty(),
         }
     }
@@ -520,11 +759,16 @@ impl MockObject {
             generator: Arc::new(move |_offset, size| vec![v; size].into_boxed_slice()),
             size,
             storage_class: None,
+            acl: None,
+            content_type: None,
+            sse_customer_key: None,
+            object_lock: ObjectLock::new(),
             restore_status: None,
             last_modified: OffsetDateTime::now_utc(),
             etag,
             parts: None,
             object_metadata: HashMap::new(),
+            object_tags: ObjectTags::new(),
             checksum: Checksum::empty(),
         }
     }
@@ -544,11 +788,16 @@ impl MockObject {
             }),
             size,
             storage_class: None,
+            acl: None,
+            content_type: None,
+            sse_customer_key: None,
+            object_lock: ObjectLock::new(),
             restore_status: None,
             last_modified: OffsetDateTime::now_utc(),
             etag,
             parts: None,
             object_metadata: HashMap::new(),
+            object_tags: ObjectTags::new(),
             checksum: Checksum::empty(),
         }
     }
@@ -587,10 +836,44 @@ impl MockObject {
         self.storage_class = storage_class;
     }
 
+    pub fn set_acl(&mut self, acl: Option<String>) {
+        self.acl = acl;
+    }
+
+    pub fn set_content_type(&mut self, content_type: Option<String>) {
+        self.content_type = content_type;
+    }
+
+    pub fn set_sse_customer_key(&mut self, sse_customer_key: Option<SseCustomerKey>) {
+        self.sse_customer_key = sse_customer_key;
+    }
+
+    pub fn set_object_lock(&mut self, object_lock: ObjectLock) {
+        self.object_lock = object_lock;
+    }
+
+    /// Check that the SSE-C key provided on a request is the one the object was encrypted with.
+    fn check_sse_customer_key(&self, sse_customer_key: Option<&SseCustomerKey>) -> Result<(), MockClientError> {
+        if self.sse_customer_key.as_ref() != sse_customer_key {
+            return Err(MockClientError(
+                "SSE-C key does not match the key the object was encrypted with".into(),
+            ));
+        }
+        Ok(())
+    }
+
     pub fn set_object_metadata(&mut self, object_metadata: HashMap<String, String>) {
         self.object_metadata = object_metadata;
     }
 
+    pub fn set_object_tags(&mut self, object_tags: ObjectTags) {
+        self.object_tags = object_tags;
+    }
+
+    pub fn object_tags(&self) -> &ObjectTags {
+        &self.object_tags
+    }
+
     pub fn set_restored(&mut self, restore_status: Option<RestoreStatus>) {
         self.restore_status = restore_status;
     }
@@ -623,6 +906,10 @@ impl std::fmt::Debug for MockObject {
         f.debug_struct("MockObject")
             .field("size", &self.size)
             .field("storage_class", &self.storage_class)
+            .field("acl", &self.acl)
+            .field("content_type", &self.content_type)
+            .field("sse_customer_key", &self.sse_customer_key)
+            .field("object_lock", &self.object_lock)
             .field("last_modified", &self.last_modified)
             .field("etag", &self.etag)
             .field("restored", &self.restore_status)
@@ -630,6 +917,14 @@ impl std::fmt::Debug for MockObject {
     }
 }
 
+/// A version of an object, or a delete marker if there is no object.
+#[derive(Debug, Clone)]
+struct MockObjectVersion {
+    version_id: String,
+    last_modified: OffsetDateTime,
+    object: Option<MockObject>,
+}
+
 fn compute_checksum(content: &[u8], algorithms: &[ChecksumAlgorithm]) -> Checksum {
     let mut checksum = Checksum::empty();
     for algorithm in algorithms {
@@ -642,6 +937,10 @@ fn compute_checksum(content: &[u8], algorithms: &[ChecksumAlgorithm]) -> Checksu
                 let crc32c = crc32c::checksum(content);
                 checksum.checksum_crc32c = Some(crc32c_to_base64(&crc32c));
             }
+            ChecksumAlgorithm::Crc64nvme => {
+                let crc64 = crc64::checksum(content);
+                checksum.checksum_crc64nvme = Some(crc64nvme_to_base64(&crc64));
+            }
             ChecksumAlgorithm::Sha1 => {
                 let sha1 = sha1::checksum(content).expect("sha1 computation failed");
                 checksum.checksum_sha1 = Some(sha1_to_base64(&sha1));
@@ -815,11 +1114,57 @@ impl ObjectClient for MockClient {
             return Err(ObjectClientError::ServiceError(DeleteObjectError::NoSuchBucket));
         }
 
+        // S3 only refuses to delete a locked object version, but the mock keeps a single version per key.
+        let locked = self
+            .objects
+            .read()
+            .unwrap()
+            .get(key)
+            .is_some_and(|object| object.object_lock.is_locked_at(OffsetDateTime::now_utc()));
+        if locked {
+            return Err(ObjectClientError::ServiceError(DeleteObjectError::ObjectLocked));
+        }
+
         self.remove_object(key);
 
         Ok(DeleteObjectResult {})
     }
 
+    async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+    ) -> ObjectClientResult<DeleteObjectsResult, DeleteObjectsError, Self::ClientError> {
+        trace!(bucket, keys = keys.len(), "DeleteObjects");
+        self.inc_op_count(Operation::DeleteObjects);
+
+        if bucket != self.config.bucket {
+            return Err(ObjectClientError::ServiceError(DeleteObjectsError::NoSuchBucket));
+        }
+        if keys.len() > MAX_DELETE_OBJECTS_KEYS {
+            return Err(ObjectClientError::ServiceError(DeleteObjectsError::TooManyKeys));
+        }
+
+        let mut errors = Vec::new();
+        let mut objects = self.objects.write().unwrap();
+        for key in keys {
+            let locked = objects
+                .get(key)
+                .is_some_and(|object| object.object_lock.is_locked_at(OffsetDateTime::now_utc()));
+            if locked {
+                errors.push(DeleteObjectsKeyError {
+                    key: key.clone(),
+                    code: "AccessDenied".to_string(),
+                    message: "Access Denied because object protected by object lock.".to_string(),
+                });
+            } else {
+                objects.remove(key);
+            }
+        }
+
+        Ok(DeleteObjectsResult { errors })
+    }
+
     async fn copy_object(
         &self,
         source_bucket: &str,
@@ -848,16 +1193,23 @@ impl ObjectClient for MockClient {
         key: &str,
         params: &GetObjectParams,
     ) -> ObjectClientResult<Self::GetObjectResponse, GetObjectError, Self::ClientError> {
-        trace!(bucket, key, ?params.range, ?params.if_match, "GetObject");
+        trace!(bucket, key, ?params.range, ?params.if_match, ?params.version_id, "GetObject");
         self.inc_op_count(Operation::GetObject);
 
         if bucket != self.config.bucket {
             return Err(ObjectClientError::ServiceError(GetObjectError::NoSuchBucket));
         }
 
-        let objects = self.objects.read().unwrap();
+        let object = match params.version_id.as_deref() {
+            Some(version_id) => self.get_object_version(key, version_id),
+            None => self.objects.read().unwrap().get(key).cloned(),
+        };
+
+        if let Some(object) = object {
+            object
+                .check_sse_customer_key(params.sse_customer_key.as_ref())
+                .map_err(ObjectClientError::ClientError)?;
 
-        if let Some(object) = objects.get(key) {
             if let Some(etag_match) = params.if_match.as_ref() {
                 if etag_match != &object.etag {
                     return Err(ObjectClientError::ServiceError(GetObjectError::PreconditionFailed));
@@ -882,7 +1234,7 @@ impl ObjectClient for MockClient {
                 None
             };
             Ok(MockGetObjectResponse {
-                object: object.clone(),
+                object,
                 next_offset,
                 length,
                 part_size: self.config.part_size,
@@ -899,15 +1251,23 @@ impl ObjectClient for MockClient {
         key: &str,
         params: &HeadObjectParams,
     ) -> ObjectClientResult<HeadObjectResult, HeadObjectError, Self::ClientError> {
-        trace!(bucket, key, "HeadObject");
+        trace!(bucket, key, ?params.version_id, "HeadObject");
         self.inc_op_count(Operation::HeadObject);
 
         if bucket != self.config.bucket {
             return Err(ObjectClientError::ServiceError(HeadObjectError::NotFound));
         }
 
-        let objects = self.objects.read().unwrap();
-        if let Some(object) = objects.get(key) {
+        let object = match params.version_id.as_deref() {
+            Some(version_id) => self.get_object_version(key, version_id),
+            None => self.objects.read().unwrap().get(key).cloned(),
+        };
+
+        if let Some(object) = object {
+            object
+                .check_sse_customer_key(params.sse_customer_key.as_ref())
+                .map_err(ObjectClientError::ClientError)?;
+
             // Checksum information is opt-in
             let checksum = match params.checksum_mode {
                 Some(ChecksumMode::Enabled) => object.checksum.clone(),
@@ -923,6 +1283,7 @@ impl ObjectClient for MockClient {
                 checksum,
                 sse_type: None,
                 sse_kms_key_id: None,
+                object_lock: object.object_lock.clone(),
             })
         } else {
             Err(ObjectClientError::ServiceError(HeadObjectError::NotFound))
@@ -951,6 +1312,33 @@ impl ObjectClient for MockClient {
         }
     }
 
+    async fn list_object_versions(
+        &self,
+        bucket: &str,
+        key_marker: Option<&str>,
+        version_id_marker: Option<&str>,
+        delimiter: &str,
+        max_keys: usize,
+        prefix: &str,
+    ) -> ObjectClientResult<ListObjectVersionsResult, ListObjectVersionsError, Self::ClientError> {
+        trace!(
+            bucket,
+            ?key_marker,
+            ?version_id_marker,
+            delimiter,
+            max_keys,
+            prefix,
+            "ListObjectVersions"
+        );
+        self.inc_op_count(Operation::ListObjectVersions);
+
+        if bucket != self.config.bucket {
+            return Err(ObjectClientError::ServiceError(ListObjectVersionsError::NoSuchBucket));
+        }
+
+        Ok(self.list_object_versions_ordered(key_marker, version_id_marker, delimiter, max_keys, prefix))
+    }
+
     async fn put_object(
         &self,
         bucket: &str,
@@ -1033,12 +1421,7 @@ impl ObjectClient for MockClient {
                                         .iter()
                                         .enumerate()
                                         .map(|(i, part)| ObjectPart {
-                                            checksum: Some(Checksum {
-                                                checksum_crc32: None,
-                                                checksum_crc32c: part.checksum.clone(),
-                                                checksum_sha1: None,
-                                                checksum_sha256: None,
-                                            }),
+                                            checksum: Some(part.checksum.clone()),
                                             // Part numbers start at 1
                                             part_number: i + 1,
                                             size: part.size,
@@ -1060,140 +1443,596 @@ impl ObjectClient for MockClient {
             Err(ObjectClientError::ServiceError(GetObjectAttributesError::NoSuchKey))
         }
     }
-}
-
-/// Mock implementation of a meta [PutObjectRequest], created by [MockClient]'s [ObjectClient::put_object].
-///
-/// For a single PutObject, see [MockClient]'s implementation of [ObjectClient::put_object_single].
-#[derive(Debug)]
-pub struct MockPutObjectRequest {
-    key: String,
-    buffer: Vec<u8>,
-    part_size: usize,
-    params: PutObjectParams,
-    objects: Arc<RwLock<BTreeMap<String, MockObject>>>,
-    in_progress_uploads: Arc<RwLock<BTreeSet<String>>>,
-}
 
-impl MockPutObjectRequest {
-    fn new(
+    async fn get_object_tagging(
+        &self,
+        bucket: &str,
         key: &str,
-        part_size: usize,
-        params: &PutObjectParams,
-        objects: &Arc<RwLock<BTreeMap<String, MockObject>>>,
-        in_progress_uploads: &Arc<RwLock<BTreeSet<String>>>,
-    ) -> Self {
-        in_progress_uploads.write().unwrap().insert(key.to_owned());
-        Self {
-            key: key.to_owned(),
-            buffer: vec![],
-            part_size,
-            params: params.clone(),
-            objects: objects.clone(),
-            in_progress_uploads: in_progress_uploads.clone(),
+    ) -> ObjectClientResult<GetObjectTaggingResult, GetObjectTaggingError, Self::ClientError> {
+        trace!(bucket, key, "GetObjectTagging");
+        self.inc_op_count(Operation::GetObjectTagging);
+
+        if bucket != self.config.bucket {
+            return Err(ObjectClientError::ServiceError(GetObjectTaggingError::NoSuchBucket));
         }
-    }
 
-    fn parts(&self) -> Vec<MockObjectPartAttributes> {
-        self.buffer
-            .chunks(self.part_size)
-            .map(|part| {
-                let size = part.len();
-                let checksum = if self.params.trailing_checksums != PutObjectTrailingChecksums::Disabled {
-                    let checksum = crc32c::checksum(part);
-                    Some(crc32c_to_base64(&checksum))
-                } else {
-                    None
-                };
-                MockObjectPartAttributes { size, checksum }
-            })
-            .collect()
+        match self.objects.read().unwrap().get(key) {
+            Some(object) => Ok(GetObjectTaggingResult {
+                tags: object.object_tags.clone(),
+            }),
+            None => Err(ObjectClientError::ServiceError(GetObjectTaggingError::NoSuchKey)),
+        }
     }
 
-    fn complete_inner(
-        mut self,
-        parts: Vec<MockObjectPartAttributes>,
-    ) -> ObjectClientResult<PutObjectResult, PutObjectError, MockClientError> {
-        let buffer = std::mem::take(&mut self.buffer);
-        let mut object: MockObject = buffer.into();
-        object.set_storage_class(self.params.storage_class.clone());
-        object.set_object_metadata(self.params.object_metadata.clone());
+    async fn put_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+        tags: &ObjectTags,
+    ) -> ObjectClientResult<PutObjectTaggingResult, PutObjectTaggingError, Self::ClientError> {
+        trace!(bucket, key, ?tags, "PutObjectTagging");
+        self.inc_op_count(Operation::PutObjectTagging);
 
-        // For S3 Standard, part attributes are only available when additional checksums are used
-        if self.params.trailing_checksums == PutObjectTrailingChecksums::Enabled {
-            let whole_obj_checksum = {
-                let mut whole_obj_checksum = Checksum::empty();
-                let part_checksums = parts
-                    .iter()
-                    .map(|part| part.checksum.clone())
-                    .map(|checksum| checksum.expect("checksum must be set when using trailing checksums"));
-                whole_obj_checksum.checksum_crc32c = Some(compute_crc32c_of_crc32c_checksums(part_checksums));
-                whole_obj_checksum
-            };
-            object.set_checksum(whole_obj_checksum);
-            object.parts = Some(MockObjectParts::Parts(parts));
-        } else {
-            object.parts = Some(MockObjectParts::Count(parts.len()));
+        if bucket != self.config.bucket {
+            return Err(ObjectClientError::ServiceError(PutObjectTaggingError::NoSuchBucket));
         }
 
-        let etag = object.etag();
-        add_object(&self.objects, &self.key, object);
-        Ok(PutObjectResult {
-            etag,
-            sse_type: None,
-            sse_kms_key_id: None,
-        })
-    }
-}
+        // S3 allows up to 10 tags per object, with keys up to 128 and values up to 256 characters
+        let valid = tags.len() <= 10
+            && tags
+                .iter()
+                .all(|(key, value)| !key.is_empty() && key.chars().count() <= 128 && value.chars().count() <= 256);
+        if !valid {
+            return Err(ObjectClientError::ServiceError(PutObjectTaggingError::InvalidTag));
+        }
 
-/// Compute a checksum of checksums, mirroring how S3 computes object checksums for MPUs.
-fn compute_crc32c_of_crc32c_checksums(individual_checksums: impl IntoIterator<Item = String>) -> String {
-    let mut checksum = crc32c::Hasher::new();
-    let mut count = 0;
-    for individual_checksum in individual_checksums {
-        count += 1;
-        checksum.update(individual_checksum.as_bytes());
+        match self.objects.write().unwrap().get_mut(key) {
+            Some(object) => {
+                object.set_object_tags(tags.clone());
+                Ok(PutObjectTaggingResult {})
+            }
+            None => Err(ObjectClientError::ServiceError(PutObjectTaggingError::NoSuchKey)),
+        }
     }
-    let mut checksum = crc32c_to_base64(&checksum.finalize());
-    write!(checksum, "-{count}").expect("should be able to append to String");
-    checksum
-}
 
-impl Drop for MockPutObjectRequest {
-    fn drop(&mut self) {
-        self.in_progress_uploads.write().unwrap().remove(&self.key);
-    }
-}
+    async fn restore_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &RestoreObjectParams,
+    ) -> ObjectClientResult<RestoreObjectResult, RestoreObjectError, Self::ClientError> {
+        trace!(bucket, key, ?params, "RestoreObject");
+        self.inc_op_count(Operation::RestoreObject);
 
-#[cfg_attr(not(docsrs), async_trait)]
-impl PutObjectRequest for MockPutObjectRequest {
-    type ClientError = MockClientError;
+        if bucket != self.config.bucket {
+            return Err(ObjectClientError::ServiceError(RestoreObjectError::NoSuchBucket));
+        }
 
-    async fn write(&mut self, slice: &[u8]) -> ObjectClientResult<(), PutObjectError, Self::ClientError> {
-        self.buffer.extend_from_slice(slice);
-        Ok(())
+        match self.objects.write().unwrap().get_mut(key) {
+            Some(object) => {
+                if !matches!(object.storage_class.as_deref(), Some("GLACIER") | Some("DEEP_ARCHIVE")) {
+                    return Err(ObjectClientError::ServiceError(RestoreObjectError::InvalidObjectState));
+                }
+                let already_restored = match object.restore_status {
+                    Some(RestoreStatus::InProgress) => {
+                        return Err(ObjectClientError::ServiceError(
+                            RestoreObjectError::RestoreAlreadyInProgress,
+                        ))
+                    }
+                    Some(RestoreStatus::Restored { .. }) => true,
+                    None => false,
+                };
+                // Restores complete immediately in the mock client
+                object.restore_status = Some(RestoreStatus::Restored {
+                    expiry: SystemTime::now() + Duration::from_secs(params.days as u64 * 24 * 60 * 60),
+                });
+                Ok(RestoreObjectResult { already_restored })
+            }
+            None => Err(ObjectClientError::ServiceError(RestoreObjectError::NoSuchKey)),
+        }
     }
 
-    async fn complete(mut self) -> ObjectClientResult<PutObjectResult, PutObjectError, Self::ClientError> {
-        let parts = self.parts();
-        self.complete_inner(parts)
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &CreateMultipartUploadParams,
+    ) -> ObjectClientResult<CreateMultipartUploadResult, CreateMultipartUploadError, Self::ClientError> {
+        trace!(bucket, key, "CreateMultipartUpload");
+        self.inc_op_count(Operation::CreateMultipartUpload);
+
+        if bucket != self.config.bucket {
+            return Err(ObjectClientError::ServiceError(
+                CreateMultipartUploadError::NoSuchBucket,
+            ));
+        }
+
+        let upload_id = format!("mock-upload-{}", self.next_upload_id.fetch_add(1, Ordering::SeqCst));
+        let upload = MockMultipartUpload {
+            key: key.to_owned(),
+            params: params.clone(),
+            initiated: OffsetDateTime::now_utc(),
+            parts: Default::default(),
+        };
+        self.multipart_uploads
+            .write()
+            .unwrap()
+            .insert(upload_id.clone(), upload);
+        Ok(CreateMultipartUploadResult { upload_id })
     }
 
-    async fn review_and_complete(
-        self,
-        review_callback: impl FnOnce(UploadReview) -> bool + Send + 'static,
-    ) -> ObjectClientResult<PutObjectResult, PutObjectError, Self::ClientError> {
-        let checksum_algorithm = if self.params.trailing_checksums != PutObjectTrailingChecksums::Disabled {
-            Some(ChecksumAlgorithm::Crc32c)
+    async fn upload_part<'a>(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: usize,
+        params: &UploadPartParams,
+        contents: impl AsRef<[u8]> + Send + 'a,
+    ) -> ObjectClientResult<UploadPartResult, UploadPartError, Self::ClientError> {
+        trace!(bucket, key, upload_id, part_number, "UploadPart");
+        self.inc_op_count(Operation::UploadPart);
+
+        if bucket != self.config.bucket {
+            return Err(ObjectClientError::ServiceError(UploadPartError::NoSuchBucket));
+        }
+        if !(1..=10000).contains(&part_number) {
+            return mock_client_error(format!("invalid part number {part_number}"));
+        }
+
+        let mut uploads = self.multipart_uploads.write().unwrap();
+        let upload = match uploads.get_mut(upload_id) {
+            Some(upload) if upload.key == key => upload,
+            _ => return Err(ObjectClientError::ServiceError(UploadPartError::NoSuchUpload)),
+        };
+        if params.sse_customer_key != upload.params.sse_customer_key {
+            return mock_client_error("SSE-C key does not match the key the upload was created with");
+        }
+
+        let bytes = contents.as_ref();
+        // The only failure of checksum validation is a mismatch
+        let checksum = validate_checksum(bytes, params.checksum.as_ref())
+            .map_err(|_| ObjectClientError::ServiceError(UploadPartError::BadChecksum))?;
+        let etag = ETag::from_object_bytes(bytes);
+        upload.parts.insert(
+            part_number,
+            MockMultipartUploadPart {
+                bytes: bytes.to_vec(),
+                etag: etag.clone(),
+                checksum,
+            },
+        );
+        Ok(UploadPartResult { etag })
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: &[CompletedPart],
+        params: &CompleteMultipartUploadParams,
+    ) -> ObjectClientResult<CompleteMultipartUploadResult, CompleteMultipartUploadError, Self::ClientError> {
+        trace!(
+            bucket,
+            key,
+            upload_id,
+            num_parts = parts.len(),
+            "CompleteMultipartUpload"
+        );
+        self.inc_op_count(Operation::CompleteMultipartUpload);
+
+        if bucket != self.config.bucket {
+            return Err(ObjectClientError::ServiceError(
+                CompleteMultipartUploadError::NoSuchBucket,
+            ));
+        }
+
+        let mut uploads = self.multipart_uploads.write().unwrap();
+        let upload = match uploads.get(upload_id) {
+            Some(upload) if upload.key == key => upload,
+            _ => {
+                return Err(ObjectClientError::ServiceError(
+                    CompleteMultipartUploadError::NoSuchUpload,
+                ))
+            }
+        };
+
+        if !parts.windows(2).all(|w| w[0].part_number < w[1].part_number) {
+            return Err(ObjectClientError::ServiceError(
+                CompleteMultipartUploadError::InvalidPartOrder,
+            ));
+        }
+
+        let mut buffer = Vec::new();
+        let mut part_attributes = Vec::with_capacity(parts.len());
+        for completed_part in parts {
+            let uploaded_part = match upload.parts.get(&completed_part.part_number) {
+                Some(part) if part.etag == completed_part.etag => part,
+                _ => {
+                    return Err(ObjectClientError::ServiceError(
+                        CompleteMultipartUploadError::InvalidPart,
+                    ))
+                }
+            };
+            if upload.params.checksum_algorithm.is_some() && uploaded_part.checksum != completed_part.checksum {
+                return Err(ObjectClientError::ServiceError(
+                    CompleteMultipartUploadError::InvalidPart,
+                ));
+            }
+            buffer.extend_from_slice(&uploaded_part.bytes);
+            part_attributes.push(MockObjectPartAttributes {
+                size: uploaded_part.bytes.len(),
+                checksum: uploaded_part.checksum.clone(),
+            });
+        }
+
+        let mut object: MockObject = buffer.into();
+        object.set_storage_class(upload.params.storage_class.clone());
+        object.set_acl(upload.params.acl.clone());
+        object.set_content_type(upload.params.content_type.clone());
+        object.set_sse_customer_key(upload.params.sse_customer_key.clone());
+        object.set_object_lock(upload.params.object_lock.clone());
+        object.set_object_metadata(upload.params.object_metadata.clone());
+        object.set_object_tags(upload.params.object_tags.clone());
+        if let Some(algorithm) = &upload.params.checksum_algorithm {
+            let whole_obj_checksum = if is_full_object_checksum(algorithm, upload.params.checksum_type) {
+                compute_checksum(object.read(0, object.len()).as_ref(), slice::from_ref(algorithm))
+            } else {
+                compute_composite_checksum(algorithm, &part_attributes)
+            };
+            object.set_checksum(whole_obj_checksum);
+            object.parts = Some(MockObjectParts::Parts(part_attributes));
         } else {
-            None
+            object.parts = Some(MockObjectParts::Count(part_attributes.len()));
+        }
+
+        let etag = object.etag();
+        add_object_if_match(&self.objects, key, object, params.if_match.as_ref())
+            .map_err(|_| ObjectClientError::ServiceError(CompleteMultipartUploadError::PreconditionFailed))?;
+        uploads.remove(upload_id);
+        Ok(CompleteMultipartUploadResult { etag })
+    }
+
+    async fn list_parts(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number_marker: Option<usize>,
+    ) -> ObjectClientResult<ListPartsResult, ListPartsError, Self::ClientError> {
+        trace!(bucket, key, upload_id, ?part_number_marker, "ListParts");
+        self.inc_op_count(Operation::ListParts);
+
+        if bucket != self.config.bucket {
+            return Err(ObjectClientError::ServiceError(ListPartsError::NoSuchBucket));
+        }
+
+        let uploads = self.multipart_uploads.read().unwrap();
+        let upload = match uploads.get(upload_id) {
+            Some(upload) if upload.key == key => upload,
+            _ => return Err(ObjectClientError::ServiceError(ListPartsError::NoSuchUpload)),
+        };
+
+        const MAX_PARTS: usize = 1000;
+        let start = part_number_marker.unwrap_or(0) + 1;
+        let parts: Vec<_> = upload
+            .parts
+            .range(start..)
+            .take(MAX_PARTS)
+            .map(|(part_number, part)| UploadedPart {
+                part_number: *part_number,
+                etag: part.etag.clone(),
+                size: part.bytes.len() as u64,
+                checksum: part.checksum.clone(),
+            })
+            .collect();
+        let next_part_number_marker = match parts.last() {
+            Some(last) if upload.parts.range(last.part_number + 1..).next().is_some() => Some(last.part_number),
+            _ => None,
         };
+        Ok(ListPartsResult {
+            parts,
+            next_part_number_marker,
+        })
+    }
+
+    async fn list_multipart_uploads(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        key_marker: Option<&str>,
+        upload_id_marker: Option<&str>,
+        max_uploads: usize,
+    ) -> ObjectClientResult<ListMultipartUploadsResult, ListMultipartUploadsError, Self::ClientError> {
+        trace!(
+            bucket,
+            prefix,
+            ?key_marker,
+            ?upload_id_marker,
+            max_uploads,
+            "ListMultipartUploads"
+        );
+        self.inc_op_count(Operation::ListMultipartUploads);
+
+        if bucket != self.config.bucket {
+            return Err(ObjectClientError::ServiceError(ListMultipartUploadsError::NoSuchBucket));
+        }
+
+        let mut uploads: Vec<_> = self
+            .multipart_uploads
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, upload)| upload.key.starts_with(prefix))
+            .map(|(upload_id, upload)| MultipartUploadInfo {
+                key: upload.key.clone(),
+                upload_id: upload_id.clone(),
+                initiated: upload.initiated,
+                storage_class: upload.params.storage_class.clone(),
+            })
+            .collect();
+        uploads.sort_by(|a, b| a.key.cmp(&b.key).then(a.initiated.cmp(&b.initiated)));
+
+        // Like S3, the upload ID marker is only used together with a key marker
+        let start = match key_marker {
+            None => 0,
+            Some(key_marker) => {
+                let after_upload_id = upload_id_marker.and_then(|upload_id_marker| {
+                    uploads
+                        .iter()
+                        .position(|upload| upload.key == key_marker && upload.upload_id == upload_id_marker)
+                });
+                match after_upload_id {
+                    Some(index) => index + 1,
+                    None => uploads.partition_point(|upload| upload.key.as_str() <= key_marker),
+                }
+            }
+        };
+
+        let mut uploads = uploads.split_off(start);
+        let (next_key_marker, next_upload_id_marker) = if uploads.len() > max_uploads {
+            uploads.truncate(max_uploads);
+            let last = uploads.last().expect("max_uploads should be positive");
+            (Some(last.key.clone()), Some(last.upload_id.clone()))
+        } else {
+            (None, None)
+        };
+
+        Ok(ListMultipartUploadsResult {
+            uploads,
+            next_key_marker,
+            next_upload_id_marker,
+        })
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> ObjectClientResult<AbortMultipartUploadResult, AbortMultipartUploadError, Self::ClientError> {
+        trace!(bucket, key, upload_id, "AbortMultipartUpload");
+        self.inc_op_count(Operation::AbortMultipartUpload);
+
+        if bucket != self.config.bucket {
+            return Err(ObjectClientError::ServiceError(AbortMultipartUploadError::NoSuchBucket));
+        }
+
+        let mut uploads = self.multipart_uploads.write().unwrap();
+        match uploads.get(upload_id) {
+            Some(upload) if upload.key == key => {
+                uploads.remove(upload_id);
+                Ok(AbortMultipartUploadResult {})
+            }
+            _ => Err(ObjectClientError::ServiceError(AbortMultipartUploadError::NoSuchUpload)),
+        }
+    }
+}
+
+/// A multipart upload created by [MockClient]'s [ObjectClient::create_multipart_upload].
+#[derive(Debug)]
+struct MockMultipartUpload {
+    key: String,
+    params: CreateMultipartUploadParams,
+    initiated: OffsetDateTime,
+    parts: BTreeMap<usize, MockMultipartUploadPart>,
+}
+
+#[derive(Debug)]
+struct MockMultipartUploadPart {
+    bytes: Vec<u8>,
+    etag: ETag,
+    checksum: Checksum,
+}
+
+/// Mock implementation of a meta [PutObjectRequest], created by [MockClient]'s [ObjectClient::put_object].
+///
+/// For a single PutObject, see [MockClient]'s implementation of [ObjectClient::put_object_single].
+#[derive(Debug)]
+pub struct MockPutObjectRequest {
+    key: String,
+    buffer: Vec<u8>,
+    part_size: usize,
+    params: PutObjectParams,
+    objects: Arc<RwLock<BTreeMap<String, MockObject>>>,
+    in_progress_uploads: Arc<RwLock<BTreeSet<String>>>,
+}
+
+impl MockPutObjectRequest {
+    fn new(
+        key: &str,
+        part_size: usize,
+        params: &PutObjectParams,
+        objects: &Arc<RwLock<BTreeMap<String, MockObject>>>,
+        in_progress_uploads: &Arc<RwLock<BTreeSet<String>>>,
+    ) -> Self {
+        in_progress_uploads.write().unwrap().insert(key.to_owned());
+        Self {
+            key: key.to_owned(),
+            buffer: vec![],
+            part_size,
+            params: params.clone(),
+            objects: objects.clone(),
+            in_progress_uploads: in_progress_uploads.clone(),
+        }
+    }
+
+    fn checksum_algorithm(&self) -> Option<ChecksumAlgorithm> {
+        (self.params.trailing_checksums != PutObjectTrailingChecksums::Disabled).then(|| {
+            self.params
+                .checksum_algorithm
+                .clone()
+                .unwrap_or(ChecksumAlgorithm::Crc32c)
+        })
+    }
+
+    fn parts(&self) -> Vec<MockObjectPartAttributes> {
+        let algorithm = self.checksum_algorithm();
+        self.buffer
+            .chunks(self.part_size)
+            .map(|part| MockObjectPartAttributes {
+                size: part.len(),
+                checksum: compute_checksum(part, algorithm.as_slice()),
+            })
+            .collect()
+    }
+
+    fn complete_inner(
+        mut self,
+        parts: Vec<MockObjectPartAttributes>,
+    ) -> ObjectClientResult<PutObjectResult, PutObjectError, MockClientError> {
+        let buffer = std::mem::take(&mut self.buffer);
+        let mut object: MockObject = buffer.into();
+        object.set_storage_class(self.params.storage_class.clone());
+        object.set_acl(self.params.acl.clone());
+        object.set_content_type(self.params.content_type.clone());
+        object.set_sse_customer_key(self.params.sse_customer_key.clone());
+        object.set_object_lock(self.params.object_lock.clone());
+        object.set_object_metadata(self.params.object_metadata.clone());
+        object.set_object_tags(self.params.object_tags.clone());
+
+        // For S3 Standard, part attributes are only available when additional checksums are used
+        if self.params.trailing_checksums == PutObjectTrailingChecksums::Enabled {
+            let algorithm = self
+                .checksum_algorithm()
+                .expect("algorithm must be set when using trailing checksums");
+            let whole_obj_checksum = if is_full_object_checksum(&algorithm, self.params.checksum_type) {
+                compute_checksum(object.read(0, object.len()).as_ref(), slice::from_ref(&algorithm))
+            } else {
+                compute_composite_checksum(&algorithm, &parts)
+            };
+            object.set_checksum(whole_obj_checksum);
+            object.parts = Some(MockObjectParts::Parts(parts));
+        } else {
+            object.parts = Some(MockObjectParts::Count(parts.len()));
+        }
+
+        let etag = object.etag();
+        add_object_if_match(&self.objects, &self.key, object, self.params.if_match.as_ref())
+            .map_err(ObjectClientError::ServiceError)?;
+        Ok(PutObjectResult {
+            etag,
+            sse_type: None,
+            sse_kms_key_id: None,
+        })
+    }
+}
+
+/// Whether S3 would store a full object checksum (rather than a composite one) for an MPU.
+///
+/// CRC64NVME only supports full object checksums, and is the only algorithm where that is the default.
+fn is_full_object_checksum(algorithm: &ChecksumAlgorithm, checksum_type: Option<ChecksumType>) -> bool {
+    match checksum_type {
+        Some(checksum_type) => checksum_type == ChecksumType::FullObject,
+        None => *algorithm == ChecksumAlgorithm::Crc64nvme,
+    }
+}
+
+/// Compute a checksum of checksums, mirroring how S3 computes composite object checksums for MPUs.
+fn compute_composite_checksum(algorithm: &ChecksumAlgorithm, parts: &[MockObjectPartAttributes]) -> Checksum {
+    let mut concatenated = Vec::new();
+    for part in parts {
+        let part_checksum = checksum_value(&part.checksum).expect("checksum must be set for every part");
+        concatenated.extend_from_slice(part_checksum.as_bytes());
+    }
+    let mut checksum = compute_checksum(&concatenated, slice::from_ref(algorithm));
+    if let Some(value) = checksum_value_mut(&mut checksum) {
+        write!(value, "-{}", parts.len()).expect("should be able to append to String");
+    }
+    checksum
+}
+
+/// The value of the checksum set in a [Checksum], if any. We assume that at most one checksum is set.
+fn checksum_value(checksum: &Checksum) -> Option<&String> {
+    let Checksum {
+        checksum_crc32,
+        checksum_crc32c,
+        checksum_crc64nvme,
+        checksum_sha1,
+        checksum_sha256,
+    } = checksum;
+    [
+        checksum_crc32,
+        checksum_crc32c,
+        checksum_crc64nvme,
+        checksum_sha1,
+        checksum_sha256,
+    ]
+    .into_iter()
+    .find_map(Option::as_ref)
+}
+
+fn checksum_value_mut(checksum: &mut Checksum) -> Option<&mut String> {
+    let Checksum {
+        checksum_crc32,
+        checksum_crc32c,
+        checksum_crc64nvme,
+        checksum_sha1,
+        checksum_sha256,
+    } = checksum;
+    [
+        checksum_crc32,
+        checksum_crc32c,
+        checksum_crc64nvme,
+        checksum_sha1,
+        checksum_sha256,
+    ]
+    .into_iter()
+    .find_map(Option::as_mut)
+}
+
+impl Drop for MockPutObjectRequest {
+    fn drop(&mut self) {
+        self.in_progress_uploads.write().unwrap().remove(&self.key);
+    }
+}
+
+#[cfg_attr(not(docsrs), async_trait)]
+impl PutObjectRequest for MockPutObjectRequest {
+    type ClientError = MockClientError;
+
+    async fn write(&mut self, slice: &[u8]) -> ObjectClientResult<(), PutObjectError, Self::ClientError> {
+        self.buffer.extend_from_slice(slice);
+        Ok(())
+    }
+
+    async fn complete(mut self) -> ObjectClientResult<PutObjectResult, PutObjectError, Self::ClientError> {
+        let parts = self.parts();
+        self.complete_inner(parts)
+    }
+
+    async fn review_and_complete(
+        self,
+        review_callback: impl FnOnce(UploadReview) -> bool + Send + 'static,
+    ) -> ObjectClientResult<PutObjectResult, PutObjectError, Self::ClientError> {
+        let checksum_algorithm = self.checksum_algorithm();
         let parts = self.parts();
         let review_parts = parts
             .iter()
             .map(|part| UploadReviewPart {
                 size: part.size as u64,
-                checksum: part.checksum.clone(),
+                checksum: checksum_value(&part.checksum).cloned(),
             })
             .collect();
         let review = UploadReview {
@@ -1210,7 +2049,7 @@ impl PutObjectRequest for MockPutObjectRequest {
 #[derive(Debug, Clone)]
 struct MockObjectPartAttributes {
     size: usize,
-    checksum: Option<String>,
+    checksum: Checksum,
 }
 
 /// Some S3 implementations only report per-part data from GetObjectAttributes if parts were
@@ -1230,6 +2069,8 @@ mod tests {
     use std::ops::Range;
     use test_case::test_case;
 
+    use crate::object_client::RestoreTier;
+
     use super::*;
 
     macro_rules! assert_client_error {
@@ -1853,6 +2694,7 @@ mod tests {
         object_b.set_checksum(Checksum {
             checksum_crc32: None,
             checksum_crc32c: None,
+            checksum_crc64nvme: None,
             checksum_sha1: Some(String::from("QwzjTQIHJO11oZbfwq1nx3dy0Wk=")),
             checksum_sha256: None,
         });
@@ -1866,6 +2708,123 @@ mod tests {
         assert_eq!(result.objects[1].checksum_algorithms, vec![ChecksumAlgorithm::Sha1]);
     }
 
+    #[test_case(1; "page size 1")]
+    #[test_case(2; "page size 2")]
+    #[test_case(1000; "single page")]
+    #[tokio::test]
+    async fn list_object_versions(page_size: usize) {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            ..Default::default()
+        });
+
+        let time = |seconds: i64| OffsetDateTime::from_unix_timestamp(seconds).unwrap();
+        let object = |last_modified: OffsetDateTime| {
+            let mut object = MockObject::constant(0u8, 5, ETag::for_tests());
+            object.set_last_modified(last_modified);
+            object
+        };
+        client.add_object_version("a.txt", "a1", object(time(100)));
+        client.add_object_version("a.txt", "a2", object(time(200)));
+        client.add_delete_marker("a.txt", "a3", time(300));
+        client.add_object_version("dir/b.txt", "b1", object(time(100)));
+        client.add_object_version("dir/c.txt", "c1", object(time(100)));
+        client.add_object_version("z.txt", "z1", object(time(100)));
+
+        let mut versions = Vec::new();
+        let mut common_prefixes = Vec::new();
+        let mut key_marker = None;
+        let mut version_id_marker = None;
+        loop {
+            let result = client
+                .list_object_versions(
+                    "test_bucket",
+                    key_marker.as_deref(),
+                    version_id_marker.as_deref(),
+                    "/",
+                    page_size,
+                    "",
+                )
+                .await
+                .expect("should not fail");
+            assert!(result.versions.len() + result.common_prefixes.len() <= page_size);
+            versions.extend(
+                result
+                    .versions
+                    .into_iter()
+                    .map(|v| (v.key, v.version_id, v.is_latest, v.is_delete_marker)),
+            );
+            common_prefixes.extend(result.common_prefixes);
+            if result.next_key_marker.is_none() {
+                break;
+            }
+            key_marker = result.next_key_marker;
+            version_id_marker = result.next_version_id_marker;
+        }
+
+        let expected = [
+            ("a.txt", "a3", true, true),
+            ("a.txt", "a2", false, false),
+            ("a.txt", "a1", false, false),
+            ("z.txt", "z1", true, false),
+        ]
+        .map(|(key, version_id, is_latest, is_delete_marker)| {
+            (key.to_owned(), version_id.to_owned(), is_latest, is_delete_marker)
+        });
+        assert_eq!(versions, expected);
+        assert_eq!(common_prefixes, vec!["dir/".to_owned()]);
+    }
+
+    #[tokio::test]
+    async fn get_object_version() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024,
+            ..Default::default()
+        });
+
+        client.add_object("a.txt", MockObject::constant(2u8, 5, ETag::for_tests()));
+        client.add_object_version("a.txt", "v1", MockObject::constant(1u8, 5, ETag::for_tests()));
+        client.add_delete_marker("a.txt", "v2", OffsetDateTime::now_utc());
+
+        let body = client
+            .get_object(
+                "test_bucket",
+                "a.txt",
+                &GetObjectParams::new().version_id(Some("v1".to_owned())),
+            )
+            .await
+            .expect("should not fail")
+            .collect()
+            .await
+            .expect("should not fail");
+        assert_eq!(&body[..], &[1u8; 5]);
+
+        let result = client
+            .get_object(
+                "test_bucket",
+                "a.txt",
+                &GetObjectParams::new().version_id(Some("v2".to_owned())),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(ObjectClientError::ServiceError(GetObjectError::NoSuchKey))
+        ));
+
+        let result = client
+            .head_object(
+                "test_bucket",
+                "a.txt",
+                &HeadObjectParams::new().version_id(Some("v3".to_owned())),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(ObjectClientError::ServiceError(HeadObjectError::NotFound))
+        ));
+    }
+
     #[tokio::test]
     async fn test_put_object() {
         let mut rng = ChaChaRng::seed_from_u64(0x12345678);
@@ -1913,6 +2872,366 @@ mod tests {
         assert_eq!(object_metadata, get_request.object.object_metadata);
     }
 
+    #[tokio::test]
+    async fn test_put_object_if_match() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024,
+            unordered_list_seed: None,
+            ..Default::default()
+        });
+
+        let obj = MockObject::constant(0xaa, 16, ETag::from("original-etag"));
+        client.add_object("key1", obj);
+
+        // Upload with the current etag succeeds
+        let put_object_params = PutObjectParams::new().if_match(Some(ETag::from("original-etag")));
+        let mut put_request = client
+            .put_object("test_bucket", "key1", &put_object_params)
+            .await
+            .expect("put_object failed");
+        put_request.write(&[0xbb; 32]).await.unwrap();
+        let put_result = put_request.complete().await.expect("put_object failed");
+
+        // Upload with the stale etag fails at completion
+        let mut put_request = client
+            .put_object("test_bucket", "key1", &put_object_params)
+            .await
+            .expect("put_object failed");
+        put_request.write(&[0xcc; 32]).await.unwrap();
+        let err = put_request
+            .complete()
+            .await
+            .expect_err("put_object with stale etag should fail");
+        assert!(matches!(
+            err,
+            ObjectClientError::ServiceError(PutObjectError::PreconditionFailed)
+        ));
+
+        // The object written by the first upload is unchanged
+        let head_result = client
+            .head_object("test_bucket", "key1", &HeadObjectParams::new())
+            .await
+            .expect("head_object failed");
+        assert_eq!(head_result.etag, put_result.etag);
+        assert_eq!(head_result.size, 32);
+
+        // Upload with an etag to a missing key fails too
+        let mut put_request = client
+            .put_object("test_bucket", "key2", &put_object_params)
+            .await
+            .expect("put_object failed");
+        put_request.write(&[0xcc; 32]).await.unwrap();
+        let err = put_request
+            .complete()
+            .await
+            .expect_err("put_object with etag on missing key should fail");
+        assert!(matches!(
+            err,
+            ObjectClientError::ServiceError(PutObjectError::PreconditionFailed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_multipart_upload() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024,
+            unordered_list_seed: None,
+            ..Default::default()
+        });
+
+        let params = CreateMultipartUploadParams::new().checksum_algorithm(Some(ChecksumAlgorithm::Crc32c));
+        let upload_id = client
+            .create_multipart_upload("test_bucket", "key1", &params)
+            .await
+            .expect("create_multipart_upload failed")
+            .upload_id;
+        assert_eq!(client.multipart_upload_count(), 1);
+
+        let contents = [vec![0xaa; 1024], vec![0xbb; 512]];
+        let mut completed_parts = Vec::new();
+        for (i, part) in contents.iter().enumerate() {
+            let checksum = UploadChecksum::Crc32c(crc32c::checksum(part));
+            let params = UploadPartParams::new().checksum(Some(checksum.clone()));
+            let result = client
+                .upload_part("test_bucket", "key1", &upload_id, i + 1, &params, part)
+                .await
+                .expect("upload_part failed");
+            completed_parts.push(CompletedPart {
+                part_number: i + 1,
+                etag: result.etag,
+                checksum: Some(checksum).into(),
+            });
+        }
+
+        // A part with a bad checksum is rejected
+        let params = UploadPartParams::new().checksum(Some(UploadChecksum::Crc32c(crc32c::checksum(b"wrong"))));
+        let err = client
+            .upload_part("test_bucket", "key1", &upload_id, 3, &params, [0xcc; 16])
+            .await
+            .expect_err("upload_part with bad checksum should fail");
+        assert!(matches!(
+            err,
+            ObjectClientError::ServiceError(UploadPartError::BadChecksum)
+        ));
+
+        let list_result = client
+            .list_parts("test_bucket", "key1", &upload_id, None)
+            .await
+            .expect("list_parts failed");
+        assert_eq!(list_result.next_part_number_marker, None);
+        let listed: Vec<_> = list_result.parts.iter().map(|p| (p.part_number, p.size)).collect();
+        assert_eq!(listed, vec![(1, 1024), (2, 512)]);
+        assert_eq!(list_result.parts[1].checksum, completed_parts[1].checksum);
+
+        // Parts must be in ascending order
+        let reversed: Vec<_> = completed_parts.iter().rev().cloned().collect();
+        let err = client
+            .complete_multipart_upload(
+                "test_bucket",
+                "key1",
+                &upload_id,
+                &reversed,
+                &CompleteMultipartUploadParams::new(),
+            )
+            .await
+            .expect_err("complete_multipart_upload with unordered parts should fail");
+        assert!(matches!(
+            err,
+            ObjectClientError::ServiceError(CompleteMultipartUploadError::InvalidPartOrder)
+        ));
+
+        let result = client
+            .complete_multipart_upload(
+                "test_bucket",
+                "key1",
+                &upload_id,
+                &completed_parts,
+                &CompleteMultipartUploadParams::new(),
+            )
+            .await
+            .expect("complete_multipart_upload failed");
+        assert_eq!(result.etag, ETag::from_object_bytes(&contents.concat()));
+        assert_eq!(client.multipart_upload_count(), 0);
+
+        let head_result = client
+            .head_object("test_bucket", "key1", &HeadObjectParams::new())
+            .await
+            .expect("head_object failed");
+        assert_eq!(head_result.size, 1536);
+        assert_eq!(head_result.etag, result.etag);
+    }
+
+    #[tokio::test]
+    async fn test_abort_multipart_upload() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024,
+            unordered_list_seed: None,
+            ..Default::default()
+        });
+
+        let upload_id = client
+            .create_multipart_upload("test_bucket", "key1", &CreateMultipartUploadParams::new())
+            .await
+            .expect("create_multipart_upload failed")
+            .upload_id;
+        client
+            .upload_part(
+                "test_bucket",
+                "key1",
+                &upload_id,
+                1,
+                &UploadPartParams::new(),
+                [0xaa; 16],
+            )
+            .await
+            .expect("upload_part failed");
+
+        client
+            .abort_multipart_upload("test_bucket", "key1", &upload_id)
+            .await
+            .expect("abort_multipart_upload failed");
+        assert_eq!(client.multipart_upload_count(), 0);
+        assert!(!client.contains_key("key1"));
+
+        let err = client
+            .list_parts("test_bucket", "key1", &upload_id, None)
+            .await
+            .expect_err("list_parts on aborted upload should fail");
+        assert!(matches!(
+            err,
+            ObjectClientError::ServiceError(ListPartsError::NoSuchUpload)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_multipart_uploads() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024,
+            unordered_list_seed: None,
+            ..Default::default()
+        });
+
+        let mut upload_ids = Vec::new();
+        for key in ["dir/b", "dir/a", "other", "dir/a"] {
+            let upload_id = client
+                .create_multipart_upload("test_bucket", key, &CreateMultipartUploadParams::new())
+                .await
+                .expect("create_multipart_upload failed")
+                .upload_id;
+            upload_ids.push(upload_id);
+        }
+        let initiated = OffsetDateTime::now_utc() - Duration::from_secs(3600);
+        client
+            .set_multipart_upload_initiated(&upload_ids[1], initiated)
+            .expect("upload should exist");
+
+        let mut listed = Vec::new();
+        let mut key_marker = None;
+        let mut upload_id_marker = None;
+        loop {
+            let result = client
+                .list_multipart_uploads(
+                    "test_bucket",
+                    "dir/",
+                    key_marker.as_deref(),
+                    upload_id_marker.as_deref(),
+                    1,
+                )
+                .await
+                .expect("list_multipart_uploads failed");
+            assert!(result.uploads.len() <= 1);
+            listed.extend(result.uploads);
+            if result.next_key_marker.is_none() {
+                break;
+            }
+            key_marker = result.next_key_marker;
+            upload_id_marker = result.next_upload_id_marker;
+        }
+
+        let listed: Vec<_> = listed
+            .iter()
+            .map(|upload| (upload.key.as_str(), upload.upload_id.as_str()))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("dir/a", upload_ids[1].as_str()),
+                ("dir/a", upload_ids[3].as_str()),
+                ("dir/b", upload_ids[0].as_str()),
+            ]
+        );
+
+        let err = client
+            .list_multipart_uploads("wrong_bucket", "", None, None, 1000)
+            .await
+            .expect_err("list_multipart_uploads on wrong bucket should fail");
+        assert!(matches!(
+            err,
+            ObjectClientError::ServiceError(ListMultipartUploadsError::NoSuchBucket)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_object_tagging() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024,
+            unordered_list_seed: None,
+            ..Default::default()
+        });
+
+        let object_tags = ObjectTags::from([("project".to_string(), "blue".to_string())]);
+        let put_object_params = PutObjectSingleParams::new().object_tags(object_tags.clone());
+        client
+            .put_object_single("test_bucket", "key1", &put_object_params, b"content")
+            .await
+            .expect("put_object_single failed");
+        let result = client
+            .get_object_tagging("test_bucket", "key1")
+            .await
+            .expect("get_object_tagging failed");
+        assert_eq!(result.tags, object_tags);
+
+        let new_tags = ObjectTags::from([("env".to_string(), "prod".to_string())]);
+        client
+            .put_object_tagging("test_bucket", "key1", &new_tags)
+            .await
+            .expect("put_object_tagging failed");
+        let result = client
+            .get_object_tagging("test_bucket", "key1")
+            .await
+            .expect("get_object_tagging failed");
+        assert_eq!(result.tags, new_tags);
+
+        let too_many_tags: ObjectTags = (0..11).map(|i| (format!("key{i}"), "value".to_string())).collect();
+        let err = client
+            .put_object_tagging("test_bucket", "key1", &too_many_tags)
+            .await
+            .expect_err("put_object_tagging with too many tags should fail");
+        assert!(matches!(
+            err,
+            ObjectClientError::ServiceError(PutObjectTaggingError::InvalidTag)
+        ));
+
+        let err = client
+            .get_object_tagging("test_bucket", "key2")
+            .await
+            .expect_err("get_object_tagging on missing key should fail");
+        assert!(matches!(
+            err,
+            ObjectClientError::ServiceError(GetObjectTaggingError::NoSuchKey)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_restore_object() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024,
+            unordered_list_seed: None,
+            ..Default::default()
+        });
+
+        client.add_object("standard", MockObject::constant(0u8, 16, ETag::for_tests()));
+        let mut archived = MockObject::constant(0u8, 16, ETag::for_tests());
+        archived.set_storage_class(Some("GLACIER".to_string()));
+        client.add_object("archived", archived);
+        let counter = client.new_counter(Operation::RestoreObject);
+
+        let params = RestoreObjectParams::new(7).tier(Some(RestoreTier::Bulk));
+        let err = ObjectClient::restore_object(&client, "test_bucket", "standard", &params)
+            .await
+            .expect_err("restoring a non-archived object should fail");
+        assert!(matches!(
+            err,
+            ObjectClientError::ServiceError(RestoreObjectError::InvalidObjectState)
+        ));
+
+        let result = ObjectClient::restore_object(&client, "test_bucket", "archived", &params)
+            .await
+            .expect("restore_object failed");
+        assert!(!result.already_restored);
+        assert!(client.is_object_restored("archived").unwrap());
+
+        let result = ObjectClient::restore_object(&client, "test_bucket", "archived", &params)
+            .await
+            .expect("restore_object failed");
+        assert!(result.already_restored);
+
+        let err = ObjectClient::restore_object(&client, "test_bucket", "missing", &params)
+            .await
+            .expect_err("restoring a missing object should fail");
+        assert!(matches!(
+            err,
+            ObjectClientError::ServiceError(RestoreObjectError::NoSuchKey)
+        ));
+        assert_eq!(counter.count(), 4);
+    }
+
     #[tokio::test]
     async fn test_put_object_single() {
         let client = MockClient::new(MockClientConfig {
@@ -2037,12 +3356,7 @@ mod tests {
                 unreachable!("we know checksums were enabled for this upload");
             };
 
-            let part_checksums = parts
-                .iter()
-                .map(|part| part.checksum.clone())
-                .map(|checksum| checksum.expect("checksum must be set when using trailing checksums"));
-            let obj_checksum = compute_crc32c_of_crc32c_checksums(part_checksums);
-            expected_obj_checksum.checksum_crc32c = Some(obj_checksum);
+            expected_obj_checksum = compute_composite_checksum(&ChecksumAlgorithm::Crc32c, parts);
         }
 
         assert_eq!(
@@ -2096,6 +3410,81 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_sse_customer_key() {
+        let body = vec![0u8; 16];
+
+        let bucket = "test_bucket";
+        let client = MockClient::new(MockClientConfig {
+            bucket: bucket.to_owned(),
+            part_size: 1024,
+            ..Default::default()
+        });
+
+        let key = "key1";
+        let sse_customer_key = SseCustomerKey::new(&[1u8; SseCustomerKey::KEY_LENGTH]).unwrap();
+        let other_key = SseCustomerKey::new(&[2u8; SseCustomerKey::KEY_LENGTH]).unwrap();
+        let put_params = PutObjectSingleParams::new().sse_customer_key(Some(sse_customer_key.clone()));
+        client.put_object_single(bucket, key, &put_params, &body).await.unwrap();
+
+        // Requests without the key, or with a different key, fail
+        for params_key in [None, Some(other_key)] {
+            let head_params = HeadObjectParams::new().sse_customer_key(params_key.clone());
+            let head_result = client.head_object(bucket, key, &head_params).await;
+            assert!(matches!(head_result, Err(ObjectClientError::ClientError(_))));
+
+            let get_params = GetObjectParams::new().sse_customer_key(params_key);
+            let get_result = client.get_object(bucket, key, &get_params).await;
+            assert!(matches!(get_result, Err(ObjectClientError::ClientError(_))));
+        }
+
+        // Requests with the right key succeed
+        let head_params = HeadObjectParams::new().sse_customer_key(Some(sse_customer_key.clone()));
+        client.head_object(bucket, key, &head_params).await.unwrap();
+
+        let get_params = GetObjectParams::new().sse_customer_key(Some(sse_customer_key));
+        let get_result = client.get_object(bucket, key, &get_params).await.unwrap();
+        let actual = get_result.collect().await.unwrap();
+        assert_eq!(&actual[..], &body[..]);
+    }
+
+    #[test_case(ObjectLock::new().legal_hold(true), true; "legal hold")]
+    #[test_case(ObjectLock::new().retention("GOVERNANCE".to_owned(), OffsetDateTime::now_utc() + Duration::from_secs(3600)), true; "retention in the future")]
+    #[test_case(ObjectLock::new().retention("COMPLIANCE".to_owned(), OffsetDateTime::now_utc() - Duration::from_secs(3600)), false; "expired retention")]
+    #[test_case(ObjectLock::new(), false; "not locked")]
+    #[tokio::test]
+    async fn test_object_lock(object_lock: ObjectLock, locked: bool) {
+        let bucket = "test_bucket";
+        let client = MockClient::new(MockClientConfig {
+            bucket: bucket.to_owned(),
+            part_size: 1024,
+            ..Default::default()
+        });
+
+        let key = "key1";
+        let put_params = PutObjectSingleParams::new().object_lock(object_lock.clone());
+        client
+            .put_object_single(bucket, key, &put_params, b"hello")
+            .await
+            .unwrap();
+        assert_eq!(client.get_object_lock(key).unwrap(), object_lock);
+
+        let head_result = client.head_object(bucket, key, &HeadObjectParams::new()).await.unwrap();
+        assert_eq!(head_result.object_lock, object_lock);
+
+        let delete_result = client.delete_object(bucket, key).await;
+        if locked {
+            assert!(matches!(
+                delete_result,
+                Err(ObjectClientError::ServiceError(DeleteObjectError::ObjectLocked))
+            ));
+            assert!(client.contains_key(key));
+        } else {
+            delete_result.unwrap();
+            assert!(!client.contains_key(key));
+        }
+    }
+
     #[tokio::test]
     async fn counter_test() {
         let bucket = "test_bucket";
@@ -2125,6 +3514,41 @@ mod tests {
         assert_eq!(1, head_counter_2.count());
     }
 
+    #[test_case(ChecksumAlgorithm::Crc32, None, false; "crc32 composite")]
+    #[test_case(ChecksumAlgorithm::Crc32, Some(ChecksumType::FullObject), true; "crc32 full object")]
+    #[test_case(ChecksumAlgorithm::Crc64nvme, None, true; "crc64nvme default")]
+    #[test_case(ChecksumAlgorithm::Sha256, Some(ChecksumType::Composite), false; "sha256 composite")]
+    #[tokio::test]
+    async fn test_checksum_type(
+        checksum_algorithm: ChecksumAlgorithm,
+        checksum_type: Option<ChecksumType>,
+        expect_full_object: bool,
+    ) {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024,
+            ..Default::default()
+        });
+
+        let body = vec![0xAAu8; 5000];
+        let put_params = PutObjectParams::new()
+            .trailing_checksums(PutObjectTrailingChecksums::Enabled)
+            .checksum_algorithm(Some(checksum_algorithm.clone()))
+            .checksum_type(checksum_type);
+        let mut put_request = client.put_object("test_bucket", "key1", &put_params).await.unwrap();
+        put_request.write(&body).await.unwrap();
+        put_request.complete().await.unwrap();
+
+        let attrs = client
+            .get_object_attributes("test_bucket", "key1", None, None, &[ObjectAttribute::Checksum])
+            .await
+            .unwrap();
+        let checksum = attrs.checksum.expect("object checksum should be present");
+        assert_eq!(checksum.algorithms(), vec![checksum_algorithm.clone()]);
+        let full_object_checksum = compute_checksum(&body, &[checksum_algorithm]);
+        assert_eq!(checksum == full_object_checksum, expect_full_object);
+    }
+
     #[test_case(PutObjectTrailingChecksums::Enabled; "enabled")]
     #[test_case(PutObjectTrailingChecksums::ReviewOnly; "review only")]
     #[test_case(PutObjectTrailingChecksums::Disabled; "disabled")]
@@ -2206,11 +3630,13 @@ mod tests {
             let Checksum {
                 checksum_crc32,
                 checksum_crc32c,
+                checksum_crc64nvme,
                 checksum_sha1,
                 checksum_sha256,
             } = attrs.checksum.expect("object checksum should be present");
             assert!(checksum_crc32.is_none(), "CRC32 should not be set");
             assert!(checksum_crc32c.is_some(), "CRC32C should be set");
+            assert!(checksum_crc64nvme.is_none(), "CRC64NVME should not be set");
             assert!(checksum_sha1.is_none(), "SHA1 should not be set");
             assert!(checksum_sha256.is_none(), "SHA256 should not be set");
         } else {
@@ -13,11 +13,17 @@ use crate::mock_client::{
     MockClient, MockClientConfig, MockClientError, MockGetObjectResponse, MockObject, MockPutObjectRequest,
 };
 use crate::object_client::{
-    Checksum, CopyObjectError, CopyObjectParams, CopyObjectResult, DeleteObjectError, DeleteObjectResult, GetBodyPart,
+    AbortMultipartUploadError, AbortMultipartUploadResult, Checksum, CompleteMultipartUploadError,
+    CompleteMultipartUploadParams, CompleteMultipartUploadResult, CompletedPart, CopyObjectError, CopyObjectParams,
+    CopyObjectResult, CreateMultipartUploadError, CreateMultipartUploadParams, CreateMultipartUploadResult,
+    DeleteObjectError, DeleteObjectResult, DeleteObjectsError, DeleteObjectsResult, GetBodyPart,
     GetObjectAttributesError, GetObjectAttributesResult, GetObjectError, GetObjectParams, GetObjectResponse,
-    HeadObjectError, HeadObjectParams, HeadObjectResult, ListObjectsError, ListObjectsResult, ObjectAttribute,
-    ObjectChecksumError, ObjectClient, ObjectClientResult, ObjectMetadata, PutObjectError, PutObjectParams,
-    PutObjectResult, PutObjectSingleParams,
+    GetObjectTaggingError, GetObjectTaggingResult, HeadObjectError, HeadObjectParams, HeadObjectResult,
+    ListMultipartUploadsError, ListMultipartUploadsResult, ListObjectVersionsError, ListObjectVersionsResult,
+    ListObjectsError, ListObjectsResult, ListPartsError, ListPartsResult, ObjectAttribute, ObjectChecksumError,
+    ObjectClient, ObjectClientResult, ObjectMetadata, ObjectTags, PutObjectError, PutObjectParams, PutObjectResult,
+    PutObjectSingleParams, PutObjectTaggingError, PutObjectTaggingResult, RestoreObjectError, RestoreObjectParams,
+    RestoreObjectResult, UploadPartError, UploadPartParams, UploadPartResult,
 };
 
 use super::MockBackpressureHandle;
@@ -132,6 +138,14 @@ impl ObjectClient for ThroughputMockClient {
         self.inner.delete_object(bucket, key).await
     }
 
+    async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+    ) -> ObjectClientResult<DeleteObjectsResult, DeleteObjectsError, Self::ClientError> {
+        self.inner.delete_objects(bucket, keys).await
+    }
+
     async fn copy_object(
         &self,
         source_bucket: &str,
@@ -169,6 +183,20 @@ impl ObjectClient for ThroughputMockClient {
             .await
     }
 
+    async fn list_object_versions(
+        &self,
+        bucket: &str,
+        key_marker: Option<&str>,
+        version_id_marker: Option<&str>,
+        delimiter: &str,
+        max_keys: usize,
+        prefix: &str,
+    ) -> ObjectClientResult<ListObjectVersionsResult, ListObjectVersionsError, Self::ClientError> {
+        self.inner
+            .list_object_versions(bucket, key_marker, version_id_marker, delimiter, max_keys, prefix)
+            .await
+    }
+
     async fn head_object(
         &self,
         bucket: &str,
@@ -209,6 +237,101 @@ impl ObjectClient for ThroughputMockClient {
             .get_object_attributes(bucket, key, max_parts, part_number_marker, object_attributes)
             .await
     }
+
+    async fn get_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> ObjectClientResult<GetObjectTaggingResult, GetObjectTaggingError, Self::ClientError> {
+        self.inner.get_object_tagging(bucket, key).await
+    }
+
+    async fn put_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+        tags: &ObjectTags,
+    ) -> ObjectClientResult<PutObjectTaggingResult, PutObjectTaggingError, Self::ClientError> {
+        self.inner.put_object_tagging(bucket, key, tags).await
+    }
+
+    async fn restore_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &RestoreObjectParams,
+    ) -> ObjectClientResult<RestoreObjectResult, RestoreObjectError, Self::ClientError> {
+        // Call the trait method explicitly, as `MockClient` has an inherent method with the same name
+        ObjectClient::restore_object(&self.inner, bucket, key, params).await
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &CreateMultipartUploadParams,
+    ) -> ObjectClientResult<CreateMultipartUploadResult, CreateMultipartUploadError, Self::ClientError> {
+        self.inner.create_multipart_upload(bucket, key, params).await
+    }
+
+    async fn upload_part<'a>(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: usize,
+        params: &UploadPartParams,
+        contents: impl AsRef<[u8]> + Send + 'a,
+    ) -> ObjectClientResult<UploadPartResult, UploadPartError, Self::ClientError> {
+        self.inner
+            .upload_part(bucket, key, upload_id, part_number, params, contents)
+            .await
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: &[CompletedPart],
+        params: &CompleteMultipartUploadParams,
+    ) -> ObjectClientResult<CompleteMultipartUploadResult, CompleteMultipartUploadError, Self::ClientError> {
+        self.inner
+            .complete_multipart_upload(bucket, key, upload_id, parts, params)
+            .await
+    }
+
+    async fn list_parts(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number_marker: Option<usize>,
+    ) -> ObjectClientResult<ListPartsResult, ListPartsError, Self::ClientError> {
+        self.inner.list_parts(bucket, key, upload_id, part_number_marker).await
+    }
+
+    async fn list_multipart_uploads(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        key_marker: Option<&str>,
+        upload_id_marker: Option<&str>,
+        max_uploads: usize,
+    ) -> ObjectClientResult<ListMultipartUploadsResult, ListMultipartUploadsError, Self::ClientError> {
+        self.inner
+            .list_multipart_uploads(bucket, prefix, key_marker, upload_id_marker, max_uploads)
+            .await
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> ObjectClientResult<AbortMultipartUploadResult, AbortMultipartUploadError, Self::ClientError> {
+        self.inner.abort_multipart_upload(bucket, key, upload_id).await
+    }
 }
 
 #[cfg(test)]
@@ -10,12 +10,17 @@ use std::collections::HashMap;
 use thiserror::Error;
 use time::OffsetDateTime;
 
-use crate::checksums::{self, crc32_to_base64, crc32c_to_base64, sha1_to_base64, sha256_to_base64};
+use crate::checksums::{
+    self, crc32_to_base64, crc32c_to_base64, crc64nvme_to_base64, sha1_to_base64, sha256_to_base64,
+};
 use crate::error_metadata::{ClientErrorMetadata, ProvideErrorMetadata};
 
 mod etag;
 pub use etag::ETag;
 
+mod sse_customer_key;
+pub use sse_customer_key::{InvalidSseCustomerKeyError, SseCustomerKey};
+
 /// A generic interface to S3-like object storage services.
 ///
 /// This trait defines the common methods that all object services implement.
@@ -55,6 +60,17 @@ pub trait ObjectClient {
         key: &str,
     ) -> ObjectClientResult<DeleteObjectResult, DeleteObjectError, Self::ClientError>;
 
+    /// Delete up to [MAX_DELETE_OBJECTS_KEYS] objects from the object store in a single request.
+    ///
+    /// Unlike [`delete_object`](Self::delete_object), failing to delete some of the objects
+    /// doesn't fail the request: the keys that couldn't be deleted are reported in the result.
+    /// Deleting an object that doesn't exist succeeds.
+    async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+    ) -> ObjectClientResult<DeleteObjectsResult, DeleteObjectsError, Self::ClientError>;
+
     /// Create a copy of an existing object. Currently, this functionality has the following limitations:
     /// - Supported only for copying between matching bucket types:
     ///     - Standard S3 to Standard S3 buckets.
@@ -90,6 +106,18 @@ pub trait ObjectClient {
         prefix: &str,
     ) -> ObjectClientResult<ListObjectsResult, ListObjectsError, Self::ClientError>;
 
+    /// List the versions and delete markers of the objects in a bucket under a given prefix, in
+    /// ascending key order. The versions of the same key are ordered from the most recent.
+    async fn list_object_versions(
+        &self,
+        bucket: &str,
+        key_marker: Option<&str>,
+        version_id_marker: Option<&str>,
+        delimiter: &str,
+        max_keys: usize,
+        prefix: &str,
+    ) -> ObjectClientResult<ListObjectVersionsResult, ListObjectVersionsError, Self::ClientError>;
+
     /// Retrieve object metadata without retrieving the object contents
     async fn head_object(
         &self,
@@ -125,6 +153,94 @@ pub trait ObjectClient {
         part_number_marker: Option<usize>,
         object_attributes: &[ObjectAttribute],
     ) -> ObjectClientResult<GetObjectAttributesResult, GetObjectAttributesError, Self::ClientError>;
+
+    /// Retrieve the tags of an object.
+    async fn get_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> ObjectClientResult<GetObjectTaggingResult, GetObjectTaggingError, Self::ClientError>;
+
+    /// Replace the tags of an object with the given set of tags.
+    async fn put_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+        tags: &ObjectTags,
+    ) -> ObjectClientResult<PutObjectTaggingResult, PutObjectTaggingError, Self::ClientError>;
+
+    /// Start restoring a temporary copy of an archived object (in the GLACIER or DEEP_ARCHIVE
+    /// storage classes), so that it can be read. Restores complete asynchronously, and their
+    /// progress is reported by the [`restore_status`](HeadObjectResult::restore_status) of the object.
+    async fn restore_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &RestoreObjectParams,
+    ) -> ObjectClientResult<RestoreObjectResult, RestoreObjectError, Self::ClientError>;
+
+    /// Initiate a multipart upload and return its upload ID.
+    ///
+    /// Unlike [`put_object`](Self::put_object), this method gives the caller control over the
+    /// individual parts of the upload. The upload must eventually be either completed with
+    /// [`complete_multipart_upload`](Self::complete_multipart_upload) or aborted with
+    /// [`abort_multipart_upload`](Self::abort_multipart_upload).
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &CreateMultipartUploadParams,
+    ) -> ObjectClientResult<CreateMultipartUploadResult, CreateMultipartUploadError, Self::ClientError>;
+
+    /// Upload a single part of a multipart upload. Part numbers start at 1.
+    async fn upload_part<'a>(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: usize,
+        params: &UploadPartParams,
+        contents: impl AsRef<[u8]> + Send + 'a,
+    ) -> ObjectClientResult<UploadPartResult, UploadPartError, Self::ClientError>;
+
+    /// Complete a multipart upload by assembling the given parts, in ascending part number order.
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: &[CompletedPart],
+        params: &CompleteMultipartUploadParams,
+    ) -> ObjectClientResult<CompleteMultipartUploadResult, CompleteMultipartUploadError, Self::ClientError>;
+
+    /// List the parts that have been uploaded for a multipart upload, starting after the given
+    /// part number marker.
+    async fn list_parts(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number_marker: Option<usize>,
+    ) -> ObjectClientResult<ListPartsResult, ListPartsError, Self::ClientError>;
+
+    /// List the multipart uploads in progress for keys beginning with the given prefix, in
+    /// ascending key order. Uploads for the same key are ordered by the time they were initiated.
+    async fn list_multipart_uploads(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        key_marker: Option<&str>,
+        upload_id_marker: Option<&str>,
+        max_uploads: usize,
+    ) -> ObjectClientResult<ListMultipartUploadsResult, ListMultipartUploadsError, Self::ClientError>;
+
+    /// Abort a multipart upload, discarding any parts uploaded so far.
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> ObjectClientResult<AbortMultipartUploadResult, AbortMultipartUploadError, Self::ClientError>;
 }
 
 /// The top-level error type returned by calls to an [`ObjectClient`].
@@ -189,6 +305,8 @@ pub struct GetObjectParams {
     pub range: Option<Range<u64>>,
     pub if_match: Option<ETag>,
     pub checksum_mode: Option<ChecksumMode>,
+    pub sse_customer_key: Option<SseCustomerKey>,
+    pub version_id: Option<String>,
 }
 
 impl GetObjectParams {
@@ -214,6 +332,18 @@ impl GetObjectParams {
         self.checksum_mode = value;
         self
     }
+
+    /// Set the customer-provided key the object was encrypted with (SSE-C).
+    pub fn sse_customer_key(mut self, value: Option<SseCustomerKey>) -> Self {
+        self.sse_customer_key = value;
+        self
+    }
+
+    /// Set the version of the object to retrieve, instead of the current version
+    pub fn version_id(mut self, value: Option<String>) -> Self {
+        self.version_id = value;
+        self
+    }
 }
 
 /// Result of a [`list_objects`](ObjectClient::list_objects) request
@@ -239,12 +369,152 @@ pub enum ListObjectsError {
     NoSuchBucket,
 }
 
+/// Result of a [`list_object_versions`](ObjectClient::list_object_versions) request
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ListObjectVersionsResult {
+    /// The list of object versions and delete markers.
+    pub versions: Vec<ObjectVersionInfo>,
+
+    /// The list of common prefixes. This rolls up all of the keys with a common prefix up to
+    /// the next instance of the delimiter.
+    pub common_prefixes: Vec<String>,
+
+    /// If present, the key marker to use to query more results.
+    pub next_key_marker: Option<String>,
+
+    /// If present, the version ID marker to use (together with the key marker) to query more results.
+    pub next_version_id_marker: Option<String>,
+}
+
+/// Errors returned by a [`list_object_versions`](ObjectClient::list_object_versions) request
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ListObjectVersionsError {
+    #[error("The bucket does not exist")]
+    NoSuchBucket,
+}
+
+/// Result of a [`get_object_tagging`](ObjectClient::get_object_tagging) request
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct GetObjectTaggingResult {
+    /// The tags of the object.
+    pub tags: ObjectTags,
+}
+
+/// Errors returned by a [`get_object_tagging`](ObjectClient::get_object_tagging) request
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GetObjectTaggingError {
+    #[error("The bucket does not exist")]
+    NoSuchBucket,
+
+    #[error("The key does not exist")]
+    NoSuchKey,
+}
+
+/// Result of a [`put_object_tagging`](ObjectClient::put_object_tagging) request
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct PutObjectTaggingResult {}
+
+/// Errors returned by a [`put_object_tagging`](ObjectClient::put_object_tagging) request
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PutObjectTaggingError {
+    #[error("The bucket does not exist")]
+    NoSuchBucket,
+
+    #[error("The key does not exist")]
+    NoSuchKey,
+
+    #[error("The tags are not valid")]
+    InvalidTag,
+}
+
+/// Parameters to a [`restore_object`](ObjectClient::restore_object) request
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct RestoreObjectParams {
+    /// Number of days to keep the restored copy of the object for
+    pub days: u32,
+    /// Retrieval tier to restore the object with. S3 uses the Standard tier if not set.
+    pub tier: Option<RestoreTier>,
+}
+
+impl RestoreObjectParams {
+    /// Create a [RestoreObjectParams] to keep the restored copy for the given number of days.
+    pub fn new(days: u32) -> Self {
+        Self { days, tier: None }
+    }
+
+    /// Set the retrieval tier.
+    pub fn tier(mut self, value: Option<RestoreTier>) -> Self {
+        self.tier = value;
+        self
+    }
+}
+
+/// Retrieval tier of a [`restore_object`](ObjectClient::restore_object) request, which trades
+/// off how quickly the object is restored against its cost.
+///
+/// See [Archive retrieval options](https://docs.aws.amazon.com/AmazonS3/latest/userguide/restoring-objects-retrieval-options.html)
+/// in the *Amazon S3 User Guide* for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreTier {
+    Expedited,
+    Standard,
+    Bulk,
+}
+
+impl RestoreTier {
+    /// The name of this tier in S3 requests.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestoreTier::Expedited => "Expedited",
+            RestoreTier::Standard => "Standard",
+            RestoreTier::Bulk => "Bulk",
+        }
+    }
+}
+
+/// Result of a [`restore_object`](ObjectClient::restore_object) request
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct RestoreObjectResult {
+    /// True if the object was already restored, in which case S3 only updated the expiry of the
+    /// restored copy.
+    pub already_restored: bool,
+}
+
+/// Errors returned by a [`restore_object`](ObjectClient::restore_object) request
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RestoreObjectError {
+    #[error("The bucket does not exist")]
+    NoSuchBucket,
+
+    #[error("The key does not exist")]
+    NoSuchKey,
+
+    #[error("The object is not in an archive storage class")]
+    InvalidObjectState,
+
+    #[error("The object is already being restored")]
+    RestoreAlreadyInProgress,
+}
+
 /// Parameters to a [`head_object`](ObjectClient::head_object) request
 #[derive(Debug, Default, Clone)]
 #[non_exhaustive]
 pub struct HeadObjectParams {
     /// Enable to retrieve checksum as part of the HeadObject request
     pub checksum_mode: Option<ChecksumMode>,
+    /// Customer-provided key the object was encrypted with
+    pub sse_customer_key: Option<SseCustomerKey>,
+    /// Version of the object to retrieve, instead of the current version
+    pub version_id: Option<String>,
 }
 
 impl HeadObjectParams {
@@ -258,6 +528,18 @@ impl HeadObjectParams {
         self.checksum_mode = value;
         self
     }
+
+    /// Set the customer-provided key for server-side encryption (SSE-C).
+    pub fn sse_customer_key(mut self, value: Option<SseCustomerKey>) -> Self {
+        self.sse_customer_key = value;
+        self
+    }
+
+    /// Set the version of the object to retrieve, instead of the current version
+    pub fn version_id(mut self, value: Option<String>) -> Self {
+        self.version_id = value;
+        self
+    }
 }
 
 /// Enable [ChecksumMode] to retrieve object checksums
@@ -305,6 +587,12 @@ pub struct HeadObjectResult {
 
     /// Server-side encryption KMS key ID that was used to store the object.
     pub sse_kms_key_id: Option<String>,
+
+    /// Object Lock retention and legal hold of the object.
+    ///
+    /// Only returned by S3 if the caller has permission to read them (`s3:GetObjectRetention` and
+    /// `s3:GetObjectLegalHold`), otherwise the object is reported as not locked.
+    pub object_lock: ObjectLock,
 }
 
 /// Errors returned by a [`head_object`](ObjectClient::head_object) request
@@ -330,6 +618,38 @@ pub struct DeleteObjectResult {}
 pub enum DeleteObjectError {
     #[error("The bucket does not exist")]
     NoSuchBucket,
+    #[error("The object is protected by Object Lock")]
+    ObjectLocked,
+}
+
+/// Maximum number of keys a [`delete_objects`](ObjectClient::delete_objects) request can delete
+pub const MAX_DELETE_OBJECTS_KEYS: usize = 1000;
+
+/// Result of a [`delete_objects`](ObjectClient::delete_objects) request
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct DeleteObjectsResult {
+    /// The keys that couldn't be deleted. All the other keys were deleted.
+    pub errors: Vec<DeleteObjectsKeyError>,
+}
+
+/// A key that a [`delete_objects`](ObjectClient::delete_objects) request couldn't delete
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeleteObjectsKeyError {
+    pub key: String,
+    /// The S3 error code, such as `AccessDenied`
+    pub code: String,
+    pub message: String,
+}
+
+/// Errors returned by a [`delete_objects`](ObjectClient::delete_objects) request
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DeleteObjectsError {
+    #[error("The bucket does not exist")]
+    NoSuchBucket,
+    #[error("Too many keys to delete in a single request")]
+    TooManyKeys,
 }
 
 /// Result of a [`copy_object`](ObjectClient::copy_object) request
@@ -397,23 +717,90 @@ pub enum GetObjectAttributesError {
 
 pub type ObjectMetadata = HashMap<String, String>;
 
+/// Tags of an S3 object, as a map from tag key to tag value.
+///
+/// See [Categorizing your storage using tags](https://docs.aws.amazon.com/AmazonS3/latest/userguide/object-tagging.html)
+/// in the *Amazon S3 User Guide* for more details.
+pub type ObjectTags = HashMap<String, String>;
+
+/// Object Lock retention and legal hold settings of an S3 object.
+///
+/// See [Locking objects with Object Lock](https://docs.aws.amazon.com/AmazonS3/latest/userguide/object-lock.html)
+/// in the *Amazon S3 User Guide* for more details.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ObjectLock {
+    /// Retention mode (GOVERNANCE or COMPLIANCE)
+    pub mode: Option<String>,
+    /// Date until which the object is retained. Always set together with `mode`.
+    pub retain_until_date: Option<OffsetDateTime>,
+    /// Whether a legal hold is placed on the object
+    pub legal_hold: bool,
+}
+
+impl ObjectLock {
+    /// Create a default [ObjectLock], which does not lock the object.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the retention mode and the date until which the object is retained.
+    pub fn retention(mut self, mode: String, retain_until_date: OffsetDateTime) -> Self {
+        self.mode = Some(mode);
+        self.retain_until_date = Some(retain_until_date);
+        self
+    }
+
+    /// Set whether a legal hold is placed on the object.
+    pub fn legal_hold(mut self, value: bool) -> Self {
+        self.legal_hold = value;
+        self
+    }
+
+    /// Whether these settings neither retain nor hold the object.
+    pub fn is_empty(&self) -> bool {
+        self.mode.is_none() && !self.legal_hold
+    }
+
+    /// Whether the object is protected from deletion at the given time.
+    pub fn is_locked_at(&self, time: OffsetDateTime) -> bool {
+        self.legal_hold || self.retain_until_date.is_some_and(|date| date > time)
+    }
+}
+
 /// Parameters to a [`put_object`](ObjectClient::put_object) request
 #[derive(Debug, Default, Clone)]
 #[non_exhaustive]
 pub struct PutObjectParams {
-    /// Enable Crc32c trailing checksums.
+    /// Enable trailing checksums.
     pub trailing_checksums: PutObjectTrailingChecksums,
+    /// Algorithm of the trailing checksums (defaults to CRC32C)
+    pub checksum_algorithm: Option<ChecksumAlgorithm>,
+    /// Whether the checksum of a multipart object covers the full object or combines the part checksums
+    pub checksum_type: Option<ChecksumType>,
     /// Storage class to be used when creating new S3 object
     pub storage_class: Option<String>,
+    /// Canned ACL to apply to the new S3 object (for example, private, bucket-owner-full-control)
+    pub acl: Option<String>,
+    /// Content type to set on the new S3 object (for example, text/html)
+    pub content_type: Option<String>,
     /// The server-side encryption algorithm to be used for this object in Amazon S3 (for example, AES256, aws:kms, aws:kms:dsse)
     pub server_side_encryption: Option<String>,
     /// If `server_side_encryption` has a valid value of aws:kms or aws:kms:dsse, this value may be used to specify AWS KMS key ID to be used
     /// when creating new S3 object
     pub ssekms_key_id: Option<String>,
+    /// Customer-provided key to encrypt the new S3 object with (SSE-C)
+    pub sse_customer_key: Option<SseCustomerKey>,
+    /// Requires pre-existing object to match the given etag in order to complete the request
+    pub if_match: Option<ETag>,
     /// Custom headers to add to the request
     pub custom_headers: Vec<(String, String)>,
     /// User-defined object metadata
     pub object_metadata: ObjectMetadata,
+    /// Tags to set on the new object
+    pub object_tags: ObjectTags,
+    /// Object Lock retention and legal hold to set on the new object
+    pub object_lock: ObjectLock,
 }
 
 impl PutObjectParams {
@@ -422,18 +809,42 @@ impl PutObjectParams {
         Self::default()
     }
 
-    /// Set Crc32c trailing checksums.
+    /// Set trailing checksums.
     pub fn trailing_checksums(mut self, value: PutObjectTrailingChecksums) -> Self {
         self.trailing_checksums = value;
         self
     }
 
+    /// Set the algorithm of the trailing checksums.
+    pub fn checksum_algorithm(mut self, value: Option<ChecksumAlgorithm>) -> Self {
+        self.checksum_algorithm = value;
+        self
+    }
+
+    /// Set the checksum type of the new object.
+    pub fn checksum_type(mut self, value: Option<ChecksumType>) -> Self {
+        self.checksum_type = value;
+        self
+    }
+
     /// Set the storage class.
     pub fn storage_class(mut self, value: String) -> Self {
         self.storage_class = Some(value);
         self
     }
 
+    /// Set the canned ACL.
+    pub fn acl(mut self, value: String) -> Self {
+        self.acl = Some(value);
+        self
+    }
+
+    /// Set the content type of the new object.
+    pub fn content_type(mut self, value: String) -> Self {
+        self.content_type = Some(value);
+        self
+    }
+
     /// Set server-side encryption type.
     pub fn server_side_encryption(mut self, value: Option<String>) -> Self {
         self.server_side_encryption = value;
@@ -446,6 +857,18 @@ impl PutObjectParams {
         self
     }
 
+    /// Set the customer-provided key for server-side encryption (SSE-C).
+    pub fn sse_customer_key(mut self, value: Option<SseCustomerKey>) -> Self {
+        self.sse_customer_key = value;
+        self
+    }
+
+    /// Set the required etag on the pre-existing object.
+    pub fn if_match(mut self, value: Option<ETag>) -> Self {
+        self.if_match = value;
+        self
+    }
+
     /// Add a custom header to the request.
     pub fn add_custom_header(mut self, name: String, value: String) -> Self {
         self.custom_headers.push((name, value));
@@ -457,9 +880,21 @@ impl PutObjectParams {
         self.object_metadata = value;
         self
     }
+
+    /// Set the tags of the new object.
+    pub fn object_tags(mut self, value: ObjectTags) -> Self {
+        self.object_tags = value;
+        self
+    }
+
+    /// Set the Object Lock retention and legal hold of the new object.
+    pub fn object_lock(mut self, value: ObjectLock) -> Self {
+        self.object_lock = value;
+        self
+    }
 }
 
-/// How CRC32c checksums are used for parts of a multi-part PutObject request
+/// How checksums are used for parts of a multi-part PutObject request
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum PutObjectTrailingChecksums {
     /// Checksums are computed, passed to upload review, and also sent to S3
@@ -488,11 +923,17 @@ pub struct PutObjectSingleParams {
     pub checksum: Option<UploadChecksum>,
     /// Storage class to be used when creating new S3 object
     pub storage_class: Option<String>,
+    /// Canned ACL to apply to the new S3 object (for example, private, bucket-owner-full-control)
+    pub acl: Option<String>,
+    /// Content type to set on the new S3 object (for example, text/html)
+    pub content_type: Option<String>,
     /// The server-side encryption algorithm to be used for this object in Amazon S3 (for example, AES256, aws:kms, aws:kms:dsse)
     pub server_side_encryption: Option<String>,
     /// If `server_side_encryption` has a valid value of aws:kms or aws:kms:dsse, this value may be used to specify AWS KMS key ID to be used
     /// when creating new S3 object
     pub ssekms_key_id: Option<String>,
+    /// Customer-provided key to encrypt the new S3 object with (SSE-C)
+    pub sse_customer_key: Option<SseCustomerKey>,
     /// Requires pre-existing object to match the given etag in order to perform the request
     pub if_match: Option<ETag>,
     /// Offset on the pre-existing object where to append the data in the request
@@ -501,6 +942,10 @@ pub struct PutObjectSingleParams {
     pub custom_headers: Vec<(String, String)>,
     /// User-defined object metadata
     pub object_metadata: ObjectMetadata,
+    /// Tags to set on the new object
+    pub object_tags: ObjectTags,
+    /// Object Lock retention and legal hold to set on the new object
+    pub object_lock: ObjectLock,
 }
 
 impl PutObjectSingleParams {
@@ -526,6 +971,18 @@ impl PutObjectSingleParams {
         self
     }
 
+    /// Set the canned ACL.
+    pub fn acl(mut self, value: String) -> Self {
+        self.acl = Some(value);
+        self
+    }
+
+    /// Set the content type of the new object.
+    pub fn content_type(mut self, value: String) -> Self {
+        self.content_type = Some(value);
+        self
+    }
+
     /// Set server-side encryption type.
     pub fn server_side_encryption(mut self, value: Option<String>) -> Self {
         self.server_side_encryption = value;
@@ -538,6 +995,12 @@ impl PutObjectSingleParams {
         self
     }
 
+    /// Set the customer-provided key for server-side encryption (SSE-C).
+    pub fn sse_customer_key(mut self, value: Option<SseCustomerKey>) -> Self {
+        self.sse_customer_key = value;
+        self
+    }
+
     /// Set the required etag on the pre-existing object.
     pub fn if_match(mut self, value: Option<ETag>) -> Self {
         self.if_match = value;
@@ -561,6 +1024,41 @@ impl PutObjectSingleParams {
         self.object_metadata = value;
         self
     }
+
+    /// Set the tags of the new object.
+    pub fn object_tags(mut self, value: ObjectTags) -> Self {
+        self.object_tags = value;
+        self
+    }
+
+    /// Set the Object Lock retention and legal hold of the new object.
+    pub fn object_lock(mut self, value: ObjectLock) -> Self {
+        self.object_lock = value;
+        self
+    }
+}
+
+/// How the checksum of an object uploaded in multiple parts is computed.
+///
+/// See [Checking object integrity](https://docs.aws.amazon.com/AmazonS3/latest/userguide/checking-object-integrity.html)
+/// in the *Amazon S3 User Guide* for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChecksumType {
+    /// The checksum covers the full object, as if it was uploaded in a single part. Only supported for CRC
+    /// algorithms.
+    FullObject,
+    /// The checksum is computed from the checksums of each part.
+    Composite,
+}
+
+impl fmt::Display for ChecksumType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChecksumType::FullObject => f.write_str("FULL_OBJECT"),
+            ChecksumType::Composite => f.write_str("COMPOSITE"),
+        }
+    }
 }
 
 /// A checksum used by the object client for integrity checks on uploads.
@@ -569,6 +1067,7 @@ impl PutObjectSingleParams {
 pub enum UploadChecksum {
     Crc32c(checksums::Crc32c),
     Crc32(checksums::Crc32),
+    Crc64nvme(checksums::Crc64),
     Sha1(checksums::Sha1),
     Sha256(checksums::Sha256),
 }
@@ -579,10 +1078,22 @@ impl UploadChecksum {
         match self {
             UploadChecksum::Crc32c(_) => ChecksumAlgorithm::Crc32c,
             UploadChecksum::Crc32(_) => ChecksumAlgorithm::Crc32,
+            UploadChecksum::Crc64nvme(_) => ChecksumAlgorithm::Crc64nvme,
             UploadChecksum::Sha1(_) => ChecksumAlgorithm::Sha1,
             UploadChecksum::Sha256(_) => ChecksumAlgorithm::Sha256,
         }
     }
+
+    /// The base64 encoding of this checksum, as sent to S3.
+    pub fn to_base64(&self) -> String {
+        match self {
+            UploadChecksum::Crc32c(crc32c) => crc32c_to_base64(crc32c),
+            UploadChecksum::Crc32(crc32) => crc32_to_base64(crc32),
+            UploadChecksum::Crc64nvme(crc64) => crc64nvme_to_base64(crc64),
+            UploadChecksum::Sha1(sha1) => sha1_to_base64(sha1),
+            UploadChecksum::Sha256(sha256) => sha256_to_base64(sha256),
+        }
+    }
 }
 
 /// A handle for controlling backpressure enabled requests.
@@ -714,6 +1225,338 @@ pub enum PutObjectError {
     NotImplemented,
 }
 
+/// Parameters to a [`create_multipart_upload`](ObjectClient::create_multipart_upload) request
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct CreateMultipartUploadParams {
+    /// Algorithm of the checksums that will be provided for each part
+    pub checksum_algorithm: Option<ChecksumAlgorithm>,
+    /// Whether the checksum of the object covers the full object or combines the part checksums
+    pub checksum_type: Option<ChecksumType>,
+    /// Storage class to be used when creating new S3 object
+    pub storage_class: Option<String>,
+    /// Canned ACL to apply to the new S3 object (for example, private, bucket-owner-full-control)
+    pub acl: Option<String>,
+    /// Content type to set on the new S3 object (for example, text/html)
+    pub content_type: Option<String>,
+    /// The server-side encryption algorithm to be used for this object in Amazon S3 (for example, AES256, aws:kms, aws:kms:dsse)
+    pub server_side_encryption: Option<String>,
+    /// If `server_side_encryption` has a valid value of aws:kms or aws:kms:dsse, this value may be used to specify AWS KMS key ID to be used
+    /// when creating new S3 object
+    pub ssekms_key_id: Option<String>,
+    /// Customer-provided key to encrypt the new S3 object with (SSE-C)
+    pub sse_customer_key: Option<SseCustomerKey>,
+    /// User-defined object metadata
+    pub object_metadata: ObjectMetadata,
+    /// Tags to set on the new object
+    pub object_tags: ObjectTags,
+    /// Object Lock retention and legal hold to set on the new object
+    pub object_lock: ObjectLock,
+}
+
+impl CreateMultipartUploadParams {
+    /// Create a default [CreateMultipartUploadParams].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the algorithm of the part checksums.
+    pub fn checksum_algorithm(mut self, value: Option<ChecksumAlgorithm>) -> Self {
+        self.checksum_algorithm = value;
+        self
+    }
+
+    /// Set the checksum type of the new object.
+    pub fn checksum_type(mut self, value: Option<ChecksumType>) -> Self {
+        self.checksum_type = value;
+        self
+    }
+
+    /// Set the storage class.
+    pub fn storage_class(mut self, value: String) -> Self {
+        self.storage_class = Some(value);
+        self
+    }
+
+    /// Set the canned ACL.
+    pub fn acl(mut self, value: String) -> Self {
+        self.acl = Some(value);
+        self
+    }
+
+    /// Set the content type of the new object.
+    pub fn content_type(mut self, value: String) -> Self {
+        self.content_type = Some(value);
+        self
+    }
+
+    /// Set server-side encryption type.
+    pub fn server_side_encryption(mut self, value: Option<String>) -> Self {
+        self.server_side_encryption = value;
+        self
+    }
+
+    /// Set KMS key ID to be used for server-side encryption.
+    pub fn ssekms_key_id(mut self, value: Option<String>) -> Self {
+        self.ssekms_key_id = value;
+        self
+    }
+
+    /// Set the customer-provided key for server-side encryption (SSE-C).
+    pub fn sse_customer_key(mut self, value: Option<SseCustomerKey>) -> Self {
+        self.sse_customer_key = value;
+        self
+    }
+
+    /// Set user defined object metadata.
+    pub fn object_metadata(mut self, value: ObjectMetadata) -> Self {
+        self.object_metadata = value;
+        self
+    }
+
+    /// Set the tags of the new object.
+    pub fn object_tags(mut self, value: ObjectTags) -> Self {
+        self.object_tags = value;
+        self
+    }
+
+    /// Set the Object Lock retention and legal hold of the new object.
+    pub fn object_lock(mut self, value: ObjectLock) -> Self {
+        self.object_lock = value;
+        self
+    }
+}
+
+/// Result of a [`create_multipart_upload`](ObjectClient::create_multipart_upload) request
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct CreateMultipartUploadResult {
+    /// ID of the new multipart upload
+    pub upload_id: String,
+}
+
+/// Errors returned by a [`create_multipart_upload`](ObjectClient::create_multipart_upload) request
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CreateMultipartUploadError {
+    #[error("The bucket does not exist")]
+    NoSuchBucket,
+}
+
+/// Parameters to an [`upload_part`](ObjectClient::upload_part) request
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct UploadPartParams {
+    /// User-provided checksum of the part data.
+    pub checksum: Option<UploadChecksum>,
+    /// Customer-provided key the multipart upload was created with (SSE-C)
+    pub sse_customer_key: Option<SseCustomerKey>,
+}
+
+impl UploadPartParams {
+    /// Create a default [UploadPartParams].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set checksum.
+    pub fn checksum(mut self, value: Option<UploadChecksum>) -> Self {
+        self.checksum = value;
+        self
+    }
+
+    /// Set the customer-provided key for server-side encryption (SSE-C).
+    pub fn sse_customer_key(mut self, value: Option<SseCustomerKey>) -> Self {
+        self.sse_customer_key = value;
+        self
+    }
+}
+
+/// Result of an [`upload_part`](ObjectClient::upload_part) request
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct UploadPartResult {
+    /// ETag of the uploaded part
+    pub etag: ETag,
+}
+
+/// Errors returned by an [`upload_part`](ObjectClient::upload_part) request
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UploadPartError {
+    #[error("The bucket does not exist")]
+    NoSuchBucket,
+
+    #[error("The multipart upload does not exist")]
+    NoSuchUpload,
+
+    #[error("The provided checksum does not match the data")]
+    BadChecksum,
+}
+
+/// A part to be assembled by a [`complete_multipart_upload`](ObjectClient::complete_multipart_upload) request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletedPart {
+    /// Number of the part, this value is a positive integer between 1 and 10,000
+    pub part_number: usize,
+
+    /// ETag returned when the part was uploaded
+    pub etag: ETag,
+
+    /// Checksum of the part. Required if the upload was created with a checksum algorithm.
+    pub checksum: Checksum,
+}
+
+/// Parameters to a [`complete_multipart_upload`](ObjectClient::complete_multipart_upload) request
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct CompleteMultipartUploadParams {
+    /// Only complete the upload if the existing object has this ETag
+    pub if_match: Option<ETag>,
+}
+
+impl CompleteMultipartUploadParams {
+    /// Create a default [CompleteMultipartUploadParams].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the required ETag of the existing object.
+    pub fn if_match(mut self, value: Option<ETag>) -> Self {
+        self.if_match = value;
+        self
+    }
+}
+
+/// Result of a [`complete_multipart_upload`](ObjectClient::complete_multipart_upload) request
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct CompleteMultipartUploadResult {
+    /// ETag of the assembled object
+    pub etag: ETag,
+}
+
+/// Errors returned by a [`complete_multipart_upload`](ObjectClient::complete_multipart_upload) request
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CompleteMultipartUploadError {
+    #[error("The bucket does not exist")]
+    NoSuchBucket,
+
+    #[error("The multipart upload does not exist")]
+    NoSuchUpload,
+
+    #[error("One or more of the specified parts could not be found or did not match")]
+    InvalidPart,
+
+    #[error("The list of parts was not in ascending order")]
+    InvalidPartOrder,
+
+    #[error("One of the parts is smaller than the minimum allowed part size")]
+    EntityTooSmall,
+
+    #[error("The existing object did not match the provided ETag")]
+    PreconditionFailed,
+}
+
+/// Metadata about a part of an in-progress multipart upload, as returned by a
+/// [`list_parts`](ObjectClient::list_parts) request
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct UploadedPart {
+    /// Number of the part, this value is a positive integer between 1 and 10,000
+    pub part_number: usize,
+
+    /// ETag of the part
+    pub etag: ETag,
+
+    /// Size of the part in bytes
+    pub size: u64,
+
+    /// Checksum of the part
+    pub checksum: Checksum,
+}
+
+/// Result of a [`list_parts`](ObjectClient::list_parts) request
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ListPartsResult {
+    /// The list of parts, in ascending part number order.
+    pub parts: Vec<UploadedPart>,
+
+    /// If present, the part number marker to use to query more results.
+    pub next_part_number_marker: Option<usize>,
+}
+
+/// Errors returned by a [`list_parts`](ObjectClient::list_parts) request
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ListPartsError {
+    #[error("The bucket does not exist")]
+    NoSuchBucket,
+
+    #[error("The multipart upload does not exist")]
+    NoSuchUpload,
+}
+
+/// Metadata about a multipart upload in progress.
+///
+/// See [MultipartUpload](https://docs.aws.amazon.com/AmazonS3/latest/API/API_MultipartUpload.html)
+/// in the *Amazon S3 API Reference* for more details.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct MultipartUploadInfo {
+    /// Key of the object being uploaded.
+    pub key: String,
+
+    /// ID of the multipart upload.
+    pub upload_id: String,
+
+    /// The time the multipart upload was initiated.
+    pub initiated: OffsetDateTime,
+
+    /// Storage class the object will be stored in once the upload is completed.
+    pub storage_class: Option<String>,
+}
+
+/// Result of a [`list_multipart_uploads`](ObjectClient::list_multipart_uploads) request
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ListMultipartUploadsResult {
+    /// The list of multipart uploads.
+    pub uploads: Vec<MultipartUploadInfo>,
+
+    /// If present, the key marker to use to query more results.
+    pub next_key_marker: Option<String>,
+
+    /// If present, the upload ID marker to use (together with the key marker) to query more results.
+    pub next_upload_id_marker: Option<String>,
+}
+
+/// Errors returned by a [`list_multipart_uploads`](ObjectClient::list_multipart_uploads) request
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ListMultipartUploadsError {
+    #[error("The bucket does not exist")]
+    NoSuchBucket,
+}
+
+/// Result of an [`abort_multipart_upload`](ObjectClient::abort_multipart_upload) request
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct AbortMultipartUploadResult {}
+
+/// Errors returned by an [`abort_multipart_upload`](ObjectClient::abort_multipart_upload) request
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AbortMultipartUploadError {
+    #[error("The bucket does not exist")]
+    NoSuchBucket,
+
+    #[error("The multipart upload does not exist")]
+    NoSuchUpload,
+}
+
 /// Restoration status for S3 objects in flexible retrieval storage classes.
 ///
 /// See [Checking restore status and expiration
@@ -768,6 +1611,40 @@ pub struct ObjectInfo {
     pub checksum_algorithms: Vec<ChecksumAlgorithm>,
 }
 
+/// Metadata about a single version of an S3 object, or a delete marker.
+///
+/// See [ObjectVersion](https://docs.aws.amazon.com/AmazonS3/latest/API/API_ObjectVersion.html) and
+/// [DeleteMarkerEntry](https://docs.aws.amazon.com/AmazonS3/latest/API/API_DeleteMarkerEntry.html)
+/// in the *Amazon S3 API Reference* for more details.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ObjectVersionInfo {
+    /// Key for this object.
+    pub key: String,
+
+    /// Version ID of this version, or `null` for versions created while versioning was not enabled.
+    pub version_id: String,
+
+    /// Whether this is the current version of the object.
+    pub is_latest: bool,
+
+    /// Whether this is a delete marker rather than a version of the object. Delete markers have no
+    /// size, entity tag, or storage class.
+    pub is_delete_marker: bool,
+
+    /// The time this version was created.
+    pub last_modified: OffsetDateTime,
+
+    /// Size of this version in bytes.
+    pub size: u64,
+
+    /// Entity tag of this version.
+    pub etag: Option<String>,
+
+    /// Storage class for this version.
+    pub storage_class: Option<String>,
+}
+
 /// All possible object attributes that can be retrived from [ObjectClient::get_object_attributes].
 /// Fields that you do not specify are not returned.
 #[derive(Debug)]
@@ -813,6 +1690,9 @@ pub struct Checksum {
     /// Base64-encoded, 32-bit CRC32C checksum of the object
     pub checksum_crc32c: Option<String>,
 
+    /// Base64-encoded, 64-bit CRC64NVME checksum of the object
+    pub checksum_crc64nvme: Option<String>,
+
     /// Base64-encoded, 160-bit SHA-1 digest of the object
     pub checksum_sha1: Option<String>,
 
@@ -826,6 +1706,7 @@ impl Checksum {
         Self {
             checksum_crc32: None,
             checksum_crc32c: None,
+            checksum_crc64nvme: None,
             checksum_sha1: None,
             checksum_sha256: None,
         }
@@ -840,6 +1721,7 @@ impl Checksum {
         let Self {
             checksum_crc32,
             checksum_crc32c,
+            checksum_crc64nvme,
             checksum_sha1,
             checksum_sha256,
         } = &self;
@@ -850,6 +1732,9 @@ impl Checksum {
         if checksum_crc32c.is_some() {
             algorithms.push(ChecksumAlgorithm::Crc32c);
         }
+        if checksum_crc64nvme.is_some() {
+            algorithms.push(ChecksumAlgorithm::Crc64nvme);
+        }
         if checksum_sha1.is_some() {
             algorithms.push(ChecksumAlgorithm::Sha1);
         }
@@ -867,6 +1752,7 @@ impl From<Option<UploadChecksum>> for Checksum {
         match value.as_ref() {
             Some(UploadChecksum::Crc32c(crc32c)) => checksum.checksum_crc32c = Some(crc32c_to_base64(crc32c)),
             Some(UploadChecksum::Crc32(crc32)) => checksum.checksum_crc32 = Some(crc32_to_base64(crc32)),
+            Some(UploadChecksum::Crc64nvme(crc64)) => checksum.checksum_crc64nvme = Some(crc64nvme_to_base64(crc64)),
             Some(UploadChecksum::Sha1(sha1)) => checksum.checksum_sha1 = Some(sha1_to_base64(sha1)),
             Some(UploadChecksum::Sha256(sha256)) => checksum.checksum_sha256 = Some(sha256_to_base64(sha256)),
             None => {}
@@ -925,6 +1811,7 @@ mod tests {
         let checksum = Checksum {
             checksum_crc32: None,
             checksum_crc32c: None,
+            checksum_crc64nvme: None,
             checksum_sha1: Some("checksum_sha1".to_string()),
             checksum_sha256: None,
         };
@@ -936,6 +1823,7 @@ mod tests {
         let checksum = Checksum {
             checksum_crc32: None,
             checksum_crc32c: None,
+            checksum_crc64nvme: None,
             checksum_sha1: None,
             checksum_sha256: None,
         };
@@ -948,6 +1836,7 @@ mod tests {
         let checksum = Checksum {
             checksum_crc32: None,
             checksum_crc32c: Some("checksum_crc32c".to_string()),
+            checksum_crc64nvme: None,
             checksum_sha1: Some("checksum_sha1".to_string()),
             checksum_sha256: None,
         };
@@ -0,0 +1,90 @@
+use std::fmt::{self, Debug};
+
+use base64ct::{Base64, Encoding};
+use md5::Digest as _;
+use thiserror::Error;
+
+/// A customer-provided key for server-side encryption (SSE-C).
+///
+/// When set on a request, the key is sent in the `x-amz-server-side-encryption-customer-*` headers
+/// so that S3 can encrypt or decrypt the object with it. S3 does not store the key, so the same key
+/// must be provided on every request that reads or writes the object's data.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SseCustomerKey {
+    key: Box<[u8]>,
+}
+
+/// Error returned when a [SseCustomerKey] cannot be constructed.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("SSE-C keys must be {expected} bytes long, but got {actual} bytes")]
+pub struct InvalidSseCustomerKeyError {
+    expected: usize,
+    actual: usize,
+}
+
+impl SseCustomerKey {
+    /// Length in bytes of an SSE-C key. S3 only supports 256-bit AES keys.
+    pub const KEY_LENGTH: usize = 32;
+
+    /// Create a new SSE-C key from its raw bytes.
+    pub fn new(key: &[u8]) -> Result<Self, InvalidSseCustomerKeyError> {
+        if key.len() != Self::KEY_LENGTH {
+            return Err(InvalidSseCustomerKeyError {
+                expected: Self::KEY_LENGTH,
+                actual: key.len(),
+            });
+        }
+        Ok(Self { key: key.into() })
+    }
+
+    /// The raw bytes of the key.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// The encryption algorithm to use with this key, as expected by S3.
+    pub fn algorithm(&self) -> &'static str {
+        "AES256"
+    }
+
+    /// The key, encoded in base64.
+    pub fn key_base64(&self) -> String {
+        Base64::encode_string(&self.key)
+    }
+
+    /// The MD5 digest of the key, encoded in base64. S3 uses it to check the key was not corrupted in transit.
+    pub fn key_md5_base64(&self) -> String {
+        let digest = md5::Md5::digest(&self.key);
+        Base64::encode_string(&digest)
+    }
+}
+
+impl Debug for SseCustomerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the key itself
+        f.debug_struct("SseCustomerKey")
+            .field("key_md5", &self.key_md5_base64())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_customer_key_encoding() {
+        let bytes: Vec<u8> = (0..32).collect();
+        let key = SseCustomerKey::new(&bytes).expect("32 byte keys are valid");
+        assert_eq!(key.algorithm(), "AES256");
+        assert_eq!(key.key_base64(), "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=");
+        assert_eq!(key.key_md5_base64(), "tP/LI3N87DFaSk0aoqYgzg==");
+        assert!(!format!("{key:?}").contains(&key.key_base64()));
+    }
+
+    #[test]
+    fn test_sse_customer_key_invalid_length() {
+        let err = SseCustomerKey::new(&[0u8; 16]).expect_err("16 byte keys are invalid");
+        assert_eq!(err.to_string(), "SSE-C keys must be 32 bytes long, but got 16 bytes");
+    }
+}
@@ -36,9 +36,10 @@ use futures::channel::oneshot;
 use percent_encoding::{percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use pin_project::{pin_project, pinned_drop};
 use thiserror::Error;
+use time::format_description::well_known::Rfc3339;
 use tracing::{debug, error, trace, Span};
 
-use crate::checksums::{crc32_to_base64, crc32c_to_base64, sha1_to_base64, sha256_to_base64};
+use crate::checksums::{crc32_to_base64, crc32c_to_base64, crc64nvme_to_base64, sha1_to_base64, sha256_to_base64};
 use crate::endpoint_config::EndpointError;
 use crate::endpoint_config::{self, EndpointConfig};
 use crate::error_metadata::{ClientErrorMetadata, ProvideErrorMetadata};
@@ -60,13 +61,18 @@ macro_rules! request_span {
 
 pub(crate) mod copy_object;
 pub(crate) mod delete_object;
+pub(crate) mod delete_objects;
 pub(crate) mod get_object;
 
 pub(crate) use get_object::S3GetObjectResponse;
 pub(crate) mod get_object_attributes;
 
 pub(crate) mod head_object;
+pub(crate) mod list_object_versions;
 pub(crate) mod list_objects;
+pub(crate) mod multipart_upload;
+pub(crate) mod object_tagging;
+pub(crate) mod restore_object;
 
 pub(crate) mod head_bucket;
 pub(crate) mod put_object;
@@ -798,14 +804,25 @@ impl S3CrtClientInner {
 #[derive(Debug, Clone, Copy)]
 enum S3Operation {
     DeleteObject,
+    DeleteObjects,
     GetObject,
     GetObjectAttributes,
     HeadBucket,
     HeadObject,
     ListObjects,
+    ListObjectVersions,
     PutObject,
     CopyObject,
     PutObjectSingle,
+    CreateMultipartUpload,
+    UploadPart,
+    CompleteMultipartUpload,
+    ListParts,
+    ListMultipartUploads,
+    AbortMultipartUpload,
+    GetObjectTagging,
+    PutObjectTagging,
+    RestoreObject,
 }
 
 impl S3Operation {
@@ -824,14 +841,25 @@ impl S3Operation {
     fn operation_name(&self) -> Option<&'static str> {
         match self {
             S3Operation::DeleteObject => Some("DeleteObject"),
+            S3Operation::DeleteObjects => Some("DeleteObjects"),
             S3Operation::GetObject => None,
             S3Operation::GetObjectAttributes => Some("GetObjectAttributes"),
             S3Operation::HeadBucket => Some("HeadBucket"),
             S3Operation::HeadObject => Some("HeadObject"),
             S3Operation::ListObjects => Some("ListObjectsV2"),
+            S3Operation::ListObjectVersions => Some("ListObjectVersions"),
             S3Operation::PutObject => None,
             S3Operation::CopyObject => None,
             S3Operation::PutObjectSingle => Some("PutObject"),
+            S3Operation::CreateMultipartUpload => Some("CreateMultipartUpload"),
+            S3Operation::UploadPart => Some("UploadPart"),
+            S3Operation::CompleteMultipartUpload => Some("CompleteMultipartUpload"),
+            S3Operation::ListParts => Some("ListParts"),
+            S3Operation::ListMultipartUploads => Some("ListMultipartUploads"),
+            S3Operation::AbortMultipartUpload => Some("AbortMultipartUpload"),
+            S3Operation::GetObjectTagging => Some("GetObjectTagging"),
+            S3Operation::PutObjectTagging => Some("PutObjectTagging"),
+            S3Operation::RestoreObject => Some("RestoreObject"),
         }
     }
 }
@@ -942,11 +970,46 @@ impl<'a> S3Message<'a> {
         let header = match checksum {
             UploadChecksum::Crc32c(crc32c) => Header::new("x-amz-checksum-crc32c", crc32c_to_base64(crc32c)),
             UploadChecksum::Crc32(crc32) => Header::new("x-amz-checksum-crc32", crc32_to_base64(crc32)),
+            UploadChecksum::Crc64nvme(crc64) => Header::new("x-amz-checksum-crc64nvme", crc64nvme_to_base64(crc64)),
             UploadChecksum::Sha1(sha1) => Header::new("x-amz-checksum-sha1", sha1_to_base64(sha1)),
             UploadChecksum::Sha256(sha256) => Header::new("x-amz-checksum-sha256", sha256_to_base64(sha256)),
         };
         self.inner.set_header(&header)
     }
+
+    /// Set the headers for server-side encryption with a customer-provided key (SSE-C).
+    fn set_sse_customer_key_headers(
+        &mut self,
+        sse_customer_key: &SseCustomerKey,
+    ) -> Result<(), mountpoint_s3_crt::common::error::Error> {
+        self.inner.set_header(&Header::new(
+            "x-amz-server-side-encryption-customer-algorithm",
+            sse_customer_key.algorithm(),
+        ))?;
+        self.inner.set_header(&Header::new(
+            "x-amz-server-side-encryption-customer-key",
+            sse_customer_key.key_base64(),
+        ))?;
+        self.inner.set_header(&Header::new(
+            "x-amz-server-side-encryption-customer-key-MD5",
+            sse_customer_key.key_md5_base64(),
+        ))
+    }
+
+    /// Set the Object Lock retention and legal hold headers, if any.
+    fn set_object_lock_headers(&mut self, object_lock: &ObjectLock) -> Result<(), ConstructionError> {
+        if let (Some(mode), Some(retain_until_date)) = (&object_lock.mode, object_lock.retain_until_date) {
+            let retain_until_date = retain_until_date.format(&Rfc3339)?;
+            self.inner.set_header(&Header::new("x-amz-object-lock-mode", mode))?;
+            self.inner
+                .set_header(&Header::new("x-amz-object-lock-retain-until-date", retain_until_date))?;
+        }
+        if object_lock.legal_hold {
+            self.inner
+                .set_header(&Header::new("x-amz-object-lock-legal-hold", "ON"))?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -1093,6 +1156,14 @@ pub enum ConstructionError {
     /// The S3 endpoint was invalid
     #[error("Invalid S3 endpoint")]
     InvalidEndpoint(#[from] EndpointError),
+
+    /// The Object Lock retain-until date could not be formatted
+    #[error("Invalid Object Lock retain-until date")]
+    InvalidRetainUntilDate(#[from] time::error::Format),
+
+    /// The checksum algorithm is not supported for uploads
+    #[error("Unsupported checksum algorithm: {0}")]
+    UnsupportedChecksumAlgorithm(ChecksumAlgorithm),
 }
 
 /// Return a string version of a [RequestType] for use in metrics
@@ -1139,12 +1210,14 @@ fn extract_range_header(headers: &Headers) -> Option<Range<u64>> {
 fn parse_checksum(headers: &Headers) -> Result<Checksum, HeadersError> {
     let checksum_crc32 = headers.get_as_optional_string("x-amz-checksum-crc32")?;
     let checksum_crc32c = headers.get_as_optional_string("x-amz-checksum-crc32c")?;
+    let checksum_crc64nvme = headers.get_as_optional_string("x-amz-checksum-crc64nvme")?;
     let checksum_sha1 = headers.get_as_optional_string("x-amz-checksum-sha1")?;
     let checksum_sha256 = headers.get_as_optional_string("x-amz-checksum-sha256")?;
 
     Ok(Checksum {
         checksum_crc32,
         checksum_crc32c,
+        checksum_crc64nvme,
         checksum_sha1,
         checksum_sha256,
     })
@@ -1296,6 +1369,14 @@ impl ObjectClient for S3CrtClient {
         self.delete_object(bucket, key).await
     }
 
+    async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+    ) -> ObjectClientResult<DeleteObjectsResult, DeleteObjectsError, Self::ClientError> {
+        self.delete_objects(bucket, keys).await
+    }
+
     async fn copy_object(
         &self,
         source_bucket: &str,
@@ -1329,6 +1410,19 @@ impl ObjectClient for S3CrtClient {
             .await
     }
 
+    async fn list_object_versions(
+        &self,
+        bucket: &str,
+        key_marker: Option<&str>,
+        version_id_marker: Option<&str>,
+        delimiter: &str,
+        max_keys: usize,
+        prefix: &str,
+    ) -> ObjectClientResult<ListObjectVersionsResult, ListObjectVersionsError, Self::ClientError> {
+        self.list_object_versions(bucket, key_marker, version_id_marker, delimiter, max_keys, prefix)
+            .await
+    }
+
     async fn head_object(
         &self,
         bucket: &str,
@@ -1368,6 +1462,97 @@ impl ObjectClient for S3CrtClient {
         self.get_object_attributes(bucket, key, max_parts, part_number_marker, object_attributes)
             .await
     }
+
+    async fn get_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> ObjectClientResult<GetObjectTaggingResult, GetObjectTaggingError, Self::ClientError> {
+        self.get_object_tagging(bucket, key).await
+    }
+
+    async fn put_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+        tags: &ObjectTags,
+    ) -> ObjectClientResult<PutObjectTaggingResult, PutObjectTaggingError, Self::ClientError> {
+        self.put_object_tagging(bucket, key, tags).await
+    }
+
+    async fn restore_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &RestoreObjectParams,
+    ) -> ObjectClientResult<RestoreObjectResult, RestoreObjectError, Self::ClientError> {
+        self.restore_object(bucket, key, params).await
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &CreateMultipartUploadParams,
+    ) -> ObjectClientResult<CreateMultipartUploadResult, CreateMultipartUploadError, Self::ClientError> {
+        self.create_multipart_upload(bucket, key, params).await
+    }
+
+    async fn upload_part<'a>(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: usize,
+        params: &UploadPartParams,
+        contents: impl AsRef<[u8]> + Send + 'a,
+    ) -> ObjectClientResult<UploadPartResult, UploadPartError, Self::ClientError> {
+        self.upload_part(bucket, key, upload_id, part_number, params, contents)
+            .await
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: &[CompletedPart],
+        params: &CompleteMultipartUploadParams,
+    ) -> ObjectClientResult<CompleteMultipartUploadResult, CompleteMultipartUploadError, Self::ClientError> {
+        self.complete_multipart_upload(bucket, key, upload_id, parts, params)
+            .await
+    }
+
+    async fn list_parts(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number_marker: Option<usize>,
+    ) -> ObjectClientResult<ListPartsResult, ListPartsError, Self::ClientError> {
+        self.list_parts(bucket, key, upload_id, part_number_marker).await
+    }
+
+    async fn list_multipart_uploads(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        key_marker: Option<&str>,
+        upload_id_marker: Option<&str>,
+        max_uploads: usize,
+    ) -> ObjectClientResult<ListMultipartUploadsResult, ListMultipartUploadsError, Self::ClientError> {
+        self.list_multipart_uploads(bucket, prefix, key_marker, upload_id_marker, max_uploads)
+            .await
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> ObjectClientResult<AbortMultipartUploadResult, AbortMultipartUploadError, Self::ClientError> {
+        self.abort_multipart_upload(bucket, key, upload_id).await
+    }
 }
 
 #[cfg(test)]
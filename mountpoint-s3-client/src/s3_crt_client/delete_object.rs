@@ -49,6 +49,19 @@ fn parse_delete_object_error(result: &MetaRequestResult) -> Option<DeleteObjectE
                 _ => None,
             }
         }
+        403 => {
+            let body = result.error_response_body.as_ref()?;
+            let root = xmltree::Element::parse(body.as_bytes()).ok()?;
+            let error_code = root.get_child("Code")?.get_text()?;
+            let message = root.get_child("Message")?.get_text()?;
+
+            // S3 reports locked object versions as a plain AccessDenied, only the message tells them apart.
+            if error_code == "AccessDenied" && message.contains("object lock") {
+                Some(DeleteObjectError::ObjectLocked)
+            } else {
+                None
+            }
+        }
         _ => None,
     }
 }
@@ -75,4 +88,20 @@ mod tests {
         let result = parse_delete_object_error(&result);
         assert_eq!(result, Some(DeleteObjectError::NoSuchBucket));
     }
+
+    #[test]
+    fn parse_403_object_locked() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>AccessDenied</Code><Message>Access Denied because object protected by object lock.</Message><RequestId>BHCQ0FTYY0HKMV43</RequestId><HostId>ntCK1jQfPxY7sSNL/GB13RttgJLjSETfIuOiuRnwImO0dQP2ttj2Qqpn5S/jSLt3Ql0TgHWuYF0=</HostId></Error>"#;
+        let result = make_result(403, OsStr::from_bytes(&body[..]));
+        let result = parse_delete_object_error(&result);
+        assert_eq!(result, Some(DeleteObjectError::ObjectLocked));
+    }
+
+    #[test]
+    fn parse_403_access_denied() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>AccessDenied</Code><Message>Access Denied</Message><RequestId>BHCQ0FTYY0HKMV43</RequestId><HostId>ntCK1jQfPxY7sSNL/GB13RttgJLjSETfIuOiuRnwImO0dQP2ttj2Qqpn5S/jSLt3Ql0TgHWuYF0=</HostId></Error>"#;
+        let result = make_result(403, OsStr::from_bytes(&body[..]));
+        let result = parse_delete_object_error(&result);
+        assert_eq!(result, None);
+    }
 }
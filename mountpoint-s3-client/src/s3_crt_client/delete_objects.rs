@@ -0,0 +1,202 @@
+use std::ops::Deref;
+use std::os::unix::prelude::OsStrExt;
+
+use mountpoint_s3_crt::io::stream::InputStream;
+use mountpoint_s3_crt::s3::client::MetaRequestResult;
+use thiserror::Error;
+
+use crate::checksums::crc32c;
+use crate::object_client::{
+    DeleteObjectsError, DeleteObjectsKeyError, DeleteObjectsResult, ObjectClientError, ObjectClientResult,
+    UploadChecksum, MAX_DELETE_OBJECTS_KEYS,
+};
+use crate::s3_crt_client::{S3CrtClient, S3Operation, S3RequestError};
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ParseError {
+    #[error("XML parsing error: {0:?}")]
+    Xml(#[from] xmltree::ParseError),
+
+    #[error("Missing field {1} from XML element {0:?}")]
+    MissingField(xmltree::Element, String),
+}
+
+impl S3CrtClient {
+    /// Create and begin a new DeleteObjects request.
+    pub(super) async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+    ) -> ObjectClientResult<DeleteObjectsResult, DeleteObjectsError, S3RequestError> {
+        if keys.len() > MAX_DELETE_OBJECTS_KEYS {
+            return Err(ObjectClientError::ServiceError(DeleteObjectsError::TooManyKeys));
+        }
+        if keys.is_empty() {
+            return Ok(DeleteObjectsResult { errors: Vec::new() });
+        }
+
+        let request_body = build_delete_request_body(keys);
+        // Scope the endpoint, message, etc. since otherwise rustc thinks we use Message across the await.
+        let request = {
+            let mut message = self
+                .inner
+                .new_request_template("POST", bucket)
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_request_path_and_query("/", &[("delete", "")])
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_content_length_header(request_body.len())
+                .map_err(S3RequestError::construction_failure)?;
+            // DeleteObjects requests must have an integrity check, which S3 accepts as an additional checksum.
+            let checksum = UploadChecksum::Crc32c(crc32c::checksum(request_body.as_bytes()));
+            message
+                .set_checksum_header(&checksum)
+                .map_err(S3RequestError::construction_failure)?;
+
+            let body_input_stream = InputStream::new_from_slice(&self.inner.allocator, request_body.as_bytes())
+                .map_err(S3RequestError::CrtError)?;
+            message.set_body_stream(Some(body_input_stream));
+
+            let span = request_span!(self.inner, "delete_objects", bucket, keys = keys.len());
+
+            self.inner.make_simple_http_request(
+                message,
+                S3Operation::DeleteObjects,
+                span,
+                parse_delete_objects_error,
+            )?
+        };
+
+        let body = request.await?;
+
+        parse_result_from_bytes(&body)
+            .map_err(|e| ObjectClientError::ClientError(S3RequestError::InternalError(e.into())))
+    }
+}
+
+/// Build the XML body of a DeleteObjects request. In quiet mode, S3 only reports the keys that
+/// failed to be deleted.
+fn build_delete_request_body(keys: &[String]) -> String {
+    let mut body = String::from("<Delete><Quiet>true</Quiet>");
+    for key in keys {
+        body.push_str("<Object><Key>");
+        push_escaped(&mut body, key);
+        body.push_str("</Key></Object>");
+    }
+    body.push_str("</Delete>");
+    body
+}
+
+/// Append text to an XML document, escaping the characters with a special meaning in XML.
+fn push_escaped(body: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => body.push_str("&amp;"),
+            '<' => body.push_str("&lt;"),
+            '>' => body.push_str("&gt;"),
+            '"' => body.push_str("&quot;"),
+            '\'' => body.push_str("&apos;"),
+            // Whitespace other than a space would otherwise be normalized by the XML parser
+            '\t' | '\n' | '\r' => body.push_str(&format!("&#x{:X};", c as u32)),
+            _ => body.push(c),
+        }
+    }
+}
+
+/// Get the text out of a child node, with the right error type.
+fn get_field(element: &xmltree::Element, name: &str) -> Result<String, ParseError> {
+    let child = element
+        .get_child(name)
+        .ok_or_else(|| ParseError::MissingField(element.clone(), name.to_string()))?;
+    Ok(child.get_text().unwrap_or_default().into_owned())
+}
+
+fn parse_result_from_bytes(bytes: &[u8]) -> Result<DeleteObjectsResult, ParseError> {
+    let mut element = xmltree::Element::parse(bytes)?;
+    let mut errors = Vec::new();
+    while let Some(error) = element.take_child("Error") {
+        errors.push(DeleteObjectsKeyError {
+            key: get_field(&error, "Key")?,
+            code: get_field(&error, "Code")?,
+            message: get_field(&error, "Message")?,
+        });
+    }
+    Ok(DeleteObjectsResult { errors })
+}
+
+fn parse_delete_objects_error(result: &MetaRequestResult) -> Option<DeleteObjectsError> {
+    match result.response_status {
+        404 => {
+            let body = result.error_response_body.as_ref()?;
+            let root = xmltree::Element::parse(body.as_bytes()).ok()?;
+            let error_code = root.get_child("Code")?;
+            let error_str = error_code.get_text()?;
+            match error_str.deref() {
+                "NoSuchBucket" => Some(DeleteObjectsError::NoSuchBucket),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{OsStr, OsString};
+
+    use super::*;
+
+    fn make_result(response_status: i32, body: impl Into<OsString>) -> MetaRequestResult {
+        MetaRequestResult {
+            response_status,
+            crt_error: 1i32.into(),
+            error_response_headers: None,
+            error_response_body: Some(body.into()),
+        }
+    }
+
+    #[test]
+    fn parse_404_no_such_bucket() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>NoSuchBucket</Code><Message>The specified bucket does not exist</Message><BucketName>amzn-s3-demo-bucket</BucketName><RequestId>4YAYHJ0E82DDDNF0</RequestId><HostId>Ajn9+i3d3VWQi339YrGqBbJqQlj5HaX2vplXp9IlDPAxsJ4vsIAsje0P2gJ0of/mTKKz/fv9pNy9RqhbLUBc/g==</HostId></Error>"#;
+        let result = make_result(404, OsStr::from_bytes(&body[..]));
+        let result = parse_delete_objects_error(&result);
+        assert_eq!(result, Some(DeleteObjectsError::NoSuchBucket));
+    }
+
+    #[test]
+    fn build_delete_objects_body() {
+        let keys = ["a/b.txt".to_string(), "<&'\">\n".to_string()];
+        assert_eq!(
+            build_delete_request_body(&keys),
+            "<Delete><Quiet>true</Quiet><Object><Key>a/b.txt</Key></Object>\
+             <Object><Key>&lt;&amp;&apos;&quot;&gt;&#xA;</Key></Object></Delete>"
+        );
+    }
+
+    #[test]
+    fn parse_delete_objects_result() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><DeleteResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Error><Key>locked.txt</Key><Code>AccessDenied</Code><Message>Access Denied because object protected by object lock.</Message></Error><Error><Key>a&amp;b</Key><Code>InternalError</Code><Message>We encountered an internal error. Please try again.</Message></Error></DeleteResult>"#;
+        let result = parse_result_from_bytes(&body[..]).expect("should parse");
+        assert_eq!(
+            result.errors,
+            vec![
+                DeleteObjectsKeyError {
+                    key: "locked.txt".to_string(),
+                    code: "AccessDenied".to_string(),
+                    message: "Access Denied because object protected by object lock.".to_string(),
+                },
+                DeleteObjectsKeyError {
+                    key: "a&b".to_string(),
+                    code: "InternalError".to_string(),
+                    message: "We encountered an internal error. Please try again.".to_string(),
+                },
+            ]
+        );
+
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><DeleteResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/"></DeleteResult>"#;
+        let result = parse_result_from_bytes(&body[..]).expect("should parse");
+        assert!(result.errors.is_empty());
+    }
+}
@@ -42,8 +42,7 @@ impl S3CrtClient {
         let (headers_sender, mut headers_receiver) = futures::channel::oneshot::channel();
 
         let mut request = {
-            let span =
-                request_span!(self.inner, "get_object", bucket, key, range=?params.range, if_match=?params.if_match);
+            let span = request_span!(self.inner, "get_object", bucket, key, range=?params.range, if_match=?params.if_match, version_id=?params.version_id);
 
             let mut message = self
                 .inner
@@ -69,6 +68,13 @@ impl S3CrtClient {
                     .map_err(S3RequestError::construction_failure)?;
             }
 
+            if let Some(sse_customer_key) = params.sse_customer_key.as_ref() {
+                // Objects encrypted with SSE-C can only be read with the same key
+                message
+                    .set_sse_customer_key_headers(sse_customer_key)
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+
             if let Some(range) = params.range.as_ref() {
                 // Range HTTP header is bounded below *inclusive*
                 let range_value = format!("bytes={}-{}", range.start, range.end.saturating_sub(1));
@@ -78,8 +84,13 @@ impl S3CrtClient {
             }
 
             let key = format!("/{key}");
+            let query: Vec<_> = params
+                .version_id
+                .iter()
+                .map(|version_id| ("versionId", version_id.as_str()))
+                .collect();
             message
-                .set_request_path(key)
+                .set_request_path_and_query(key, query)
                 .map_err(S3RequestError::construction_failure)?;
 
             let mut options = S3CrtClientInner::new_meta_request_options(message, S3Operation::GetObject);
@@ -84,12 +84,14 @@ impl GetObjectAttributesResult {
     fn parse_checksums(element: &xmltree::Element) -> Result<Checksum, ParseError> {
         let checksum_crc32 = get_field_or_none(element, "ChecksumCRC32")?;
         let checksum_crc32c = get_field_or_none(element, "ChecksumCRC32C")?;
+        let checksum_crc64nvme = get_field_or_none(element, "ChecksumCRC64NVME")?;
         let checksum_sha1 = get_field_or_none(element, "ChecksumSHA1")?;
         let checksum_sha256 = get_field_or_none(element, "ChecksumSHA256")?;
 
         Ok(Checksum {
             checksum_crc32,
             checksum_crc32c,
+            checksum_crc64nvme,
             checksum_sha1,
             checksum_sha256,
         })
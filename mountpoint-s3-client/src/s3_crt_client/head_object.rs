@@ -6,12 +6,13 @@ use mountpoint_s3_crt::http::request_response::{Header, Headers, HeadersError};
 use mountpoint_s3_crt::s3::client::MetaRequestResult;
 use regex::Regex;
 use thiserror::Error;
-use time::format_description::well_known::Rfc2822;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
 use time::OffsetDateTime;
 use tracing::error;
 
 use crate::object_client::{
-    HeadObjectError, HeadObjectParams, HeadObjectResult, ObjectClientError, ObjectClientResult, RestoreStatus,
+    HeadObjectError, HeadObjectParams, HeadObjectResult, ObjectClientError, ObjectClientResult, ObjectLock,
+    RestoreStatus,
 };
 use crate::s3_crt_client::{parse_checksum, S3CrtClient, S3Operation, S3RequestError};
 
@@ -65,6 +66,20 @@ impl HeadObjectResult {
         Ok(Some(RestoreStatus::Restored { expiry: expiry.into() }))
     }
 
+    fn parse_object_lock(headers: &Headers) -> Result<ObjectLock, ParseError> {
+        let mut object_lock = ObjectLock::new();
+        if let (Some(mode), Some(retain_until_date)) = (
+            headers.get_as_optional_string("x-amz-object-lock-mode")?,
+            headers.get_as_optional_string("x-amz-object-lock-retain-until-date")?,
+        ) {
+            let retain_until_date = OffsetDateTime::parse(&retain_until_date, &Rfc3339)
+                .map_err(|e| ParseError::OffsetDateTime(e, "ObjectLockRetainUntilDate".into()))?;
+            object_lock = object_lock.retention(mode, retain_until_date);
+        }
+        let legal_hold = headers.get_as_optional_string("x-amz-object-lock-legal-hold")?;
+        Ok(object_lock.legal_hold(legal_hold.as_deref() == Some("ON")))
+    }
+
     /// Parse from HeadObject headers
     fn parse_from_hdr(headers: &Headers) -> Result<Self, ParseError> {
         let last_modified = OffsetDateTime::parse(&headers.get_as_string("Last-Modified")?, &Rfc2822)
@@ -77,6 +92,7 @@ impl HeadObjectResult {
         let sse_type = headers.get_as_optional_string("x-amz-server-side-encryption")?;
         let sse_kms_key_id = headers.get_as_optional_string("x-amz-server-side-encryption-aws-kms-key-id")?;
         let checksum = parse_checksum(headers)?;
+        let object_lock = Self::parse_object_lock(headers)?;
         let result = HeadObjectResult {
             size,
             last_modified,
@@ -86,6 +102,7 @@ impl HeadObjectResult {
             checksum,
             sse_type,
             sse_kms_key_id,
+            object_lock,
         };
         Ok(result)
     }
@@ -110,8 +127,13 @@ impl S3CrtClient {
                 .map_err(S3RequestError::construction_failure)?;
 
             let key = key.to_string();
+            let query: Vec<_> = params
+                .version_id
+                .iter()
+                .map(|version_id| ("versionId", version_id.as_str()))
+                .collect();
             message
-                .set_request_path(format!("/{key}"))
+                .set_request_path_and_query(format!("/{key}"), query)
                 .map_err(S3RequestError::construction_failure)?;
 
             let bucket = bucket.to_owned();
@@ -127,7 +149,13 @@ impl S3CrtClient {
                 }
             }
 
-            let span = request_span!(self.inner, "head_object", bucket, key);
+            if let Some(sse_customer_key) = params.sse_customer_key.as_ref() {
+                message
+                    .set_sse_customer_key_headers(sse_customer_key)
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+
+            let span = request_span!(self.inner, "head_object", bucket, key, version_id=?params.version_id);
 
             self.inner.make_meta_request(
                 message,
@@ -0,0 +1,297 @@
+use std::ops::Deref;
+use std::os::unix::prelude::OsStrExt;
+use std::str::FromStr;
+
+use mountpoint_s3_crt::s3::client::MetaRequestResult;
+use thiserror::Error;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::object_client::{
+    ListObjectVersionsError, ListObjectVersionsResult, ObjectClientError, ObjectClientResult, ObjectVersionInfo,
+};
+use crate::s3_crt_client::{S3CrtClient, S3Operation, S3RequestError};
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ParseError {
+    #[error("XML response was not valid: problem = {1}, xml node = {0:?}")]
+    InvalidResponse(xmltree::Element, String),
+
+    #[error("XML parsing error: {0:?}")]
+    Xml(#[from] xmltree::ParseError),
+
+    #[error("Missing field {1} from XML element {0:?}")]
+    MissingField(xmltree::Element, String),
+
+    #[error("Failed to parse field {1} as bool: {0:?}")]
+    Bool(#[source] std::str::ParseBoolError, String),
+
+    #[error("Failed to parse field {1} as int: {0:?}")]
+    Int(#[source] std::num::ParseIntError, String),
+
+    #[error("Failed to parse field {1} as OffsetDateTime: {0:?}")]
+    OffsetDateTime(#[source] time::error::Parse, String),
+}
+
+/// Copy text out of an XML element, with the right error type.
+fn get_text(element: &xmltree::Element) -> Result<String, ParseError> {
+    Ok(element
+        .get_text()
+        .ok_or_else(|| ParseError::InvalidResponse(element.clone(), "field has no text".to_string()))?
+        .to_string())
+}
+
+/// Wrapper to get child with some name out of an XML element, with the right error type.
+fn get_child<'a>(element: &'a xmltree::Element, name: &str) -> Result<&'a xmltree::Element, ParseError> {
+    element
+        .get_child(name)
+        .ok_or_else(|| ParseError::MissingField(element.clone(), name.to_string()))
+}
+
+/// Get the text out of a child node, with the right error type.
+fn get_field(element: &xmltree::Element, name: &str) -> Result<String, ParseError> {
+    get_text(get_child(element, name)?)
+}
+
+fn parse_bool(element: &xmltree::Element, name: &str) -> Result<bool, ParseError> {
+    bool::from_str(&get_field(element, name)?).map_err(|e| ParseError::Bool(e, name.to_string()))
+}
+
+fn parse_result_from_bytes(bytes: &[u8]) -> Result<ListObjectVersionsResult, ParseError> {
+    parse_result_from_xml(&xmltree::Element::parse(bytes)?)
+}
+
+fn parse_result_from_xml(element: &xmltree::Element) -> Result<ListObjectVersionsResult, ParseError> {
+    let mut versions = Vec::new();
+    let mut common_prefixes = Vec::new();
+
+    // Versions and delete markers of the same key are interleaved, from the most recent, so we
+    // need to walk the children in document order rather than collecting each kind separately.
+    for child in element.children.iter().filter_map(|node| node.as_element()) {
+        match child.name.as_str() {
+            "Version" => versions.push(parse_version_info_from_xml(child, false)?),
+            "DeleteMarker" => versions.push(parse_version_info_from_xml(child, true)?),
+            "CommonPrefixes" => common_prefixes.push(get_field(child, "Prefix")?),
+            _ => {}
+        }
+    }
+
+    let (next_key_marker, next_version_id_marker) = if parse_bool(element, "IsTruncated")? {
+        let next_version_id_marker = match element.get_child("NextVersionIdMarker") {
+            Some(elem) => Some(get_text(elem)?),
+            None => None,
+        };
+        (Some(get_field(element, "NextKeyMarker")?), next_version_id_marker)
+    } else {
+        (None, None)
+    };
+
+    Ok(ListObjectVersionsResult {
+        versions,
+        common_prefixes,
+        next_key_marker,
+        next_version_id_marker,
+    })
+}
+
+fn parse_version_info_from_xml(
+    element: &xmltree::Element,
+    is_delete_marker: bool,
+) -> Result<ObjectVersionInfo, ParseError> {
+    let last_modified = OffsetDateTime::parse(&get_field(element, "LastModified")?, &Rfc3339)
+        .map_err(|e| ParseError::OffsetDateTime(e, "LastModified".to_string()))?;
+
+    let size = if is_delete_marker {
+        0
+    } else {
+        u64::from_str(&get_field(element, "Size")?).map_err(|e| ParseError::Int(e, "Size".to_string()))?
+    };
+
+    Ok(ObjectVersionInfo {
+        key: get_field(element, "Key")?,
+        version_id: get_field(element, "VersionId")?,
+        is_latest: parse_bool(element, "IsLatest")?,
+        is_delete_marker,
+        last_modified,
+        size,
+        etag: get_field(element, "ETag").ok(),
+        storage_class: get_field(element, "StorageClass").ok(),
+    })
+}
+
+impl S3CrtClient {
+    pub(super) async fn list_object_versions(
+        &self,
+        bucket: &str,
+        key_marker: Option<&str>,
+        version_id_marker: Option<&str>,
+        delimiter: &str,
+        max_keys: usize,
+        prefix: &str,
+    ) -> ObjectClientResult<ListObjectVersionsResult, ListObjectVersionsError, S3RequestError> {
+        // Scope the endpoint, message, etc. since otherwise rustc thinks we use Message across the await.
+        let body = {
+            let mut message = self
+                .inner
+                .new_request_template("GET", bucket)
+                .map_err(S3RequestError::construction_failure)?;
+            let max_keys = format!("{max_keys}");
+            let mut query = vec![
+                ("versions", ""),
+                ("delimiter", delimiter),
+                ("max-keys", &max_keys),
+                ("prefix", prefix),
+            ];
+            if let Some(key_marker) = key_marker {
+                query.push(("key-marker", key_marker));
+            }
+            if let Some(version_id_marker) = version_id_marker {
+                query.push(("version-id-marker", version_id_marker));
+            }
+
+            message
+                .set_request_path_and_query("/", query)
+                .map_err(S3RequestError::construction_failure)?;
+
+            let span = request_span!(
+                self.inner,
+                "list_object_versions",
+                bucket,
+                continued = key_marker.is_some(),
+                delimiter,
+                max_keys,
+                prefix
+            );
+
+            self.inner.make_simple_http_request(
+                message,
+                S3Operation::ListObjectVersions,
+                span,
+                parse_list_object_versions_error,
+            )?
+        };
+
+        let body = body.await?;
+
+        parse_result_from_bytes(&body)
+            .map_err(|e| ObjectClientError::ClientError(S3RequestError::InternalError(e.into())))
+    }
+}
+
+fn parse_list_object_versions_error(result: &MetaRequestResult) -> Option<ListObjectVersionsError> {
+    match result.response_status {
+        404 => {
+            let body = result.error_response_body.as_ref()?;
+            let root = xmltree::Element::parse(body.as_bytes()).ok()?;
+            let error_code = root.get_child("Code")?;
+            let error_str = error_code.get_text()?;
+            match error_str.deref() {
+                "NoSuchBucket" => Some(ListObjectVersionsError::NoSuchBucket),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{OsStr, OsString};
+
+    use super::*;
+
+    fn make_result(response_status: i32, body: impl Into<OsString>) -> MetaRequestResult {
+        MetaRequestResult {
+            response_status,
+            crt_error: 1i32.into(),
+            error_response_headers: None,
+            error_response_body: Some(body.into()),
+        }
+    }
+
+    #[test]
+    fn parse_404_no_such_bucket() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>NoSuchBucket</Code><Message>The specified bucket does not exist</Message><BucketName>amzn-s3-demo-bucket</BucketName><RequestId>4YAYHJ0E82DDDNF0</RequestId><HostId>Ajn9+i3d3VWQi339YrGqBbJqQlj5HaX2vplXp9IlDPAxsJ4vsIAsje0P2gJ0of/mTKKz/fv9pNy9RqhbLUBc/g==</HostId></Error>"#;
+        let result = make_result(404, OsStr::from_bytes(&body[..]));
+        let result = parse_list_object_versions_error(&result);
+        assert_eq!(result, Some(ListObjectVersionsError::NoSuchBucket));
+    }
+
+    #[test]
+    fn parse_list_object_versions_result() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?>
+<ListVersionsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Name>amzn-s3-demo-bucket</Name>
+  <Prefix>data/</Prefix>
+  <KeyMarker></KeyMarker>
+  <VersionIdMarker></VersionIdMarker>
+  <NextKeyMarker>data/b.txt</NextKeyMarker>
+  <NextVersionIdMarker>3/L4kqtJl40Nr8X8gdRQBpUMLUo</NextVersionIdMarker>
+  <MaxKeys>3</MaxKeys>
+  <Delimiter>/</Delimiter>
+  <IsTruncated>true</IsTruncated>
+  <DeleteMarker>
+    <Key>data/a.txt</Key>
+    <VersionId>QUpfdndhfd8438MNFDN93jdnJFkdmqnh893</VersionId>
+    <IsLatest>true</IsLatest>
+    <LastModified>2024-11-12T17:50:30.000Z</LastModified>
+  </DeleteMarker>
+  <Version>
+    <Key>data/a.txt</Key>
+    <VersionId>null</VersionId>
+    <IsLatest>false</IsLatest>
+    <LastModified>2024-10-10T17:50:30.000Z</LastModified>
+    <ETag>"fba9dede5f27731c9771645a39863328"</ETag>
+    <Size>434234</Size>
+    <StorageClass>STANDARD</StorageClass>
+  </Version>
+  <Version>
+    <Key>data/b.txt</Key>
+    <VersionId>3/L4kqtJl40Nr8X8gdRQBpUMLUo</VersionId>
+    <IsLatest>true</IsLatest>
+    <LastModified>2024-10-11T12:00:00.000Z</LastModified>
+    <ETag>"9b2cf535f27731c974343645a3985328"</ETag>
+    <Size>166</Size>
+    <StorageClass>STANDARD</StorageClass>
+  </Version>
+  <CommonPrefixes>
+    <Prefix>data/dir/</Prefix>
+  </CommonPrefixes>
+</ListVersionsResult>"#;
+        let result = parse_result_from_bytes(body).expect("result should parse");
+
+        assert_eq!(result.next_key_marker.as_deref(), Some("data/b.txt"));
+        assert_eq!(
+            result.next_version_id_marker.as_deref(),
+            Some("3/L4kqtJl40Nr8X8gdRQBpUMLUo")
+        );
+        assert_eq!(result.common_prefixes, vec!["data/dir/".to_owned()]);
+
+        let keys: Vec<_> = result
+            .versions
+            .iter()
+            .map(|v| (v.key.as_str(), v.version_id.as_str(), v.is_delete_marker))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("data/a.txt", "QUpfdndhfd8438MNFDN93jdnJFkdmqnh893", true),
+                ("data/a.txt", "null", false),
+                ("data/b.txt", "3/L4kqtJl40Nr8X8gdRQBpUMLUo", false),
+            ]
+        );
+        assert!(result.versions[0].is_latest);
+        assert_eq!(result.versions[0].size, 0);
+        assert_eq!(result.versions[0].etag, None);
+        assert_eq!(result.versions[1].size, 434234);
+        assert_eq!(
+            result.versions[1].etag.as_deref(),
+            Some("\"fba9dede5f27731c9771645a39863328\"")
+        );
+        assert_eq!(
+            result.versions[1].last_modified,
+            OffsetDateTime::parse("2024-10-10T17:50:30Z", &Rfc3339).unwrap()
+        );
+    }
+}
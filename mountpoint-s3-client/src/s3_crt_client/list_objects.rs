@@ -124,6 +124,7 @@ fn parse_checksum_algorithm(element: &mut xmltree::Element) -> Result<Vec<Checks
         let checksum_algorithm = match algo_string.as_str() {
             "CRC32" => ChecksumAlgorithm::Crc32,
             "CRC32C" => ChecksumAlgorithm::Crc32c,
+            "CRC64NVME" => ChecksumAlgorithm::Crc64nvme,
             "SHA1" => ChecksumAlgorithm::Sha1,
             "SHA256" => ChecksumAlgorithm::Sha256,
             _ => ChecksumAlgorithm::Unknown(algo_string),
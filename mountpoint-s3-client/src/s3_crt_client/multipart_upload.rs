@@ -0,0 +1,746 @@
+//! Low-level multipart upload operations, giving the caller control over individual parts.
+//!
+//! Most uploads should use [`put_object`](crate::ObjectClient::put_object) instead, which lets the
+//! CRT drive the multipart upload.
+
+use std::fmt::Write as _;
+use std::ops::Deref;
+use std::os::unix::prelude::OsStrExt;
+
+use mountpoint_s3_crt::http::request_response::Header;
+use mountpoint_s3_crt::io::stream::InputStream;
+use mountpoint_s3_crt::s3::client::MetaRequestResult;
+use thiserror::Error;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::object_client::{
+    AbortMultipartUploadError, AbortMultipartUploadResult, Checksum, CompleteMultipartUploadError,
+    CompleteMultipartUploadParams, CompleteMultipartUploadResult, CompletedPart, CreateMultipartUploadError,
+    CreateMultipartUploadParams, CreateMultipartUploadResult, ETag, ListMultipartUploadsError,
+    ListMultipartUploadsResult, ListPartsError, ListPartsResult, MultipartUploadInfo, ObjectClientError,
+    ObjectClientResult, UploadPartError, UploadPartParams, UploadPartResult, UploadedPart,
+};
+use crate::s3_crt_client::object_tagging::tagging_header_value;
+use crate::s3_crt_client::put_object::{get_etag, response_headers_handler};
+use crate::s3_crt_client::{S3CrtClient, S3CrtClientInner, S3Operation, S3RequestError};
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ParseError {
+    #[error("XML response was not valid: problem = {1}, xml node = {0:?}")]
+    InvalidResponse(xmltree::Element, String),
+
+    #[error("XML parsing error: {0:?}")]
+    Xml(#[from] xmltree::ParseError),
+
+    #[error("Missing field {1} from XML element {0:?}")]
+    MissingField(xmltree::Element, String),
+
+    #[error("Failed to parse field {1} as bool: {0:?}")]
+    Bool(#[source] std::str::ParseBoolError, String),
+
+    #[error("Failed to parse field {1} as int: {0:?}")]
+    Int(#[source] std::num::ParseIntError, String),
+
+    #[error("Failed to parse field {1} as OffsetDateTime: {0:?}")]
+    OffsetDateTime(#[source] time::error::Parse, String),
+}
+
+impl S3CrtClient {
+    /// Create and begin a new CreateMultipartUpload request.
+    pub(super) async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &CreateMultipartUploadParams,
+    ) -> ObjectClientResult<CreateMultipartUploadResult, CreateMultipartUploadError, S3RequestError> {
+        let request = {
+            let mut message = self
+                .inner
+                .new_request_template("POST", bucket)
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_request_path_and_query(format!("/{key}"), &[("uploads", "")])
+                .map_err(S3RequestError::construction_failure)?;
+
+            if let Some(checksum_algorithm) = &params.checksum_algorithm {
+                message
+                    .set_header(&Header::new("x-amz-checksum-algorithm", checksum_algorithm.to_string()))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if let Some(checksum_type) = &params.checksum_type {
+                message
+                    .set_header(&Header::new("x-amz-checksum-type", checksum_type.to_string()))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if let Some(storage_class) = &params.storage_class {
+                message
+                    .set_header(&Header::new("x-amz-storage-class", storage_class))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if let Some(sse) = &params.server_side_encryption {
+                message
+                    .set_header(&Header::new("x-amz-server-side-encryption", sse))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if let Some(key_id) = &params.ssekms_key_id {
+                message
+                    .set_header(&Header::new("x-amz-server-side-encryption-aws-kms-key-id", key_id))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if let Some(sse_customer_key) = &params.sse_customer_key {
+                message
+                    .set_sse_customer_key_headers(sse_customer_key)
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            for (name, value) in &params.object_metadata {
+                message
+                    .set_header(&Header::new(format!("x-amz-meta-{}", name), value))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if !params.object_tags.is_empty() {
+                message
+                    .set_header(&Header::new("x-amz-tagging", tagging_header_value(&params.object_tags)))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if let Some(acl) = &params.acl {
+                message
+                    .set_header(&Header::new("x-amz-acl", acl))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if let Some(content_type) = &params.content_type {
+                message
+                    .set_header(&Header::new("Content-Type", content_type))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            message
+                .set_object_lock_headers(&params.object_lock)
+                .map_err(S3RequestError::construction_failure)?;
+
+            let span = request_span!(self.inner, "create_multipart_upload", bucket, key);
+
+            self.inner.make_simple_http_request(
+                message,
+                S3Operation::CreateMultipartUpload,
+                span,
+                parse_create_multipart_upload_error,
+            )?
+        };
+
+        let body = request.await?;
+
+        parse_create_multipart_upload_result(&body)
+            .map_err(|e| ObjectClientError::ClientError(S3RequestError::InternalError(e.into())))
+    }
+
+    /// Create and begin a new UploadPart request.
+    pub(super) async fn upload_part<'a>(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: usize,
+        params: &UploadPartParams,
+        contents: impl AsRef<[u8]> + Send + 'a,
+    ) -> ObjectClientResult<UploadPartResult, UploadPartError, S3RequestError> {
+        let span = request_span!(self.inner, "upload_part", bucket, key, upload_id, part_number);
+
+        let (on_headers, response_headers) = response_headers_handler();
+        let slice = contents.as_ref();
+        let request = {
+            let mut message = self
+                .inner
+                .new_request_template("PUT", bucket)
+                .map_err(S3RequestError::construction_failure)?;
+            let part_number = part_number.to_string();
+            message
+                .set_request_path_and_query(
+                    format!("/{key}"),
+                    &[("partNumber", part_number.as_str()), ("uploadId", upload_id)],
+                )
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_content_length_header(slice.len())
+                .map_err(S3RequestError::construction_failure)?;
+            if let Some(checksum) = &params.checksum {
+                message
+                    .set_checksum_header(checksum)
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if let Some(sse_customer_key) = &params.sse_customer_key {
+                message
+                    .set_sse_customer_key_headers(sse_customer_key)
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+
+            let body_input_stream =
+                InputStream::new_from_slice(&self.inner.allocator, slice).map_err(S3RequestError::CrtError)?;
+            message.set_body_stream(Some(body_input_stream));
+
+            let options = S3CrtClientInner::new_meta_request_options(message, S3Operation::UploadPart);
+            self.inner.make_simple_http_request_from_options(
+                options,
+                span,
+                |_| {},
+                parse_upload_part_error,
+                on_headers,
+            )?
+        };
+
+        request.await?;
+
+        let headers = response_headers
+            .await
+            .expect("headers should be available since the request completed successfully");
+        let etag = get_etag(&headers).map_err(|e| S3RequestError::InternalError(Box::new(e)))?;
+        Ok(UploadPartResult { etag })
+    }
+
+    /// Create and begin a new CompleteMultipartUpload request.
+    pub(super) async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: &[CompletedPart],
+        params: &CompleteMultipartUploadParams,
+    ) -> ObjectClientResult<CompleteMultipartUploadResult, CompleteMultipartUploadError, S3RequestError> {
+        let span = request_span!(
+            self.inner,
+            "complete_multipart_upload",
+            bucket,
+            key,
+            upload_id,
+            num_parts = parts.len()
+        );
+
+        let request_body = build_complete_multipart_upload_body(parts);
+        let request = {
+            let mut message = self
+                .inner
+                .new_request_template("POST", bucket)
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_request_path_and_query(format!("/{key}"), &[("uploadId", upload_id)])
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_content_length_header(request_body.len())
+                .map_err(S3RequestError::construction_failure)?;
+            if let Some(etag) = &params.if_match {
+                message
+                    .set_header(&Header::new("If-Match", etag.as_str()))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+
+            let body_input_stream = InputStream::new_from_slice(&self.inner.allocator, request_body.as_bytes())
+                .map_err(S3RequestError::CrtError)?;
+            message.set_body_stream(Some(body_input_stream));
+
+            self.inner.make_simple_http_request(
+                message,
+                S3Operation::CompleteMultipartUpload,
+                span,
+                parse_complete_multipart_upload_error,
+            )?
+        };
+
+        let body = request.await?;
+
+        parse_complete_multipart_upload_result(&body)
+            .map_err(|e| ObjectClientError::ClientError(S3RequestError::InternalError(e.into())))
+    }
+
+    /// Create and begin a new ListParts request.
+    pub(super) async fn list_parts(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number_marker: Option<usize>,
+    ) -> ObjectClientResult<ListPartsResult, ListPartsError, S3RequestError> {
+        let request = {
+            let mut message = self
+                .inner
+                .new_request_template("GET", bucket)
+                .map_err(S3RequestError::construction_failure)?;
+
+            let part_number_marker_str = part_number_marker.map(|marker| marker.to_string());
+            let mut query = vec![("uploadId", upload_id)];
+            if let Some(marker) = part_number_marker_str.as_deref() {
+                query.push(("part-number-marker", marker));
+            }
+            message
+                .set_request_path_and_query(format!("/{key}"), query)
+                .map_err(S3RequestError::construction_failure)?;
+
+            let span = request_span!(self.inner, "list_parts", bucket, key, upload_id, ?part_number_marker);
+
+            self.inner
+                .make_simple_http_request(message, S3Operation::ListParts, span, parse_list_parts_error)?
+        };
+
+        let body = request.await?;
+
+        parse_list_parts_result(&body)
+            .map_err(|e| ObjectClientError::ClientError(S3RequestError::InternalError(e.into())))
+    }
+
+    /// Create and begin a new ListMultipartUploads request.
+    pub(super) async fn list_multipart_uploads(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        key_marker: Option<&str>,
+        upload_id_marker: Option<&str>,
+        max_uploads: usize,
+    ) -> ObjectClientResult<ListMultipartUploadsResult, ListMultipartUploadsError, S3RequestError> {
+        let request = {
+            let mut message = self
+                .inner
+                .new_request_template("GET", bucket)
+                .map_err(S3RequestError::construction_failure)?;
+
+            let max_uploads = format!("{max_uploads}");
+            let mut query = vec![("uploads", ""), ("max-uploads", &max_uploads), ("prefix", prefix)];
+            if let Some(key_marker) = key_marker {
+                query.push(("key-marker", key_marker));
+            }
+            if let Some(upload_id_marker) = upload_id_marker {
+                query.push(("upload-id-marker", upload_id_marker));
+            }
+            message
+                .set_request_path_and_query("/", query)
+                .map_err(S3RequestError::construction_failure)?;
+
+            let span = request_span!(
+                self.inner,
+                "list_multipart_uploads",
+                bucket,
+                prefix,
+                continued = key_marker.is_some(),
+                max_uploads
+            );
+
+            self.inner.make_simple_http_request(
+                message,
+                S3Operation::ListMultipartUploads,
+                span,
+                parse_list_multipart_uploads_error,
+            )?
+        };
+
+        let body = request.await?;
+
+        parse_list_multipart_uploads_result(&body)
+            .map_err(|e| ObjectClientError::ClientError(S3RequestError::InternalError(e.into())))
+    }
+
+    /// Create and begin a new AbortMultipartUpload request.
+    pub(super) async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> ObjectClientResult<AbortMultipartUploadResult, AbortMultipartUploadError, S3RequestError> {
+        let request = {
+            let mut message = self
+                .inner
+                .new_request_template("DELETE", bucket)
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_request_path_and_query(format!("/{key}"), &[("uploadId", upload_id)])
+                .map_err(S3RequestError::construction_failure)?;
+
+            let span = request_span!(self.inner, "abort_multipart_upload", bucket, key, upload_id);
+
+            self.inner.make_simple_http_request(
+                message,
+                S3Operation::AbortMultipartUpload,
+                span,
+                parse_abort_multipart_upload_error,
+            )?
+        };
+
+        let _body = request.await?;
+
+        Ok(AbortMultipartUploadResult {})
+    }
+}
+
+/// Build the XML body of a CompleteMultipartUpload request.
+fn build_complete_multipart_upload_body(parts: &[CompletedPart]) -> String {
+    let mut body = String::from("<CompleteMultipartUpload>");
+    for part in parts {
+        body.push_str("<Part>");
+        write!(body, "<PartNumber>{}</PartNumber>", part.part_number).expect("should be able to append to String");
+        write!(body, "<ETag>{}</ETag>", escape_xml(part.etag.as_str())).expect("should be able to append to String");
+        let Checksum {
+            checksum_crc32,
+            checksum_crc32c,
+            checksum_crc64nvme,
+            checksum_sha1,
+            checksum_sha256,
+        } = &part.checksum;
+        for (name, value) in [
+            ("ChecksumCRC32", checksum_crc32),
+            ("ChecksumCRC32C", checksum_crc32c),
+            ("ChecksumCRC64NVME", checksum_crc64nvme),
+            ("ChecksumSHA1", checksum_sha1),
+            ("ChecksumSHA256", checksum_sha256),
+        ] {
+            if let Some(value) = value {
+                write!(body, "<{name}>{}</{name}>", escape_xml(value)).expect("should be able to append to String");
+            }
+        }
+        body.push_str("</Part>");
+    }
+    body.push_str("</CompleteMultipartUpload>");
+    body
+}
+
+/// Escape the characters that cannot appear verbatim in XML text.
+pub(super) fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn parse_create_multipart_upload_result(bytes: &[u8]) -> Result<CreateMultipartUploadResult, ParseError> {
+    let element = xmltree::Element::parse(bytes)?;
+    let upload_id = get_field(&element, "UploadId")?;
+    Ok(CreateMultipartUploadResult { upload_id })
+}
+
+fn parse_complete_multipart_upload_result(bytes: &[u8]) -> Result<CompleteMultipartUploadResult, ParseError> {
+    let element = xmltree::Element::parse(bytes)?;
+    // CompleteMultipartUpload can fail after S3 has already responded with a 200 status code. In
+    // that case, the error is reported in the body of the response.
+    if element.name == "Error" {
+        return Err(ParseError::InvalidResponse(
+            element,
+            "CompleteMultipartUpload returned an error".to_owned(),
+        ));
+    }
+    let etag = get_field(&element, "ETag")?;
+    Ok(CompleteMultipartUploadResult { etag: etag.into() })
+}
+
+fn parse_list_parts_result(bytes: &[u8]) -> Result<ListPartsResult, ParseError> {
+    let mut element = xmltree::Element::parse(bytes)?;
+
+    let mut parts = Vec::new();
+    while let Some(part) = element.take_child("Part") {
+        let part_number = get_field(&part, "PartNumber")?;
+        let part_number = part_number
+            .parse::<usize>()
+            .map_err(|e| ParseError::Int(e, "PartNumber".to_string()))?;
+        let size = get_field(&part, "Size")?;
+        let size = size
+            .parse::<u64>()
+            .map_err(|e| ParseError::Int(e, "Size".to_string()))?;
+        let etag: ETag = get_field(&part, "ETag")?.into();
+        let checksum = Checksum {
+            checksum_crc32: get_field_or_none(&part, "ChecksumCRC32")?,
+            checksum_crc32c: get_field_or_none(&part, "ChecksumCRC32C")?,
+            checksum_crc64nvme: get_field_or_none(&part, "ChecksumCRC64NVME")?,
+            checksum_sha1: get_field_or_none(&part, "ChecksumSHA1")?,
+            checksum_sha256: get_field_or_none(&part, "ChecksumSHA256")?,
+        };
+        parts.push(UploadedPart {
+            part_number,
+            etag,
+            size,
+            checksum,
+        });
+    }
+
+    let is_truncated = get_field(&element, "IsTruncated")?;
+    let is_truncated = is_truncated
+        .parse::<bool>()
+        .map_err(|e| ParseError::Bool(e, "IsTruncated".to_string()))?;
+    let next_part_number_marker = if is_truncated {
+        let marker = get_field(&element, "NextPartNumberMarker")?;
+        Some(
+            marker
+                .parse::<usize>()
+                .map_err(|e| ParseError::Int(e, "NextPartNumberMarker".to_string()))?,
+        )
+    } else {
+        None
+    };
+
+    Ok(ListPartsResult {
+        parts,
+        next_part_number_marker,
+    })
+}
+
+fn parse_list_multipart_uploads_result(bytes: &[u8]) -> Result<ListMultipartUploadsResult, ParseError> {
+    let mut element = xmltree::Element::parse(bytes)?;
+
+    let mut uploads = Vec::new();
+    while let Some(upload) = element.take_child("Upload") {
+        let initiated = get_field(&upload, "Initiated")?;
+        let initiated = OffsetDateTime::parse(&initiated, &Rfc3339)
+            .map_err(|e| ParseError::OffsetDateTime(e, "Initiated".to_string()))?;
+        uploads.push(MultipartUploadInfo {
+            key: get_field(&upload, "Key")?,
+            upload_id: get_field(&upload, "UploadId")?,
+            initiated,
+            storage_class: get_field_or_none(&upload, "StorageClass")?,
+        });
+    }
+
+    let is_truncated = get_field(&element, "IsTruncated")?;
+    let is_truncated = is_truncated
+        .parse::<bool>()
+        .map_err(|e| ParseError::Bool(e, "IsTruncated".to_string()))?;
+    let (next_key_marker, next_upload_id_marker) = if is_truncated {
+        (
+            Some(get_field(&element, "NextKeyMarker")?),
+            get_field_or_none(&element, "NextUploadIdMarker")?,
+        )
+    } else {
+        (None, None)
+    };
+
+    Ok(ListMultipartUploadsResult {
+        uploads,
+        next_key_marker,
+        next_upload_id_marker,
+    })
+}
+
+/// Extract the error code from an S3 error response.
+fn get_error_code(result: &MetaRequestResult) -> Option<String> {
+    let body = result.error_response_body.as_ref()?;
+    let root = xmltree::Element::parse(body.as_bytes()).ok()?;
+    let error_code = root.get_child("Code")?;
+    Some(error_code.get_text()?.into_owned())
+}
+
+fn parse_create_multipart_upload_error(result: &MetaRequestResult) -> Option<CreateMultipartUploadError> {
+    match result.response_status {
+        404 => match get_error_code(result)?.deref() {
+            "NoSuchBucket" => Some(CreateMultipartUploadError::NoSuchBucket),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn parse_upload_part_error(result: &MetaRequestResult) -> Option<UploadPartError> {
+    match result.response_status {
+        400 => match get_error_code(result)?.deref() {
+            "BadDigest" => Some(UploadPartError::BadChecksum),
+            _ => None,
+        },
+        404 => match get_error_code(result)?.deref() {
+            "NoSuchBucket" => Some(UploadPartError::NoSuchBucket),
+            "NoSuchUpload" => Some(UploadPartError::NoSuchUpload),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn parse_complete_multipart_upload_error(result: &MetaRequestResult) -> Option<CompleteMultipartUploadError> {
+    match result.response_status {
+        400 => match get_error_code(result)?.deref() {
+            "InvalidPart" => Some(CompleteMultipartUploadError::InvalidPart),
+            "InvalidPartOrder" => Some(CompleteMultipartUploadError::InvalidPartOrder),
+            "EntityTooSmall" => Some(CompleteMultipartUploadError::EntityTooSmall),
+            _ => None,
+        },
+        412 => Some(CompleteMultipartUploadError::PreconditionFailed),
+        404 => match get_error_code(result)?.deref() {
+            "NoSuchBucket" => Some(CompleteMultipartUploadError::NoSuchBucket),
+            "NoSuchUpload" => Some(CompleteMultipartUploadError::NoSuchUpload),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn parse_list_parts_error(result: &MetaRequestResult) -> Option<ListPartsError> {
+    match result.response_status {
+        404 => match get_error_code(result)?.deref() {
+            "NoSuchBucket" => Some(ListPartsError::NoSuchBucket),
+            "NoSuchUpload" => Some(ListPartsError::NoSuchUpload),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn parse_list_multipart_uploads_error(result: &MetaRequestResult) -> Option<ListMultipartUploadsError> {
+    match result.response_status {
+        404 => match get_error_code(result)?.deref() {
+            "NoSuchBucket" => Some(ListMultipartUploadsError::NoSuchBucket),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn parse_abort_multipart_upload_error(result: &MetaRequestResult) -> Option<AbortMultipartUploadError> {
+    match result.response_status {
+        404 => match get_error_code(result)?.deref() {
+            "NoSuchBucket" => Some(AbortMultipartUploadError::NoSuchBucket),
+            "NoSuchUpload" => Some(AbortMultipartUploadError::NoSuchUpload),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Copy text out of an XML element, with the right error type.
+fn get_text(element: &xmltree::Element) -> Result<String, ParseError> {
+    Ok(element
+        .get_text()
+        .ok_or_else(|| ParseError::InvalidResponse(element.clone(), "field has no text".to_owned()))?
+        .to_string())
+}
+
+/// Wrapper to get child with some name out of an XML element, with the right error type.
+fn get_child<'a>(element: &'a xmltree::Element, name: &str) -> Result<&'a xmltree::Element, ParseError> {
+    element
+        .get_child(name)
+        .ok_or_else(|| ParseError::MissingField(element.clone(), name.to_string()))
+}
+
+/// Get the text out of a child node, with the right error type.
+fn get_field(element: &xmltree::Element, name: &str) -> Result<String, ParseError> {
+    get_text(get_child(element, name)?)
+}
+
+/// Get the text out of a child node, return [None] if the child node is missing.
+fn get_field_or_none(element: &xmltree::Element, name: &str) -> Result<Option<String>, ParseError> {
+    match get_field(element, name) {
+        Ok(value) => Ok(Some(value)),
+        Err(ParseError::MissingField(_, _)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{OsStr, OsString};
+
+    use super::*;
+
+    fn make_result(response_status: i32, body: impl Into<OsString>) -> MetaRequestResult {
+        MetaRequestResult {
+            response_status,
+            crt_error: 1i32.into(),
+            error_response_headers: None,
+            error_response_body: Some(body.into()),
+        }
+    }
+
+    #[test]
+    fn parse_404_no_such_upload() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>NoSuchUpload</Code><Message>The specified upload does not exist. The upload ID may be invalid, or the upload may have been aborted or completed.</Message><UploadId>VXBsb2FkIElEIGZvciA2aWWpbmcncyBteS1tb3ZpZS5tMnRzIHVwbG9hZA</UploadId><RequestId>656c76696e6727732072657175657374</RequestId><HostId>Uuag1LuByRx9e6j5Onimru9pO4ZVKnJ2Qz7/C1NPcfTWAtRPfTaOFg==</HostId></Error>"#;
+        let result = make_result(404, OsStr::from_bytes(&body[..]));
+        assert_eq!(parse_upload_part_error(&result), Some(UploadPartError::NoSuchUpload));
+        assert_eq!(
+            parse_complete_multipart_upload_error(&result),
+            Some(CompleteMultipartUploadError::NoSuchUpload)
+        );
+        assert_eq!(parse_list_parts_error(&result), Some(ListPartsError::NoSuchUpload));
+        assert_eq!(
+            parse_abort_multipart_upload_error(&result),
+            Some(AbortMultipartUploadError::NoSuchUpload)
+        );
+    }
+
+    #[test]
+    fn parse_400_invalid_part() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>InvalidPart</Code><Message>One or more of the specified parts could not be found.  The part may not have been uploaded, or the specified entity tag may not match the part's entity tag.</Message><RequestId>656c76696e6727732072657175657374</RequestId><HostId>Uuag1LuByRx9e6j5Onimru9pO4ZVKnJ2Qz7/C1NPcfTWAtRPfTaOFg==</HostId></Error>"#;
+        let result = make_result(400, OsStr::from_bytes(&body[..]));
+        assert_eq!(
+            parse_complete_multipart_upload_error(&result),
+            Some(CompleteMultipartUploadError::InvalidPart)
+        );
+    }
+
+    #[test]
+    fn parse_create_multipart_upload() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><InitiateMultipartUploadResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Bucket>example-bucket</Bucket><Key>example-object</Key><UploadId>VXBsb2FkIElEIGZvciA2aWWpbmcncyBteS1tb3ZpZS5tMnRzIHVwbG9hZA</UploadId></InitiateMultipartUploadResult>"#;
+        let result = parse_create_multipart_upload_result(&body[..]).expect("parsing should succeed");
+        assert_eq!(
+            result.upload_id,
+            "VXBsb2FkIElEIGZvciA2aWWpbmcncyBteS1tb3ZpZS5tMnRzIHVwbG9hZA"
+        );
+    }
+
+    #[test]
+    fn parse_complete_multipart_upload_error_in_body() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>InternalError</Code><Message>We encountered an internal error. Please try again.</Message><RequestId>656c76696e6727732072657175657374</RequestId><HostId>Uuag1LuByRx9e6j5Onimru9pO4ZVKnJ2Qz7/C1NPcfTWAtRPfTaOFg==</HostId></Error>"#;
+        let result = parse_complete_multipart_upload_result(&body[..]);
+        assert!(matches!(result, Err(ParseError::InvalidResponse(_, _))));
+    }
+
+    #[test]
+    fn parse_list_parts() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><ListPartsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Bucket>example-bucket</Bucket><Key>example-object</Key><UploadId>XXBsb2FkIElEIGZvciBlbHZpbmcncyVcdS1tb3ZpZS5tMnRzEEEwbG9hZA</UploadId><PartNumberMarker>1</PartNumberMarker><NextPartNumberMarker>3</NextPartNumberMarker><MaxParts>2</MaxParts><IsTruncated>true</IsTruncated><Part><PartNumber>2</PartNumber><LastModified>2010-11-10T20:48:34.000Z</LastModified><ETag>"7778aef83f66abc1fa1e8477f296d394"</ETag><Size>10485760</Size><ChecksumCRC32C>AAAAAA==</ChecksumCRC32C></Part><Part><PartNumber>3</PartNumber><LastModified>2010-11-10T20:48:33.000Z</LastModified><ETag>"aaaa18db4cc2f85cedef654fccc4a4x8"</ETag><Size>10485760</Size><ChecksumCRC32C>AAAAAA==</ChecksumCRC32C></Part></ListPartsResult>"#;
+        let result = parse_list_parts_result(&body[..]).expect("parsing should succeed");
+        assert_eq!(result.next_part_number_marker, Some(3));
+        assert_eq!(result.parts.len(), 2);
+        assert_eq!(result.parts[0].part_number, 2);
+        assert_eq!(result.parts[0].etag.as_str(), "\"7778aef83f66abc1fa1e8477f296d394\"");
+        assert_eq!(result.parts[0].size, 10485760);
+        assert_eq!(result.parts[0].checksum.checksum_crc32c.as_deref(), Some("AAAAAA=="));
+        assert_eq!(result.parts[1].part_number, 3);
+    }
+
+    #[test]
+    fn parse_list_multipart_uploads() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><ListMultipartUploadsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Bucket>example-bucket</Bucket><KeyMarker></KeyMarker><UploadIdMarker></UploadIdMarker><NextKeyMarker>my-movie.m2ts</NextKeyMarker><NextUploadIdMarker>YW55IGlkZWEgd2h5IGVsdmluZydzIHVwbG9hZCBmYWlsZWQ</NextUploadIdMarker><Prefix>my-</Prefix><MaxUploads>2</MaxUploads><IsTruncated>true</IsTruncated><Upload><Key>my-divisor</Key><UploadId>XMgbGlrZSBlbHZpbmcncyBub3QgaGF2aW5nIG11Y2ggbHVjaw</UploadId><Initiator><ID>arn:aws:iam::111122223333:user/user1-11111a31-17b5-4fb7-9df5-b111111f13de</ID><DisplayName>user1-11111a31-17b5-4fb7-9df5-b111111f13de</DisplayName></Initiator><StorageClass>REDUCED_REDUNDANCY</StorageClass><Initiated>2010-11-10T20:48:33.000Z</Initiated></Upload><Upload><Key>my-movie.m2ts</Key><UploadId>YW55IGlkZWEgd2h5IGVsdmluZydzIHVwbG9hZCBmYWlsZWQ</UploadId><StorageClass>STANDARD</StorageClass><Initiated>2010-11-11T20:48:33.000Z</Initiated></Upload></ListMultipartUploadsResult>"#;
+        let result = parse_list_multipart_uploads_result(&body[..]).expect("parsing should succeed");
+        assert_eq!(result.next_key_marker.as_deref(), Some("my-movie.m2ts"));
+        assert_eq!(
+            result.next_upload_id_marker.as_deref(),
+            Some("YW55IGlkZWEgd2h5IGVsdmluZydzIHVwbG9hZCBmYWlsZWQ")
+        );
+        assert_eq!(result.uploads.len(), 2);
+        assert_eq!(result.uploads[0].key, "my-divisor");
+        assert_eq!(
+            result.uploads[0].upload_id,
+            "XMgbGlrZSBlbHZpbmcncyBub3QgaGF2aW5nIG11Y2ggbHVjaw"
+        );
+        assert_eq!(result.uploads[0].storage_class.as_deref(), Some("REDUCED_REDUNDANCY"));
+        assert_eq!(result.uploads[0].initiated.unix_timestamp(), 1289422113);
+        assert_eq!(result.uploads[1].key, "my-movie.m2ts");
+    }
+
+    #[test]
+    fn build_complete_body() {
+        let parts = [
+            CompletedPart {
+                part_number: 1,
+                etag: "\"etag1\"".into(),
+                checksum: Checksum::empty(),
+            },
+            CompletedPart {
+                part_number: 2,
+                etag: "\"etag2\"".into(),
+                checksum: Checksum {
+                    checksum_crc32c: Some("AAAAAA==".to_owned()),
+                    ..Checksum::empty()
+                },
+            },
+        ];
+        let body = build_complete_multipart_upload_body(&parts);
+        assert_eq!(
+            body,
+            "<CompleteMultipartUpload>\
+            <Part><PartNumber>1</PartNumber><ETag>&quot;etag1&quot;</ETag></Part>\
+            <Part><PartNumber>2</PartNumber><ETag>&quot;etag2&quot;</ETag><ChecksumCRC32C>AAAAAA==</ChecksumCRC32C></Part>\
+            </CompleteMultipartUpload>"
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use std::fmt::Write as _;
+use std::ops::Deref;
+use std::os::unix::prelude::OsStrExt;
+
+use mountpoint_s3_crt::io::stream::InputStream;
+use mountpoint_s3_crt::s3::client::MetaRequestResult;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use thiserror::Error;
+
+use crate::checksums::crc32c;
+use crate::object_client::{
+    GetObjectTaggingError, GetObjectTaggingResult, ObjectClientError, ObjectClientResult, ObjectTags,
+    PutObjectTaggingError, PutObjectTaggingResult, UploadChecksum,
+};
+use crate::s3_crt_client::multipart_upload::escape_xml;
+use crate::s3_crt_client::{S3CrtClient, S3Operation, S3RequestError};
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ParseError {
+    #[error("XML response was not valid: problem = {1}, xml node = {0:?}")]
+    InvalidResponse(xmltree::Element, String),
+
+    #[error("XML parsing error: {0:?}")]
+    Xml(#[from] xmltree::ParseError),
+
+    #[error("Missing field {1} from XML element {0:?}")]
+    MissingField(xmltree::Element, String),
+}
+
+impl S3CrtClient {
+    /// Create and begin a new GetObjectTagging request.
+    pub(super) async fn get_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> ObjectClientResult<GetObjectTaggingResult, GetObjectTaggingError, S3RequestError> {
+        let request = {
+            let mut message = self
+                .inner
+                .new_request_template("GET", bucket)
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_request_path_and_query(format!("/{key}"), &[("tagging", "")])
+                .map_err(S3RequestError::construction_failure)?;
+
+            let span = request_span!(self.inner, "get_object_tagging", bucket, key);
+
+            self.inner.make_simple_http_request(
+                message,
+                S3Operation::GetObjectTagging,
+                span,
+                parse_get_object_tagging_error,
+            )?
+        };
+
+        let body = request.await?;
+
+        let tags = parse_tagging(&body)
+            .map_err(|e| ObjectClientError::ClientError(S3RequestError::InternalError(e.into())))?;
+        Ok(GetObjectTaggingResult { tags })
+    }
+
+    /// Create and begin a new PutObjectTagging request.
+    pub(super) async fn put_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+        tags: &ObjectTags,
+    ) -> ObjectClientResult<PutObjectTaggingResult, PutObjectTaggingError, S3RequestError> {
+        let request_body = build_tagging_body(tags);
+        let request = {
+            let mut message = self
+                .inner
+                .new_request_template("PUT", bucket)
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_request_path_and_query(format!("/{key}"), &[("tagging", "")])
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_content_length_header(request_body.len())
+                .map_err(S3RequestError::construction_failure)?;
+            // PutObjectTagging requires an integrity check on the request body
+            let checksum = UploadChecksum::Crc32c(crc32c::checksum(request_body.as_bytes()));
+            message
+                .set_checksum_header(&checksum)
+                .map_err(S3RequestError::construction_failure)?;
+
+            let body_input_stream = InputStream::new_from_slice(&self.inner.allocator, request_body.as_bytes())
+                .map_err(S3RequestError::CrtError)?;
+            message.set_body_stream(Some(body_input_stream));
+
+            let span = request_span!(self.inner, "put_object_tagging", bucket, key, num_tags = tags.len());
+
+            self.inner.make_simple_http_request(
+                message,
+                S3Operation::PutObjectTagging,
+                span,
+                parse_put_object_tagging_error,
+            )?
+        };
+
+        let _body = request.await?;
+
+        Ok(PutObjectTaggingResult {})
+    }
+}
+
+/// Encode tags as the value of an `x-amz-tagging` header, which uses URL query parameter syntax.
+pub(super) fn tagging_header_value(tags: &ObjectTags) -> String {
+    // RFC 3986 unreserved characters are the only ones left unencoded
+    const URLENCODE_TAG: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+    // Sort the tags so that the header is deterministic
+    let mut tags: Vec<_> = tags.iter().collect();
+    tags.sort();
+
+    let mut value = String::new();
+    for (i, (key, tag_value)) in tags.into_iter().enumerate() {
+        if i > 0 {
+            value.push('&');
+        }
+        write!(
+            value,
+            "{}={}",
+            utf8_percent_encode(key, URLENCODE_TAG),
+            utf8_percent_encode(tag_value, URLENCODE_TAG)
+        )
+        .expect("should be able to append to String");
+    }
+    value
+}
+
+/// Build the XML body of a PutObjectTagging request.
+fn build_tagging_body(tags: &ObjectTags) -> String {
+    let mut tags: Vec<_> = tags.iter().collect();
+    tags.sort();
+
+    let mut body = String::from("<Tagging><TagSet>");
+    for (key, value) in tags {
+        write!(
+            body,
+            "<Tag><Key>{}</Key><Value>{}</Value></Tag>",
+            escape_xml(key),
+            escape_xml(value)
+        )
+        .expect("should be able to append to String");
+    }
+    body.push_str("</TagSet></Tagging>");
+    body
+}
+
+fn parse_tagging(bytes: &[u8]) -> Result<ObjectTags, ParseError> {
+    let mut element = xmltree::Element::parse(bytes)?;
+    let mut tag_set = element
+        .take_child("TagSet")
+        .ok_or_else(|| ParseError::MissingField(element.clone(), "TagSet".to_string()))?;
+
+    let mut tags = ObjectTags::new();
+    while let Some(tag) = tag_set.take_child("Tag") {
+        let key = get_field(&tag, "Key")?;
+        // Tag values can be empty, in which case the element has no text
+        let value = get_child(&tag, "Value")?
+            .get_text()
+            .map(|text| text.into_owned())
+            .unwrap_or_default();
+        tags.insert(key, value);
+    }
+    Ok(tags)
+}
+
+/// Extract the error code from an S3 error response.
+fn get_error_code(result: &MetaRequestResult) -> Option<String> {
+    let body = result.error_response_body.as_ref()?;
+    let root = xmltree::Element::parse(body.as_bytes()).ok()?;
+    let error_code = root.get_child("Code")?;
+    Some(error_code.get_text()?.into_owned())
+}
+
+fn parse_get_object_tagging_error(result: &MetaRequestResult) -> Option<GetObjectTaggingError> {
+    match result.response_status {
+        404 => match get_error_code(result)?.deref() {
+            "NoSuchBucket" => Some(GetObjectTaggingError::NoSuchBucket),
+            "NoSuchKey" => Some(GetObjectTaggingError::NoSuchKey),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn parse_put_object_tagging_error(result: &MetaRequestResult) -> Option<PutObjectTaggingError> {
+    match result.response_status {
+        400 => match get_error_code(result)?.deref() {
+            "InvalidTag" => Some(PutObjectTaggingError::InvalidTag),
+            _ => None,
+        },
+        404 => match get_error_code(result)?.deref() {
+            "NoSuchBucket" => Some(PutObjectTaggingError::NoSuchBucket),
+            "NoSuchKey" => Some(PutObjectTaggingError::NoSuchKey),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Copy text out of an XML element, with the right error type.
+fn get_text(element: &xmltree::Element) -> Result<String, ParseError> {
+    Ok(element
+        .get_text()
+        .ok_or_else(|| ParseError::InvalidResponse(element.clone(), "field has no text".to_owned()))?
+        .to_string())
+}
+
+/// Wrapper to get child with some name out of an XML element, with the right error type.
+fn get_child<'a>(element: &'a xmltree::Element, name: &str) -> Result<&'a xmltree::Element, ParseError> {
+    element
+        .get_child(name)
+        .ok_or_else(|| ParseError::MissingField(element.clone(), name.to_string()))
+}
+
+/// Get the text out of a child node, with the right error type.
+fn get_field(element: &xmltree::Element, name: &str) -> Result<String, ParseError> {
+    get_text(get_child(element, name)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{OsStr, OsString};
+
+    use super::*;
+
+    fn make_result(response_status: i32, body: impl Into<OsString>) -> MetaRequestResult {
+        MetaRequestResult {
+            response_status,
+            crt_error: 1i32.into(),
+            error_response_headers: None,
+            error_response_body: Some(body.into()),
+        }
+    }
+
+    #[test]
+    fn parse_404_no_such_key() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>NoSuchKey</Code><Message>The specified key does not exist.</Message><Key>not-a-key</Key><RequestId>NN5YVRKZ2ABE4N4G</RequestId><HostId>JHCWYJL3kP+E1ew6UzU0drhm3JdHwm4N9f3/k7MFl+2uIn66yCOV/gV5O/AXuqa5Bjzu2Gyzn9k=</HostId></Error>"#;
+        let result = make_result(404, OsStr::from_bytes(&body[..]));
+        let result = parse_get_object_tagging_error(&result);
+        assert_eq!(result, Some(GetObjectTaggingError::NoSuchKey));
+    }
+
+    #[test]
+    fn parse_400_invalid_tag() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>InvalidTag</Code><Message>The TagValue you have provided is invalid</Message><RequestId>NN5YVRKZ2ABE4N4G</RequestId><HostId>JHCWYJL3kP+E1ew6UzU0drhm3JdHwm4N9f3/k7MFl+2uIn66yCOV/gV5O/AXuqa5Bjzu2Gyzn9k=</HostId></Error>"#;
+        let result = make_result(400, OsStr::from_bytes(&body[..]));
+        let result = parse_put_object_tagging_error(&result);
+        assert_eq!(result, Some(PutObjectTaggingError::InvalidTag));
+    }
+
+    #[test]
+    fn parse_get_object_tagging() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Tagging xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><TagSet><Tag><Key>tag1</Key><Value>val1</Value></Tag><Tag><Key>tag2</Key><Value></Value></Tag></TagSet></Tagging>"#;
+        let tags = parse_tagging(&body[..]).expect("parsing should succeed");
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags.get("tag1").map(String::as_str), Some("val1"));
+        assert_eq!(tags.get("tag2").map(String::as_str), Some(""));
+    }
+
+    #[test]
+    fn build_put_object_tagging_body() {
+        let tags = ObjectTags::from([
+            ("project".to_string(), "blue & green".to_string()),
+            ("env".to_string(), "prod".to_string()),
+        ]);
+        let body = build_tagging_body(&tags);
+        assert_eq!(
+            body,
+            "<Tagging><TagSet><Tag><Key>env</Key><Value>prod</Value></Tag><Tag><Key>project</Key><Value>blue &amp; green</Value></Tag></TagSet></Tagging>"
+        );
+        assert_eq!(parse_tagging(body.as_bytes()).expect("parsing should succeed"), tags);
+    }
+
+    #[test]
+    fn encode_tagging_header() {
+        let tags = ObjectTags::from([
+            ("project".to_string(), "blue & green".to_string()),
+            ("env".to_string(), "prod".to_string()),
+            ("path".to_string(), "a/b=c".to_string()),
+        ]);
+        assert_eq!(
+            tagging_header_value(&tags),
+            "env=prod&path=a%2Fb%3Dc&project=blue%20%26%20green"
+        );
+    }
+}
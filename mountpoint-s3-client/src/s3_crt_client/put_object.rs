@@ -5,6 +5,7 @@ use std::time::Instant;
 
 use crate::object_client::{
     ObjectClientResult, PutObjectError, PutObjectParams, PutObjectRequest, PutObjectResult, PutObjectSingleParams,
+    SseCustomerKey,
 };
 use async_trait::async_trait;
 use futures::channel::oneshot::{self, Receiver};
@@ -12,14 +13,15 @@ use futures::future::FusedFuture as _;
 use futures::select_biased;
 use mountpoint_s3_crt::http::request_response::{Header, Headers, HeadersError};
 use mountpoint_s3_crt::io::stream::InputStream;
-use mountpoint_s3_crt::s3::client::{ChecksumConfig, MetaRequestResult, RequestType, UploadReview};
+use mountpoint_s3_crt::s3::client::{ChecksumAlgorithm, ChecksumConfig, MetaRequestResult, RequestType, UploadReview};
 use thiserror::Error;
 use tracing::error;
 use xmltree::Element;
 
+use super::object_tagging::tagging_header_value;
 use super::{
-    emit_throughput_metric, ETag, PutObjectTrailingChecksums, S3CrtClient, S3CrtClientInner, S3HttpRequest, S3Message,
-    S3Operation, S3RequestError,
+    emit_throughput_metric, ConstructionError, ETag, PutObjectTrailingChecksums, S3CrtClient, S3CrtClientInner,
+    S3HttpRequest, S3Message, S3Operation, S3RequestError,
 };
 
 const ETAG_HEADER_NAME: &str = "ETag";
@@ -46,20 +48,64 @@ impl S3CrtClient {
                 params.storage_class.as_deref(),
                 params.server_side_encryption.as_deref(),
                 params.ssekms_key_id.as_deref(),
+                params.sse_customer_key.as_ref(),
             )?;
 
+            let checksum_algorithm = params.checksum_algorithm.as_ref().unwrap_or(&ChecksumAlgorithm::Crc32c);
             let checksum_config = match params.trailing_checksums {
-                PutObjectTrailingChecksums::Enabled => Some(ChecksumConfig::trailing_crc32c()),
-                PutObjectTrailingChecksums::ReviewOnly => Some(ChecksumConfig::upload_review_crc32c()),
+                PutObjectTrailingChecksums::Enabled => Some(ChecksumConfig::trailing(checksum_algorithm)),
+                PutObjectTrailingChecksums::ReviewOnly => Some(ChecksumConfig::upload_review(checksum_algorithm)),
                 PutObjectTrailingChecksums::Disabled => None,
             };
+            let checksum_config = checksum_config
+                .map(|config| {
+                    config.ok_or_else(|| {
+                        S3RequestError::construction_failure(ConstructionError::UnsupportedChecksumAlgorithm(
+                            checksum_algorithm.clone(),
+                        ))
+                    })
+                })
+                .transpose()?;
             message.set_checksum_config(checksum_config);
 
+            // The CRT forwards the PUT headers to the CreateMultipartUpload request.
+            if let Some(checksum_type) = &params.checksum_type {
+                message
+                    .set_header(&Header::new("x-amz-checksum-type", checksum_type.to_string()))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+
+            // The CRT forwards this header to the CompleteMultipartUpload request, so the condition is
+            // evaluated against the object in S3 at the time the upload completes.
+            if let Some(etag) = &params.if_match {
+                message
+                    .set_header(&Header::new("If-Match", etag.as_str()))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+
             for (name, value) in &params.object_metadata {
                 message
                     .set_header(&Header::new(format!("x-amz-meta-{}", name), value))
                     .map_err(S3RequestError::construction_failure)?
             }
+            if !params.object_tags.is_empty() {
+                message
+                    .set_header(&Header::new("x-amz-tagging", tagging_header_value(&params.object_tags)))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if let Some(acl) = &params.acl {
+                message
+                    .set_header(&Header::new("x-amz-acl", acl))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if let Some(content_type) = &params.content_type {
+                message
+                    .set_header(&Header::new("Content-Type", content_type))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            message
+                .set_object_lock_headers(&params.object_lock)
+                .map_err(S3RequestError::construction_failure)?;
             for (name, value) in &params.custom_headers {
                 message
                     .inner
@@ -87,7 +133,7 @@ impl S3CrtClient {
                         }
                     }
                 },
-                |_| None,
+                parse_put_object_error,
                 on_headers,
             )?
         };
@@ -134,6 +180,7 @@ impl S3CrtClient {
                 params.storage_class.as_deref(),
                 params.server_side_encryption.as_deref(),
                 params.ssekms_key_id.as_deref(),
+                params.sse_customer_key.as_ref(),
             )?;
             message
                 .set_content_length_header(content_length)
@@ -158,6 +205,24 @@ impl S3CrtClient {
                     .set_header(&Header::new(format!("x-amz-meta-{}", name), value))
                     .map_err(S3RequestError::construction_failure)?;
             }
+            if !params.object_tags.is_empty() {
+                message
+                    .set_header(&Header::new("x-amz-tagging", tagging_header_value(&params.object_tags)))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if let Some(acl) = &params.acl {
+                message
+                    .set_header(&Header::new("x-amz-acl", acl))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            if let Some(content_type) = &params.content_type {
+                message
+                    .set_header(&Header::new("Content-Type", content_type))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+            message
+                .set_object_lock_headers(&params.object_lock)
+                .map_err(S3RequestError::construction_failure)?;
             for (name, value) in &params.custom_headers {
                 message
                     .inner
@@ -196,6 +261,7 @@ impl S3CrtClient {
         storage_class: Option<&str>,
         server_side_encryption: Option<&str>,
         ssekms_key_id: Option<&str>,
+        sse_customer_key: Option<&SseCustomerKey>,
     ) -> Result<S3Message<'_>, S3RequestError> {
         let mut message = self
             .inner
@@ -223,6 +289,12 @@ impl S3CrtClient {
                 .set_header(&Header::new(SSE_KEY_ID_HEADER_NAME, key_id))
                 .map_err(S3RequestError::construction_failure)?;
         }
+        // The CRT forwards these headers to each UploadPart request of a multipart upload.
+        if let Some(sse_customer_key) = sse_customer_key {
+            message
+                .set_sse_customer_key_headers(sse_customer_key)
+                .map_err(S3RequestError::construction_failure)?;
+        }
 
         Ok(message)
     }
@@ -295,10 +367,17 @@ enum S3PutObjectRequestError {
     CreateMultipartUploadFailed,
 }
 
-fn get_etag(response_headers: &Headers) -> Result<ETag, HeadersError> {
+pub(super) fn get_etag(response_headers: &Headers) -> Result<ETag, HeadersError> {
     Ok(response_headers.get_as_string(ETAG_HEADER_NAME)?.into())
 }
 
+fn parse_put_object_error(result: &MetaRequestResult) -> Option<PutObjectError> {
+    match result.response_status {
+        412 => Some(PutObjectError::PreconditionFailed),
+        _ => None,
+    }
+}
+
 fn parse_put_object_single_error(result: &MetaRequestResult) -> Option<PutObjectError> {
     match result.response_status {
         400 => {
@@ -358,7 +437,7 @@ fn extract_result(response_headers: Headers) -> Result<PutObjectResult, S3Reques
 }
 
 /// Creates `on_headers` callback that will send the response headers to the matching `Receiver`.
-fn response_headers_handler() -> (impl FnMut(&Headers, i32), Receiver<Headers>) {
+pub(super) fn response_headers_handler() -> (impl FnMut(&Headers, i32), Receiver<Headers>) {
     let (response_headers_sender, response_headers) = oneshot::channel();
     // The callback signature (`FnMut`) allows for it to be invoked multiple times,
     // but for PUT requests it will only be called once (on CompleteMultipartUpload
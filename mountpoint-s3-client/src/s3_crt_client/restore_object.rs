@@ -0,0 +1,166 @@
+use std::fmt::Write as _;
+use std::ops::Deref;
+use std::os::unix::prelude::OsStrExt;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+
+use mountpoint_s3_crt::http::request_response::Headers;
+use mountpoint_s3_crt::io::stream::InputStream;
+use mountpoint_s3_crt::s3::client::MetaRequestResult;
+
+use crate::checksums::crc32c;
+use crate::object_client::{
+    ObjectClientResult, RestoreObjectError, RestoreObjectParams, RestoreObjectResult, UploadChecksum,
+};
+use crate::s3_crt_client::{S3CrtClient, S3CrtClientInner, S3Operation, S3RequestError};
+
+impl S3CrtClient {
+    /// Create and begin a new RestoreObject request.
+    pub(super) async fn restore_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &RestoreObjectParams,
+    ) -> ObjectClientResult<RestoreObjectResult, RestoreObjectError, S3RequestError> {
+        let request_body = build_restore_request_body(params);
+        // S3 responds with 202 Accepted when it starts a restore, and 200 OK when the object was
+        // already restored and it only updated the expiry of the restored copy.
+        let response_status = Arc::new(AtomicI32::new(0));
+        let request = {
+            let mut message = self
+                .inner
+                .new_request_template("POST", bucket)
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_request_path_and_query(format!("/{key}"), &[("restore", "")])
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_content_length_header(request_body.len())
+                .map_err(S3RequestError::construction_failure)?;
+            let checksum = UploadChecksum::Crc32c(crc32c::checksum(request_body.as_bytes()));
+            message
+                .set_checksum_header(&checksum)
+                .map_err(S3RequestError::construction_failure)?;
+
+            let body_input_stream = InputStream::new_from_slice(&self.inner.allocator, request_body.as_bytes())
+                .map_err(S3RequestError::CrtError)?;
+            message.set_body_stream(Some(body_input_stream));
+
+            let span = request_span!(
+                self.inner,
+                "restore_object",
+                bucket,
+                key,
+                days = params.days,
+                tier = ?params.tier
+            );
+
+            let response_status_clone = response_status.clone();
+            let options = S3CrtClientInner::new_meta_request_options(message, S3Operation::RestoreObject);
+            self.inner.make_simple_http_request_from_options(
+                options,
+                span,
+                |_| {},
+                parse_restore_object_error,
+                move |_: &Headers, status: i32| response_status_clone.store(status, Ordering::SeqCst),
+            )?
+        };
+
+        let _body = request.await?;
+
+        Ok(RestoreObjectResult {
+            already_restored: response_status.load(Ordering::SeqCst) == 200,
+        })
+    }
+}
+
+/// Build the XML body of a RestoreObject request.
+fn build_restore_request_body(params: &RestoreObjectParams) -> String {
+    let mut body = String::from("<RestoreRequest>");
+    write!(body, "<Days>{}</Days>", params.days).expect("should be able to append to String");
+    if let Some(tier) = params.tier {
+        write!(
+            body,
+            "<GlacierJobParameters><Tier>{}</Tier></GlacierJobParameters>",
+            tier.as_str()
+        )
+        .expect("should be able to append to String");
+    }
+    body.push_str("</RestoreRequest>");
+    body
+}
+
+/// Extract the error code from an S3 error response.
+fn get_error_code(result: &MetaRequestResult) -> Option<String> {
+    let body = result.error_response_body.as_ref()?;
+    let root = xmltree::Element::parse(body.as_bytes()).ok()?;
+    let error_code = root.get_child("Code")?;
+    Some(error_code.get_text()?.into_owned())
+}
+
+fn parse_restore_object_error(result: &MetaRequestResult) -> Option<RestoreObjectError> {
+    match result.response_status {
+        403 => match get_error_code(result)?.deref() {
+            "InvalidObjectState" => Some(RestoreObjectError::InvalidObjectState),
+            _ => None,
+        },
+        404 => match get_error_code(result)?.deref() {
+            "NoSuchBucket" => Some(RestoreObjectError::NoSuchBucket),
+            "NoSuchKey" => Some(RestoreObjectError::NoSuchKey),
+            _ => None,
+        },
+        409 => match get_error_code(result)?.deref() {
+            "RestoreAlreadyInProgress" => Some(RestoreObjectError::RestoreAlreadyInProgress),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{OsStr, OsString};
+
+    use crate::types::RestoreTier;
+
+    use super::*;
+
+    fn make_result(response_status: i32, body: impl Into<OsString>) -> MetaRequestResult {
+        MetaRequestResult {
+            response_status,
+            crt_error: 1i32.into(),
+            error_response_headers: None,
+            error_response_body: Some(body.into()),
+        }
+    }
+
+    #[test]
+    fn parse_403_invalid_object_state() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>InvalidObjectState</Code><Message>Restore is not allowed for the object's current storage class</Message><RequestId>NN5YVRKZ2ABE4N4G</RequestId><HostId>JHCWYJL3kP+E1ew6UzU0drhm3JdHwm4N9f3/k7MFl+2uIn66yCOV/gV5O/AXuqa5Bjzu2Gyzn9k=</HostId></Error>"#;
+        let result = make_result(403, OsStr::from_bytes(&body[..]));
+        let result = parse_restore_object_error(&result);
+        assert_eq!(result, Some(RestoreObjectError::InvalidObjectState));
+    }
+
+    #[test]
+    fn parse_409_restore_already_in_progress() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>RestoreAlreadyInProgress</Code><Message>Object restore is already in progress</Message><RequestId>NN5YVRKZ2ABE4N4G</RequestId><HostId>JHCWYJL3kP+E1ew6UzU0drhm3JdHwm4N9f3/k7MFl+2uIn66yCOV/gV5O/AXuqa5Bjzu2Gyzn9k=</HostId></Error>"#;
+        let result = make_result(409, OsStr::from_bytes(&body[..]));
+        let result = parse_restore_object_error(&result);
+        assert_eq!(result, Some(RestoreObjectError::RestoreAlreadyInProgress));
+    }
+
+    #[test]
+    fn build_restore_object_body() {
+        let params = RestoreObjectParams::new(7);
+        assert_eq!(
+            build_restore_request_body(&params),
+            "<RestoreRequest><Days>7</Days></RestoreRequest>"
+        );
+        let params = params.tier(Some(RestoreTier::Bulk));
+        assert_eq!(
+            build_restore_request_body(&params),
+            "<RestoreRequest><Days>7</Days><GlacierJobParameters><Tier>Bulk</Tier></GlacierJobParameters></RestoreRequest>"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "s3_tests")]
+
+pub mod common;
+
+use common::*;
+use mountpoint_s3_client::error::{DeleteObjectsError, ObjectClientError};
+use mountpoint_s3_client::types::MAX_DELETE_OBJECTS_KEYS;
+use mountpoint_s3_client::{ObjectClient, S3CrtClient};
+
+#[tokio::test]
+async fn test_delete_objects() {
+    let sdk_client = get_test_sdk_client().await;
+    let (bucket, prefix) = get_test_bucket_and_prefix("test_delete_objects");
+
+    let names = ["a", "b/c", "d&<e>", "keep"];
+    create_objects_for_test(&sdk_client, &bucket, &prefix, &names).await;
+
+    let client: S3CrtClient = get_test_client();
+    let keys = ["a", "b/c", "d&<e>", "nonexistent"]
+        .iter()
+        .map(|name| format!("{prefix}{name}"))
+        .collect::<Vec<_>>();
+    let result = client
+        .delete_objects(&bucket, &keys)
+        .await
+        .expect("delete_objects should succeed");
+    assert!(result.errors.is_empty(), "unexpected errors: {:?}", result.errors);
+
+    let result = client
+        .list_objects(&bucket, None, "", 10, &prefix)
+        .await
+        .expect("list_objects should succeed");
+    let remaining = result
+        .objects
+        .iter()
+        .map(|object| object.key.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(remaining, vec![format!("{prefix}keep")]);
+}
+
+#[tokio::test]
+async fn test_delete_objects_too_many_keys() {
+    let (bucket, prefix) = get_test_bucket_and_prefix("test_delete_objects_too_many_keys");
+
+    let keys = (0..=MAX_DELETE_OBJECTS_KEYS)
+        .map(|i| format!("{prefix}{i}"))
+        .collect::<Vec<_>>();
+
+    let client: S3CrtClient = get_test_client();
+    let result = client.delete_objects(&bucket, &keys).await;
+    assert!(matches!(
+        result,
+        Err(ObjectClientError::ServiceError(DeleteObjectsError::TooManyKeys))
+    ));
+}
+
+#[tokio::test]
+async fn test_delete_objects_404_bucket() {
+    let (_bucket, prefix) = get_test_bucket_and_prefix("test_delete_objects_404_bucket");
+
+    let keys = vec![format!("{prefix}nonexistent_key")];
+
+    let client: S3CrtClient = get_test_client();
+    let result = client.delete_objects("amzn-s3-demo-bucket", &keys).await;
+    assert!(matches!(
+        result,
+        Err(ObjectClientError::ServiceError(DeleteObjectsError::NoSuchBucket))
+    ));
+}
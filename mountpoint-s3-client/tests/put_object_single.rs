@@ -10,7 +10,8 @@ use mountpoint_s3_client::checksums::crc32c;
 use mountpoint_s3_client::config::S3ClientConfig;
 use mountpoint_s3_client::error::{ObjectClientError, PutObjectError};
 use mountpoint_s3_client::types::{
-    Checksum, ChecksumAlgorithm, GetObjectParams, PutObjectResult, PutObjectSingleParams, UploadChecksum,
+    Checksum, ChecksumAlgorithm, GetObjectParams, HeadObjectParams, PutObjectResult, PutObjectSingleParams,
+    SseCustomerKey, UploadChecksum,
 };
 use mountpoint_s3_client::{ObjectClient, S3CrtClient};
 use mountpoint_s3_crt::checksums::{crc32, sha1, sha256};
@@ -129,6 +130,7 @@ async fn test_put_checksums(checksum_algorithm: Option<ChecksumAlgorithm>) {
     let output_checksum = Checksum {
         checksum_crc32: output.checksum_crc32,
         checksum_crc32c: output.checksum_crc32_c,
+        checksum_crc64nvme: None,
         checksum_sha1: output.checksum_sha1,
         checksum_sha256: output.checksum_sha256,
     };
@@ -322,6 +324,45 @@ async fn test_put_object_sse(sse_type: Option<&str>, kms_key_id: Option<String>)
     check_sse(&bucket, &key, sse_type, &kms_key_id, put_object_result).await;
 }
 
+// Test that objects uploaded with a customer-provided key (SSE-C) can only be read with the same key.
+#[tokio::test]
+#[cfg(not(feature = "s3express_tests"))]
+async fn test_put_object_sse_customer_key() {
+    let (bucket, prefix) = get_test_bucket_and_prefix("test_put_object_sse_customer_key");
+    let client = get_test_client();
+    let key = format!("{prefix}hello");
+
+    let mut rng = rand::thread_rng();
+    let mut key_bytes = [0u8; SseCustomerKey::KEY_LENGTH];
+    rng.fill(&mut key_bytes[..]);
+    let sse_customer_key = SseCustomerKey::new(&key_bytes).unwrap();
+
+    let contents = b"hello world";
+    let params = PutObjectSingleParams::new().sse_customer_key(Some(sse_customer_key.clone()));
+    client
+        .put_object_single(&bucket, &key, &params, contents)
+        .await
+        .expect("put_object should succeed");
+
+    client
+        .head_object(&bucket, &key, &HeadObjectParams::new())
+        .await
+        .expect_err("head_object without the key should fail");
+    let head_params = HeadObjectParams::new().sse_customer_key(Some(sse_customer_key.clone()));
+    let head_result = client
+        .head_object(&bucket, &key, &head_params)
+        .await
+        .expect("head_object with the key should succeed");
+    assert_eq!(head_result.size, contents.len() as u64);
+
+    let get_params = GetObjectParams::new().sse_customer_key(Some(sse_customer_key));
+    let result = client
+        .get_object(&bucket, &key, &get_params)
+        .await
+        .expect("get_object with the key should succeed");
+    check_get_result(result, None, &contents[..]).await;
+}
+
 #[tokio::test]
 async fn test_put_object_header() {
     let (bucket, prefix) = get_test_bucket_and_prefix("test_put_object_header");
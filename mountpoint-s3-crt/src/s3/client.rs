@@ -1416,6 +1416,32 @@ pub struct ChecksumConfig {
 }
 
 impl ChecksumConfig {
+    /// Create a [ChecksumConfig] enabling trailing checksums with the given algorithm in PUT requests.
+    ///
+    /// Returns `None` if the algorithm is not supported by the CRT.
+    pub fn trailing(checksum_algorithm: &ChecksumAlgorithm) -> Option<Self> {
+        Some(Self {
+            inner: aws_s3_checksum_config {
+                location: aws_s3_checksum_location::AWS_SCL_TRAILER,
+                checksum_algorithm: checksum_algorithm.to_aws_s3_checksum_algorithm()?,
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Create a [ChecksumConfig] enabling checksums with the given algorithm only for upload review.
+    ///
+    /// Returns `None` if the algorithm is not supported by the CRT.
+    pub fn upload_review(checksum_algorithm: &ChecksumAlgorithm) -> Option<Self> {
+        Some(Self {
+            inner: aws_s3_checksum_config {
+                location: aws_s3_checksum_location::AWS_SCL_NONE,
+                checksum_algorithm: checksum_algorithm.to_aws_s3_checksum_algorithm()?,
+                ..Default::default()
+            },
+        })
+    }
+
     /// Create a [ChecksumConfig] enabling Crc32c trailing checksums in PUT requests.
     pub fn trailing_crc32c() -> Self {
         Self {
@@ -1456,6 +1482,8 @@ pub enum ChecksumAlgorithm {
     Sha1,
     /// Sha256 checksum.
     Sha256,
+    /// Crc64nvme checksum.
+    Crc64nvme,
     /// Checksum of a type unknown to this S3 client.
     ///
     /// This type will be used if Mountpoint ever encounters a checksum algorithm it doesn't recognize.
@@ -1471,9 +1499,21 @@ impl ChecksumAlgorithm {
             aws_s3_checksum_algorithm::AWS_SCA_CRC32 => Some(ChecksumAlgorithm::Crc32),
             aws_s3_checksum_algorithm::AWS_SCA_SHA1 => Some(ChecksumAlgorithm::Sha1),
             aws_s3_checksum_algorithm::AWS_SCA_SHA256 => Some(ChecksumAlgorithm::Sha256),
+            aws_s3_checksum_algorithm::AWS_SCA_CRC64NVME => Some(ChecksumAlgorithm::Crc64nvme),
             _ => unreachable!("unknown aws_s3_checksum_algorithm"),
         }
     }
+
+    fn to_aws_s3_checksum_algorithm(&self) -> Option<aws_s3_checksum_algorithm> {
+        match self {
+            ChecksumAlgorithm::Crc32c => Some(aws_s3_checksum_algorithm::AWS_SCA_CRC32C),
+            ChecksumAlgorithm::Crc32 => Some(aws_s3_checksum_algorithm::AWS_SCA_CRC32),
+            ChecksumAlgorithm::Sha1 => Some(aws_s3_checksum_algorithm::AWS_SCA_SHA1),
+            ChecksumAlgorithm::Sha256 => Some(aws_s3_checksum_algorithm::AWS_SCA_SHA256),
+            ChecksumAlgorithm::Crc64nvme => Some(aws_s3_checksum_algorithm::AWS_SCA_CRC64NVME),
+            ChecksumAlgorithm::Unknown(_) => None,
+        }
+    }
 }
 
 impl Display for ChecksumAlgorithm {
@@ -1483,6 +1523,7 @@ impl Display for ChecksumAlgorithm {
             ChecksumAlgorithm::Crc32 => f.write_str("CRC32"),
             ChecksumAlgorithm::Sha1 => f.write_str("SHA1"),
             ChecksumAlgorithm::Sha256 => f.write_str("SHA256"),
+            ChecksumAlgorithm::Crc64nvme => f.write_str("CRC64NVME"),
             ChecksumAlgorithm::Unknown(algorithm) => write!(f, "Unknown algorithm: {:?}", algorithm),
         }
     }
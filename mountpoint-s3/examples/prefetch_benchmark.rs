@@ -133,6 +133,7 @@ fn main() {
                     bucket.clone(),
                     object_id.clone(),
                     size,
+                    None,
                 );
 
                 scope.spawn(|| {
@@ -3,7 +3,7 @@ use std::time::Instant;
 
 use clap::Parser;
 use mountpoint_s3::mem_limiter::MemoryLimiter;
-use mountpoint_s3::upload::Uploader;
+use mountpoint_s3::upload::{Uploader, UploaderConfig};
 use mountpoint_s3::ServerSideEncryption;
 use mountpoint_s3_client::config::{EndpointConfig, S3ClientConfig};
 use mountpoint_s3_client::types::ChecksumAlgorithm;
@@ -81,7 +81,7 @@ struct UploadBenchmarkArgs {
     #[clap(
         long,
         help = "Checksum algorithm to use for S3 uploads",
-        value_name = "off|crc32c|crc32|sha1|sha256",
+        value_name = "off|crc32c|crc32|crc64nvme|sha1|sha256",
         default_value = "crc32c"
     )]
     pub checksum_algorithm: String,
@@ -122,6 +122,7 @@ fn main() {
             "off" => None,
             "crc32c" => Some(ChecksumAlgorithm::Crc32c),
             "crc32" => Some(ChecksumAlgorithm::Crc32),
+            "crc64nvme" => Some(ChecksumAlgorithm::Crc64nvme),
             "sha1" => Some(ChecksumAlgorithm::Sha1),
             "sha256" => Some(ChecksumAlgorithm::Sha256),
             other => Some(ChecksumAlgorithm::Unknown(other.to_string())),
@@ -130,10 +131,9 @@ fn main() {
             client.clone(),
             runtime.clone(),
             mem_limiter,
-            None,
-            server_side_encryption,
-            buffer_size,
-            checksum_algorithm,
+            UploaderConfig::new(buffer_size)
+                .server_side_encryption(server_side_encryption)
+                .default_checksum_algorithm(checksum_algorithm),
         );
 
         let start = Instant::now();
@@ -165,7 +165,7 @@ where
 
     let bucket = args.bucket.clone();
     let key = args.key.clone();
-    let mut upload_request = uploader.start_atomic_upload(&bucket, &key).unwrap();
+    let mut upload_request = uploader.start_atomic_upload(&bucket, &key, None).unwrap();
 
     let mut total_bytes_written = 0;
     let target_size = args.object_size;
@@ -1,10 +1,13 @@
-use std::{fmt::Debug, future::Future};
+use std::{fmt::Debug, future::Future, io, thread};
 
 use async_channel::{Receiver, Sender};
 use futures::task::{Spawn, SpawnError, SpawnExt};
 
+use crate::sync::Arc;
+
 /// Type-erasure for a [Spawn] implementation.
-pub struct BoxRuntime(Box<dyn Spawn + Send + Sync>);
+#[derive(Clone)]
+pub struct BoxRuntime(Arc<dyn Spawn + Send + Sync>);
 
 impl Spawn for BoxRuntime {
     fn spawn_obj(&self, future: futures::task::FutureObj<'static, ()>) -> Result<(), SpawnError> {
@@ -20,7 +23,7 @@ impl Debug for BoxRuntime {
 
 impl BoxRuntime {
     pub fn new(runtime: impl Spawn + Sync + Send + 'static) -> Self {
-        BoxRuntime(Box::new(runtime))
+        BoxRuntime(Arc::new(runtime))
     }
 
     /// Spawns a task that polls the given future to completion and return
@@ -40,6 +43,58 @@ impl BoxRuntime {
     }
 }
 
+type BlockingJob = Box<dyn FnOnce() + Send>;
+
+/// A fixed set of long-lived threads that run blocking functions, like file system I/O, so that
+/// they don't block the threads polling futures. The threads exit once every clone of the pool has
+/// been dropped.
+#[derive(Clone)]
+pub struct BlockingPool {
+    sender: Sender<BlockingJob>,
+}
+
+impl Debug for BlockingPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockingPool")
+            .field("queued", &self.sender.len())
+            .finish()
+    }
+}
+
+impl BlockingPool {
+    /// Start a pool of `threads` threads with the given name.
+    pub fn new(name: &str, threads: usize) -> io::Result<Self> {
+        assert!(threads > 0, "a blocking pool needs at least one thread");
+        let (sender, receiver) = async_channel::unbounded::<BlockingJob>();
+        for _ in 0..threads {
+            let receiver = receiver.clone();
+            thread::Builder::new().name(name.to_owned()).spawn(move || {
+                while let Ok(job) = receiver.recv_blocking() {
+                    job();
+                }
+            })?;
+        }
+        Ok(Self { sender })
+    }
+
+    /// Run a blocking function on one of the threads of the pool and wait for its result.
+    pub async fn run<T, F>(&self, f: F) -> io::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (sender, receiver) = async_channel::bounded(1);
+        let job: BlockingJob = Box::new(move || {
+            _ = sender.send_blocking(f());
+        });
+        self.sender
+            .send(job)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "blocking pool has stopped"))?;
+        Ok(receiver.recv().await.expect("blocking function should not panic"))
+    }
+}
+
 /// Creates an async one shot channel with a [RemoteResult] on the receiving end.
 pub fn result_channel<T, E>() -> (ResultSender<T, E>, RemoteResult<T, E>) {
     let (sender, receiver) = async_channel::bounded(1);
@@ -93,13 +148,14 @@ impl<T, E> Drop for RemoteResult<T, E> {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
     use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::Arc;
 
     use futures::executor::{block_on, ThreadPool};
     use test_case::test_case;
 
-    use super::{result_channel, BoxRuntime};
+    use super::{result_channel, BlockingPool, BoxRuntime};
 
     #[test_case(Ok(42))]
     #[test_case(Err("error"))]
@@ -126,6 +182,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_blocking_pool() {
+        let pool = BlockingPool::new("test-blocking", 2).unwrap();
+        let thread_id = std::thread::current().id();
+        let thread_ids: Vec<_> = (0..10)
+            .map(|_| block_on(pool.run(|| std::thread::current().id())).unwrap())
+            .collect();
+        assert!(
+            thread_ids.iter().all(|id| *id != thread_id),
+            "functions should run on the threads of the pool"
+        );
+        let distinct: HashSet<_> = thread_ids.iter().collect();
+        assert!(distinct.len() <= 2, "the threads of the pool should be reused");
+    }
+
     /// Verify that [RemoteResult] always drops the result.
     #[test_case(true; "after await")]
     #[test_case(false; "without await")]
@@ -13,6 +13,7 @@
 use std::sync::Arc;
 
 use anyhow::anyhow;
+use clap::Parser;
 use futures::executor::ThreadPool;
 
 use mountpoint_s3::cli::CliArgs;
@@ -22,7 +23,7 @@ use mountpoint_s3_client::mock_client::{MockClientConfig, MockObject};
 use mountpoint_s3_client::types::ETag;
 
 fn main() -> anyhow::Result<()> {
-    mountpoint_s3::cli::main(create_mock_client)
+    mountpoint_s3::cli::main(CliArgs::parse(), create_mock_client)
 }
 
 fn create_mock_client(args: &CliArgs) -> anyhow::Result<(Arc<ThroughputMockClient>, ThreadPool, S3Personality)> {
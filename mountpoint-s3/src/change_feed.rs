@@ -0,0 +1,327 @@
+//! Feeds of changes to objects, read from S3 event notifications, to keep the metadata cache fresh
+//! even with long metadata TTLs.
+//!
+//! A change feed yields the messages S3 sends for event notifications, each a JSON object with a
+//! `Records` array. `ObjectCreated:*` and `ObjectRemoved:*` records become [ObjectChange]s, and
+//! other records, like the test event S3 sends when notifications are configured, are ignored.
+//! Feeds implement [ChangeFeed], and [FileChangeFeed] reads one message per line from a file or a
+//! named pipe, which anything that receives the notifications (for example, a script polling an
+//! SQS queue) can write to.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::manifest::decode_key;
+
+#[derive(Debug, Error)]
+pub enum ChangeFeedError {
+    #[error("I/O error reading change feed")]
+    IoError(#[from] io::Error),
+
+    #[error("invalid event notification message")]
+    InvalidMessage(#[from] serde_json::Error),
+}
+
+/// What happened to an object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Removed,
+}
+
+/// A change to an object, reported by an event notification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectChange {
+    pub kind: ChangeKind,
+    pub bucket: String,
+    pub key: String,
+    /// The ETag of a created object, without the quotes S3 puts around ETags elsewhere
+    pub etag: Option<String>,
+}
+
+/// A source of changes to objects
+pub trait ChangeFeed: Send + 'static {
+    /// Wait up to `timeout` for the changes reported by the next message, returning no changes if
+    /// there is no message by then, or `None` once the feed has ended. Invalid messages are
+    /// returned as errors, and the feed carries on after them.
+    fn next_changes(&mut self, timeout: Duration) -> Option<Result<Vec<ObjectChange>, ChangeFeedError>>;
+}
+
+#[derive(Debug, Deserialize)]
+struct EventMessage {
+    #[serde(rename = "Records", default)]
+    records: Vec<EventRecord>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventRecord {
+    event_name: String,
+    s3: EventEntity,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventEntity {
+    bucket: EventBucket,
+    object: EventObject,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventBucket {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventObject {
+    key: String,
+    e_tag: Option<String>,
+}
+
+/// Parse the changes reported by an event notification message. Records for other events, and
+/// with keys that aren't valid UTF-8, are skipped.
+pub fn parse_message(message: &str) -> Result<Vec<ObjectChange>, ChangeFeedError> {
+    let message: EventMessage = serde_json::from_str(message)?;
+    let changes = message
+        .records
+        .into_iter()
+        .filter_map(|record| {
+            let kind = if record.event_name.starts_with("ObjectCreated:") {
+                ChangeKind::Created
+            } else if record.event_name.starts_with("ObjectRemoved:") {
+                ChangeKind::Removed
+            } else {
+                return None;
+            };
+            Some(ObjectChange {
+                kind,
+                bucket: record.s3.bucket.name,
+                key: decode_key(&record.s3.object.key)?,
+                etag: record.s3.object.e_tag,
+            })
+        })
+        .collect();
+    Ok(changes)
+}
+
+/// Reads event notification messages from a file, one per line. A named pipe is opened again
+/// whenever its writer closes it, so the feed only ends for regular files.
+#[derive(Debug)]
+pub struct FileChangeFeed {
+    path: PathBuf,
+    file: Option<File>,
+    is_pipe: bool,
+    /// What has been read past the last complete line
+    pending: Vec<u8>,
+}
+
+/// The outcome of reading more of a [FileChangeFeed]
+enum Fill {
+    /// Some data was read, or may be ready to read
+    Read,
+    /// A named pipe had nothing to read before the deadline
+    TimedOut,
+    /// The end of the file, or the writer of a named pipe closed it
+    Ended,
+}
+
+impl FileChangeFeed {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_owned(),
+            file: None,
+            is_pipe: false,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Take the next line from what has been read so far, or all of it if `all` is set.
+    fn take_line(&mut self, all: bool) -> Option<String> {
+        let end = match self.pending.iter().position(|&byte| byte == b'\n') {
+            Some(newline) => newline + 1,
+            None if all && !self.pending.is_empty() => self.pending.len(),
+            None => return None,
+        };
+        let line = self.pending.drain(..end).collect::<Vec<_>>();
+        Some(String::from_utf8_lossy(&line).into_owned())
+    }
+
+    /// Read more of the file, waiting until `deadline` for a named pipe to have something to read.
+    fn fill(&mut self, deadline: Instant) -> io::Result<Fill> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => {
+                // Opening a named pipe would otherwise wait for a writer, with no way to stop
+                let file = OpenOptions::new()
+                    .read(true)
+                    .custom_flags(libc::O_NONBLOCK)
+                    .open(&self.path)?;
+                self.is_pipe = file.metadata()?.file_type().is_fifo();
+                self.file.insert(file)
+            }
+        };
+        if self.is_pipe {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let mut pollfd = libc::pollfd {
+                fd: file.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: `pollfd` is a single valid entry, and the file outlives the call
+            let ready = unsafe { libc::poll(&mut pollfd, 1, timeout.as_millis().try_into().unwrap_or(i32::MAX)) };
+            match ready {
+                0 => return Ok(Fill::TimedOut),
+                ready if ready < 0 => {
+                    let e = io::Error::last_os_error();
+                    return match e.kind() {
+                        io::ErrorKind::Interrupted => Ok(Fill::Read),
+                        _ => Err(e),
+                    };
+                }
+                _ => {}
+            }
+        }
+        let mut buffer = [0u8; 64 * 1024];
+        match file.read(&mut buffer) {
+            Ok(0) => {
+                if self.is_pipe {
+                    self.file = None;
+                }
+                Ok(Fill::Ended)
+            }
+            Ok(read) => {
+                self.pending.extend_from_slice(&buffer[..read]);
+                Ok(Fill::Read)
+            }
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted) => Ok(Fill::Read),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl ChangeFeed for FileChangeFeed {
+    fn next_changes(&mut self, timeout: Duration) -> Option<Result<Vec<ObjectChange>, ChangeFeedError>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let line = match self.take_line(false) {
+                Some(line) => line,
+                None => match self.fill(deadline) {
+                    Ok(Fill::Read) => continue,
+                    Ok(Fill::TimedOut) => return Some(Ok(Vec::new())),
+                    // A last line may have no newline
+                    Ok(Fill::Ended) => match self.take_line(true) {
+                        Some(line) => line,
+                        None if self.is_pipe => continue,
+                        None => return None,
+                    },
+                    Err(e) => return Some(Err(e.into())),
+                },
+            };
+            if !line.trim().is_empty() {
+                return Some(parse_message(&line));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const MESSAGE: &str = r#"{"Records":[
+        {"eventVersion":"2.1","eventSource":"aws:s3","eventName":"ObjectCreated:Put",
+         "s3":{"bucket":{"name":"bucket"},"object":{"key":"dir/a+file%3F","size":5,"eTag":"abc","sequencer":"01"}}},
+        {"eventVersion":"2.1","eventSource":"aws:s3","eventName":"ObjectRemoved:Delete",
+         "s3":{"bucket":{"name":"bucket"},"object":{"key":"b","sequencer":"02"}}},
+        {"eventVersion":"2.1","eventSource":"aws:s3","eventName":"ObjectRestore:Completed",
+         "s3":{"bucket":{"name":"bucket"},"object":{"key":"c","eTag":"def"}}}
+    ]}"#;
+
+    #[test]
+    fn test_parse_message() {
+        let changes = parse_message(MESSAGE).unwrap();
+        assert_eq!(
+            changes,
+            [
+                ObjectChange {
+                    kind: ChangeKind::Created,
+                    bucket: "bucket".to_owned(),
+                    key: "dir/a file?".to_owned(),
+                    etag: Some("abc".to_owned()),
+                },
+                ObjectChange {
+                    kind: ChangeKind::Removed,
+                    bucket: "bucket".to_owned(),
+                    key: "b".to_owned(),
+                    etag: None,
+                },
+            ]
+        );
+
+        // Test events have no records
+        let test_event = r#"{"Service":"Amazon S3","Event":"s3:TestEvent","Bucket":"bucket"}"#;
+        assert!(parse_message(test_event).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_file_change_feed() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "{}", MESSAGE.replace('\n', "")).unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "not json").unwrap();
+        writeln!(file, r#"{{"Records":[]}}"#).unwrap();
+        write!(file, r#"{{"Records":[]}}"#).unwrap();
+
+        let mut feed = FileChangeFeed::new(file.path());
+        let timeout = Duration::from_secs(10);
+        assert_eq!(feed.next_changes(timeout).unwrap().unwrap().len(), 2);
+        assert!(matches!(
+            feed.next_changes(timeout),
+            Some(Err(ChangeFeedError::InvalidMessage(_)))
+        ));
+        assert!(feed.next_changes(timeout).unwrap().unwrap().is_empty());
+        // The last line has no newline
+        assert!(feed.next_changes(timeout).unwrap().unwrap().is_empty());
+        assert!(feed.next_changes(timeout).is_none());
+    }
+
+    #[test]
+    fn test_pipe_change_feed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("feed");
+        nix::unistd::mkfifo(&path, nix::sys::stat::Mode::S_IRWXU).unwrap();
+
+        // Waiting for a writer times out
+        let mut feed = FileChangeFeed::new(&path);
+        let start = Instant::now();
+        assert!(feed
+            .next_changes(Duration::from_millis(50))
+            .unwrap()
+            .unwrap()
+            .is_empty());
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // Each writer's messages are read, and the feed carries on after the writer closes the pipe
+        for _ in 0..2 {
+            let mut writer = OpenOptions::new().write(true).open(&path).unwrap();
+            writeln!(writer, "{}", MESSAGE.replace('\n', "")).unwrap();
+            drop(writer);
+            let changes = feed.next_changes(Duration::from_secs(10)).unwrap().unwrap();
+            assert_eq!(changes.len(), 2);
+            assert!(feed
+                .next_changes(Duration::from_millis(50))
+                .unwrap()
+                .unwrap()
+                .is_empty());
+        }
+    }
+}
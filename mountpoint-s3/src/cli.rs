@@ -5,18 +5,20 @@ use std::fs::File;
 use std::io::{Read, Write};
 use std::num::NonZeroUsize;
 use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::fs::FileTypeExt;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{anyhow, Context as _};
-use clap::{value_parser, ArgGroup, Parser, ValueEnum};
+use clap::{value_parser, ArgGroup, Args, CommandFactory, FromArgMatches, Parser, ValueEnum};
 use fuser::{MountOption, Session};
 use futures::executor::block_on;
 use futures::task::Spawn;
 use mountpoint_s3_client::config::{AddressingStyle, EndpointConfig, S3ClientAuthConfig, S3ClientConfig};
 use mountpoint_s3_client::error::ObjectClientError;
 use mountpoint_s3_client::instance_info::InstanceInfo;
+use mountpoint_s3_client::types::{ChecksumAlgorithm, ObjectLock, ObjectTags, SseCustomerKey};
 use mountpoint_s3_client::user_agent::UserAgent;
 use mountpoint_s3_client::{ObjectClient, S3CrtClient, S3RequestError};
 use mountpoint_s3_crt::auth::signing_config::SigningAlgorithm;
@@ -28,21 +30,29 @@ use nix::sys::signal::Signal;
 use nix::unistd::ForkResult;
 use regex::Regex;
 use sysinfo::{RefreshKind, System};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
 
 use crate::data_cache::{
     CacheLimit, DiskDataCache, DiskDataCacheConfig, ExpressDataCache, ExpressDataCacheConfig, ManagedCacheDir,
     MultilevelDataCache,
 };
-use crate::fs::{CacheConfig, ServerSideEncryption, TimeToLive};
+use crate::fs::{CacheConfig, ServerSideEncryption, TimeToLive, UnionFilesystem, UnionSource, MAX_UNION_SOURCES};
 use crate::fuse::session::FuseSession;
-use crate::fuse::S3FuseFilesystem;
+use crate::fuse::{send_invalidations, S3FuseFilesystem};
+use crate::key_filter::{KeyFilter, KeyPattern};
 use crate::logging::{init_logging, prepare_log_file_name, LoggingConfig};
-use crate::mem_limiter::MINIMUM_MEM_LIMIT;
-use crate::prefetch::{caching_prefetch, default_prefetch, Prefetch};
+use crate::manifest::{Manifest, ManifestLocation};
+use crate::mem_limiter::{MemoryLimiter, MINIMUM_MEM_LIMIT};
+use crate::prefetch::{caching_prefetch, default_prefetch, Prefetch, PrefetcherConfig};
 use crate::prefix::Prefix;
 use crate::s3::S3Personality;
+use crate::upload::{ContentTypeMap, UploadJournal};
 use crate::{autoconfigure, build_info, metrics, S3Filesystem, S3FilesystemConfig};
 
+pub mod list_uploads;
+use list_uploads::ListUploadsArgs;
+
 const CLIENT_OPTIONS_HEADER: &str = "Client options";
 const MOUNT_OPTIONS_HEADER: &str = "Mount options";
 const BUCKET_OPTIONS_HEADER: &str = "Bucket options";
@@ -51,7 +61,12 @@ const LOGGING_OPTIONS_HEADER: &str = "Logging options";
 const CACHING_OPTIONS_HEADER: &str = "Caching options";
 const ADVANCED_OPTIONS_HEADER: &str = "Advanced options";
 
-#[derive(Parser, Debug)]
+// S3 limits on object tags
+const MAX_TAGS_PER_OBJECT: usize = 10;
+const MAX_TAG_KEY_LENGTH: usize = 128;
+const MAX_TAG_VALUE_LENGTH: usize = 256;
+
+#[derive(Parser, Debug, Clone)]
 #[clap(
     name = "mount-s3",
     about = "Mountpoint for Amazon S3",
@@ -87,21 +102,17 @@ Learn more in Mountpoint's configuration documentation (CONFIGURATION.md).\
     pub prefix: Option<Prefix>,
 
     #[clap(
-        long,
-        help = "AWS region of the bucket [default: auto-detect region]",
-        help_heading = BUCKET_OPTIONS_HEADER
-    )]
-    pub region: Option<String>,
-
-    #[clap(
-        long,
-        help = "S3 endpoint URL [default: auto-detect endpoint]",
-        help_heading = BUCKET_OPTIONS_HEADER
+        long = "source",
+        help = "Mount another bucket as the directory NAME of a union mount, where the first bucket becomes a \
+                directory named after it. Can be specified multiple times",
+        help_heading = BUCKET_OPTIONS_HEADER,
+        value_name = "NAME=BUCKET[,prefix=PREFIX][,region=REGION][,bucket-type=TYPE]",
+        value_parser = parse_union_source,
     )]
-    pub endpoint_url: Option<String>,
+    pub sources: Vec<UnionSourceArgs>,
 
-    #[clap(long, help = "Force path-style addressing", help_heading = BUCKET_OPTIONS_HEADER)]
-    pub force_path_style: bool,
+    #[clap(flatten)]
+    pub client_args: S3ClientArgs,
 
     #[clap(long, help = "Use S3 Transfer Acceleration when accessing S3. This must be enabled on the bucket.", help_heading = BUCKET_OPTIONS_HEADER)]
     pub transfer_acceleration: bool,
@@ -109,22 +120,9 @@ Learn more in Mountpoint's configuration documentation (CONFIGURATION.md).\
     #[clap(long, help = "Use dual-stack endpoints when accessing S3", help_heading = BUCKET_OPTIONS_HEADER)]
     pub dual_stack: bool,
 
-    #[clap(long, help = "Set the 'x-amz-request-payer' to 'requester' on S3 requests", help_heading = BUCKET_OPTIONS_HEADER)]
-    pub requester_pays: bool,
-
     #[clap(long, help = "Type of S3 bucket to use [default: inferred from bucket name]", help_heading = BUCKET_OPTIONS_HEADER)]
     pub bucket_type: Option<BucketType>,
 
-    #[clap(
-        long,
-        help = "Do not sign requests. Credentials will not be loaded if this argument is provided.",
-        help_heading = AWS_CREDENTIALS_OPTIONS_HEADER
-    )]
-    pub no_sign_request: bool,
-
-    #[clap(long, help = "Use a specific profile from your credential file.", help_heading = AWS_CREDENTIALS_OPTIONS_HEADER)]
-    pub profile: Option<String>,
-
     #[clap(
         long,
         help = "Mount file system in read-only mode",
@@ -135,6 +133,75 @@ Learn more in Mountpoint's configuration documentation (CONFIGURATION.md).\
     #[clap(long, help = "Set the storage class for new objects", help_heading = BUCKET_OPTIONS_HEADER)]
     pub storage_class: Option<String>,
 
+    #[clap(
+        long,
+        help = "Set the canned ACL for new objects",
+        help_heading = BUCKET_OPTIONS_HEADER,
+        value_name = "ACL",
+        value_parser = clap::builder::PossibleValuesParser::new([
+            "private",
+            "public-read",
+            "public-read-write",
+            "authenticated-read",
+            "aws-exec-read",
+            "bucket-owner-read",
+            "bucket-owner-full-control",
+        ]),
+    )]
+    pub acl: Option<String>,
+
+    #[clap(
+        long = "tag",
+        help = "Tag to apply to new objects. Can be specified multiple times",
+        help_heading = BUCKET_OPTIONS_HEADER,
+        value_name = "KEY=VALUE",
+        value_parser = parse_tag,
+    )]
+    pub tags: Vec<(String, String)>,
+
+    #[clap(
+        long,
+        help = "Set the Object Lock retention mode for new objects",
+        help_heading = BUCKET_OPTIONS_HEADER,
+        value_name = "MODE",
+        value_parser = clap::builder::PossibleValuesParser::new(["GOVERNANCE", "COMPLIANCE"]),
+        requires = "object_lock_retain_days",
+    )]
+    pub object_lock_mode: Option<String>,
+
+    #[clap(
+        long,
+        help = "Retain new objects with Object Lock for this many days after they are uploaded",
+        help_heading = BUCKET_OPTIONS_HEADER,
+        value_name = "DAYS",
+        value_parser = value_parser!(u32).range(1..=36500),
+        requires = "object_lock_mode",
+    )]
+    pub object_lock_retain_days: Option<u32>,
+
+    #[clap(
+        long,
+        help = "Place an Object Lock legal hold on new objects",
+        help_heading = BUCKET_OPTIONS_HEADER
+    )]
+    pub object_lock_legal_hold: bool,
+
+    #[clap(
+        long,
+        help = "Set the content type of new objects based on their file extension",
+        help_heading = BUCKET_OPTIONS_HEADER
+    )]
+    pub infer_content_type: bool,
+
+    #[clap(
+        long,
+        help = "File mapping content types to file extensions, in the format of /etc/mime.types, to override the built-in content types",
+        help_heading = BUCKET_OPTIONS_HEADER,
+        requires = "infer_content_type",
+        value_name = "PATH",
+    )]
+    pub content_type_map: Option<PathBuf>,
+
     #[clap(
         long,
         help = "Allow delete operations on file system",
@@ -156,6 +223,119 @@ Learn more in Mountpoint's configuration documentation (CONFIGURATION.md).\
     )]
     pub incremental_upload: bool,
 
+    #[clap(
+        long,
+        help = "Verify files read from start to end against the full object checksum stored in S3",
+        help_heading = MOUNT_OPTIONS_HEADER,
+    )]
+    pub verify_reads: bool,
+
+    #[clap(
+        long,
+        help = "Show the objects in a versioned bucket as they were at this time, in RFC 3339 format (e.g. 2024-11-01T12:00:00Z). Requires --read-only",
+        help_heading = MOUNT_OPTIONS_HEADER,
+        value_name = "TIMESTAMP",
+        value_parser = parse_timestamp,
+        requires = "read_only",
+    )]
+    pub as_of: Option<OffsetDateTime>,
+
+    #[clap(
+        long,
+        help = "Show the versions of each object in a versioned bucket in a read-only <NAME>.versions directory",
+        help_heading = MOUNT_OPTIONS_HEADER,
+    )]
+    pub show_versions: bool,
+
+    #[clap(
+        long,
+        help = "Serve the directory structure and file metadata from this manifest of the objects in the bucket \
+                (a local file or s3://<BUCKET>/<KEY>), rather than listing the bucket. Requires --read-only",
+        help_heading = MOUNT_OPTIONS_HEADER,
+        value_name = "FILE|S3_URI",
+        requires = "read_only",
+        conflicts_with_all = ["as_of", "show_versions"],
+    )]
+    pub manifest: Option<ManifestLocation>,
+
+    #[clap(
+        long,
+        help = "Serve the directory structure and file metadata from the S3 Inventory report described by this \
+                manifest.json file (a local file or s3://<BUCKET>/<KEY>), reading every CSV file it lists. \
+                Requires --read-only",
+        help_heading = MOUNT_OPTIONS_HEADER,
+        value_name = "FILE|S3_URI",
+        requires = "read_only",
+        conflicts_with_all = ["manifest", "as_of", "show_versions"],
+    )]
+    pub inventory_manifest: Option<ManifestLocation>,
+
+    #[clap(
+        long = "include",
+        help = "Only show files whose keys match this glob (or regular expression, if prefixed with `regex:`). \
+                Can be specified multiple times",
+        help_heading = MOUNT_OPTIONS_HEADER,
+        value_name = "PATTERN",
+    )]
+    pub include: Vec<KeyPattern>,
+
+    #[clap(
+        long = "exclude",
+        help = "Hide files and directories whose keys match this glob (or regular expression, if prefixed with \
+                `regex:`). Can be specified multiple times",
+        help_heading = MOUNT_OPTIONS_HEADER,
+        value_name = "PATTERN",
+    )]
+    pub exclude: Vec<KeyPattern>,
+
+    #[clap(
+        long,
+        help = "Show keys that aren't valid file names, such as `a//b` or `a/../b`, under escaped names \
+                (`%` for an empty component, `%2E` for `.`, `%2E%2E` for `..`, and `%25` for `%`)",
+        help_heading = MOUNT_OPTIONS_HEADER,
+    )]
+    pub escape_names: bool,
+
+    #[clap(
+        long,
+        help = "Create a zero-byte `<NAME>/` directory marker object for each new directory, \
+                so that empty directories persist, and delete it when the directory is removed",
+        help_heading = MOUNT_OPTIONS_HEADER,
+    )]
+    pub directory_markers: bool,
+
+    #[clap(
+        long,
+        help = "Expose object tags as `user.s3.tag.<KEY>` extended attributes, along with the `user.s3.restore` \
+                and `user.s3.delete` attributes to restore archived objects and delete directories",
+        help_heading = MOUNT_OPTIONS_HEADER,
+    )]
+    pub enable_xattrs: bool,
+
+    #[clap(
+        long,
+        help = "Derive inode numbers from a hash of the object key, so that a file keeps the same inode number \
+                across remounts",
+        help_heading = MOUNT_OPTIONS_HEADER,
+    )]
+    pub stable_inode_numbers: bool,
+
+    #[clap(
+        long,
+        help = "Support exporting the mount over NFS, so that file handles stay valid after the kernel evicts \
+                inodes from its cache. Implies --stable-inode-numbers",
+        help_heading = MOUNT_OPTIONS_HEADER,
+    )]
+    pub nfs_export: bool,
+
+    #[clap(
+        long,
+        help = "Record uploads in a journal in this directory, so that uploads interrupted by a crash are completed on the next mount",
+        help_heading = MOUNT_OPTIONS_HEADER,
+        value_name = "DIRECTORY",
+    )]
+    pub upload_journal_dir: Option<PathBuf>,
+
     #[clap(long, help = "Automatically unmount on exit", help_heading = MOUNT_OPTIONS_HEADER)]
     pub auto_unmount: bool,
 
@@ -265,15 +445,6 @@ Learn more in Mountpoint's configuration documentation (CONFIGURATION.md).\
     #[clap(short, long, help = "Run as foreground process")]
     pub foreground: bool,
 
-    #[clap(
-        long,
-        help = "Account ID of the expected bucket owner. \
-            If the bucket is owned by a different account, S3 requests fail with an access denied error.",
-        help_heading = BUCKET_OPTIONS_HEADER,
-        value_name = "AWS_ACCOUNT_ID"
-    )]
-    pub expected_bucket_owner: Option<String>,
-
     #[clap(
         short,
         long,
@@ -317,6 +488,43 @@ Learn more in Mountpoint's configuration documentation (CONFIGURATION.md).\
     )]
     pub metadata_ttl: Option<TimeToLive>,
 
+    #[clap(
+        long,
+        help = "Save cached metadata to this file on unmount, and load it on the next mount to start with a warm \
+                metadata cache",
+        value_name = "FILE",
+        help_heading = CACHING_OPTIONS_HEADER,
+    )]
+    pub metadata_cache_file: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "Check the most recently opened files for changes in S3 every this many seconds, and drop the files \
+                that changed from the kernel's caches",
+        value_name = "SECONDS",
+        value_parser = value_parser!(u64).range(1..),
+        help_heading = CACHING_OPTIONS_HEADER,
+    )]
+    pub poll_recent_files: Option<u64>,
+
+    #[clap(
+        long,
+        help = "Keep cached metadata fresh with the S3 event notification messages read from this file or named pipe, \
+                one JSON message per line",
+        value_name = "FILE",
+        help_heading = CACHING_OPTIONS_HEADER,
+        conflicts_with_all = ["as_of", "manifest", "inventory_manifest"],
+    )]
+    pub change_feed: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "Keep the file content cached by the kernel when a file is opened again, if its object has the same \
+                ETag as when the file was last opened",
+        help_heading = CACHING_OPTIONS_HEADER,
+    )]
+    pub keep_page_cache: bool,
+
     #[clap(
         long,
         help = "Maximum size of the cache directory in MiB [default: preserve 5% of available space]",
@@ -372,6 +580,15 @@ Learn more in Mountpoint's configuration documentation (CONFIGURATION.md).\
     )]
     pub sse_kms_key_id: Option<String>,
 
+    #[clap(
+        long,
+        help = "File containing a 256-bit key to use for server-side encryption with customer-provided keys (SSE-C) when reading and writing objects",
+        help_heading = BUCKET_OPTIONS_HEADER,
+        conflicts_with_all = ["sse", "cache_xz"],
+        value_name = "PATH",
+    )]
+    pub sse_customer_key_file: Option<PathBuf>,
+
     #[clap(
         long,
         help = "Checksum algorithm to use for S3 uploads [default: crc32c]",
@@ -420,23 +637,81 @@ impl ValueEnum for BucketType {
 #[derive(Debug, Clone, Copy)]
 pub enum UploadChecksums {
     Crc32c,
+    Crc32,
+    Crc64nvme,
+    Sha1,
+    Sha256,
     Off,
 }
 
 impl ValueEnum for UploadChecksums {
     fn value_variants<'a>() -> &'a [Self] {
-        &[Self::Crc32c, Self::Off]
+        &[
+            Self::Crc32c,
+            Self::Crc32,
+            Self::Crc64nvme,
+            Self::Sha1,
+            Self::Sha256,
+            Self::Off,
+        ]
     }
 
     fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
         match self {
             Self::Crc32c => Some(clap::builder::PossibleValue::new("crc32c")),
+            Self::Crc32 => Some(clap::builder::PossibleValue::new("crc32")),
+            Self::Crc64nvme => Some(clap::builder::PossibleValue::new("crc64nvme")),
+            Self::Sha1 => Some(clap::builder::PossibleValue::new("sha1")),
+            Self::Sha256 => Some(clap::builder::PossibleValue::new("sha256")),
             Self::Off => Some(clap::builder::PossibleValue::new("off")),
         }
     }
 }
 
-impl CliArgs {
+/// Arguments to connect to a bucket, shared by `mount-s3` and its subcommands.
+#[derive(Args, Debug, Clone)]
+pub struct S3ClientArgs {
+    #[clap(
+        long,
+        help = "AWS region of the bucket [default: auto-detect region]",
+        help_heading = BUCKET_OPTIONS_HEADER
+    )]
+    pub region: Option<String>,
+
+    #[clap(
+        long,
+        help = "S3 endpoint URL [default: auto-detect endpoint]",
+        help_heading = BUCKET_OPTIONS_HEADER
+    )]
+    pub endpoint_url: Option<String>,
+
+    #[clap(long, help = "Force path-style addressing", help_heading = BUCKET_OPTIONS_HEADER)]
+    pub force_path_style: bool,
+
+    #[clap(long, help = "Set the 'x-amz-request-payer' to 'requester' on S3 requests", help_heading = BUCKET_OPTIONS_HEADER)]
+    pub requester_pays: bool,
+
+    #[clap(
+        long,
+        help = "Account ID of the expected bucket owner. \
+            If the bucket is owned by a different account, S3 requests fail with an access denied error.",
+        help_heading = BUCKET_OPTIONS_HEADER,
+        value_name = "AWS_ACCOUNT_ID"
+    )]
+    pub expected_bucket_owner: Option<String>,
+
+    #[clap(
+        long,
+        help = "Do not sign requests. Credentials will not be loaded if this argument is provided.",
+        help_heading = AWS_CREDENTIALS_OPTIONS_HEADER
+    )]
+    pub no_sign_request: bool,
+
+    #[clap(long, help = "Use a specific profile from your credential file.", help_heading = AWS_CREDENTIALS_OPTIONS_HEADER)]
+    pub profile: Option<String>,
+}
+
+impl S3ClientArgs {
     fn addressing_style(&self) -> AddressingStyle {
         if self.force_path_style {
             AddressingStyle::Path
@@ -445,6 +720,63 @@ impl CliArgs {
         }
     }
 
+    /// Create a client for `bucket` with the given configuration, after applying these arguments to
+    /// it. The region and endpoint are detected if they were not given.
+    fn create_client(
+        &self,
+        bucket: &str,
+        prefix: &Prefix,
+        endpoint_config: EndpointConfig,
+        mut client_config: S3ClientConfig,
+        instance_info: &InstanceInfo,
+    ) -> anyhow::Result<S3CrtClient> {
+        let auth_config = if self.no_sign_request {
+            S3ClientAuthConfig::NoSigning
+        } else if let Some(profile_name) = &self.profile {
+            S3ClientAuthConfig::Profile(profile_name.to_owned())
+        } else {
+            S3ClientAuthConfig::Default
+        };
+        client_config = client_config.auth_config(auth_config);
+        if self.requester_pays {
+            client_config = client_config.request_payer("requester");
+        }
+        if let Some(owner) = &self.expected_bucket_owner {
+            client_config = client_config.bucket_owner(owner);
+        }
+        // Transient errors are really bad for file systems (applications don't usually expect them), so
+        // let's be more stubborn than the SDK default. With the CRT defaults of 500ms backoff, full
+        // jitter, and 20s max backoff time, 10 attempts will take an average of 55 seconds.
+        client_config = client_config.max_attempts(NonZeroUsize::new(10).unwrap());
+
+        create_client_for_bucket(
+            bucket,
+            prefix,
+            self.region.clone(),
+            self.endpoint_url.clone(),
+            endpoint_config,
+            client_config,
+            instance_info,
+        )
+        .context("Failed to create S3 client")
+    }
+}
+
+/// Another bucket to mount as a directory of a union mount, from a `--source` argument
+#[derive(Debug, Clone)]
+pub struct UnionSourceArgs {
+    pub name: String,
+    pub bucket_name: String,
+    pub prefix: Option<Prefix>,
+    pub region: Option<String>,
+    pub bucket_type: Option<BucketType>,
+}
+
+impl CliArgs {
+    fn addressing_style(&self) -> AddressingStyle {
+        self.client_args.addressing_style()
+    }
+
     fn prefix(&self) -> Prefix {
         self.prefix.as_ref().cloned().unwrap_or_default()
     }
@@ -527,6 +859,14 @@ impl CliArgs {
 
     /// Human-readable description of the bucket being mounted
     fn bucket_description(&self) -> String {
+        if !self.sources.is_empty() {
+            let sources = self
+                .union_source_args()
+                .iter()
+                .map(|(name, args)| format!("{} as {}", args.bucket_description(), name))
+                .collect::<Vec<_>>();
+            return format!("union of {}", sources.join(", "));
+        }
         if let Some(prefix) = self.prefix.as_ref() {
             format!("prefix {} of bucket {}", prefix, self.bucket_name)
         } else {
@@ -534,6 +874,28 @@ impl CliArgs {
         }
     }
 
+    /// The name and arguments of each source of a union mount, starting with the bucket given as
+    /// the first argument and named after it.
+    fn union_source_args(&self) -> Vec<(String, CliArgs)> {
+        let mut first = self.clone();
+        first.sources.clear();
+        let mut sources = vec![(self.bucket_name.clone(), first)];
+        for source in &self.sources {
+            let mut args = self.clone();
+            args.sources.clear();
+            args.bucket_name = source.bucket_name.clone();
+            args.prefix = source.prefix.clone();
+            if source.region.is_some() {
+                args.client_args.region = source.region.clone();
+            }
+            if source.bucket_type.is_some() {
+                args.bucket_type = source.bucket_type.clone();
+            }
+            sources.push((source.name.clone(), args));
+        }
+        sources
+    }
+
     fn fuse_session_config(&self) -> anyhow::Result<FuseSessionConfig> {
         let mount_point = MountPoint::new(&self.mount_point).context("Failed to create mount point")?;
         let fs_name = String::from("mountpoint-s3");
@@ -581,13 +943,47 @@ impl CliArgs {
     }
 }
 
-pub fn main<ClientBuilder, Client, Runtime>(client_builder: ClientBuilder) -> anyhow::Result<()>
+/// What `mount-s3` was invoked to do: mount a bucket, or run one of its subcommands.
+#[derive(Debug)]
+pub enum Command {
+    Mount(Box<CliArgs>),
+    ListUploads(ListUploadsArgs),
+}
+
+impl Command {
+    /// Parse the command line arguments, exiting on error.
+    pub fn parse() -> Self {
+        Self::try_parse_from(env::args_os()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// Parse the given arguments. Mounting a bucket takes the arguments of [CliArgs] directly, so
+    /// a bucket named like a subcommand must be preceded by `--`.
+    pub fn try_parse_from<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let mut command = CliArgs::command()
+            .subcommand(ListUploadsArgs::command())
+            .args_conflicts_with_subcommands(true)
+            .subcommand_negates_reqs(true);
+        let matches = command.try_get_matches_from_mut(args)?;
+        let parsed = match matches.subcommand() {
+            Some((list_uploads::COMMAND_NAME, matches)) => {
+                ListUploadsArgs::from_arg_matches(matches).map(Self::ListUploads)
+            }
+            _ => CliArgs::from_arg_matches(&matches).map(|args| Self::Mount(Box::new(args))),
+        };
+        parsed.map_err(|e| e.format(&mut command))
+    }
+}
+
+pub fn main<ClientBuilder, Client, Runtime>(args: CliArgs, client_builder: ClientBuilder) -> anyhow::Result<()>
 where
-    ClientBuilder: FnOnce(&CliArgs) -> anyhow::Result<(Client, Runtime, S3Personality)>,
+    ClientBuilder: Fn(&CliArgs) -> anyhow::Result<(Client, Runtime, S3Personality)>,
     Client: ObjectClient + Clone + Send + Sync + 'static,
     Runtime: Spawn + Clone + Send + Sync + 'static,
 {
-    let args = CliArgs::parse();
     let successful_mount_msg = format!(
         "{} is mounted at {}",
         args.bucket_description(),
@@ -753,14 +1149,6 @@ pub fn create_s3_client(args: &CliArgs) -> anyhow::Result<(S3CrtClient, EventLoo
     });
     tracing::info!("target network throughput {throughput_target_gbps} Gbps");
 
-    let auth_config = if args.no_sign_request {
-        S3ClientAuthConfig::NoSigning
-    } else if let Some(profile_name) = &args.profile {
-        S3ClientAuthConfig::Profile(profile_name.to_owned())
-    } else {
-        S3ClientAuthConfig::Default
-    };
-
     let user_agent_prefix = if let Some(custom_prefix) = &args.user_agent_prefix {
         format!("{} mountpoint-s3/{}", custom_prefix, build_info::FULL_VERSION)
     } else {
@@ -803,7 +1191,6 @@ pub fn create_s3_client(args: &CliArgs) -> anyhow::Result<(S3CrtClient, EventLoo
     // but this is the first window size we prefer.
     let initial_read_window_size = 1024 * 1024 + 128 * 1024;
     let mut client_config = S3ClientConfig::new()
-        .auth_config(auth_config)
         .throughput_target_gbps(throughput_target_gbps)
         .read_part_size(args.read_part_size.unwrap_or(args.part_size) as usize)
         .write_part_size(args.write_part_size.unwrap_or(args.part_size) as usize)
@@ -813,27 +1200,14 @@ pub fn create_s3_client(args: &CliArgs) -> anyhow::Result<(S3CrtClient, EventLoo
     if let Some(interfaces) = &args.bind {
         client_config = client_config.network_interface_names(interfaces.clone());
     }
-    if args.requester_pays {
-        client_config = client_config.request_payer("requester");
-    }
-    if let Some(owner) = &args.expected_bucket_owner {
-        client_config = client_config.bucket_owner(owner);
-    }
-    // Transient errors are really bad for file systems (applications don't usually expect them), so
-    // let's be more stubborn than the SDK default. With the CRT defaults of 500ms backoff, full
-    // jitter, and 20s max backoff time, 10 attempts will take an average of 55 seconds.
-    client_config = client_config.max_attempts(NonZeroUsize::new(10).unwrap());
 
-    let client = create_client_for_bucket(
+    let client = args.client_args.create_client(
         &args.bucket_name,
         &args.prefix(),
-        args.region.clone(),
-        args.endpoint_url.clone(),
         endpoint_config,
         client_config,
         &instance_info,
-    )
-    .context("Failed to create S3 client")?;
+    )?;
     let runtime = client.event_loop_group();
     let s3_personality = infer_s3_personality(args.bucket_type.clone(), &args.bucket_name, client.endpoint_config());
 
@@ -853,7 +1227,7 @@ fn create_disk_cache(
 
 fn mount<ClientBuilder, Client, Runtime>(args: CliArgs, client_builder: ClientBuilder) -> anyhow::Result<FuseSession>
 where
-    ClientBuilder: FnOnce(&CliArgs) -> anyhow::Result<(Client, Runtime, S3Personality)>,
+    ClientBuilder: Fn(&CliArgs) -> anyhow::Result<(Client, Runtime, S3Personality)>,
     Client: ObjectClient + Clone + Send + Sync + 'static,
     Runtime: Spawn + Clone + Send + Sync + 'static,
 {
@@ -863,11 +1237,14 @@ where
     let fuse_config = args.fuse_session_config()?;
 
     validate_sse_args(args.sse.as_deref(), args.sse_kms_key_id.as_deref())?;
-
-    let (client, runtime, s3_personality) = client_builder(&args)?;
-
-    let bucket_description = args.bucket_description();
-    tracing::debug!("using S3 personality {s3_personality:?} for {bucket_description}");
+    let sse_customer_key = args
+        .sse_customer_key_file
+        .as_deref()
+        .map(read_sse_customer_key)
+        .transpose()?;
+    let object_tags = object_tags_from_args(&args.tags)?;
+    let object_lock = object_lock_from_args(&args)?;
+    let content_types = content_types_from_args(&args)?;
 
     let mut filesystem_config = S3FilesystemConfig::default();
     if let Some(uid) = args.uid {
@@ -883,11 +1260,31 @@ where
         filesystem_config.file_mode = file_mode;
     }
     filesystem_config.storage_class = args.storage_class.clone();
+    filesystem_config.acl = args.acl.clone();
+    filesystem_config.object_tags = object_tags;
+    filesystem_config.object_lock = object_lock;
+    filesystem_config.content_types = content_types;
     filesystem_config.allow_delete = args.allow_delete;
     filesystem_config.allow_overwrite = args.allow_overwrite;
     filesystem_config.incremental_upload = args.incremental_upload;
-    filesystem_config.s3_personality = s3_personality;
-    filesystem_config.server_side_encryption = ServerSideEncryption::new(args.sse.clone(), args.sse_kms_key_id.clone());
+    filesystem_config.as_of = args.as_of;
+    filesystem_config.show_versions = args.show_versions;
+    filesystem_config.key_filter = KeyFilter::new(args.include.clone(), args.exclude.clone());
+    filesystem_config.escape_names = args.escape_names;
+    filesystem_config.directory_markers = args.directory_markers;
+    filesystem_config.enable_xattrs = args.enable_xattrs;
+    filesystem_config.stable_inode_numbers = args.stable_inode_numbers;
+    filesystem_config.nfs_export = args.nfs_export;
+    filesystem_config.metadata_cache_file = args.metadata_cache_file.clone();
+    filesystem_config.poll_recent_files = args.poll_recent_files.map(Duration::from_secs);
+    if let Some(path) = &args.change_feed {
+        // The feed is only read in the background, so check that it's there before mounting
+        validate_change_feed(path)?;
+    }
+    filesystem_config.change_feed = args.change_feed.clone();
+    filesystem_config.keep_page_cache = args.keep_page_cache;
+    filesystem_config.server_side_encryption = ServerSideEncryption::new(args.sse.clone(), args.sse_kms_key_id.clone())
+        .with_customer_key(sse_customer_key.clone());
 
     let sys = System::new_with_specifics(RefreshKind::everything());
     let default_mem_target = (sys.total_memory() as f64 * 0.95) as u64;
@@ -898,18 +1295,6 @@ where
         filesystem_config.mem_limit = max_mem_target * 1024 * 1024;
     }
 
-    // Written in this awkward way to force us to update it if we add new checksum types
-    filesystem_config.use_upload_checksums = match args.upload_checksums {
-        Some(UploadChecksums::Crc32c) | None => true,
-        Some(UploadChecksums::Off) => false,
-    };
-    if !s3_personality.supports_additional_checksums() && args.upload_checksums.is_none() {
-        tracing::info!("disabling upload checksums because target S3 personality does not support them");
-        filesystem_config.use_upload_checksums = false;
-    }
-
-    let prefetcher_config = Default::default();
-
     let mut metadata_cache_ttl = args.metadata_ttl.unwrap_or_else(|| {
         if args.cache.is_some() || args.cache_express_bucket_name().is_some() {
             // When the data cache is enabled, use 1min as metadata-ttl.
@@ -935,6 +1320,40 @@ where
     tracing::trace!("using metadata TTL setting {metadata_cache_ttl:?}");
     filesystem_config.cache_config = CacheConfig::new(metadata_cache_ttl);
 
+    let prefetcher_config = PrefetcherConfig {
+        verify_reads: args.verify_reads,
+        ..Default::default()
+    };
+
+    if !args.sources.is_empty() {
+        return mount_union(args, client_builder, filesystem_config, prefetcher_config, fuse_config);
+    }
+
+    let (client, runtime, s3_personality) = client_builder(&args)?;
+
+    let bucket_description = args.bucket_description();
+    tracing::debug!("using S3 personality {s3_personality:?} for {bucket_description}");
+    configure_s3_personality(&mut filesystem_config, &args, s3_personality, &bucket_description)?;
+
+    if let Some(journal_dir) = &args.upload_journal_dir {
+        let upload_journal = UploadJournal::new(journal_dir).context("failed to create upload journal directory")?;
+        let mem_limiter = MemoryLimiter::new(client.clone(), filesystem_config.mem_limit);
+        block_on(upload_journal.replay(&client, &args.bucket_name, sse_customer_key.as_ref(), &mem_limiter))
+            .context("failed to replay upload journal")?;
+        filesystem_config.upload_journal = Some(upload_journal);
+    }
+
+    if let Some(location) = &args.manifest {
+        let manifest = block_on(Manifest::load(&client, location)).context("failed to load manifest")?;
+        tracing::info!(objects = manifest.len(), "loaded manifest from {location:?}");
+        filesystem_config.manifest = Some(manifest.into());
+    } else if let Some(location) = &args.inventory_manifest {
+        let manifest =
+            block_on(Manifest::load_inventory(&client, location)).context("failed to load S3 Inventory report")?;
+        tracing::info!(objects = manifest.len(), "loaded S3 Inventory report from {location:?}");
+        filesystem_config.manifest = Some(manifest.into());
+    }
+
     match (args.disk_data_cache_config(), args.express_data_cache_config()) {
         (None, Some((config, bucket_name, cache_bucket_name))) => {
             tracing::trace!("using S3 Express One Zone bucket as a cache for object content");
@@ -951,7 +1370,7 @@ where
                 &args.prefix.unwrap_or_default(),
                 filesystem_config,
             );
-            create_fuse_session(fs, fuse_config, &bucket_description)
+            create_fuse_session(S3FuseFilesystem::new(fs), fuse_config, &bucket_description)
         }
         (Some((disk_data_cache_config, cache_dir_path)), None) => {
             tracing::trace!("using local disk as a cache for object content");
@@ -966,7 +1385,7 @@ where
                 &args.prefix.unwrap_or_default(),
                 filesystem_config,
             );
-            let mut fuse_session = create_fuse_session(fs, fuse_config, &bucket_description)?;
+            let mut fuse_session = create_fuse_session(S3FuseFilesystem::new(fs), fuse_config, &bucket_description)?;
             fuse_session.run_on_close(Box::new(move || {
                 drop(managed_cache_dir);
             }));
@@ -989,7 +1408,7 @@ where
                 &args.prefix.unwrap_or_default(),
                 filesystem_config,
             );
-            let mut fuse_session = create_fuse_session(fs, fuse_config, &bucket_description)?;
+            let mut fuse_session = create_fuse_session(S3FuseFilesystem::new(fs), fuse_config, &bucket_description)?;
             fuse_session.run_on_close(Box::new(move || {
                 drop(managed_cache_dir);
             }));
@@ -1006,11 +1425,112 @@ where
                 &args.prefix.unwrap_or_default(),
                 filesystem_config,
             );
-            create_fuse_session(fs, fuse_config, &bucket_description)
+            create_fuse_session(S3FuseFilesystem::new(fs), fuse_config, &bucket_description)
         }
     }
 }
 
+/// Mount each of the sources in `args` as a directory of a union mount.
+fn mount_union<ClientBuilder, Client, Runtime>(
+    args: CliArgs,
+    client_builder: ClientBuilder,
+    filesystem_config: S3FilesystemConfig,
+    prefetcher_config: PrefetcherConfig,
+    fuse_config: FuseSessionConfig,
+) -> anyhow::Result<FuseSession>
+where
+    ClientBuilder: Fn(&CliArgs) -> anyhow::Result<(Client, Runtime, S3Personality)>,
+    Client: ObjectClient + Clone + Send + Sync + 'static,
+    Runtime: Spawn + Clone + Send + Sync + 'static,
+{
+    if args.cache.is_some() || args.cache_express_bucket_name().is_some() {
+        return Err(anyhow!("caching object content is not supported with --source"));
+    }
+    if args.upload_journal_dir.is_some() {
+        return Err(anyhow!("--upload-journal-dir is not supported with --source"));
+    }
+    if args.metadata_cache_file.is_some() {
+        return Err(anyhow!("--metadata-cache-file is not supported with --source"));
+    }
+    if args.manifest.is_some() || args.inventory_manifest.is_some() {
+        return Err(anyhow!(
+            "--manifest and --inventory-manifest are not supported with --source"
+        ));
+    }
+    if args.change_feed.is_some() {
+        return Err(anyhow!("--change-feed is not supported with --source"));
+    }
+
+    let source_args = args.union_source_args();
+    if source_args.len() > MAX_UNION_SOURCES {
+        return Err(anyhow!("at most {} sources can be mounted", MAX_UNION_SOURCES));
+    }
+    for (i, (name, _)) in source_args.iter().enumerate() {
+        if source_args[..i].iter().any(|(other, _)| other == name) {
+            return Err(anyhow!("more than one source is named {name}"));
+        }
+    }
+
+    // The sources share the memory limit
+    let mem_limit = (filesystem_config.mem_limit / source_args.len() as u64).max(MINIMUM_MEM_LIMIT);
+
+    let mut sources = Vec::with_capacity(source_args.len());
+    for (name, args) in source_args {
+        let (client, runtime, s3_personality) = client_builder(&args)?;
+
+        let bucket_description = args.bucket_description();
+        tracing::debug!("using S3 personality {s3_personality:?} for {bucket_description} in source {name}");
+        let mut filesystem_config = filesystem_config.clone();
+        filesystem_config.mem_limit = mem_limit;
+        filesystem_config.inode_number_namespace = name.clone();
+        configure_s3_personality(&mut filesystem_config, &args, s3_personality, &bucket_description)?;
+
+        let prefetcher = default_prefetch(runtime.clone(), prefetcher_config);
+        let fs = create_filesystem(
+            client,
+            prefetcher,
+            runtime,
+            &args.bucket_name,
+            &args.prefix(),
+            filesystem_config,
+        );
+        sources.push(UnionSource::new(&name, fs));
+    }
+
+    let fuse_fs = S3FuseFilesystem::new_union(UnionFilesystem::new(sources));
+    create_fuse_session(fuse_fs, fuse_config, &args.bucket_description())
+}
+
+/// Apply the settings of the file system that depend on the S3 personality of the bucket.
+fn configure_s3_personality(
+    filesystem_config: &mut S3FilesystemConfig,
+    args: &CliArgs,
+    s3_personality: S3Personality,
+    bucket_description: &str,
+) -> anyhow::Result<()> {
+    filesystem_config.s3_personality = s3_personality;
+
+    // Written in this awkward way to force us to update it if we add new checksum types
+    filesystem_config.upload_checksum_algorithm = match args.upload_checksums {
+        Some(UploadChecksums::Crc32c) | None => Some(ChecksumAlgorithm::Crc32c),
+        Some(UploadChecksums::Crc32) => Some(ChecksumAlgorithm::Crc32),
+        Some(UploadChecksums::Crc64nvme) => Some(ChecksumAlgorithm::Crc64nvme),
+        Some(UploadChecksums::Sha1) => Some(ChecksumAlgorithm::Sha1),
+        Some(UploadChecksums::Sha256) => Some(ChecksumAlgorithm::Sha256),
+        Some(UploadChecksums::Off) => None,
+    };
+    if !s3_personality.supports_additional_checksums() && args.upload_checksums.is_none() {
+        tracing::info!("disabling upload checksums because target S3 personality does not support them");
+        filesystem_config.upload_checksum_algorithm = None;
+    }
+    if !filesystem_config.object_lock.is_empty() && filesystem_config.upload_checksum_algorithm.is_none() {
+        return Err(anyhow!(
+            "Object Lock options require upload checksums, which are not supported by {bucket_description}"
+        ));
+    }
+    Ok(())
+}
+
 fn create_filesystem<Client, Prefetcher, Runtime>(
     client: Client,
     prefetcher: Prefetcher,
@@ -1029,7 +1549,7 @@ where
 }
 
 fn create_fuse_session<Client, Prefetcher>(
-    fs: S3Filesystem<Client, Prefetcher>,
+    fuse_fs: S3FuseFilesystem<Client, Prefetcher>,
     fuse_session_config: FuseSessionConfig,
     bucket_description: &str,
 ) -> anyhow::Result<FuseSession>
@@ -1037,9 +1557,9 @@ where
     Client: ObjectClient + Clone + Send + Sync + 'static,
     Prefetcher: Prefetch + Send + Sync + 'static,
 {
-    let fuse_fs = S3FuseFilesystem::new(fs);
     tracing::debug!(?fuse_session_config, "creating fuse session");
     let mount_point_path = format!("{}", &fuse_session_config.mount_point);
+    let invalidations = fuse_fs.invalidations();
     let session = match fuse_session_config.mount_point {
         MountPoint::Directory(path) => {
             Session::new(fuse_fs, path, &fuse_session_config.options).context("Failed to create FUSE session")?
@@ -1051,6 +1571,7 @@ where
             session_acl_from_mount_options(&fuse_session_config.options),
         ),
     };
+    send_invalidations(invalidations, session.notifier()).context("Failed to start kernel cache invalidation")?;
     let session = FuseSession::new(session, fuse_session_config.max_threads).context("Failed to start FUSE session")?;
 
     tracing::info!("successfully mounted {} at {}", bucket_description, mount_point_path);
@@ -1352,6 +1873,19 @@ fn infer_s3_personality(
 /// Disallow specifying `--sse-kms-key-id` when `--sse=AES256` as this is not allowed by the S3 API.
 /// We are not able to perform this check via clap API (the closest it has is `conflicts_with` method),
 /// thus having a custom validation.
+/// Check that a change feed exists, can be read, and is a file or named pipe.
+fn validate_change_feed(path: &Path) -> anyhow::Result<()> {
+    nix::unistd::access(path, nix::unistd::AccessFlags::R_OK)
+        .with_context(|| format!("cannot read change feed {path:?}"))?;
+    let file_type = std::fs::metadata(path)
+        .with_context(|| format!("cannot read change feed {path:?}"))?
+        .file_type();
+    if !file_type.is_file() && !file_type.is_fifo() {
+        return Err(anyhow!("change feed {path:?} must be a file or a named pipe"));
+    }
+    Ok(())
+}
+
 fn validate_sse_args(sse_type: Option<&str>, sse_kms_key_id: Option<&str>) -> anyhow::Result<()> {
     if sse_kms_key_id.is_some() && sse_type == Some("AES256") {
         Err(anyhow!("--sse-kms-key-id can not be used with --sse AES256"))
@@ -1360,6 +1894,117 @@ fn validate_sse_args(sse_type: Option<&str>, sse_kms_key_id: Option<&str>) -> an
     }
 }
 
+/// Read the SSE-C key given with `--sse-customer-key-file`. The file must contain exactly the 32 raw bytes of the key.
+fn read_sse_customer_key(path: &Path) -> anyhow::Result<SseCustomerKey> {
+    let key = std::fs::read(path).with_context(|| format!("failed to read SSE-C key from {path:?}"))?;
+    SseCustomerKey::new(&key).with_context(|| format!("invalid SSE-C key in {path:?}"))
+}
+
+/// Parse an `--as-of` CLI parameter as an RFC 3339 timestamp.
+fn parse_timestamp(timestamp: &str) -> anyhow::Result<OffsetDateTime> {
+    OffsetDateTime::parse(timestamp, &Rfc3339)
+        .map_err(|_| anyhow!("timestamps must be in RFC 3339 format, for example 2024-11-01T12:00:00Z"))
+}
+
+/// Parse a `--tag` CLI parameter of the form `KEY=VALUE`. The value may be empty, but the key may not.
+fn parse_tag(tag: &str) -> anyhow::Result<(String, String)> {
+    let (key, value) = tag
+        .split_once('=')
+        .ok_or_else(|| anyhow!("tags must be of the form KEY=VALUE"))?;
+    if key.is_empty() || key.chars().count() > MAX_TAG_KEY_LENGTH {
+        return Err(anyhow!("tag keys must be 1-{MAX_TAG_KEY_LENGTH} characters long"));
+    }
+    if value.chars().count() > MAX_TAG_VALUE_LENGTH {
+        return Err(anyhow!(
+            "tag values must be at most {MAX_TAG_VALUE_LENGTH} characters long"
+        ));
+    }
+    Ok((key.to_owned(), value.to_owned()))
+}
+
+fn parse_union_source(source: &str) -> anyhow::Result<UnionSourceArgs> {
+    let (name, rest) = source
+        .split_once('=')
+        .ok_or_else(|| anyhow!("sources must be of the form NAME=BUCKET[,OPTION=VALUE]..."))?;
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\0']) {
+        return Err(anyhow!("source name {name:?} is not a valid directory name"));
+    }
+    let mut options = rest.split(',');
+    let bucket_name = parse_bucket_name(options.next().unwrap_or_default())?;
+    let mut source = UnionSourceArgs {
+        name: name.to_owned(),
+        bucket_name,
+        prefix: None,
+        region: None,
+        bucket_type: None,
+    };
+    for option in options {
+        let (key, value) = option
+            .split_once('=')
+            .ok_or_else(|| anyhow!("source options must be of the form OPTION=VALUE"))?;
+        match key {
+            "prefix" => source.prefix = Some(Prefix::new(value).context("invalid source prefix")?),
+            "region" => source.region = Some(value.to_owned()),
+            "bucket-type" => {
+                let bucket_type =
+                    BucketType::from_str(value, false).map_err(|e| anyhow!("invalid bucket type: {e}"))?;
+                source.bucket_type = Some(bucket_type);
+            }
+            _ => return Err(anyhow!("unknown source option {key:?}")),
+        }
+    }
+    Ok(source)
+}
+
+/// Collect the `--tag` CLI parameters, rejecting duplicate keys and more tags than S3 allows on an object.
+fn object_tags_from_args(tags: &[(String, String)]) -> anyhow::Result<ObjectTags> {
+    if tags.len() > MAX_TAGS_PER_OBJECT {
+        return Err(anyhow!(
+            "at most {MAX_TAGS_PER_OBJECT} tags can be specified with --tag"
+        ));
+    }
+    let mut object_tags = ObjectTags::new();
+    for (key, value) in tags {
+        if object_tags.insert(key.clone(), value.clone()).is_some() {
+            return Err(anyhow!("tag key {key:?} was specified more than once"));
+        }
+    }
+    Ok(object_tags)
+}
+
+/// Build the Object Lock settings for new objects from the `--object-lock-*` CLI parameters.
+///
+/// S3 requires a checksum on every request that sets Object Lock, so these options are incompatible with
+/// `--upload-checksums off`.
+fn object_lock_from_args(args: &CliArgs) -> anyhow::Result<ObjectLock> {
+    let mut object_lock = ObjectLock::new().legal_hold(args.object_lock_legal_hold);
+    if let (Some(mode), Some(days)) = (&args.object_lock_mode, args.object_lock_retain_days) {
+        let retain_until_date = OffsetDateTime::now_utc() + time::Duration::days(days.into());
+        object_lock = object_lock.retention(mode.clone(), retain_until_date);
+    }
+    if !object_lock.is_empty() && matches!(args.upload_checksums, Some(UploadChecksums::Off)) {
+        return Err(anyhow!(
+            "--object-lock-mode and --object-lock-legal-hold can not be used with --upload-checksums off"
+        ));
+    }
+    Ok(object_lock)
+}
+
+/// Build the map used to infer content types from the `--infer-content-type` and `--content-type-map` CLI parameters.
+fn content_types_from_args(args: &CliArgs) -> anyhow::Result<Option<ContentTypeMap>> {
+    if !args.infer_content_type {
+        return Ok(None);
+    }
+    let content_types = ContentTypeMap::new();
+    let Some(path) = &args.content_type_map else {
+        return Ok(Some(content_types));
+    };
+    let content_types = content_types
+        .with_mapping_file(path)
+        .with_context(|| format!("invalid content type mapping file {path:?}"))?;
+    Ok(Some(content_types))
+}
+
 /// Parses file descriptor from given mount point.
 /// The syntax for passing file descriptors as mount points is "/dev/fd/N",
 /// and this function basically returns "N".
@@ -1426,6 +2071,106 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_validate_change_feed() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("feed.jsonl");
+        std::fs::write(&file, "").unwrap();
+        let pipe = dir.path().join("feed");
+        nix::unistd::mkfifo(&pipe, nix::sys::stat::Mode::S_IRWXU).unwrap();
+
+        validate_change_feed(&file).expect("files can be change feeds");
+        validate_change_feed(&pipe).expect("named pipes can be change feeds");
+        validate_change_feed(&dir.path().join("missing")).expect_err("missing feeds are rejected");
+        validate_change_feed(dir.path()).expect_err("directories are rejected");
+    }
+
+    #[test]
+    fn test_parse_command() {
+        let command = Command::try_parse_from(["mount-s3", "test-bucket", "/mnt"]).unwrap();
+        assert!(matches!(command, Command::Mount(args) if args.bucket_name == "test-bucket"));
+
+        let command = Command::try_parse_from(["mount-s3", "list-uploads", "test-bucket", "--abort"]).unwrap();
+        assert!(matches!(command, Command::ListUploads(args) if args.bucket_name == "test-bucket" && args.abort));
+
+        // The subcommand takes the same arguments to connect to the bucket
+        let command = Command::try_parse_from([
+            "mount-s3",
+            "list-uploads",
+            "test-bucket",
+            "--region",
+            "us-west-2",
+            "--no-sign-request",
+        ])
+        .unwrap();
+        assert!(matches!(command, Command::ListUploads(args)
+            if args.client_args.region.as_deref() == Some("us-west-2") && args.client_args.no_sign_request));
+
+        // A bucket named like the subcommand can only be mounted after `--`
+        let command = Command::try_parse_from(["mount-s3", "--", "list-uploads", "/mnt"]).unwrap();
+        assert!(matches!(command, Command::Mount(args) if args.bucket_name == "list-uploads"));
+        let result = Command::try_parse_from(["mount-s3", "list-uploads", "/mnt"]);
+        assert!(
+            !matches!(result, Ok(Command::Mount(_))),
+            "the bucket name should be parsed as the subcommand"
+        );
+    }
+
+    #[test_case("project=blue", Some(("project", "blue")); "simple tag")]
+    #[test_case("project=", Some(("project", "")); "empty value")]
+    #[test_case("expr=a=b", Some(("expr", "a=b")); "value containing =")]
+    #[test_case("=blue", None; "empty key")]
+    #[test_case("project", None; "missing value")]
+    fn test_parse_tag(tag: &str, expected: Option<(&str, &str)>) {
+        let parsed = parse_tag(tag);
+        match expected {
+            Some((key, value)) => assert_eq!(parsed.expect("valid tag"), (key.to_owned(), value.to_owned())),
+            None => {
+                parsed.expect_err("invalid tag");
+            }
+        }
+    }
+
+    #[test]
+    fn test_object_tags_from_args() {
+        let tags = vec![("a".to_owned(), "1".to_owned()), ("b".to_owned(), "2".to_owned())];
+        let object_tags = object_tags_from_args(&tags).expect("valid tags");
+        assert_eq!(object_tags.len(), 2);
+        assert_eq!(object_tags.get("b").map(String::as_str), Some("2"));
+
+        let duplicate = vec![("a".to_owned(), "1".to_owned()), ("a".to_owned(), "2".to_owned())];
+        object_tags_from_args(&duplicate).expect_err("duplicate tag keys");
+
+        let too_many: Vec<_> = (0..=MAX_TAGS_PER_OBJECT)
+            .map(|i| (i.to_string(), String::new()))
+            .collect();
+        object_tags_from_args(&too_many).expect_err("too many tags");
+    }
+
+    #[test_case("raw=raw-bucket", Some(("raw", "raw-bucket", "")); "bucket")]
+    #[test_case("curated=curated-bucket,prefix=tables/", Some(("curated", "curated-bucket", "tables/")); "bucket and prefix")]
+    #[test_case("fast=fast--usw2-az1--x-s3,region=us-west-2,bucket-type=directory", Some(("fast", "fast--usw2-az1--x-s3", "")); "client options")]
+    #[test_case("raw-bucket", None; "missing name")]
+    #[test_case("a/b=raw-bucket", None; "name with slash")]
+    #[test_case("..=raw-bucket", None; "dot dot name")]
+    #[test_case("raw=raw-bucket,prefix=tables", None; "prefix without delimiter")]
+    #[test_case("raw=raw-bucket,size=10", None; "unknown option")]
+    #[test_case("raw=s3://raw-bucket", None; "bucket URI")]
+    fn test_parse_union_source(source: &str, expected: Option<(&str, &str, &str)>) {
+        let parsed = parse_union_source(source);
+        match expected {
+            Some((name, bucket_name, prefix)) => {
+                let parsed = parsed.expect("valid source");
+                assert_eq!(parsed.name, name);
+                assert_eq!(parsed.bucket_name, bucket_name);
+                assert_eq!(parsed.prefix.unwrap_or_default().as_str(), prefix);
+            }
+            None => {
+                parsed.expect_err("invalid source");
+            }
+        }
+    }
+
     #[test_case("/dev/fd/3", Some(3); "valid file descriptor")]
     #[test_case("/dev/fd/378", Some(378); "long valid file descriptor")]
     #[test_case("/dev/fd/-1", None; "invalid file descriptor")]
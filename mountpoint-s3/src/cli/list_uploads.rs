@@ -0,0 +1,269 @@
+//! The `mount-s3 list-uploads` subcommand, which finds incomplete multipart uploads under a prefix
+//! of a bucket, and optionally aborts them.
+//!
+//! Incomplete multipart uploads are left behind when an upload is interrupted, for example because
+//! Mountpoint was killed while a file was being written. S3 keeps (and charges for) their parts
+//! until they are either completed or aborted.
+
+use std::io::Write;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context as _};
+use clap::Parser;
+use futures::executor::block_on;
+use mountpoint_s3_client::config::{EndpointConfig, S3ClientConfig};
+use mountpoint_s3_client::error::{AbortMultipartUploadError, ObjectClientError};
+use mountpoint_s3_client::instance_info::InstanceInfo;
+use mountpoint_s3_client::user_agent::UserAgent;
+use mountpoint_s3_client::{ObjectClient, S3CrtClient};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use super::{parse_bucket_name, S3ClientArgs, BUCKET_OPTIONS_HEADER};
+use crate::build_info;
+use crate::prefix::Prefix;
+
+/// Name of the subcommand, which must be the first argument to `mount-s3`.
+pub const COMMAND_NAME: &str = "list-uploads";
+
+/// Maximum number of uploads to request in each ListMultipartUploads request.
+const MAX_UPLOADS_PER_REQUEST: usize = 1000;
+
+#[derive(Parser, Debug)]
+#[clap(
+    name = COMMAND_NAME,
+    about = "List incomplete multipart uploads under a prefix of a bucket, and optionally abort them",
+    version = build_info::FULL_VERSION,
+)]
+pub struct ListUploadsArgs {
+    #[clap(help = "Name of bucket to list uploads in", value_parser = parse_bucket_name)]
+    pub bucket_name: String,
+
+    #[clap(
+        long,
+        help = "Prefix inside the bucket to list uploads under, ending in '/' [default: the entire bucket]",
+        help_heading = BUCKET_OPTIONS_HEADER
+    )]
+    pub prefix: Option<Prefix>,
+
+    #[clap(
+        long,
+        help = "Only include uploads initiated at least this long ago, in seconds or with an s/m/h/d suffix",
+        value_name = "AGE",
+        default_value = "1d",
+        value_parser = parse_age,
+    )]
+    pub older_than: Duration,
+
+    #[clap(long, help = "Abort the uploads that are listed, deleting any parts uploaded so far")]
+    pub abort: bool,
+
+    #[clap(flatten)]
+    pub client_args: S3ClientArgs,
+}
+
+impl ListUploadsArgs {
+    fn prefix(&self) -> Prefix {
+        self.prefix.as_ref().cloned().unwrap_or_default()
+    }
+}
+
+pub fn main<ClientBuilder, Client>(args: ListUploadsArgs, client_builder: ClientBuilder) -> anyhow::Result<()>
+where
+    ClientBuilder: FnOnce(&ListUploadsArgs) -> anyhow::Result<Client>,
+    Client: ObjectClient,
+{
+    let client = client_builder(&args)?;
+
+    let initiated_before = OffsetDateTime::now_utc() - args.older_than;
+    let mut stdout = std::io::stdout().lock();
+    let count = block_on(list_uploads(
+        &client,
+        &args.bucket_name,
+        &args.prefix(),
+        initiated_before,
+        args.abort,
+        &mut stdout,
+    ))?;
+    if args.abort {
+        eprintln!("aborted {count} incomplete multipart uploads");
+    } else {
+        eprintln!("found {count} incomplete multipart uploads");
+    }
+    Ok(())
+}
+
+/// Create a real S3 client
+pub fn create_s3_client(args: &ListUploadsArgs) -> anyhow::Result<S3CrtClient> {
+    // Placeholder region will be filled in by [S3ClientArgs::create_client]
+    let endpoint_config = EndpointConfig::new("PLACEHOLDER").addressing_style(args.client_args.addressing_style());
+
+    let instance_info = InstanceInfo::new();
+    let user_agent_prefix = format!("mountpoint-s3/{}", build_info::FULL_VERSION);
+    let mut user_agent = UserAgent::new_with_instance_info(Some(user_agent_prefix), &instance_info);
+    user_agent.value("mp-list-uploads");
+
+    let client_config = S3ClientConfig::new().user_agent(user_agent);
+    args.client_args.create_client(
+        &args.bucket_name,
+        &args.prefix(),
+        endpoint_config,
+        client_config,
+        &instance_info,
+    )
+}
+
+/// Write a line to `out` for each multipart upload under `prefix` that was initiated before
+/// `initiated_before`, and abort those uploads if `abort` is set.
+///
+/// Returns the number of uploads listed. Uploads that fail to abort are reported, but don't stop
+/// the remaining uploads from being listed and aborted.
+async fn list_uploads<Client: ObjectClient>(
+    client: &Client,
+    bucket: &str,
+    prefix: &Prefix,
+    initiated_before: OffsetDateTime,
+    abort: bool,
+    out: &mut impl Write,
+) -> anyhow::Result<usize> {
+    let mut count = 0;
+    let mut failures = 0;
+    let mut key_marker = None;
+    let mut upload_id_marker = None;
+    loop {
+        let result = client
+            .list_multipart_uploads(
+                bucket,
+                prefix.as_str(),
+                key_marker.as_deref(),
+                upload_id_marker.as_deref(),
+                MAX_UPLOADS_PER_REQUEST,
+            )
+            .await
+            .with_context(|| format!("ListMultipartUploads failed for bucket {bucket}"))?;
+
+        for upload in result
+            .uploads
+            .iter()
+            .filter(|upload| upload.initiated < initiated_before)
+        {
+            let initiated = upload.initiated.format(&Rfc3339)?;
+            if abort {
+                match client
+                    .abort_multipart_upload(bucket, &upload.key, &upload.upload_id)
+                    .await
+                {
+                    // The upload was completed or aborted since we listed it
+                    Ok(_) | Err(ObjectClientError::ServiceError(AbortMultipartUploadError::NoSuchUpload)) => {}
+                    Err(e) => {
+                        eprintln!(
+                            "failed to abort upload {} for key {}: {e:?}",
+                            upload.upload_id, upload.key
+                        );
+                        failures += 1;
+                        continue;
+                    }
+                }
+            }
+            writeln!(out, "{initiated}\t{}\t{}", upload.upload_id, upload.key)?;
+            count += 1;
+        }
+
+        if result.next_key_marker.is_none() {
+            break;
+        }
+        key_marker = result.next_key_marker;
+        upload_id_marker = result.next_upload_id_marker;
+    }
+
+    if failures > 0 {
+        return Err(anyhow!("failed to abort {failures} multipart uploads"));
+    }
+    Ok(count)
+}
+
+/// Parse an age given in seconds, or with an `s`, `m`, `h`, or `d` suffix.
+fn parse_age(age: &str) -> anyhow::Result<Duration> {
+    let (value, unit_secs) = match age.char_indices().last() {
+        Some((i, 's')) => (&age[..i], 1),
+        Some((i, 'm')) => (&age[..i], 60),
+        Some((i, 'h')) => (&age[..i], 60 * 60),
+        Some((i, 'd')) => (&age[..i], 24 * 60 * 60),
+        _ => (age, 1),
+    };
+    let value: u64 = value
+        .parse()
+        .map_err(|_| anyhow!("must be a number of seconds, optionally followed by s, m, h, or d"))?;
+    let secs = value
+        .checked_mul(unit_secs)
+        .ok_or_else(|| anyhow!("age is too large"))?;
+    Ok(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig};
+    use mountpoint_s3_client::types::CreateMultipartUploadParams;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("30", Some(30); "seconds without suffix")]
+    #[test_case("30s", Some(30); "seconds")]
+    #[test_case("5m", Some(300); "minutes")]
+    #[test_case("2h", Some(7200); "hours")]
+    #[test_case("7d", Some(604800); "days")]
+    #[test_case("", None; "empty")]
+    #[test_case("d", None; "missing number")]
+    #[test_case("1w", None; "unknown suffix")]
+    #[test_case("-1h", None; "negative")]
+    fn test_parse_age(age: &str, expected_secs: Option<u64>) {
+        let parsed = parse_age(age).ok();
+        assert_eq!(parsed, expected_secs.map(Duration::from_secs));
+    }
+
+    #[test_case(false; "list only")]
+    #[test_case(true; "abort")]
+    fn test_list_uploads(abort: bool) {
+        let bucket = "test_bucket";
+        let client = MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 1024,
+            ..Default::default()
+        });
+
+        let now = OffsetDateTime::now_utc();
+        let create_upload = |key: &str, age: Duration| {
+            let upload_id = block_on(client.create_multipart_upload(bucket, key, &CreateMultipartUploadParams::new()))
+                .expect("create_multipart_upload should succeed")
+                .upload_id;
+            client
+                .set_multipart_upload_initiated(&upload_id, now - age)
+                .expect("upload should exist");
+            upload_id
+        };
+        let old_upload = create_upload("dir/old", Duration::from_secs(2 * 24 * 60 * 60));
+        let _recent_upload = create_upload("dir/recent", Duration::from_secs(60));
+        let _outside_prefix_upload = create_upload("other/old", Duration::from_secs(2 * 24 * 60 * 60));
+
+        let prefix = Prefix::new("dir/").unwrap();
+        let mut out = Vec::new();
+        let count = block_on(list_uploads(
+            &client,
+            bucket,
+            &prefix,
+            now - Duration::from_secs(24 * 60 * 60),
+            abort,
+            &mut out,
+        ))
+        .expect("list_uploads should succeed");
+
+        assert_eq!(count, 1);
+        let out = String::from_utf8(out).unwrap();
+        let fields: Vec<_> = out.trim_end().split('\t').collect();
+        assert_eq!(fields[1..], [old_upload.as_str(), "dir/old"]);
+
+        let expected_remaining = if abort { 2 } else { 3 };
+        assert_eq!(client.multipart_upload_count(), expected_remaining);
+    }
+}
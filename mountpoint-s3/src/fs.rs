@@ -3,28 +3,38 @@
 use bytes::Bytes;
 use futures::task::Spawn;
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
 use std::time::{Duration, UNIX_EPOCH};
 use thiserror::Error;
+use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
-use tracing::{debug, trace, Level};
+use tracing::{debug, trace, warn, Level};
 
-use fuser::consts::FOPEN_DIRECT_IO;
+use fuser::consts::{FOPEN_DIRECT_IO, FOPEN_KEEP_CACHE};
 use fuser::{FileAttr, KernelConfig};
-use mountpoint_s3_client::types::ChecksumAlgorithm;
+use mountpoint_s3_client::error::{
+    GetObjectTaggingError, HeadObjectError, ObjectClientError, PutObjectTaggingError, RestoreObjectError,
+};
+use mountpoint_s3_client::error_metadata::ProvideErrorMetadata;
+use mountpoint_s3_client::types::{HeadObjectParams, ObjectTags, RestoreObjectParams, RestoreStatus, RestoreTier};
 use mountpoint_s3_client::ObjectClient;
 
+use crate::change_feed::FileChangeFeed;
 use crate::logging;
 use crate::mem_limiter::MemoryLimiter;
 use crate::prefetch::{Prefetch, PrefetchResult};
 use crate::prefix::Prefix;
-use crate::superblock::{InodeError, InodeKind, LookedUp, ReaddirHandle, Superblock, SuperblockConfig};
+use crate::superblock::{
+    ChangeFeedFollower, InodeError, InodeKind, LookedUp, ReaddirHandle, RecentFilesPoller, SnapshotError, Superblock,
+    SuperblockConfig,
+};
 use crate::sync::atomic::{AtomicU64, Ordering};
 use crate::sync::{Arc, AsyncMutex, AsyncRwLock};
-use crate::upload::Uploader;
+use crate::upload::{Uploader, UploaderConfig};
 
-pub use crate::superblock::InodeNo;
+pub use crate::superblock::{InodeNo, Invalidation};
 
 mod config;
 pub use config::{CacheConfig, S3FilesystemConfig};
@@ -42,12 +52,18 @@ pub use flags::OpenFlags;
 mod handles;
 use handles::{DirHandle, FileHandle, FileHandleState};
 
+mod name_escaping;
+use name_escaping::{escape_name, unescape_name};
+
 mod sse;
 pub use sse::{ServerSideEncryption, SseCorruptedError};
 
 mod time_to_live;
 pub use time_to_live::TimeToLive;
 
+mod union;
+pub use union::{UnionFilesystem, UnionSource, MAX_UNION_SOURCES};
+
 pub const FUSE_ROOT_INODE: InodeNo = 1u64;
 
 #[derive(Debug)]
@@ -60,6 +76,10 @@ where
     client: Client,
     mem_limiter: Arc<MemoryLimiter<Client>>,
     superblock: Superblock,
+    /// Set when the most recently opened files are polled for changes
+    _poller: Option<RecentFilesPoller>,
+    /// Set when changes to objects are read from a change feed
+    _change_feed: Option<ChangeFeedFollower>,
     prefetcher: Prefetcher,
     uploader: Uploader<Client>,
     bucket: String,
@@ -165,17 +185,55 @@ where
         let superblock_config = SuperblockConfig {
             cache_config: config.cache_config.clone(),
             s3_personality: config.s3_personality,
+            server_side_encryption: config.server_side_encryption.clone(),
+            check_object_lock: !config.object_lock.is_empty(),
+            as_of: config.as_of,
+            show_versions: config.show_versions,
+            key_filter: config.key_filter.clone(),
+            escape_names: config.escape_names,
+            directory_markers: config.directory_markers,
+            stable_inode_numbers: config.stable_inode_numbers || config.nfs_export,
+            inode_number_namespace: config.inode_number_namespace.clone(),
+            nfs_export: config.nfs_export,
+            manifest: config.manifest.clone(),
+            poll_recent_files: config.poll_recent_files.is_some(),
         };
         let superblock = Superblock::new(bucket, prefix, superblock_config);
+        if let Some(path) = &config.metadata_cache_file {
+            match superblock.load_snapshot(path) {
+                Ok(loaded) => debug!(?path, loaded, "loaded metadata cache"),
+                Err(SnapshotError::IoError(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                    debug!(?path, "no metadata cache to load")
+                }
+                Err(e) => warn!(?path, "failed to load metadata cache: {:?}", e),
+            }
+        }
+        let poller = config.poll_recent_files.and_then(|interval| {
+            superblock
+                .start_polling(client.clone(), interval)
+                .inspect_err(|e| warn!("failed to start polling recently opened files: {:?}", e))
+                .ok()
+        });
+        let change_feed = config.change_feed.as_ref().and_then(|path| {
+            superblock
+                .follow_changes(FileChangeFeed::new(path))
+                .inspect_err(|e| warn!(?path, "failed to start reading change feed: {:?}", e))
+                .ok()
+        });
         let mem_limiter = Arc::new(MemoryLimiter::new(client.clone(), config.mem_limit));
         let uploader = Uploader::new(
             client.clone(),
             runtime,
             mem_limiter.clone(),
-            config.storage_class.to_owned(),
-            config.server_side_encryption.clone(),
-            client.write_part_size().unwrap(),
-            config.use_upload_checksums.then_some(ChecksumAlgorithm::Crc32c),
+            UploaderConfig::new(client.write_part_size().unwrap())
+                .storage_class(config.storage_class.to_owned())
+                .acl(config.acl.to_owned())
+                .server_side_encryption(config.server_side_encryption.clone())
+                .default_checksum_algorithm(config.upload_checksum_algorithm.clone())
+                .upload_journal(config.upload_journal.clone())
+                .object_tags(config.object_tags.clone())
+                .object_lock(config.object_lock.clone())
+                .content_types(config.content_types.clone()),
         );
 
         Self {
@@ -183,6 +241,8 @@ where
             client,
             mem_limiter,
             superblock,
+            _poller: poller,
+            _change_feed: change_feed,
             prefetcher,
             uploader,
             bucket: bucket.to_string(),
@@ -207,6 +267,16 @@ where
         })
     }
 
+    /// Save the metadata cache, if configured to, before the file system is unmounted.
+    pub fn destroy(&self) {
+        if let Some(path) = &self.config.metadata_cache_file {
+            match self.superblock.save_snapshot(path) {
+                Ok(saved) => debug!(?path, saved, "saved metadata cache"),
+                Err(e) => warn!(?path, "failed to save metadata cache: {:?}", e),
+            }
+        }
+    }
+
     pub async fn init(&self, config: &mut KernelConfig) -> Result<(), libc::c_int> {
         const ENV_VAR_KEY_MAX_BACKGROUND: &str = "UNSTABLE_MOUNTPOINT_MAX_BACKGROUND";
         const ENV_VAR_KEY_CONGESTION_THRESHOLD: &str = "UNSTABLE_MOUNTPOINT_CONGESTION_THRESHOLD";
@@ -249,6 +319,16 @@ where
             );
         }
 
+        if self.config.nfs_export {
+            // The mount still works without export support, only not over NFS, so don't fail it.
+            if let Err(unsupported) = config.add_capabilities(fuser::consts::FUSE_EXPORT_SUPPORT) {
+                tracing::warn!(
+                    "the host does not support the FUSE_EXPORT_SUPPORT capability (unsupported bits: {unsupported:#x}), \
+                     so the file system cannot be exported over NFS"
+                );
+            }
+        }
+
         if self.config.allow_overwrite {
             // Overwrites require FUSE_ATOMIC_O_TRUNC capability on the host, so we will panic if the
             // host doesn't support it.
@@ -262,6 +342,44 @@ where
         Ok(())
     }
 
+    /// Whether inode numbers are derived from keys, and so use all 64 bits
+    fn has_stable_inode_numbers(&self) -> bool {
+        self.config.stable_inode_numbers || self.config.nfs_export
+    }
+
+    /// Send the entries and inodes to drop from the kernel's caches to `callback`, as changes to
+    /// their objects are noticed. The callback must not block.
+    pub fn on_invalidation(&self, callback: impl Fn(Invalidation) + Send + Sync + 'static) {
+        let escape_names = self.config.escape_names;
+        self.superblock.on_invalidation(move |invalidation| {
+            callback(match invalidation {
+                Invalidation::Entry { parent, name } if escape_names => Invalidation::Entry {
+                    parent,
+                    name: escape_name(&name).into_owned(),
+                },
+                invalidation => invalidation,
+            })
+        });
+    }
+
+    /// The key component named by a file name from the kernel, or `None` if the name is not a
+    /// valid escaped name.
+    fn key_component<'a>(&self, name: &'a OsStr) -> Option<Cow<'a, OsStr>> {
+        if !self.config.escape_names {
+            return Some(Cow::Borrowed(name));
+        }
+        unescape_name(name).map(|component| Cow::Owned(component.into()))
+    }
+
+    /// The file name to show an inode under.
+    fn file_name(&self, inode_name: &str) -> OsString {
+        if self.config.escape_names {
+            escape_name(inode_name).into_owned().into()
+        } else {
+            inode_name.into()
+        }
+    }
+
     fn make_attr(&self, lookup: &LookedUp) -> FileAttr {
         /// From man stat(2): `st_blocks`: "This field indicates the number of blocks allocated to
         /// the file, in 512-byte units."
@@ -306,9 +424,32 @@ where
     pub async fn lookup(&self, parent: InodeNo, name: &OsStr) -> Result<Entry, Error> {
         trace!("fs:lookup with parent {:?} name {:?}", parent, name);
 
+        // When resolving NFS file handles, the kernel looks up inodes by number through the names
+        // `.` and `..`, which are never the names of keys.
+        if self.config.nfs_export && (name == "." || name == "..") {
+            let lookup = if name == "." {
+                self.superblock.lookup_self(&self.client, parent).await?
+            } else {
+                self.superblock.lookup_parent(&self.client, parent).await?
+            };
+            return Ok(Entry {
+                ttl: lookup.validity(),
+                attr: self.make_attr(&lookup),
+                generation: 0,
+            });
+        }
+
+        let Some(name) = self.key_component(name) else {
+            return Err(err!(
+                libc::ENOENT,
+                Level::DEBUG,
+                "{:?} is not a valid escaped name",
+                name
+            ));
+        };
         let lookup = self
             .superblock
-            .lookup(&self.client, parent, name)
+            .lookup(&self.client, parent, &name)
             .await
             .map_err(|err| match err {
                 InodeError::FileDoesNotExist(_, _) => {
@@ -420,6 +561,15 @@ where
             FileHandleState::new_read_handle(&lookup, self).await?
         };
 
+        // The kernel drops the content it cached for a file when it's opened, unless asked to keep
+        // it, which is only safe if the object is the one that content was read from.
+        let keep_cache = match &state {
+            FileHandleState::Read { handle, .. } => {
+                self.config.keep_page_cache && !direct_io && handle.is_unchanged_since_last_open()
+            }
+            FileHandleState::Write(_) => false,
+        };
+
         let inode = lookup.inode.clone();
         let full_key = lookup.inode.full_key().to_owned();
         let handle = FileHandle {
@@ -432,7 +582,13 @@ where
         debug!(fh, ino, "new file handle created");
         self.file_handles.write().await.insert(fh, Arc::new(handle));
 
-        let reply_flags = if direct_io { FOPEN_DIRECT_IO } else { 0 };
+        let reply_flags = if direct_io {
+            FOPEN_DIRECT_IO
+        } else if keep_cache {
+            FOPEN_KEEP_CACHE
+        } else {
+            0
+        };
 
         Ok(Opened { fh, flags: reply_flags })
     }
@@ -492,9 +648,12 @@ where
             ));
         }
 
+        let Some(name) = self.key_component(name) else {
+            return Err(err!(libc::EINVAL, "{:?} is not a valid escaped name", name));
+        };
         let lookup = self
             .superblock
-            .create(&self.client, parent, name, InodeKind::File)
+            .create(&self.client, parent, &name, InodeKind::File)
             .await?;
         debug!(ino = lookup.inode.ino(), "new inode created");
         let attr = self.make_attr(&lookup);
@@ -506,10 +665,25 @@ where
     }
 
     pub async fn mkdir(&self, parent: InodeNo, name: &OsStr, _mode: libc::mode_t, _umask: u32) -> Result<Entry, Error> {
+        let Some(name) = self.key_component(name) else {
+            return Err(err!(libc::EINVAL, "{:?} is not a valid escaped name", name));
+        };
         let lookup = self
             .superblock
-            .create(&self.client, parent, name, InodeKind::Directory)
+            .create(&self.client, parent, &name, InodeKind::Directory)
             .await?;
+        if self.config.directory_markers {
+            let key = lookup.inode.full_key();
+            if let Err(e) = self.uploader.put_directory_marker(&self.bucket, key).await {
+                // Don't leave behind a local directory the caller was told failed to be created
+                if let Err(rmdir_err) = self.superblock.rmdir(&self.client, parent, &name).await {
+                    warn!(?key, error=?rmdir_err, "failed to remove directory after its marker failed to upload");
+                }
+                self.superblock.forget(lookup.inode.ino(), 1);
+                return Err(e.into());
+            }
+            self.superblock.finish_directory_marker(lookup.inode.ino())?;
+        }
         let attr = self.make_attr(&lookup);
         Ok(Entry {
             ttl: lookup.validity(),
@@ -739,7 +913,7 @@ where
             let entry = DirectoryEntry {
                 ino: attr.ino,
                 offset: dir_handle.offset() + 1,
-                name: next.inode.name().into(),
+                name: self.file_name(next.inode.name()),
                 attr,
                 generation: 0,
                 ttl: next.validity(),
@@ -857,7 +1031,10 @@ where
     }
 
     pub async fn rmdir(&self, parent_ino: InodeNo, name: &OsStr) -> Result<(), Error> {
-        self.superblock.rmdir(&self.client, parent_ino, name).await?;
+        let Some(name) = self.key_component(name) else {
+            return Err(err!(libc::ENOENT, "{:?} is not a valid escaped name", name));
+        };
+        self.superblock.rmdir(&self.client, parent_ino, &name).await?;
         Ok(())
     }
 
@@ -876,7 +1053,10 @@ where
                 "Deletes are disabled. Use '--allow-delete' mount option to enable it."
             ));
         }
-        Ok(self.superblock.unlink(&self.client, parent_ino, name).await?)
+        let Some(name) = self.key_component(name) else {
+            return Err(err!(libc::ENOENT, "{:?} is not a valid escaped name", name));
+        };
+        Ok(self.superblock.unlink(&self.client, parent_ino, &name).await?)
     }
 
     pub async fn statfs(&self, _ino: InodeNo) -> Result<StatFs, Error> {
@@ -893,6 +1073,360 @@ where
         };
         Ok(reply)
     }
+
+    /// Get the value of an extended attribute. Only `user.s3.tag.<key>` attributes on objects in
+    /// S3 are supported, and are backed by the object's tags, along with the `user.s3.restore`
+    /// attribute that reports the restore status of archived objects.
+    pub async fn getxattr(&self, ino: InodeNo, name: &OsStr) -> Result<Vec<u8>, Error> {
+        trace!("fs:getxattr with ino {:?} name {:?}", ino, name);
+        self.check_xattrs_enabled()?;
+
+        if name == RESTORE_XATTR_NAME {
+            return self.get_restore_status(ino).await.map(String::into_bytes);
+        }
+        let Some(tag_key) = tag_key_from_xattr_name(name) else {
+            return Err(no_such_xattr());
+        };
+        let Some(lookup) = self.remote_object(ino).await? else {
+            return Err(no_such_xattr());
+        };
+        let tags = self.get_object_tags(&lookup).await.map_err(tagging_error)?;
+        match tags.get(tag_key) {
+            Some(value) => Ok(value.as_bytes().to_vec()),
+            None => Err(no_such_xattr()),
+        }
+    }
+
+    /// List the extended attributes of an inode, as a sequence of null-terminated names.
+    pub async fn listxattr(&self, ino: InodeNo) -> Result<Vec<u8>, Error> {
+        trace!("fs:listxattr with ino {:?}", ino);
+        self.check_xattrs_enabled()?;
+
+        let Some(lookup) = self.remote_object(ino).await? else {
+            return Ok(Vec::new());
+        };
+        let tags = match self.get_object_tags(&lookup).await {
+            Ok(tags) => tags,
+            // Tools like `cp -a` and `rsync -X` list the attributes of every file they copy, so they
+            // shouldn't fail when the tags can't be read, for example without s3:GetObjectTagging.
+            Err(ObjectClientError::ClientError(error)) => {
+                warn!(
+                    key = lookup.inode.full_key(),
+                    ?error,
+                    "failed to get object tags, listing no attributes"
+                );
+                return Ok(Vec::new());
+            }
+            Err(error) => return Err(tagging_error(error)),
+        };
+        let mut tag_keys: Vec<_> = tags.keys().collect();
+        tag_keys.sort();
+
+        let mut names = Vec::new();
+        for tag_key in tag_keys {
+            names.extend_from_slice(TAG_XATTR_PREFIX.as_bytes());
+            names.extend_from_slice(tag_key.as_bytes());
+            names.push(0);
+        }
+        Ok(names)
+    }
+
+    /// Set the value of an extended attribute, which updates the corresponding tag on the object in S3.
+    /// Setting `user.s3.restore` instead starts restoring an archived object, and setting
+    /// `user.s3.delete` to `recursive` on a directory deletes everything under it.
+    pub async fn setxattr(&self, ino: InodeNo, name: &OsStr, value: &[u8], flags: i32) -> Result<(), Error> {
+        trace!("fs:setxattr with ino {:?} name {:?} flags {:?}", ino, name, flags);
+        self.check_xattrs_enabled()?;
+
+        if name == DELETE_XATTR_NAME {
+            if value != DELETE_RECURSIVE_XATTR_VALUE.as_bytes() {
+                return Err(err!(
+                    libc::EINVAL,
+                    "the only supported delete request is {DELETE_RECURSIVE_XATTR_VALUE:?}"
+                ));
+            }
+            return self.delete_recursive(ino).await;
+        }
+
+        if name == RESTORE_XATTR_NAME {
+            let params = std::str::from_utf8(value)
+                .ok()
+                .and_then(parse_restore_request)
+                .ok_or_else(|| {
+                    err!(
+                        libc::EINVAL,
+                        "restore requests must be of the form \"days=<days>[;tier=<tier>]\""
+                    )
+                })?;
+            return self.restore_object(ino, &params).await;
+        }
+        let Some(tag_key) = tag_key_from_xattr_name(name) else {
+            return Err(err!(
+                libc::ENOTSUP,
+                "only extended attributes with the {TAG_XATTR_PREFIX:?} prefix are supported"
+            ));
+        };
+        let Ok(value) = std::str::from_utf8(value) else {
+            return Err(err!(libc::EINVAL, "tag values must be valid UTF-8"));
+        };
+        let Some(lookup) = self.remote_object(ino).await? else {
+            return Err(err!(
+                libc::ENOTSUP,
+                "tags can only be set on files that have been uploaded to S3"
+            ));
+        };
+
+        // Tags can only be replaced as a whole, so read the current tags and update the one we want
+        let mut tags = self.get_object_tags(&lookup).await.map_err(tagging_error)?;
+        let exists = tags.contains_key(tag_key);
+        if flags & libc::XATTR_CREATE != 0 && exists {
+            return Err(err!(libc::EEXIST, "extended attribute already exists"));
+        }
+        if flags & libc::XATTR_REPLACE != 0 && !exists {
+            return Err(err!(libc::ENODATA, "extended attribute not found"));
+        }
+        tags.insert(tag_key.to_owned(), value.to_owned());
+        self.put_object_tags(&lookup, tags).await
+    }
+
+    /// Remove an extended attribute, which deletes the corresponding tag from the object in S3.
+    pub async fn removexattr(&self, ino: InodeNo, name: &OsStr) -> Result<(), Error> {
+        trace!("fs:removexattr with ino {:?} name {:?}", ino, name);
+        self.check_xattrs_enabled()?;
+
+        let Some(tag_key) = tag_key_from_xattr_name(name) else {
+            return Err(no_such_xattr());
+        };
+        let Some(lookup) = self.remote_object(ino).await? else {
+            return Err(no_such_xattr());
+        };
+
+        let mut tags = self.get_object_tags(&lookup).await.map_err(tagging_error)?;
+        if tags.remove(tag_key).is_none() {
+            return Err(no_such_xattr());
+        }
+        self.put_object_tags(&lookup, tags).await
+    }
+
+    /// Extended attributes are opt-in, since the kernel asks for `security.capability` on every
+    /// write and copying tools list the attributes of every file. Returning ENOSYS lets the kernel
+    /// stop sending extended attribute requests for the rest of the mount.
+    fn check_xattrs_enabled(&self) -> Result<(), Error> {
+        if self.config.enable_xattrs {
+            Ok(())
+        } else {
+            Err(err!(libc::ENOSYS, Level::DEBUG, "extended attributes are not enabled"))
+        }
+    }
+
+    /// Look up an inode, if it is a file that exists in S3.
+    async fn remote_object(&self, ino: InodeNo) -> Result<Option<LookedUp>, Error> {
+        let lookup = self.superblock.getattr(&self.client, ino, false).await?;
+        if lookup.inode.kind() != InodeKind::File || !lookup.inode.is_remote()? {
+            return Ok(None);
+        }
+        Ok(Some(lookup))
+    }
+
+    /// Get the tags of the object backing a file. They are cached alongside the file's stat, so
+    /// that they are only fetched from S3 again once the stat expires.
+    async fn get_object_tags(
+        &self,
+        lookup: &LookedUp,
+    ) -> Result<ObjectTags, ObjectClientError<GetObjectTaggingError, Client::ClientError>> {
+        if let Some(tags) = &lookup.stat.tags {
+            return Ok(tags.clone());
+        }
+        let tags = self
+            .client
+            .get_object_tagging(&self.bucket, lookup.inode.full_key())
+            .await?
+            .tags;
+        self.cache_tags(lookup, tags.clone());
+        Ok(tags)
+    }
+
+    fn cache_tags(&self, lookup: &LookedUp, tags: ObjectTags) {
+        let ino = lookup.inode.ino();
+        if let Err(error) = self.superblock.cache_tags(ino, lookup.stat.etag.as_deref(), tags) {
+            debug!(ino, ?error, "failed to cache object tags");
+        }
+    }
+
+    async fn put_object_tags(&self, lookup: &LookedUp, tags: ObjectTags) -> Result<(), Error> {
+        match self
+            .client
+            .put_object_tagging(&self.bucket, lookup.inode.full_key(), &tags)
+            .await
+        {
+            Ok(_) => {
+                self.cache_tags(lookup, tags);
+                Ok(())
+            }
+            Err(ObjectClientError::ServiceError(PutObjectTaggingError::NoSuchKey)) => {
+                Err(err!(libc::ENOENT, "object no longer exists in S3"))
+            }
+            Err(ObjectClientError::ServiceError(PutObjectTaggingError::InvalidTag)) => {
+                Err(err!(libc::EINVAL, "S3 rejected the object tags as invalid"))
+            }
+            Err(e) => Err(err!(libc::EIO, source:e, "put object tagging failed")),
+        }
+    }
+
+    async fn delete_recursive(&self, ino: InodeNo) -> Result<(), Error> {
+        if !self.config.allow_delete {
+            return Err(err!(
+                libc::EPERM,
+                "Deletes are disabled. Use '--allow-delete' mount option to enable it."
+            ));
+        }
+        let summary = self.superblock.delete_recursive(&self.client, ino).await?;
+        debug!(ino, ?summary, "recursive delete finished");
+        if summary.failed > 0 {
+            return Err(err!(
+                libc::EIO,
+                "failed to delete {} of {} objects",
+                summary.failed,
+                summary.deleted + summary.failed
+            ));
+        }
+        Ok(())
+    }
+
+    /// Look up the S3 key of an inode that can be restored, if it is a file that exists in S3.
+    async fn restorable_object_key(&self, ino: InodeNo) -> Result<Option<String>, Error> {
+        let lookup = self.superblock.getattr(&self.client, ino, false).await?;
+        if lookup.inode.kind() != InodeKind::File || !lookup.inode.is_remote()? {
+            return Ok(None);
+        }
+        if lookup.stat.version_id.is_some() {
+            return Err(err!(
+                libc::ENOTSUP,
+                "restoring specific object versions is not supported"
+            ));
+        }
+        Ok(Some(lookup.inode.full_key().to_owned()))
+    }
+
+    async fn restore_object(&self, ino: InodeNo, params: &RestoreObjectParams) -> Result<(), Error> {
+        let Some(full_key) = self.restorable_object_key(ino).await? else {
+            return Err(err!(
+                libc::ENOTSUP,
+                "only files that have been uploaded to S3 can be restored"
+            ));
+        };
+        match self.client.restore_object(&self.bucket, &full_key, params).await {
+            // Requesting a restore again while one is running is harmless, so that batch jobs can
+            // retry their requests
+            Ok(_) | Err(ObjectClientError::ServiceError(RestoreObjectError::RestoreAlreadyInProgress)) => Ok(()),
+            Err(ObjectClientError::ServiceError(RestoreObjectError::NoSuchKey)) => {
+                Err(err!(libc::ENOENT, "object no longer exists in S3"))
+            }
+            Err(ObjectClientError::ServiceError(RestoreObjectError::InvalidObjectState)) => {
+                Err(err!(libc::EINVAL, "object is not in an archive storage class"))
+            }
+            Err(e) => Err(err!(libc::EIO, source:e, "restore object failed")),
+        }
+    }
+
+    /// Describe the restore status of an archived object, which is one of `archived`,
+    /// `in-progress`, or `restored; expiry=<date>`.
+    async fn get_restore_status(&self, ino: InodeNo) -> Result<String, Error> {
+        let Some(full_key) = self.restorable_object_key(ino).await? else {
+            return Err(no_such_xattr());
+        };
+        // Restores complete asynchronously, so always fetch the current status from S3
+        let sse_customer_key = self
+            .config
+            .server_side_encryption
+            .customer_key()
+            .map_err(|e| err!(libc::EIO, source:e, "SSE settings corrupted"))?;
+        let params = HeadObjectParams::new().sse_customer_key(sse_customer_key);
+        let head_object = match self.client.head_object(&self.bucket, &full_key, &params).await {
+            Ok(head_object) => head_object,
+            Err(ObjectClientError::ServiceError(HeadObjectError::NotFound)) => {
+                return Err(err!(libc::ENOENT, "object no longer exists in S3"));
+            }
+            Err(e) => return Err(err!(libc::EIO, source:e, "head object failed")),
+        };
+        match head_object.restore_status {
+            Some(RestoreStatus::InProgress) => Ok("in-progress".to_owned()),
+            Some(RestoreStatus::Restored { expiry }) => {
+                let expiry = OffsetDateTime::from(expiry)
+                    .format(&Rfc3339)
+                    .map_err(|e| err!(libc::EIO, source:e, "invalid restore expiry"))?;
+                Ok(format!("restored; expiry={expiry}"))
+            }
+            None => match head_object.storage_class.as_deref() {
+                Some("GLACIER") | Some("DEEP_ARCHIVE") => Ok("archived".to_owned()),
+                _ => Err(no_such_xattr()),
+            },
+        }
+    }
+}
+
+/// Prefix of the extended attributes that expose the tags of an S3 object.
+pub const TAG_XATTR_PREFIX: &str = "user.s3.tag.";
+
+/// Name of the extended attribute used to restore archived objects and report their restore status.
+pub const RESTORE_XATTR_NAME: &str = "user.s3.restore";
+
+/// Name of the extended attribute that deletes everything under a directory when set to
+/// [DELETE_RECURSIVE_XATTR_VALUE].
+pub const DELETE_XATTR_NAME: &str = "user.s3.delete";
+
+/// Value of the [DELETE_XATTR_NAME] extended attribute that requests a recursive delete.
+pub const DELETE_RECURSIVE_XATTR_VALUE: &str = "recursive";
+
+/// Map a failed GetObjectTagging request to an error.
+fn tagging_error<E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static>(
+    error: ObjectClientError<GetObjectTaggingError, E>,
+) -> Error {
+    match error {
+        ObjectClientError::ServiceError(GetObjectTaggingError::NoSuchKey) => {
+            err!(libc::ENOENT, "object no longer exists in S3")
+        }
+        error if error.meta().http_code == Some(403) => {
+            err!(libc::EACCES, source:error, "get object tagging was denied")
+        }
+        error => err!(libc::EIO, source:error, "get object tagging failed"),
+    }
+}
+
+/// Missing extended attributes are routine (the kernel probes for security attributes on every
+/// write), so they are only logged at debug level.
+fn no_such_xattr() -> Error {
+    err!(libc::ENODATA, Level::DEBUG, "extended attribute not found")
+}
+
+/// Extract the tag key from an extended attribute name, if it is a tag attribute.
+fn tag_key_from_xattr_name(name: &OsStr) -> Option<&str> {
+    name.to_str()?
+        .strip_prefix(TAG_XATTR_PREFIX)
+        .filter(|tag_key| !tag_key.is_empty())
+}
+
+/// Parse the value of a `user.s3.restore` extended attribute, of the form `days=7;tier=Bulk`.
+/// The number of days is required, and the tier is optional.
+fn parse_restore_request(value: &str) -> Option<RestoreObjectParams> {
+    let mut days = None;
+    let mut tier = None;
+    for field in value.trim().split(';') {
+        let (name, value) = field.split_once('=')?;
+        match name.trim() {
+            "days" => days = Some(value.trim().parse::<u32>().ok().filter(|days| *days > 0)?),
+            "tier" => {
+                tier = Some(match value.trim().to_ascii_lowercase().as_str() {
+                    "expedited" => RestoreTier::Expedited,
+                    "standard" => RestoreTier::Standard,
+                    "bulk" => RestoreTier::Bulk,
+                    _ => return None,
+                })
+            }
+            _ => return None,
+        }
+    }
+    Some(RestoreObjectParams::new(days?).tier(tier))
 }
 
 #[cfg(test)]
@@ -958,4 +1492,31 @@ mod tests {
         assert_eq!(err.errno, libc::EIO);
         assert_eq!(format!("{}", err), "put failed to start: SSE settings corrupted: Checksum mismatch. expected: Crc32c(752912206), actual: Crc32c(1265531471)");
     }
+
+    #[test]
+    fn test_parse_restore_request() {
+        let params = parse_restore_request("days=7").expect("should parse");
+        assert_eq!(params.days, 7);
+        assert_eq!(params.tier, None);
+
+        let params = parse_restore_request("days=1; tier=Bulk\n").expect("should parse");
+        assert_eq!(params.days, 1);
+        assert_eq!(params.tier, Some(RestoreTier::Bulk));
+
+        let params = parse_restore_request("tier=expedited;days=30").expect("should parse");
+        assert_eq!(params.days, 30);
+        assert_eq!(params.tier, Some(RestoreTier::Expedited));
+
+        for invalid in [
+            "",
+            "days=0",
+            "days=-1",
+            "tier=Bulk",
+            "days=7;tier=Fast",
+            "days=7;speed=fast",
+            "7",
+        ] {
+            assert!(parse_restore_request(invalid).is_none(), "{invalid:?} should not parse");
+        }
+    }
 }
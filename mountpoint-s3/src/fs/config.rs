@@ -1,14 +1,21 @@
+use std::path::PathBuf;
 use std::time::Duration;
 
+use mountpoint_s3_client::types::{ChecksumAlgorithm, ObjectLock, ObjectTags};
 use nix::unistd::{getgid, getuid};
+use time::OffsetDateTime;
 
+use crate::key_filter::KeyFilter;
+use crate::manifest::Manifest;
 use crate::mem_limiter::MINIMUM_MEM_LIMIT;
 use crate::s3::S3Personality;
 use crate::superblock::WriteMode;
+use crate::sync::Arc;
+use crate::upload::{ContentTypeMap, UploadJournal};
 
 use super::{ServerSideEncryption, TimeToLive};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct S3FilesystemConfig {
     /// Kernel cache config
     pub cache_config: CacheConfig,
@@ -30,14 +37,53 @@ pub struct S3FilesystemConfig {
     pub incremental_upload: bool,
     /// Storage class to be used for new object uploads
     pub storage_class: Option<String>,
+    /// Canned ACL to be used for new object uploads
+    pub acl: Option<String>,
     /// S3 personality (for different S3 semantics)
     pub s3_personality: S3Personality,
     /// Server side encryption configuration to be used when creating new S3 object
     pub server_side_encryption: ServerSideEncryption,
-    /// Use additional checksums for uploads
-    pub use_upload_checksums: bool,
+    /// Algorithm of the additional checksums for uploads, if enabled
+    pub upload_checksum_algorithm: Option<ChecksumAlgorithm>,
     /// Memory limit
     pub mem_limit: u64,
+    /// Journal to record atomic uploads in, so they can be resumed after a crash
+    pub upload_journal: Option<UploadJournal>,
+    /// Tags to apply to new object uploads
+    pub object_tags: ObjectTags,
+    /// Object Lock retention and legal hold to apply to new object uploads
+    pub object_lock: ObjectLock,
+    /// Infer the content type of new object uploads from their extension with this map, if set
+    pub content_types: Option<ContentTypeMap>,
+    /// Show the objects in the bucket as they were at this time, using their versions
+    pub as_of: Option<OffsetDateTime>,
+    /// Serve the virtual `<name>.versions` directories listing the versions of objects
+    pub show_versions: bool,
+    /// Hide the keys that don't pass this filter
+    pub key_filter: KeyFilter,
+    /// Escape the key components that aren't valid file names, rather than hiding them
+    pub escape_names: bool,
+    /// Create a zero-byte `name/` directory marker object on mkdir, and delete it on rmdir
+    pub directory_markers: bool,
+    /// Expose object tags, restores and recursive deletes as `user.s3.*` extended attributes
+    pub enable_xattrs: bool,
+    /// Derive inode numbers from a hash of the key, so that they are the same across remounts
+    pub stable_inode_numbers: bool,
+    /// Mixed into stable inode numbers, so that the sources of a union mount don't share numbers
+    pub inode_number_namespace: String,
+    /// Support exporting the file system over NFS. Implies stable inode numbers.
+    pub nfs_export: bool,
+    /// File to save the metadata cache to on unmount, and load it from on mount
+    pub metadata_cache_file: Option<PathBuf>,
+    /// Serve the namespace from this manifest of the objects in the bucket, rather than listing it
+    pub manifest: Option<Arc<Manifest>>,
+    /// Check the most recently opened files for changes in S3 at this interval
+    pub poll_recent_files: Option<Duration>,
+    /// File or named pipe to read S3 event notifications from, to keep the metadata cache fresh
+    pub change_feed: Option<PathBuf>,
+    /// Keep the content the kernel cached for a file when it's opened again, if its object hasn't
+    /// changed since it was last opened
+    pub keep_page_cache: bool,
 }
 
 impl Default for S3FilesystemConfig {
@@ -56,10 +102,29 @@ impl Default for S3FilesystemConfig {
             allow_overwrite: false,
             incremental_upload: false,
             storage_class: None,
+            acl: None,
             s3_personality: S3Personality::default(),
             server_side_encryption: Default::default(),
-            use_upload_checksums: true,
+            upload_checksum_algorithm: Some(ChecksumAlgorithm::Crc32c),
             mem_limit: MINIMUM_MEM_LIMIT,
+            upload_journal: None,
+            object_tags: Default::default(),
+            object_lock: Default::default(),
+            content_types: None,
+            as_of: None,
+            show_versions: false,
+            key_filter: Default::default(),
+            escape_names: false,
+            directory_markers: false,
+            enable_xattrs: false,
+            stable_inode_numbers: false,
+            inode_number_namespace: String::new(),
+            nfs_export: false,
+            metadata_cache_file: None,
+            manifest: None,
+            poll_recent_files: None,
+            change_feed: None,
+            keep_page_cache: false,
         }
     }
 }
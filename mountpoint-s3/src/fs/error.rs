@@ -69,6 +69,9 @@ macro_rules! err {
     ($errno:expr, $message:literal) => {
         err!($errno, __source:None, ::tracing::Level::WARN, Default::default(), $message,)
     };
+    ($errno:expr, $level:expr, $message:literal) => {
+        err!($errno, __source:None, $level, Default::default(), $message,)
+    };
 }
 
 /// A dynamic error type returned by the Mountpoint filesystem. See the [err!] macro for more
@@ -130,10 +133,12 @@ impl<E: std::error::Error + Send + Sync + 'static> From<PrefetchReadError<E>> fo
             )) => err!(libc::ESTALE, "object was mutated remotely"),
             PrefetchReadError::Integrity(e) => err!(libc::EIO, source:e, "integrity error"),
             PrefetchReadError::PartReadFailed(e) => err!(libc::EIO, source:e, "part read failed"),
+            PrefetchReadError::ChecksumMismatch { .. } => err!(libc::EIO, source:err, "object checksum mismatch"),
             PrefetchReadError::GetRequestFailed(_)
             | PrefetchReadError::GetRequestTerminatedUnexpectedly
             | PrefetchReadError::GetRequestReturnedWrongOffset { .. }
             | PrefetchReadError::BackpressurePreconditionFailed
+            | PrefetchReadError::ChecksumComputationFailed(_)
             | PrefetchReadError::ReadWindowIncrement => {
                 err!(libc::EIO, source:err, "get request failed")
             }
@@ -175,6 +180,10 @@ impl ToErrno for InodeError {
             InodeError::CorruptedMetadata(_) => libc::EIO,
             InodeError::SetAttrNotPermittedOnRemoteInode(_) => libc::EPERM,
             InodeError::StaleInode { .. } => libc::ESTALE,
+            InodeError::SseCorrupted(_) => libc::EIO,
+            InodeError::ObjectLocked(_) => libc::EPERM,
+            InodeError::HiddenByKeyFilter(_) => libc::EPERM,
+            InodeError::UnknownInode(_) => libc::ESTALE,
         }
     }
 }
@@ -189,6 +198,11 @@ impl<E: std::error::Error> ToErrno for UploadError<E> {
             UploadError::HeadObjectFailed(_) => libc::EIO,
             UploadError::OutOfOrderWrite { .. } => libc::EINVAL,
             UploadError::ObjectTooBig { .. } => libc::EFBIG,
+            UploadError::CreateMultipartUploadFailed(_) => libc::EIO,
+            UploadError::UploadPartFailed(_) => libc::EIO,
+            UploadError::CompleteMultipartUploadFailed(_) => libc::EIO,
+            UploadError::ReviewFailed => libc::EIO,
+            UploadError::JournalFailed(_) => libc::EIO,
         }
     }
 }
@@ -1,5 +1,6 @@
 use std::str::FromStr as _;
 
+use mountpoint_s3_client::error::{CompleteMultipartUploadError, ObjectClientError, PutObjectError};
 use mountpoint_s3_client::types::ETag;
 use mountpoint_s3_client::ObjectClient;
 use tracing::{debug, error, trace};
@@ -9,7 +10,7 @@ use crate::prefetch::Prefetch;
 use crate::superblock::{Inode, LookedUp, ReadHandle, ReaddirHandle, WriteHandle};
 use crate::sync::atomic::{AtomicI64, Ordering};
 use crate::sync::AsyncMutex;
-use crate::upload::{AppendUploadRequest, UploadRequest};
+use crate::upload::{AppendUploadRequest, UploadError, UploadRequest};
 
 use super::{DirectoryEntry, Error, InodeNo, OpenFlags, S3Filesystem, ToErrno};
 
@@ -100,9 +101,11 @@ where
         let handle = fs.superblock.write(&fs.client, ino, &write_mode, is_truncate).await?;
         let bucket = &fs.bucket;
         let key = lookup.inode.full_key();
+        // When overwriting, only complete the upload if the object was not modified since it was opened.
+        let overwritten_etag = handle.overwritten_etag().cloned();
         let handle = if write_mode.incremental_upload {
             let initial_etag = if is_truncate {
-                None
+                overwritten_etag
             } else {
                 lookup.stat.etag.as_ref().map(|e| e.into())
             };
@@ -122,7 +125,7 @@ where
         } else {
             let request = fs
                 .uploader
-                .start_atomic_upload(bucket, key)
+                .start_atomic_upload(bucket, key, overwritten_etag)
                 .map_err(|e| err!(libc::EIO, source:e, "put failed to start"))?;
             FileHandleState::Write(UploadState::MPUInProgress { request, handle })
         };
@@ -140,6 +143,11 @@ where
                 "objects in flexible retrieval storage classes are not accessible",
             ));
         }
+        let sse_customer_key = fs
+            .config
+            .server_side_encryption
+            .customer_key()
+            .map_err(|e| err!(libc::EIO, source:e, "SSE settings corrupted"))?;
         let handle = fs.superblock.read(&fs.client, lookup.inode.ino()).await?;
         let full_key = lookup.inode.full_key().to_owned();
         let object_size = lookup.stat.size as u64;
@@ -147,13 +155,14 @@ where
             None => return Err(err!(libc::EBADF, "no E-Tag for inode {}", lookup.inode.ino())),
             Some(etag) => ETag::from_str(etag).expect("E-Tag should be set"),
         };
-        let object_id = ObjectId::new(full_key, etag);
+        let object_id = ObjectId::new_versioned(full_key, etag, lookup.stat.version_id.clone());
         let request = fs.prefetcher.prefetch(
             fs.client.clone(),
             fs.mem_limiter.clone(),
             fs.bucket.clone(),
             object_id,
             object_size,
+            sse_customer_key,
         );
         let handle = FileHandleState::Read { handle, request };
         metrics::gauge!("fs.current_handles", "type" => "read").increment(1.0);
@@ -345,11 +354,11 @@ where
     async fn complete_upload(upload: UploadRequest<Client>, key: &str, handle: WriteHandle) -> Result<(), Error> {
         let size = upload.size();
         let (put_result, etag) = match upload.complete().await {
-            Ok(result) => {
+            Ok(etag) => {
                 debug!(key, size, "put succeeded");
-                (Ok(()), Some(result.etag))
+                (Ok(()), Some(etag))
             }
-            Err(e) => (Err(err!(libc::EIO, source:e, "put failed")), None),
+            Err(e) => (Err(put_error(e)), None),
         };
         if let Err(err) = handle.finish(etag) {
             // Log the issue but still return put_result.
@@ -386,7 +395,7 @@ where
                 debug!(key, "no put required");
                 Ok(None)
             }
-            Err(e) => Err(err!(libc::EIO, source:e, "put failed")),
+            Err(e) => Err(put_error(e)),
         }
     }
 
@@ -398,6 +407,23 @@ where
     }
 }
 
+/// Convert an error completing an upload into an [Error] to return to the kernel.
+///
+/// A failed precondition means the object was modified in S3 by another writer since the file was
+/// opened, so we report it with a distinct errno rather than a generic I/O error.
+fn put_error<E: std::error::Error + Send + Sync + 'static>(error: UploadError<E>) -> Error {
+    match error {
+        UploadError::PutRequestFailed(ObjectClientError::ServiceError(PutObjectError::PreconditionFailed))
+        | UploadError::CompleteMultipartUploadFailed(ObjectClientError::ServiceError(
+            CompleteMultipartUploadError::PreconditionFailed,
+        )) => {
+            metrics::counter!("fs.upload.precondition_failed").increment(1);
+            err!(libc::ESTALE, source:error, "put failed: object was modified remotely since it was opened")
+        }
+        _ => err!(libc::EIO, source:error, "put failed"),
+    }
+}
+
 /// Get the thread-group id (tgid) from a process id (pid).
 /// Despite the names, the process id is actually the thread id
 /// and the thread-group id is the parent process id.
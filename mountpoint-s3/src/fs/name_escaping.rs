@@ -0,0 +1,87 @@
+//! Reversible escaping of the key components that aren't valid file names.
+//!
+//! With `--escape-names`, the file system shows every key component under a name that can be
+//! translated back to it. A `%` is shown as `%25` and a NUL as `%00`, and the components that can't
+//! be names on their own are shown as reserved names: `%` for the empty component between two
+//! consecutive `/`, `%2E` for `.`, and `%2E%2E` for `..`. Any other name is shown as-is.
+
+use std::borrow::Cow;
+use std::ffi::OsStr;
+
+/// Name shown for the empty key component, as in the key `a//b`
+const EMPTY_NAME: &str = "%";
+
+/// Translate a key component into the file name that shows it.
+pub fn escape_name(component: &str) -> Cow<'_, str> {
+    match component {
+        "" => EMPTY_NAME.into(),
+        "." => "%2E".into(),
+        ".." => "%2E%2E".into(),
+        _ if component.contains(['%', '\0']) => component.replace('%', "%25").replace('\0', "%00").into(),
+        _ => component.into(),
+    }
+}
+
+/// Translate a file name back into the key component it shows, or `None` if no key component is
+/// shown with this name.
+///
+/// Only the names [escape_name] returns are accepted, so that every key component has exactly
+/// one name and every name exactly one key component.
+pub fn unescape_name(name: &OsStr) -> Option<String> {
+    let name = name.to_str()?;
+    if name == EMPTY_NAME {
+        return Some(String::new());
+    }
+
+    let mut bytes = Vec::with_capacity(name.len());
+    let mut rest = name.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail.get(..2)?;
+            let hex = std::str::from_utf8(hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    let component = String::from_utf8(bytes).ok()?;
+
+    if component.contains('/') || escape_name(&component) != name {
+        return None;
+    }
+    Some(component)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("", "%"; "empty")]
+    #[test_case(".", "%2E"; "dot")]
+    #[test_case("..", "%2E%2E"; "dot dot")]
+    #[test_case("...", "..."; "three dots")]
+    #[test_case("a", "a"; "plain")]
+    #[test_case("100%", "100%25"; "percent")]
+    #[test_case("%2E", "%252E"; "escaped dot")]
+    #[test_case("%", "%25"; "lone percent")]
+    #[test_case("a\0b", "a%00b"; "nul")]
+    #[test_case("caf\u{e9}", "caf\u{e9}"; "unicode")]
+    fn test_escape_round_trip(component: &str, name: &str) {
+        assert_eq!(escape_name(component), name);
+        assert_eq!(unescape_name(OsStr::new(name)).as_deref(), Some(component));
+    }
+
+    #[test_case("%2e"; "lowercase dot")]
+    #[test_case("%41"; "needlessly escaped")]
+    #[test_case("%2F"; "delimiter")]
+    #[test_case("a%"; "truncated")]
+    #[test_case("%zz"; "not hex")]
+    #[test_case("%2E."; "mixed dots")]
+    #[test_case("%FF"; "not utf8")]
+    fn test_unescape_invalid(name: &str) {
+        assert_eq!(unescape_name(OsStr::new(name)), None);
+    }
+}
@@ -1,11 +1,14 @@
+use mountpoint_s3_client::types::SseCustomerKey;
 use mountpoint_s3_crt::checksums::crc32c::{self, Crc32c};
 use thiserror::Error;
 
-/// Server-side encryption configuration for newly created objects
+/// Server-side encryption configuration for newly created objects, and the customer-provided key
+/// (SSE-C) to use when reading or writing objects, if any
 #[derive(Debug, Clone)]
 pub struct ServerSideEncryption {
     sse_type: Option<String>,
     sse_kms_key_id: Option<String>,
+    sse_customer_key: Option<SseCustomerKey>,
     checksum: Crc32c,
 }
 
@@ -24,6 +27,7 @@ impl Default for ServerSideEncryption {
         Self {
             sse_type: Default::default(),
             sse_kms_key_id: Default::default(),
+            sse_customer_key: Default::default(),
             checksum: Crc32c::new(0),
         }
     }
@@ -32,18 +36,35 @@ impl Default for ServerSideEncryption {
 impl ServerSideEncryption {
     /// Construct SSE settings from raw values provided via CLI
     pub fn new(sse_type: Option<String>, sse_kms_key_id: Option<String>) -> Self {
-        let checksum = Self::compute_checksum(sse_type.as_deref(), sse_kms_key_id.as_deref());
+        let checksum = Self::compute_checksum(sse_type.as_deref(), sse_kms_key_id.as_deref(), None);
         Self {
             sse_type,
             sse_kms_key_id,
+            sse_customer_key: None,
             checksum,
         }
     }
 
-    /// Computes the checksum of SSE settings by combining two strings containing the type and the key
+    /// Set the customer-provided key (SSE-C) to send on every request reading or writing object data
+    pub fn with_customer_key(mut self, sse_customer_key: Option<SseCustomerKey>) -> Self {
+        self.checksum = Self::compute_checksum(
+            self.sse_type.as_deref(),
+            self.sse_kms_key_id.as_deref(),
+            sse_customer_key.as_ref(),
+        );
+        self.sse_customer_key = sse_customer_key;
+        self
+    }
+
+    /// Computes the checksum of SSE settings by combining two strings containing the type and the key,
+    /// followed by the bytes of the customer-provided key.
     /// Note, that this implementation yields the same result for Some("") and None, but we may safely
     /// assume that it will never be called with an empty string as one of its parameters.
-    fn compute_checksum(sse_type: Option<&str>, sse_kms_key_id: Option<&str>) -> Crc32c {
+    fn compute_checksum(
+        sse_type: Option<&str>,
+        sse_kms_key_id: Option<&str>,
+        sse_customer_key: Option<&SseCustomerKey>,
+    ) -> Crc32c {
         let mut hasher = crc32c::Hasher::new();
         if let Some(maybe_sse_type) = sse_type {
             hasher.update(maybe_sse_type.as_bytes());
@@ -51,11 +72,18 @@ impl ServerSideEncryption {
         if let Some(maybe_sse_kms_key_id) = sse_kms_key_id {
             hasher.update(maybe_sse_kms_key_id.as_bytes());
         }
+        if let Some(sse_customer_key) = sse_customer_key {
+            hasher.update(sse_customer_key.as_bytes());
+        }
         hasher.finalize()
     }
 
     fn validate(&self) -> Result<(), SseCorruptedError> {
-        let computed = Self::compute_checksum(self.sse_type.as_deref(), self.sse_kms_key_id.as_deref());
+        let computed = Self::compute_checksum(
+            self.sse_type.as_deref(),
+            self.sse_kms_key_id.as_deref(),
+            self.sse_customer_key.as_ref(),
+        );
         if computed == self.checksum {
             Ok(())
         } else {
@@ -71,6 +99,12 @@ impl ServerSideEncryption {
         Ok((self.sse_type, self.sse_kms_key_id))
     }
 
+    /// Checks that SSE settings still match the checksum and returns the customer-provided key, if any.
+    pub fn customer_key(&self) -> Result<Option<SseCustomerKey>, SseCorruptedError> {
+        self.validate()?;
+        Ok(self.sse_customer_key.clone())
+    }
+
     /// Checks that values provided as arguments to this function match the values stored in the object.
     /// S3 will return some values for sse type and key even if they were not set on our side.
     /// We want to check only the values which we set.
@@ -100,6 +134,11 @@ impl ServerSideEncryption {
         self.sse_type = sse_type;
         self.sse_kms_key_id = sse_kms_key_id;
     }
+
+    #[cfg(test)]
+    pub fn corrupt_customer_key(&mut self, sse_customer_key: Option<SseCustomerKey>) {
+        self.sse_customer_key = sse_customer_key;
+    }
 }
 
 #[cfg(test)]
@@ -138,6 +177,18 @@ mod tests {
         assert_eq!(key_id, returned_key_id.as_deref());
     }
 
+    #[test_case(Some([1u8; 32]), Some([2u8; 32]))]
+    #[test_case(Some([1u8; 32]), None)]
+    #[test_case(None, Some([2u8; 32]))]
+    fn test_sse_customer_key_corrupted(key: Option<[u8; 32]>, key_corrupted: Option<[u8; 32]>) {
+        let key = key.map(|k| SseCustomerKey::new(&k).unwrap());
+        let mut sse = ServerSideEncryption::default().with_customer_key(key.clone());
+        assert_eq!(sse.customer_key().expect("key should match the checksum"), key);
+        sse.corrupt_customer_key(key_corrupted.map(|k| SseCustomerKey::new(&k).unwrap()));
+        sse.customer_key()
+            .expect_err("customer_key() should produce an error when the key does not match the checksum");
+    }
+
     #[test_case(Some("aws:kms"), Some("some_key_alias"), Some("aws:kmr"), Some("some_key_alias"))]
     #[test_case(Some("aws:kms"), Some("some_key_alias"), Some("aws:kms"), Some("some_key_ali`s"))]
     #[test_case(Some("aws:kms"), Some("some_key_alias"), None, Some("some_key_alias"))]
@@ -0,0 +1,1116 @@
+//! A file system presenting several [S3Filesystem]s, each mounting its own bucket and prefix, side
+//! by side as the children of a synthetic root directory.
+//!
+//! Each source keeps its own inode and file handle numbers, so the union file system tags the
+//! numbers it hands to the kernel with the index of the source they belong to, and routes every
+//! operation to that source. Stable inode numbers use all 64 bits, so they can't be tagged. The
+//! sources instead mix their names into the hash of each key, so that their numbers don't overlap,
+//! and each stable inode number is routed to the source that knows it. A [UnionFilesystem] with a single source and no synthetic root is just
+//! a pass-through to that source.
+
+use std::ffi::OsStr;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use fuser::KernelConfig;
+use mountpoint_s3_client::ObjectClient;
+use time::OffsetDateTime;
+use tracing::{trace, Level};
+
+use crate::prefetch::Prefetch;
+
+use super::{
+    no_such_xattr, Attr, DirectoryEntry, DirectoryReplier, Entry, Error, InodeNo, Invalidation, OpenFlags, Opened,
+    S3Filesystem, StatFs, FUSE_ROOT_INODE,
+};
+
+/// Inode and file handle numbers of a source are shifted into the low bits, and the index of the
+/// source (plus one, so that no source uses the numbers of the synthetic root) into the high bits.
+const SOURCE_SHIFT: u32 = 48;
+const SOURCE_MASK: u64 = (1 << SOURCE_SHIFT) - 1;
+
+/// The maximum number of sources in a union file system
+pub const MAX_UNION_SOURCES: usize = (u16::MAX - 1) as usize;
+
+/// The file handle of the synthetic root directory
+const ROOT_DIR_HANDLE: u64 = 0;
+
+/// One of the file systems making up a [UnionFilesystem]
+#[derive(Debug)]
+pub struct UnionSource<Client, Prefetcher>
+where
+    Client: ObjectClient + Clone + Send + Sync + 'static,
+    Prefetcher: Prefetch,
+{
+    name: String,
+    fs: S3Filesystem<Client, Prefetcher>,
+}
+
+impl<Client, Prefetcher> UnionSource<Client, Prefetcher>
+where
+    Client: ObjectClient + Clone + Send + Sync + 'static,
+    Prefetcher: Prefetch,
+{
+    /// Create a new source that will appear as the directory `name` in the root of the union.
+    pub fn new(name: &str, fs: S3Filesystem<Client, Prefetcher>) -> Self {
+        Self {
+            name: name.to_owned(),
+            fs,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UnionFilesystem<Client, Prefetcher>
+where
+    Client: ObjectClient + Clone + Send + Sync + 'static,
+    Prefetcher: Prefetch,
+{
+    sources: Vec<UnionSource<Client, Prefetcher>>,
+    /// Whether the sources are the children of a synthetic root directory. Otherwise, there is a
+    /// single source and its root is the root of the file system.
+    synthetic_root: bool,
+    /// Whether the sources have stable inode numbers, which are not tagged
+    stable_inode_numbers: bool,
+}
+
+/// The file system an inode or file handle belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    /// The synthetic root directory
+    Root,
+    /// The given inode or file handle number of the source at this index
+    Source(usize, u64),
+}
+
+impl<Client, Prefetcher> From<S3Filesystem<Client, Prefetcher>> for UnionFilesystem<Client, Prefetcher>
+where
+    Client: ObjectClient + Clone + Send + Sync + 'static,
+    Prefetcher: Prefetch,
+{
+    fn from(fs: S3Filesystem<Client, Prefetcher>) -> Self {
+        Self {
+            sources: vec![UnionSource::new("", fs)],
+            synthetic_root: false,
+            stable_inode_numbers: false,
+        }
+    }
+}
+
+impl<Client, Prefetcher> UnionFilesystem<Client, Prefetcher>
+where
+    Client: ObjectClient + Clone + Send + Sync + 'static,
+    Prefetcher: Prefetch,
+{
+    /// Create a new union of the given sources, whose names must be unique valid file names.
+    pub fn new(sources: Vec<UnionSource<Client, Prefetcher>>) -> Self {
+        assert!(!sources.is_empty(), "a union needs at least one source");
+        assert!(sources.len() <= MAX_UNION_SOURCES, "too many union sources");
+        let stable_inode_numbers = sources[0].fs.has_stable_inode_numbers();
+        assert!(
+            sources
+                .iter()
+                .all(|source| source.fs.has_stable_inode_numbers() == stable_inode_numbers),
+            "either all or none of the sources must have stable inode numbers"
+        );
+        Self {
+            sources,
+            synthetic_root: true,
+            stable_inode_numbers,
+        }
+    }
+
+    /// Find the file system the given inode or file handle number belongs to.
+    fn target(&self, number: u64) -> Result<Target, Error> {
+        if !self.synthetic_root {
+            return Ok(Target::Source(0, number));
+        }
+        if number == FUSE_ROOT_INODE || number == ROOT_DIR_HANDLE {
+            return Ok(Target::Root);
+        }
+        let index = (number >> SOURCE_SHIFT) as usize;
+        if index == 0 || index > self.sources.len() {
+            return Err(err!(libc::EBADF, "number {} does not belong to any source", number));
+        }
+        Ok(Target::Source(index - 1, number & SOURCE_MASK))
+    }
+
+    /// Find the file system the given inode number belongs to. The roots of the sources are always
+    /// tagged, since their numbers aren't derived from a key.
+    fn target_inode(&self, ino: InodeNo) -> Result<Target, Error> {
+        if !self.stable_inode_numbers || !self.synthetic_root {
+            return self.target(ino);
+        }
+        if let Ok(target @ (Target::Root | Target::Source(_, FUSE_ROOT_INODE))) = self.target(ino) {
+            return Ok(target);
+        }
+        match self
+            .sources
+            .iter()
+            .position(|source| source.fs.superblock.contains(ino))
+        {
+            Some(index) => Ok(Target::Source(index, ino)),
+            None => Err(err!(libc::ESTALE, "inode {} does not belong to any source", ino)),
+        }
+    }
+
+    /// Translate an inode number of the source at `index` into a union one.
+    fn union_ino(&self, index: usize, ino: InodeNo) -> InodeNo {
+        tag_ino(self.union_number(index, 0), self.stable_inode_numbers, ino)
+    }
+
+    /// Translate an inode or file handle number of the source at `index` into a union one.
+    fn union_number(&self, index: usize, number: u64) -> u64 {
+        if self.synthetic_root {
+            debug_assert!(number <= SOURCE_MASK, "source number {number} out of range");
+            ((index as u64 + 1) << SOURCE_SHIFT) | number
+        } else {
+            number
+        }
+    }
+
+    /// Find the source mounted at the given name in the synthetic root.
+    fn source_named(&self, name: &OsStr) -> Option<usize> {
+        self.sources.iter().position(|source| OsStr::new(&source.name) == name)
+    }
+
+    /// Record an operation on the source at `index` in the per-source metrics.
+    fn record<T>(&self, index: usize, op: &'static str, result: &Result<T, Error>) {
+        if !self.synthetic_root {
+            return;
+        }
+        let source = self.sources[index].name.clone();
+        metrics::counter!("fs.source.ops", "source" => source.clone(), "op" => op).increment(1);
+        if result.is_err() {
+            metrics::counter!("fs.source.op_failures", "source" => source, "op" => op).increment(1);
+        }
+    }
+
+    fn root_error(op: &str) -> Error {
+        err!(libc::EPERM, "{} is not supported in the root of a union mount", op)
+    }
+
+    /// Attributes of the root of the source at `index`, as seen from the union.
+    async fn source_root_attr(&self, index: usize) -> Result<Attr, Error> {
+        let mut attr = self.sources[index].fs.getattr(FUSE_ROOT_INODE).await?;
+        attr.attr.ino = self.union_ino(index, FUSE_ROOT_INODE);
+        Ok(attr)
+    }
+
+    /// Attributes of the synthetic root, which are the attributes of the first source's root.
+    async fn root_attr(&self) -> Result<Attr, Error> {
+        let mut attr = self.sources[0].fs.getattr(FUSE_ROOT_INODE).await?;
+        attr.attr.ino = FUSE_ROOT_INODE;
+        Ok(attr)
+    }
+
+    /// Entry for the synthetic root, which is the answer to lookups of `.` and `..` in it.
+    async fn root_entry(&self) -> Result<Entry, Error> {
+        let attr = self.root_attr().await?;
+        Ok(Entry {
+            ttl: attr.ttl,
+            attr: attr.attr,
+            generation: 0,
+        })
+    }
+
+    pub async fn init(&self, config: &mut KernelConfig) -> Result<(), libc::c_int> {
+        for source in &self.sources {
+            source.fs.init(config).await?;
+        }
+        Ok(())
+    }
+
+    pub fn destroy(&self) {
+        for source in &self.sources {
+            source.fs.destroy();
+        }
+    }
+
+    /// Send the entries and inodes to drop from the kernel's caches to `callback`, with the
+    /// numbers of the union.
+    pub fn on_invalidation(&self, callback: impl Fn(Invalidation) + Send + Sync + 'static) {
+        let callback = Arc::new(callback);
+        for (index, source) in self.sources.iter().enumerate() {
+            let callback = callback.clone();
+            let tag = self.union_number(index, 0);
+            let stable = self.stable_inode_numbers;
+            source.fs.on_invalidation(move |invalidation| {
+                callback(match invalidation {
+                    Invalidation::Inode(ino) => Invalidation::Inode(tag_ino(tag, stable, ino)),
+                    Invalidation::Entry { parent, name } => Invalidation::Entry {
+                        parent: tag_ino(tag, stable, parent),
+                        name,
+                    },
+                })
+            });
+        }
+    }
+
+    pub async fn lookup(&self, parent: InodeNo, name: &OsStr) -> Result<Entry, Error> {
+        let target = match self.target_inode(parent) {
+            // No source knows a stable inode it has forgotten, so ask each of them to resolve the
+            // NFS file handle
+            Err(_) if self.stable_inode_numbers && name == "." => return self.lookup_forgotten(parent).await,
+            target => target?,
+        };
+        match target {
+            Target::Root => {
+                trace!("union:lookup in root name {:?}", name);
+                // Lookups of `.` and `..` resolve NFS file handles, and the root is its own parent
+                if name == "." || name == ".." {
+                    return self.root_entry().await;
+                }
+                let Some(index) = self.source_named(name) else {
+                    return Err(err!(libc::ENOENT, Level::DEBUG, "no source with this name"));
+                };
+                let attr = self.source_root_attr(index).await?;
+                Ok(Entry {
+                    ttl: attr.ttl,
+                    attr: attr.attr,
+                    generation: 0,
+                })
+            }
+            Target::Source(index, parent) => {
+                if self.synthetic_root && parent == FUSE_ROOT_INODE && name == ".." {
+                    return self.root_entry().await;
+                }
+                let result = self.sources[index].fs.lookup(parent, name).await;
+                self.record(index, "lookup", &result);
+                let mut entry = result?;
+                entry.attr.ino = self.union_ino(index, entry.attr.ino);
+                Ok(entry)
+            }
+        }
+    }
+
+    /// Look up a stable inode that no source knows, in each source in turn.
+    async fn lookup_forgotten(&self, ino: InodeNo) -> Result<Entry, Error> {
+        let mut error = err!(libc::ESTALE, "inode {} does not belong to any source", ino);
+        for (index, source) in self.sources.iter().enumerate() {
+            match source.fs.lookup(ino, ".".as_ref()).await {
+                Ok(mut entry) => {
+                    entry.attr.ino = self.union_ino(index, entry.attr.ino);
+                    return Ok(entry);
+                }
+                Err(e) => error = e,
+            }
+        }
+        Err(error)
+    }
+
+    pub async fn getattr(&self, ino: InodeNo) -> Result<Attr, Error> {
+        match self.target_inode(ino)? {
+            Target::Root => self.root_attr().await,
+            Target::Source(index, ino) => {
+                let result = self.sources[index].fs.getattr(ino).await;
+                self.record(index, "getattr", &result);
+                let mut attr = result?;
+                attr.attr.ino = self.union_ino(index, attr.attr.ino);
+                Ok(attr)
+            }
+        }
+    }
+
+    pub async fn setattr(
+        &self,
+        ino: InodeNo,
+        atime: Option<OffsetDateTime>,
+        mtime: Option<OffsetDateTime>,
+        size: Option<u64>,
+        flags: Option<u32>,
+    ) -> Result<Attr, Error> {
+        match self.target_inode(ino)? {
+            Target::Root => Err(Self::root_error("setattr")),
+            Target::Source(index, ino) => {
+                let result = self.sources[index].fs.setattr(ino, atime, mtime, size, flags).await;
+                self.record(index, "setattr", &result);
+                let mut attr = result?;
+                attr.attr.ino = self.union_ino(index, attr.attr.ino);
+                Ok(attr)
+            }
+        }
+    }
+
+    pub async fn forget(&self, ino: InodeNo, n: u64) {
+        match self.target_inode(ino) {
+            Ok(Target::Source(index, ino)) => {
+                // The roots of the sources live as long as the union, so we don't pass their
+                // lookups on to the source (which would otherwise try to remove its root).
+                if self.synthetic_root && ino == FUSE_ROOT_INODE {
+                    return;
+                }
+                self.sources[index].fs.forget(ino, n).await;
+            }
+            Ok(Target::Root) => {}
+            Err(e) => tracing::error!("forget called on unknown inode {ino}: {e}"),
+        }
+    }
+
+    pub async fn open(&self, ino: InodeNo, flags: OpenFlags, pid: u32) -> Result<Opened, Error> {
+        match self.target_inode(ino)? {
+            Target::Root => Err(err!(libc::EISDIR, "cannot open the root directory as a file")),
+            Target::Source(index, ino) => {
+                let result = self.sources[index].fs.open(ino, flags, pid).await;
+                self.record(index, "open", &result);
+                let mut opened = result?;
+                opened.fh = self.union_number(index, opened.fh);
+                Ok(opened)
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)] // We don't get to choose this interface
+    pub async fn read(
+        &self,
+        ino: InodeNo,
+        fh: u64,
+        offset: i64,
+        size: u32,
+        flags: i32,
+        lock: Option<u64>,
+    ) -> Result<Bytes, Error> {
+        let Target::Source(index, fh) = self.target(fh)? else {
+            return Err(err!(libc::EBADF, "invalid file handle"));
+        };
+        let ino = self.inner_ino(index, ino)?;
+        let result = self.sources[index].fs.read(ino, fh, offset, size, flags, lock).await;
+        self.record(index, "read", &result);
+        if let (true, Ok(data)) = (self.synthetic_root, &result) {
+            let source = self.sources[index].name.clone();
+            metrics::counter!("fs.source.total_bytes", "source" => source, "type" => "read")
+                .increment(data.len() as u64);
+        }
+        result
+    }
+
+    pub async fn mknod(
+        &self,
+        parent: InodeNo,
+        name: &OsStr,
+        mode: libc::mode_t,
+        umask: u32,
+        rdev: u32,
+    ) -> Result<Entry, Error> {
+        match self.target_inode(parent)? {
+            Target::Root => Err(Self::root_error("mknod")),
+            Target::Source(index, parent) => {
+                let result = self.sources[index].fs.mknod(parent, name, mode, umask, rdev).await;
+                self.record(index, "mknod", &result);
+                let mut entry = result?;
+                entry.attr.ino = self.union_ino(index, entry.attr.ino);
+                Ok(entry)
+            }
+        }
+    }
+
+    pub async fn mkdir(&self, parent: InodeNo, name: &OsStr, mode: libc::mode_t, umask: u32) -> Result<Entry, Error> {
+        match self.target_inode(parent)? {
+            Target::Root => Err(Self::root_error("mkdir")),
+            Target::Source(index, parent) => {
+                let result = self.sources[index].fs.mkdir(parent, name, mode, umask).await;
+                self.record(index, "mkdir", &result);
+                let mut entry = result?;
+                entry.attr.ino = self.union_ino(index, entry.attr.ino);
+                Ok(entry)
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)] // We don't get to choose this interface
+    pub async fn write(
+        &self,
+        ino: InodeNo,
+        fh: u64,
+        offset: i64,
+        data: &[u8],
+        write_flags: u32,
+        flags: i32,
+        lock_owner: Option<u64>,
+    ) -> Result<u32, Error> {
+        let Target::Source(index, fh) = self.target(fh)? else {
+            return Err(err!(libc::EBADF, "invalid file handle"));
+        };
+        let ino = self.inner_ino(index, ino)?;
+        let result = self.sources[index]
+            .fs
+            .write(ino, fh, offset, data, write_flags, flags, lock_owner)
+            .await;
+        self.record(index, "write", &result);
+        if let (true, Ok(written)) = (self.synthetic_root, &result) {
+            let source = self.sources[index].name.clone();
+            metrics::counter!("fs.source.total_bytes", "source" => source, "type" => "write")
+                .increment(*written as u64);
+        }
+        result
+    }
+
+    pub async fn opendir(&self, parent: InodeNo, flags: i32) -> Result<Opened, Error> {
+        match self.target_inode(parent)? {
+            Target::Root => Ok(Opened {
+                fh: ROOT_DIR_HANDLE,
+                flags: 0,
+            }),
+            Target::Source(index, parent) => {
+                let result = self.sources[index].fs.opendir(parent, flags).await;
+                self.record(index, "opendir", &result);
+                let mut opened = result?;
+                opened.fh = self.union_number(index, opened.fh);
+                Ok(opened)
+            }
+        }
+    }
+
+    pub async fn readdir<R: DirectoryReplier>(
+        &self,
+        parent: InodeNo,
+        fh: u64,
+        offset: i64,
+        reply: R,
+    ) -> Result<R, Error> {
+        self.readdir_impl(parent, fh, offset, false, reply).await
+    }
+
+    pub async fn readdirplus<R: DirectoryReplier>(
+        &self,
+        parent: InodeNo,
+        fh: u64,
+        offset: i64,
+        reply: R,
+    ) -> Result<R, Error> {
+        self.readdir_impl(parent, fh, offset, true, reply).await
+    }
+
+    async fn readdir_impl<R: DirectoryReplier>(
+        &self,
+        parent: InodeNo,
+        fh: u64,
+        offset: i64,
+        is_readdirplus: bool,
+        reply: R,
+    ) -> Result<R, Error> {
+        let (index, fh) = match self.target(fh)? {
+            Target::Root => return self.readdir_root(offset, reply).await,
+            Target::Source(index, fh) => (index, fh),
+        };
+        let parent = self.inner_ino(index, parent)?;
+
+        /// Wrap a replier to translate the entries of a source into union ones
+        struct SourceReply<'a, R: DirectoryReplier, Client, Prefetcher>
+        where
+            Client: ObjectClient + Clone + Send + Sync + 'static,
+            Prefetcher: Prefetch,
+        {
+            reply: R,
+            union: &'a UnionFilesystem<Client, Prefetcher>,
+            index: usize,
+            parent: InodeNo,
+        }
+
+        impl<R: DirectoryReplier, Client, Prefetcher> DirectoryReplier for SourceReply<'_, R, Client, Prefetcher>
+        where
+            Client: ObjectClient + Clone + Send + Sync + 'static,
+            Prefetcher: Prefetch,
+        {
+            fn add(&mut self, mut entry: DirectoryEntry) -> bool {
+                if self.union.synthetic_root && self.parent == FUSE_ROOT_INODE && entry.name == ".." {
+                    // The parent of a source's root is the synthetic root
+                    entry.ino = FUSE_ROOT_INODE;
+                } else {
+                    entry.ino = self.union.union_ino(self.index, entry.ino);
+                }
+                entry.attr.ino = entry.ino;
+                self.reply.add(entry)
+            }
+        }
+
+        let reply = SourceReply {
+            reply,
+            union: self,
+            index,
+            parent,
+        };
+        let fs = &self.sources[index].fs;
+        let result = if is_readdirplus {
+            fs.readdirplus(parent, fh, offset, reply).await
+        } else {
+            fs.readdir(parent, fh, offset, reply).await
+        };
+        self.record(index, if is_readdirplus { "readdirplus" } else { "readdir" }, &result);
+        Ok(result?.reply)
+    }
+
+    /// List the synthetic root, whose entries after `.` and `..` are the sources in order, so the
+    /// offset of each entry is just its position in that list.
+    async fn readdir_root<R: DirectoryReplier>(&self, offset: i64, mut reply: R) -> Result<R, Error> {
+        trace!("union:readdir in root offset {:?}", offset);
+        let first = &self.sources[0].fs;
+        let root_lookup = first.superblock.getattr(&first.client, FUSE_ROOT_INODE, false).await?;
+        let mut root_attr = first.make_attr(&root_lookup);
+        root_attr.ino = FUSE_ROOT_INODE;
+
+        let start = offset.max(0) as usize;
+        for position in start..self.sources.len() + 2 {
+            let entry = match position {
+                0 | 1 => DirectoryEntry {
+                    ino: FUSE_ROOT_INODE,
+                    offset: position as i64 + 1,
+                    name: if position == 0 { ".".into() } else { "..".into() },
+                    attr: root_attr,
+                    generation: 0,
+                    ttl: root_lookup.validity(),
+                    lookup: root_lookup.clone(),
+                },
+                _ => {
+                    let index = position - 2;
+                    let fs = &self.sources[index].fs;
+                    let lookup = fs.superblock.getattr(&fs.client, FUSE_ROOT_INODE, false).await?;
+                    let mut attr = fs.make_attr(&lookup);
+                    attr.ino = self.union_ino(index, FUSE_ROOT_INODE);
+                    DirectoryEntry {
+                        ino: attr.ino,
+                        offset: position as i64 + 1,
+                        name: self.sources[index].name.clone().into(),
+                        attr,
+                        generation: 0,
+                        ttl: lookup.validity(),
+                        lookup,
+                    }
+                }
+            };
+            if reply.add(entry) {
+                break;
+            }
+        }
+        Ok(reply)
+    }
+
+    pub async fn fsync(&self, ino: InodeNo, fh: u64, datasync: bool) -> Result<(), Error> {
+        let Target::Source(index, fh) = self.target(fh)? else {
+            return Err(err!(libc::EBADF, "invalid file handle"));
+        };
+        let ino = self.inner_ino(index, ino)?;
+        let result = self.sources[index].fs.fsync(ino, fh, datasync).await;
+        self.record(index, "fsync", &result);
+        result
+    }
+
+    pub async fn flush(&self, ino: InodeNo, fh: u64, lock_owner: u64, pid: u32) -> Result<(), Error> {
+        let Target::Source(index, fh) = self.target(fh)? else {
+            return Err(err!(libc::EBADF, "invalid file handle"));
+        };
+        let ino = self.inner_ino(index, ino)?;
+        let result = self.sources[index].fs.flush(ino, fh, lock_owner, pid).await;
+        self.record(index, "flush", &result);
+        result
+    }
+
+    pub async fn release(
+        &self,
+        ino: InodeNo,
+        fh: u64,
+        flags: i32,
+        lock_owner: Option<u64>,
+        flush: bool,
+    ) -> Result<(), Error> {
+        let Target::Source(index, fh) = self.target(fh)? else {
+            return Err(err!(libc::EBADF, "invalid file handle"));
+        };
+        let ino = self.inner_ino(index, ino)?;
+        let result = self.sources[index].fs.release(ino, fh, flags, lock_owner, flush).await;
+        self.record(index, "release", &result);
+        result
+    }
+
+    pub async fn rmdir(&self, parent: InodeNo, name: &OsStr) -> Result<(), Error> {
+        match self.target_inode(parent)? {
+            Target::Root => Err(Self::root_error("rmdir")),
+            Target::Source(index, parent) => {
+                let result = self.sources[index].fs.rmdir(parent, name).await;
+                self.record(index, "rmdir", &result);
+                result
+            }
+        }
+    }
+
+    pub async fn releasedir(&self, ino: InodeNo, fh: u64, flags: i32) -> Result<(), Error> {
+        match self.target(fh)? {
+            Target::Root => Ok(()),
+            Target::Source(index, fh) => {
+                let ino = self.inner_ino(index, ino)?;
+                let result = self.sources[index].fs.releasedir(ino, fh, flags).await;
+                self.record(index, "releasedir", &result);
+                result
+            }
+        }
+    }
+
+    pub async fn unlink(&self, parent: InodeNo, name: &OsStr) -> Result<(), Error> {
+        match self.target_inode(parent)? {
+            Target::Root => Err(Self::root_error("unlink")),
+            Target::Source(index, parent) => {
+                let result = self.sources[index].fs.unlink(parent, name).await;
+                self.record(index, "unlink", &result);
+                result
+            }
+        }
+    }
+
+    pub async fn statfs(&self, ino: InodeNo) -> Result<StatFs, Error> {
+        match self.target_inode(ino)? {
+            Target::Root => self.sources[0].fs.statfs(FUSE_ROOT_INODE).await,
+            Target::Source(index, ino) => self.sources[index].fs.statfs(ino).await,
+        }
+    }
+
+    pub async fn getxattr(&self, ino: InodeNo, name: &OsStr) -> Result<Vec<u8>, Error> {
+        match self.target_inode(ino)? {
+            Target::Root => Err(no_such_xattr()),
+            Target::Source(index, ino) => {
+                let result = self.sources[index].fs.getxattr(ino, name).await;
+                self.record(index, "getxattr", &result);
+                result
+            }
+        }
+    }
+
+    pub async fn listxattr(&self, ino: InodeNo) -> Result<Vec<u8>, Error> {
+        match self.target_inode(ino)? {
+            Target::Root => Ok(Vec::new()),
+            Target::Source(index, ino) => {
+                let result = self.sources[index].fs.listxattr(ino).await;
+                self.record(index, "listxattr", &result);
+                result
+            }
+        }
+    }
+
+    pub async fn setxattr(&self, ino: InodeNo, name: &OsStr, value: &[u8], flags: i32) -> Result<(), Error> {
+        match self.target_inode(ino)? {
+            Target::Root => Err(Self::root_error("setxattr")),
+            Target::Source(index, ino) => {
+                let result = self.sources[index].fs.setxattr(ino, name, value, flags).await;
+                self.record(index, "setxattr", &result);
+                result
+            }
+        }
+    }
+
+    pub async fn removexattr(&self, ino: InodeNo, name: &OsStr) -> Result<(), Error> {
+        match self.target_inode(ino)? {
+            Target::Root => Err(no_such_xattr()),
+            Target::Source(index, ino) => {
+                let result = self.sources[index].fs.removexattr(ino, name).await;
+                self.record(index, "removexattr", &result);
+                result
+            }
+        }
+    }
+
+    /// Translate an inode number that must belong to the source at `index`, as the inode of an
+    /// operation on one of that source's file handles does.
+    fn inner_ino(&self, index: usize, ino: InodeNo) -> Result<InodeNo, Error> {
+        match self.target_inode(ino)? {
+            Target::Source(source, ino) if source == index => Ok(ino),
+            _ => Err(err!(libc::EBADF, "inode {} does not match file handle", ino)),
+        }
+    }
+}
+
+/// Tag an inode number of a source with `tag`, unless it's a stable inode number other than the
+/// source's root.
+fn tag_ino(tag: u64, stable: bool, ino: InodeNo) -> InodeNo {
+    if stable && ino != FUSE_ROOT_INODE {
+        ino
+    } else {
+        tag | ino
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::executor::ThreadPool;
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockObject};
+    use mountpoint_s3_client::types::ETag;
+
+    use crate::fs::S3FilesystemConfig;
+    use crate::prefetch::default_prefetch;
+    use crate::prefix::Prefix;
+
+    use super::*;
+
+    struct Names(Vec<(String, InodeNo)>);
+
+    impl DirectoryReplier for &mut Names {
+        fn add(&mut self, entry: DirectoryEntry) -> bool {
+            self.0.push((entry.name.to_string_lossy().into_owned(), entry.ino));
+            false
+        }
+    }
+
+    fn make_source(
+        name: &str,
+        bucket: &str,
+        prefix: &str,
+    ) -> (Arc<MockClient>, UnionSource<Arc<MockClient>, impl Prefetch>) {
+        make_source_with_config(name, bucket, prefix, S3FilesystemConfig::default())
+    }
+
+    fn make_source_with_config(
+        name: &str,
+        bucket: &str,
+        prefix: &str,
+        config: S3FilesystemConfig,
+    ) -> (Arc<MockClient>, UnionSource<Arc<MockClient>, impl Prefetch>) {
+        let client = Arc::new(MockClient::new(MockClientConfig {
+            bucket: bucket.to_owned(),
+            part_size: 1024 * 1024,
+            enable_backpressure: true,
+            initial_read_window_size: 1024 * 1024,
+            ..Default::default()
+        }));
+        let runtime = ThreadPool::builder().pool_size(1).create().unwrap();
+        let prefetcher = default_prefetch(runtime.clone(), Default::default());
+        let prefix = Prefix::new(prefix).expect("valid prefix");
+        let fs = S3Filesystem::new(client.clone(), prefetcher, runtime, bucket, &prefix, config);
+        (client, UnionSource::new(name, fs))
+    }
+
+    #[tokio::test]
+    async fn test_union_routing() {
+        let (raw_client, raw) = make_source("raw", "raw-bucket", "");
+        let (curated_client, curated) = make_source("curated", "curated-bucket", "tables/");
+        raw_client.add_object("a.txt", MockObject::constant(1u8, 10, ETag::for_tests()));
+        curated_client.add_object("tables/a.txt", MockObject::constant(2u8, 20, ETag::for_tests()));
+        curated_client.add_object("b.txt", MockObject::constant(3u8, 30, ETag::for_tests()));
+        let union = UnionFilesystem::new(vec![raw, curated]);
+
+        let mut names = Names(vec![]);
+        let opened = union.opendir(FUSE_ROOT_INODE, 0).await.unwrap();
+        union.readdir(FUSE_ROOT_INODE, opened.fh, 0, &mut names).await.unwrap();
+        let listed: Vec<_> = names.0.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(listed, [".", "..", "raw", "curated"]);
+        union.releasedir(FUSE_ROOT_INODE, opened.fh, 0).await.unwrap();
+
+        let raw_root = union.lookup(FUSE_ROOT_INODE, "raw".as_ref()).await.unwrap();
+        let curated_root = union.lookup(FUSE_ROOT_INODE, "curated".as_ref()).await.unwrap();
+        assert_ne!(raw_root.attr.ino, curated_root.attr.ino);
+        assert_eq!(names.0[2].1, raw_root.attr.ino);
+        union
+            .lookup(FUSE_ROOT_INODE, "missing".as_ref())
+            .await
+            .expect_err("unknown source should not exist");
+
+        // The same name in each source is a different file, in its own bucket and prefix
+        let raw_file = union.lookup(raw_root.attr.ino, "a.txt".as_ref()).await.unwrap();
+        let curated_file = union.lookup(curated_root.attr.ino, "a.txt".as_ref()).await.unwrap();
+        assert_ne!(raw_file.attr.ino, curated_file.attr.ino);
+        assert_eq!(raw_file.attr.size, 10);
+        assert_eq!(curated_file.attr.size, 20);
+        union
+            .lookup(curated_root.attr.ino, "b.txt".as_ref())
+            .await
+            .expect_err("keys outside the prefix should not be visible");
+
+        let attr = union.getattr(curated_file.attr.ino).await.unwrap();
+        assert_eq!(attr.attr.ino, curated_file.attr.ino);
+
+        let opened = union.open(curated_file.attr.ino, OpenFlags::empty(), 0).await.unwrap();
+        let data = union
+            .read(curated_file.attr.ino, opened.fh, 0, 20, 0, None)
+            .await
+            .unwrap();
+        assert_eq!(&data[..], &[2u8; 20][..]);
+        union
+            .read(raw_file.attr.ino, opened.fh, 0, 20, 0, None)
+            .await
+            .expect_err("file handle should not be usable with another source's inode");
+        union
+            .release(curated_file.attr.ino, opened.fh, 0, None, false)
+            .await
+            .unwrap();
+
+        // The parent of a source's root is the synthetic root
+        let mut names = Names(vec![]);
+        let opened = union.opendir(raw_root.attr.ino, 0).await.unwrap();
+        union
+            .readdir(raw_root.attr.ino, opened.fh, 0, &mut names)
+            .await
+            .unwrap();
+        assert_eq!(
+            names.0,
+            [
+                (".".to_owned(), raw_root.attr.ino),
+                ("..".to_owned(), FUSE_ROOT_INODE),
+                ("a.txt".to_owned(), raw_file.attr.ino),
+            ]
+        );
+
+        union
+            .mkdir(FUSE_ROOT_INODE, "new".as_ref(), libc::S_IFDIR, 0)
+            .await
+            .expect_err("can't create directories in the root");
+        union.forget(raw_root.attr.ino, 1).await;
+        union.lookup(raw_root.attr.ino, "a.txt".as_ref()).await.unwrap();
+    }
+
+    #[test]
+    fn test_union_numbers() {
+        let (_, first) = make_source("first", "first-bucket", "");
+        let (_, second) = make_source("second", "second-bucket", "");
+        let union = UnionFilesystem::new(vec![first, second]);
+
+        assert_eq!(union.target(FUSE_ROOT_INODE).unwrap(), Target::Root);
+        assert_eq!(union.target(ROOT_DIR_HANDLE).unwrap(), Target::Root);
+        for (index, number) in [(0, FUSE_ROOT_INODE), (0, 2), (1, FUSE_ROOT_INODE), (1, SOURCE_MASK)] {
+            let tagged = union.union_ino(index, number);
+            assert_eq!(tagged >> SOURCE_SHIFT, index as u64 + 1);
+            assert_eq!(union.target(tagged).unwrap(), Target::Source(index, number));
+        }
+        // Numbers of the same source never collide with each other across sources
+        assert_ne!(union.union_ino(0, 2), union.union_ino(1, 2));
+
+        // Untagged numbers and numbers tagged with a source that doesn't exist belong to nobody
+        for number in [2, SOURCE_MASK, 3 << SOURCE_SHIFT, u64::MAX] {
+            let err = union.target(number).expect_err("number should not belong to a source");
+            assert_eq!(err.errno, libc::EBADF);
+        }
+    }
+
+    #[test]
+    fn test_single_source_numbers() {
+        let (_, source) = make_source("", "bucket", "");
+        let union = UnionFilesystem::from(source.fs);
+
+        for number in [ROOT_DIR_HANDLE, FUSE_ROOT_INODE, 2, SOURCE_MASK + 1, u64::MAX] {
+            assert_eq!(union.union_ino(0, number), number);
+            assert_eq!(union.target(number).unwrap(), Target::Source(0, number));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_union_unknown_source() {
+        let (client, source) = make_source("source", "bucket", "");
+        client.add_object("a.txt", MockObject::constant(1u8, 10, ETag::for_tests()));
+        let union = UnionFilesystem::new(vec![source]);
+        let root = union.lookup(FUSE_ROOT_INODE, "source".as_ref()).await.unwrap();
+        let file = union.lookup(root.attr.ino, "a.txt".as_ref()).await.unwrap();
+
+        // A number tagged with the index of a source that doesn't exist
+        let unknown = union.union_ino(1, file.attr.ino & SOURCE_MASK);
+        let err = union.lookup(unknown, "a.txt".as_ref()).await.unwrap_err();
+        assert_eq!(err.errno, libc::EBADF);
+        let err = union.getattr(unknown).await.unwrap_err();
+        assert_eq!(err.errno, libc::EBADF);
+        let err = union.open(unknown, OpenFlags::empty(), 0).await.unwrap_err();
+        assert_eq!(err.errno, libc::EBADF);
+        let err = union.opendir(unknown, 0).await.unwrap_err();
+        assert_eq!(err.errno, libc::EBADF);
+        // Forgetting an unknown inode is logged and otherwise ignored
+        union.forget(unknown, 1).await;
+
+        let opened = union.open(file.attr.ino, OpenFlags::empty(), 0).await.unwrap();
+        let err = union.read(unknown, opened.fh, 0, 10, 0, None).await.unwrap_err();
+        assert_eq!(err.errno, libc::EBADF);
+        let err = union
+            .read(file.attr.ino, union.union_ino(1, 1), 0, 10, 0, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.errno, libc::EBADF);
+        // The synthetic root's directory handle is not a file handle
+        let err = union
+            .read(file.attr.ino, ROOT_DIR_HANDLE, 0, 10, 0, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.errno, libc::EBADF);
+        union.release(file.attr.ino, opened.fh, 0, None, false).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_union_stable_inode_numbers() {
+        let make_stable_source = |name: &str, bucket: &str| {
+            let config = S3FilesystemConfig {
+                stable_inode_numbers: true,
+                inode_number_namespace: name.to_owned(),
+                ..Default::default()
+            };
+            make_source_with_config(name, bucket, "", config)
+        };
+        let (first_client, first) = make_stable_source("first", "first-bucket");
+        let (second_client, second) = make_stable_source("second", "second-bucket");
+        first_client.add_object("a.txt", MockObject::constant(1u8, 10, ETag::for_tests()));
+        second_client.add_object("a.txt", MockObject::constant(2u8, 20, ETag::for_tests()));
+        let union = UnionFilesystem::new(vec![first, second]);
+
+        // The roots of the sources are still tagged
+        let first_root = union.lookup(FUSE_ROOT_INODE, "first".as_ref()).await.unwrap();
+        let second_root = union.lookup(FUSE_ROOT_INODE, "second".as_ref()).await.unwrap();
+        assert_eq!(first_root.attr.ino, union.union_ino(0, FUSE_ROOT_INODE));
+        assert_eq!(second_root.attr.ino, union.union_ino(1, FUSE_ROOT_INODE));
+
+        // Other numbers are passed through untagged, and differ between sources for the same key
+        let first_file = union.lookup(first_root.attr.ino, "a.txt".as_ref()).await.unwrap();
+        let second_file = union.lookup(second_root.attr.ino, "a.txt".as_ref()).await.unwrap();
+        assert_ne!(first_file.attr.ino, second_file.attr.ino);
+        let first_fs = &union.sources[0].fs;
+        assert!(first_fs.superblock.contains(first_file.attr.ino));
+        assert_eq!(union.getattr(first_file.attr.ino).await.unwrap().attr.size, 10);
+        assert_eq!(union.getattr(second_file.attr.ino).await.unwrap().attr.size, 20);
+
+        let mut names = Names(vec![]);
+        let opened = union.opendir(first_root.attr.ino, 0).await.unwrap();
+        union
+            .readdir(first_root.attr.ino, opened.fh, 0, &mut names)
+            .await
+            .unwrap();
+        union.releasedir(first_root.attr.ino, opened.fh, 0).await.unwrap();
+        assert_eq!(
+            names.0,
+            [
+                (".".to_owned(), first_root.attr.ino),
+                ("..".to_owned(), FUSE_ROOT_INODE),
+                ("a.txt".to_owned(), first_file.attr.ino),
+            ]
+        );
+
+        // Once every source has forgotten a number, it belongs to none of them
+        union.forget(second_file.attr.ino, 1).await;
+        let err = union.getattr(second_file.attr.ino).await.unwrap_err();
+        assert_eq!(err.errno, libc::ESTALE);
+        assert_eq!(union.getattr(first_file.attr.ino).await.unwrap().attr.size, 10);
+    }
+
+    #[tokio::test]
+    async fn test_union_root_operations() {
+        let (_, first) = make_source("first", "first-bucket", "");
+        let (_, second) = make_source("second", "second-bucket", "");
+        let union = UnionFilesystem::new(vec![first, second]);
+
+        let attr = union.getattr(FUSE_ROOT_INODE).await.unwrap();
+        assert_eq!(attr.attr.ino, FUSE_ROOT_INODE);
+        for name in [".", ".."] {
+            let entry = union.lookup(FUSE_ROOT_INODE, name.as_ref()).await.unwrap();
+            assert_eq!(entry.attr.ino, FUSE_ROOT_INODE);
+        }
+        let err = union.lookup(FUSE_ROOT_INODE, "third".as_ref()).await.unwrap_err();
+        assert_eq!(err.errno, libc::ENOENT);
+
+        // `..` of a source's root is the synthetic root
+        let second_root = union.lookup(FUSE_ROOT_INODE, "second".as_ref()).await.unwrap();
+        assert_eq!(second_root.attr.ino, union.union_ino(1, FUSE_ROOT_INODE));
+        let parent = union.lookup(second_root.attr.ino, "..".as_ref()).await.unwrap();
+        assert_eq!(parent.attr.ino, FUSE_ROOT_INODE);
+
+        let err = union.open(FUSE_ROOT_INODE, OpenFlags::empty(), 0).await.unwrap_err();
+        assert_eq!(err.errno, libc::EISDIR);
+        let err = union
+            .mknod(FUSE_ROOT_INODE, "file".as_ref(), libc::S_IFREG, 0, 0)
+            .await
+            .unwrap_err();
+        assert_eq!(err.errno, libc::EPERM);
+        let err = union.unlink(FUSE_ROOT_INODE, "first".as_ref()).await.unwrap_err();
+        assert_eq!(err.errno, libc::EPERM);
+        let err = union.rmdir(FUSE_ROOT_INODE, "first".as_ref()).await.unwrap_err();
+        assert_eq!(err.errno, libc::EPERM);
+        assert!(union.listxattr(FUSE_ROOT_INODE).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_union_readdir_root_offsets() {
+        let sources = ["a", "b", "c"].map(|name| make_source(name, &format!("{name}-bucket"), "").1);
+        let union = UnionFilesystem::new(sources.into());
+        let opened = union.opendir(FUSE_ROOT_INODE, 0).await.unwrap();
+        assert_eq!(opened.fh, ROOT_DIR_HANDLE);
+
+        /// Replier that accepts a limited number of entries, recording their offsets
+        struct Limited {
+            entries: Vec<(String, InodeNo, i64)>,
+            limit: usize,
+        }
+
+        impl DirectoryReplier for &mut Limited {
+            fn add(&mut self, entry: DirectoryEntry) -> bool {
+                if self.entries.len() == self.limit {
+                    return true;
+                }
+                let name = entry.name.to_string_lossy().into_owned();
+                self.entries.push((name, entry.ino, entry.offset));
+                false
+            }
+        }
+
+        // Page through the root two entries at a time, resuming from the last offset
+        let mut listed = vec![];
+        let mut offset = 0;
+        loop {
+            let mut reply = Limited {
+                entries: vec![],
+                limit: 2,
+            };
+            union
+                .readdirplus(FUSE_ROOT_INODE, opened.fh, offset, &mut reply)
+                .await
+                .unwrap();
+            let Some(&(_, _, last)) = reply.entries.last() else {
+                break;
+            };
+            offset = last;
+            listed.extend(reply.entries);
+        }
+        let expected: Vec<_> = [".", "..", "a", "b", "c"]
+            .into_iter()
+            .enumerate()
+            .map(|(position, name)| {
+                let ino = match position {
+                    0 | 1 => FUSE_ROOT_INODE,
+                    _ => union.union_ino(position - 2, FUSE_ROOT_INODE),
+                };
+                (name.to_owned(), ino, position as i64 + 1)
+            })
+            .collect();
+        assert_eq!(listed, expected);
+        union.releasedir(FUSE_ROOT_INODE, opened.fh, 0).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_union_write_routing() {
+        let (first_client, first) = make_source("first", "first-bucket", "");
+        let (second_client, second) = make_source("second", "second-bucket", "data/");
+        let union = UnionFilesystem::new(vec![first, second]);
+        let second_root = union.lookup(FUSE_ROOT_INODE, "second".as_ref()).await.unwrap();
+
+        let entry = union
+            .mknod(
+                second_root.attr.ino,
+                "new.txt".as_ref(),
+                libc::S_IFREG | libc::S_IRWXU,
+                0,
+                0,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            union.target(entry.attr.ino).unwrap(),
+            Target::Source(1, entry.attr.ino & SOURCE_MASK)
+        );
+        let opened = union.open(entry.attr.ino, OpenFlags::O_WRONLY, 0).await.unwrap();
+        assert_eq!(
+            union.target(opened.fh).unwrap(),
+            Target::Source(1, opened.fh & SOURCE_MASK)
+        );
+        let written = union
+            .write(entry.attr.ino, opened.fh, 0, &[4u8; 16], 0, 0, None)
+            .await
+            .unwrap();
+        assert_eq!(written, 16);
+        union.release(entry.attr.ino, opened.fh, 0, None, true).await.unwrap();
+
+        assert!(second_client.contains_key("data/new.txt"));
+        assert!(!first_client.contains_key("new.txt"));
+        assert!(!first_client.contains_key("data/new.txt"));
+        let attr = union.getattr(entry.attr.ino).await.unwrap();
+        assert_eq!(attr.attr.size, 16);
+    }
+}
@@ -8,7 +8,7 @@ use std::time::SystemTime;
 use time::OffsetDateTime;
 use tracing::{field, instrument, Instrument};
 
-use crate::fs::{DirectoryEntry, DirectoryReplier, InodeNo, S3Filesystem, ToErrno};
+use crate::fs::{DirectoryEntry, DirectoryReplier, InodeNo, S3Filesystem, ToErrno, UnionFilesystem};
 use crate::prefetch::Prefetch;
 #[cfg(target_os = "macos")]
 use fuser::ReplyXTimes;
@@ -60,14 +60,15 @@ macro_rules! fuse_unsupported {
     };
 }
 
-/// This is just a thin wrapper around [S3Filesystem] that implements the actual `fuser` protocol,
-/// so that we can test our actual filesystem implementation without having actual FUSE in the loop.
+/// This is just a thin wrapper around [S3Filesystem] (or a [UnionFilesystem] of several) that
+/// implements the actual `fuser` protocol, so that we can test our actual filesystem implementation
+/// without having actual FUSE in the loop.
 pub struct S3FuseFilesystem<Client, Prefetcher>
 where
     Client: ObjectClient + Clone + Send + Sync + 'static,
     Prefetcher: Prefetch,
 {
-    fs: S3Filesystem<Client, Prefetcher>,
+    fs: UnionFilesystem<Client, Prefetcher>,
 }
 
 impl<Client, Prefetcher> S3FuseFilesystem<Client, Prefetcher>
@@ -76,8 +77,45 @@ where
     Prefetcher: Prefetch,
 {
     pub fn new(fs: S3Filesystem<Client, Prefetcher>) -> Self {
+        Self { fs: fs.into() }
+    }
+
+    pub fn new_union(fs: UnionFilesystem<Client, Prefetcher>) -> Self {
         Self { fs }
     }
+
+    /// Start queueing the entries and inodes the file system asks to drop from the kernel's caches,
+    /// to be sent with [send_invalidations] once the FUSE session is created.
+    pub fn invalidations(&self) -> Receiver<Invalidation> {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        self.fs.on_invalidation(move |invalidation| {
+            // The receiver is gone once the session has ended, and then there's nothing to invalidate
+            let _ = sender.lock().unwrap().send(invalidation);
+        });
+        receiver
+    }
+}
+
+/// Send queued invalidations to the kernel from a background thread, which stops once the file
+/// system is dropped. They can't be sent while handling a FUSE request, since the kernel may be
+/// holding locks the invalidation needs until we reply.
+pub fn send_invalidations(invalidations: Receiver<Invalidation>, notifier: Notifier) -> io::Result<()> {
+    thread::Builder::new()
+        .name("fuse-invalidations".to_owned())
+        .spawn(move || {
+            for invalidation in invalidations {
+                let result = match &invalidation {
+                    // Drop both the attributes and all of the content
+                    Invalidation::Inode(ino) => notifier.inval_inode(*ino, 0, 0),
+                    Invalidation::Entry { parent, name } => notifier.inval_entry(*parent, OsStr::new(name)),
+                };
+                if let Err(e) = result {
+                    debug!(?invalidation, "failed to invalidate kernel cache: {e}");
+                }
+            }
+        })?;
+    Ok(())
 }
 
 impl<Client, Prefetcher> Filesystem for S3FuseFilesystem<Client, Prefetcher>
@@ -90,6 +128,11 @@ where
         block_on(self.fs.init(config).in_current_span())
     }
 
+    #[instrument(level = "warn", skip_all)]
+    fn destroy(&self) {
+        self.fs.destroy();
+    }
+
     #[instrument(level="warn", skip_all, fields(req=_req.unique(), ino=parent, name=?name))]
     fn lookup(&self, _req: &Request<'_>, parent: InodeNo, name: &OsStr, reply: ReplyEntry) {
         match block_on(self.fs.lookup(parent, name).in_current_span()) {
@@ -423,27 +466,39 @@ where
         _req: &Request<'_>,
         ino: u64,
         name: &OsStr,
-        _value: &[u8],
-        _flags: i32,
+        value: &[u8],
+        flags: i32,
         _position: u32,
         reply: ReplyEmpty,
     ) {
-        fuse_unsupported!("setxattr", reply);
+        match block_on(self.fs.setxattr(ino, name, value, flags).in_current_span()) {
+            Ok(()) => reply.ok(),
+            Err(e) => fuse_error!("setxattr", reply, e),
+        }
     }
 
     #[instrument(level="warn", skip_all, fields(req=_req.unique(), ino=ino, name=?name))]
-    fn getxattr(&self, _req: &Request<'_>, ino: u64, name: &OsStr, _size: u32, reply: ReplyXattr) {
-        fuse_unsupported!("getxattr", reply);
+    fn getxattr(&self, _req: &Request<'_>, ino: u64, name: &OsStr, size: u32, reply: ReplyXattr) {
+        match block_on(self.fs.getxattr(ino, name).in_current_span()) {
+            Ok(value) => reply_xattr(reply, size, &value),
+            Err(e) => fuse_error!("getxattr", reply, e),
+        }
     }
 
     #[instrument(level="warn", skip_all, fields(req=_req.unique(), ino=ino))]
-    fn listxattr(&self, _req: &Request<'_>, ino: u64, _size: u32, reply: ReplyXattr) {
-        fuse_unsupported!("listxattr", reply);
+    fn listxattr(&self, _req: &Request<'_>, ino: u64, size: u32, reply: ReplyXattr) {
+        match block_on(self.fs.listxattr(ino).in_current_span()) {
+            Ok(names) => reply_xattr(reply, size, &names),
+            Err(e) => fuse_error!("listxattr", reply, e),
+        }
     }
 
     #[instrument(level="warn", skip_all, fields(req=_req.unique(), ino=ino, name=?name))]
     fn removexattr(&self, _req: &Request<'_>, ino: u64, name: &OsStr, reply: ReplyEmpty) {
-        fuse_unsupported!("removexattr", reply);
+        match block_on(self.fs.removexattr(ino, name).in_current_span()) {
+            Ok(()) => reply.ok(),
+            Err(e) => fuse_error!("removexattr", reply, e),
+        }
     }
 
     #[instrument(level="warn", skip_all, fields(req=_req.unique(), ino=ino, mask=mask))]
@@ -598,3 +653,15 @@ where
         }
     }
 }
+
+/// Reply to a `getxattr` or `listxattr` call. A `size` of zero asks for the size of the value,
+/// otherwise the value is returned if it fits in `size` bytes.
+fn reply_xattr(reply: ReplyXattr, size: u32, value: &[u8]) {
+    if size == 0 {
+        reply.size(value.len() as u32);
+    } else if value.len() > size as usize {
+        reply.error(libc::ERANGE);
+    } else {
+        reply.data(value);
+    }
+}
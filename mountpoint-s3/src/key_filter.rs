@@ -0,0 +1,245 @@
+//! Filters that hide some of the keys in the bucket from the file system.
+//!
+//! Keys are matched relative to the mount prefix, and the keys of directories end in `/`.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use regex::Regex;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("invalid pattern {pattern:?}")]
+pub struct KeyPatternError {
+    pattern: String,
+    #[source]
+    source: regex::Error,
+}
+
+/// Include and exclude patterns deciding which keys are visible in the file system.
+///
+/// A directory is visible unless it matches an exclude pattern. A file is visible if it matches
+/// none of the exclude patterns and, if there are any include patterns, at least one of them.
+#[derive(Debug, Clone, Default)]
+pub struct KeyFilter {
+    include: Vec<KeyPattern>,
+    exclude: Vec<KeyPattern>,
+}
+
+impl KeyFilter {
+    pub fn new(include: Vec<KeyPattern>, exclude: Vec<KeyPattern>) -> Self {
+        Self { include, exclude }
+    }
+
+    /// Does this filter show every key?
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Is the given key, relative to the mount prefix, visible in the file system? The keys of
+    /// directories must end in `/`.
+    pub fn is_visible(&self, key: &str) -> bool {
+        if self.exclude.iter().any(|pattern| pattern.matches(key)) {
+            return false;
+        }
+        key.ends_with('/') || self.include.is_empty() || self.include.iter().any(|pattern| pattern.matches(key))
+    }
+
+    /// The include and exclude patterns of this filter, as they were given
+    pub fn patterns(&self) -> (Vec<String>, Vec<String>) {
+        let to_strings = |patterns: &[KeyPattern]| patterns.iter().map(|pattern| pattern.to_string()).collect();
+        (to_strings(&self.include), to_strings(&self.exclude))
+    }
+}
+
+/// A pattern matching keys, relative to the mount prefix.
+///
+/// Patterns are globs by default, where `*` and `?` match any characters except `/`, `**` matches
+/// any characters including `/`, and `[...]` matches a set of characters. As in `.gitignore`
+/// files, a glob with no `/` (other than a trailing one) matches the name of a file or directory at
+/// any depth, any other glob matches the whole key, and a glob ending in `/` only matches
+/// directories. Patterns prefixed with `regex:` are instead regular expressions, searched for in
+/// the whole key.
+#[derive(Debug, Clone)]
+pub struct KeyPattern {
+    pattern: String,
+    regex: Regex,
+    kind: KeyPatternKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyPatternKind {
+    /// A glob matched against the last component of the key
+    NameGlob { directory_only: bool },
+    /// A glob matched against the whole key
+    PathGlob { directory_only: bool },
+    /// A regular expression searched for in the whole key
+    Regex,
+}
+
+impl KeyPattern {
+    pub fn new(pattern: &str) -> Result<Self, KeyPatternError> {
+        let (regex, kind) = match pattern.strip_prefix("regex:") {
+            Some(regex) => (regex.to_owned(), KeyPatternKind::Regex),
+            None => {
+                let (glob, directory_only) = match pattern.strip_suffix('/') {
+                    Some(glob) => (glob, true),
+                    None => (pattern, false),
+                };
+                // A leading `/` anchors the glob to the mount prefix, like any other `/`
+                let kind = if glob.contains('/') {
+                    KeyPatternKind::PathGlob { directory_only }
+                } else {
+                    KeyPatternKind::NameGlob { directory_only }
+                };
+                (glob_to_regex(glob.strip_prefix('/').unwrap_or(glob)), kind)
+            }
+        };
+        let regex = Regex::new(&regex).map_err(|source| KeyPatternError {
+            pattern: pattern.to_owned(),
+            source,
+        })?;
+        Ok(Self {
+            pattern: pattern.to_owned(),
+            regex,
+            kind,
+        })
+    }
+
+    /// Does this pattern match the given key? The keys of directories end in `/`.
+    fn matches(&self, key: &str) -> bool {
+        let is_directory = key.ends_with('/');
+        let path = key.trim_end_matches('/');
+        match self.kind {
+            KeyPatternKind::Regex => self.regex.is_match(key),
+            KeyPatternKind::NameGlob { directory_only } => {
+                let name = path.rsplit('/').next().unwrap_or(path);
+                (is_directory || !directory_only) && self.regex.is_match(name)
+            }
+            KeyPatternKind::PathGlob { directory_only } => {
+                (is_directory || !directory_only) && self.regex.is_match(path)
+            }
+        }
+    }
+}
+
+impl FromStr for KeyPattern {
+    type Err = KeyPatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        KeyPattern::new(s)
+    }
+}
+
+impl Display for KeyPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+/// Translate a glob into an anchored regular expression.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    // `**/` matches any number of directories, including none
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                let rest: String = chars.clone().collect();
+                match rest.find(']') {
+                    // `[]` and `[!]` aren't character sets, so the `[` is a literal
+                    Some(end) if end > usize::from(rest.starts_with('!')) => {
+                        let set = &rest[..end];
+                        regex.push('[');
+                        let set = match set.strip_prefix('!') {
+                            Some(set) => {
+                                regex.push('^');
+                                set
+                            }
+                            None => set,
+                        };
+                        for c in set.chars() {
+                            if matches!(c, '\\' | '[' | ']' | '^' | '&' | '~') {
+                                regex.push('\\');
+                            }
+                            regex.push(c);
+                        }
+                        regex.push(']');
+                        for _ in 0..=end {
+                            chars.next();
+                        }
+                    }
+                    _ => regex.push_str(r"\["),
+                }
+            }
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("*.crc", "part-0.crc", true; "name glob matches file")]
+    #[test_case("*.crc", "a/b/.part-0.crc", true; "name glob matches at any depth")]
+    #[test_case("*.crc", "a.crc/", true; "name glob matches directory")]
+    #[test_case("*.crc", "a.crc/b", false; "name glob does not match file in directory")]
+    #[test_case("_temporary/", "a/_temporary/", true; "directory glob matches directory")]
+    #[test_case("_temporary/", "a/_temporary", false; "directory glob does not match file")]
+    #[test_case("/logs/", "logs/", true; "anchored glob matches at root")]
+    #[test_case("/logs/", "a/logs/", false; "anchored glob does not match deeper")]
+    #[test_case("data/*.csv", "data/a.csv", true; "path glob matches key")]
+    #[test_case("data/*.csv", "data/x/a.csv", false; "star does not match slash")]
+    #[test_case("data/**/*.csv", "data/a.csv", true; "double star matches no directories")]
+    #[test_case("data/**/*.csv", "data/x/y/a.csv", true; "double star matches many directories")]
+    #[test_case("data/**", "data/x/y", true; "trailing double star matches everything")]
+    #[test_case("part-?.parquet", "part-1.parquet", true; "question mark matches one character")]
+    #[test_case("part-[0-4].parquet", "part-3.parquet", true; "character set matches")]
+    #[test_case("part-[!0-4].parquet", "part-3.parquet", false; "negated character set does not match")]
+    #[test_case("a[b", "a[b", true; "unterminated bracket is literal")]
+    #[test_case("a.b", "axb", false; "dot is literal")]
+    #[test_case("regex:\\.spark-staging", "out/.spark-staging-1/", true; "regex searches whole key")]
+    #[test_case("regex:^logs/", "a/logs/", false; "anchored regex")]
+    fn test_pattern(pattern: &str, key: &str, expected: bool) {
+        let pattern = KeyPattern::new(pattern).expect("pattern should be valid");
+        assert_eq!(pattern.matches(key), expected, "{pattern:?} against {key:?}");
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        KeyPattern::new("regex:(").expect_err("invalid regex should fail");
+    }
+
+    #[test]
+    fn test_filter() {
+        let patterns = |patterns: &[&str]| patterns.iter().map(|p| p.parse().unwrap()).collect();
+        let filter = KeyFilter::new(patterns(&["*.parquet"]), patterns(&["_temporary/", "*.crc"]));
+
+        assert!(filter.is_visible("a/part-0.parquet"));
+        assert!(!filter.is_visible("a/part-0.csv"));
+        assert!(!filter.is_visible("a/part-0.parquet.crc"));
+        // Include patterns only apply to files
+        assert!(filter.is_visible("a/"));
+        assert!(!filter.is_visible("a/_temporary/"));
+
+        let filter = KeyFilter::default();
+        assert!(filter.is_empty());
+        assert!(filter.is_visible("a/part-0.csv"));
+    }
+}
@@ -1,12 +1,15 @@
 mod async_util;
 pub mod autoconfigure;
 mod build_info;
+pub mod change_feed;
 mod checksums;
 pub mod cli;
 pub mod data_cache;
 pub mod fs;
 pub mod fuse;
+pub mod key_filter;
 pub mod logging;
+pub mod manifest;
 pub mod mem_limiter;
 pub mod metrics;
 pub mod object;
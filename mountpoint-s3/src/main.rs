@@ -1,3 +1,8 @@
+use mountpoint_s3::cli::{self, list_uploads, Command};
+
 fn main() -> anyhow::Result<()> {
-    mountpoint_s3::cli::main(mountpoint_s3::cli::create_s3_client)
+    match Command::parse() {
+        Command::Mount(args) => cli::main(*args, cli::create_s3_client),
+        Command::ListUploads(args) => list_uploads::main(args, list_uploads::create_s3_client),
+    }
 }
@@ -0,0 +1,941 @@
+//! Manifests listing the objects in a bucket, to serve the namespace of a read-only mount without
+//! listing the bucket.
+//!
+//! A manifest is either a JSON lines file, with one `{"key": ..., "size": ..., "etag": ...,
+//! "last_modified": ...}` object per line (the last modified time being optional), a CSV file whose
+//! first line names its columns, or an S3 Inventory report. An S3 Inventory report is described by
+//! its `manifest.json` file, which lists the gzipped CSV files of the report and names their
+//! columns in its `fileSchema`, since the CSV files have no header. Files ending in `.gz` are
+//! decompressed as they are read. The objects are kept sorted by key in a compact index, so that
+//! lookups and listings are binary searches.
+
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use flate2::write::GzDecoder;
+use futures::{pin_mut, StreamExt};
+use mountpoint_s3_client::types::{ClientBackpressureHandle, GetObjectParams, GetObjectResponse};
+use mountpoint_s3_client::ObjectClient;
+use percent_encoding::percent_decode_str;
+use serde::Deserialize;
+use thiserror::Error;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tracing::warn;
+
+#[derive(Debug, Error)]
+pub enum ManifestError {
+    #[error("I/O error reading manifest")]
+    IoError(#[from] io::Error),
+
+    #[error("failed to download manifest from S3")]
+    ClientError(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("invalid manifest line {line}: {message}")]
+    InvalidLine { line: usize, message: String },
+
+    #[error("invalid S3 Inventory manifest: {0}")]
+    InvalidInventoryManifest(String),
+
+    #[error("{0} manifests are not supported; use a CSV S3 Inventory report or a JSON lines manifest")]
+    UnsupportedFormat(String),
+}
+
+/// Where to read a manifest from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestLocation {
+    Local(PathBuf),
+    S3 { bucket: String, key: String },
+}
+
+impl ManifestLocation {
+    /// The path or key of the file, whose extension gives its format
+    fn name(&self) -> Cow<'_, str> {
+        match self {
+            Self::Local(path) => path.to_string_lossy(),
+            Self::S3 { key, .. } => Cow::Borrowed(key),
+        }
+    }
+}
+
+impl FromStr for ManifestLocation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(uri) = s.strip_prefix("s3://") else {
+            return Ok(Self::Local(PathBuf::from(s)));
+        };
+        match uri.split_once('/') {
+            Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => Ok(Self::S3 {
+                bucket: bucket.to_owned(),
+                key: key.to_owned(),
+            }),
+            _ => Err(format!("expected an S3 URI of the form s3://<BUCKET>/<KEY>, got {s:?}")),
+        }
+    }
+}
+
+/// The format of a manifest, from the extension of its file name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    JsonLines,
+    /// CSV with the given columns, or whose first line names the columns
+    Csv(Option<CsvColumns>),
+}
+
+impl ManifestFormat {
+    /// The format of a file with the given name, ignoring any `.gz` extension
+    fn for_name(name: &str, csv_columns: Option<CsvColumns>) -> Result<Self, ManifestError> {
+        let name = name.to_ascii_lowercase();
+        let name = name.strip_suffix(".gz").unwrap_or(&name);
+        if name.ends_with(".csv") {
+            Ok(Self::Csv(csv_columns))
+        } else if name.ends_with(".parquet") {
+            Err(ManifestError::UnsupportedFormat("Apache Parquet".to_owned()))
+        } else if name.ends_with(".orc") {
+            Err(ManifestError::UnsupportedFormat("Apache ORC".to_owned()))
+        } else if csv_columns.is_some() {
+            Err(ManifestError::InvalidInventoryManifest(format!(
+                "S3 Inventory manifests describe CSV files, but {name:?} is not a .csv or .csv.gz file"
+            )))
+        } else {
+            Ok(Self::JsonLines)
+        }
+    }
+}
+
+/// Is the file with the given name compressed with gzip?
+fn is_gzip(name: &str) -> bool {
+    name.to_ascii_lowercase().ends_with(".gz")
+}
+
+/// An object listed in a manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestObject {
+    pub key: String,
+    pub size: u64,
+    pub last_modified: Option<OffsetDateTime>,
+    pub etag: String,
+}
+
+/// A page of the objects and common prefixes directly under a prefix, as a ListObjectsV2 request
+/// with the delimiter `/` would return them.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ManifestListing {
+    pub objects: Vec<ManifestObject>,
+    pub common_prefixes: Vec<String>,
+    /// Key to start the next page at, if there are more entries
+    pub next_start_at: Option<String>,
+}
+
+/// The objects listed in a manifest, sorted by key.
+pub struct Manifest {
+    /// The keys and ETags of every object, back to back
+    data: String,
+    entries: Vec<ManifestEntry>,
+}
+
+/// An object in the index, whose key and ETag are stored in [Manifest::data]. Kept small, since
+/// there can be hundreds of millions of them.
+#[derive(Debug, Clone, Copy)]
+struct ManifestEntry {
+    /// Offset of the key in the data, followed by the ETag
+    offset: usize,
+    key_len: u32,
+    etag_len: u32,
+    size: u64,
+    /// As a Unix timestamp in nanoseconds, or [NO_LAST_MODIFIED]
+    last_modified: i64,
+}
+
+const NO_LAST_MODIFIED: i64 = i64::MIN;
+
+impl Manifest {
+    /// Read the manifest at the given location, using the client for manifests in S3.
+    pub async fn load(client: &impl ObjectClient, location: &ManifestLocation) -> Result<Self, ManifestError> {
+        let mut builder = ManifestBuilder::new();
+        let format = ManifestFormat::for_name(&location.name(), None)?;
+        read_file(client, location, format, &mut builder).await?;
+        Ok(builder.build())
+    }
+
+    /// Read every CSV file of the S3 Inventory report described by the `manifest.json` file at the
+    /// given location. The CSV files are read from the inventory's destination bucket with the
+    /// client, and decompressed as they download.
+    pub async fn load_inventory(
+        client: &impl ObjectClient,
+        location: &ManifestLocation,
+    ) -> Result<Self, ManifestError> {
+        let mut manifest_json = Vec::new();
+        match location {
+            ManifestLocation::Local(path) => manifest_json = std::fs::read(path)?,
+            ManifestLocation::S3 { bucket, key } => {
+                download(client, bucket, key, |body| {
+                    manifest_json.extend_from_slice(body);
+                    Ok(())
+                })
+                .await?
+            }
+        }
+        let inventory = InventoryManifest::parse(&manifest_json)?;
+        let columns = inventory.columns()?;
+        let bucket = inventory.destination_bucket()?;
+        let mut builder = ManifestBuilder::new();
+        for file in &inventory.files {
+            let location = ManifestLocation::S3 {
+                bucket: bucket.to_owned(),
+                key: file.key.clone(),
+            };
+            let format = ManifestFormat::for_name(&file.key, Some(columns))?;
+            read_file(client, &location, format, &mut builder).await?;
+        }
+        Ok(builder.build())
+    }
+
+    pub fn from_reader(reader: impl BufRead, format: ManifestFormat) -> Result<Self, ManifestError> {
+        let mut builder = ManifestBuilder::new();
+        let mut file = builder.file(format);
+        for line in reader.lines() {
+            file.add_line(&line?)?;
+        }
+        Ok(builder.build())
+    }
+
+    /// Number of objects in the manifest
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The object with the given key, if any
+    pub fn get(&self, key: &str) -> Option<ManifestObject> {
+        let entry = self.entries.get(self.lower_bound(key))?;
+        (self.key(entry) == key).then(|| self.object(entry))
+    }
+
+    /// Does any object have a key starting with the given prefix?
+    pub fn contains_prefix(&self, prefix: &str) -> bool {
+        self.entries
+            .get(self.lower_bound(prefix))
+            .is_some_and(|entry| self.key(entry).starts_with(prefix))
+    }
+
+    /// List up to `max_keys` objects and common prefixes directly under the given prefix, starting
+    /// at the given key (as returned by a previous listing) or at the start of the prefix.
+    pub fn list(&self, prefix: &str, start_at: Option<&str>, max_keys: usize) -> ManifestListing {
+        let mut listing = ManifestListing::default();
+        let mut index = self.lower_bound(start_at.unwrap_or(prefix));
+        while let Some(entry) = self.entries.get(index) {
+            let key = self.key(entry);
+            if !key.starts_with(prefix) {
+                break;
+            }
+            if listing.objects.len() + listing.common_prefixes.len() >= max_keys {
+                listing.next_start_at = Some(key.to_owned());
+                break;
+            }
+            match key[prefix.len()..].find('/') {
+                Some(delimiter) => {
+                    // Skip every other key with the same common prefix
+                    let common_prefix = &key[..prefix.len() + delimiter + 1];
+                    index = self.entries.partition_point(|entry| {
+                        let key = self.key(entry);
+                        key < common_prefix || key.starts_with(common_prefix)
+                    });
+                    listing.common_prefixes.push(common_prefix.to_owned());
+                }
+                None => {
+                    listing.objects.push(self.object(entry));
+                    index += 1;
+                }
+            }
+        }
+        listing
+    }
+
+    /// Index of the first entry whose key is not less than the given key
+    fn lower_bound(&self, key: &str) -> usize {
+        self.entries.partition_point(|entry| self.key(entry) < key)
+    }
+
+    fn key(&self, entry: &ManifestEntry) -> &str {
+        &self.data[entry.offset..entry.offset + entry.key_len as usize]
+    }
+
+    fn object(&self, entry: &ManifestEntry) -> ManifestObject {
+        let etag_offset = entry.offset + entry.key_len as usize;
+        ManifestObject {
+            key: self.key(entry).to_owned(),
+            size: entry.size,
+            last_modified: match entry.last_modified {
+                NO_LAST_MODIFIED => None,
+                nanos => OffsetDateTime::from_unix_timestamp_nanos(nanos.into()).ok(),
+            },
+            etag: self.data[etag_offset..etag_offset + entry.etag_len as usize].to_owned(),
+        }
+    }
+}
+
+impl Debug for Manifest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Manifest")
+            .field("objects", &self.entries.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A line of a JSON lines manifest
+#[derive(Deserialize)]
+struct JsonLine {
+    key: String,
+    size: u64,
+    etag: String,
+    #[serde(default)]
+    last_modified: Option<String>,
+}
+
+/// The `manifest.json` file of an S3 Inventory report, of which we only need the format and schema
+/// of the report, and where its files are.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InventoryManifest {
+    file_format: String,
+    file_schema: String,
+    #[serde(default)]
+    destination_bucket: String,
+    #[serde(default)]
+    files: Vec<InventoryFile>,
+}
+
+#[derive(Deserialize)]
+struct InventoryFile {
+    key: String,
+}
+
+impl InventoryManifest {
+    fn parse(manifest_json: &[u8]) -> Result<Self, ManifestError> {
+        let manifest: Self = serde_json::from_slice(manifest_json)
+            .map_err(|e| ManifestError::InvalidInventoryManifest(e.to_string()))?;
+        match manifest.file_format.to_ascii_uppercase().as_str() {
+            "CSV" => Ok(manifest),
+            "PARQUET" => Err(ManifestError::UnsupportedFormat("Apache Parquet".to_owned())),
+            "ORC" => Err(ManifestError::UnsupportedFormat("Apache ORC".to_owned())),
+            _ => Err(ManifestError::InvalidInventoryManifest(format!(
+                "unknown file format {}",
+                manifest.file_format
+            ))),
+        }
+    }
+
+    fn columns(&self) -> Result<CsvColumns, ManifestError> {
+        let names: Vec<_> = self.file_schema.split(',').collect();
+        CsvColumns::from_names(&names).ok_or_else(|| {
+            ManifestError::InvalidInventoryManifest("the fileSchema must include Key, Size, and ETag".to_owned())
+        })
+    }
+
+    /// The name of the bucket the report was delivered to, which the manifest gives as an ARN
+    fn destination_bucket(&self) -> Result<&str, ManifestError> {
+        let bucket = self.destination_bucket.rsplit(':').next().unwrap_or_default();
+        if bucket.is_empty() {
+            return Err(ManifestError::InvalidInventoryManifest(
+                "the destinationBucket is missing".to_owned(),
+            ));
+        }
+        Ok(bucket)
+    }
+}
+
+/// Positions of the columns of a CSV manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvColumns {
+    key: usize,
+    size: usize,
+    last_modified: Option<usize>,
+    etag: usize,
+}
+
+impl CsvColumns {
+    /// Find the columns from their names, which are the names of the fields of S3 Inventory
+    /// reports. Returns `None` if a required column is missing.
+    fn from_names<S: AsRef<str>>(names: &[S]) -> Option<Self> {
+        let position = |name: &str| names.iter().position(|field| field.as_ref().trim() == name);
+        Some(Self {
+            key: position("Key")?,
+            size: position("Size")?,
+            last_modified: position("LastModifiedDate"),
+            etag: position("ETag")?,
+        })
+    }
+
+    /// Find the columns from the `fileSchema` of an S3 Inventory `manifest.json` file, which must
+    /// describe a CSV report.
+    pub fn from_inventory_manifest(manifest_json: &[u8]) -> Result<Self, ManifestError> {
+        InventoryManifest::parse(manifest_json)?.columns()
+    }
+}
+
+/// Builds the index of a manifest from the lines of one or more files, which can come in any order.
+struct ManifestBuilder {
+    data: String,
+    entries: Vec<ManifestEntry>,
+}
+
+impl ManifestBuilder {
+    fn new() -> Self {
+        Self {
+            data: String::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Start adding the lines of a file in the given format
+    fn file(&mut self, format: ManifestFormat) -> ManifestFileBuilder<'_> {
+        let csv_columns = match format {
+            ManifestFormat::Csv(csv_columns) => csv_columns,
+            ManifestFormat::JsonLines => None,
+        };
+        ManifestFileBuilder {
+            builder: self,
+            format,
+            csv_columns,
+            line: 0,
+        }
+    }
+
+    fn push(&mut self, object: ManifestObject) {
+        let offset = self.data.len();
+        self.data.push_str(&object.key);
+        self.data.push_str(&object.etag);
+        self.entries.push(ManifestEntry {
+            offset,
+            key_len: object.key.len() as u32,
+            etag_len: object.etag.len() as u32,
+            size: object.size,
+            last_modified: object
+                .last_modified
+                .and_then(|time| time.unix_timestamp_nanos().try_into().ok())
+                .unwrap_or(NO_LAST_MODIFIED),
+        });
+    }
+
+    fn build(mut self) -> Manifest {
+        let data = &self.data;
+        let key = |entry: &ManifestEntry| &data[entry.offset..entry.offset + entry.key_len as usize];
+        self.entries.sort_by(|a, b| key(a).cmp(key(b)));
+        let count = self.entries.len();
+        self.entries.dedup_by(|a, b| key(a) == key(b));
+        if self.entries.len() < count {
+            warn!(
+                duplicates = count - self.entries.len(),
+                "manifest lists some keys more than once; using their first line"
+            );
+        }
+        self.entries.shrink_to_fit();
+        self.data.shrink_to_fit();
+        Manifest {
+            data: self.data,
+            entries: self.entries,
+        }
+    }
+}
+
+/// Adds the lines of one file of a manifest to a [ManifestBuilder]
+struct ManifestFileBuilder<'a> {
+    builder: &'a mut ManifestBuilder,
+    format: ManifestFormat,
+    csv_columns: Option<CsvColumns>,
+    line: usize,
+}
+
+impl ManifestFileBuilder<'_> {
+    fn add_bytes(&mut self, line: &[u8]) -> Result<(), ManifestError> {
+        match std::str::from_utf8(line) {
+            Ok(line) => self.add_line(line),
+            Err(_) => Err(self.invalid("not valid UTF-8")),
+        }
+    }
+
+    fn add_line(&mut self, line: &str) -> Result<(), ManifestError> {
+        self.line += 1;
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        let object = match self.format {
+            ManifestFormat::JsonLines => self.parse_json_line(line)?,
+            ManifestFormat::Csv(_) => match self.parse_csv_line(line)? {
+                Some(object) => object,
+                None => return Ok(()),
+            },
+        };
+
+        self.builder.push(object);
+        Ok(())
+    }
+
+    fn parse_json_line(&self, line: &str) -> Result<ManifestObject, ManifestError> {
+        let parsed: JsonLine = serde_json::from_str(line).map_err(|e| self.invalid(e))?;
+        let last_modified = parsed.last_modified.map(|time| self.parse_time(&time)).transpose()?;
+        Ok(ManifestObject {
+            key: parsed.key,
+            size: parsed.size,
+            last_modified,
+            etag: parsed.etag,
+        })
+    }
+
+    /// Parse a line of a CSV manifest. Unless the columns were given, the first line names them,
+    /// with the names of the fields of S3 Inventory reports. Keys are URL-encoded like in those
+    /// reports.
+    fn parse_csv_line(&mut self, line: &str) -> Result<Option<ManifestObject>, ManifestError> {
+        let fields = split_csv_line(line).ok_or_else(|| self.invalid("unterminated quoted field"))?;
+        let Some(columns) = &self.csv_columns else {
+            let Some(columns) = CsvColumns::from_names(&fields) else {
+                return Err(self.invalid(
+                    "the first line must name the columns, including Key, Size, and ETag, \
+                     unless an S3 Inventory manifest names them",
+                ));
+            };
+            self.csv_columns = Some(columns);
+            return Ok(None);
+        };
+
+        let field = |index: usize| fields.get(index).ok_or_else(|| self.invalid("missing field"));
+        let key = decode_key(field(columns.key)?).ok_or_else(|| self.invalid("key is not valid UTF-8"))?;
+        let size = field(columns.size)?
+            .parse()
+            .map_err(|_| self.invalid("size is not a number"))?;
+        let last_modified = match columns.last_modified {
+            Some(index) => Some(self.parse_time(field(index)?)?),
+            None => None,
+        };
+        let etag = field(columns.etag)?.clone();
+        Ok(Some(ManifestObject {
+            key,
+            size,
+            last_modified,
+            etag,
+        }))
+    }
+
+    fn parse_time(&self, time: &str) -> Result<OffsetDateTime, ManifestError> {
+        OffsetDateTime::parse(time, &Rfc3339).map_err(|e| self.invalid(e))
+    }
+
+    fn invalid(&self, message: impl ToString) -> ManifestError {
+        ManifestError::InvalidLine {
+            line: self.line,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Splits the bytes of a manifest file into lines as they arrive, decompressing them first if the
+/// file is gzipped.
+enum FileSink<'a> {
+    Plain(LineSplitter<'a>),
+    Gzip(GzDecoder<LineSplitter<'a>>),
+}
+
+impl<'a> FileSink<'a> {
+    fn new(builder: &'a mut ManifestBuilder, format: ManifestFormat, gzip: bool) -> Self {
+        let splitter = LineSplitter {
+            file: builder.file(format),
+            pending: Vec::new(),
+        };
+        if gzip {
+            Self::Gzip(GzDecoder::new(splitter))
+        } else {
+            Self::Plain(splitter)
+        }
+    }
+
+    /// Add the last line of the file, which may not end with a newline
+    fn finish(self) -> Result<(), ManifestError> {
+        let mut splitter = match self {
+            Self::Plain(splitter) => splitter,
+            Self::Gzip(decoder) => decoder.finish().map_err(manifest_error)?,
+        };
+        let pending = std::mem::take(&mut splitter.pending);
+        splitter.file.add_bytes(&pending)
+    }
+}
+
+impl Write for FileSink<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(splitter) => splitter.write(buf),
+            Self::Gzip(decoder) => decoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct LineSplitter<'a> {
+    file: ManifestFileBuilder<'a>,
+    /// The start of a line whose end hasn't arrived yet
+    pending: Vec<u8>,
+}
+
+impl Write for LineSplitter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let mut start = 0;
+        while let Some(newline) = self.pending[start..].iter().position(|&byte| byte == b'\n') {
+            self.file
+                .add_bytes(&self.pending[start..start + newline])
+                .map_err(io::Error::other)?;
+            start += newline + 1;
+        }
+        self.pending.drain(..start);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Recover a [ManifestError] that a [LineSplitter] returned as an [io::Error]
+fn manifest_error(e: io::Error) -> ManifestError {
+    if !e.get_ref().is_some_and(|inner| inner.is::<ManifestError>()) {
+        return ManifestError::IoError(e);
+    }
+    match e.into_inner().map(|inner| inner.downcast::<ManifestError>()) {
+        Some(Ok(inner)) => *inner,
+        _ => unreachable!("the inner error is a ManifestError"),
+    }
+}
+
+/// Read one file of a manifest from the given location into the builder.
+async fn read_file(
+    client: &impl ObjectClient,
+    location: &ManifestLocation,
+    format: ManifestFormat,
+    builder: &mut ManifestBuilder,
+) -> Result<(), ManifestError> {
+    let mut sink = FileSink::new(builder, format, is_gzip(&location.name()));
+    match location {
+        ManifestLocation::Local(path) => {
+            io::copy(&mut File::open(path)?, &mut sink).map_err(manifest_error)?;
+        }
+        ManifestLocation::S3 { bucket, key } => {
+            download(client, bucket, key, |body| sink.write_all(body).map_err(manifest_error)).await?;
+        }
+    }
+    sink.finish()
+}
+
+/// Download an object from S3, passing each part of its body to `f` as it arrives.
+async fn download(
+    client: &impl ObjectClient,
+    bucket: &str,
+    key: &str,
+    mut f: impl FnMut(&[u8]) -> Result<(), ManifestError>,
+) -> Result<(), ManifestError> {
+    let mut response = client
+        .get_object(bucket, key, &GetObjectParams::new())
+        .await
+        .map_err(|e| ManifestError::ClientError(e.into()))?;
+    let mut backpressure_handle = response.backpressure_handle().cloned();
+    pin_mut!(response);
+    while let Some(part) = response.next().await {
+        let (_offset, body) = part.map_err(|e| ManifestError::ClientError(e.into()))?;
+        if let Some(handle) = backpressure_handle.as_mut() {
+            handle.increment_read_window(body.len());
+        }
+        f(&body)?;
+    }
+    Ok(())
+}
+
+/// Decode a key URL-encoded like in S3 Inventory reports and event notifications, where a space
+/// is encoded as `+`. Returns `None` if the decoded key is not valid UTF-8.
+pub(crate) fn decode_key(encoded: &str) -> Option<String> {
+    let key = encoded.replace('+', " ");
+    let key = percent_decode_str(&key).decode_utf8().ok()?;
+    Some(key.into_owned())
+}
+
+/// Split a line of CSV into its fields, which can be quoted. Returns `None` if a quoted field is
+/// not terminated.
+fn split_csv_line(line: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockObject};
+
+    use super::*;
+
+    fn keys(listing: &ManifestListing) -> (Vec<&str>, Vec<&str>) {
+        let objects = listing.objects.iter().map(|object| object.key.as_str()).collect();
+        let prefixes = listing.common_prefixes.iter().map(String::as_str).collect();
+        (objects, prefixes)
+    }
+
+    #[test]
+    fn test_json_lines_manifest() {
+        let lines = r#"{"key": "dir/b", "size": 2, "etag": "\"etag-b\"", "last_modified": "2024-11-01T12:00:00Z"}
+{"key": "dir-1/c", "size": 3, "etag": "\"etag-c\""}
+
+{"key": "a", "size": 1, "etag": "\"etag-a\""}
+{"key": "dir/sub/d", "size": 4, "etag": "\"etag-d\""}
+{"key": "dir/sub/e", "size": 5, "etag": "\"etag-e\""}
+"#;
+        let manifest = Manifest::from_reader(lines.as_bytes(), ManifestFormat::JsonLines).unwrap();
+        assert_eq!(manifest.len(), 5);
+
+        let object = manifest.get("dir/b").unwrap();
+        assert_eq!(object.size, 2);
+        assert_eq!(object.etag, "\"etag-b\"");
+        assert_eq!(
+            object.last_modified,
+            Some(OffsetDateTime::parse("2024-11-01T12:00:00Z", &Rfc3339).unwrap())
+        );
+        assert_eq!(manifest.get("dir-1/c").unwrap().last_modified, None);
+        assert_eq!(manifest.get("dir"), None);
+
+        assert!(manifest.contains_prefix("dir/"));
+        assert!(manifest.contains_prefix("dir/sub/"));
+        assert!(!manifest.contains_prefix("dir/b/"));
+
+        let listing = manifest.list("", None, 100);
+        assert_eq!(keys(&listing), (vec!["a"], vec!["dir-1/", "dir/"]));
+        let listing = manifest.list("dir/", None, 100);
+        assert_eq!(keys(&listing), (vec!["dir/b"], vec!["dir/sub/"]));
+        assert_eq!(listing.next_start_at, None);
+    }
+
+    #[test]
+    fn test_list_pages() {
+        let lines = ["a/1", "a/2", "b", "c/1", "c/2", "d"]
+            .iter()
+            .map(|key| format!(r#"{{"key": "{key}", "size": 0, "etag": "etag"}}"#))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let manifest = Manifest::from_reader(lines.as_bytes(), ManifestFormat::JsonLines).unwrap();
+
+        let mut start_at = None;
+        let mut pages = Vec::new();
+        loop {
+            let listing = manifest.list("", start_at.as_deref(), 2);
+            pages.push(keys(&listing).0.len() + keys(&listing).1.len());
+            start_at = listing.next_start_at;
+            if start_at.is_none() {
+                break;
+            }
+        }
+        assert_eq!(pages, vec![2, 2]);
+        assert_eq!(keys(&manifest.list("", Some("c/1"), 10)), (vec!["d"], vec!["c/"]),);
+    }
+
+    #[test]
+    fn test_csv_manifest() {
+        let lines = "\"Bucket\",\"Key\",\"Size\",\"LastModifiedDate\",\"ETag\"
+\"bucket\",\"dir/my+file%2B1.txt\",\"10\",\"2024-11-01T12:00:00.000Z\",\"abc\"
+\"bucket\",\"quoted\"\"key\",\"20\",\"2024-11-01T12:00:00.000Z\",\"def\"
+";
+        let manifest = Manifest::from_reader(lines.as_bytes(), ManifestFormat::Csv(None)).unwrap();
+        let object = manifest.get("dir/my file+1.txt").unwrap();
+        assert_eq!(object.size, 10);
+        assert_eq!(object.etag, "abc");
+        assert_eq!(manifest.get("quoted\"key").unwrap().size, 20);
+
+        let err =
+            Manifest::from_reader("\"bucket\",\"key\",\"10\"\n".as_bytes(), ManifestFormat::Csv(None)).unwrap_err();
+        assert!(matches!(err, ManifestError::InvalidLine { line: 1, .. }));
+    }
+
+    #[test]
+    fn test_inventory_csv_manifest() {
+        // S3 Inventory CSV files have no header, and are described by the inventory's manifest.json
+        let inventory_manifest = r#"{
+            "sourceBucket": "bucket",
+            "destinationBucket": "arn:aws:s3:::inventory-bucket",
+            "version": "2016-11-30",
+            "creationTimestamp": "1730462400000",
+            "fileFormat": "CSV",
+            "fileSchema": "Bucket, Key, Size, LastModifiedDate, ETag, StorageClass",
+            "files": [{"key": "data/1.csv.gz", "size": 100, "MD5checksum": "abc"}]
+        }"#;
+        let columns = CsvColumns::from_inventory_manifest(inventory_manifest.as_bytes()).unwrap();
+        let lines = "\"bucket\",\"a\",\"10\",\"2024-11-01T12:00:00.000Z\",\"abc\",\"STANDARD\"
+\"bucket\",\"dir/b\",\"20\",\"2024-11-01T12:00:00.000Z\",\"def\",\"STANDARD\"
+";
+        let manifest = Manifest::from_reader(lines.as_bytes(), ManifestFormat::Csv(Some(columns))).unwrap();
+        assert_eq!(manifest.len(), 2);
+        assert_eq!(manifest.get("a").unwrap().size, 10);
+        assert_eq!(manifest.get("dir/b").unwrap().etag, "def");
+
+        let format = ManifestFormat::for_name("inventory.CSV", Some(columns)).unwrap();
+        assert_eq!(format, ManifestFormat::Csv(Some(columns)));
+        let err = ManifestFormat::for_name("manifest.jsonl", Some(columns)).unwrap_err();
+        assert!(matches!(err, ManifestError::InvalidInventoryManifest(_)));
+
+        for inventory_manifest in [
+            r#"{"fileFormat": "CSV", "fileSchema": "Bucket, Key, LastModifiedDate"}"#,
+            r#"{"fileFormat": "CSV"}"#,
+            r#"{"fileFormat": "JSON", "fileSchema": "Bucket, Key, Size, ETag"}"#,
+        ] {
+            let err = CsvColumns::from_inventory_manifest(inventory_manifest.as_bytes()).unwrap_err();
+            assert!(matches!(err, ManifestError::InvalidInventoryManifest(_)));
+        }
+    }
+
+    #[test]
+    fn test_unsupported_formats() {
+        for inventory_manifest in [
+            r#"{"fileFormat": "Parquet", "fileSchema": "message s3.inventory { required binary bucket; }"}"#,
+            r#"{"fileFormat": "ORC", "fileSchema": "struct<bucket:string,key:string>"}"#,
+        ] {
+            let err = CsvColumns::from_inventory_manifest(inventory_manifest.as_bytes()).unwrap_err();
+            assert!(matches!(err, ManifestError::UnsupportedFormat(_)), "{err:?}");
+        }
+        for name in ["data/1.parquet", "data/1.orc", "data/1.ORC.gz"] {
+            let err = ManifestFormat::for_name(name, None).unwrap_err();
+            assert!(matches!(err, ManifestError::UnsupportedFormat(_)), "{err:?}");
+        }
+        assert_eq!(
+            ManifestFormat::for_name("data/1.csv.gz", None).unwrap(),
+            ManifestFormat::Csv(None)
+        );
+    }
+
+    fn gzip(content: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_load_inventory_report() {
+        // Small parts, so that each gzipped file is decompressed across several parts
+        let client = MockClient::new(MockClientConfig {
+            bucket: "inventory-bucket".to_owned(),
+            part_size: 16,
+            ..Default::default()
+        });
+        let files = [
+            (
+                "bucket/config/data/1.csv.gz",
+                "\"bucket\",\"a\",\"10\",\"2024-11-01T12:00:00.000Z\",\"abc\",\"STANDARD\"
+\"bucket\",\"dir/b\",\"20\",\"2024-11-01T12:00:00.000Z\",\"def\",\"STANDARD\"
+",
+            ),
+            (
+                "bucket/config/data/2.csv.gz",
+                "\"bucket\",\"dir/c+d\",\"30\",\"2024-11-01T12:00:00.000Z\",\"ghi\",\"GLACIER\"
+",
+            ),
+            (
+                "bucket/config/data/3.csv.gz",
+                // The last line of a file may not end with a newline
+                "\"bucket\",\"e\",\"40\",\"2024-11-01T12:00:00.000Z\",\"jkl\",\"STANDARD\"",
+            ),
+        ];
+        for (key, content) in files {
+            client.add_object(key, MockObject::from(gzip(content)));
+        }
+        let inventory_manifest = r#"{
+            "sourceBucket": "bucket",
+            "destinationBucket": "arn:aws:s3:::inventory-bucket",
+            "version": "2016-11-30",
+            "creationTimestamp": "1730462400000",
+            "fileFormat": "CSV",
+            "fileSchema": "Bucket, Key, Size, LastModifiedDate, ETag, StorageClass",
+            "files": [
+                {"key": "bucket/config/data/1.csv.gz", "size": 82, "MD5checksum": "8ee1b9b9c5b2d4c0f0b4e3e7a1d6f2c9"},
+                {"key": "bucket/config/data/2.csv.gz", "size": 71, "MD5checksum": "5d41402abc4b2a76b9719d911017c592"},
+                {"key": "bucket/config/data/3.csv.gz", "size": 68, "MD5checksum": "7d793037a0760186574b0282f2f435e7"}
+            ]
+        }"#;
+        client.add_object(
+            "bucket/config/2024-11-01T01-00Z/manifest.json",
+            MockObject::from(inventory_manifest),
+        );
+        let local_manifest = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(local_manifest.path(), inventory_manifest).unwrap();
+
+        for location in [
+            ManifestLocation::S3 {
+                bucket: "inventory-bucket".to_owned(),
+                key: "bucket/config/2024-11-01T01-00Z/manifest.json".to_owned(),
+            },
+            ManifestLocation::Local(local_manifest.path().to_owned()),
+        ] {
+            let manifest = Manifest::load_inventory(&client, &location).await.unwrap();
+            assert_eq!(manifest.len(), 4);
+            assert_eq!(manifest.get("a").unwrap().size, 10);
+            assert_eq!(manifest.get("dir/b").unwrap().etag, "def");
+            assert_eq!(manifest.get("dir/c d").unwrap().size, 30);
+            assert_eq!(manifest.get("e").unwrap().etag, "jkl");
+            assert_eq!(
+                keys(&manifest.list("dir/", None, 10)),
+                (vec!["dir/b", "dir/c d"], vec![])
+            );
+        }
+
+        // A bad line in any file fails the whole report
+        client.add_object(
+            "bucket/config/data/2.csv.gz",
+            MockObject::from(gzip(
+                "\"bucket\",\"f\",\"not a size\",\"2024-11-01T12:00:00.000Z\",\"mno\",\"STANDARD\"\n",
+            )),
+        );
+        let err = Manifest::load_inventory(&client, &ManifestLocation::Local(local_manifest.path().to_owned()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ManifestError::InvalidLine { line: 1, .. }), "{err:?}");
+    }
+
+    #[test]
+    fn test_manifest_location() {
+        assert_eq!(
+            "s3://bucket/path/manifest.csv".parse(),
+            Ok(ManifestLocation::S3 {
+                bucket: "bucket".to_owned(),
+                key: "path/manifest.csv".to_owned(),
+            })
+        );
+        assert_eq!(
+            "manifest.jsonl".parse(),
+            Ok(ManifestLocation::Local(PathBuf::from("manifest.jsonl")))
+        );
+        assert!("s3://bucket".parse::<ManifestLocation>().is_err());
+    }
+}
@@ -36,6 +36,7 @@ mod caching_stream;
 mod part;
 mod part_queue;
 mod part_stream;
+mod read_verifier;
 mod seek_window;
 mod task;
 
@@ -46,11 +47,13 @@ use async_trait::async_trait;
 use futures::task::Spawn;
 use metrics::{counter, histogram};
 use mountpoint_s3_client::error::{GetObjectError, ObjectClientError};
+use mountpoint_s3_client::types::{ChecksumAlgorithm, ChecksumMode, HeadObjectParams, SseCustomerKey};
 use mountpoint_s3_client::ObjectClient;
 use part::PartOperationError;
 use part_stream::RequestTaskConfig;
+use read_verifier::ReadVerifier;
 use thiserror::Error;
-use tracing::trace;
+use tracing::{trace, warn};
 
 use crate::checksums::{ChecksummedBytes, IntegrityError};
 use crate::data_cache::DataCache;
@@ -61,12 +64,14 @@ use crate::prefetch::part_stream::{ClientPartStream, ObjectPartStream, RequestRa
 use crate::prefetch::seek_window::SeekWindow;
 use crate::prefetch::task::RequestTask;
 use crate::sync::Arc;
+use crate::upload::ChecksumHasherError;
 
 /// Generic interface to handle reading data from an object.
 pub trait Prefetch {
     type PrefetchResult<Client: ObjectClient + Clone + Send + Sync + 'static>: PrefetchResult<Client>;
 
-    /// Start a new prefetch request to the specified object.
+    /// Start a new prefetch request to the specified object. If the object is encrypted with a
+    /// customer-provided key (SSE-C), `sse_customer_key` must be that key.
     fn prefetch<Client>(
         &self,
         client: Client,
@@ -74,6 +79,7 @@ pub trait Prefetch {
         bucket: String,
         object_id: ObjectId,
         size: u64,
+        sse_customer_key: Option<SseCustomerKey>,
     ) -> Self::PrefetchResult<Client>
     where
         Client: ObjectClient + Clone + Send + Sync + 'static;
@@ -114,6 +120,16 @@ pub enum PrefetchReadError<E> {
 
     #[error("read window increment failed")]
     ReadWindowIncrement,
+
+    #[error("object checksum mismatch: S3 has {algorithm} {expected}, but data read has {actual}")]
+    ChecksumMismatch {
+        algorithm: ChecksumAlgorithm,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("failed to compute object checksum")]
+    ChecksumComputationFailed(#[from] ChecksumHasherError),
 }
 
 pub type DefaultPrefetcher<Runtime> = Prefetcher<ClientPartStream<Runtime>>;
@@ -157,6 +173,8 @@ pub struct PrefetcherConfig {
     /// The maximum distance the prefetcher will seek backwards before resetting and starting a new
     /// S3 request. We keep this much data in memory in addition to any inflight requests.
     pub max_backward_seek_distance: u64,
+    /// Verify reads of a whole object, from start to end, against the full object checksum stored by S3
+    pub verify_reads: bool,
 }
 
 impl Default for PrefetcherConfig {
@@ -172,6 +190,7 @@ impl Default for PrefetcherConfig {
             // just start a new request instead.
             max_forward_seek_wait_distance: 16 * 1024 * 1024,
             max_backward_seek_distance: 1 * 1024 * 1024,
+            verify_reads: false,
         }
     }
 }
@@ -243,6 +262,7 @@ where
         bucket: String,
         object_id: ObjectId,
         size: u64,
+        sse_customer_key: Option<SseCustomerKey>,
     ) -> Self::PrefetchResult<Client>
     where
         Client: ObjectClient + Clone + Send + Sync + 'static,
@@ -255,6 +275,7 @@ where
             bucket,
             object_id,
             size,
+            sse_customer_key,
         )
     }
 }
@@ -280,6 +301,9 @@ pub struct PrefetchGetObject<Stream: ObjectPartStream, Client: ObjectClient> {
     next_sequential_read_offset: u64,
     next_request_offset: u64,
     size: u64,
+    sse_customer_key: Option<SseCustomerKey>,
+    /// Checks the data against the object checksum, while it is read sequentially from the start of the object
+    read_verifier: Option<ReadVerifier>,
 }
 
 #[async_trait]
@@ -316,6 +340,7 @@ where
     Client: ObjectClient + Clone + Send + Sync + 'static,
 {
     /// Create and spawn a new prefetching request for an object
+    #[allow(clippy::too_many_arguments)]
     fn new(
         client: Client,
         part_stream: Arc<Stream>,
@@ -324,6 +349,7 @@ where
         bucket: String,
         object_id: ObjectId,
         size: u64,
+        sse_customer_key: Option<SseCustomerKey>,
     ) -> Self {
         PrefetchGetObject {
             client,
@@ -339,6 +365,8 @@ where
             bucket,
             object_id,
             size,
+            sse_customer_key,
+            read_verifier: None,
         }
     }
 
@@ -385,6 +413,9 @@ where
         assert_eq!(self.next_sequential_read_offset, offset);
 
         if self.backpressure_task.is_none() {
+            if self.config.verify_reads && offset == 0 {
+                self.read_verifier = self.start_read_verifier().await?;
+            }
             self.backpressure_task = Some(self.spawn_read_backpressure_request()?);
         }
 
@@ -399,6 +430,7 @@ where
             let part = current_task.read(to_read as usize).await?;
             self.backward_seek_window.push(part.clone());
             let part_bytes = part.into_bytes(&self.object_id, self.next_sequential_read_offset)?;
+            self.verify_read(self.next_sequential_read_offset, &part_bytes)?;
 
             self.next_sequential_read_offset += part_bytes.len() as u64;
             // If we can complete the read with just a single buffer, early return to avoid copying
@@ -416,6 +448,66 @@ where
         Ok(response)
     }
 
+    /// Look up the checksum of the object to verify a read of the whole object.
+    ///
+    /// We use a HeadObject request, as S3 does not return the object checksum for the ranged GetObject requests
+    /// made by the prefetcher. If the object has no full object checksum, reads are not verified.
+    async fn start_read_verifier(&self) -> Result<Option<ReadVerifier>, PrefetchReadError<Client::ClientError>> {
+        let params = HeadObjectParams::new()
+            .checksum_mode(Some(ChecksumMode::Enabled))
+            .sse_customer_key(self.sse_customer_key.clone())
+            .version_id(self.object_id.version_id().map(str::to_owned));
+        let head_object = match self
+            .client
+            .head_object(&self.bucket, self.object_id.key(), &params)
+            .await
+        {
+            Ok(head_object) => head_object,
+            Err(e) => {
+                warn!(key=self.object_id.key(), error=?e, "HeadObject request failed, read will not be verified");
+                counter!("prefetch.verify_reads", "result" => "unavailable").increment(1);
+                return Ok(None);
+            }
+        };
+        // If the object changed, the GetObject request will fail anyway.
+        if &head_object.etag != self.object_id.etag() {
+            return Ok(None);
+        }
+        let verifier = ReadVerifier::new(&head_object.checksum)?;
+        if verifier.is_none() {
+            trace!(
+                key = self.object_id.key(),
+                "no full object checksum, read will not be verified"
+            );
+            counter!("prefetch.verify_reads", "result" => "unavailable").increment(1);
+        }
+        Ok(verifier)
+    }
+
+    /// Pass the data read at the given offset to the read verifier, if any, and check the object checksum once all
+    /// the object has been read.
+    fn verify_read(
+        &mut self,
+        offset: u64,
+        bytes: &ChecksummedBytes,
+    ) -> Result<(), PrefetchReadError<Client::ClientError>> {
+        let Some(verifier) = self.read_verifier.as_mut() else {
+            return Ok(());
+        };
+        let data = bytes.clone().into_bytes()?;
+        if !verifier.update(offset, &data)? {
+            trace!(offset, "read skipped part of the object, read will not be verified");
+            self.read_verifier = None;
+            return Ok(());
+        }
+        if verifier.next_offset() == self.size {
+            if let Some(verifier) = self.read_verifier.take() {
+                verifier.finish()?;
+            }
+        }
+        Ok(())
+    }
+
     /// Spawn a backpressure GetObject request which has a range from current offset to the end of the file.
     /// We will be using flow-control window to control how much data we want to download into the prefetcher.
     fn spawn_read_backpressure_request(
@@ -447,6 +539,7 @@ where
             initial_read_window_size,
             max_read_window_size: self.config.max_read_window_size,
             read_window_size_multiplier: self.config.sequential_prefetch_multiplier,
+            sse_customer_key: self.sse_customer_key.clone(),
         };
         Ok(self
             .part_stream
@@ -456,6 +549,7 @@ where
     /// Reset this prefetch request to a new offset, clearing any existing tasks queued.
     fn reset_prefetch_to_offset(&mut self, offset: u64) {
         self.backpressure_task = None;
+        self.read_verifier = None;
         self.backward_seek_window.clear();
         self.sequential_read_start_offset = offset;
         self.next_sequential_read_offset = offset;
@@ -565,10 +659,11 @@ mod tests {
     use super::caching_stream::CachingPartStream;
     use super::*;
     use futures::executor::{block_on, ThreadPool};
+    use mountpoint_s3_client::checksums::{crc64, crc64nvme_to_base64};
     use mountpoint_s3_client::error::GetObjectError;
     use mountpoint_s3_client::failure_client::{countdown_failure_client, CountdownFailureConfig, RequestFailureMap};
     use mountpoint_s3_client::mock_client::{ramp_bytes, MockClient, MockClientConfig, MockClientError, MockObject};
-    use mountpoint_s3_client::types::ETag;
+    use mountpoint_s3_client::types::{Checksum, ETag};
     use proptest::proptest;
     use proptest::strategy::{Just, Strategy};
     use proptest_derive::Arbitrary;
@@ -632,11 +727,19 @@ mod tests {
             read_timeout: Duration::from_secs(5),
             max_forward_seek_wait_distance: test_config.max_forward_seek_wait_distance,
             max_backward_seek_distance: test_config.max_backward_seek_distance,
+            verify_reads: false,
         };
 
         let prefetcher = Prefetcher::new(part_stream, prefetcher_config);
         let object_id = ObjectId::new("hello".to_owned(), etag);
-        let mut request = prefetcher.prefetch(client, mem_limiter.into(), "test-bucket".to_owned(), object_id, size);
+        let mut request = prefetcher.prefetch(
+            client,
+            mem_limiter.into(),
+            "test-bucket".to_owned(),
+            object_id,
+            size,
+            None,
+        );
 
         let mut next_offset = 0;
         loop {
@@ -707,6 +810,62 @@ mod tests {
         run_sequential_read_test(part_stream, 256 * 1024 * 1024 + 111, 1024 * 1024, config);
     }
 
+    #[test_case(default_stream(), true; "default stream, matching checksum")]
+    #[test_case(default_stream(), false; "default stream, mismatched checksum")]
+    #[test_case(caching_stream(1 * MB), true; "caching stream, matching checksum")]
+    #[test_case(caching_stream(1 * MB), false; "caching stream, mismatched checksum")]
+    fn sequential_read_verified<Stream>(part_stream: Stream, checksum_matches: bool)
+    where
+        Stream: ObjectPartStream + Send + Sync + 'static,
+    {
+        let config = MockClientConfig {
+            bucket: "test-bucket".to_string(),
+            part_size: 8 * MB,
+            enable_backpressure: true,
+            initial_read_window_size: 256 * 1024,
+            ..Default::default()
+        };
+        let client = Arc::new(MockClient::new(config));
+        let mem_limiter = MemoryLimiter::new(client.clone(), MINIMUM_MEM_LIMIT);
+        let size = 4 * MB + 111;
+        let mut object = MockObject::ramp(0xaa, size, ETag::for_tests());
+        let seed = if checksum_matches { 0xaa } else { 0xab };
+        let mut checksum = Checksum::empty();
+        checksum.checksum_crc64nvme = Some(crc64nvme_to_base64(&crc64::checksum(&ramp_bytes(seed, size))));
+        object.set_checksum(checksum);
+        let etag = object.etag();
+
+        client.add_object("hello", object);
+
+        let prefetcher_config = PrefetcherConfig {
+            verify_reads: true,
+            ..Default::default()
+        };
+        let prefetcher = Prefetcher::new(part_stream, prefetcher_config);
+        let object_id = ObjectId::new("hello".to_owned(), etag);
+        let mut request = prefetcher.prefetch(
+            client,
+            mem_limiter.into(),
+            "test-bucket".to_owned(),
+            object_id,
+            size as u64,
+            None,
+        );
+
+        let read_size = 1 * MB;
+        let mut next_offset = 0;
+        while next_offset + (read_size as u64) < size as u64 {
+            let buf = block_on(request.read(next_offset, read_size)).expect("only the last read should fail");
+            next_offset += buf.len() as u64;
+        }
+        let result = block_on(request.read(next_offset, read_size));
+        if checksum_matches {
+            assert_eq!(result.unwrap().len() as u64, size as u64 - next_offset);
+        } else {
+            assert!(matches!(result, Err(PrefetchReadError::ChecksumMismatch { .. })));
+        }
+    }
+
     fn fail_with_backpressure_precondition_test<Stream>(
         part_stream: Stream,
         test_config: TestConfig,
@@ -735,6 +894,7 @@ mod tests {
             "test-bucket".to_owned(),
             object_id,
             object_size as u64,
+            None,
         );
         let result = block_on(request.read(0, read_size));
         assert!(matches!(result, Err(PrefetchReadError::BackpressurePreconditionFailed)));
@@ -832,7 +992,14 @@ mod tests {
 
         let prefetcher = Prefetcher::new(part_stream, prefetcher_config);
         let object_id = ObjectId::new("hello".to_owned(), etag);
-        let mut request = prefetcher.prefetch(client, mem_limiter.into(), "test-bucket".to_owned(), object_id, size);
+        let mut request = prefetcher.prefetch(
+            client,
+            mem_limiter.into(),
+            "test-bucket".to_owned(),
+            object_id,
+            size,
+            None,
+        );
 
         let mut next_offset = 0;
         loop {
@@ -969,6 +1136,7 @@ mod tests {
             "test-bucket".to_owned(),
             object_id,
             object_size,
+            None,
         );
 
         for (offset, length) in reads {
@@ -1143,6 +1311,7 @@ mod tests {
                 "test-bucket".to_owned(),
                 object_id,
                 OBJECT_SIZE as u64,
+                None,
             );
 
             // The first read should trigger the prefetcher to try and get the whole object (in 2 parts).
@@ -1201,6 +1370,7 @@ mod tests {
                 "test-bucket".to_owned(),
                 object_id,
                 OBJECT_SIZE as u64,
+                None,
             );
             if first_read_size > 0 {
                 let _first_read = block_on(request.read(0, first_read_size)).unwrap();
@@ -1243,6 +1413,7 @@ mod tests {
                 "test-bucket".to_owned(),
                 object_id,
                 OBJECT_SIZE as u64,
+                None,
             );
             if first_read_size > 0 {
                 let _first_read = block_on(request.read(0, first_read_size)).unwrap();
@@ -1310,6 +1481,7 @@ mod tests {
                 "test-bucket".to_owned(),
                 object_id,
                 object_size,
+                None,
             );
 
             let mut next_offset = 0;
@@ -1376,6 +1548,7 @@ mod tests {
                 "test-bucket".to_owned(),
                 object_id,
                 object_size,
+                None,
             );
 
             let num_reads = rng.gen_range(10usize..50);
@@ -220,6 +220,7 @@ where
             cache_key.clone(),
             first_read_window_end_offset,
             block_aligned_byte_range,
+            self.config.sse_customer_key.clone(),
         );
 
         let mut part_composer = CachingPartComposer {
@@ -473,6 +474,7 @@ mod tests {
                 initial_read_window_size,
                 max_read_window_size,
                 read_window_size_multiplier,
+                sse_customer_key: None,
             };
             let request_task = stream.spawn_get_object_request(&mock_client, config, mem_limiter.clone());
             compare_read(&id, &object, request_task);
@@ -499,6 +501,7 @@ mod tests {
                 initial_read_window_size,
                 max_read_window_size,
                 read_window_size_multiplier,
+                sse_customer_key: None,
             };
             let request_task = stream.spawn_get_object_request(&mock_client, config, mem_limiter.clone());
             compare_read(&id, &object, request_task);
@@ -550,6 +553,7 @@ mod tests {
                     initial_read_window_size,
                     max_read_window_size,
                     read_window_size_multiplier,
+                    sse_customer_key: None,
                 };
                 let request_task = stream.spawn_get_object_request(&mock_client, config, mem_limiter.clone());
                 compare_read(&id, &object, request_task);
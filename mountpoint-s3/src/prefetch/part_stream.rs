@@ -3,7 +3,7 @@ use bytes::Bytes;
 use futures::task::{Spawn, SpawnExt};
 use futures::{pin_mut, Stream, StreamExt};
 use mountpoint_s3_client::{
-    types::{ClientBackpressureHandle, GetObjectParams, GetObjectResponse},
+    types::{ClientBackpressureHandle, GetObjectParams, GetObjectResponse, SseCustomerKey},
     ObjectClient,
 };
 use std::marker::{Send, Sync};
@@ -48,6 +48,7 @@ pub struct RequestTaskConfig {
     pub initial_read_window_size: usize,
     pub max_read_window_size: usize,
     pub read_window_size_multiplier: usize,
+    pub sse_customer_key: Option<SseCustomerKey>,
 }
 
 /// The range of a [ObjectPartStream::spawn_get_object_request] request.
@@ -225,6 +226,7 @@ where
                         config.object_id.clone(),
                         first_read_window_end_offset,
                         config.range,
+                        config.sse_customer_key,
                     );
 
                     let part_composer = ClientPartComposer {
@@ -302,6 +304,7 @@ pub fn read_from_client_stream<'a, Client: ObjectClient + Clone + 'a>(
     object_id: ObjectId,
     first_read_window_end_offset: u64,
     range: RequestRange,
+    sse_customer_key: Option<SseCustomerKey>,
 ) -> impl Stream<Item = RequestReaderOutput<Client::ClientError>> + 'a {
     try_stream! {
         // Let's start by issuing the first request with a range trimmed to initial read window offset
@@ -313,6 +316,7 @@ pub fn read_from_client_stream<'a, Client: ObjectClient + Clone + 'a>(
                 bucket.clone(),
                 object_id.clone(),
                 first_req_range.into(),
+                sse_customer_key.clone(),
             );
             pin_mut!(first_request_stream);
             while let Some(next) = first_request_stream.next().await {
@@ -343,6 +347,7 @@ pub fn read_from_client_stream<'a, Client: ObjectClient + Clone + 'a>(
                 bucket.clone(),
                 object_id.clone(),
                 range.into(),
+                sse_customer_key.clone(),
             );
             pin_mut!(request_stream);
             while let Some(next) = request_stream.next().await {
@@ -360,10 +365,16 @@ fn read_from_request<'a, Client: ObjectClient + 'a>(
     bucket: String,
     id: ObjectId,
     request_range: Range<u64>,
+    sse_customer_key: Option<SseCustomerKey>,
 ) -> impl Stream<Item = RequestReaderOutput<Client::ClientError>> + 'a {
     try_stream! {
+        let params = GetObjectParams::new()
+            .range(Some(request_range.clone()))
+            .if_match(Some(id.etag().clone()))
+            .sse_customer_key(sse_customer_key)
+            .version_id(id.version_id().map(str::to_owned));
         let mut request = client
-            .get_object(&bucket, id.key(), &GetObjectParams::new().range(Some(request_range.clone())).if_match(Some(id.etag().clone())))
+            .get_object(&bucket, id.key(), &params)
             .await
             .inspect_err(|e| error!(key=id.key(), error=?e, "GetObject request failed"))
             .map_err(PrefetchReadError::GetRequestFailed)?;
@@ -0,0 +1,147 @@
+use metrics::counter;
+use mountpoint_s3_client::types::{Checksum, ChecksumAlgorithm};
+
+use crate::prefetch::PrefetchReadError;
+use crate::sync::Mutex;
+use crate::upload::{ChecksumHasher, ChecksumHasherError};
+
+/// Verifies the data of an object, read sequentially from its start, against the full object checksum stored by S3.
+///
+/// The same data can be passed to the verifier more than once (for example after a backwards seek), as long as
+/// there are no gaps: only the bytes past the ones already seen are hashed.
+#[derive(Debug)]
+pub struct ReadVerifier {
+    algorithm: ChecksumAlgorithm,
+    expected: String,
+    /// The CRT hashers are not [Sync], unlike the prefetcher
+    hasher: Mutex<ChecksumHasher>,
+    next_offset: u64,
+}
+
+impl ReadVerifier {
+    /// Create a verifier for an object with the given checksum. Returns `None` if S3 does not have a full object
+    /// checksum for the object.
+    pub fn new(checksum: &Checksum) -> Result<Option<Self>, ChecksumHasherError> {
+        let Some((algorithm, expected)) = full_object_checksum(checksum) else {
+            return Ok(None);
+        };
+        let hasher = ChecksumHasher::new(&Some(algorithm.clone()))?;
+        Ok(Some(Self {
+            algorithm,
+            expected: expected.to_owned(),
+            hasher: Mutex::new(hasher),
+            next_offset: 0,
+        }))
+    }
+
+    /// Offset of the first byte the verifier has not seen yet.
+    pub fn next_offset(&self) -> u64 {
+        self.next_offset
+    }
+
+    /// Add the data read at the given offset. Returns `false` if the data starts past [Self::next_offset], in which
+    /// case the object can no longer be verified.
+    pub fn update(&mut self, offset: u64, data: &[u8]) -> Result<bool, ChecksumHasherError> {
+        if offset > self.next_offset {
+            return Ok(false);
+        }
+        let already_seen = (self.next_offset - offset).min(data.len() as u64) as usize;
+        self.hasher.lock().unwrap().update(&data[already_seen..])?;
+        self.next_offset += (data.len() - already_seen) as u64;
+        Ok(true)
+    }
+
+    /// Compare the checksum of all the data seen so far with the one stored by S3.
+    pub fn finish<E>(self) -> Result<(), PrefetchReadError<E>> {
+        let hasher = std::mem::take(&mut *self.hasher.lock().unwrap());
+        let actual = hasher
+            .finalize()?
+            .map(|checksum| checksum.to_base64())
+            .unwrap_or_default();
+        if actual != self.expected {
+            counter!("prefetch.verify_reads", "result" => "mismatch").increment(1);
+            return Err(PrefetchReadError::ChecksumMismatch {
+                algorithm: self.algorithm,
+                expected: self.expected,
+                actual,
+            });
+        }
+        counter!("prefetch.verify_reads", "result" => "match").increment(1);
+        Ok(())
+    }
+}
+
+/// Return the first full object checksum of an object, if any.
+fn full_object_checksum(checksum: &Checksum) -> Option<(ChecksumAlgorithm, &str)> {
+    [
+        (ChecksumAlgorithm::Crc64nvme, &checksum.checksum_crc64nvme),
+        (ChecksumAlgorithm::Crc32, &checksum.checksum_crc32),
+        (ChecksumAlgorithm::Crc32c, &checksum.checksum_crc32c),
+        (ChecksumAlgorithm::Sha1, &checksum.checksum_sha1),
+        (ChecksumAlgorithm::Sha256, &checksum.checksum_sha256),
+    ]
+    .into_iter()
+    .find_map(|(algorithm, value)| {
+        let value = value.as_deref()?;
+        // Composite checksums of multipart objects end with the number of parts (e.g. `-3`),
+        // and can't be compared to a checksum of the whole object.
+        (!value.contains('-')).then_some((algorithm, value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use mountpoint_s3_client::checksums::{crc32c, crc32c_to_base64};
+    use test_case::test_case;
+
+    use super::*;
+
+    fn checksum(algorithm: ChecksumAlgorithm, value: &str) -> Checksum {
+        let mut checksum = Checksum::empty();
+        let field = match algorithm {
+            ChecksumAlgorithm::Crc32 => &mut checksum.checksum_crc32,
+            ChecksumAlgorithm::Crc32c => &mut checksum.checksum_crc32c,
+            ChecksumAlgorithm::Crc64nvme => &mut checksum.checksum_crc64nvme,
+            ChecksumAlgorithm::Sha1 => &mut checksum.checksum_sha1,
+            ChecksumAlgorithm::Sha256 => &mut checksum.checksum_sha256,
+            _ => unreachable!(),
+        };
+        *field = Some(value.to_owned());
+        checksum
+    }
+
+    #[test]
+    fn test_no_full_object_checksum() {
+        assert!(ReadVerifier::new(&Checksum::empty()).unwrap().is_none());
+        let composite = checksum(ChecksumAlgorithm::Crc32c, "TybNMw==-3");
+        assert!(ReadVerifier::new(&composite).unwrap().is_none());
+    }
+
+    #[test_case(&[(0, &b"hello "[..]), (6, &b"world"[..])], true; "sequential")]
+    #[test_case(&[(0, &b"hello "[..]), (3, &b"lo world"[..])], true; "overlapping")]
+    #[test_case(&[(0, &b"hello "[..]), (0, &b"hello "[..]), (6, &b"world"[..])], true; "repeated")]
+    #[test_case(&[(0, &b"hello "[..]), (6, &b"there"[..])], false; "different data")]
+    fn test_verify(reads: &[(u64, &[u8])], expected_match: bool) {
+        let body = b"hello world";
+        let expected = crc32c_to_base64(&crc32c::checksum(body));
+        let mut verifier = ReadVerifier::new(&checksum(ChecksumAlgorithm::Crc32c, &expected))
+            .unwrap()
+            .unwrap();
+        for (offset, data) in reads {
+            assert!(verifier.update(*offset, data).unwrap());
+        }
+        assert_eq!(verifier.next_offset(), body.len() as u64);
+        let result = verifier.finish::<std::io::Error>();
+        assert_eq!(result.is_ok(), expected_match);
+    }
+
+    #[test]
+    fn test_gap() {
+        let mut verifier = ReadVerifier::new(&checksum(ChecksumAlgorithm::Crc32, "AAAAAA=="))
+            .unwrap()
+            .unwrap();
+        assert!(verifier.update(0, b"hello").unwrap());
+        assert!(!verifier.update(6, b"world").unwrap());
+        assert_eq!(verifier.next_offset(), 5);
+    }
+}
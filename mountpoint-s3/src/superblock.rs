@@ -28,29 +28,45 @@ use std::time::Duration;
 
 use anyhow::anyhow;
 use futures::{select_biased, FutureExt};
-use mountpoint_s3_client::error::{HeadObjectError, ObjectClientError};
+use mountpoint_s3_client::error::{DeleteObjectError, HeadObjectError, ObjectClientError};
 use mountpoint_s3_client::error_metadata::ProvideErrorMetadata;
-use mountpoint_s3_client::types::{HeadObjectParams, HeadObjectResult};
+use mountpoint_s3_client::types::{HeadObjectParams, HeadObjectResult, ObjectTags, ObjectVersionInfo};
 use mountpoint_s3_client::ObjectClient;
 use thiserror::Error;
 use time::OffsetDateTime;
 use tracing::{debug, error, trace, warn};
 
 use crate::fs::error_metadata::{ErrorMetadata, MOUNTPOINT_ERROR_CLIENT};
-use crate::fs::CacheConfig;
+use crate::fs::{CacheConfig, ServerSideEncryption, SseCorruptedError};
+use crate::key_filter::KeyFilter;
 use crate::logging;
+use crate::manifest::Manifest;
 use crate::prefix::Prefix;
 use crate::s3::S3Personality;
-use crate::sync::atomic::{AtomicU64, Ordering};
-use crate::sync::{Arc, RwLock};
+use crate::sync::{Arc, Mutex, RwLock};
+
+mod changes;
+pub use changes::ChangeFeedFollower;
 
 mod expiry;
 use expiry::Expiry;
 
+mod export;
+use export::ExportedInodes;
+
 mod inode;
-use inode::{valid_inode_name, InodeErrorInfo, InodeKindData, InodeStat, InodeState, WriteStatus};
+use inode::{
+    valid_escaped_inode_name, valid_inode_name, InodeErrorInfo, InodeKindData, InodeStat, InodeState, WriteStatus,
+};
+
+pub use inode::{Inode, InodeKind, InodeNo, ReadHandle, SyntheticKind, WriteHandle, WriteMode};
+
+mod inode_numbers;
+use inode_numbers::InodeNumbers;
 
-pub use inode::{Inode, InodeKind, InodeNo, ReadHandle, WriteHandle, WriteMode};
+mod invalidation;
+pub use invalidation::{Invalidation, RecentFilesPoller};
+use invalidation::{KernelCache, RecentFiles};
 
 mod negative_cache;
 use negative_cache::NegativeCache;
@@ -58,6 +74,14 @@ use negative_cache::NegativeCache;
 mod readdir;
 pub use readdir::ReaddirHandle;
 
+mod recursive_delete;
+pub use recursive_delete::RecursiveDeleteSummary;
+
+mod snapshot;
+pub use snapshot::SnapshotError;
+
+mod versions;
+
 /// Superblock is the root object of the file system
 #[derive(Debug)]
 pub struct Superblock {
@@ -67,9 +91,15 @@ pub struct Superblock {
 #[derive(Debug)]
 struct SuperblockInner {
     bucket: String,
+    prefix: Prefix,
     inodes: RwLock<InodeMap>,
     negative_cache: NegativeCache,
-    next_ino: AtomicU64,
+    inode_numbers: InodeNumbers,
+    /// Set when the file system can be exported over NFS
+    exported: Option<Mutex<ExportedInodes>>,
+    kernel_cache: KernelCache,
+    /// Set when the most recently opened files are polled for changes
+    recent_files: Option<Mutex<RecentFiles>>,
     mount_time: OffsetDateTime,
     config: SuperblockConfig,
 }
@@ -79,6 +109,32 @@ struct SuperblockInner {
 pub struct SuperblockConfig {
     pub cache_config: CacheConfig,
     pub s3_personality: S3Personality,
+    /// Used for the customer-provided key (SSE-C) that HeadObject requests must be sent with
+    pub server_side_encryption: ServerSideEncryption,
+    /// Check whether an object is protected by Object Lock before deleting it on unlink
+    pub check_object_lock: bool,
+    /// Show the versions of objects that were current at this time, rather than the latest ones
+    pub as_of: Option<OffsetDateTime>,
+    /// Serve the virtual `<name>.versions` directories listing the versions of objects
+    pub show_versions: bool,
+    /// Hide the keys that don't pass this filter
+    pub key_filter: KeyFilter,
+    /// Accept every key component as a name, for a file system layer that escapes names
+    pub escape_names: bool,
+    /// Remove empty remote directories on rmdir by deleting their directory marker objects
+    pub directory_markers: bool,
+    /// Derive inode numbers from a hash of the key, rather than handing them out in order
+    pub stable_inode_numbers: bool,
+    /// Mixed into the hash of each key for stable inode numbers, so that the file systems of a
+    /// union mount give different numbers to the same key
+    pub inode_number_namespace: String,
+    /// Remember where to find forgotten inodes, so that they can be looked up again by number
+    /// through NFS file handles. Requires stable inode numbers.
+    pub nfs_export: bool,
+    /// Serve lookups and listings from this manifest, rather than from S3
+    pub manifest: Option<Arc<Manifest>>,
+    /// Keep track of the most recently opened files, to poll them for changes
+    pub poll_recent_files: bool,
 }
 
 impl Superblock {
@@ -91,18 +147,34 @@ impl Superblock {
         inodes.insert(root.ino(), root);
 
         let negative_cache = NegativeCache::new(config.cache_config.negative_cache_size, config.cache_config.file_ttl);
+        let inode_numbers = InodeNumbers::new(config.stable_inode_numbers, &config.inode_number_namespace);
+        assert!(
+            !config.nfs_export || config.stable_inode_numbers,
+            "NFS export requires stable inode numbers"
+        );
+        let exported = config.nfs_export.then(Default::default);
+        let recent_files = config.poll_recent_files.then(Default::default);
 
         let inner = SuperblockInner {
             bucket: bucket.to_owned(),
+            prefix: prefix.clone(),
             inodes: RwLock::new(inodes),
             negative_cache,
-            next_ino: AtomicU64::new(2),
+            inode_numbers,
+            exported,
+            kernel_cache: Default::default(),
+            recent_files,
             mount_time,
             config,
         };
         Self { inner: Arc::new(inner) }
     }
 
+    /// Whether an inode with this number is in use
+    pub fn contains(&self, ino: InodeNo) -> bool {
+        self.inner.inodes.read().unwrap().get(&ino).is_some()
+    }
+
     /// The kernel tells us when it removes a reference to an [InodeNo] from its internal caches via a forget call.
     /// The kernel may forget a number of references (`n`) in one forget message to our FUSE implementation.
     /// If the lookup count reaches zero, it is safe for the [Superblock] to delete the [Inode].
@@ -129,6 +201,13 @@ impl Superblock {
                 error!("forget called on inode {ino} already removed from the superblock");
                 return;
             };
+            // Keep track of the inode in case it's asked for again through an NFS file handle.
+            // Inodes that were already asked for that way hold a reference to their parent.
+            let holds_parent_reference = self
+                .inner
+                .exported
+                .as_ref()
+                .is_some_and(|exported| exported.lock().unwrap().evict(&inode));
 
             let parent = {
                 if let Some(parent) = self.inner.inodes.read().unwrap().get(&inode.parent()).cloned() {
@@ -161,6 +240,11 @@ impl Superblock {
                 metrics::counter!("metadata_cache.inode_forgotten_before_expiry")
                     .increment(state.stat.is_valid().into());
             };
+
+            drop(parent_state);
+            if holds_parent_reference {
+                self.forget(parent.ino(), 1);
+            }
         }
     }
 
@@ -220,6 +304,17 @@ impl Superblock {
         }
     }
 
+    /// Cache the tags of a file alongside its stat, so that they are only fetched again once the
+    /// stat expires. The tags are not cached if the object changed since `etag` was looked up.
+    pub fn cache_tags(&self, ino: InodeNo, etag: Option<&str>, tags: ObjectTags) -> Result<(), InodeError> {
+        let inode = self.inner.get(ino)?;
+        let mut state = inode.get_mut_inode_state()?;
+        if state.stat.is_valid() && state.stat.etag.as_deref() == etag {
+            state.stat.tags = Some(tags);
+        }
+        Ok(())
+    }
+
     /// Set the attributes for an inode
     pub async fn setattr<OC: ObjectClient>(
         &self,
@@ -276,6 +371,9 @@ impl Superblock {
         trace!(?ino, "read");
 
         let inode = self.inner.get(ino)?;
+        if let Some(recent_files) = &self.inner.recent_files {
+            recent_files.lock().unwrap().insert(ino);
+        }
         ReadHandle::new(inode)
     }
 
@@ -298,7 +396,7 @@ impl Superblock {
         let parent_ino = dir.parent();
 
         let dir_key = dir.full_key();
-        assert!(dir_key.is_empty() || dir_key.ends_with('/'));
+        assert!(dir.synthetic().is_some() || dir_key.is_empty() || dir_key.ends_with('/'));
 
         ReaddirHandle::new(self.inner.clone(), dir_ino, parent_ino, dir_key.to_string(), page_size)
     }
@@ -336,6 +434,16 @@ impl Superblock {
         // Put inode creation in a block so we don't hold the lock on the parent state longer than needed.
         let lookup = {
             let parent_inode = self.inner.get(dir)?;
+            if parent_inode.synthetic().is_some() {
+                return Err(InodeError::InodeNotWritable(parent_inode.err()));
+            }
+            let mut key = format!("{}{}", parent_inode.full_key(), name);
+            if kind == InodeKind::Directory {
+                key.push('/');
+            }
+            if !self.inner.is_key_visible(&key) {
+                return Err(InodeError::HiddenByKeyFilter(key));
+            }
             let mut parent_state = parent_inode.get_mut_inode_state()?;
 
             // Check again for the child now that the parent is locked, since we might have lost to a
@@ -364,9 +472,9 @@ impl Superblock {
             };
 
             let state = InodeState::new(&stat, kind, WriteStatus::LocalUnopened);
-            let inode = self
-                .inner
-                .create_inode_locked(&parent_inode, &mut parent_state, name, kind, state, true)?;
+            let inode =
+                self.inner
+                    .create_inode_locked(&parent_inode, &mut parent_state, name, kind, None, state, true)?;
             LookedUp { inode, stat }
         };
 
@@ -375,7 +483,8 @@ impl Superblock {
     }
 
     /// Remove local-only empty directory, i.e., the ones created by mkdir.
-    /// It does not affect empty directories represented remotely with directory markers.
+    /// With directory markers enabled, empty remote directories are removed too, by deleting their
+    /// directory marker object.
     pub async fn rmdir<OC: ObjectClient>(
         &self,
         client: &OC,
@@ -396,16 +505,22 @@ impl Superblock {
             return Err(InodeError::NotADirectory(inode.err()));
         }
 
+        let directory_markers = self.inner.config.directory_markers;
+        if directory_markers && inode.get_inode_state()?.write_status == WriteStatus::Remote {
+            self.delete_directory_marker(client, &inode).await?;
+        }
+
         let parent = self.inner.get(parent_ino)?;
         let mut parent_state = parent.get_mut_inode_state()?;
         let mut inode_state = inode.get_mut_inode_state()?;
+        let is_local = inode_state.write_status != WriteStatus::Remote;
 
         match &inode_state.write_status {
             WriteStatus::LocalOpen => unreachable!("A directory cannot be in Local open state"),
-            WriteStatus::Remote => {
+            WriteStatus::Remote if !directory_markers => {
                 return Err(InodeError::CannotRemoveRemoteDirectory(inode.err()));
             }
-            WriteStatus::LocalUnopened => match &mut inode_state.kind_data {
+            WriteStatus::Remote | WriteStatus::LocalUnopened => match &mut inode_state.kind_data {
                 InodeKindData::File {} => unreachable!("Already checked that inode is a directory"),
                 InodeKindData::Directory {
                     writing_children,
@@ -430,11 +545,13 @@ impl Superblock {
                 writing_children,
                 ..
             } => {
-                let removed = writing_children.remove(&inode.ino());
-                debug_assert!(
-                    removed,
-                    "should be able to remove the directory from its parents writing children as it was local"
-                );
+                if is_local {
+                    let removed = writing_children.remove(&inode.ino());
+                    debug_assert!(
+                        removed,
+                        "should be able to remove the directory from its parents writing children as it was local"
+                    );
+                }
                 children.remove(inode.name());
             }
         }
@@ -442,6 +559,64 @@ impl Superblock {
         Ok(())
     }
 
+    /// Delete the directory marker object of a remote directory, after checking that there is
+    /// nothing else under its prefix. The directory may not have a marker, if it only exists
+    /// because of the keys under it, in which case it's not empty anyway.
+    async fn delete_directory_marker<OC: ObjectClient>(&self, client: &OC, inode: &Inode) -> Result<(), InodeError> {
+        if inode.synthetic().is_some() {
+            return Err(InodeError::InodeNotWritable(inode.err()));
+        }
+        if let InodeKindData::Directory { writing_children, .. } = &inode.get_inode_state()?.kind_data {
+            if !writing_children.is_empty() {
+                return Err(InodeError::DirectoryNotEmpty(inode.err()));
+            }
+        }
+
+        let (bucket, s3_key) = (self.inner.bucket.as_str(), inode.full_key());
+        let result = client
+            .list_objects(bucket, None, "/", 2, s3_key)
+            .await
+            .map_err(|e| InodeError::client_error(e, "ListObjectsV2 failed", bucket, s3_key))?;
+        let has_children =
+            !result.common_prefixes.is_empty() || result.objects.iter().any(|object| object.key != s3_key);
+        if has_children {
+            return Err(InodeError::DirectoryNotEmpty(inode.err()));
+        }
+        if !result.objects.iter().any(|object| object.key == s3_key) {
+            // Already deleted by someone else
+            return Ok(());
+        }
+
+        if self.inner.config.check_object_lock {
+            self.check_object_lock(client, inode).await?;
+        }
+        debug!(inode=%inode.err(), "rmdir on remote directory will delete key {}", s3_key);
+        match client.delete_object(bucket, s3_key).await {
+            Ok(_res) => Ok(()),
+            Err(ObjectClientError::ServiceError(DeleteObjectError::ObjectLocked)) => {
+                Err(InodeError::ObjectLocked(inode.err()))
+            }
+            Err(e) => Err(InodeError::client_error(e, "DeleteObject failed", bucket, s3_key)),
+        }
+    }
+
+    /// Mark a directory created by [Superblock::create] as remote, once its directory marker
+    /// object has been uploaded.
+    pub fn finish_directory_marker(&self, ino: InodeNo) -> Result<(), InodeError> {
+        let inode = self.inner.get(ino)?;
+        let parent = self.inner.get(inode.parent())?;
+        let mut parent_state = parent.get_mut_inode_state()?;
+        let mut inode_state = inode.get_mut_inode_state()?;
+        if inode.kind() != InodeKind::Directory || inode_state.write_status != WriteStatus::LocalUnopened {
+            return Err(InodeError::InodeInvalidWriteStatus(inode.err()));
+        }
+        inode_state.write_status = WriteStatus::Remote;
+        if let InodeKindData::Directory { writing_children, .. } = &mut parent_state.kind_data {
+            writing_children.remove(&ino);
+        }
+        Ok(())
+    }
+
     /// Unlink the entry described by `parent_ino` and `name`.
     ///
     /// If the entry exists, delete it from S3 and the superblock.
@@ -470,6 +645,11 @@ impl Superblock {
             return Err(InodeError::IsDirectory(inode.err()));
         }
 
+        // Deleting the object would delete its latest version, not the one this file serves
+        if inode.synthetic().is_some() {
+            return Err(InodeError::InodeNotWritable(inode.err()));
+        }
+
         let write_status = {
             let inode_state = inode.get_inode_state()?;
             inode_state.write_status
@@ -487,11 +667,17 @@ impl Superblock {
             }
             WriteStatus::Remote => {
                 let (bucket, s3_key) = (self.inner.bucket.as_str(), inode.full_key());
+                if self.inner.config.check_object_lock {
+                    self.check_object_lock(client, &inode).await?;
+                }
                 debug!(parent=?parent_ino, ?name, "unlink on remote file will delete key {}", s3_key);
                 let delete_obj_result = client.delete_object(bucket, s3_key).await;
 
                 match delete_obj_result {
                     Ok(_res) => (),
+                    Err(ObjectClientError::ServiceError(DeleteObjectError::ObjectLocked)) => {
+                        return Err(InodeError::ObjectLocked(inode.err()));
+                    }
                     Err(e) => {
                         error!(
                             inode=%inode.err(),
@@ -528,6 +714,24 @@ impl Superblock {
 
         Ok(())
     }
+
+    /// Refuse to unlink a file whose object is currently protected by Object Lock.
+    ///
+    /// In a versioned bucket, deleting the object would otherwise succeed by adding a delete marker on top of it,
+    /// hiding the object even though its data is retained.
+    async fn check_object_lock<OC: ObjectClient>(&self, client: &OC, inode: &Inode) -> Result<(), InodeError> {
+        let (bucket, s3_key) = (self.inner.bucket.as_str(), inode.full_key());
+        let sse_customer_key = self.inner.config.server_side_encryption.customer_key()?;
+        let head_object_params = HeadObjectParams::new().sse_customer_key(sse_customer_key);
+        match client.head_object(bucket, s3_key, &head_object_params).await {
+            Ok(HeadObjectResult { object_lock, .. }) if object_lock.is_locked_at(OffsetDateTime::now_utc()) => {
+                warn!(inode=%inode.err(), ?object_lock, "unlink refused on object protected by Object Lock");
+                Err(InodeError::ObjectLocked(inode.err()))
+            }
+            Ok(_) | Err(ObjectClientError::ServiceError(HeadObjectError::NotFound)) => Ok(()),
+            Err(e) => Err(InodeError::client_error(e, "HeadObject failed", bucket, s3_key)),
+        }
+    }
 }
 
 impl SuperblockInner {
@@ -553,7 +757,7 @@ impl SuperblockInner {
         let lookup_count = inode.inc_lookup_count();
         if lookup_count == 1 {
             let previous = self.inodes.write().unwrap().insert(inode.ino(), inode.clone());
-            assert!(previous.is_none(), "inode numbers are never reused");
+            assert!(previous.is_none(), "inode numbers are never shared by inodes in use");
         }
         lookup_count
     }
@@ -589,7 +793,10 @@ impl SuperblockInner {
         let lookup = match lookup {
             Some(lookup) => lookup?,
             None => {
-                let remote = self.remote_lookup(client, parent_ino, name).await?;
+                let remote = match self.remote_lookup(client, parent_ino, name).await? {
+                    None => self.remote_lookup_versions_directory(client, parent_ino, name).await?,
+                    remote => remote,
+                };
                 self.update_from_remote(parent_ino, name, remote)?
             }
         };
@@ -657,6 +864,9 @@ impl SuperblockInner {
         if parent.kind() != InodeKind::Directory {
             return Err(InodeError::NotADirectory(parent.err()));
         }
+        if parent.synthetic().is_some() {
+            return self.remote_lookup_in_versions_directory(client, &parent, name).await;
+        }
         let mut full_path = parent.full_key().to_owned();
         assert!(full_path.is_empty() || full_path.ends_with('/'));
         full_path.push_str(name);
@@ -664,6 +874,35 @@ impl SuperblockInner {
         let mut full_path_suffixed = full_path.clone();
         full_path_suffixed.push('/');
 
+        // Keys hidden by the key filter don't exist as far as the file system is concerned, and we
+        // can skip the requests entirely if neither a file nor a directory would be visible.
+        // A file can't have an empty name, which would make its key the one of the parent directory.
+        let file_visible = !name.is_empty() && self.is_key_visible(&full_path);
+        let directory_visible = self.is_key_visible(&full_path_suffixed);
+        if !file_visible && !directory_visible {
+            trace!(parent = ?parent_ino, ?name, "hidden by the key filter");
+            return Ok(None);
+        }
+
+        let remote = self
+            .remote_lookup_key(client, parent_ino, name, &full_path, &full_path_suffixed)
+            .await?;
+        Ok(remote.filter(|remote| match remote.kind {
+            InodeKind::File => file_visible,
+            InodeKind::Directory => directory_visible,
+        }))
+    }
+
+    /// Lookup the object or directory with the given key in S3, as the child of the parent
+    /// directory with the given name.
+    async fn remote_lookup_key<OC: ObjectClient>(
+        &self,
+        client: &OC,
+        parent_ino: InodeNo,
+        name: &str,
+        full_path: &str,
+        full_path_suffixed: &str,
+    ) -> Result<Option<RemoteLookup>, InodeError> {
         // We need to try two requests here, one to find an object with the given name, and one to
         // discover a possible shadowing (implicit) directory with the same name. There's a few
         // different cases we need to consider here:
@@ -688,10 +927,21 @@ impl SuperblockInner {
         //       "/" to the prefix in the request, the first common prefix we'll get back will be
         //       "dir-1/", because that precedes "dir/" in lexicographic order. Doing the
         //       ListObjects with "/" appended makes sure we always observe the correct prefix.
-        let head_object_params = HeadObjectParams::new();
-        let mut file_lookup = client.head_object(&self.bucket, &full_path, &head_object_params).fuse();
+        if let Some(manifest) = &self.config.manifest {
+            return Ok(self.manifest_lookup(manifest, parent_ino, name, full_path, full_path_suffixed));
+        }
+
+        if let Some(as_of) = self.config.as_of {
+            return self
+                .remote_lookup_as_of(client, parent_ino, name, full_path, full_path_suffixed, as_of)
+                .await;
+        }
+
+        let sse_customer_key = self.config.server_side_encryption.customer_key()?;
+        let head_object_params = HeadObjectParams::new().sse_customer_key(sse_customer_key);
+        let mut file_lookup = client.head_object(&self.bucket, full_path, &head_object_params).fuse();
         let mut dir_lookup = client
-            .list_objects(&self.bucket, None, "/", 1, &full_path_suffixed)
+            .list_objects(&self.bucket, None, "/", 1, full_path_suffixed)
             .fuse();
 
         let mut file_state = None;
@@ -706,24 +956,24 @@ impl SuperblockInner {
                         }
                         // If the object is not found, might be a directory, so keep going
                         Err(ObjectClientError::ServiceError(HeadObjectError::NotFound)) => {},
-                        Err(e) => return Err(InodeError::client_error(e, "HeadObject failed", &self.bucket, &full_path)),
+                        Err(e) => return Err(InodeError::client_error(e, "HeadObject failed", &self.bucket, full_path)),
                     }
                 }
 
                 result = dir_lookup => {
-                    let result = result.map_err(|e| InodeError::client_error(e, "ListObjectsV2 failed", &self.bucket, &full_path))?;
+                    let result = result.map_err(|e| InodeError::client_error(e, "ListObjectsV2 failed", &self.bucket, full_path))?;
 
                     let found_directory = if result
                         .common_prefixes
                         .first()
-                        .map(|prefix| prefix.starts_with(&full_path_suffixed))
+                        .map(|prefix| prefix.starts_with(full_path_suffixed))
                         .unwrap_or(false)
                     {
                         true
                     } else if result
                         .objects
                         .first()
-                        .map(|object| object.key.starts_with(&full_path_suffixed))
+                        .map(|object| object.key.starts_with(full_path_suffixed))
                         .unwrap_or(false)
                     {
                         if result.objects[0].key == full_path_suffixed {
@@ -752,7 +1002,7 @@ impl SuperblockInner {
                     if found_directory {
                         trace!(parent = ?parent_ino, ?name, "lookup ListObjects found a directory");
                         let stat = InodeStat::for_directory(self.mount_time, self.config.cache_config.dir_ttl);
-                        return Ok(Some(RemoteLookup { kind: InodeKind::Directory, stat }));
+                        return Ok(Some(RemoteLookup { kind: InodeKind::Directory, stat, synthetic: None }));
                     }
                 }
             }
@@ -767,6 +1017,7 @@ impl SuperblockInner {
             Ok(Some(RemoteLookup {
                 kind: InodeKind::File,
                 stat,
+                synthetic: None,
             }))
         } else {
             trace!(parent = ?parent_ino, ?name, "not found");
@@ -774,6 +1025,187 @@ impl SuperblockInner {
         }
     }
 
+    /// Lookup an inode in the parent directory with the given name as it was at the given time,
+    /// using ListObjectVersions. As in [Self::remote_lookup], directories shadow files.
+    async fn remote_lookup_as_of<OC: ObjectClient>(
+        &self,
+        client: &OC,
+        parent_ino: InodeNo,
+        name: &str,
+        full_path: &str,
+        full_path_suffixed: &str,
+        as_of: OffsetDateTime,
+    ) -> Result<Option<RemoteLookup>, InodeError> {
+        let (file_version, found_directory) = futures::try_join!(
+            versions::resolve_key_as_of(client, &self.bucket, full_path, as_of),
+            versions::prefix_exists_as_of(client, &self.bucket, full_path_suffixed, as_of),
+        )?;
+
+        if found_directory {
+            trace!(parent = ?parent_ino, ?name, "lookup ListObjectVersions found a directory");
+            let stat = InodeStat::for_directory(self.mount_time, self.config.cache_config.dir_ttl);
+            return Ok(Some(RemoteLookup {
+                kind: InodeKind::Directory,
+                stat,
+                synthetic: None,
+            }));
+        }
+
+        if let Some(version) = file_version {
+            trace!(parent = ?parent_ino, ?name, version_id = ?version.version_id, "found a file version in S3");
+            let stat = InodeStat::for_file(
+                version.size as usize,
+                version.last_modified,
+                version.etag,
+                version.storage_class,
+                None,
+                self.config.cache_config.file_ttl,
+            )
+            .with_version_id(Some(version.version_id));
+            Ok(Some(RemoteLookup {
+                kind: InodeKind::File,
+                stat,
+                synthetic: None,
+            }))
+        } else {
+            trace!(parent = ?parent_ino, ?name, "not found");
+            Ok(None)
+        }
+    }
+
+    /// Lookup an inode in the parent directory with the given name in the manifest. As in
+    /// [Self::remote_lookup], directories shadow files.
+    fn manifest_lookup(
+        &self,
+        manifest: &Manifest,
+        parent_ino: InodeNo,
+        name: &str,
+        full_path: &str,
+        full_path_suffixed: &str,
+    ) -> Option<RemoteLookup> {
+        if manifest.contains_prefix(full_path_suffixed) {
+            trace!(parent = ?parent_ino, ?name, "manifest lookup found a directory");
+            let stat = InodeStat::for_directory(self.mount_time, self.config.cache_config.dir_ttl);
+            return Some(RemoteLookup {
+                kind: InodeKind::Directory,
+                stat,
+                synthetic: None,
+            });
+        }
+
+        let Some(object) = manifest.get(full_path) else {
+            trace!(parent = ?parent_ino, ?name, "not found in manifest");
+            return None;
+        };
+        trace!(parent = ?parent_ino, ?name, etag = ?object.etag, "found a regular file in manifest");
+        let stat = InodeStat::for_file(
+            object.size as usize,
+            object.last_modified.unwrap_or(self.mount_time),
+            Some(object.etag),
+            None,
+            None,
+            self.config.cache_config.file_ttl,
+        );
+        Some(RemoteLookup {
+            kind: InodeKind::File,
+            stat,
+            synthetic: None,
+        })
+    }
+
+    /// Lookup a virtual `<name>.versions` directory in the parent directory, if the file system
+    /// serves them. Only called once there is no object or directory with the given name, so that
+    /// keys ending in `.versions` stay accessible.
+    async fn remote_lookup_versions_directory<OC: ObjectClient>(
+        &self,
+        client: &OC,
+        parent_ino: InodeNo,
+        name: &str,
+    ) -> Result<Option<RemoteLookup>, InodeError> {
+        if !self.config.show_versions {
+            return Ok(None);
+        }
+        let Some(versioned_name) = name.strip_suffix(versions::VERSIONS_DIRECTORY_SUFFIX) else {
+            return Ok(None);
+        };
+        let parent = self.get(parent_ino)?;
+        if parent.synthetic().is_some() || !self.is_valid_name(versioned_name, InodeKind::File) {
+            return Ok(None);
+        }
+
+        let mut key = parent.full_key().to_owned();
+        key.push_str(versioned_name);
+        let key = versions::find_versions_directory_key(client, &self.bucket, &key).await?;
+        Ok(key
+            .filter(|key| self.is_key_visible(key))
+            .map(|key| self.versions_directory_lookup(key)))
+    }
+
+    /// Lookup an entry in a versions directory: a version of its object, or the versions directory
+    /// of one of the children of its directory.
+    async fn remote_lookup_in_versions_directory<OC: ObjectClient>(
+        &self,
+        client: &OC,
+        parent: &Inode,
+        name: &str,
+    ) -> Result<Option<RemoteLookup>, InodeError> {
+        let parent_key = parent.full_key();
+        if parent_key.ends_with('/') {
+            let key = format!("{parent_key}{name}");
+            let key = versions::find_versions_directory_key(client, &self.bucket, &key).await?;
+            return Ok(key
+                .filter(|key| self.is_key_visible(key))
+                .map(|key| self.versions_directory_lookup(key)));
+        }
+
+        let version = versions::find_version_by_name(client, &self.bucket, parent_key, name).await?;
+        Ok(version.map(|version| self.object_version_lookup(version)))
+    }
+
+    /// Is the object or directory (whose key ends in '/') with the given key visible through the
+    /// key filter? Keys are matched relative to the mount prefix.
+    fn is_key_visible(&self, key: &str) -> bool {
+        let key = key.strip_prefix(self.prefix.as_str()).unwrap_or(key);
+        self.config.key_filter.is_visible(key)
+    }
+
+    /// Can an inode of the given kind have this name? When names are escaped by the file system
+    /// layer, any key component is valid, except an empty one for a file.
+    fn is_valid_name(&self, name: &str, kind: InodeKind) -> bool {
+        if self.config.escape_names {
+            valid_escaped_inode_name(name, kind)
+        } else {
+            valid_inode_name(name)
+        }
+    }
+
+    /// Build the [RemoteLookup] for a versions directory listing the given key.
+    fn versions_directory_lookup(&self, key: String) -> RemoteLookup {
+        RemoteLookup {
+            kind: InodeKind::Directory,
+            stat: InodeStat::for_directory(self.mount_time, self.config.cache_config.dir_ttl),
+            synthetic: Some((SyntheticKind::VersionsDirectory, key)),
+        }
+    }
+
+    /// Build the [RemoteLookup] for a file in a versions directory serving the given version.
+    fn object_version_lookup(&self, version: ObjectVersionInfo) -> RemoteLookup {
+        let stat = InodeStat::for_file(
+            version.size as usize,
+            version.last_modified,
+            version.etag,
+            version.storage_class,
+            None,
+            self.config.cache_config.file_ttl,
+        )
+        .with_version_id(Some(version.version_id));
+        RemoteLookup {
+            kind: InodeKind::File,
+            stat,
+            synthetic: Some((SyntheticKind::ObjectVersion, version.key)),
+        }
+    }
+
     /// Update the inode with the given name in a parent directory with the remote data.
     /// It may update or delete an existing inode, or insert a new one.
     pub fn update_from_remote(
@@ -824,11 +1256,15 @@ impl SuperblockInner {
                 let mut existing_state = existing_inode.get_mut_inode_state()?;
                 let existing_is_remote = existing_state.write_status == WriteStatus::Remote;
                 if remote.kind == existing_inode.kind()
+                    && remote.is_same_synthetic(existing_inode)
                     && existing_is_remote
                     && existing_state.stat.etag == remote.stat.etag
                 {
                     trace!(parent=?existing_inode.parent(), name=?existing_inode.name(), ino=?existing_inode.ino(), "updating inode in place");
-                    existing_state.stat = remote.stat.clone();
+                    existing_state.stat = InodeStat {
+                        opened_etag: existing_state.stat.opened_etag.take(),
+                        ..remote.stat.clone()
+                    };
                     Ok(Some(LookedUp {
                         inode: existing_inode.clone(),
                         stat: remote.stat.clone(),
@@ -886,16 +1322,25 @@ impl SuperblockInner {
                     // being written. It must have previously existed but been removed on the remote
                     // side.
                     children.remove(name);
+                    self.invalidate(&existing_inode);
                     Err(InodeError::FileDoesNotExist(name.to_owned(), parent.err()))
                 }
             }
             (Some(remote), None) => {
                 let state = InodeState::new(&remote.stat, remote.kind, WriteStatus::Remote);
-                self.create_inode_locked(&parent, &mut parent_state, name, remote.kind, state, false)
-                    .map(|inode| LookedUp {
-                        inode,
-                        stat: remote.stat,
-                    })
+                self.create_inode_locked(
+                    &parent,
+                    &mut parent_state,
+                    name,
+                    remote.kind,
+                    remote.synthetic,
+                    state,
+                    false,
+                )
+                .map(|inode| LookedUp {
+                    inode,
+                    stat: remote.stat,
+                })
             }
             (Some(remote), Some(existing_inode)) => {
                 // We need to reconcile the existing state with the state we just got from the
@@ -917,11 +1362,14 @@ impl SuperblockInner {
                 // Try to update in place if we can. The fast path does this too, but here we can
                 // also handle the case of a local directory becoming remote, which requires
                 // updating the parent.
-                let same_kind = remote.kind == existing_inode.kind();
+                let same_kind = remote.kind == existing_inode.kind() && remote.is_same_synthetic(&existing_inode);
                 let same_etag = existing_state.stat.etag == remote.stat.etag;
                 if same_kind && same_etag && (existing_is_remote || remote.kind == InodeKind::Directory) {
                     trace!(parent=?existing_inode.parent(), name=?existing_inode.name(), ino=?existing_inode.ino(), "updating inode in place (slow path)");
-                    existing_state.stat = remote.stat.clone();
+                    existing_state.stat = InodeStat {
+                        opened_etag: existing_state.stat.opened_etag.take(),
+                        ..remote.stat.clone()
+                    };
                     if remote.kind == InodeKind::Directory && !existing_is_remote {
                         trace!(parent=?existing_inode.parent(), name=?existing_inode.name(), ino=?existing_inode.ino(), "local directory has become remote");
                         existing_state.write_status = WriteStatus::Remote;
@@ -953,9 +1401,17 @@ impl SuperblockInner {
                     ino=?existing_inode.ino(),
                     "inode needs to be recreated",
                 );
+                self.invalidate(&existing_inode);
                 let state = InodeState::new(&remote.stat, remote.kind, WriteStatus::Remote);
-                let new_inode =
-                    self.create_inode_locked(&parent, &mut parent_state, name, remote.kind, state, false)?;
+                let new_inode = self.create_inode_locked(
+                    &parent,
+                    &mut parent_state,
+                    name,
+                    remote.kind,
+                    remote.synthetic,
+                    state,
+                    false,
+                )?;
                 Ok(LookedUp {
                     inode: new_inode,
                     stat: remote.stat,
@@ -964,36 +1420,48 @@ impl SuperblockInner {
         }
     }
 
-    /// Create a new inode in the parent directory, which is already write-locked.
+    /// Create a new inode in the parent directory, which is already write-locked. Synthetic inodes
+    /// are created with the given full key rather than one derived from the parent.
     ///
     /// Don't use this directly unless you need to do inode creation without re-acquiring the parent
     /// write lock. Prefer [SuperblockInner::update_from_remote] instead.
+    #[allow(clippy::too_many_arguments)]
     fn create_inode_locked(
         &self,
         parent: &Inode,
         parent_locked: &mut InodeState,
         name: &str,
         kind: InodeKind,
+        synthetic: Option<(SyntheticKind, String)>,
         state: InodeState,
         is_new_file: bool,
     ) -> Result<Inode, InodeError> {
-        if !valid_inode_name(name) {
+        if !self.is_valid_name(name, kind) {
             warn!(?name, "invalid file name; {} will not be available", kind.as_str());
             return Err(InodeError::InvalidFileName(OsString::from(name)));
         }
 
-        let next_ino = self.next_ino.fetch_add(1, Ordering::SeqCst);
+        let (full_key, synthetic) = match synthetic {
+            Some((synthetic, full_key)) => (full_key, Some(synthetic)),
+            None => {
+                let mut full_key = parent.full_key().to_owned();
+                assert!(full_key.is_empty() || full_key.ends_with('/'));
+                full_key.push_str(name);
+                if kind == InodeKind::Directory {
+                    full_key.push('/');
+                }
+                (full_key, None)
+            }
+        };
 
-        let mut full_key = parent.full_key().to_owned();
-        assert!(full_key.is_empty() || full_key.ends_with('/'));
-        full_key.push_str(name);
-        if kind == InodeKind::Directory {
-            full_key.push('/');
-        }
+        let number = self
+            .inode_numbers
+            .allocate(&full_key, synthetic.map(|synthetic| (synthetic, name)));
+        let next_ino = number.ino;
 
-        trace!(parent=?parent.ino(), ?name, ?kind, new_ino=?next_ino, ?full_key, "creating new inode");
+        trace!(parent=?parent.ino(), ?name, ?kind, ?synthetic, new_ino=?next_ino, ?full_key, "creating new inode");
 
-        let inode = Inode::new(next_ino, parent.ino(), name.to_owned(), full_key, kind, state);
+        let inode = Inode::new(number, parent.ino(), name.to_owned(), full_key, kind, synthetic, state);
 
         match &mut parent_locked.kind_data {
             InodeKindData::File {} => {
@@ -1024,6 +1492,18 @@ impl SuperblockInner {
 pub struct RemoteLookup {
     kind: InodeKind,
     stat: InodeStat,
+    /// For synthetic inodes, their kind and the key they are derived from
+    synthetic: Option<(SyntheticKind, String)>,
+}
+
+impl RemoteLookup {
+    /// Whether the existing inode presents the same synthetic entry (or neither is synthetic).
+    fn is_same_synthetic(&self, inode: &Inode) -> bool {
+        match &self.synthetic {
+            Some((synthetic, key)) => inode.synthetic() == Some(*synthetic) && inode.full_key() == key,
+            None => inode.synthetic().is_none(),
+        }
+    }
 }
 
 /// Result of a call to [Superblock::lookup] or [Superblock::getattr]. `stat` is a copy of the
@@ -1116,6 +1596,14 @@ pub enum InodeError {
         old_inode: InodeErrorInfo,
         new_inode: InodeErrorInfo,
     },
+    #[error("SSE settings corrupted")]
+    SseCorrupted(#[from] SseCorruptedError),
+    #[error("inode {0} cannot be unlinked because the object is protected by Object Lock")]
+    ObjectLocked(InodeErrorInfo),
+    #[error("key {0:?} is hidden by the key filter")]
+    HiddenByKeyFilter(String),
+    #[error("inode {0} was forgotten and can't be found again")]
+    UnknownInode(InodeNo),
 }
 
 impl InodeError {
@@ -1333,6 +1821,7 @@ mod tests {
                     ..Default::default()
                 },
                 s3_personality: S3Personality::Standard,
+                ..Default::default()
             },
         );
 
@@ -1388,6 +1877,7 @@ mod tests {
                     ..Default::default()
                 },
                 s3_personality: S3Personality::Standard,
+                ..Default::default()
             },
         );
 
@@ -1498,6 +1988,87 @@ mod tests {
         }
     }
 
+    #[test_case(""; "unprefixed")]
+    #[test_case("test_prefix/"; "prefixed")]
+    #[tokio::test]
+    async fn test_key_filter(prefix: &str) {
+        let client_config = MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let client = Arc::new(MockClient::new(client_config));
+
+        let keys = &[
+            format!("{prefix}out/part-0.parquet"),
+            format!("{prefix}out/part-0.parquet.crc"),
+            format!("{prefix}out/_SUCCESS"),
+            format!("{prefix}out/_temporary/0/part-1.parquet"),
+            format!("{prefix}logs/part-2.parquet"),
+        ];
+        for key in keys {
+            client.add_object(key, MockObject::constant(0xaa, 30, ETag::for_tests()));
+        }
+
+        let patterns = |patterns: &[&str]| patterns.iter().map(|p| p.parse().unwrap()).collect();
+        let key_filter = KeyFilter::new(patterns(&["*.parquet"]), patterns(&["*.crc", "_temporary/", "/logs/"]));
+        let prefix = Prefix::new(prefix).expect("valid prefix");
+        let superblock = Superblock::new(
+            "test_bucket",
+            &prefix,
+            SuperblockConfig {
+                key_filter,
+                ..Default::default()
+            },
+        );
+
+        let dir_handle = superblock.readdir(&client, FUSE_ROOT_INODE, 2).await.unwrap();
+        let entries = dir_handle.collect(&client).await.unwrap();
+        assert_eq!(
+            entries.iter().map(|entry| entry.inode.name()).collect::<Vec<_>>(),
+            &["out"]
+        );
+
+        let out = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "out".as_ref())
+            .await
+            .expect("directory should be visible");
+        let dir_handle = superblock.readdir(&client, out.inode.ino(), 2).await.unwrap();
+        let entries = dir_handle.collect(&client).await.unwrap();
+        assert_eq!(
+            entries.iter().map(|entry| entry.inode.name()).collect::<Vec<_>>(),
+            &["part-0.parquet"]
+        );
+
+        for (parent, name) in [
+            (FUSE_ROOT_INODE, "logs"),
+            (out.inode.ino(), "part-0.parquet.crc"),
+            (out.inode.ino(), "_SUCCESS"),
+            (out.inode.ino(), "_temporary"),
+        ] {
+            let err = superblock
+                .lookup(&client, parent, name.as_ref())
+                .await
+                .expect_err("hidden keys should not exist");
+            assert!(matches!(err, InodeError::FileDoesNotExist(_, _)), "{name}: {err:?}");
+        }
+
+        for (name, kind) in [
+            ("part-1.parquet.crc", InodeKind::File),
+            ("_temporary", InodeKind::Directory),
+        ] {
+            let err = superblock
+                .create(&client, out.inode.ino(), name.as_ref(), kind)
+                .await
+                .expect_err("hidden keys should not be creatable");
+            assert_eq!(err.to_errno(), libc::EPERM);
+        }
+        superblock
+            .create(&client, out.inode.ino(), "part-1.parquet".as_ref(), InodeKind::File)
+            .await
+            .expect("visible keys should be creatable");
+    }
+
     #[test_case(""; "unprefixed")]
     #[test_case("test_prefix/"; "prefixed")]
     #[tokio::test]
@@ -0,0 +1,372 @@
+//! Applying the changes to objects reported by a [ChangeFeed] to the metadata cache.
+//!
+//! Changes are only used as hints: the inode of a changed or removed object is expired and dropped
+//! from the kernel's caches, so that it's looked up in S3 again when next used, and a created
+//! object is removed from the negative cache. The directory an object is created in or removed
+//! from is expired too, and its listing and entry for the object dropped from the kernel's caches,
+//! so that the object shows up in or disappears from the directory. Nothing is created or deleted
+//! from a change alone, which makes duplicate, late, and reordered notifications harmless.
+//!
+//! The directories further up are left alone, so a directory whose last object is removed is only
+//! noticed to be gone when its metadata expires.
+
+use std::io;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use tracing::{debug, trace, warn};
+
+use crate::change_feed::{ChangeFeed, ChangeFeedError, ChangeKind, ObjectChange};
+use crate::fs::FUSE_ROOT_INODE;
+use crate::sync::mpsc::{channel, Sender, TryRecvError};
+
+use super::{Inode, InodeKindData, Superblock};
+
+/// How long to wait for changes before checking whether to stop following the feed
+const SHUTDOWN_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Applies the changes from a feed to a superblock from a background thread, until dropped or the
+/// feed ends
+#[derive(Debug)]
+pub struct ChangeFeedFollower {
+    shutdown: Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for ChangeFeedFollower {
+    fn drop(&mut self) {
+        let _ = self.shutdown.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Superblock {
+    /// Apply a change to an object to the metadata cache.
+    pub fn apply_change(&self, change: &ObjectChange) {
+        if change.bucket != self.inner.bucket {
+            trace!(?change, "ignoring change in another bucket");
+            return;
+        }
+        let Some(key) = change.key.strip_prefix(self.inner.prefix.as_str()) else {
+            trace!(?change, "ignoring change outside of the prefix");
+            return;
+        };
+        if !self.inner.is_key_visible(&change.key) {
+            trace!(?change, "ignoring change to hidden key");
+            return;
+        }
+        metrics::counter!("superblock.change_feed.changes").increment(1);
+
+        // A directory marker changes the directory it marks
+        let key = key.strip_suffix('/').unwrap_or(key);
+        let mut components = key.split('/').peekable();
+        let Ok(mut dir) = self.inner.get(FUSE_ROOT_INODE) else {
+            return;
+        };
+        while let Some(name) = components.next() {
+            let child = {
+                let Ok(dir_state) = dir.get_inode_state() else {
+                    return;
+                };
+                let InodeKindData::Directory { children, .. } = &dir_state.kind_data else {
+                    return;
+                };
+                children.get(name).cloned()
+            };
+            let Some(child) = child else {
+                // Nothing below this name is known, but it may be in the negative cache, or cached
+                // as missing by the kernel
+                if change.kind == ChangeKind::Created {
+                    self.inner.negative_cache.remove(dir.ino(), name);
+                    self.inner.invalidate_entry(dir.ino(), name);
+                    self.expire_listing(&dir);
+                }
+                return;
+            };
+            if components.peek().is_some() {
+                dir = child;
+                continue;
+            }
+
+            if child.synthetic().is_some() || !matches!(child.is_remote(), Ok(true)) {
+                return;
+            }
+            let Ok(mut state) = child.get_mut_inode_state() else {
+                return;
+            };
+            // S3 quotes ETags everywhere but in event notifications
+            let unchanged = change.kind == ChangeKind::Created
+                && change.etag.is_some()
+                && state.stat.etag.as_deref().map(|etag| etag.trim_matches('"')) == change.etag.as_deref();
+            if unchanged {
+                trace!(?change, ino = child.ino(), "object is unchanged");
+                return;
+            }
+            debug!(?change, ino = child.ino(), "expiring changed object");
+            state.stat.update_validity(Duration::from_secs(0));
+            drop(state);
+            self.inner.invalidate(&child);
+            if change.kind == ChangeKind::Removed {
+                self.expire_listing(&dir);
+            }
+        }
+    }
+
+    /// Expire a directory whose children have changed, and drop its listing from the kernel's
+    /// caches. The root is never expired, since it can't be looked up again.
+    fn expire_listing(&self, dir: &Inode) {
+        trace!(ino = dir.ino(), "expiring directory listing");
+        if dir.ino() != FUSE_ROOT_INODE {
+            if let Ok(mut dir_state) = dir.get_mut_inode_state() {
+                dir_state.stat.update_validity(Duration::from_secs(0));
+            }
+        }
+        self.inner.invalidate_listing(dir);
+    }
+
+    /// Apply the changes from a feed from a background thread, until the returned
+    /// [ChangeFeedFollower] is dropped or the feed ends.
+    pub fn follow_changes(&self, mut feed: impl ChangeFeed) -> io::Result<ChangeFeedFollower> {
+        let superblock = Superblock {
+            inner: self.inner.clone(),
+        };
+        let (shutdown, receiver) = channel();
+        let thread = thread::Builder::new().name("change-feed".to_owned()).spawn(move || {
+            while let Err(TryRecvError::Empty) = receiver.try_recv() {
+                let Some(result) = feed.next_changes(SHUTDOWN_CHECK_INTERVAL) else {
+                    debug!("change feed ended");
+                    return;
+                };
+                match result {
+                    Ok(changes) => {
+                        for change in &changes {
+                            superblock.apply_change(change);
+                        }
+                    }
+                    Err(ChangeFeedError::InvalidMessage(e)) => warn!("ignoring invalid change feed message: {e}"),
+                    Err(e) => {
+                        warn!("failed to read change feed: {:?}", e);
+                        return;
+                    }
+                }
+            }
+        })?;
+        Ok(ChangeFeedFollower {
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockObject};
+    use mountpoint_s3_client::types::ETag;
+
+    use crate::change_feed::FileChangeFeed;
+    use crate::fs::{CacheConfig, TimeToLive};
+    use crate::prefix::Prefix;
+
+    use super::super::{InodeNo, Invalidation, SuperblockConfig};
+    use super::*;
+
+    fn change(kind: ChangeKind, key: &str, etag: Option<&str>) -> ObjectChange {
+        ObjectChange {
+            kind,
+            bucket: "test_bucket".to_owned(),
+            key: key.to_owned(),
+            etag: etag.map(str::to_owned),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_change() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024 * 1024,
+            ..Default::default()
+        });
+        client.add_object(
+            "prefix/dir/a",
+            MockObject::constant(0xaa, 30, ETag::from_str("\"a\"").unwrap()),
+        );
+        let config = SuperblockConfig {
+            cache_config: CacheConfig::new(TimeToLive::Indefinite),
+            ..Default::default()
+        };
+        let superblock = Superblock::new("test_bucket", &Prefix::new("prefix/").unwrap(), config);
+        let invalidations = Arc::new(Mutex::new(Vec::new()));
+        let recorded = invalidations.clone();
+        superblock.on_invalidation(move |invalidation| recorded.lock().unwrap().push(invalidation));
+
+        let dir = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "dir".as_ref())
+            .await
+            .unwrap();
+        let a = superblock.lookup(&client, dir.inode.ino(), "a".as_ref()).await.unwrap();
+        superblock
+            .lookup(&client, dir.inode.ino(), "b".as_ref())
+            .await
+            .expect_err("b should not exist yet");
+
+        // Changes to other buckets and prefixes, and unchanged objects, are ignored
+        superblock.apply_change(&ObjectChange {
+            bucket: "other_bucket".to_owned(),
+            ..change(ChangeKind::Removed, "prefix/dir/a", None)
+        });
+        superblock.apply_change(&change(ChangeKind::Removed, "dir/a", None));
+        superblock.apply_change(&change(ChangeKind::Created, "prefix/dir/a", Some("a")));
+        assert!(invalidations.lock().unwrap().is_empty());
+        assert!(a.inode.get_inode_state().unwrap().stat.is_valid());
+
+        // Created objects are no longer negatively cached, and their directory is expired
+        client.add_object("prefix/dir/b", MockObject::constant(0xaa, 30, ETag::for_tests()));
+        superblock.apply_change(&change(ChangeKind::Created, "prefix/dir/b", Some("test_etag")));
+        assert!(!dir.inode.get_inode_state().unwrap().stat.is_valid());
+        assert_eq!(
+            *invalidations.lock().unwrap(),
+            [
+                Invalidation::Entry {
+                    parent: dir.inode.ino(),
+                    name: "b".to_owned(),
+                },
+                Invalidation::Inode(dir.inode.ino()),
+            ]
+        );
+        invalidations.lock().unwrap().clear();
+        let b = superblock
+            .lookup(&client, dir.inode.ino(), "b".as_ref())
+            .await
+            .expect("b should be found");
+        let dir = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "dir".as_ref())
+            .await
+            .unwrap();
+
+        // Changed objects are expired and invalidated
+        superblock.apply_change(&change(ChangeKind::Created, "prefix/dir/a", Some("new")));
+        assert!(!a.inode.get_inode_state().unwrap().stat.is_valid());
+        assert_eq!(
+            *invalidations.lock().unwrap(),
+            [
+                Invalidation::Entry {
+                    parent: dir.inode.ino(),
+                    name: "a".to_owned(),
+                },
+                Invalidation::Inode(a.inode.ino()),
+            ]
+        );
+        invalidations.lock().unwrap().clear();
+
+        // Removed objects expire their directory too
+        superblock.apply_change(&change(ChangeKind::Removed, "prefix/dir/b", None));
+        assert!(!dir.inode.get_inode_state().unwrap().stat.is_valid());
+        assert_eq!(
+            *invalidations.lock().unwrap(),
+            [
+                Invalidation::Entry {
+                    parent: dir.inode.ino(),
+                    name: "b".to_owned(),
+                },
+                Invalidation::Inode(b.inode.ino()),
+                Invalidation::Inode(dir.inode.ino()),
+            ]
+        );
+    }
+
+    async fn list(superblock: &Superblock, client: &MockClient, dir_ino: InodeNo) -> Vec<String> {
+        let handle = superblock.readdir(client, dir_ino, 2).await.unwrap();
+        let entries = handle.collect(client).await.unwrap();
+        entries.iter().map(|entry| entry.inode.name().to_owned()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_readdir_after_change() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024 * 1024,
+            ..Default::default()
+        });
+        client.add_object("dir/a", MockObject::constant(0xaa, 30, ETag::for_tests()));
+        let config = SuperblockConfig {
+            cache_config: CacheConfig::new(TimeToLive::Indefinite),
+            ..Default::default()
+        };
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), config);
+        let invalidations = Arc::new(Mutex::new(Vec::new()));
+        let recorded = invalidations.clone();
+        superblock.on_invalidation(move |invalidation| recorded.lock().unwrap().push(invalidation));
+
+        let dir = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "dir".as_ref())
+            .await
+            .unwrap();
+        assert_eq!(list(&superblock, &client, dir.inode.ino()).await, ["a"]);
+        superblock
+            .lookup(&client, dir.inode.ino(), "b".as_ref())
+            .await
+            .expect_err("b should not exist yet");
+
+        // A created object shows up in the listing of its directory, which the kernel is told to drop
+        client.add_object("dir/b", MockObject::constant(0xaa, 30, ETag::for_tests()));
+        superblock.apply_change(&change(ChangeKind::Created, "dir/b", None));
+        assert!(invalidations
+            .lock()
+            .unwrap()
+            .contains(&Invalidation::Inode(dir.inode.ino())));
+        assert_eq!(list(&superblock, &client, dir.inode.ino()).await, ["a", "b"]);
+        superblock
+            .lookup(&client, dir.inode.ino(), "b".as_ref())
+            .await
+            .expect("b should be found");
+    }
+
+    #[tokio::test]
+    async fn test_follow_changes() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024 * 1024,
+            ..Default::default()
+        });
+        client.add_object("a", MockObject::constant(0xaa, 30, ETag::from_str("\"a\"").unwrap()));
+        let config = SuperblockConfig {
+            cache_config: CacheConfig::new(TimeToLive::Indefinite),
+            ..Default::default()
+        };
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), config);
+        let a = superblock.lookup(&client, FUSE_ROOT_INODE, "a".as_ref()).await.unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("feed");
+        nix::unistd::mkfifo(&path, nix::sys::stat::Mode::S_IRWXU).unwrap();
+
+        // The follower stops when dropped, even while no one has opened the pipe for writing
+        let follower = superblock.follow_changes(FileChangeFeed::new(&path)).unwrap();
+        thread::sleep(Duration::from_millis(50));
+        let start = Instant::now();
+        drop(follower);
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "follower should stop promptly"
+        );
+
+        // Changes written to the pipe are applied
+        let _follower = superblock.follow_changes(FileChangeFeed::new(&path)).unwrap();
+        let mut writer = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        let message = r#"{"Records":[{"eventName":"ObjectRemoved:Delete","s3":{"bucket":{"name":"test_bucket"},"object":{"key":"a"}}}]}"#;
+        writeln!(writer, "{message}").unwrap();
+        drop(writer);
+        let start = Instant::now();
+        while a.inode.get_inode_state().unwrap().stat.is_valid() {
+            assert!(start.elapsed() < Duration::from_secs(10), "change should be applied");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
@@ -0,0 +1,468 @@
+//! Support for exporting the file system over NFS.
+//!
+//! The kernel hands out NFS file handles made of inode numbers, and NFS clients can present them
+//! long after the kernel has forgotten the inode. To resolve them, the kernel looks up the name `.`
+//! in the inode, and `..` to find the parent of a directory. With stable inode numbers, an inode
+//! number stands for a key, so we remember where to find each inode the kernel forgets, and look it
+//! up again when asked for it.
+//!
+//! We only remember the most recently forgotten inodes. File handles for other inodes, including
+//! the ones handed out before a remount, are resolved by searching the bucket for the key whose
+//! inode number matches, which lists every object under the mounted prefix.
+//!
+//! Looking up an inode requires its parent, so a resurrected inode keeps a reference to its parent
+//! (resurrecting it too if needed) for as long as it exists, just like the kernel does for the
+//! inodes it looks up by name.
+
+use std::collections::HashSet;
+
+use linked_hash_map::LinkedHashMap;
+use mountpoint_s3_client::ObjectClient;
+use tracing::{debug, trace};
+
+use crate::fs::FUSE_ROOT_INODE;
+use crate::sync::Mutex;
+
+use super::{Inode, InodeError, InodeKind, InodeNo, LookedUp, Superblock};
+
+/// Maximum number of forgotten inodes to remember. Inodes forgotten longer ago are found by
+/// searching the bucket instead.
+const MAX_EVICTED_INODES: usize = 100_000;
+
+/// Number of keys in each page of the listing that searches the bucket for an inode
+const SEARCH_PAGE_SIZE: usize = 1000;
+
+/// Inodes the kernel has forgotten, but may still ask for through NFS file handles
+#[derive(Debug, Default)]
+pub struct ExportedInodes {
+    /// Where to look up each forgotten inode, from the least recently forgotten or used
+    evicted: LinkedHashMap<InodeNo, EvictedInode>,
+    /// Inodes looked up again after being forgotten, each holding a reference to its parent
+    resurrected: HashSet<InodeNo>,
+}
+
+#[derive(Debug, Clone)]
+struct EvictedInode {
+    parent: InodeNo,
+    name: String,
+}
+
+impl ExportedInodes {
+    /// Record where to find an inode the kernel has forgotten. Returns whether the inode was
+    /// holding a reference to its parent, which must then be released.
+    pub fn evict(&mut self, inode: &Inode) -> bool {
+        // Synthetic inodes can't be found from their name alone
+        if inode.synthetic().is_none() {
+            let evicted = EvictedInode {
+                parent: inode.parent(),
+                name: inode.name().to_owned(),
+            };
+            self.insert(inode.ino(), evicted);
+        }
+        self.resurrected.remove(&inode.ino())
+    }
+
+    fn insert(&mut self, ino: InodeNo, evicted: EvictedInode) {
+        self.evicted.insert(ino, evicted);
+        if self.evicted.len() > MAX_EVICTED_INODES {
+            self.evicted.pop_front();
+        }
+    }
+}
+
+impl Superblock {
+    /// Look up an inode by number, as the kernel does for the name `.` when resolving an NFS
+    /// file handle, and increment its lookup count. Inodes the kernel has already forgotten are
+    /// looked up again.
+    pub async fn lookup_self<OC: ObjectClient>(&self, client: &OC, ino: InodeNo) -> Result<LookedUp, InodeError> {
+        trace!(ino, "lookup self");
+        match self.inner.get(ino) {
+            Ok(_) => {
+                let lookup = self.getattr(client, ino, false).await?;
+                self.inner.remember(&lookup.inode);
+                Ok(lookup)
+            }
+            Err(InodeError::InodeDoesNotExist(_)) => self.resurrect(client, ino).await,
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Look up the parent of a directory, as the kernel does for the name `..` when reconnecting
+    /// an NFS file handle to the directory tree, and increment its lookup count. The root is its
+    /// own parent.
+    pub async fn lookup_parent<OC: ObjectClient>(&self, client: &OC, ino: InodeNo) -> Result<LookedUp, InodeError> {
+        trace!(ino, "lookup parent");
+        let inode = self.inner.get(ino)?;
+        if inode.kind() != InodeKind::Directory {
+            return Err(InodeError::NotADirectory(inode.err()));
+        }
+        self.lookup_self(client, inode.parent()).await
+    }
+
+    /// Look up an inode the kernel has forgotten again, along with any of its forgotten ancestors.
+    async fn resurrect<OC: ObjectClient>(&self, client: &OC, ino: InodeNo) -> Result<LookedUp, InodeError> {
+        let Some(exported) = &self.inner.exported else {
+            return Err(InodeError::InodeDoesNotExist(ino));
+        };
+
+        // Find the forgotten ancestors, up to the nearest one that is still known.
+        let mut forgotten = Vec::new();
+        let mut next = ino;
+        let mut searched = false;
+        while self.inner.inodes.read().unwrap().get(&next).is_none() {
+            let evicted = exported.lock().unwrap().evicted.get_refresh(&next).cloned();
+            let evicted = match evicted {
+                Some(evicted) => evicted,
+                None => {
+                    // Search the bucket at most once, since finding an inode also remembers where
+                    // to find its ancestors.
+                    if searched || !self.search(client, exported, next).await? {
+                        return Err(InodeError::UnknownInode(ino));
+                    }
+                    searched = true;
+                    continue;
+                }
+            };
+            let parent = evicted.parent;
+            forgotten.push((next, evicted));
+            next = parent;
+        }
+
+        // Hold a reference to the known ancestor, which the first resurrected inode keeps.
+        let ancestor = self.inner.get(next)?;
+        self.inner.remember(&ancestor);
+
+        let mut lookup = None;
+        while let Some((expected_ino, evicted)) = forgotten.pop() {
+            let result = match self
+                .inner
+                .lookup_by_name(client, evicted.parent, evicted.name.as_ref(), false)
+                .await
+            {
+                Ok(looked_up) if looked_up.inode.ino() == expected_ino => Ok(looked_up),
+                // The object has since been deleted or replaced, so the file handle is stale
+                Ok(_) | Err(InodeError::FileDoesNotExist(_, _)) => Err(InodeError::UnknownInode(expected_ino)),
+                Err(e) => Err(e),
+            };
+            let looked_up = match result {
+                Ok(looked_up) => looked_up,
+                Err(e) => {
+                    debug!(ino = expected_ino, ?evicted, "failed to look up forgotten inode: {e}");
+                    self.forget(evicted.parent, 1);
+                    return Err(e);
+                }
+            };
+
+            // The reference to this inode is either the one the kernel gets from this lookup, or
+            // the one the next resurrected inode holds on its parent.
+            if self.inner.remember(&looked_up.inode) == 1 {
+                exported.lock().unwrap().resurrected.insert(expected_ino);
+            } else {
+                // Someone else looked up this inode in the meantime, and its parent already has a
+                // reference for it.
+                self.forget(evicted.parent, 1);
+            }
+            lookup = Some(looked_up);
+        }
+        Ok(lookup.expect("at least the requested inode was forgotten"))
+    }
+
+    /// Search the bucket for the key of a forgotten inode we don't remember, and remember where to
+    /// find it and its ancestors. Returns whether the inode was found.
+    async fn search<OC: ObjectClient>(
+        &self,
+        client: &OC,
+        exported: &Mutex<ExportedInodes>,
+        ino: InodeNo,
+    ) -> Result<bool, InodeError> {
+        debug!(ino, "searching the bucket for forgotten inode");
+        metrics::counter!("fs.nfs_export_searches").increment(1);
+        let bucket = &self.inner.bucket;
+        let prefix = self.inner.prefix.as_str();
+        let mut continuation_token = None;
+        loop {
+            let result = client
+                .list_objects(bucket, continuation_token.as_deref(), "", SEARCH_PAGE_SIZE, prefix)
+                .await
+                .map_err(|e| InodeError::client_error(e, "ListObjectsV2 failed", bucket, prefix))?;
+            for object in &result.objects {
+                let Some(path) = self.find_in_key(&object.key, ino) else {
+                    continue;
+                };
+                let mut exported = exported.lock().unwrap();
+                let mut parent = FUSE_ROOT_INODE;
+                for (number, name) in path {
+                    exported.insert(number, EvictedInode { parent, name });
+                    parent = number;
+                }
+                return Ok(true);
+            }
+            continuation_token = result.next_continuation_token;
+            if continuation_token.is_none() {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Check whether the inode for a key, or for one of the directories leading to it, has the
+    /// given number. If so, returns the number and name of each inode from the root to that one.
+    fn find_in_key(&self, key: &str, ino: InodeNo) -> Option<Vec<(InodeNo, String)>> {
+        let mut path = Vec::new();
+        let mut start = self.inner.prefix.as_str().len();
+        while start < key.len() {
+            let (end, name) = match key[start..].find('/') {
+                Some(delimiter) => (start + delimiter + 1, &key[start..start + delimiter]),
+                None => (key.len(), &key[start..]),
+            };
+            if name.is_empty() {
+                return None;
+            }
+            let number = self.inner.inode_numbers.number_of(&key[..end])?;
+            path.push((number, name.to_owned()));
+            if number == ino {
+                return Some(path);
+            }
+            start = end;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockObject, Operation};
+    use mountpoint_s3_client::types::ETag;
+
+    use crate::prefix::Prefix;
+
+    use super::super::SuperblockConfig;
+    use super::*;
+
+    fn make_superblock(nfs_export: bool) -> (MockClient, Superblock) {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024 * 1024,
+            ..Default::default()
+        });
+        client.add_object("dir/sub/file.txt", MockObject::constant(0xaa, 15, ETag::for_tests()));
+        let config = SuperblockConfig {
+            stable_inode_numbers: true,
+            nfs_export,
+            ..Default::default()
+        };
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), config);
+        (client, superblock)
+    }
+
+    /// Look up `dir/sub/file.txt`, returning the inode numbers of each component.
+    async fn lookup_path(client: &MockClient, superblock: &Superblock) -> [InodeNo; 3] {
+        let mut inos = [FUSE_ROOT_INODE; 3];
+        let mut parent = FUSE_ROOT_INODE;
+        for (i, name) in ["dir", "sub", "file.txt"].into_iter().enumerate() {
+            let lookup = superblock.lookup(client, parent, name.as_ref()).await.unwrap();
+            inos[i] = lookup.inode.ino();
+            parent = inos[i];
+        }
+        inos
+    }
+
+    fn is_known(superblock: &Superblock, ino: InodeNo) -> bool {
+        superblock.inner.get(ino).is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_lookup_self_known_inode() {
+        let (client, superblock) = make_superblock(true);
+        let [dir_ino, sub_ino, file_ino] = lookup_path(&client, &superblock).await;
+
+        let lookup = superblock.lookup_self(&client, file_ino).await.unwrap();
+        assert_eq!(lookup.inode.ino(), file_ino);
+        assert_eq!(lookup.stat.size, 15);
+
+        // The lookup took another reference, so it takes two forgets to evict the file
+        superblock.forget(file_ino, 1);
+        assert!(is_known(&superblock, file_ino));
+        superblock.forget(file_ino, 1);
+        assert!(!is_known(&superblock, file_ino));
+        superblock.forget(sub_ino, 1);
+        superblock.forget(dir_ino, 1);
+    }
+
+    #[tokio::test]
+    async fn test_resurrect() {
+        let (client, superblock) = make_superblock(true);
+        let [dir_ino, sub_ino, file_ino] = lookup_path(&client, &superblock).await;
+        superblock.forget(file_ino, 1);
+        superblock.forget(sub_ino, 1);
+        superblock.forget(dir_ino, 1);
+        for ino in [dir_ino, sub_ino, file_ino] {
+            assert!(!is_known(&superblock, ino));
+        }
+
+        // Looking up the file brings back its forgotten ancestors too
+        let lookup = superblock.lookup_self(&client, file_ino).await.unwrap();
+        assert_eq!(lookup.inode.ino(), file_ino);
+        assert_eq!(lookup.stat.size, 15);
+        for ino in [dir_ino, sub_ino, file_ino] {
+            assert!(is_known(&superblock, ino));
+        }
+
+        // The ancestors are held by their resurrected children, and released along with them
+        superblock.forget(file_ino, 1);
+        for ino in [dir_ino, sub_ino, file_ino] {
+            assert!(!is_known(&superblock, ino));
+        }
+
+        // They can be resurrected again, as many times as needed
+        let lookup = superblock.lookup_self(&client, sub_ino).await.unwrap();
+        assert_eq!(lookup.inode.ino(), sub_ino);
+        assert!(is_known(&superblock, dir_ino));
+        assert!(!is_known(&superblock, file_ino));
+        superblock.forget(sub_ino, 1);
+        assert!(!is_known(&superblock, dir_ino));
+    }
+
+    #[tokio::test]
+    async fn test_resurrect_stale() {
+        let (client, superblock) = make_superblock(true);
+        let [dir_ino, sub_ino, file_ino] = lookup_path(&client, &superblock).await;
+        superblock.forget(file_ino, 1);
+        superblock.forget(sub_ino, 1);
+        superblock.forget(dir_ino, 1);
+
+        // Deleted objects can't be found again, and their resurrected ancestors are released
+        client.remove_object("dir/sub/file.txt");
+        client.add_object("dir/other.txt", MockObject::constant(0xaa, 15, ETag::for_tests()));
+        let err = superblock
+            .lookup_self(&client, file_ino)
+            .await
+            .expect_err("object was deleted");
+        assert!(matches!(err, InodeError::UnknownInode(_)));
+        for ino in [dir_ino, sub_ino, file_ino] {
+            assert!(!is_known(&superblock, ino));
+        }
+
+        // Nor can inodes that were never handed out
+        let err = superblock
+            .lookup_self(&client, file_ino + 1)
+            .await
+            .expect_err("inode never existed");
+        assert!(matches!(err, InodeError::UnknownInode(_)));
+    }
+
+    #[tokio::test]
+    async fn test_resurrect_after_remount() {
+        let (client, superblock) = make_superblock(true);
+        let [dir_ino, sub_ino, file_ino] = lookup_path(&client, &superblock).await;
+        client.add_object("dir/other.txt", MockObject::constant(0xaa, 15, ETag::for_tests()));
+        drop(superblock);
+
+        // A new mount remembers nothing, but finds the keys with the same inode numbers in the bucket
+        let remounted = Superblock::new(
+            "test_bucket",
+            &Prefix::default(),
+            SuperblockConfig {
+                stable_inode_numbers: true,
+                nfs_export: true,
+                ..Default::default()
+            },
+        );
+        let list_counter = client.new_counter(Operation::ListObjectsV2);
+        let lookup = remounted.lookup_self(&client, file_ino).await.unwrap();
+        assert_eq!(lookup.inode.ino(), file_ino);
+        assert_eq!(lookup.stat.size, 15);
+        for ino in [dir_ino, sub_ino, file_ino] {
+            assert!(is_known(&remounted, ino));
+        }
+        assert!(list_counter.count() > 0);
+
+        // Once found, the ancestors are remembered like any other forgotten inode
+        remounted.forget(file_ino, 1);
+        let lookup = remounted.lookup_self(&client, sub_ino).await.unwrap();
+        assert_eq!(lookup.inode.ino(), sub_ino);
+        remounted.forget(sub_ino, 1);
+        for ino in [dir_ino, sub_ino, file_ino] {
+            assert!(!is_known(&remounted, ino));
+        }
+
+        // Numbers that match no key in the bucket are stale
+        let err = remounted
+            .lookup_self(&client, file_ino + 1)
+            .await
+            .expect_err("inode never existed");
+        assert!(matches!(err, InodeError::UnknownInode(_)));
+    }
+
+    #[tokio::test]
+    async fn test_resurrect_beyond_evicted_limit() {
+        let (client, superblock) = make_superblock(true);
+        let [dir_ino, sub_ino, file_ino] = lookup_path(&client, &superblock).await;
+        superblock.forget(file_ino, 1);
+        superblock.forget(sub_ino, 1);
+        superblock.forget(dir_ino, 1);
+
+        // Forget enough other inodes that these ones are no longer remembered
+        {
+            let mut exported = superblock.inner.exported.as_ref().unwrap().lock().unwrap();
+            for ino in 0..MAX_EVICTED_INODES as InodeNo {
+                let evicted = EvictedInode {
+                    parent: FUSE_ROOT_INODE,
+                    name: format!("other-{ino}"),
+                };
+                exported.insert(u64::MAX - ino, evicted);
+            }
+            assert_eq!(exported.evicted.len(), MAX_EVICTED_INODES);
+            assert!(!exported.evicted.contains_key(&file_ino));
+        }
+
+        // The file handle is still resolved, by searching the bucket
+        let lookup = superblock.lookup_self(&client, file_ino).await.unwrap();
+        assert_eq!(lookup.inode.ino(), file_ino);
+        assert!(is_known(&superblock, dir_ino));
+        superblock.forget(file_ino, 1);
+        assert!(!is_known(&superblock, dir_ino));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_parent() {
+        let (client, superblock) = make_superblock(true);
+        let [dir_ino, sub_ino, file_ino] = lookup_path(&client, &superblock).await;
+        superblock.forget(file_ino, 1);
+        superblock.forget(sub_ino, 1);
+        superblock.forget(dir_ino, 1);
+
+        // The kernel reconnects a directory to the tree by looking up `..` in it
+        let sub = superblock.lookup_self(&client, sub_ino).await.unwrap();
+        let parent = superblock.lookup_parent(&client, sub.inode.ino()).await.unwrap();
+        assert_eq!(parent.inode.ino(), dir_ino);
+        let parent = superblock.lookup_parent(&client, dir_ino).await.unwrap();
+        assert_eq!(parent.inode.ino(), FUSE_ROOT_INODE);
+        let parent = superblock.lookup_parent(&client, FUSE_ROOT_INODE).await.unwrap();
+        assert_eq!(parent.inode.ino(), FUSE_ROOT_INODE);
+
+        let file = superblock.lookup(&client, sub_ino, "file.txt".as_ref()).await.unwrap();
+        let err = superblock
+            .lookup_parent(&client, file.inode.ino())
+            .await
+            .expect_err("files have no `..` entry");
+        assert!(matches!(err, InodeError::NotADirectory(_)));
+    }
+
+    #[tokio::test]
+    async fn test_no_export() {
+        let (client, superblock) = make_superblock(false);
+        let [dir_ino, sub_ino, file_ino] = lookup_path(&client, &superblock).await;
+        superblock.forget(file_ino, 1);
+
+        // Without NFS export, forgotten inodes are not remembered
+        let err = superblock
+            .lookup_self(&client, file_ino)
+            .await
+            .expect_err("inode was forgotten");
+        assert!(matches!(err, InodeError::InodeDoesNotExist(_)));
+        let lookup = superblock.lookup_self(&client, sub_ino).await.unwrap();
+        assert_eq!(lookup.inode.ino(), sub_ino);
+        superblock.forget(sub_ino, 2);
+        superblock.forget(dir_ino, 1);
+    }
+}
@@ -5,7 +5,7 @@ use std::os::unix::ffi::OsStrExt as _;
 use std::time::{Duration, SystemTime};
 
 use fuser::FileType;
-use mountpoint_s3_client::types::{ETag, RestoreStatus};
+use mountpoint_s3_client::types::{ETag, ObjectTags, RestoreStatus};
 use mountpoint_s3_crt::checksums::crc32c::{self, Crc32c};
 use time::OffsetDateTime;
 use tracing::trace;
@@ -13,6 +13,7 @@ use tracing::trace;
 use crate::sync::atomic::{AtomicBool, Ordering};
 use crate::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
+use super::inode_numbers::{InodeNumber, InodeNumberClaim};
 use super::{Expiry, InodeError, SuperblockInner};
 
 pub type InodeNo = u64;
@@ -36,7 +37,11 @@ struct InodeInner {
     // TODO deduplicate keys by string interning or something -- many keys will have common prefixes
     full_key: String,
     kind: InodeKind,
+    /// Set for inodes that don't map one-to-one to keys in the bucket
+    synthetic: Option<SyntheticKind>,
     checksum: Crc32c,
+    /// Keeps a stable inode number from being given to another inode while this one exists
+    _ino_claim: Option<InodeNumberClaim>,
 
     /// Mutable inode state. This lock should also be held to serialize operations on an inode (like
     /// creating a new child).
@@ -72,6 +77,10 @@ impl Inode {
         &self.inner.full_key
     }
 
+    pub fn synthetic(&self) -> Option<SyntheticKind> {
+        self.inner.synthetic
+    }
+
     /// Increment lookup count for [Inode] by 1, returning the new value.
     /// This should be called whenever we pass a `fuse_reply_entry` or `fuse_reply_create` struct to the FUSE driver.
     ///
@@ -134,13 +143,15 @@ impl Inode {
 
     /// Create a new inode.
     pub(super) fn new(
-        ino: InodeNo,
+        number: InodeNumber,
         parent: InodeNo,
         name: String,
         full_key: String,
         kind: InodeKind,
+        synthetic: Option<SyntheticKind>,
         state: InodeState,
     ) -> Self {
+        let InodeNumber { ino, claim } = number;
         let checksum = Self::compute_checksum(ino, &full_key);
         let sync = RwLock::new(state);
         let inner = InodeInner {
@@ -149,7 +160,9 @@ impl Inode {
             name,
             full_key,
             kind,
+            synthetic,
             checksum,
+            _ino_claim: claim,
             sync,
         };
         Self { inner: inner.into() }
@@ -158,11 +171,12 @@ impl Inode {
     /// Create the root inode.
     pub(super) fn new_root(prefix: String, mount_time: OffsetDateTime) -> Self {
         Self::new(
-            ROOT_INODE_NO,
+            InodeNumber::unclaimed(ROOT_INODE_NO),
             ROOT_INODE_NO,
             String::new(),
             prefix,
             InodeKind::Directory,
+            None,
             InodeState {
                 // The root inode never expires because there's no remote to consult for its
                 // metadata, and it always exists.
@@ -222,6 +236,12 @@ pub fn valid_inode_name<T: AsRef<OsStr>>(name: T) -> bool {
     !name.as_bytes().contains(&b'\0')
 }
 
+/// With escaped names, any key component is a valid name for a directory, and any non-empty one
+/// for a file, as long as it doesn't contain the delimiter /.
+pub fn valid_escaped_inode_name(name: &str, kind: InodeKind) -> bool {
+    !name.contains('/') && (kind == InodeKind::Directory || !name.is_empty())
+}
+
 /// A wrapper that prints useful customer-facing error messages for inodes by including the object
 /// key rather than just the inode number.
 pub struct InodeErrorInfo(Inode);
@@ -277,6 +297,18 @@ impl InodeKind {
     }
 }
 
+/// The kinds of synthetic [Inode]s, which are presented by the file system but don't map one-to-one
+/// to keys in the bucket. Their full key is the key they are derived from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticKind {
+    /// A read-only directory listing the versions of the object at its full key, or, if its full
+    /// key ends in '/', the versions directories of the objects and directories under it.
+    VersionsDirectory,
+    /// A read-only file serving a version of the object at its full key. The version is given by
+    /// its [InodeStat::version_id].
+    ObjectVersion,
+}
+
 impl From<InodeKind> for FileType {
     fn from(kind: InodeKind) -> Self {
         match kind {
@@ -338,6 +370,18 @@ pub struct InodeStat {
     /// are only readable after restoration. For objects with other storage classes
     /// this field should be always `true`.
     pub is_readable: bool,
+    /// Storage class of the object, if S3 reported one
+    pub storage_class: Option<String>,
+    /// Version of the object to read, when the file system shows the objects as they were at a
+    /// point in time. `None` to read the latest version.
+    pub version_id: Option<String>,
+    /// Etag of the object when the file was last opened for reading, to tell whether the content
+    /// the kernel has cached for the file is still current. `None` if the file hasn't been opened
+    /// for reading since the inode was created or last opened for writing.
+    pub opened_etag: Option<String>,
+    /// Tags of the object, once they have been fetched for its extended attributes. They are
+    /// cached for as long as the rest of the stat.
+    pub tags: Option<ObjectTags>,
 }
 
 /// Inode write status (local vs remote)
@@ -360,9 +404,9 @@ impl InodeStat {
     /// restored, and so we override their permissions to 000 and reject reads to them. We also warn
     /// the first time we see an object like this, because FUSE enforces the 000 permissions on our
     /// behalf so we might not see an attempted `open` call.
-    fn is_readable(storage_class: Option<String>, restore_status: Option<RestoreStatus>) -> bool {
+    fn is_readable(storage_class: Option<&str>, restore_status: Option<RestoreStatus>) -> bool {
         static HAS_SENT_WARNING: AtomicBool = AtomicBool::new(false);
-        match storage_class.as_deref() {
+        match storage_class {
             Some("GLACIER") | Some("DEEP_ARCHIVE") => {
                 let restored =
                     matches!(restore_status, Some(RestoreStatus::Restored { expiry }) if expiry > SystemTime::now());
@@ -386,7 +430,7 @@ impl InodeStat {
         restore_status: Option<RestoreStatus>,
         validity: Duration,
     ) -> InodeStat {
-        let is_readable = Self::is_readable(storage_class, restore_status);
+        let is_readable = Self::is_readable(storage_class.as_deref(), restore_status);
         InodeStat {
             expiry: Expiry::from_now(validity),
             size,
@@ -395,6 +439,10 @@ impl InodeStat {
             mtime: datetime,
             etag,
             is_readable,
+            storage_class,
+            version_id: None,
+            opened_etag: None,
+            tags: None,
         }
     }
 
@@ -408,9 +456,19 @@ impl InodeStat {
             mtime: datetime,
             etag: None,
             is_readable: true,
+            storage_class: None,
+            version_id: None,
+            opened_etag: None,
+            tags: None,
         }
     }
 
+    /// Set the version of the object to read for a file.
+    pub fn with_version_id(mut self, version_id: Option<String>) -> Self {
+        self.version_id = version_id;
+        self
+    }
+
     pub fn update_validity(&mut self, validity: Duration) {
         self.expiry = Expiry::from_now(validity);
     }
@@ -444,6 +502,8 @@ impl WriteMode {
 pub struct WriteHandle {
     inner: Arc<SuperblockInner>,
     inode: Inode,
+    /// ETag of the remote object being overwritten, as observed when the handle was created.
+    overwritten_etag: Option<ETag>,
 }
 
 impl WriteHandle {
@@ -454,10 +514,17 @@ impl WriteHandle {
         mode: &WriteMode,
         is_truncate: bool,
     ) -> Result<Self, InodeError> {
+        if inode.synthetic().is_some() {
+            return Err(InodeError::InodeNotWritable(inode.err()));
+        }
         let mut state = inode.get_mut_inode_state()?;
         if state.reader_count > 0 {
             return Err(InodeError::InodeNotWritableWhileReading(inode.err()));
         }
+        let mut overwritten_etag = None;
+        // The kernel may cache what is written, which isn't what will be read back from S3
+        state.stat.opened_etag = None;
+        state.stat.tags = None;
         match state.write_status {
             WriteStatus::LocalUnopened => {
                 state.write_status = WriteStatus::LocalOpen;
@@ -471,13 +538,25 @@ impl WriteHandle {
 
                 if is_truncate {
                     state.stat.size = 0;
+                    overwritten_etag = state.stat.etag.as_deref().map(ETag::from);
                 }
 
                 state.write_status = WriteStatus::LocalOpen;
             }
         }
         drop(state);
-        Ok(Self { inner, inode })
+        Ok(Self {
+            inner,
+            inode,
+            overwritten_etag,
+        })
+    }
+
+    /// The ETag of the remote object this handle is overwriting, if any.
+    ///
+    /// Used to make the upload conditional on the object not having been modified since it was opened.
+    pub fn overwritten_etag(&self) -> Option<&ETag> {
+        self.overwritten_etag.as_ref()
     }
 
     pub fn inc_file_size(&self, len: usize) {
@@ -546,6 +625,7 @@ impl WriteHandle {
 #[derive(Debug)]
 pub struct ReadHandle {
     inode: Inode,
+    unchanged_since_last_open: bool,
 }
 
 impl ReadHandle {
@@ -556,8 +636,19 @@ impl ReadHandle {
             return Err(InodeError::InodeNotReadableWhileWriting(inode.err()));
         }
         state.reader_count += 1;
+        let unchanged_since_last_open = state.stat.etag.is_some() && state.stat.opened_etag == state.stat.etag;
+        state.stat.opened_etag = state.stat.etag.clone();
         drop(state);
-        Ok(Self { inode })
+        Ok(Self {
+            inode,
+            unchanged_since_last_open,
+        })
+    }
+
+    /// Whether the object had the same ETag the last time the file was opened for reading, so
+    /// that the content the kernel cached then can still be used.
+    pub fn is_unchanged_since_last_open(&self) -> bool {
+        self.unchanged_since_last_open
     }
 
     /// Update status of the inode to reflect the read being finished
@@ -586,11 +677,12 @@ mod tests {
         let ino = 42;
         let inode_name = "made-up-inode";
         let inode = Inode::new(
-            ino,
+            InodeNumber::unclaimed(ino),
             ROOT_INODE_NO,
             inode_name.to_owned(),
             inode_name.to_owned(),
             InodeKind::File,
+            None,
             InodeState {
                 write_status: WriteStatus::Remote,
                 stat: InodeStat::for_file(0, OffsetDateTime::now_utc(), None, None, None, Default::default()),
@@ -723,7 +815,9 @@ mod tests {
                 name: file_name.into(),
                 full_key: file_name.into(),
                 kind: InodeKind::File,
+                synthetic: None,
                 checksum: bad_checksum,
+                _ino_claim: None,
                 sync: RwLock::new(InodeState {
                     stat: InodeStat::for_file(
                         0,
@@ -783,7 +877,9 @@ mod tests {
                 name: inode_name.to_owned(),
                 full_key: inode_name.to_owned(),
                 kind: InodeKind::File,
+                synthetic: None,
                 checksum,
+                _ino_claim: None,
                 sync: RwLock::new(InodeState {
                     write_status: WriteStatus::LocalOpen,
                     stat: InodeStat::for_file(0, OffsetDateTime::UNIX_EPOCH, None, None, None, Default::default()),
@@ -0,0 +1,263 @@
+//! Allocation of inode numbers.
+//!
+//! By default, inode numbers come from a counter, so the same key gets a different number after
+//! every remount, and every time its inode is forgotten and looked up again. With stable inode
+//! numbers, the number of an inode is instead derived from a 64-bit hash of its key, so that tools
+//! keying caches on `(st_dev, st_ino)` see the same number for the same key across remounts.
+//!
+//! Two keys can hash to the same number. A number is never shared by two inodes in use at the same
+//! time, so while an inode keeps its number claimed, other keys with the same number probe a
+//! sequence of alternative numbers derived from their hash. This also happens for the same key
+//! when, for example, a file is replaced by a directory while the kernel still references the file.
+//! Claims are released when their inode is forgotten, so we only keep track of the inodes in use.
+
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::sync::atomic::{AtomicU64, Ordering};
+use crate::sync::{Arc, Mutex};
+
+use super::{InodeNo, SyntheticKind};
+
+/// The smallest inode number that can be handed out. Smaller numbers are reserved for the root.
+const FIRST_INODE_NO: InodeNo = crate::fs::FUSE_ROOT_INODE + 1;
+
+/// The number of a new inode, along with the claim that keeps it from being given to another inode
+/// while the inode exists.
+#[derive(Debug)]
+pub struct InodeNumber {
+    pub ino: InodeNo,
+    pub claim: Option<InodeNumberClaim>,
+}
+
+impl InodeNumber {
+    /// A number that isn't derived from a key, so doesn't need to be claimed
+    pub fn unclaimed(ino: InodeNo) -> Self {
+        Self { ino, claim: None }
+    }
+}
+
+/// Keeps a stable inode number claimed while an inode with this number is in use, and releases it
+/// when dropped.
+#[derive(Debug)]
+pub struct InodeNumberClaim {
+    ino: InodeNo,
+    claimed: Arc<Mutex<HashMap<InodeNo, KeyDigest>>>,
+}
+
+impl Drop for InodeNumberClaim {
+    fn drop(&mut self) {
+        self.claimed.lock().unwrap().remove(&self.ino);
+    }
+}
+
+/// Digest of everything that identifies an inode, to tell which key a number belongs to without
+/// keeping the key around
+type KeyDigest = [u8; 32];
+
+#[derive(Debug)]
+pub enum InodeNumbers {
+    /// Numbers are handed out in order, and never reused
+    Counter(AtomicU64),
+    /// Numbers are derived from the key of each inode
+    Stable {
+        /// Mixed into the hash of every key, so that the file systems of a union mount give
+        /// different numbers to the same key
+        namespace: String,
+        /// The digests of the keys of the inodes in use, by number
+        claimed: Arc<Mutex<HashMap<InodeNo, KeyDigest>>>,
+    },
+}
+
+impl InodeNumbers {
+    /// Create a new allocator. Stable numbers are derived from the namespace as well as the key.
+    pub fn new(stable: bool, namespace: &str) -> Self {
+        if stable {
+            Self::Stable {
+                namespace: namespace.to_owned(),
+                claimed: Default::default(),
+            }
+        } else {
+            Self::Counter(AtomicU64::new(FIRST_INODE_NO))
+        }
+    }
+
+    /// Allocate the number of a new inode with the given full key. Synthetic inodes are told apart
+    /// by their kind and name, since several of them share the key they are derived from.
+    ///
+    /// The returned number's claim must be kept by the inode for as long as it exists.
+    pub fn allocate(&self, full_key: &str, synthetic: Option<(SyntheticKind, &str)>) -> InodeNumber {
+        match self {
+            Self::Counter(next_ino) => InodeNumber::unclaimed(next_ino.fetch_add(1, Ordering::SeqCst)),
+            Self::Stable { namespace, claimed } => {
+                let digest = key_digest(namespace, full_key, synthetic);
+                let mut claimed_numbers = claimed.lock().unwrap();
+                let (attempt, ino) = probes(&digest)
+                    .enumerate()
+                    .find(|(_, ino)| !claimed_numbers.contains_key(ino))
+                    .expect("ran out of inode numbers");
+                if attempt > 0 {
+                    debug!(?full_key, ino, attempt, "using alternative inode number");
+                    metrics::counter!("superblock.stable_inode_collisions").increment(1);
+                }
+                claimed_numbers.insert(ino, digest);
+                let claim = InodeNumberClaim {
+                    ino,
+                    claimed: claimed.clone(),
+                };
+                InodeNumber {
+                    ino,
+                    claim: Some(claim),
+                }
+            }
+        }
+    }
+
+    /// The number of the inode in use for the given full key, if any, or else the number it would
+    /// be allocated if it was looked up now. Returns `None` if numbers are not derived from keys.
+    pub fn number_of(&self, full_key: &str) -> Option<InodeNo> {
+        let Self::Stable { namespace, claimed } = self else {
+            return None;
+        };
+        let digest = key_digest(namespace, full_key, None);
+        let claimed = claimed.lock().unwrap();
+        probes(&digest).find(|ino| match claimed.get(ino) {
+            None => true,
+            Some(claimed_digest) => *claimed_digest == digest,
+        })
+    }
+}
+
+/// Hash everything that identifies an inode. Each part is prefixed with its length, since keys can
+/// contain any character.
+fn key_digest(namespace: &str, full_key: &str, synthetic: Option<(SyntheticKind, &str)>) -> KeyDigest {
+    let mut hasher = Sha256::new();
+    for part in [namespace, full_key] {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part.as_bytes());
+    }
+    if let Some((kind, name)) = synthetic {
+        let kind: u8 = match kind {
+            SyntheticKind::VersionsDirectory => 1,
+            SyntheticKind::ObjectVersion => 2,
+        };
+        hasher.update([kind]);
+        hasher.update((name.len() as u64).to_be_bytes());
+        hasher.update(name.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// The inode number to try for a key on the given attempt. The first attempt uses the key digest
+/// directly, and later ones hash it again with the attempt number.
+fn probe(digest: &KeyDigest, attempt: u32) -> InodeNo {
+    let bytes: [u8; 32] = if attempt == 0 {
+        *digest
+    } else {
+        let mut hasher = Sha256::new();
+        hasher.update(digest);
+        hasher.update(attempt.to_be_bytes());
+        hasher.finalize().into()
+    };
+    u64::from_be_bytes(bytes[..8].try_into().unwrap())
+}
+
+/// The inode numbers to try for a key, in order, skipping the reserved ones
+fn probes(digest: &KeyDigest) -> impl Iterator<Item = InodeNo> + '_ {
+    (0u32..)
+        .map(|attempt| probe(digest, attempt))
+        .filter(|&ino| ino >= FIRST_INODE_NO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claimed_count(numbers: &InodeNumbers) -> usize {
+        let InodeNumbers::Stable { claimed, .. } = numbers else {
+            unreachable!()
+        };
+        claimed.lock().unwrap().len()
+    }
+
+    #[test]
+    fn test_counter_numbers() {
+        let numbers = InodeNumbers::new(false, "");
+        assert_eq!(numbers.allocate("a", None).ino, 2);
+        assert_eq!(numbers.allocate("a", None).ino, 3);
+        assert_eq!(numbers.number_of("a"), None);
+    }
+
+    #[test]
+    fn test_stable_numbers() {
+        let numbers = InodeNumbers::new(true, "");
+        let number = numbers.allocate("dir/a", None);
+        let ino = number.ino;
+        assert!(ino >= FIRST_INODE_NO);
+        assert!(number.claim.is_some());
+
+        // Numbers are the same in another mount, and can be found from the key
+        let other_mount = InodeNumbers::new(true, "");
+        assert_eq!(other_mount.number_of("dir/a"), Some(ino));
+        assert_eq!(other_mount.allocate("dir/a", None).ino, ino);
+        assert_eq!(numbers.number_of("dir/a"), Some(ino));
+
+        // But not in a mount with another namespace
+        let other_namespace = InodeNumbers::new(true, "other");
+        assert_ne!(other_namespace.allocate("dir/a", None).ino, ino);
+
+        // Other keys and synthetic inodes derived from the same key get other numbers
+        let other = numbers.allocate("dir/b", None);
+        assert_ne!(other.ino, ino);
+        let versions = numbers.allocate("dir/a", Some((SyntheticKind::VersionsDirectory, "a.versions")));
+        assert_ne!(versions.ino, ino);
+
+        // A number isn't shared with another inode for the same key while it's in use
+        let second = numbers.allocate("dir/a", None);
+        assert_ne!(second.ino, ino);
+        assert_eq!(claimed_count(&numbers), 4);
+
+        // But it's released once the inode is gone, and reused
+        drop(number);
+        drop(second);
+        assert_eq!(claimed_count(&numbers), 2);
+        assert_eq!(numbers.allocate("dir/a", None).ino, ino);
+        drop(other);
+        drop(versions);
+        assert_eq!(claimed_count(&numbers), 0);
+    }
+
+    #[test]
+    fn test_stable_numbers_use_64_bits() {
+        let numbers = InodeNumbers::new(true, "");
+        let high_bits = (0..100)
+            .map(|i| numbers.allocate(&format!("key-{i}"), None))
+            .any(|number| number.ino >> 48 != 0);
+        assert!(high_bits);
+    }
+
+    #[test]
+    fn test_stable_number_collision() {
+        let numbers = InodeNumbers::new(true, "");
+        let digest = key_digest("", "a", None);
+        let ino = probe(&digest, 0);
+
+        // Pretend that another key's inode has this key's number
+        let InodeNumbers::Stable { claimed, .. } = &numbers else {
+            unreachable!()
+        };
+        claimed.lock().unwrap().insert(ino, key_digest("", "b", None));
+
+        assert_eq!(numbers.number_of("a"), Some(probe(&digest, 1)));
+        let collided = numbers.allocate("a", None);
+        assert_eq!(collided.ino, probe(&digest, 1));
+        assert_eq!(numbers.number_of("a"), Some(probe(&digest, 1)));
+        assert_eq!(numbers.allocate("a", None).ino, probe(&digest, 2));
+
+        // Once the other key's inode is gone, the number is this key's again
+        claimed.lock().unwrap().remove(&ino);
+        assert_eq!(numbers.number_of("a"), Some(ino));
+    }
+}
@@ -0,0 +1,321 @@
+//! Invalidation of the kernel's caches when objects change in S3.
+//!
+//! The kernel caches the entries and attributes we reply with for as long as their TTL, and the
+//! content of a file for as long as its attributes don't change. When a lookup or a listing finds
+//! that an object has been replaced or deleted, the superblock asks for the affected entries and
+//! inodes to be dropped from the kernel's caches, through a callback set by the FUSE layer that
+//! sends the notifications. A listing can't tell a deleted object from one created since it
+//! started, so the children it doesn't return are only expired, and looked up again when next used.
+//!
+//! Changes are only noticed when something looks at the object, which the kernel doesn't do while
+//! its caches are valid. The superblock can also poll the most recently opened files in the
+//! background, so that changes to them are noticed within the polling interval.
+
+use std::collections::VecDeque;
+use std::fmt::{self, Debug};
+use std::io;
+use std::sync::OnceLock;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use futures::executor::block_on;
+use mountpoint_s3_client::ObjectClient;
+use tracing::{debug, trace};
+
+use crate::sync::mpsc::{channel, RecvTimeoutError, Sender};
+
+use super::{Inode, InodeError, InodeNo, Superblock, SuperblockInner};
+
+/// Maximum number of recently opened files to poll
+const MAX_RECENT_FILES: usize = 1000;
+
+/// A kernel cache entry to drop because the object behind it has changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Invalidation {
+    /// The attributes and content of an inode
+    Inode(InodeNo),
+    /// The entry for a name in a directory
+    Entry { parent: InodeNo, name: String },
+}
+
+type InvalidationCallback = Box<dyn Fn(Invalidation) + Send + Sync>;
+
+/// Where the superblock sends invalidations, once someone asks for them
+#[derive(Default)]
+pub struct KernelCache {
+    callback: OnceLock<InvalidationCallback>,
+}
+
+impl Debug for KernelCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KernelCache")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+impl KernelCache {
+    fn is_enabled(&self) -> bool {
+        self.callback.get().is_some()
+    }
+
+    fn invalidate(&self, invalidation: Invalidation) {
+        if let Some(callback) = self.callback.get() {
+            trace!(?invalidation, "invalidating kernel cache");
+            metrics::counter!("superblock.kernel_cache_invalidations").increment(1);
+            callback(invalidation);
+        }
+    }
+}
+
+/// The most recently opened files, most recent last
+#[derive(Debug, Default)]
+pub struct RecentFiles {
+    files: VecDeque<InodeNo>,
+}
+
+impl RecentFiles {
+    pub fn insert(&mut self, ino: InodeNo) {
+        if let Some(index) = self.files.iter().position(|file| *file == ino) {
+            self.files.remove(index);
+        }
+        self.files.push_back(ino);
+        if self.files.len() > MAX_RECENT_FILES {
+            self.files.pop_front();
+        }
+    }
+
+    fn remove(&mut self, ino: InodeNo) {
+        self.files.retain(|file| *file != ino);
+    }
+}
+
+/// Polls the most recently opened files of a superblock from a background thread, until dropped
+#[derive(Debug)]
+pub struct RecentFilesPoller {
+    shutdown: Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for RecentFilesPoller {
+    fn drop(&mut self) {
+        let _ = self.shutdown.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl SuperblockInner {
+    /// Whether the children of directories need to be checked against complete listings
+    pub(super) fn invalidates_kernel_cache(&self) -> bool {
+        self.kernel_cache.is_enabled()
+    }
+
+    /// Ask for an inode that no longer matches its object to be dropped from the kernel's caches,
+    /// along with its entry in its parent.
+    pub(super) fn invalidate(&self, inode: &Inode) {
+        self.invalidate_entry(inode.parent(), inode.name());
+        self.kernel_cache.invalidate(Invalidation::Inode(inode.ino()));
+    }
+
+    /// Ask for the entry for a name in a directory to be dropped from the kernel's caches, whether
+    /// it's cached as existing or as missing.
+    pub(super) fn invalidate_entry(&self, parent: InodeNo, name: &str) {
+        self.kernel_cache.invalidate(Invalidation::Entry {
+            parent,
+            name: name.to_owned(),
+        });
+    }
+
+    /// Ask for the attributes and listing of a directory whose children have changed to be dropped
+    /// from the kernel's caches.
+    pub(super) fn invalidate_listing(&self, dir: &Inode) {
+        self.kernel_cache.invalidate(Invalidation::Inode(dir.ino()));
+    }
+}
+
+impl Superblock {
+    /// Send the entries and inodes to drop from the kernel's caches to `callback`, as changes to
+    /// their objects are noticed. The callback must not block, since it's called with inodes
+    /// locked, and can only be set once.
+    pub fn on_invalidation(&self, callback: impl Fn(Invalidation) + Send + Sync + 'static) {
+        let set = self.inner.kernel_cache.callback.set(Box::new(callback));
+        assert!(set.is_ok(), "the invalidation callback can only be set once");
+    }
+
+    /// Look up the most recently opened files in S3 again, so that changes to them are noticed.
+    /// Files being written are skipped, and files that are gone are no longer polled.
+    pub async fn poll_recent_files<OC: ObjectClient>(&self, client: &OC) {
+        let Some(recent_files) = &self.inner.recent_files else {
+            return;
+        };
+        let files = recent_files.lock().unwrap().files.clone();
+        trace!(count = files.len(), "polling recently opened files");
+        for ino in files {
+            let result = match self.inner.get(ino).and_then(|inode| inode.is_remote()) {
+                Ok(true) => self.getattr(client, ino, true).await.map(|_| ()),
+                Ok(false) => continue,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => (),
+                Err(
+                    e @ (InodeError::InodeDoesNotExist(_)
+                    | InodeError::FileDoesNotExist(..)
+                    | InodeError::StaleInode { .. }),
+                ) => {
+                    debug!(ino, "no longer polling file: {e}");
+                    recent_files.lock().unwrap().remove(ino);
+                }
+                Err(e) => debug!(ino, "failed to poll file: {e}"),
+            }
+        }
+    }
+
+    /// Start polling the most recently opened files with [Superblock::poll_recent_files] from a
+    /// background thread, at the given interval.
+    pub fn start_polling<OC>(&self, client: OC, interval: Duration) -> io::Result<RecentFilesPoller>
+    where
+        OC: ObjectClient + Send + Sync + 'static,
+    {
+        let superblock = Superblock {
+            inner: self.inner.clone(),
+        };
+        let (shutdown, receiver) = channel();
+        let thread = thread::Builder::new()
+            .name("recent-files-poller".to_owned())
+            .spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(interval) {
+                    block_on(superblock.poll_recent_files(&client));
+                }
+            })?;
+        Ok(RecentFilesPoller {
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockObject};
+    use mountpoint_s3_client::types::ETag;
+
+    use crate::fs::FUSE_ROOT_INODE;
+    use crate::prefix::Prefix;
+
+    use super::super::SuperblockConfig;
+    use super::*;
+
+    fn record_invalidations(superblock: &Superblock) -> Arc<Mutex<Vec<Invalidation>>> {
+        let invalidations = Arc::new(Mutex::new(Vec::new()));
+        let recorded = invalidations.clone();
+        superblock.on_invalidation(move |invalidation| recorded.lock().unwrap().push(invalidation));
+        invalidations
+    }
+
+    fn invalidated(ino: InodeNo, name: &str) -> [Invalidation; 2] {
+        [
+            Invalidation::Entry {
+                parent: FUSE_ROOT_INODE,
+                name: name.to_owned(),
+            },
+            Invalidation::Inode(ino),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_changed_objects() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024 * 1024,
+            ..Default::default()
+        });
+        client.add_object("changed", MockObject::constant(0xaa, 30, ETag::for_tests()));
+        client.add_object("deleted", MockObject::constant(0xaa, 30, ETag::for_tests()));
+        client.add_object("listed", MockObject::constant(0xaa, 30, ETag::for_tests()));
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), Default::default());
+        let invalidations = record_invalidations(&superblock);
+
+        let mut inos = Vec::new();
+        for name in ["changed", "deleted", "listed"] {
+            let lookup = superblock
+                .lookup(&client, FUSE_ROOT_INODE, name.as_ref())
+                .await
+                .unwrap();
+            inos.push(lookup.inode.ino());
+        }
+
+        // Lookups notice changed and deleted objects
+        let new_etag = ETag::from_str("\"new_etag\"").unwrap();
+        client.add_object("changed", MockObject::constant(0xbb, 30, new_etag));
+        client.remove_object("deleted");
+        let changed = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "changed".as_ref())
+            .await
+            .unwrap();
+        assert_ne!(changed.inode.ino(), inos[0]);
+        superblock
+            .lookup(&client, FUSE_ROOT_INODE, "deleted".as_ref())
+            .await
+            .expect_err("deleted object should not be found");
+        let expected = [invalidated(inos[0], "changed"), invalidated(inos[1], "deleted")].concat();
+        assert_eq!(*invalidations.lock().unwrap(), expected);
+
+        // Complete listings notice the children they don't return, and unchanged objects aren't
+        // invalidated
+        invalidations.lock().unwrap().clear();
+        client.remove_object("listed");
+        let dir_handle = superblock.readdir(&client, FUSE_ROOT_INODE, 10).await.unwrap();
+        let entries = dir_handle.collect(&client).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(*invalidations.lock().unwrap(), invalidated(inos[2], "listed"));
+    }
+
+    #[tokio::test]
+    async fn test_poll_recent_files() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024 * 1024,
+            ..Default::default()
+        });
+        client.add_object("opened", MockObject::constant(0xaa, 30, ETag::for_tests()));
+        client.add_object("not_opened", MockObject::constant(0xaa, 30, ETag::for_tests()));
+        let config = SuperblockConfig {
+            poll_recent_files: true,
+            ..Default::default()
+        };
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), config);
+        let invalidations = record_invalidations(&superblock);
+
+        let opened = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "opened".as_ref())
+            .await
+            .unwrap();
+        superblock
+            .lookup(&client, FUSE_ROOT_INODE, "not_opened".as_ref())
+            .await
+            .unwrap();
+        let read_handle = superblock.read(&client, opened.inode.ino()).await.unwrap();
+        read_handle.finish().unwrap();
+
+        // Unchanged files stay polled
+        superblock.poll_recent_files(&client).await;
+        assert!(invalidations.lock().unwrap().is_empty());
+
+        // Only the opened file is polled, and only until it changes
+        client.remove_object("opened");
+        client.remove_object("not_opened");
+        superblock.poll_recent_files(&client).await;
+        assert_eq!(
+            *invalidations.lock().unwrap(),
+            invalidated(opened.inode.ino(), "opened")
+        );
+        let recent_files = superblock.inner.recent_files.as_ref().unwrap();
+        assert!(recent_files.lock().unwrap().files.is_empty());
+    }
+}
@@ -40,19 +40,25 @@
 //!   representing the local children of the directory.
 //!   These children are listed only once, at the start of the readdir operation, and so are a
 //!   snapshot in time of the directory.
+//!   With a manifest, [RemoteIter] lists its entries from the manifest rather than S3.
+//! * [VersionsIter] is an iterator over the [ReaddirEntry]s of a synthetic versions directory,
+//!   returned by paginated calls to ListObjectVersions. These directories have no local children,
+//!   so [ReaddirIter] uses it directly.
 
 use std::cmp::Ordering;
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
 
-use mountpoint_s3_client::types::ObjectInfo;
+use mountpoint_s3_client::types::{ObjectInfo, ObjectVersionInfo};
 use mountpoint_s3_client::ObjectClient;
 use tracing::{error, trace, warn};
 
+use crate::manifest::ManifestObject;
 use crate::sync::{Arc, AsyncMutex, Mutex};
 
 use super::{
-    inode::valid_inode_name, InodeError, InodeKind, InodeKindData, InodeNo, InodeStat, LookedUp, RemoteLookup,
-    SuperblockInner,
+    versions, InodeError, InodeKind, InodeKindData, InodeNo, InodeStat, LookedUp, RemoteLookup, SuperblockInner,
+    SyntheticKind,
 };
 
 /// Handle for an inflight directory listing
@@ -63,6 +69,8 @@ pub struct ReaddirHandle {
     parent_ino: InodeNo,
     iter: AsyncMutex<ReaddirIter>,
     readded: Mutex<Option<LookedUp>>,
+    /// Names returned so far, when children missing from the listing need to be expired
+    listed: Mutex<Option<HashSet<String>>>,
 }
 
 impl ReaddirHandle {
@@ -73,11 +81,11 @@ impl ReaddirHandle {
         full_path: String,
         page_size: usize,
     ) -> Result<Self, InodeError> {
+        let dir = inner.get(dir_ino)?;
         let local_entries = {
-            let inode = inner.get(dir_ino)?;
-            let kind_data = &inode.get_inode_state()?.kind_data;
+            let kind_data = &dir.get_inode_state()?.kind_data;
             let local_files = match kind_data {
-                InodeKindData::File { .. } => return Err(InodeError::NotADirectory(inode.err())),
+                InodeKindData::File { .. } => return Err(InodeError::NotADirectory(dir.err())),
                 InodeKindData::Directory { writing_children, .. } => writing_children.iter().map(|ino| {
                     let inode = inner.get(*ino)?;
                     let stat = inode.get_inode_state()?.stat.clone();
@@ -99,18 +107,21 @@ impl ReaddirHandle {
             }
         };
 
-        let iter = if inner.config.s3_personality.is_list_ordered() {
-            ReaddirIter::ordered(&inner.bucket, &full_path, page_size, local_entries.into())
+        let iter = if dir.synthetic() == Some(SyntheticKind::VersionsDirectory) {
+            ReaddirIter::Versions(VersionsIter::new(inner.clone(), &full_path, page_size))
+        } else if inner.config.s3_personality.is_list_ordered() {
+            ReaddirIter::ordered(inner.clone(), &full_path, page_size, local_entries.into())
         } else {
-            ReaddirIter::unordered(&inner.bucket, &full_path, page_size, local_entries.into())
+            ReaddirIter::unordered(inner.clone(), &full_path, page_size, local_entries.into())
         };
 
         Ok(Self {
-            inner,
             dir_ino,
             parent_ino,
             iter: AsyncMutex::new(iter),
             readded: Default::default(),
+            listed: Mutex::new(inner.invalidates_kernel_cache().then(Default::default)),
+            inner,
         })
     }
 
@@ -132,13 +143,20 @@ impl ReaddirHandle {
 
             if let Some(next) = next {
                 // Short-circuit the update if we know it'll fail because the name is invalid
-                if !valid_inode_name(next.name()) {
+                if !self.inner.is_valid_name(next.name(), next.inode_kind()) {
                     warn!("{} has an invalid name and will be unavailable", next.description());
                 } else {
                     let lookup = self.instantiate_remote_inode(next)?;
+                    if let Some(listed) = self.listed.lock().unwrap().as_mut() {
+                        listed.insert(lookup.inode.name().to_owned());
+                    }
                     return Ok(Some(lookup));
                 }
             } else {
+                let listed = self.listed.lock().unwrap().take();
+                if let Some(listed) = listed {
+                    self.expire_unlisted(&listed);
+                }
                 return Ok(None);
             }
         }
@@ -161,6 +179,33 @@ impl ReaddirHandle {
         self.parent_ino
     }
 
+    /// Expire the remote children of the directory that a complete listing didn't return, and
+    /// drop them from the kernel's caches. Their objects may have been deleted, or only created
+    /// after the listing went past them, so they need to be looked up again.
+    fn expire_unlisted(&self, listed: &HashSet<String>) {
+        let Ok(dir) = self.inner.get(self.dir_ino) else {
+            return;
+        };
+        let Ok(dir_state) = dir.get_inode_state() else {
+            return;
+        };
+        let InodeKindData::Directory { children, .. } = &dir_state.kind_data else {
+            return;
+        };
+        for child in children.values() {
+            if listed.contains(child.name()) || child.synthetic().is_some() || !matches!(child.is_remote(), Ok(true)) {
+                continue;
+            }
+            let Ok(mut child_state) = child.get_mut_inode_state() else {
+                continue;
+            };
+            trace!(parent=?self.dir_ino, name=?child.name(), ino=?child.ino(), "child missing from listing");
+            child_state.stat.update_validity(Duration::from_secs(0));
+            drop(child_state);
+            self.inner.invalidate(child);
+        }
+    }
+
     /// Create or update an inode for the given ReaddirEntry.
     fn instantiate_remote_inode(&self, entry: ReaddirEntry) -> Result<LookedUp, InodeError> {
         let remote_lookup = match &entry {
@@ -173,6 +218,7 @@ impl ReaddirHandle {
                 Some(RemoteLookup {
                     stat,
                     kind: InodeKind::Directory,
+                    synthetic: None,
                 })
             }
             ReaddirEntry::RemoteObject { object_info, .. } => {
@@ -187,8 +233,44 @@ impl ReaddirHandle {
                 Some(RemoteLookup {
                     stat,
                     kind: InodeKind::File,
+                    synthetic: None,
+                })
+            }
+            ReaddirEntry::ManifestObject { object, .. } => {
+                let stat = InodeStat::for_file(
+                    object.size as usize,
+                    object.last_modified.unwrap_or(self.inner.mount_time),
+                    Some(object.etag.clone()),
+                    None,
+                    None,
+                    self.inner.config.cache_config.file_ttl,
+                );
+                Some(RemoteLookup {
+                    stat,
+                    kind: InodeKind::File,
+                    synthetic: None,
+                })
+            }
+            ReaddirEntry::RemoteObjectVersion { version_info, .. } => {
+                let stat = InodeStat::for_file(
+                    version_info.size as usize,
+                    version_info.last_modified,
+                    version_info.etag.clone(),
+                    version_info.storage_class.clone(),
+                    None,
+                    self.inner.config.cache_config.file_ttl,
+                )
+                .with_version_id(Some(version_info.version_id.clone()));
+                Some(RemoteLookup {
+                    stat,
+                    kind: InodeKind::File,
+                    synthetic: None,
                 })
             }
+            ReaddirEntry::VersionsDirectory { key, .. } => Some(self.inner.versions_directory_lookup(key.clone())),
+            ReaddirEntry::ObjectVersion { version_info, .. } => {
+                Some(self.inner.object_version_lookup(version_info.clone()))
+            }
         };
         self.inner.update_from_remote(self.dir_ino, entry.name(), remote_lookup)
     }
@@ -207,9 +289,36 @@ impl ReaddirHandle {
 /// should be done lazily by the consumer of the entry.
 #[derive(Debug, Clone)]
 enum ReaddirEntry {
-    RemotePrefix { name: String },
-    RemoteObject { name: String, object_info: ObjectInfo },
-    LocalInode { lookup: LookedUp },
+    RemotePrefix {
+        name: String,
+    },
+    RemoteObject {
+        name: String,
+        object_info: ObjectInfo,
+    },
+    /// An object listed in the manifest the file system is served from
+    ManifestObject {
+        name: String,
+        object: ManifestObject,
+    },
+    /// The version of an object that was current at the time the file system shows
+    RemoteObjectVersion {
+        name: String,
+        version_info: ObjectVersionInfo,
+    },
+    LocalInode {
+        lookup: LookedUp,
+    },
+    /// The versions directory of an object or directory, in a versions directory
+    VersionsDirectory {
+        name: String,
+        key: String,
+    },
+    /// A version of an object, in its versions directory
+    ObjectVersion {
+        name: String,
+        version_info: ObjectVersionInfo,
+    },
 }
 
 // This looks a little silly but makes the [Ord] implementation for [ReaddirEntry] a bunch clearer
@@ -225,18 +334,37 @@ impl ReaddirEntry {
         match self {
             Self::RemotePrefix { name } => name,
             Self::RemoteObject { name, .. } => name,
+            Self::ManifestObject { name, .. } => name,
+            Self::RemoteObjectVersion { name, .. } => name,
             Self::LocalInode { lookup } => lookup.inode.name(),
+            Self::VersionsDirectory { name, .. } => name,
+            Self::ObjectVersion { name, .. } => name,
         }
     }
 
     fn kind(&self) -> ReaddirEntryKind {
         match self {
-            Self::RemotePrefix { .. } => ReaddirEntryKind::RemotePrefix,
-            Self::RemoteObject { .. } => ReaddirEntryKind::RemoteObject,
+            Self::RemotePrefix { .. } | Self::VersionsDirectory { .. } => ReaddirEntryKind::RemotePrefix,
+            Self::RemoteObject { .. }
+            | Self::ManifestObject { .. }
+            | Self::RemoteObjectVersion { .. }
+            | Self::ObjectVersion { .. } => ReaddirEntryKind::RemoteObject,
             Self::LocalInode { .. } => ReaddirEntryKind::LocalInode,
         }
     }
 
+    /// The kind of inode this entry would become
+    fn inode_kind(&self) -> InodeKind {
+        match self {
+            Self::RemotePrefix { .. } | Self::VersionsDirectory { .. } => InodeKind::Directory,
+            Self::RemoteObject { .. }
+            | Self::ManifestObject { .. }
+            | Self::RemoteObjectVersion { .. }
+            | Self::ObjectVersion { .. } => InodeKind::File,
+            Self::LocalInode { lookup } => lookup.inode.kind(),
+        }
+    }
+
     /// How to describe this entry in an error message
     fn description(&self) -> String {
         match self {
@@ -246,6 +374,15 @@ impl ReaddirEntry {
             Self::RemoteObject { name, object_info } => {
                 format!("file '{}' (full key {:?})", name, object_info.key)
             }
+            Self::ManifestObject { name, object } => {
+                format!("file '{}' (full key {:?})", name, object.key)
+            }
+            Self::RemoteObjectVersion { name, version_info } => {
+                format!(
+                    "file '{}' (full key {:?}, version {:?})",
+                    name, version_info.key, version_info.version_id
+                )
+            }
             Self::LocalInode { lookup } => {
                 let kind = match lookup.inode.kind() {
                     InodeKind::Directory => "directory",
@@ -253,6 +390,15 @@ impl ReaddirEntry {
                 };
                 format!("local {} '{}'", kind, lookup.inode.name())
             }
+            Self::VersionsDirectory { name, key } => {
+                format!("versions directory '{name}' (full key {key:?})")
+            }
+            Self::ObjectVersion { name, version_info } => {
+                format!(
+                    "version '{}' (full key {:?}, version {:?})",
+                    name, version_info.key, version_info.version_id
+                )
+            }
         }
     }
 }
@@ -290,21 +436,33 @@ impl Ord for ReaddirEntry {
 enum ReaddirIter {
     Ordered(ordered::ReaddirIter),
     Unordered(unordered::ReaddirIter),
+    Versions(VersionsIter),
 }
 
 impl ReaddirIter {
-    fn ordered(bucket: &str, full_path: &str, page_size: usize, local_entries: VecDeque<ReaddirEntry>) -> Self {
-        Self::Ordered(ordered::ReaddirIter::new(bucket, full_path, page_size, local_entries))
+    fn ordered(
+        inner: Arc<SuperblockInner>,
+        full_path: &str,
+        page_size: usize,
+        local_entries: VecDeque<ReaddirEntry>,
+    ) -> Self {
+        Self::Ordered(ordered::ReaddirIter::new(inner, full_path, page_size, local_entries))
     }
 
-    fn unordered(bucket: &str, full_path: &str, page_size: usize, local_entries: VecDeque<ReaddirEntry>) -> Self {
-        Self::Unordered(unordered::ReaddirIter::new(bucket, full_path, page_size, local_entries))
+    fn unordered(
+        inner: Arc<SuperblockInner>,
+        full_path: &str,
+        page_size: usize,
+        local_entries: VecDeque<ReaddirEntry>,
+    ) -> Self {
+        Self::Unordered(unordered::ReaddirIter::new(inner, full_path, page_size, local_entries))
     }
 
     async fn next(&mut self, client: &impl ObjectClient) -> Result<Option<ReaddirEntry>, InodeError> {
         match self {
             Self::Ordered(iter) => iter.next(client).await,
             Self::Unordered(iter) => iter.next(client).await,
+            Self::Versions(iter) => iter.next(client).await,
         }
     }
 }
@@ -313,6 +471,11 @@ impl ReaddirIter {
 enum RemoteIterState {
     /// Next ListObjects call should use this continuation token
     InProgress(Option<String>),
+    /// Next ListObjectVersions call should use these key and version markers
+    InProgressVersions {
+        key_marker: Option<String>,
+        version_id_marker: Option<String>,
+    },
     /// No more ListObjects calls to make
     Finished,
 }
@@ -322,11 +485,17 @@ enum RemoteIterState {
 ///
 /// If the S3 implementation returns ordered results, this iterator will re-sort the stream to
 /// account for common prefixes not being in lexicographic order (see the module comment).
+///
+/// When the file system shows the objects as they were at a point in time, this iterator uses
+/// paginated ListObjectVersions calls instead, and only returns the files and directories that
+/// existed at that time.
+///
+/// Entries whose keys are hidden by the key filter are skipped.
 #[derive(Debug)]
 struct RemoteIter {
     /// Prepared entries in order to be returned by the iterator.
     entries: VecDeque<ReaddirEntry>,
-    bucket: String,
+    inner: Arc<SuperblockInner>,
     /// S3 prefix for the [RemoteIter], used when listing objects in S3.
     full_path: String,
     /// The maximum number of keys to be returned by a single S3 ListObjectsV2 request.
@@ -334,42 +503,70 @@ struct RemoteIter {
     state: RemoteIterState,
     /// Does the S3 implementation return ordered results?
     ordered: bool,
+    /// Resolves the versions returned by ListObjectVersions, if listing at a point in time.
+    version_resolver: Option<versions::VersionResolver>,
 }
 
 impl RemoteIter {
-    fn new(bucket: &str, full_path: &str, page_size: usize, ordered: bool) -> Self {
+    fn new(inner: Arc<SuperblockInner>, full_path: &str, page_size: usize, ordered: bool) -> Self {
+        let as_of = inner.config.as_of;
+        let state = match as_of {
+            Some(_) => RemoteIterState::InProgressVersions {
+                key_marker: None,
+                version_id_marker: None,
+            },
+            None => RemoteIterState::InProgress(None),
+        };
         Self {
             entries: VecDeque::new(),
-            bucket: bucket.to_owned(),
+            inner,
             full_path: full_path.to_owned(),
             page_size,
-            state: RemoteIterState::InProgress(None),
+            state,
             ordered,
+            version_resolver: as_of.map(versions::VersionResolver::new),
         }
     }
 
     async fn next(&mut self, client: &impl ObjectClient) -> Result<Option<ReaddirEntry>, InodeError> {
-        if self.entries.is_empty() {
+        // Loop because a page may have no entries, for example if none of its versions existed at the
+        // time we are listing.
+        while self.entries.is_empty() {
             let continuation_token = match &mut self.state {
                 RemoteIterState::Finished => {
                     trace!(self=?self as *const _, prefix=?self.full_path, "remote iter finished");
                     return Ok(None);
                 }
                 RemoteIterState::InProgress(token) => token.take(),
+                RemoteIterState::InProgressVersions {
+                    key_marker,
+                    version_id_marker,
+                } => {
+                    let (key_marker, version_id_marker) = (key_marker.take(), version_id_marker.take());
+                    self.list_object_versions(client, key_marker, version_id_marker).await?;
+                    continue;
+                }
             };
 
+            if self.inner.config.manifest.is_some() {
+                self.list_manifest(continuation_token);
+                continue;
+            }
+
             trace!(self=?self as *const _, prefix=?self.full_path, ?continuation_token, "continuing remote iter");
 
             let result = client
                 .list_objects(
-                    &self.bucket,
+                    &self.inner.bucket,
                     continuation_token.as_deref(),
                     "/",
                     self.page_size,
                     self.full_path.as_str(),
                 )
                 .await
-                .map_err(|e| InodeError::client_error(e, "ListObjectsV2 failed", &self.bucket, &self.full_path))?;
+                .map_err(|e| {
+                    InodeError::client_error(e, "ListObjectsV2 failed", &self.inner.bucket, &self.full_path)
+                })?;
 
             self.state = match result.next_continuation_token {
                 Some(token) => RemoteIterState::InProgress(Some(token)),
@@ -379,13 +576,18 @@ impl RemoteIter {
             let prefixes = result
                 .common_prefixes
                 .into_iter()
+                .filter(|prefix| self.inner.is_key_visible(prefix))
                 .map(|prefix| ReaddirEntry::RemotePrefix {
                     name: prefix[self.full_path.len()..prefix.len() - 1].to_owned(),
                 });
 
+            // The directory marker object of the directory being listed is what makes it a directory,
+            // rather than a file with an empty name inside it.
             let objects = result
                 .objects
                 .into_iter()
+                .filter(|object_info| object_info.key != self.full_path)
+                .filter(|object_info| self.inner.is_key_visible(&object_info.key))
                 .map(|object_info| ReaddirEntry::RemoteObject {
                     name: object_info.key[self.full_path.len()..].to_owned(),
                     object_info,
@@ -407,6 +609,225 @@ impl RemoteIter {
 
         Ok(self.entries.pop_front())
     }
+
+    /// List the next page of entries from the manifest, starting at the key in the continuation
+    /// token.
+    fn list_manifest(&mut self, start_at: Option<String>) {
+        trace!(self=?self as *const _, prefix=?self.full_path, ?start_at, "continuing manifest iter");
+
+        let manifest = self
+            .inner
+            .config
+            .manifest
+            .as_ref()
+            .expect("listing requires a manifest");
+        let listing = manifest.list(&self.full_path, start_at.as_deref(), self.page_size);
+        self.state = match listing.next_start_at {
+            Some(start_at) => RemoteIterState::InProgress(Some(start_at)),
+            None => RemoteIterState::Finished,
+        };
+
+        let prefixes = listing
+            .common_prefixes
+            .into_iter()
+            .filter(|prefix| self.inner.is_key_visible(prefix))
+            .map(|prefix| ReaddirEntry::RemotePrefix {
+                name: prefix[self.full_path.len()..prefix.len() - 1].to_owned(),
+            });
+        let objects = listing
+            .objects
+            .into_iter()
+            .filter(|object| object.key != self.full_path)
+            .filter(|object| self.inner.is_key_visible(&object.key))
+            .map(|object| ReaddirEntry::ManifestObject {
+                name: object.key[self.full_path.len()..].to_owned(),
+                object,
+            });
+
+        // The manifest is sorted, but has the same problem with common prefixes as ListObjectsV2.
+        let mut new_entries = prefixes.chain(objects).collect::<Vec<_>>();
+        new_entries.sort();
+
+        self.entries.extend(new_entries);
+    }
+
+    /// List the next page of object versions, and queue up the entries that existed at the time of
+    /// the [versions::VersionResolver].
+    async fn list_object_versions(
+        &mut self,
+        client: &impl ObjectClient,
+        key_marker: Option<String>,
+        version_id_marker: Option<String>,
+    ) -> Result<(), InodeError> {
+        trace!(self=?self as *const _, prefix=?self.full_path, ?key_marker, ?version_id_marker, "continuing remote versions iter");
+
+        let result = client
+            .list_object_versions(
+                &self.inner.bucket,
+                key_marker.as_deref(),
+                version_id_marker.as_deref(),
+                "/",
+                self.page_size,
+                self.full_path.as_str(),
+            )
+            .await
+            .map_err(|e| {
+                InodeError::client_error(e, "ListObjectVersions failed", &self.inner.bucket, &self.full_path)
+            })?;
+
+        self.state = match result.next_key_marker {
+            Some(key_marker) => RemoteIterState::InProgressVersions {
+                key_marker: Some(key_marker),
+                version_id_marker: result.next_version_id_marker,
+            },
+            None => RemoteIterState::Finished,
+        };
+
+        let resolver = self
+            .version_resolver
+            .as_mut()
+            .expect("listing versions requires a version resolver");
+
+        // A common prefix only existed at that time if any key under it did.
+        let bucket = &self.inner.bucket;
+        let as_of = resolver.as_of();
+        let common_prefixes = result
+            .common_prefixes
+            .into_iter()
+            .filter(|prefix| self.inner.is_key_visible(prefix));
+        let prefixes = futures::future::try_join_all(common_prefixes.map(|prefix| async move {
+            let exists = versions::prefix_exists_as_of(client, bucket, &prefix, as_of).await?;
+            Ok::<_, InodeError>(exists.then_some(prefix))
+        }))
+        .await?;
+        let prefixes = prefixes.into_iter().flatten().map(|prefix| ReaddirEntry::RemotePrefix {
+            name: prefix[self.full_path.len()..prefix.len() - 1].to_owned(),
+        });
+
+        let objects = result
+            .versions
+            .into_iter()
+            .filter_map(|version| resolver.resolve(version))
+            .filter(|version_info| self.inner.is_key_visible(&version_info.key))
+            .map(|version_info| ReaddirEntry::RemoteObjectVersion {
+                name: version_info.key[self.full_path.len()..].to_owned(),
+                version_info,
+            });
+
+        // ListObjectVersions results are sorted by key, but have the same problem with common
+        // prefixes as ListObjectsV2.
+        let mut new_entries = prefixes.chain(objects).collect::<Vec<_>>();
+        new_entries.sort();
+
+        self.entries.extend(new_entries);
+        Ok(())
+    }
+}
+
+/// An iterator over the [ReaddirEntry]s of a versions directory, returned by paginated
+/// ListObjectVersions calls to S3.
+///
+/// The versions directory of an object lists its versions, from the most recent, skipping delete
+/// markers. The versions directory of a directory (whose key ends in '/') lists the versions
+/// directories of its children, including ones that have since been deleted.
+#[derive(Debug)]
+struct VersionsIter {
+    /// Prepared entries in order to be returned by the iterator.
+    entries: VecDeque<ReaddirEntry>,
+    inner: Arc<SuperblockInner>,
+    /// Key of the object or directory whose versions are listed
+    key: String,
+    /// The maximum number of keys to be returned by a single S3 ListObjectVersions request.
+    page_size: usize,
+    state: RemoteIterState,
+    /// Names of the children already returned, since a child can appear as both an object and a
+    /// directory, and the versions of an object can span pages.
+    seen_names: HashSet<String>,
+}
+
+impl VersionsIter {
+    fn new(inner: Arc<SuperblockInner>, key: &str, page_size: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            inner,
+            key: key.to_owned(),
+            page_size,
+            state: RemoteIterState::InProgressVersions {
+                key_marker: None,
+                version_id_marker: None,
+            },
+            seen_names: HashSet::new(),
+        }
+    }
+
+    async fn next(&mut self, client: &impl ObjectClient) -> Result<Option<ReaddirEntry>, InodeError> {
+        while self.entries.is_empty() {
+            let RemoteIterState::InProgressVersions {
+                key_marker,
+                version_id_marker,
+            } = &mut self.state
+            else {
+                trace!(self=?self as *const _, key=?self.key, "versions iter finished");
+                return Ok(None);
+            };
+            let (key_marker, version_id_marker) = (key_marker.take(), version_id_marker.take());
+
+            trace!(self=?self as *const _, key=?self.key, ?key_marker, ?version_id_marker, "continuing versions iter");
+
+            let result = client
+                .list_object_versions(
+                    &self.inner.bucket,
+                    key_marker.as_deref(),
+                    version_id_marker.as_deref(),
+                    "/",
+                    self.page_size,
+                    &self.key,
+                )
+                .await
+                .map_err(|e| InodeError::client_error(e, "ListObjectVersions failed", &self.inner.bucket, &self.key))?;
+
+            self.state = match result.next_key_marker {
+                Some(key_marker) => RemoteIterState::InProgressVersions {
+                    key_marker: Some(key_marker),
+                    version_id_marker: result.next_version_id_marker,
+                },
+                None => RemoteIterState::Finished,
+            };
+
+            if !self.key.ends_with('/') {
+                // The versions of the object come first, followed by any keys it is a prefix of.
+                for version_info in result.versions {
+                    if version_info.key != self.key {
+                        self.state = RemoteIterState::Finished;
+                        break;
+                    }
+                    if !version_info.is_delete_marker {
+                        let name = versions::version_entry_name(&version_info);
+                        self.entries
+                            .push_back(ReaddirEntry::ObjectVersion { name, version_info });
+                    }
+                }
+                continue;
+            }
+
+            // Directories shadow objects with the same name, so take the common prefixes first.
+            let prefixes = result.common_prefixes.into_iter();
+            let keys = result.versions.into_iter().map(|version_info| version_info.key);
+            for key in prefixes.chain(keys) {
+                let name = key[self.key.len()..].trim_end_matches('/');
+                if name.is_empty() || self.seen_names.contains(name) || !self.inner.is_key_visible(&key) {
+                    continue;
+                }
+                self.seen_names.insert(name.to_owned());
+                self.entries.push_back(ReaddirEntry::VersionsDirectory {
+                    name: name.to_owned(),
+                    key,
+                });
+            }
+        }
+
+        Ok(self.entries.pop_front())
+    }
 }
 
 /// Iterator implementation for S3 implementations that provide lexicographically ordered LIST.
@@ -429,13 +850,13 @@ mod ordered {
 
     impl ReaddirIter {
         pub(super) fn new(
-            bucket: &str,
+            inner: Arc<SuperblockInner>,
             full_path: &str,
             page_size: usize,
             local_entries: VecDeque<ReaddirEntry>,
         ) -> Self {
             Self {
-                remote: RemoteIter::new(bucket, full_path, page_size, true),
+                remote: RemoteIter::new(inner, full_path, page_size, true),
                 local: LocalIter::new(local_entries),
                 next_remote: None,
                 next_local: None,
@@ -536,7 +957,7 @@ mod unordered {
 
     impl ReaddirIter {
         pub(super) fn new(
-            bucket: &str,
+            inner: Arc<SuperblockInner>,
             full_path: &str,
             page_size: usize,
             local_entries: VecDeque<ReaddirEntry>,
@@ -552,7 +973,7 @@ mod unordered {
                 .collect::<HashMap<_, _>>();
 
             Self {
-                remote: RemoteIter::new(bucket, full_path, page_size, false),
+                remote: RemoteIter::new(inner, full_path, page_size, false),
                 local: local_map,
                 local_iter: VecDeque::new(),
             }
@@ -0,0 +1,306 @@
+//! Recursive deletion of everything under a directory, with batched DeleteObjects requests.
+//!
+//! Deleting a large directory tree one `unlink` at a time costs a DeleteObject request per file,
+//! serialized through FUSE. Instead, we list every key under the directory's prefix and delete
+//! them in batches of up to [MAX_DELETE_OBJECTS_KEYS], with a few batches in flight at once. Keys
+//! that S3 fails to delete are logged and counted, but don't stop the rest of the deletion.
+
+use std::time::Duration;
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use mountpoint_s3_client::types::MAX_DELETE_OBJECTS_KEYS;
+use mountpoint_s3_client::ObjectClient;
+use tracing::{debug, warn};
+
+use crate::fs::FUSE_ROOT_INODE;
+
+use super::{InodeError, InodeKind, InodeKindData, InodeNo, Superblock};
+
+/// Maximum number of DeleteObjects requests to have in flight at once.
+const MAX_CONCURRENT_DELETE_REQUESTS: usize = 8;
+
+/// Outcome of a [Superblock::delete_recursive] call.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RecursiveDeleteSummary {
+    /// Number of objects deleted
+    pub deleted: usize,
+    /// Number of objects S3 failed to delete
+    pub failed: usize,
+}
+
+impl Superblock {
+    /// Delete every object under the directory `dir_ino` from S3, and forget the directory's
+    /// children so that they are looked up again.
+    ///
+    /// Keys hidden by the key filter are not deleted. Fails without deleting anything if the
+    /// directory is the root of the mount, whose prefix covers the whole mount (or bucket), or if a
+    /// file directly in the directory is being written.
+    pub async fn delete_recursive<OC: ObjectClient>(
+        &self,
+        client: &OC,
+        dir_ino: InodeNo,
+    ) -> Result<RecursiveDeleteSummary, InodeError> {
+        let dir = self.inner.get(dir_ino)?;
+        if dir.kind() != InodeKind::Directory {
+            return Err(InodeError::NotADirectory(dir.err()));
+        }
+        if dir_ino == FUSE_ROOT_INODE || dir.synthetic().is_some() {
+            return Err(InodeError::InodeNotWritable(dir.err()));
+        }
+        if let InodeKindData::Directory { writing_children, .. } = &dir.get_inode_state()?.kind_data {
+            if !writing_children.is_empty() {
+                return Err(InodeError::UnlinkNotPermittedWhileWriting(dir.err()));
+            }
+        }
+
+        let (bucket, prefix) = (self.inner.bucket.as_str(), dir.full_key());
+        debug!(inode=%dir.err(), "deleting everything under prefix {:?}", prefix);
+
+        let delete_batch = |keys: Vec<String>| async move {
+            let result = client
+                .delete_objects(bucket, &keys)
+                .await
+                .map_err(|e| InodeError::client_error(e, "DeleteObjects failed", bucket, prefix))?;
+            for error in &result.errors {
+                warn!(key=?error.key, code=?error.code, "failed to delete object: {}", error.message);
+            }
+            Ok::<_, InodeError>(RecursiveDeleteSummary {
+                deleted: keys.len() - result.errors.len(),
+                failed: result.errors.len(),
+            })
+        };
+
+        let mut summary = RecursiveDeleteSummary::default();
+        let mut add_batch = |batch: RecursiveDeleteSummary| {
+            metrics::counter!("superblock.delete_recursive.objects", "result" => "deleted")
+                .increment(batch.deleted as u64);
+            metrics::counter!("superblock.delete_recursive.objects", "result" => "failed")
+                .increment(batch.failed as u64);
+            summary.deleted += batch.deleted;
+            summary.failed += batch.failed;
+        };
+
+        let mut deletes = FuturesUnordered::new();
+        let mut continuation_token = None;
+        loop {
+            let result = client
+                .list_objects(
+                    bucket,
+                    continuation_token.as_deref(),
+                    "",
+                    MAX_DELETE_OBJECTS_KEYS,
+                    prefix,
+                )
+                .await
+                .map_err(|e| InodeError::client_error(e, "ListObjectsV2 failed", bucket, prefix))?;
+            let keys = result
+                .objects
+                .into_iter()
+                .map(|object_info| object_info.key)
+                .filter(|key| self.inner.is_key_visible(key))
+                .collect::<Vec<_>>();
+            if !keys.is_empty() {
+                if deletes.len() >= MAX_CONCURRENT_DELETE_REQUESTS {
+                    let batch = deletes.next().await.expect("deletes should not be empty")?;
+                    add_batch(batch);
+                }
+                deletes.push(delete_batch(keys));
+            }
+            continuation_token = result.next_continuation_token;
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+        while let Some(batch) = deletes.next().await {
+            add_batch(batch?);
+        }
+
+        // The directory's children no longer exist in S3 (except for those that failed to be
+        // deleted), so drop them from our caches and the kernel's, and have the directory looked
+        // up again.
+        let mut dir_state = dir.get_mut_inode_state()?;
+        dir_state.stat.update_validity(Duration::from_secs(0));
+        if let InodeKindData::Directory {
+            children,
+            writing_children,
+            ..
+        } = &mut dir_state.kind_data
+        {
+            children.retain(|_, child| {
+                let retain = writing_children.contains(&child.ino());
+                if !retain {
+                    self.inner.invalidate(child);
+                }
+                retain
+            });
+        }
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockObject, Operation};
+    use mountpoint_s3_client::types::{ETag, ObjectLock};
+    use test_case::test_case;
+    use time::OffsetDateTime;
+
+    use crate::prefix::Prefix;
+
+    use super::super::Invalidation;
+    use super::*;
+
+    fn make_client() -> MockClient {
+        MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024 * 1024,
+            ..Default::default()
+        })
+    }
+
+    fn add_object(client: &MockClient, key: &str) {
+        client.add_object(key, MockObject::constant(0xaa, 15, ETag::for_tests()));
+    }
+
+    #[test_case(""; "unprefixed")]
+    #[test_case("dir/"; "prefixed")]
+    #[tokio::test]
+    async fn test_delete_recursive_root(prefix: &str) {
+        let client = make_client();
+        add_object(&client, "dir/file");
+        add_object(&client, "other");
+        let superblock = Superblock::new("test_bucket", &Prefix::new(prefix).unwrap(), Default::default());
+
+        let list_counter = client.new_counter(Operation::ListObjectsV2);
+        let delete_counter = client.new_counter(Operation::DeleteObjects);
+        let err = superblock
+            .delete_recursive(&client, FUSE_ROOT_INODE)
+            .await
+            .expect_err("the root of the mount can't be deleted recursively");
+        assert!(matches!(err, InodeError::InodeNotWritable(_)));
+        assert_eq!(list_counter.count(), 0);
+        assert_eq!(delete_counter.count(), 0);
+        assert_eq!(client.object_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_delete_recursive_batches() {
+        let client = make_client();
+        let count = 2 * MAX_DELETE_OBJECTS_KEYS + 500;
+        for i in 0..count {
+            add_object(&client, &format!("dir/{}/file{i}", i % 10));
+        }
+        add_object(&client, "dir2/file");
+        add_object(&client, "other");
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), Default::default());
+        let dir = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "dir".as_ref())
+            .await
+            .unwrap();
+
+        let delete_counter = client.new_counter(Operation::DeleteObjects);
+        let summary = superblock.delete_recursive(&client, dir.inode.ino()).await.unwrap();
+        assert_eq!(
+            summary,
+            RecursiveDeleteSummary {
+                deleted: count,
+                failed: 0
+            }
+        );
+        assert_eq!(delete_counter.count(), 3);
+        assert_eq!(client.object_count(), 2);
+        assert!(client.contains_key("dir2/file"));
+        assert!(client.contains_key("other"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_recursive_failed_keys() {
+        let client = make_client();
+        add_object(&client, "dir/a");
+        add_object(&client, "dir/b");
+        let retain_until_date = OffsetDateTime::now_utc() + Duration::from_secs(24 * 60 * 60);
+        let mut locked = MockObject::constant(0xaa, 15, ETag::for_tests());
+        locked.set_object_lock(ObjectLock::new().retention("COMPLIANCE".to_owned(), retain_until_date));
+        client.add_object("dir/locked", locked);
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), Default::default());
+        let dir = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "dir".as_ref())
+            .await
+            .unwrap();
+
+        // Keys that fail to delete are counted, and don't stop the others from being deleted
+        let summary = superblock.delete_recursive(&client, dir.inode.ino()).await.unwrap();
+        assert_eq!(summary, RecursiveDeleteSummary { deleted: 2, failed: 1 });
+        assert!(!client.contains_key("dir/a"));
+        assert!(!client.contains_key("dir/b"));
+        assert!(client.contains_key("dir/locked"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_recursive_while_writing() {
+        let client = make_client();
+        add_object(&client, "dir/a");
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), Default::default());
+        let dir = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "dir".as_ref())
+            .await
+            .unwrap();
+        superblock
+            .create(&client, dir.inode.ino(), "new".as_ref(), InodeKind::File)
+            .await
+            .unwrap();
+
+        let delete_counter = client.new_counter(Operation::DeleteObjects);
+        let err = superblock
+            .delete_recursive(&client, dir.inode.ino())
+            .await
+            .expect_err("directories with files being written can't be deleted");
+        assert!(matches!(err, InodeError::UnlinkNotPermittedWhileWriting(_)));
+        assert_eq!(delete_counter.count(), 0);
+        assert!(client.contains_key("dir/a"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_recursive_invalidates_children() {
+        let client = make_client();
+        add_object(&client, "dir/a");
+        add_object(&client, "dir/sub/b");
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), Default::default());
+        let invalidations = Arc::new(Mutex::new(Vec::new()));
+        let recorded = invalidations.clone();
+        superblock.on_invalidation(move |invalidation| recorded.lock().unwrap().push(invalidation));
+
+        let dir_ino = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "dir".as_ref())
+            .await
+            .unwrap()
+            .inode
+            .ino();
+        let mut child_inos = Vec::new();
+        for name in ["a", "sub"] {
+            let lookup = superblock.lookup(&client, dir_ino, name.as_ref()).await.unwrap();
+            child_inos.push(lookup.inode.ino());
+        }
+
+        superblock.delete_recursive(&client, dir_ino).await.unwrap();
+        let invalidations = invalidations.lock().unwrap();
+        for (name, ino) in ["a", "sub"].into_iter().zip(child_inos) {
+            let entry = Invalidation::Entry {
+                parent: dir_ino,
+                name: name.to_owned(),
+            };
+            assert!(invalidations.contains(&entry), "entry {name:?} should be invalidated");
+            assert!(invalidations.contains(&Invalidation::Inode(ino)));
+        }
+        drop(invalidations);
+
+        let err = superblock
+            .lookup(&client, dir_ino, "a".as_ref())
+            .await
+            .expect_err("deleted children should be looked up again");
+        assert!(matches!(err, InodeError::FileDoesNotExist(_, _)));
+    }
+}
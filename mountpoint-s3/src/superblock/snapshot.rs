@@ -0,0 +1,400 @@
+//! Snapshots of the metadata cache, to start a new mount with a warm cache.
+//!
+//! A snapshot records every remote file and directory the superblock knows about, with the
+//! metadata served from the cache and the time that metadata was last checked against S3. When a
+//! snapshot is loaded, each entry is only valid for what is left of the configured TTL since it was
+//! last checked, so loading a snapshot never extends how long metadata is trusted. A snapshot also
+//! records the mount options that decide which keys are shown and under which names, and is
+//! discarded entirely if any of them differ.
+
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use time::OffsetDateTime;
+use tracing::{debug, warn};
+
+use super::{Inode, InodeKind, InodeKindData, InodeStat, InodeState, Superblock, SuperblockInner, WriteStatus};
+
+/// Written before the snapshot, and bumped whenever the format of [MetadataSnapshot] changes
+const SNAPSHOT_VERSION: u32 = 2;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("I/O error accessing metadata snapshot")]
+    IoError(#[from] io::Error),
+
+    #[error("invalid metadata snapshot")]
+    InvalidSnapshot(#[from] bincode::Error),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MetadataSnapshot {
+    options: SnapshotOptions,
+    /// Every entry comes after its parent directory
+    entries: Vec<SnapshotEntry>,
+}
+
+/// The mount options that change which keys are shown, and how
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SnapshotOptions {
+    bucket: String,
+    prefix: String,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    escape_names: bool,
+    directory_markers: bool,
+    show_versions: bool,
+    /// As a Unix timestamp in nanoseconds
+    as_of: Option<i128>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotEntry {
+    /// Index of the parent directory in the entries plus one, or 0 for the root
+    parent: usize,
+    name: String,
+    is_directory: bool,
+    size: usize,
+    /// As a Unix timestamp in nanoseconds
+    mtime: i128,
+    etag: Option<String>,
+    is_readable: bool,
+    storage_class: Option<String>,
+    version_id: Option<String>,
+    /// When the metadata was last checked against S3, as a Unix timestamp in nanoseconds
+    checked: i128,
+}
+
+impl SuperblockInner {
+    fn snapshot_options(&self) -> SnapshotOptions {
+        let (include, exclude) = self.config.key_filter.patterns();
+        SnapshotOptions {
+            bucket: self.bucket.clone(),
+            prefix: self.prefix.to_string(),
+            include,
+            exclude,
+            escape_names: self.config.escape_names,
+            directory_markers: self.config.directory_markers,
+            show_versions: self.config.show_versions,
+            as_of: self.config.as_of.map(OffsetDateTime::unix_timestamp_nanos),
+        }
+    }
+
+    /// The TTL new metadata for an inode of this kind is valid for
+    fn ttl(&self, kind: InodeKind) -> Duration {
+        match kind {
+            InodeKind::File => self.config.cache_config.file_ttl,
+            InodeKind::Directory => self.config.cache_config.dir_ttl,
+        }
+    }
+}
+
+impl Superblock {
+    fn root(&self) -> Inode {
+        self.inner
+            .get(crate::fs::FUSE_ROOT_INODE)
+            .expect("the root inode is always known")
+    }
+
+    /// Save the metadata of every remote file and directory to a snapshot file, returning the
+    /// number of entries saved. The file is replaced atomically.
+    pub fn save_snapshot(&self, path: &Path) -> Result<usize, SnapshotError> {
+        let now = OffsetDateTime::now_utc();
+        let mut entries = Vec::new();
+        let mut directories = VecDeque::from([(0, self.root())]);
+        while let Some((index, dir)) = directories.pop_front() {
+            let Ok(dir_state) = dir.get_inode_state() else {
+                continue;
+            };
+            let InodeKindData::Directory { children, .. } = &dir_state.kind_data else {
+                continue;
+            };
+            for child in children.values() {
+                if child.synthetic().is_some() {
+                    continue;
+                }
+                let Ok(state) = child.get_inode_state() else {
+                    continue;
+                };
+                if state.write_status != WriteStatus::Remote {
+                    continue;
+                }
+                // The TTL started when the metadata was last checked, so whatever has run out of it
+                // is the time since then.
+                let elapsed = self
+                    .inner
+                    .ttl(child.kind())
+                    .saturating_sub(state.stat.expiry.remaining_ttl());
+                entries.push(SnapshotEntry {
+                    parent: index,
+                    name: child.name().to_owned(),
+                    is_directory: child.kind() == InodeKind::Directory,
+                    size: state.stat.size,
+                    mtime: state.stat.mtime.unix_timestamp_nanos(),
+                    etag: state.stat.etag.clone(),
+                    is_readable: state.stat.is_readable,
+                    storage_class: state.stat.storage_class.clone(),
+                    version_id: state.stat.version_id.clone(),
+                    checked: (now - elapsed).unix_timestamp_nanos(),
+                });
+                if child.kind() == InodeKind::Directory {
+                    directories.push_back((entries.len(), child.clone()));
+                }
+            }
+        }
+
+        let snapshot = MetadataSnapshot {
+            options: self.inner.snapshot_options(),
+            entries,
+        };
+        let temp_path = path.with_extension("tmp");
+        let mut file = BufWriter::new(File::create(&temp_path)?);
+        bincode::serialize_into(&mut file, &SNAPSHOT_VERSION)?;
+        bincode::serialize_into(&mut file, &snapshot)?;
+        file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        Ok(snapshot.entries.len())
+    }
+
+    /// Load the metadata from a snapshot file saved by [Superblock::save_snapshot] into a new
+    /// superblock, returning the number of entries loaded. Snapshots saved with other mount
+    /// options, and entries whose TTL has since run out, are ignored.
+    pub fn load_snapshot(&self, path: &Path) -> Result<usize, SnapshotError> {
+        let mut file = BufReader::new(File::open(path)?);
+        let version: u32 = bincode::deserialize_from(&mut file)?;
+        if version != SNAPSHOT_VERSION {
+            warn!(?path, version, "ignoring metadata snapshot with unknown version");
+            return Ok(0);
+        }
+        let snapshot: MetadataSnapshot = bincode::deserialize_from(file)?;
+        if snapshot.options != self.inner.snapshot_options() {
+            warn!(?path, "ignoring metadata snapshot saved with different mount options");
+            debug!(saved=?snapshot.options, current=?self.inner.snapshot_options(), "mount options differ");
+            return Ok(0);
+        }
+
+        let now = OffsetDateTime::now_utc();
+        let mut inodes = vec![Some(self.root())];
+        for entry in snapshot.entries {
+            let kind = if entry.is_directory {
+                InodeKind::Directory
+            } else {
+                InodeKind::File
+            };
+            let validity = OffsetDateTime::from_unix_timestamp_nanos(entry.checked)
+                .ok()
+                .and_then(|checked| Duration::try_from(now - checked).ok())
+                .and_then(|elapsed| self.inner.ttl(kind).checked_sub(elapsed));
+            // Expired files are of no use, but expired directories still hold their children.
+            let validity = match (kind, validity) {
+                (InodeKind::File, None) => {
+                    inodes.push(None);
+                    continue;
+                }
+                (_, validity) => validity.unwrap_or_default(),
+            };
+            let Some(parent) = inodes.get(entry.parent).cloned().flatten() else {
+                inodes.push(None);
+                continue;
+            };
+
+            let mtime = OffsetDateTime::from_unix_timestamp_nanos(entry.mtime).unwrap_or(OffsetDateTime::UNIX_EPOCH);
+            let stat = InodeStat {
+                size: entry.size,
+                etag: entry.etag,
+                is_readable: entry.is_readable,
+                storage_class: entry.storage_class,
+                version_id: entry.version_id,
+                ..InodeStat::for_directory(mtime, validity)
+            };
+            let state = InodeState::new(&stat, kind, WriteStatus::Remote);
+            let inode = parent.get_mut_inode_state().ok().and_then(|mut parent_state| {
+                self.inner
+                    .create_inode_locked(&parent, &mut parent_state, &entry.name, kind, None, state, false)
+                    .ok()
+            });
+            inodes.push(inode);
+        }
+        let loaded = inodes.iter().skip(1).filter(|inode| inode.is_some()).count();
+        Ok(loaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockObject};
+    use mountpoint_s3_client::types::ETag;
+    use test_case::test_case;
+
+    use crate::fs::{CacheConfig, FUSE_ROOT_INODE};
+    use crate::prefix::Prefix;
+
+    use super::super::{InodeError, SuperblockConfig};
+    use super::*;
+
+    fn make_client() -> MockClient {
+        MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024 * 1024,
+            ..Default::default()
+        })
+    }
+
+    fn make_config(file_ttl: Duration) -> SuperblockConfig {
+        SuperblockConfig {
+            cache_config: CacheConfig {
+                serve_lookup_from_cache: true,
+                file_ttl,
+                dir_ttl: Duration::from_secs(600),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Save a snapshot of a superblock that has looked up `dir/file.txt` and `other.txt`, and has
+    /// a local file being written.
+    async fn save_snapshot(path: &Path) {
+        let client = make_client();
+        client.add_object("dir/file.txt", MockObject::constant(0xaa, 15, ETag::for_tests()));
+        let mut other = MockObject::constant(0xaa, 30, ETag::for_tests());
+        other.set_storage_class(Some("STANDARD_IA".to_owned()));
+        client.add_object("other.txt", other);
+        let config = make_config(Duration::from_secs(600));
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), config);
+        let dir = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "dir".as_ref())
+            .await
+            .unwrap();
+        superblock
+            .lookup(&client, dir.inode.ino(), "file.txt".as_ref())
+            .await
+            .unwrap();
+        superblock
+            .lookup(&client, FUSE_ROOT_INODE, "other.txt".as_ref())
+            .await
+            .unwrap();
+        superblock
+            .create(&client, dir.inode.ino(), "local.txt".as_ref(), InodeKind::File)
+            .await
+            .unwrap();
+
+        let saved = superblock.save_snapshot(path).unwrap();
+        assert_eq!(saved, 3, "local files should not be saved");
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        let snapshot_dir = tempfile::tempdir().unwrap();
+        let path = snapshot_dir.path().join("snapshot");
+        save_snapshot(&path).await;
+
+        // The loaded metadata is served from the cache, without asking S3
+        let client = make_client();
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), make_config(Duration::from_secs(600)));
+        assert_eq!(superblock.load_snapshot(&path).unwrap(), 3);
+        let dir = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "dir".as_ref())
+            .await
+            .unwrap();
+        assert_eq!(dir.inode.kind(), InodeKind::Directory);
+        let file = superblock
+            .lookup(&client, dir.inode.ino(), "file.txt".as_ref())
+            .await
+            .unwrap();
+        assert_eq!(file.stat.size, 15);
+        assert_eq!(file.stat.etag.as_deref(), Some(ETag::for_tests().as_str()));
+        let other = superblock
+            .lookup(&client, FUSE_ROOT_INODE, "other.txt".as_ref())
+            .await
+            .unwrap();
+        assert_eq!(other.stat.size, 30);
+        assert_eq!(other.stat.storage_class.as_deref(), Some("STANDARD_IA"));
+        let err = superblock
+            .lookup(&client, dir.inode.ino(), "local.txt".as_ref())
+            .await
+            .expect_err("local files are not saved");
+        assert!(matches!(err, InodeError::FileDoesNotExist(_, _)));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_expired_entries() {
+        let snapshot_dir = tempfile::tempdir().unwrap();
+        let path = snapshot_dir.path().join("snapshot");
+        save_snapshot(&path).await;
+
+        // With no TTL left for files, only the directories are loaded
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), make_config(Duration::ZERO));
+        assert_eq!(superblock.load_snapshot(&path).unwrap(), 1);
+    }
+
+    #[test_case("other_bucket", "", false; "bucket")]
+    #[test_case("test_bucket", "dir/", false; "prefix")]
+    #[test_case("test_bucket", "", true; "escape names")]
+    #[tokio::test]
+    async fn test_snapshot_options_mismatch(bucket: &str, prefix: &str, escape_names: bool) {
+        let snapshot_dir = tempfile::tempdir().unwrap();
+        let path = snapshot_dir.path().join("snapshot");
+        save_snapshot(&path).await;
+
+        let config = SuperblockConfig {
+            escape_names,
+            ..make_config(Duration::from_secs(600))
+        };
+        let superblock = Superblock::new(bucket, &Prefix::new(prefix).unwrap(), config);
+        assert_eq!(superblock.load_snapshot(&path).unwrap(), 0);
+        let root = superblock.root();
+        let root_state = root.get_inode_state().unwrap();
+        let InodeKindData::Directory { children, .. } = &root_state.kind_data else {
+            panic!("root should be a directory");
+        };
+        assert!(children.is_empty(), "nothing should be loaded");
+    }
+
+    #[test_case(SuperblockConfig { directory_markers: true, ..make_config(Duration::from_secs(600)) }; "directory markers")]
+    #[test_case(SuperblockConfig { show_versions: true, ..make_config(Duration::from_secs(600)) }; "show versions")]
+    #[tokio::test]
+    async fn test_snapshot_config_mismatch(config: SuperblockConfig) {
+        let snapshot_dir = tempfile::tempdir().unwrap();
+        let path = snapshot_dir.path().join("snapshot");
+        save_snapshot(&path).await;
+
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), config);
+        assert_eq!(superblock.load_snapshot(&path).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_corrupt() {
+        let snapshot_dir = tempfile::tempdir().unwrap();
+        let path = snapshot_dir.path().join("snapshot");
+        let superblock = Superblock::new("test_bucket", &Prefix::default(), make_config(Duration::from_secs(600)));
+
+        let err = superblock.load_snapshot(&path).expect_err("snapshot doesn't exist");
+        assert!(matches!(err, SnapshotError::IoError(e) if e.kind() == io::ErrorKind::NotFound));
+
+        save_snapshot(&path).await;
+        let data = fs::read(&path).unwrap();
+
+        // Truncated and corrupt snapshots are rejected
+        fs::write(&path, &data[..data.len() - 10]).unwrap();
+        let err = superblock.load_snapshot(&path).expect_err("snapshot is truncated");
+        assert!(matches!(err, SnapshotError::InvalidSnapshot(_)));
+        // The bucket name follows the version and its length, and is no longer valid UTF-8
+        let mut garbage = data.clone();
+        garbage[12..12 + "test_bucket".len()].fill(0xff);
+        fs::write(&path, &garbage).unwrap();
+        let err = superblock.load_snapshot(&path).expect_err("snapshot is corrupt");
+        assert!(matches!(err, SnapshotError::InvalidSnapshot(_)));
+
+        // Snapshots of another version are ignored
+        let mut other_version = data;
+        other_version[..4].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+        fs::write(&path, &other_version).unwrap();
+        assert_eq!(superblock.load_snapshot(&path).unwrap(), 0);
+    }
+}
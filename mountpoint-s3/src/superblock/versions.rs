@@ -0,0 +1,337 @@
+//! Utilities for resolving the objects in a versioned bucket as they were at a point in time.
+//!
+//! When the file system is mounted with an `as_of` time, lookups and directory listings use
+//! ListObjectVersions instead of HeadObject and ListObjectsV2. For each key, the version that was
+//! current at that time is the most recent one last modified at or before it. If that version is a
+//! delete marker, or there is no such version, the key didn't exist at that time.
+//!
+//! This module also supports the virtual `<name>.versions` directories, which list every version
+//! of an object (or, for a directory, the versions directories of its children) as read-only files.
+
+use mountpoint_s3_client::types::ObjectVersionInfo;
+use mountpoint_s3_client::ObjectClient;
+use time::format_description::FormatItem;
+use time::{macros, OffsetDateTime, UtcOffset};
+
+use super::InodeError;
+
+/// Maximum number of versions to request in a single ListObjectVersions call.
+const LIST_VERSIONS_PAGE_SIZE: usize = 1000;
+
+/// Suffix of the names of the virtual directories that list the versions of an object or directory.
+pub(super) const VERSIONS_DIRECTORY_SUFFIX: &str = ".versions";
+
+/// Tracks the versions of keys in a ListObjectVersions stream, which returns the versions of each
+/// key from the most recent, to find the version that was current at a point in time.
+#[derive(Debug)]
+pub(super) struct VersionResolver {
+    as_of: OffsetDateTime,
+    /// The last key we found the current version for (or found not to exist)
+    last_resolved_key: Option<String>,
+}
+
+impl VersionResolver {
+    pub(super) fn new(as_of: OffsetDateTime) -> Self {
+        Self {
+            as_of,
+            last_resolved_key: None,
+        }
+    }
+
+    /// The time to find the current versions at.
+    pub(super) fn as_of(&self) -> OffsetDateTime {
+        self.as_of
+    }
+
+    /// Feed the next version in the stream. Returns `Some` if this is the version of its key that
+    /// was current at the resolver's time, and `None` if it is not, or if it is a delete marker.
+    pub(super) fn resolve(&mut self, version: ObjectVersionInfo) -> Option<ObjectVersionInfo> {
+        if self.last_resolved_key.as_deref() == Some(version.key.as_str()) || version.last_modified > self.as_of {
+            return None;
+        }
+        self.last_resolved_key = Some(version.key.clone());
+        (!version.is_delete_marker).then_some(version)
+    }
+}
+
+/// Find the version of the given key that was current at the given time, if any.
+pub(super) async fn resolve_key_as_of<OC: ObjectClient>(
+    client: &OC,
+    bucket: &str,
+    key: &str,
+    as_of: OffsetDateTime,
+) -> Result<Option<ObjectVersionInfo>, InodeError> {
+    let mut key_marker = None;
+    let mut version_id_marker = None;
+    loop {
+        // The versions of `key` come first when listing it as a prefix. Use a delimiter so that
+        // we don't page through the versions of any keys "under" it.
+        let result = client
+            .list_object_versions(
+                bucket,
+                key_marker.as_deref(),
+                version_id_marker.as_deref(),
+                "/",
+                LIST_VERSIONS_PAGE_SIZE,
+                key,
+            )
+            .await
+            .map_err(|e| InodeError::client_error(e, "ListObjectVersions failed", bucket, key))?;
+
+        for version in result.versions {
+            if version.key != key {
+                return Ok(None);
+            }
+            if version.last_modified <= as_of {
+                return Ok((!version.is_delete_marker).then_some(version));
+            }
+        }
+
+        match result.next_key_marker {
+            Some(next_key_marker) if next_key_marker == key => {
+                key_marker = Some(next_key_marker);
+                version_id_marker = result.next_version_id_marker;
+            }
+            _ => return Ok(None),
+        }
+    }
+}
+
+/// Check whether any key with the given prefix existed at the given time.
+pub(super) async fn prefix_exists_as_of<OC: ObjectClient>(
+    client: &OC,
+    bucket: &str,
+    prefix: &str,
+    as_of: OffsetDateTime,
+) -> Result<bool, InodeError> {
+    let mut resolver = VersionResolver::new(as_of);
+    let mut key_marker = None;
+    let mut version_id_marker = None;
+    loop {
+        let result = client
+            .list_object_versions(
+                bucket,
+                key_marker.as_deref(),
+                version_id_marker.as_deref(),
+                "",
+                LIST_VERSIONS_PAGE_SIZE,
+                prefix,
+            )
+            .await
+            .map_err(|e| InodeError::client_error(e, "ListObjectVersions failed", bucket, prefix))?;
+
+        if result
+            .versions
+            .into_iter()
+            .any(|version| resolver.resolve(version).is_some())
+        {
+            return Ok(true);
+        }
+
+        match result.next_key_marker {
+            Some(next_key_marker) => {
+                key_marker = Some(next_key_marker);
+                version_id_marker = result.next_version_id_marker;
+            }
+            None => return Ok(false),
+        }
+    }
+}
+
+/// Name of the file serving the given version in the versions directory of its object, made of the
+/// time the version was last modified and its version ID. Version IDs can contain '/', which can't
+/// appear in a file name, so we percent-encode it.
+pub(super) fn version_entry_name(version: &ObjectVersionInfo) -> String {
+    const TIMESTAMP_FORMAT: &[FormatItem<'static>] =
+        macros::format_description!("[year]-[month]-[day]T[hour]-[minute]-[second]Z");
+    let timestamp = version
+        .last_modified
+        .to_offset(UtcOffset::UTC)
+        .format(TIMESTAMP_FORMAT)
+        .expect("couldn't format timestamp for version name");
+    let version_id = version.version_id.replace('%', "%25").replace('/', "%2F");
+    format!("{timestamp}_{version_id}")
+}
+
+/// Find the key that the versions directory for `key` lists: `key/` if there are or were any
+/// objects under it, or otherwise `key` if there are any versions of that object. As for lookups,
+/// directories shadow files.
+pub(super) async fn find_versions_directory_key<OC: ObjectClient>(
+    client: &OC,
+    bucket: &str,
+    key: &str,
+) -> Result<Option<String>, InodeError> {
+    let directory_key = format!("{key}/");
+    let (object_result, directory_result) = futures::try_join!(
+        client.list_object_versions(bucket, None, None, "/", 1, key),
+        client.list_object_versions(bucket, None, None, "/", 1, &directory_key),
+    )
+    .map_err(|e| InodeError::client_error(e, "ListObjectVersions failed", bucket, key))?;
+
+    if !directory_result.versions.is_empty() || !directory_result.common_prefixes.is_empty() {
+        Ok(Some(directory_key))
+    } else if object_result.versions.first().is_some_and(|version| version.key == key) {
+        Ok(Some(key.to_owned()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Find the version of the object at `key` that the file with the given name in its versions
+/// directory serves, if any.
+pub(super) async fn find_version_by_name<OC: ObjectClient>(
+    client: &OC,
+    bucket: &str,
+    key: &str,
+    name: &str,
+) -> Result<Option<ObjectVersionInfo>, InodeError> {
+    let mut key_marker = None;
+    let mut version_id_marker = None;
+    loop {
+        let result = client
+            .list_object_versions(
+                bucket,
+                key_marker.as_deref(),
+                version_id_marker.as_deref(),
+                "/",
+                LIST_VERSIONS_PAGE_SIZE,
+                key,
+            )
+            .await
+            .map_err(|e| InodeError::client_error(e, "ListObjectVersions failed", bucket, key))?;
+
+        for version in result.versions {
+            if version.key != key {
+                return Ok(None);
+            }
+            if !version.is_delete_marker && version_entry_name(&version) == name {
+                return Ok(Some(version));
+            }
+        }
+
+        match result.next_key_marker {
+            Some(next_key_marker) if next_key_marker == key => {
+                key_marker = Some(next_key_marker);
+                version_id_marker = result.next_version_id_marker;
+            }
+            _ => return Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockObject};
+    use mountpoint_s3_client::types::ETag;
+
+    use super::*;
+
+    fn time(seconds: i64) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(seconds).unwrap()
+    }
+
+    fn add_version(client: &MockClient, key: &str, version_id: &str, last_modified: i64) {
+        let mut object = MockObject::constant(0u8, 5, ETag::for_tests());
+        object.set_last_modified(time(last_modified));
+        client.add_object_version(key, version_id, object);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_key_as_of() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            ..Default::default()
+        });
+        add_version(&client, "a", "a1", 100);
+        add_version(&client, "a", "a2", 200);
+        client.add_delete_marker("a", "a3", time(300));
+        add_version(&client, "a", "a4", 400);
+        add_version(&client, "a/b", "ab1", 50);
+
+        for (as_of, expected) in [
+            (50, None),
+            (100, Some("a1")),
+            (250, Some("a2")),
+            (300, None),
+            (500, Some("a4")),
+        ] {
+            let version = resolve_key_as_of(&client, "test_bucket", "a", time(as_of))
+                .await
+                .unwrap();
+            assert_eq!(
+                version.map(|v| v.version_id).as_deref(),
+                expected,
+                "wrong version at {as_of}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prefix_exists_as_of() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            ..Default::default()
+        });
+        add_version(&client, "dir/a", "a1", 100);
+        client.add_delete_marker("dir/a", "a2", time(200));
+        add_version(&client, "dir/sub/b", "b1", 300);
+        client.add_delete_marker("dir/sub/b", "b2", time(400));
+
+        for (as_of, expected) in [(50, false), (150, true), (250, false), (350, true), (450, false)] {
+            let exists = prefix_exists_as_of(&client, "test_bucket", "dir/", time(as_of))
+                .await
+                .unwrap();
+            assert_eq!(exists, expected, "wrong result at {as_of}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_versions_directory_key() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            ..Default::default()
+        });
+        add_version(&client, "file", "f1", 100);
+        add_version(&client, "file-2", "f2", 100);
+        add_version(&client, "shadowed", "s1", 100);
+        add_version(&client, "shadowed/a", "a1", 100);
+        add_version(&client, "deleted/b", "b1", 100);
+        client.add_delete_marker("deleted/b", "b2", time(200));
+
+        for (key, expected) in [
+            ("file", Some("file")),
+            ("fil", None),
+            ("shadowed", Some("shadowed/")),
+            ("deleted", Some("deleted/")),
+            ("missing", None),
+        ] {
+            let found = find_versions_directory_key(&client, "test_bucket", key).await.unwrap();
+            assert_eq!(found.as_deref(), expected, "wrong result for {key}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_version_by_name() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            ..Default::default()
+        });
+        add_version(&client, "a", "a/1", 100);
+        client.add_delete_marker("a", "a2", time(200));
+        add_version(&client, "a", "a3", 300);
+
+        for (name, expected) in [
+            ("1970-01-01T00-01-40Z_a%2F1", Some("a/1")),
+            ("1970-01-01T00-05-00Z_a3", Some("a3")),
+            ("1970-01-01T00-03-20Z_a2", None),
+            ("1970-01-01T00-05-00Z_a1", None),
+        ] {
+            let version = find_version_by_name(&client, "test_bucket", "a", name).await.unwrap();
+            assert_eq!(
+                version.map(|v| v.version_id).as_deref(),
+                expected,
+                "wrong version for {name}"
+            );
+        }
+    }
+}
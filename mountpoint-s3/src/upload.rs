@@ -2,8 +2,11 @@ use std::fmt::Debug;
 
 use futures::task::Spawn;
 
-use mountpoint_s3_client::error::{HeadObjectError, ObjectClientError, PutObjectError};
-use mountpoint_s3_client::types::{ChecksumAlgorithm, ETag};
+use mountpoint_s3_client::error::{
+    CompleteMultipartUploadError, CreateMultipartUploadError, HeadObjectError, ObjectClientError, PutObjectError,
+    UploadPartError,
+};
+use mountpoint_s3_client::types::{ChecksumAlgorithm, ETag, ObjectLock, ObjectTags, PutObjectSingleParams};
 use mountpoint_s3_client::ObjectClient;
 
 use thiserror::Error;
@@ -18,13 +21,20 @@ mod atomic;
 pub use atomic::UploadRequest;
 use atomic::UploadRequestParams;
 
+mod content_type;
+pub use content_type::{ContentTypeMap, ContentTypeMapError};
+
 mod hasher;
+pub(crate) use hasher::ChecksumHasher;
 pub use hasher::ChecksumHasherError;
 
 mod incremental;
 use incremental::AppendUploadQueueParams;
 pub use incremental::AppendUploadRequest;
 
+mod journal;
+pub use journal::{JournalError, UploadJournal};
+
 /// An [Uploader] creates and manages streaming PutObject requests.
 #[derive(Debug)]
 pub struct Uploader<Client: ObjectClient> {
@@ -32,6 +42,7 @@ pub struct Uploader<Client: ObjectClient> {
     runtime: BoxRuntime,
     mem_limiter: Arc<MemoryLimiter<Client>>,
     storage_class: Option<String>,
+    acl: Option<String>,
     server_side_encryption: ServerSideEncryption,
     buffer_size: usize,
     /// Default checksum algorithm, if any, to be used for new S3 objects.
@@ -39,6 +50,93 @@ pub struct Uploader<Client: ObjectClient> {
     /// Only [ChecksumAlgorithm::Crc32c] is supported for multi-part uploads.
     /// For existing objects, Mountpoint will instead append using the existing checksum algorithm on the object.
     default_checksum_algorithm: Option<ChecksumAlgorithm>,
+    /// Journal to record atomic uploads in, so they can be resumed after a crash.
+    upload_journal: Option<UploadJournal>,
+    /// Tags to apply to new S3 objects.
+    object_tags: ObjectTags,
+    /// Object Lock retention and legal hold to apply to new S3 objects.
+    object_lock: ObjectLock,
+    /// Infer the content type of new S3 objects from the extension of their key with this map, if set.
+    content_types: Option<ContentTypeMap>,
+}
+
+/// Configuration for the uploads created by an [Uploader].
+#[derive(Debug, Clone)]
+pub struct UploaderConfig {
+    buffer_size: usize,
+    storage_class: Option<String>,
+    acl: Option<String>,
+    server_side_encryption: ServerSideEncryption,
+    default_checksum_algorithm: Option<ChecksumAlgorithm>,
+    upload_journal: Option<UploadJournal>,
+    object_tags: ObjectTags,
+    object_lock: ObjectLock,
+    content_types: Option<ContentTypeMap>,
+}
+
+impl UploaderConfig {
+    /// Create a new configuration with the given buffer size and default settings for everything else.
+    pub fn new(buffer_size: usize) -> Self {
+        Self {
+            buffer_size,
+            storage_class: None,
+            acl: None,
+            server_side_encryption: Default::default(),
+            default_checksum_algorithm: None,
+            upload_journal: None,
+            object_tags: Default::default(),
+            object_lock: Default::default(),
+            content_types: None,
+        }
+    }
+
+    /// Set the storage class for new S3 objects.
+    pub fn storage_class(mut self, storage_class: Option<String>) -> Self {
+        self.storage_class = storage_class;
+        self
+    }
+
+    /// Set the canned ACL for new S3 objects.
+    pub fn acl(mut self, acl: Option<String>) -> Self {
+        self.acl = acl;
+        self
+    }
+
+    /// Set the server-side encryption settings for new S3 objects.
+    pub fn server_side_encryption(mut self, server_side_encryption: ServerSideEncryption) -> Self {
+        self.server_side_encryption = server_side_encryption;
+        self
+    }
+
+    /// Set the default checksum algorithm for new S3 objects.
+    pub fn default_checksum_algorithm(mut self, default_checksum_algorithm: Option<ChecksumAlgorithm>) -> Self {
+        self.default_checksum_algorithm = default_checksum_algorithm;
+        self
+    }
+
+    /// Set the journal to record atomic uploads in.
+    pub fn upload_journal(mut self, upload_journal: Option<UploadJournal>) -> Self {
+        self.upload_journal = upload_journal;
+        self
+    }
+
+    /// Set the tags to apply to new S3 objects.
+    pub fn object_tags(mut self, object_tags: ObjectTags) -> Self {
+        self.object_tags = object_tags;
+        self
+    }
+
+    /// Set the Object Lock retention and legal hold to apply to new S3 objects.
+    pub fn object_lock(mut self, object_lock: ObjectLock) -> Self {
+        self.object_lock = object_lock;
+        self
+    }
+
+    /// Set the map used to infer the content type of new S3 objects from their extension.
+    pub fn content_types(mut self, content_types: Option<ContentTypeMap>) -> Self {
+        self.content_types = content_types;
+        self
+    }
 }
 
 #[derive(Debug, Error)]
@@ -63,6 +161,21 @@ pub enum UploadError<E> {
 
     #[error("object exceeded maximum upload size of {maximum_size} bytes")]
     ObjectTooBig { maximum_size: usize },
+
+    #[error("create multipart upload request failed")]
+    CreateMultipartUploadFailed(#[from] ObjectClientError<CreateMultipartUploadError, E>),
+
+    #[error("upload part request failed")]
+    UploadPartFailed(#[from] ObjectClientError<UploadPartError, E>),
+
+    #[error("complete multipart upload request failed")]
+    CompleteMultipartUploadFailed(#[from] ObjectClientError<CompleteMultipartUploadError, E>),
+
+    #[error("upload review failed, aborting the upload")]
+    ReviewFailed,
+
+    #[error("upload journal error")]
+    JournalFailed(#[from] JournalError),
 }
 
 impl<Client> Uploader<Client>
@@ -74,27 +187,33 @@ where
         client: Client,
         runtime: impl Spawn + Sync + Send + 'static,
         mem_limiter: Arc<MemoryLimiter<Client>>,
-        storage_class: Option<String>,
-        server_side_encryption: ServerSideEncryption,
-        buffer_size: usize,
-        default_checksum_algorithm: Option<ChecksumAlgorithm>,
+        config: UploaderConfig,
     ) -> Self {
         Self {
             client,
             runtime: BoxRuntime::new(runtime),
             mem_limiter,
-            storage_class,
-            server_side_encryption,
-            buffer_size,
-            default_checksum_algorithm,
+            storage_class: config.storage_class,
+            acl: config.acl,
+            server_side_encryption: config.server_side_encryption,
+            buffer_size: config.buffer_size,
+            default_checksum_algorithm: config.default_checksum_algorithm,
+            upload_journal: config.upload_journal,
+            object_tags: config.object_tags,
+            object_lock: config.object_lock,
+            content_types: config.content_types,
         }
     }
 
     /// Start a new atomic upload.
+    ///
+    /// If `if_match` is provided, the upload will only complete if the object in S3 still has the given ETag.
+    /// If an upload journal is configured, the upload is recorded in it so it can be resumed after a crash.
     pub fn start_atomic_upload(
         &self,
         bucket: &str,
         key: &str,
+        if_match: Option<ETag>,
     ) -> Result<UploadRequest<Client>, UploadError<Client::ClientError>> {
         let params = UploadRequestParams {
             bucket: bucket.to_owned(),
@@ -102,8 +221,14 @@ where
             server_side_encryption: self.server_side_encryption.clone(),
             default_checksum_algorithm: self.default_checksum_algorithm.clone(),
             storage_class: self.storage_class.clone(),
+            acl: self.acl.clone(),
+            if_match,
+            upload_journal: self.upload_journal.clone(),
+            object_tags: self.object_tags.clone(),
+            object_lock: self.object_lock.clone(),
+            content_type: self.content_type_for_key(key),
         };
-        UploadRequest::new(&self.runtime, self.client.clone(), params)
+        UploadRequest::new(&self.runtime, self.client.clone(), self.mem_limiter.clone(), params)
     }
 
     /// Start a new incremental upload.
@@ -121,6 +246,10 @@ where
             initial_etag,
             server_side_encryption: self.server_side_encryption.clone(),
             default_checksum_algorithm: self.default_checksum_algorithm.clone(),
+            object_tags: self.object_tags.clone(),
+            object_lock: self.object_lock.clone(),
+            content_type: self.content_type_for_key(&key),
+            acl: self.acl.clone(),
             capacity: MAX_BYTES_IN_QUEUE / self.buffer_size,
         };
         AppendUploadRequest::new(
@@ -132,6 +261,31 @@ where
         )
     }
 
+    /// Create the zero-byte directory marker object with the given key, which ends in `/`.
+    pub async fn put_directory_marker(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<ETag, UploadError<Client::ClientError>> {
+        let (sse_type, key_id) = self.server_side_encryption.clone().into_inner()?;
+        let mut params = PutObjectSingleParams::new()
+            .object_tags(self.object_tags.clone())
+            .object_lock(self.object_lock.clone());
+        params.storage_class = self.storage_class.clone();
+        params.acl = self.acl.clone();
+        params.server_side_encryption = sse_type;
+        params.ssekms_key_id = key_id;
+        params.sse_customer_key = self.server_side_encryption.customer_key()?;
+        let result = self.client.put_object_single(bucket, key, &params, b"").await?;
+        Ok(result.etag)
+    }
+
+    /// The content type to set on a new object with the given key, if content types are inferred.
+    fn content_type_for_key(&self, key: &str) -> Option<String> {
+        let content_types = self.content_types.as_ref()?;
+        content_types.content_type_for_key(key).map(str::to_owned)
+    }
+
     #[cfg(test)]
     pub fn corrupt_sse(&mut self, sse_type: Option<String>, sse_kms_key_id: Option<String>) {
         self.server_side_encryption.corrupt_data(sse_type, sse_kms_key_id)
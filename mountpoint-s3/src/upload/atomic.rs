@@ -1,30 +1,36 @@
 use std::fmt::Debug;
+use std::mem;
 
 use mountpoint_s3_client::checksums::{crc32c, crc32c_from_base64, Crc32c};
 use mountpoint_s3_client::error::{ObjectClientError, PutObjectError};
 use mountpoint_s3_client::types::{
-    ChecksumAlgorithm, PutObjectParams, PutObjectResult, PutObjectTrailingChecksums, UploadReview,
+    ChecksumAlgorithm, ChecksumType, CreateMultipartUploadParams, ETag, ObjectLock, ObjectTags, PutObjectParams,
+    PutObjectTrailingChecksums, UploadReview,
 };
 use mountpoint_s3_client::{ObjectClient, PutObjectRequest};
 use tracing::error;
 
 use crate::async_util::{BoxRuntime, RemoteResult};
 use crate::checksums::combine_checksums;
+use crate::mem_limiter::MemoryLimiter;
+use crate::sync::Arc;
 use crate::ServerSideEncryption;
 
-use super::UploadError;
+use super::hasher::ChecksumHasher;
+use super::journal::{JournaledUpload, JournaledUploadParams};
+use super::{ChecksumHasherError, UploadError, UploadJournal};
 
 const MAX_S3_MULTIPART_UPLOAD_PARTS: usize = 10000;
 
 /// Manages the upload of an object to S3.
 ///
-/// Wraps a PutObject request and enforces sequential writes.
+/// Wraps a PutObject request, or a journaled multipart upload, and enforces sequential writes.
 pub struct UploadRequest<Client: ObjectClient> {
-    request: RemoteResult<Client::PutObjectRequest, ObjectClientError<PutObjectError, Client::ClientError>>,
+    request: UploadTarget<Client>,
     bucket: String,
     key: String,
     next_request_offset: u64,
-    hasher: crc32c::Hasher,
+    hasher: UploadHasher,
     maximum_upload_size: Option<usize>,
     sse: ServerSideEncryption,
 }
@@ -36,6 +42,26 @@ pub struct UploadRequestParams {
     pub server_side_encryption: ServerSideEncryption,
     pub default_checksum_algorithm: Option<ChecksumAlgorithm>,
     pub storage_class: Option<String>,
+    /// Canned ACL to apply to the new object.
+    pub acl: Option<String>,
+    /// Require the object in S3 to match this ETag when completing the upload.
+    pub if_match: Option<ETag>,
+    /// Record the upload in this journal, so it can be resumed after a crash.
+    pub upload_journal: Option<UploadJournal>,
+    /// Tags to apply to the new object.
+    pub object_tags: ObjectTags,
+    /// Object Lock retention and legal hold to apply to the new object.
+    pub object_lock: ObjectLock,
+    /// Content type to set on the new object.
+    pub content_type: Option<String>,
+}
+
+/// The request an [UploadRequest] writes data to.
+enum UploadTarget<Client: ObjectClient> {
+    /// A streaming PutObject request.
+    PutObject(RemoteResult<Client::PutObjectRequest, ObjectClientError<PutObjectError, Client::ClientError>>),
+    /// A multipart upload recorded in an upload journal.
+    Journaled(JournaledUpload<Client>),
 }
 
 impl<Client> UploadRequest<Client>
@@ -45,48 +71,94 @@ where
     pub fn new(
         runtime: &BoxRuntime,
         client: Client,
+        mem_limiter: Arc<MemoryLimiter<Client>>,
         params: UploadRequestParams,
-    ) -> Result<Self, UploadError<Client::ClientError>> {
-        let mut put_object_params = PutObjectParams::new();
-
-        match &params.default_checksum_algorithm {
-            Some(ChecksumAlgorithm::Crc32c) => {
-                put_object_params = put_object_params.trailing_checksums(PutObjectTrailingChecksums::Enabled);
-            }
-            Some(unsupported) => {
-                unimplemented!("checksum algorithm not supported: {:?}", unsupported);
-            }
-            None => {
-                put_object_params = put_object_params.trailing_checksums(PutObjectTrailingChecksums::ReviewOnly);
-            }
-        }
+    ) -> Result<Self, UploadError<Client::ClientError>>
+    where
+        Client: Clone + Sync,
+    {
+        let checksum_type = params.default_checksum_algorithm.as_ref().and_then(checksum_type);
 
-        if let Some(storage_class) = &params.storage_class {
-            put_object_params = put_object_params.storage_class(storage_class.clone());
-        }
         // If we have detected corruption of SSE settings, we return an error, which will currently be reported as
         // `libc::EIO` on `open()`. MP won't be able to open files for write from this point, but this is a relatively
         // low-risk error as data can not be uploaded with wrong SSE settings yet. Thus there is no strong reason for
         // MP to crash and it may continue serving read's.
         let (sse_type, key_id) = params.server_side_encryption.clone().into_inner()?;
-        put_object_params = put_object_params.server_side_encryption(sse_type);
-        put_object_params = put_object_params.ssekms_key_id(key_id);
+        let sse_customer_key = params.server_side_encryption.customer_key()?;
 
-        let put_bucket = params.bucket.to_owned();
-        let put_key = params.key.to_owned();
         let maximum_upload_size = client
             .write_part_size()
             .map(|ps| ps.saturating_mul(MAX_S3_MULTIPART_UPLOAD_PARTS));
-        let request = runtime
-            .spawn_with_result(async move { client.put_object(&put_bucket, &put_key, &put_object_params).await })
-            .unwrap();
+        let hasher = UploadHasher::new(&params.default_checksum_algorithm, client.write_part_size())?;
+
+        let request = if let Some(upload_journal) = &params.upload_journal {
+            let mut create_params = CreateMultipartUploadParams::new()
+                .checksum_algorithm(params.default_checksum_algorithm.clone())
+                .checksum_type(checksum_type)
+                .server_side_encryption(sse_type)
+                .ssekms_key_id(key_id)
+                .sse_customer_key(sse_customer_key)
+                .object_tags(params.object_tags.clone())
+                .object_lock(params.object_lock.clone());
+            if let Some(storage_class) = &params.storage_class {
+                create_params = create_params.storage_class(storage_class.clone());
+            }
+            if let Some(acl) = &params.acl {
+                create_params = create_params.acl(acl.clone());
+            }
+            if let Some(content_type) = &params.content_type {
+                create_params = create_params.content_type(content_type.clone());
+            }
+            let part_size = client.write_part_size().expect("client should have a write part size");
+            let journaled_params = JournaledUploadParams {
+                bucket: params.bucket.clone(),
+                key: params.key.clone(),
+                part_size,
+                create_params,
+                if_match: params.if_match.clone(),
+            };
+            UploadTarget::Journaled(upload_journal.start_upload(runtime, client, mem_limiter, journaled_params))
+        } else {
+            // Without additional checksums, the client still computes CRC32C checksums to review the upload.
+            let trailing_checksums = if params.default_checksum_algorithm.is_some() {
+                PutObjectTrailingChecksums::Enabled
+            } else {
+                PutObjectTrailingChecksums::ReviewOnly
+            };
+            let mut put_object_params = PutObjectParams::new()
+                .trailing_checksums(trailing_checksums)
+                .checksum_algorithm(params.default_checksum_algorithm.clone())
+                .checksum_type(checksum_type)
+                .if_match(params.if_match.clone())
+                .server_side_encryption(sse_type)
+                .ssekms_key_id(key_id)
+                .sse_customer_key(sse_customer_key)
+                .object_tags(params.object_tags.clone())
+                .object_lock(params.object_lock.clone());
+            if let Some(storage_class) = &params.storage_class {
+                put_object_params = put_object_params.storage_class(storage_class.clone());
+            }
+            if let Some(acl) = &params.acl {
+                put_object_params = put_object_params.acl(acl.clone());
+            }
+            if let Some(content_type) = &params.content_type {
+                put_object_params = put_object_params.content_type(content_type.clone());
+            }
+
+            let put_bucket = params.bucket.to_owned();
+            let put_key = params.key.to_owned();
+            let request = runtime
+                .spawn_with_result(async move { client.put_object(&put_bucket, &put_key, &put_object_params).await })
+                .unwrap();
+            UploadTarget::PutObject(request)
+        };
 
         Ok(UploadRequest {
             request,
             bucket: params.bucket,
             key: params.key,
             next_request_offset: 0,
-            hasher: crc32c::Hasher::new(),
+            hasher,
             maximum_upload_size,
             sse: params.server_side_encryption,
         })
@@ -110,23 +182,59 @@ where
             }
         }
 
-        self.hasher.update(data);
-        self.request.get_mut().await?.unwrap().write(data).await?;
+        self.hasher.update(data)?;
+        match &mut self.request {
+            UploadTarget::PutObject(request) => request.get_mut().await?.unwrap().write(data).await?,
+            UploadTarget::Journaled(upload) => upload.write(data).await?,
+        }
 
         self.next_request_offset += data.len() as u64;
         Ok(data.len())
     }
 
-    pub async fn complete(self) -> Result<PutObjectResult, UploadError<Client::ClientError>> {
+    /// Complete the upload and return the ETag of the new object.
+    pub async fn complete(self) -> Result<ETag, UploadError<Client::ClientError>> {
         let size = self.size();
-        let checksum = self.hasher.finalize();
-        let result = self
-            .request
-            .into_inner()
-            .await?
-            .unwrap()
-            .review_and_complete(move |review| verify_checksums(review, size, checksum))
-            .await?;
+        let request = match self.request {
+            UploadTarget::PutObject(request) => request,
+            // Parts are uploaded from the staged data, so review them against the data that was written.
+            UploadTarget::Journaled(upload) => {
+                return match self.hasher {
+                    UploadHasher::Crc32c(hasher) => {
+                        let checksum = hasher.finalize();
+                        upload
+                            .complete(move |review| verify_checksums(review, size, checksum))
+                            .await
+                    }
+                    UploadHasher::Parts(hasher) => {
+                        let checksums = hasher.finalize()?;
+                        upload
+                            .complete(move |review| verify_part_checksums(review, size, &checksums))
+                            .await
+                    }
+                };
+            }
+        };
+        let result = match self.hasher {
+            UploadHasher::Crc32c(hasher) => {
+                let checksum = hasher.finalize();
+                request
+                    .into_inner()
+                    .await?
+                    .unwrap()
+                    .review_and_complete(move |review| verify_checksums(review, size, checksum))
+                    .await?
+            }
+            UploadHasher::Parts(hasher) => {
+                let checksums = hasher.finalize()?;
+                request
+                    .into_inner()
+                    .await?
+                    .unwrap()
+                    .review_and_complete(move |review| verify_part_checksums(review, size, &checksums))
+                    .await?
+            }
+        };
         if let Err(err) = self
             .sse
             .verify_response(result.sse_type.as_deref(), result.sse_kms_key_id.as_deref())
@@ -139,7 +247,7 @@ where
             // 2. the reported error is severe as the object was already uploaded to S3.
             std::process::exit(1);
         }
-        Ok(result)
+        Ok(result.etag)
     }
 }
 
@@ -154,6 +262,108 @@ impl<Client: ObjectClient> Debug for UploadRequest<Client> {
     }
 }
 
+/// The checksum type to request for new objects uploaded in multiple parts.
+///
+/// S3 only supports full object checksums for CRC algorithms, and composite checksums for the others.
+fn checksum_type(checksum_algorithm: &ChecksumAlgorithm) -> Option<ChecksumType> {
+    match checksum_algorithm {
+        ChecksumAlgorithm::Crc32 | ChecksumAlgorithm::Crc32c | ChecksumAlgorithm::Crc64nvme => {
+            Some(ChecksumType::FullObject)
+        }
+        _ => None,
+    }
+}
+
+/// Computes checksums of the data written to an upload, to verify the parts uploaded by the client.
+#[derive(Debug)]
+enum UploadHasher {
+    /// The CRC32C checksum of the whole object, compared to the combined checksums of the uploaded parts.
+    Crc32c(crc32c::Hasher),
+    /// The checksum of each part, for algorithms where checksums can not be combined.
+    Parts(PartsHasher),
+}
+
+impl UploadHasher {
+    fn new(
+        checksum_algorithm: &Option<ChecksumAlgorithm>,
+        part_size: Option<usize>,
+    ) -> Result<Self, ChecksumHasherError> {
+        match checksum_algorithm {
+            None | Some(ChecksumAlgorithm::Crc32c) => Ok(Self::Crc32c(crc32c::Hasher::new())),
+            Some(algorithm) => {
+                let part_size = part_size.expect("client should have a write part size");
+                Ok(Self::Parts(PartsHasher::new(algorithm.clone(), part_size)?))
+            }
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) -> Result<(), ChecksumHasherError> {
+        match self {
+            UploadHasher::Crc32c(hasher) => hasher.update(data),
+            UploadHasher::Parts(hasher) => hasher.update(data)?,
+        }
+        Ok(())
+    }
+}
+
+/// Computes the checksum of each part of an upload, assuming that the client splits the upload in parts of its
+/// write part size.
+#[derive(Debug)]
+struct PartsHasher {
+    algorithm: ChecksumAlgorithm,
+    part_size: usize,
+    /// Hasher for the current part.
+    hasher: ChecksumHasher,
+    /// Number of bytes of the current part hashed so far.
+    hashed_len: usize,
+    /// Base64-encoded checksums of the previous parts.
+    checksums: Vec<String>,
+}
+
+impl PartsHasher {
+    fn new(algorithm: ChecksumAlgorithm, part_size: usize) -> Result<Self, ChecksumHasherError> {
+        let hasher = ChecksumHasher::new(&Some(algorithm.clone()))?;
+        Ok(Self {
+            algorithm,
+            part_size,
+            hasher,
+            hashed_len: 0,
+            checksums: Vec::new(),
+        })
+    }
+
+    fn update(&mut self, mut data: &[u8]) -> Result<(), ChecksumHasherError> {
+        while !data.is_empty() {
+            let len = data.len().min(self.part_size - self.hashed_len);
+            self.hasher.update(&data[..len])?;
+            self.hashed_len += len;
+            data = &data[len..];
+            if self.hashed_len == self.part_size {
+                self.finish_part()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish_part(&mut self) -> Result<(), ChecksumHasherError> {
+        let next_hasher = ChecksumHasher::new(&Some(self.algorithm.clone()))?;
+        let hasher = mem::replace(&mut self.hasher, next_hasher);
+        if let Some(checksum) = hasher.finalize()? {
+            self.checksums.push(checksum.to_base64());
+        }
+        self.hashed_len = 0;
+        Ok(())
+    }
+
+    /// Return the checksums of all the (non-empty) parts.
+    fn finalize(mut self) -> Result<Vec<String>, ChecksumHasherError> {
+        if self.hashed_len > 0 {
+            self.finish_part()?;
+        }
+        Ok(self.checksums)
+    }
+}
+
 fn verify_checksums(review: UploadReview, expected_size: u64, expected_checksum: Crc32c) -> bool {
     let mut uploaded_size = 0u64;
     let mut uploaded_checksum = Crc32c::new(0);
@@ -195,6 +405,42 @@ fn verify_checksums(review: UploadReview, expected_size: u64, expected_checksum:
     true
 }
 
+fn verify_part_checksums(review: UploadReview, expected_size: u64, expected_checksums: &[String]) -> bool {
+    // Empty parts have no data to verify.
+    let parts: Vec<_> = review.parts.iter().filter(|part| part.size > 0).collect();
+    let uploaded_size: u64 = parts.iter().map(|part| part.size).sum();
+    if uploaded_size != expected_size {
+        error!(
+            uploaded_size,
+            expected_size, "Total uploaded size differs from expected size"
+        );
+        return false;
+    }
+
+    if parts.len() != expected_checksums.len() {
+        error!(
+            uploaded_parts = parts.len(),
+            expected_parts = expected_checksums.len(),
+            "Number of uploaded parts differs from expected number of parts"
+        );
+        return false;
+    }
+
+    for (i, (part, expected_checksum)) in parts.iter().zip(expected_checksums).enumerate() {
+        if part.checksum.as_ref() != Some(expected_checksum) {
+            error!(
+                part_number = i + 1,
+                uploaded_checksum = ?part.checksum,
+                ?expected_checksum,
+                "Checksum of uploaded part differs from expected checksum"
+            );
+            return false;
+        }
+    }
+
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -202,12 +448,12 @@ mod tests {
     use crate::fs::SseCorruptedError;
     use crate::mem_limiter::{MemoryLimiter, MINIMUM_MEM_LIMIT};
     use crate::sync::Arc;
-    use crate::upload::Uploader;
+    use crate::upload::{Uploader, UploaderConfig};
 
     use futures::executor::ThreadPool;
     use mountpoint_s3_client::failure_client::{countdown_failure_client, CountdownFailureConfig};
     use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockClientError};
-    use mountpoint_s3_client::types::ChecksumAlgorithm;
+    use mountpoint_s3_client::types::{Checksum, ChecksumAlgorithm, ObjectAttribute, UploadReviewPart};
     use test_case::test_case;
 
     use super::*;
@@ -228,10 +474,10 @@ mod tests {
             client,
             runtime,
             mem_limiter.into(),
-            storage_class,
-            server_side_encryption,
-            buffer_size,
-            use_additional_checksums.then_some(ChecksumAlgorithm::Crc32c),
+            UploaderConfig::new(buffer_size)
+                .storage_class(storage_class)
+                .server_side_encryption(server_side_encryption)
+                .default_checksum_algorithm(use_additional_checksums.then_some(ChecksumAlgorithm::Crc32c)),
         )
     }
 
@@ -247,7 +493,7 @@ mod tests {
             ..Default::default()
         }));
         let uploader = new_uploader_for_test(client.clone(), None, ServerSideEncryption::default(), true);
-        let mut request = uploader.start_atomic_upload(bucket, key).unwrap();
+        let mut request = uploader.start_atomic_upload(bucket, key, None).unwrap();
 
         _ = request.write(0, &[]).await.unwrap();
 
@@ -260,6 +506,40 @@ mod tests {
         assert!(!client.is_upload_in_progress(key));
     }
 
+    #[test_case(None; "no additional checksums")]
+    #[test_case(Some(ChecksumAlgorithm::Crc32c); "crc32c")]
+    #[test_case(Some(ChecksumAlgorithm::Sha256); "sha256")]
+    #[tokio::test]
+    async fn journaled_complete_test(checksum_algorithm: Option<ChecksumAlgorithm>) {
+        let bucket = "bucket";
+        let key = "hello";
+
+        let client = Arc::new(MockClient::new(MockClientConfig {
+            bucket: bucket.to_owned(),
+            part_size: 32,
+            ..Default::default()
+        }));
+        let journal_dir = tempfile::tempdir().unwrap();
+        let uploader = Uploader::new(
+            client.clone(),
+            ThreadPool::builder().pool_size(1).create().unwrap(),
+            MemoryLimiter::new(client.clone(), MINIMUM_MEM_LIMIT).into(),
+            UploaderConfig::new(32)
+                .default_checksum_algorithm(checksum_algorithm)
+                .upload_journal(Some(UploadJournal::new(journal_dir.path()).unwrap())),
+        );
+        let mut request = uploader.start_atomic_upload(bucket, key, None).unwrap();
+        let mut offset = 0;
+        for _ in 0..10 {
+            offset += request.write(offset, &[0xaa; 10]).await.unwrap() as i64;
+        }
+
+        // The parts uploaded from the staged data pass the review against the written data
+        request.complete().await.unwrap();
+        assert!(client.contains_key(key));
+        assert_eq!(client.multipart_upload_count(), 0);
+    }
+
     #[tokio::test]
     async fn write_order_test() {
         let bucket = "bucket";
@@ -279,7 +559,7 @@ mod tests {
             true,
         );
 
-        let mut request = uploader.start_atomic_upload(bucket, key).unwrap();
+        let mut request = uploader.start_atomic_upload(bucket, key, None).unwrap();
 
         let data = b"foo";
         let mut offset = 0;
@@ -330,7 +610,7 @@ mod tests {
 
         // First request fails on first write.
         {
-            let mut request = uploader.start_atomic_upload(bucket, key).unwrap();
+            let mut request = uploader.start_atomic_upload(bucket, key, None).unwrap();
 
             let data = b"foo";
             request.write(0, data).await.expect_err("first write should fail");
@@ -340,7 +620,7 @@ mod tests {
 
         // Second request fails on complete (after one write).
         {
-            let mut request = uploader.start_atomic_upload(bucket, key).unwrap();
+            let mut request = uploader.start_atomic_upload(bucket, key, None).unwrap();
 
             let data = b"foo";
             _ = request.write(0, data).await.unwrap();
@@ -368,7 +648,7 @@ mod tests {
             ..Default::default()
         }));
         let uploader = new_uploader_for_test(client.clone(), None, ServerSideEncryption::default(), true);
-        let mut request = uploader.start_atomic_upload(bucket, key).unwrap();
+        let mut request = uploader.start_atomic_upload(bucket, key, None).unwrap();
 
         let successful_writes = PART_SIZE * MAX_S3_MULTIPART_UPLOAD_PARTS / write_size;
         let data = vec![0xaa; write_size];
@@ -407,7 +687,7 @@ mod tests {
             .server_side_encryption
             .corrupt_data(sse_type_corrupted.map(String::from), key_id_corrupted.map(String::from));
         let err = uploader
-            .start_atomic_upload("bucket", "hello")
+            .start_atomic_upload("bucket", "hello", None)
             .expect_err("sse checksum must be checked");
         assert!(matches!(
             err,
@@ -433,7 +713,94 @@ mod tests {
             true,
         );
         uploader
-            .start_atomic_upload(bucket, key)
+            .start_atomic_upload(bucket, key, None)
             .expect("put with sse should succeed");
     }
+
+    #[test_case(ChecksumAlgorithm::Crc32c, Some(ChecksumType::FullObject), false; "crc32c")]
+    #[test_case(ChecksumAlgorithm::Crc32, Some(ChecksumType::FullObject), false; "crc32")]
+    #[test_case(ChecksumAlgorithm::Crc64nvme, Some(ChecksumType::FullObject), false; "crc64nvme")]
+    #[test_case(ChecksumAlgorithm::Sha1, None, false; "sha1")]
+    #[test_case(ChecksumAlgorithm::Sha256, None, false; "sha256")]
+    #[test_case(ChecksumAlgorithm::Crc32c, Some(ChecksumType::FullObject), true; "crc32c journaled")]
+    #[test_case(ChecksumAlgorithm::Crc32, Some(ChecksumType::FullObject), true; "crc32 journaled")]
+    #[test_case(ChecksumAlgorithm::Crc64nvme, Some(ChecksumType::FullObject), true; "crc64nvme journaled")]
+    #[test_case(ChecksumAlgorithm::Sha1, None, true; "sha1 journaled")]
+    #[test_case(ChecksumAlgorithm::Sha256, None, true; "sha256 journaled")]
+    #[tokio::test]
+    async fn checksum_algorithm_test(
+        checksum_algorithm: ChecksumAlgorithm,
+        expected_type: Option<ChecksumType>,
+        journaled: bool,
+    ) {
+        let bucket = "bucket";
+        let key = "hello";
+        let part_size = 32;
+
+        let client = Arc::new(MockClient::new(MockClientConfig {
+            bucket: bucket.to_owned(),
+            part_size,
+            ..Default::default()
+        }));
+        let runtime = ThreadPool::builder().pool_size(1).create().unwrap();
+        let mem_limiter = MemoryLimiter::new(client.clone(), MINIMUM_MEM_LIMIT);
+        // Journaled uploads are completed with CompleteMultipartUpload rather than by the PutObject request
+        let journal_dir = tempfile::tempdir().unwrap();
+        let upload_journal = journaled.then(|| UploadJournal::new(journal_dir.path()).unwrap());
+        let uploader = Uploader::new(
+            client.clone(),
+            runtime,
+            mem_limiter.into(),
+            UploaderConfig::new(part_size)
+                .default_checksum_algorithm(Some(checksum_algorithm.clone()))
+                .upload_journal(upload_journal),
+        );
+        assert_eq!(checksum_type(&checksum_algorithm), expected_type);
+
+        let data: Vec<u8> = (0..100).collect();
+        let mut request = uploader.start_atomic_upload(bucket, key, None).unwrap();
+        let mut offset = 0;
+        for chunk in data.chunks(7) {
+            offset += request.write(offset, chunk).await.unwrap() as i64;
+        }
+        request.complete().await.unwrap();
+
+        let attributes = client
+            .get_object_attributes(bucket, key, None, None, &[ObjectAttribute::Checksum])
+            .await
+            .unwrap();
+        let checksum = attributes.checksum.expect("object should have a checksum");
+        assert_eq!(checksum.algorithms(), vec![checksum_algorithm.clone()]);
+        let mut hasher = ChecksumHasher::new(&Some(checksum_algorithm)).unwrap();
+        hasher.update(&data).unwrap();
+        let full_object_checksum: Checksum = hasher.finalize().unwrap().into();
+        if expected_type == Some(ChecksumType::FullObject) {
+            assert_eq!(checksum, full_object_checksum);
+        } else {
+            assert_ne!(checksum, full_object_checksum, "checksum should be composite");
+        }
+    }
+
+    #[test]
+    fn verify_part_checksums_test() {
+        let review = |checksums: &[&str]| UploadReview {
+            parts: checksums
+                .iter()
+                .map(|checksum| UploadReviewPart {
+                    size: 32,
+                    checksum: Some(checksum.to_string()),
+                })
+                .collect(),
+            checksum_algorithm: Some(ChecksumAlgorithm::Sha256),
+        };
+        let expected = ["a".to_owned(), "b".to_owned()];
+
+        assert!(verify_part_checksums(review(&["a", "b"]), 64, &expected));
+        assert!(
+            !verify_part_checksums(review(&["a", "c"]), 64, &expected),
+            "wrong checksum"
+        );
+        assert!(!verify_part_checksums(review(&["a"]), 32, &expected), "missing part");
+        assert!(!verify_part_checksums(review(&["a", "b"]), 65, &expected), "wrong size");
+    }
 }
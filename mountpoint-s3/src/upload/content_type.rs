@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use thiserror::Error;
+
+/// Content types for common file extensions, used when inferring the `Content-Type` of new objects.
+const DEFAULT_CONTENT_TYPES: &[(&str, &str)] = &[
+    ("aac", "audio/aac"),
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("bz2", "application/x-bzip2"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("eot", "application/vnd.ms-fontobject"),
+    ("epub", "application/epub+zip"),
+    ("flac", "audio/flac"),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("ics", "text/calendar"),
+    ("jar", "application/java-archive"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("jsonld", "application/ld+json"),
+    ("m4a", "audio/mp4"),
+    ("map", "application/json"),
+    ("md", "text/markdown"),
+    ("mjs", "text/javascript"),
+    ("mov", "video/quicktime"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("mpeg", "video/mpeg"),
+    ("oga", "audio/ogg"),
+    ("ogg", "audio/ogg"),
+    ("ogv", "video/ogg"),
+    ("otf", "font/otf"),
+    ("parquet", "application/vnd.apache.parquet"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("rtf", "application/rtf"),
+    ("svg", "image/svg+xml"),
+    ("tar", "application/x-tar"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("tsv", "text/tab-separated-values"),
+    ("ttf", "font/ttf"),
+    ("txt", "text/plain"),
+    ("wasm", "application/wasm"),
+    ("wav", "audio/wav"),
+    ("weba", "audio/webm"),
+    ("webm", "video/webm"),
+    ("webmanifest", "application/manifest+json"),
+    ("webp", "image/webp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("xhtml", "application/xhtml+xml"),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("xml", "application/xml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("zip", "application/zip"),
+    ("zst", "application/zstd"),
+];
+
+/// Maps file extensions to the `Content-Type` to set on new objects.
+///
+/// Starts from a built-in table of common types, which can be overridden with a mapping file in the format of
+/// `/etc/mime.types`: each line holds a content type followed by the extensions it applies to, and `#` starts a comment.
+#[derive(Debug, Clone)]
+pub struct ContentTypeMap {
+    types_by_extension: HashMap<String, String>,
+}
+
+#[derive(Debug, Error)]
+pub enum ContentTypeMapError {
+    #[error("failed to read content type mapping file")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid content type {content_type:?} on line {line}")]
+    InvalidContentType { line: usize, content_type: String },
+}
+
+impl ContentTypeMap {
+    /// Create a map with the built-in content types.
+    pub fn new() -> Self {
+        let types_by_extension = DEFAULT_CONTENT_TYPES
+            .iter()
+            .map(|(extension, content_type)| (extension.to_string(), content_type.to_string()))
+            .collect();
+        Self { types_by_extension }
+    }
+
+    /// Override the built-in content types with the ones in the given mapping file.
+    pub fn with_mapping_file(self, path: &Path) -> Result<Self, ContentTypeMapError> {
+        let contents = std::fs::read_to_string(path)?;
+        self.with_mappings(&contents)
+    }
+
+    fn with_mappings(mut self, contents: &str) -> Result<Self, ContentTypeMapError> {
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.split_once('#').map_or(line, |(line, _comment)| line);
+            let mut fields = line.split_whitespace();
+            let Some(content_type) = fields.next() else {
+                continue;
+            };
+            if !is_valid_content_type(content_type) {
+                return Err(ContentTypeMapError::InvalidContentType {
+                    line: index + 1,
+                    content_type: content_type.to_owned(),
+                });
+            }
+            for extension in fields {
+                self.types_by_extension
+                    .insert(extension.to_ascii_lowercase(), content_type.to_owned());
+            }
+        }
+        Ok(self)
+    }
+
+    /// Return the content type for an object key, based on the extension of its last path component.
+    pub fn content_type_for_key(&self, key: &str) -> Option<&str> {
+        let name = key.rsplit('/').next().unwrap_or(key);
+        // Names like ".bashrc" have no extension.
+        let (stem, extension) = name.rsplit_once('.')?;
+        if stem.is_empty() {
+            return None;
+        }
+        self.types_by_extension
+            .get(&extension.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+}
+
+impl Default for ContentTypeMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Content types must be of the form `type/subtype`, and must be valid in an HTTP header.
+fn is_valid_content_type(content_type: &str) -> bool {
+    let Some((kind, subtype)) = content_type.split_once('/') else {
+        return false;
+    };
+    !kind.is_empty() && !subtype.is_empty() && content_type.chars().all(|c| c.is_ascii_graphic())
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("index.html", Some("text/html"))]
+    #[test_case("site/assets/logo.PNG", Some("image/png"))]
+    #[test_case("data/archive.tar.gz", Some("application/gzip"))]
+    #[test_case("dir.json/file", None; "extension only on directory")]
+    #[test_case(".bashrc", None; "hidden file")]
+    #[test_case("Makefile", None; "no extension")]
+    #[test_case("file.unknown", None; "unknown extension")]
+    fn test_builtin_content_types(key: &str, expected: Option<&str>) {
+        let map = ContentTypeMap::new();
+        assert_eq!(map.content_type_for_key(key), expected);
+    }
+
+    #[test]
+    fn test_mapping_overrides() {
+        let mappings = "# custom types\n\
+                        application/x-ndjson   ndjson jsonl\n\
+                        \n\
+                        text/plain  JSON  # serve JSON as text\n";
+        let map = ContentTypeMap::new().with_mappings(mappings).unwrap();
+        assert_eq!(map.content_type_for_key("events.jsonl"), Some("application/x-ndjson"));
+        assert_eq!(map.content_type_for_key("config.json"), Some("text/plain"));
+        assert_eq!(map.content_type_for_key("index.html"), Some("text/html"));
+    }
+
+    #[test_case("text html"; "missing subtype")]
+    #[test_case("text/ html"; "empty subtype")]
+    #[test_case("/html html"; "empty type")]
+    fn test_invalid_mapping(mappings: &str) {
+        let err = ContentTypeMap::new()
+            .with_mappings(&format!("text/css css\n{mappings}\n"))
+            .expect_err("mapping should be invalid");
+        assert!(matches!(err, ContentTypeMapError::InvalidContentType { line: 2, .. }));
+    }
+}
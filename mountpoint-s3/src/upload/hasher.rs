@@ -1,4 +1,4 @@
-use mountpoint_s3_client::checksums::{crc32, crc32c, sha1, sha256};
+use mountpoint_s3_client::checksums::{crc32, crc32c, crc64, sha1, sha256};
 use mountpoint_s3_client::types::{ChecksumAlgorithm, UploadChecksum};
 use mountpoint_s3_crt::common::allocator::Allocator;
 use thiserror::Error;
@@ -9,6 +9,7 @@ pub enum ChecksumHasher {
     None,
     Crc32(crc32::Hasher),
     Crc32c(crc32c::Hasher),
+    Crc64nvme(crc64::Crc64Hasher),
     Sha1(sha1::Sha1Hasher),
     Sha256(sha256::Sha256Hasher),
 }
@@ -18,6 +19,7 @@ impl ChecksumHasher {
         match checksum_algorithm {
             Some(ChecksumAlgorithm::Crc32) => Ok(Self::Crc32(crc32::Hasher::new())),
             Some(ChecksumAlgorithm::Crc32c) => Ok(Self::Crc32c(crc32c::Hasher::new())),
+            Some(ChecksumAlgorithm::Crc64nvme) => Ok(Self::Crc64nvme(crc64::Crc64Hasher::new())),
             Some(ChecksumAlgorithm::Sha1) => Ok(Self::Sha1(sha1::Sha1Hasher::new(&Allocator::default())?)),
             Some(ChecksumAlgorithm::Sha256) => Ok(Self::Sha256(sha256::Sha256Hasher::new(&Allocator::default())?)),
             Some(other) => Err(ChecksumHasherError::UnsupportedChecksumAlgorithm(other.clone())),
@@ -30,6 +32,7 @@ impl ChecksumHasher {
             ChecksumHasher::None => {}
             ChecksumHasher::Crc32(hasher) => hasher.update(data),
             ChecksumHasher::Crc32c(hasher) => hasher.update(data),
+            ChecksumHasher::Crc64nvme(hasher) => hasher.update(data),
             ChecksumHasher::Sha1(hasher) => hasher.update(data)?,
             ChecksumHasher::Sha256(hasher) => hasher.update(data)?,
         };
@@ -41,6 +44,7 @@ impl ChecksumHasher {
             ChecksumHasher::None => Ok(None),
             ChecksumHasher::Crc32(hasher) => Ok(Some(UploadChecksum::Crc32(hasher.finalize()))),
             ChecksumHasher::Crc32c(hasher) => Ok(Some(UploadChecksum::Crc32c(hasher.finalize()))),
+            ChecksumHasher::Crc64nvme(hasher) => Ok(Some(UploadChecksum::Crc64nvme(hasher.finalize()))),
             ChecksumHasher::Sha1(hasher) => Ok(Some(UploadChecksum::Sha1(hasher.finalize(&Allocator::default())?))),
             ChecksumHasher::Sha256(hasher) => Ok(Some(UploadChecksum::Sha256(hasher.finalize(&Allocator::default())?))),
         }
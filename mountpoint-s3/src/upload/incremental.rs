@@ -8,7 +8,8 @@ use futures::future::RemoteHandle;
 use futures::task::SpawnExt as _;
 use mountpoint_s3_client::error::{ObjectClientError, PutObjectError};
 use mountpoint_s3_client::types::{
-    ChecksumAlgorithm, ChecksumMode, ETag, HeadObjectParams, PutObjectResult, PutObjectSingleParams, UploadChecksum,
+    ChecksumAlgorithm, ChecksumMode, ETag, HeadObjectParams, ObjectLock, ObjectTags, PutObjectResult,
+    PutObjectSingleParams, UploadChecksum,
 };
 use mountpoint_s3_client::ObjectClient;
 use tracing::{debug_span, trace, Instrument};
@@ -48,6 +49,15 @@ pub struct AppendUploadQueueParams {
     ///
     /// If the object already exists, its current algorithm will be used instead.
     pub default_checksum_algorithm: Option<ChecksumAlgorithm>,
+    /// Tags to apply when creating a new object. Not used when appending to an existing object.
+    pub object_tags: ObjectTags,
+    /// Canned ACL to apply when creating a new object. Not used when appending to an existing object.
+    pub acl: Option<String>,
+    /// Object Lock retention and legal hold to apply when creating a new object.
+    /// Not used when appending to an existing object.
+    pub object_lock: ObjectLock,
+    /// Content type to set when creating a new object. Not used when appending to an existing object.
+    pub content_type: Option<String>,
     pub capacity: usize,
 }
 
@@ -280,13 +290,10 @@ where
         return Ok(params.default_checksum_algorithm.clone());
     }
     // We are appending to an existing object, find out which checksum algorithm it uses.
-    let head_object = client
-        .head_object(
-            &params.bucket,
-            &params.key,
-            &HeadObjectParams::new().checksum_mode(Some(ChecksumMode::Enabled)),
-        )
-        .await?;
+    let head_params = HeadObjectParams::new()
+        .checksum_mode(Some(ChecksumMode::Enabled))
+        .sse_customer_key(params.server_side_encryption.customer_key()?);
+    let head_object = client.head_object(&params.bucket, &params.key, &head_params).await?;
 
     trace!(?head_object, "received head_object response");
     if Some(head_object.etag) != params.initial_etag {
@@ -307,15 +314,12 @@ async fn run_append_loop<Client>(
 ) where
     Client: ObjectClient + Send + Sync + 'static,
 {
-    let bucket = params.bucket;
-    let key = params.key;
-    let sse = params.server_side_encryption;
-    let mut etag = params.initial_etag;
+    let mut etag = params.initial_etag.clone();
     let mut offset = params.initial_offset;
 
     while let Ok(buffer) = request_receiver.recv().await {
         let buffer_len = buffer.len();
-        let response = append(&client, &bucket, &key, buffer, offset, etag.take(), sse.clone())
+        let response = append(&client, &params, buffer, offset, etag.take())
             .await
             .inspect(|result| {
                 offset += buffer_len as u64;
@@ -343,26 +347,41 @@ async fn run_append_loop<Client>(
 
 async fn append<Client: ObjectClient>(
     client: &Client,
-    bucket: &str,
-    key: &str,
+    params: &AppendUploadQueueParams,
     buffer: UploadBuffer<Client>,
     offset: u64,
     etag: Option<ETag>,
-    server_side_encryption: ServerSideEncryption,
 ) -> Result<PutObjectResult, UploadError<Client::ClientError>> {
+    let (bucket, key) = (params.bucket.as_str(), params.key.as_str());
     trace!(key, offset, len = buffer.len(), "preparing PutObject request");
     let (data, checksum) = buffer.freeze()?;
+    // When writing at offset 0, an etag is only present if we are overwriting an existing object
+    // and want to make sure it was not modified since it was opened. Tags, ACLs, Object Lock
+    // settings and the content type can only be set when the object is created.
     let mut request_params = if offset == 0 {
-        PutObjectSingleParams::new()
+        let mut request_params = PutObjectSingleParams::new()
+            .if_match(etag)
+            .object_tags(params.object_tags.clone())
+            .object_lock(params.object_lock.clone());
+        request_params.acl = params.acl.clone();
+        request_params.content_type = params.content_type.clone();
+        request_params
     } else {
         PutObjectSingleParams::new_for_append(offset).if_match(etag)
     };
-    let (sse_type, key_id) = server_side_encryption
+    let (sse_type, key_id) = params
+        .server_side_encryption
+        .clone()
         .into_inner()
         .map_err(UploadError::SseCorruptedError)?;
+    let sse_customer_key = params
+        .server_side_encryption
+        .customer_key()
+        .map_err(UploadError::SseCorruptedError)?;
     request_params.checksum = checksum;
     request_params.server_side_encryption = sse_type;
     request_params.ssekms_key_id = key_id;
+    request_params.sse_customer_key = sse_customer_key;
     client
         .put_object_single(bucket, key, &request_params, data)
         .await
@@ -451,7 +470,7 @@ mod tests {
 
     use crate::mem_limiter::MINIMUM_MEM_LIMIT;
 
-    use super::super::Uploader;
+    use super::super::{Uploader, UploaderConfig};
     use super::*;
 
     use futures::executor::ThreadPool;
@@ -476,10 +495,9 @@ mod tests {
             client,
             runtime,
             mem_limiter.into(),
-            None,
-            server_side_encryption.unwrap_or_default(),
-            buffer_size,
-            default_checksum_algorithm,
+            UploaderConfig::new(buffer_size)
+                .server_side_encryption(server_side_encryption.unwrap_or_default())
+                .default_checksum_algorithm(default_checksum_algorithm),
         )
     }
 
@@ -1175,10 +1193,7 @@ mod tests {
             client.clone(),
             ThreadPool::builder().pool_size(1).create().unwrap(),
             mem_limiter.into(),
-            None,
-            Default::default(),
-            part_size,
-            None,
+            UploaderConfig::new(part_size),
         );
 
         let mut offset = 0;
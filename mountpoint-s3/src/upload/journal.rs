@@ -0,0 +1,1096 @@
+//! A local journal of multipart uploads, used to resume uploads interrupted by a crash.
+//!
+//! When a journal is configured, atomic uploads are made with explicit CreateMultipartUpload,
+//! UploadPart and CompleteMultipartUpload requests rather than a single streaming PutObject. Each
+//! upload has a journal file recording its upload ID and the parts uploaded so far, and a staging
+//! file holding the data of the part currently being written. Once the application closes the file,
+//! the journal holds everything needed to finish the upload. If Mountpoint exits before the upload
+//! finishes, the next mount with the same journal directory replays the journal: closed uploads are
+//! completed, and uploads that were still being written are aborted.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context as _;
+use futures::task::SpawnExt;
+use mountpoint_s3_client::checksums::{crc32c, crc32c_to_base64};
+use mountpoint_s3_client::error::{
+    AbortMultipartUploadError, CompleteMultipartUploadError, ListPartsError, ObjectClientError, UploadPartError,
+};
+use mountpoint_s3_client::types::{
+    Checksum, ChecksumAlgorithm, CompleteMultipartUploadParams, CompletedPart, CreateMultipartUploadParams, ETag,
+    SseCustomerKey, UploadChecksum, UploadPartParams, UploadReview, UploadReviewPart, UploadedPart,
+};
+use mountpoint_s3_client::ObjectClient;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use crate::async_util::{BlockingPool, BoxRuntime};
+use crate::mem_limiter::{BufferArea, MemoryLimiter};
+use crate::sync::atomic::{AtomicU64, Ordering};
+use crate::sync::{Arc, Mutex};
+
+use super::hasher::{ChecksumHasher, ChecksumHasherError};
+use super::UploadError;
+
+const JOURNAL_EXTENSION: &str = "journal";
+const STAGING_EXTENSION: &str = "staging";
+/// Number of threads doing the file system I/O of the journal.
+const JOURNAL_IO_THREADS: usize = 4;
+
+/// A directory holding the journals of in-progress multipart uploads.
+///
+/// A journal directory must not be shared by Mountpoint processes running at the same time.
+#[derive(Debug, Clone)]
+pub struct UploadJournal {
+    dir: PathBuf,
+    next_id: Arc<AtomicU64>,
+    /// Threads doing the blocking file system I/O of all the uploads in the journal.
+    io: BlockingPool,
+}
+
+#[derive(Debug, Error)]
+pub enum JournalError {
+    #[error("I/O error accessing upload journal")]
+    IoError(#[from] io::Error),
+
+    #[error("invalid upload journal record")]
+    InvalidRecord(#[from] serde_json::Error),
+}
+
+/// A record in an upload journal. Journal files contain one JSON-encoded record per line.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum JournalRecord {
+    /// The multipart upload was created.
+    Start {
+        bucket: String,
+        key: String,
+        upload_id: String,
+        if_match: Option<String>,
+        /// Algorithm of the part checksums, for example `CRC32C`.
+        checksum_algorithm: Option<String>,
+    },
+    /// A part was uploaded.
+    Part {
+        part_number: usize,
+        etag: String,
+        size: u64,
+        checksum: Option<String>,
+    },
+    /// The file was closed, so the upload should be completed rather than aborted.
+    Closed,
+}
+
+/// Outcome of replaying a single journal.
+#[derive(Debug, PartialEq, Eq)]
+enum ReplayOutcome {
+    Completed,
+    Aborted,
+    /// The upload no longer exists in S3, so it was already completed or aborted.
+    Gone,
+    /// The journal belongs to another bucket and was left untouched.
+    Skipped,
+}
+
+impl UploadJournal {
+    /// Open a journal in the given directory, creating the directory if needed.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, JournalError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            next_id: Default::default(),
+            io: BlockingPool::new("upload-journal", JOURNAL_IO_THREADS)?,
+        })
+    }
+
+    fn new_entry(&self) -> JournalEntry {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let counter = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let id = format!("{}-{}-{}", std::process::id(), timestamp, counter);
+        JournalEntry::new(&self.dir, id)
+    }
+
+    /// Complete or abort the uploads to `bucket` left behind by a previous Mountpoint process.
+    ///
+    /// Journals that cannot be replayed because of a transient error are kept for the next replay.
+    /// SSE-C keys are never written to the journal, so uploads started with a customer-provided key
+    /// can only be completed if the same `sse_customer_key` is provided here. The memory of the staged
+    /// parts read back from the journal is reserved through `mem_limiter`.
+    pub async fn replay<Client: ObjectClient>(
+        &self,
+        client: &Client,
+        bucket: &str,
+        sse_customer_key: Option<&SseCustomerKey>,
+        mem_limiter: &MemoryLimiter<Client>,
+    ) -> Result<(), JournalError> {
+        let dir = self.dir.clone();
+        let journal_paths = self.io.run(move || list_files(&dir, JOURNAL_EXTENSION)).await??;
+        for path in journal_paths {
+            let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let entry = JournalEntry::new(&self.dir, id.to_owned());
+            match replay_entry(client, bucket, sse_customer_key, mem_limiter, &self.io, &entry).await {
+                Ok(ReplayOutcome::Skipped) => {}
+                Ok(outcome) => {
+                    metrics::counter!("upload_journal.replayed", "outcome" => format!("{outcome:?}")).increment(1);
+                    let mut entry = entry;
+                    self.io.run(move || entry.remove()).await??;
+                }
+                Err(error) => {
+                    metrics::counter!("upload_journal.replay_failures").increment(1);
+                    warn!(
+                        ?path,
+                        ?error,
+                        "failed to replay upload journal, will retry on next mount"
+                    );
+                }
+            }
+        }
+
+        // Remove staging files left behind without a journal, e.g. by a crash before the upload was created.
+        let dir = self.dir.clone();
+        self.io.run(move || remove_orphaned_staging_files(&dir)).await?
+    }
+
+    /// Start a new journaled multipart upload.
+    pub(super) fn start_upload<Client>(
+        &self,
+        runtime: &BoxRuntime,
+        client: Client,
+        mem_limiter: Arc<MemoryLimiter<Client>>,
+        params: JournaledUploadParams,
+    ) -> JournaledUpload<Client>
+    where
+        Client: ObjectClient + Clone + Send + Sync + 'static,
+    {
+        let abort_client = client.clone();
+        let abort_runtime = runtime.clone();
+        let abort_bucket = params.bucket.clone();
+        let abort_key = params.key.clone();
+        let abort = move |upload_id: String| {
+            let spawn_result = abort_runtime.spawn(async move {
+                if let Err(error) = abort_client
+                    .abort_multipart_upload(&abort_bucket, &abort_key, &upload_id)
+                    .await
+                {
+                    warn!(key = abort_key, upload_id, ?error, "failed to abort multipart upload");
+                }
+            });
+            if let Err(error) = spawn_result {
+                warn!(?error, "failed to spawn multipart upload abort");
+            }
+        };
+
+        JournaledUpload {
+            client,
+            mem_limiter,
+            params,
+            entry: Arc::new(Mutex::new(self.new_entry())),
+            io: self.io.clone(),
+            buffer: Vec::new(),
+            upload_id: None,
+            staged_len: 0,
+            parts: Vec::new(),
+            review_parts: Vec::new(),
+            abort: Some(Box::new(abort)),
+        }
+    }
+}
+
+/// List the files in `dir` with the given extension.
+fn list_files(dir: &Path, extension: &str) -> Result<Vec<PathBuf>, JournalError> {
+    let mut paths = Vec::new();
+    for dir_entry in fs::read_dir(dir)? {
+        let path = dir_entry?.path();
+        if path.extension() == Some(extension.as_ref()) {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// Remove the staging files in `dir` that have no journal.
+fn remove_orphaned_staging_files(dir: &Path) -> Result<(), JournalError> {
+    for path in list_files(dir, STAGING_EXTENSION)? {
+        let Some(id) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.split('.').next())
+        else {
+            continue;
+        };
+        if !JournalEntry::new(dir, id.to_owned()).journal_path().exists() {
+            debug!(?path, "removing orphaned staging file");
+            remove_if_exists(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parameters to start a [JournaledUpload].
+pub(super) struct JournaledUploadParams {
+    pub bucket: String,
+    pub key: String,
+    pub part_size: usize,
+    pub create_params: CreateMultipartUploadParams,
+    pub if_match: Option<ETag>,
+}
+
+/// A multipart upload whose progress is recorded in an [UploadJournal].
+pub struct JournaledUpload<Client: ObjectClient> {
+    client: Client,
+    mem_limiter: Arc<MemoryLimiter<Client>>,
+    params: JournaledUploadParams,
+    /// Shared with the threads doing the file system I/O on the entry.
+    entry: Arc<Mutex<JournalEntry>>,
+    io: BlockingPool,
+    /// Buffer handed to the I/O threads to write to the staging file, reused across writes.
+    buffer: Vec<u8>,
+    upload_id: Option<String>,
+    /// Number of bytes of the next part written to the staging file.
+    staged_len: usize,
+    parts: Vec<CompletedPart>,
+    /// Size and checksum of each uploaded part, to review the upload before completing it.
+    review_parts: Vec<UploadReviewPart>,
+    /// Aborts the upload if it is dropped before completing. Taken on completion.
+    abort: Option<Box<dyn FnOnce(String) + Send + Sync>>,
+}
+
+impl<Client> JournaledUpload<Client>
+where
+    Client: ObjectClient + Send + 'static,
+{
+    pub async fn write(&mut self, mut data: &[u8]) -> Result<(), UploadError<Client::ClientError>> {
+        while !data.is_empty() {
+            let len = data.len().min(self.params.part_size - self.staged_len);
+            let part_number = self.next_part_number();
+            let mut staged = mem::take(&mut self.buffer);
+            staged.clear();
+            staged.extend_from_slice(&data[..len]);
+            self.buffer = self
+                .with_entry(move |entry| {
+                    entry.write_staging(part_number, &staged)?;
+                    Ok(staged)
+                })
+                .await?;
+            self.staged_len += len;
+            data = &data[len..];
+            if self.staged_len == self.params.part_size {
+                self.upload_staged_part().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Complete the upload if `review_callback` accepts the parts that were uploaded, like
+    /// [PutObjectRequest::review_and_complete](mountpoint_s3_client::PutObjectRequest::review_and_complete).
+    pub async fn complete(
+        mut self,
+        review_callback: impl FnOnce(UploadReview) -> bool,
+    ) -> Result<ETag, UploadError<Client::ClientError>> {
+        let upload_id = self.upload_id().await?;
+
+        // From this point on, a replay of the journal will complete the upload.
+        self.with_entry(|entry| {
+            entry.sync_staging()?;
+            entry.append(&JournalRecord::Closed)
+        })
+        .await?;
+
+        // An upload needs at least one part, even if it is empty.
+        if self.staged_len > 0 || self.parts.is_empty() {
+            self.upload_staged_part().await?;
+        }
+
+        let review = UploadReview {
+            parts: mem::take(&mut self.review_parts),
+            checksum_algorithm: self.params.create_params.checksum_algorithm.clone(),
+        };
+        if !review_callback(review) {
+            return Err(UploadError::ReviewFailed);
+        }
+
+        let params = CompleteMultipartUploadParams::new().if_match(self.params.if_match.clone());
+        let result = self
+            .client
+            .complete_multipart_upload(&self.params.bucket, &self.params.key, &upload_id, &self.parts, &params)
+            .await?;
+
+        self.abort = None;
+        if let Err(error) = self.with_entry(|entry| entry.remove()).await {
+            warn!(key = self.params.key, ?error, "failed to remove upload journal");
+        }
+        Ok(result.etag)
+    }
+
+    fn next_part_number(&self) -> usize {
+        self.parts.len() + 1
+    }
+
+    /// Run a function on the journal entry on the I/O threads of the journal, since it does blocking I/O.
+    async fn with_entry<T, F>(&self, f: F) -> Result<T, JournalError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut JournalEntry) -> Result<T, JournalError> + Send + 'static,
+    {
+        let entry = self.entry.clone();
+        self.io.run(move || f(&mut entry.lock().unwrap())).await?
+    }
+
+    /// Return the ID of the multipart upload, creating it if needed.
+    async fn upload_id(&mut self) -> Result<String, UploadError<Client::ClientError>> {
+        if let Some(upload_id) = &self.upload_id {
+            return Ok(upload_id.clone());
+        }
+
+        let result = self
+            .client
+            .create_multipart_upload(&self.params.bucket, &self.params.key, &self.params.create_params)
+            .await?;
+        self.upload_id = Some(result.upload_id.clone());
+        let record = JournalRecord::Start {
+            bucket: self.params.bucket.clone(),
+            key: self.params.key.clone(),
+            upload_id: result.upload_id.clone(),
+            if_match: self.params.if_match.as_ref().map(|etag| etag.as_str().to_owned()),
+            checksum_algorithm: self
+                .params
+                .create_params
+                .checksum_algorithm
+                .as_ref()
+                .map(ToString::to_string),
+        };
+        self.with_entry(move |entry| entry.append(&record)).await?;
+        Ok(result.upload_id)
+    }
+
+    async fn upload_staged_part(&mut self) -> Result<(), UploadError<Client::ClientError>> {
+        let upload_id = self.upload_id().await?;
+        let part_number = self.next_part_number();
+        let size = self.staged_len;
+        let reservation = PartReservation::new(&self.mem_limiter, size as u64);
+        let data = self
+            .with_entry(move |entry| entry.read_staging(part_number, size))
+            .await?;
+        let params = upload_part_params(
+            &data,
+            &self.params.create_params.checksum_algorithm,
+            self.params.create_params.sse_customer_key.clone(),
+        )?;
+        let checksum = params.checksum.as_ref().map(UploadChecksum::to_base64);
+        // Without additional checksums, parts are reviewed with CRC32C checksums, as for PutObject requests.
+        let review_checksum = checksum
+            .clone()
+            .unwrap_or_else(|| crc32c_to_base64(&crc32c::checksum(&data)));
+        let part = upload_part(
+            &self.client,
+            &self.params.bucket,
+            &self.params.key,
+            &upload_id,
+            part_number,
+            data,
+            params,
+        )
+        .await?;
+        drop(reservation);
+        let record = JournalRecord::Part {
+            part_number,
+            etag: part.etag.as_str().to_owned(),
+            size: self.staged_len as u64,
+            checksum,
+        };
+        self.with_entry(move |entry| {
+            entry.append(&record)?;
+            entry.remove_staging(part_number)
+        })
+        .await?;
+        self.parts.push(part);
+        self.review_parts.push(UploadReviewPart {
+            size: self.staged_len as u64,
+            checksum: Some(review_checksum),
+        });
+        self.staged_len = 0;
+        Ok(())
+    }
+}
+
+impl<Client: ObjectClient> Drop for JournaledUpload<Client> {
+    fn drop(&mut self) {
+        let Some(abort) = self.abort.take() else {
+            return;
+        };
+        if let Err(error) = self.entry.lock().unwrap().remove() {
+            warn!(key = self.params.key, ?error, "failed to remove upload journal");
+        }
+        if let Some(upload_id) = self.upload_id.take() {
+            abort(upload_id);
+        }
+    }
+}
+
+impl<Client: ObjectClient> std::fmt::Debug for JournaledUpload<Client> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JournaledUpload")
+            .field("entry", &self.entry)
+            .field("upload_id", &self.upload_id)
+            .field("staged_len", &self.staged_len)
+            .field("parts", &self.parts.len())
+            .finish()
+    }
+}
+
+/// The journal and staging files of a single upload.
+#[derive(Debug)]
+struct JournalEntry {
+    dir: PathBuf,
+    id: String,
+    journal: Option<File>,
+    /// The staging file currently being written, and the part number it holds.
+    staging: Option<(usize, File)>,
+}
+
+impl JournalEntry {
+    fn new(dir: &Path, id: String) -> Self {
+        Self {
+            dir: dir.to_owned(),
+            id,
+            journal: None,
+            staging: None,
+        }
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.{}", self.id, JOURNAL_EXTENSION))
+    }
+
+    fn staging_path(&self, part_number: usize) -> PathBuf {
+        self.dir
+            .join(format!("{}.{}.{}", self.id, part_number, STAGING_EXTENSION))
+    }
+
+    /// Durably append a record to the journal file.
+    fn append(&mut self, record: &JournalRecord) -> Result<(), JournalError> {
+        if self.journal.is_none() {
+            let file = OpenOptions::new().create(true).append(true).open(self.journal_path())?;
+            self.journal = Some(file);
+        }
+        let journal = self.journal.as_mut().unwrap();
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        journal.write_all(&line)?;
+        journal.sync_data()?;
+        Ok(())
+    }
+
+    fn write_staging(&mut self, part_number: usize, data: &[u8]) -> Result<(), JournalError> {
+        if !matches!(self.staging, Some((staged_part, _)) if staged_part == part_number) {
+            let file = File::create(self.staging_path(part_number))?;
+            self.staging = Some((part_number, file));
+        }
+        let (_, file) = self.staging.as_mut().unwrap();
+        file.write_all(data)?;
+        Ok(())
+    }
+
+    fn sync_staging(&self) -> Result<(), JournalError> {
+        if let Some((_, file)) = &self.staging {
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Read the `len` bytes staged for a part into a buffer of exactly that size.
+    fn read_staging(&self, part_number: usize, len: usize) -> Result<Vec<u8>, JournalError> {
+        let mut data = vec![0; len];
+        if len > 0 {
+            File::open(self.staging_path(part_number))?.read_exact(&mut data)?;
+        }
+        Ok(data)
+    }
+
+    /// Return the number of bytes staged for a part, or `None` if there is no staging file.
+    fn staging_len(&self, part_number: usize) -> Result<Option<usize>, JournalError> {
+        match fs::metadata(self.staging_path(part_number)) {
+            Ok(metadata) => Ok(Some(metadata.len() as usize)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn remove_staging(&mut self, part_number: usize) -> Result<(), JournalError> {
+        if matches!(self.staging, Some((staged_part, _)) if staged_part == part_number) {
+            self.staging = None;
+        }
+        remove_if_exists(&self.staging_path(part_number))?;
+        Ok(())
+    }
+
+    /// Remove the journal file and all staging files of this upload.
+    fn remove(&mut self) -> Result<(), JournalError> {
+        self.journal = None;
+        self.staging = None;
+        let staging_prefix = format!("{}.", self.id);
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            let is_staging_file = path.extension() == Some(STAGING_EXTENSION.as_ref())
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(&staging_prefix));
+            if is_staging_file {
+                remove_if_exists(&path)?;
+            }
+        }
+        remove_if_exists(&self.journal_path())?;
+        Ok(())
+    }
+}
+
+/// Read the records of a journal file.
+fn read_records(path: &Path) -> Result<Vec<JournalRecord>, JournalError> {
+    let file = File::open(path)?;
+    let lines = BufReader::new(file).lines().collect::<Result<Vec<_>, _>>()?;
+    let mut records = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        match serde_json::from_str(line) {
+            Ok(record) => records.push(record),
+            // The last record may have been partially written when the process exited.
+            Err(_) if i == lines.len() - 1 => break,
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(records)
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), io::Error> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+/// Memory reserved through the [MemoryLimiter] for a part read back from a staging file, released
+/// when dropped. The reservation always succeeds, as an upload cannot make progress without its part,
+/// but it makes the prefetcher hold less data while the part is in memory.
+struct PartReservation<'a, Client: ObjectClient> {
+    mem_limiter: &'a MemoryLimiter<Client>,
+    size: u64,
+}
+
+impl<'a, Client: ObjectClient> PartReservation<'a, Client> {
+    fn new(mem_limiter: &'a MemoryLimiter<Client>, size: u64) -> Self {
+        mem_limiter.reserve(BufferArea::Upload, size);
+        Self { mem_limiter, size }
+    }
+}
+
+impl<Client: ObjectClient> Drop for PartReservation<'_, Client> {
+    fn drop(&mut self) {
+        self.mem_limiter.release(BufferArea::Upload, self.size);
+    }
+}
+
+fn upload_part_params(
+    data: &[u8],
+    checksum_algorithm: &Option<ChecksumAlgorithm>,
+    sse_customer_key: Option<SseCustomerKey>,
+) -> Result<UploadPartParams, ChecksumHasherError> {
+    let mut hasher = ChecksumHasher::new(checksum_algorithm)?;
+    hasher.update(data)?;
+    Ok(UploadPartParams::new()
+        .checksum(hasher.finalize()?)
+        .sse_customer_key(sse_customer_key))
+}
+
+fn parse_checksum_algorithm(algorithm: &str) -> anyhow::Result<ChecksumAlgorithm> {
+    match algorithm {
+        "CRC32" => Ok(ChecksumAlgorithm::Crc32),
+        "CRC32C" => Ok(ChecksumAlgorithm::Crc32c),
+        "CRC64NVME" => Ok(ChecksumAlgorithm::Crc64nvme),
+        "SHA1" => Ok(ChecksumAlgorithm::Sha1),
+        "SHA256" => Ok(ChecksumAlgorithm::Sha256),
+        _ => Err(anyhow::anyhow!("unsupported checksum algorithm {algorithm:?}")),
+    }
+}
+
+/// Build the [Checksum] of a completed part from the checksum recorded in the journal.
+fn part_checksum(checksum_algorithm: Option<&ChecksumAlgorithm>, value: Option<String>) -> Checksum {
+    let mut checksum = Checksum::empty();
+    match checksum_algorithm {
+        Some(ChecksumAlgorithm::Crc32) => checksum.checksum_crc32 = value,
+        Some(ChecksumAlgorithm::Crc32c) => checksum.checksum_crc32c = value,
+        Some(ChecksumAlgorithm::Crc64nvme) => checksum.checksum_crc64nvme = value,
+        Some(ChecksumAlgorithm::Sha1) => checksum.checksum_sha1 = value,
+        Some(ChecksumAlgorithm::Sha256) => checksum.checksum_sha256 = value,
+        Some(_) | None => {}
+    }
+    checksum
+}
+
+async fn upload_part<Client: ObjectClient>(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    part_number: usize,
+    data: Vec<u8>,
+    params: UploadPartParams,
+) -> Result<CompletedPart, ObjectClientError<UploadPartError, Client::ClientError>> {
+    let result = client
+        .upload_part(bucket, key, upload_id, part_number, &params, data)
+        .await?;
+    Ok(CompletedPart {
+        part_number,
+        etag: result.etag,
+        checksum: params.checksum.into(),
+    })
+}
+
+/// List all the parts of a multipart upload, or return `None` if the upload does not exist.
+async fn list_all_parts<Client: ObjectClient>(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+) -> Result<Option<Vec<UploadedPart>>, ObjectClientError<ListPartsError, Client::ClientError>> {
+    let mut parts = Vec::new();
+    let mut part_number_marker = None;
+    loop {
+        let result = match client.list_parts(bucket, key, upload_id, part_number_marker).await {
+            Ok(result) => result,
+            Err(ObjectClientError::ServiceError(ListPartsError::NoSuchUpload)) => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        parts.extend(result.parts);
+        part_number_marker = result.next_part_number_marker;
+        if part_number_marker.is_none() {
+            return Ok(Some(parts));
+        }
+    }
+}
+
+async fn abort<Client: ObjectClient>(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+) -> Result<ReplayOutcome, ObjectClientError<AbortMultipartUploadError, Client::ClientError>> {
+    match client.abort_multipart_upload(bucket, key, upload_id).await {
+        Ok(_) => Ok(ReplayOutcome::Aborted),
+        Err(ObjectClientError::ServiceError(AbortMultipartUploadError::NoSuchUpload)) => Ok(ReplayOutcome::Gone),
+        Err(error) => Err(error),
+    }
+}
+
+async fn replay_entry<Client: ObjectClient>(
+    client: &Client,
+    bucket: &str,
+    sse_customer_key: Option<&SseCustomerKey>,
+    mem_limiter: &MemoryLimiter<Client>,
+    io: &BlockingPool,
+    entry: &JournalEntry,
+) -> anyhow::Result<ReplayOutcome> {
+    let journal_path = entry.journal_path();
+    let records = io
+        .run(move || read_records(&journal_path))
+        .await?
+        .context("failed to read journal")?;
+    let Some(JournalRecord::Start {
+        bucket: upload_bucket,
+        key,
+        upload_id,
+        if_match,
+        checksum_algorithm,
+    }) = records.first()
+    else {
+        // The process exited before the upload was created, so there is nothing to clean up in S3.
+        return Ok(ReplayOutcome::Gone);
+    };
+    if upload_bucket != bucket {
+        return Ok(ReplayOutcome::Skipped);
+    }
+
+    let checksum_algorithm = checksum_algorithm
+        .as_deref()
+        .map(parse_checksum_algorithm)
+        .transpose()?;
+
+    let closed = records.iter().any(|record| matches!(record, JournalRecord::Closed));
+    if !closed {
+        info!(
+            key,
+            upload_id, "aborting multipart upload for a file that was not closed"
+        );
+        return Ok(abort(client, bucket, key, upload_id).await?);
+    }
+
+    // Check that the parts recorded in the journal were not lost.
+    let Some(uploaded_parts) = list_all_parts(client, bucket, key, upload_id).await? else {
+        info!(key, upload_id, "multipart upload was already completed or aborted");
+        return Ok(ReplayOutcome::Gone);
+    };
+    let mut parts = Vec::new();
+    for record in &records {
+        let JournalRecord::Part {
+            part_number,
+            etag,
+            size,
+            checksum,
+        } = record
+        else {
+            continue;
+        };
+        let found = uploaded_parts
+            .iter()
+            .any(|part| part.part_number == *part_number && part.etag.as_str() == etag && part.size == *size);
+        if !found {
+            warn!(
+                key,
+                upload_id, part_number, "uploaded part is missing, aborting multipart upload"
+            );
+            return Ok(abort(client, bucket, key, upload_id).await?);
+        }
+        parts.push(CompletedPart {
+            part_number: *part_number,
+            etag: etag.as_str().into(),
+            checksum: part_checksum(checksum_algorithm.as_ref(), checksum.clone()),
+        });
+    }
+
+    // Upload the data that was staged but not yet uploaded when the process exited.
+    let part_number = parts.len() + 1;
+    let staging = JournalEntry::new(&entry.dir, entry.id.clone());
+    let staged_len = io
+        .run(move || staging.staging_len(part_number))
+        .await?
+        .context("failed to read staging file")?
+        .unwrap_or_default();
+    if staged_len > 0 || parts.is_empty() {
+        let _reservation = PartReservation::new(mem_limiter, staged_len as u64);
+        let staging = JournalEntry::new(&entry.dir, entry.id.clone());
+        let data = io
+            .run(move || staging.read_staging(part_number, staged_len))
+            .await?
+            .context("failed to read staging file")?;
+        let params = upload_part_params(&data, &checksum_algorithm, sse_customer_key.cloned())?;
+        parts.push(upload_part(client, bucket, key, upload_id, part_number, data, params).await?);
+    }
+
+    let params = CompleteMultipartUploadParams::new().if_match(if_match.as_deref().map(ETag::from));
+    match client
+        .complete_multipart_upload(bucket, key, upload_id, &parts, &params)
+        .await
+    {
+        Ok(_) => {
+            info!(key, upload_id, "completed multipart upload from journal");
+            Ok(ReplayOutcome::Completed)
+        }
+        Err(ObjectClientError::ServiceError(CompleteMultipartUploadError::PreconditionFailed)) => {
+            warn!(
+                key,
+                upload_id, "object was modified remotely, aborting multipart upload"
+            );
+            Ok(abort(client, bucket, key, upload_id).await?)
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use futures::executor::ThreadPool;
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig};
+    use mountpoint_s3_client::types::{GetObjectParams, GetObjectResponse, HeadObjectParams, ObjectAttribute};
+    use test_case::test_case;
+
+    use super::*;
+    use crate::mem_limiter::MINIMUM_MEM_LIMIT;
+
+    const BUCKET: &str = "bucket";
+    const PART_SIZE: usize = 32;
+
+    fn new_client() -> Arc<MockClient> {
+        Arc::new(MockClient::new(MockClientConfig {
+            bucket: BUCKET.to_owned(),
+            part_size: PART_SIZE,
+            ..Default::default()
+        }))
+    }
+
+    fn start_upload(journal: &UploadJournal, client: Arc<MockClient>, key: &str) -> JournaledUpload<Arc<MockClient>> {
+        start_upload_with_checksum_algorithm(journal, client, key, ChecksumAlgorithm::Crc32c)
+    }
+
+    fn start_upload_with_checksum_algorithm(
+        journal: &UploadJournal,
+        client: Arc<MockClient>,
+        key: &str,
+        checksum_algorithm: ChecksumAlgorithm,
+    ) -> JournaledUpload<Arc<MockClient>> {
+        let runtime = BoxRuntime::new(ThreadPool::builder().pool_size(1).create().unwrap());
+        let params = JournaledUploadParams {
+            bucket: BUCKET.to_owned(),
+            key: key.to_owned(),
+            part_size: PART_SIZE,
+            create_params: CreateMultipartUploadParams::new().checksum_algorithm(Some(checksum_algorithm)),
+            if_match: None,
+        };
+        let mem_limiter = new_mem_limiter(&client).into();
+        journal.start_upload(&runtime, client, mem_limiter, params)
+    }
+
+    fn new_mem_limiter(client: &Arc<MockClient>) -> MemoryLimiter<Arc<MockClient>> {
+        MemoryLimiter::new(client.clone(), MINIMUM_MEM_LIMIT)
+    }
+
+    fn journal_files(journal: &UploadJournal) -> usize {
+        fs::read_dir(&journal.dir).unwrap().count()
+    }
+
+    #[tokio::test]
+    async fn test_journaled_upload() {
+        let journal_dir = tempfile::tempdir().unwrap();
+        let journal = UploadJournal::new(journal_dir.path()).unwrap();
+        let client = new_client();
+
+        let mut upload = start_upload(&journal, client.clone(), "key");
+        upload.write(&[0xaa; 50]).await.unwrap();
+        upload.write(&[0xbb; 50]).await.unwrap();
+        assert_eq!(client.multipart_upload_count(), 1);
+        assert!(journal_files(&journal) > 0);
+
+        let etag = upload
+            .complete(|review| {
+                let sizes: Vec<_> = review.parts.iter().map(|part| part.size).collect();
+                sizes == [32, 32, 32, 4] && review.parts.iter().all(|part| part.checksum.is_some())
+            })
+            .await
+            .unwrap();
+        let head = client
+            .head_object(BUCKET, "key", &HeadObjectParams::new())
+            .await
+            .unwrap();
+        assert_eq!(head.size, 100);
+        assert_eq!(head.etag, etag);
+        assert_eq!(client.multipart_upload_count(), 0);
+        assert_eq!(journal_files(&journal), 0);
+    }
+
+    #[tokio::test]
+    async fn test_journaled_upload_review_failed() {
+        let journal_dir = tempfile::tempdir().unwrap();
+        let journal = UploadJournal::new(journal_dir.path()).unwrap();
+        let client = new_client();
+
+        let mut upload = start_upload(&journal, client.clone(), "key");
+        upload.write(&[0xaa; 50]).await.unwrap();
+        let result = upload.complete(|_| false).await;
+        assert!(matches!(result, Err(UploadError::ReviewFailed)));
+
+        // The upload is aborted in the background
+        let start = Instant::now();
+        while client.multipart_upload_count() > 0 && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(client.multipart_upload_count(), 0);
+        assert!(!client.contains_key("key"));
+        assert_eq!(journal_files(&journal), 0);
+    }
+
+    #[test_case(ChecksumAlgorithm::Crc32c; "crc32c")]
+    #[test_case(ChecksumAlgorithm::Crc64nvme; "crc64nvme")]
+    #[test_case(ChecksumAlgorithm::Sha256; "sha256")]
+    #[tokio::test]
+    async fn test_replay_closed_upload(checksum_algorithm: ChecksumAlgorithm) {
+        let journal_dir = tempfile::tempdir().unwrap();
+        let journal = UploadJournal::new(journal_dir.path()).unwrap();
+        let client = new_client();
+
+        // Simulate a crash after the file was closed, but before the upload completed.
+        let mut upload =
+            start_upload_with_checksum_algorithm(&journal, client.clone(), "key", checksum_algorithm.clone());
+        upload.write(&[0xaa; 40]).await.unwrap();
+        upload.upload_id().await.unwrap();
+        upload.entry.lock().unwrap().sync_staging().unwrap();
+        upload.entry.lock().unwrap().append(&JournalRecord::Closed).unwrap();
+        upload.abort = None;
+        drop(upload);
+        assert!(!client.contains_key("key"));
+
+        journal
+            .replay(&client, BUCKET, None, &new_mem_limiter(&client))
+            .await
+            .unwrap();
+
+        let head = client
+            .head_object(BUCKET, "key", &HeadObjectParams::new())
+            .await
+            .unwrap();
+        assert_eq!(head.size, 40);
+        let attributes = client
+            .get_object_attributes(BUCKET, "key", None, None, &[ObjectAttribute::Checksum])
+            .await
+            .unwrap();
+        let checksum = attributes.checksum.expect("object should have a checksum");
+        assert_eq!(checksum.algorithms(), vec![checksum_algorithm]);
+        assert_eq!(client.multipart_upload_count(), 0);
+        assert_eq!(journal_files(&journal), 0);
+    }
+
+    #[tokio::test]
+    async fn test_replay_closed_upload_with_sse_customer_key() {
+        let journal_dir = tempfile::tempdir().unwrap();
+        let journal = UploadJournal::new(journal_dir.path()).unwrap();
+        let client = new_client();
+        let sse_customer_key = SseCustomerKey::new(&[0x42; SseCustomerKey::KEY_LENGTH]).unwrap();
+
+        let runtime = BoxRuntime::new(ThreadPool::builder().pool_size(1).create().unwrap());
+        let params = JournaledUploadParams {
+            bucket: BUCKET.to_owned(),
+            key: "key".to_owned(),
+            part_size: PART_SIZE,
+            create_params: CreateMultipartUploadParams::new().sse_customer_key(Some(sse_customer_key.clone())),
+            if_match: None,
+        };
+        let mut upload = journal.start_upload(&runtime, client.clone(), new_mem_limiter(&client).into(), params);
+        upload.write(&[0xaa; 40]).await.unwrap();
+        upload.upload_id().await.unwrap();
+        upload.entry.lock().unwrap().sync_staging().unwrap();
+        upload.entry.lock().unwrap().append(&JournalRecord::Closed).unwrap();
+        upload.abort = None;
+        drop(upload);
+
+        // The key is not in the journal, so the staged part can only be uploaded if it is provided again.
+        journal
+            .replay(&client, BUCKET, None, &new_mem_limiter(&client))
+            .await
+            .unwrap();
+        assert_eq!(client.multipart_upload_count(), 1);
+        assert!(journal_files(&journal) > 0);
+
+        journal
+            .replay(&client, BUCKET, Some(&sse_customer_key), &new_mem_limiter(&client))
+            .await
+            .unwrap();
+        let head_params = HeadObjectParams::new().sse_customer_key(Some(sse_customer_key));
+        let head = client.head_object(BUCKET, "key", &head_params).await.unwrap();
+        assert_eq!(head.size, 40);
+        assert_eq!(journal_files(&journal), 0);
+    }
+
+    #[tokio::test]
+    async fn test_replay_with_constrained_memory() {
+        let journal_dir = tempfile::tempdir().unwrap();
+        let journal = UploadJournal::new(journal_dir.path()).unwrap();
+        let client = new_client();
+
+        // Simulate a crash after closing two files, each with several uploaded parts and a staged part.
+        let contents = [vec![0xaa; 3 * PART_SIZE + 4], vec![0xbb; 2 * PART_SIZE + 20]];
+        for (i, data) in contents.iter().enumerate() {
+            let mut upload = start_upload(&journal, client.clone(), &format!("key{i}"));
+            upload.write(data).await.unwrap();
+            upload.entry.lock().unwrap().sync_staging().unwrap();
+            upload.entry.lock().unwrap().append(&JournalRecord::Closed).unwrap();
+            upload.abort = None;
+            drop(upload);
+        }
+        assert_eq!(client.multipart_upload_count(), 2);
+
+        // Only leave room for a single part on top of the 128 MiB the limiter reserves for other uses.
+        let mem_limiter = MemoryLimiter::new(client.clone(), 128 * 1024 * 1024 + PART_SIZE as u64);
+        assert_eq!(mem_limiter.available_mem(), PART_SIZE as u64);
+
+        journal.replay(&client, BUCKET, None, &mem_limiter).await.unwrap();
+
+        for (i, data) in contents.iter().enumerate() {
+            let response = client
+                .get_object(BUCKET, &format!("key{i}"), &GetObjectParams::new())
+                .await
+                .unwrap();
+            assert_eq!(*response.collect().await.unwrap(), **data);
+        }
+        assert_eq!(client.multipart_upload_count(), 0);
+        assert_eq!(journal_files(&journal), 0);
+        assert_eq!(
+            mem_limiter.available_mem(),
+            PART_SIZE as u64,
+            "memory reserved for the staged parts should be released"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_unclosed_upload() {
+        let journal_dir = tempfile::tempdir().unwrap();
+        let journal = UploadJournal::new(journal_dir.path()).unwrap();
+        let client = new_client();
+
+        // Simulate a crash while the file was still being written.
+        let mut upload = start_upload(&journal, client.clone(), "key");
+        upload.write(&[0xaa; 40]).await.unwrap();
+        upload.abort = None;
+        drop(upload);
+        assert_eq!(client.multipart_upload_count(), 1);
+
+        journal
+            .replay(&client, BUCKET, None, &new_mem_limiter(&client))
+            .await
+            .unwrap();
+
+        assert!(!client.contains_key("key"));
+        assert_eq!(client.multipart_upload_count(), 0);
+        assert_eq!(journal_files(&journal), 0);
+    }
+
+    #[tokio::test]
+    async fn test_replay_other_bucket() {
+        let journal_dir = tempfile::tempdir().unwrap();
+        let journal = UploadJournal::new(journal_dir.path()).unwrap();
+        let client = new_client();
+
+        let mut upload = start_upload(&journal, client.clone(), "key");
+        upload.write(&[0xaa; 40]).await.unwrap();
+        upload.abort = None;
+        drop(upload);
+
+        journal
+            .replay(&client, "other-bucket", None, &new_mem_limiter(&client))
+            .await
+            .unwrap();
+
+        assert_eq!(client.multipart_upload_count(), 1);
+        assert!(journal_files(&journal) > 0);
+    }
+
+    #[tokio::test]
+    async fn test_drop_aborts_upload() {
+        let journal_dir = tempfile::tempdir().unwrap();
+        let journal = UploadJournal::new(journal_dir.path()).unwrap();
+        let client = new_client();
+
+        let mut upload = start_upload(&journal, client.clone(), "key");
+        upload.write(&[0xaa; 40]).await.unwrap();
+        drop(upload);
+
+        assert_eq!(journal_files(&journal), 0);
+        // The abort request is sent in the background.
+        for _ in 0..100 {
+            if client.multipart_upload_count() == 0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(client.multipart_upload_count(), 0);
+    }
+}
@@ -97,6 +97,30 @@ fn invalid_file_mode() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn invalid_tag() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let mut cmd = Command::cargo_bin("mount-s3")?;
+
+    cmd.arg("test-bucket").arg(dir.path()).arg("--tag=project");
+    let error_message = "'--tag <KEY=VALUE>': tags must be of the form KEY=VALUE";
+    cmd.assert().failure().stderr(predicate::str::contains(error_message));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_acl() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let mut cmd = Command::cargo_bin("mount-s3")?;
+
+    cmd.arg("test-bucket").arg(dir.path()).arg("--acl=log-delivery-write");
+    let error_message = "invalid value 'log-delivery-write' for '--acl <ACL>'";
+    cmd.assert().failure().stderr(predicate::str::contains(error_message));
+
+    Ok(())
+}
+
 #[test]
 fn print_version_long() -> Result<(), Box<dyn std::error::Error>> {
     let mut cmd = Command::cargo_bin("mount-s3")?;
@@ -278,6 +302,129 @@ fn sse_key_not_allowed_with_aes256() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn sse_customer_key_not_allowed_with_sse() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let key_file = dir.path().join("sse-c.key");
+    fs::write(&key_file, [0u8; 32])?;
+    let mut cmd = Command::cargo_bin("mount-s3")?;
+    cmd.arg("test-bucket")
+        .arg(dir.path())
+        .arg("--sse=AES256")
+        .arg("--sse-customer-key-file")
+        .arg(&key_file);
+    let error_message = "the argument '--sse <SSE>' cannot be used with '--sse-customer-key-file <PATH>'";
+    cmd.assert().failure().stderr(predicate::str::contains(error_message));
+
+    Ok(())
+}
+
+#[test]
+fn sse_customer_key_invalid_length() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let key_file = dir.path().join("sse-c.key");
+    fs::write(&key_file, [0u8; 16])?;
+    let mut cmd = Command::cargo_bin("mount-s3")?;
+    cmd.arg("test-bucket")
+        .arg(dir.path())
+        .arg("--sse-customer-key-file")
+        .arg(&key_file);
+    let error_message = "SSE-C keys must be 32 bytes long, but got 16 bytes";
+    cmd.assert().failure().stderr(predicate::str::contains(error_message));
+
+    Ok(())
+}
+
+#[test_case(&["--object-lock-mode=GOVERNANCE", "--object-lock-retain-days=30"]; "retention")]
+#[test_case(&["--object-lock-legal-hold"]; "legal hold")]
+fn object_lock_not_allowed_without_upload_checksums(
+    object_lock_args: &[&str],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let mut cmd = Command::cargo_bin("mount-s3")?;
+    cmd.arg("test-bucket")
+        .arg(dir.path())
+        .args(object_lock_args)
+        .arg("--upload-checksums=off");
+    let error_message = "--object-lock-mode and --object-lock-legal-hold can not be used with --upload-checksums off";
+    cmd.assert().failure().stderr(predicate::str::contains(error_message));
+
+    Ok(())
+}
+
+#[test]
+fn content_type_map_requires_infer_content_type() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let map_file = dir.path().join("mime.types");
+    fs::write(&map_file, "text/plain txt\n")?;
+    let mut cmd = Command::cargo_bin("mount-s3")?;
+    cmd.arg("test-bucket")
+        .arg(dir.path())
+        .arg("--content-type-map")
+        .arg(&map_file);
+    let error_message = "the following required arguments were not provided";
+    cmd.assert().failure().stderr(predicate::str::contains(error_message));
+
+    Ok(())
+}
+
+#[test]
+fn content_type_map_invalid() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let map_file = dir.path().join("mime.types");
+    fs::write(&map_file, "# types\ntext txt\n")?;
+    let mut cmd = Command::cargo_bin("mount-s3")?;
+    cmd.arg("test-bucket")
+        .arg(dir.path())
+        .arg("--infer-content-type")
+        .arg("--content-type-map")
+        .arg(&map_file);
+    let error_message = "invalid content type \"text\" on line 2";
+    cmd.assert().failure().stderr(predicate::str::contains(error_message));
+
+    Ok(())
+}
+
+#[test]
+fn object_lock_mode_requires_retain_days() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let mut cmd = Command::cargo_bin("mount-s3")?;
+    cmd.arg("test-bucket")
+        .arg(dir.path())
+        .arg("--object-lock-mode=COMPLIANCE");
+    let error_message = "the following required arguments were not provided";
+    cmd.assert().failure().stderr(predicate::str::contains(error_message));
+
+    Ok(())
+}
+
+#[test]
+fn as_of_requires_read_only() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let mut cmd = Command::cargo_bin("mount-s3")?;
+    cmd.arg("test-bucket")
+        .arg(dir.path())
+        .arg("--as-of=2024-11-01T12:00:00Z");
+    let error_message = "the following required arguments were not provided";
+    cmd.assert().failure().stderr(predicate::str::contains(error_message));
+
+    Ok(())
+}
+
+#[test]
+fn as_of_invalid_timestamp() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let mut cmd = Command::cargo_bin("mount-s3")?;
+    cmd.arg("test-bucket")
+        .arg(dir.path())
+        .arg("--read-only")
+        .arg("--as-of=2024-11-01");
+    let error_message = "timestamps must be in RFC 3339 format";
+    cmd.assert().failure().stderr(predicate::str::contains(error_message));
+
+    Ok(())
+}
+
 #[test_case(Some(1024), Some(1024))]
 #[test_case(None, Some(1024))]
 #[test_case(Some(1024), None)]
@@ -301,3 +448,25 @@ fn verify_new_part_size_config_conflict_with_old_one(
 
     Ok(())
 }
+
+#[test]
+fn list_uploads_invalid_age() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("mount-s3")?;
+
+    cmd.arg("list-uploads").arg("test-bucket").arg("--older-than=1w");
+    let error_message = "'--older-than <AGE>': must be a number of seconds, optionally followed by s, m, h, or d";
+    cmd.assert().failure().stderr(predicate::str::contains(error_message));
+
+    Ok(())
+}
+
+#[test]
+fn list_uploads_requires_bucket() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("mount-s3")?;
+
+    cmd.arg("list-uploads").arg("--abort");
+    let error_message = "Usage: mount-s3 list-uploads";
+    cmd.assert().failure().stderr(predicate::str::contains(error_message));
+
+    Ok(())
+}
@@ -602,6 +602,7 @@ pub mod s3_session {
             let object_checksum = attrs.checksum.map(|checksum| Checksum {
                 checksum_crc32: checksum.checksum_crc32,
                 checksum_crc32c: checksum.checksum_crc32_c,
+                checksum_crc64nvme: None,
                 checksum_sha1: checksum.checksum_sha1,
                 checksum_sha256: checksum.checksum_sha256,
             });
@@ -617,6 +618,7 @@ pub mod s3_session {
                             Some(Checksum {
                                 checksum_crc32: part.checksum_crc32,
                                 checksum_crc32c: part.checksum_crc32_c,
+                                checksum_crc64nvme: None,
                                 checksum_sha1: part.checksum_sha1,
                                 checksum_sha256: part.checksum_sha256,
                             })
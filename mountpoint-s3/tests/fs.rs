@@ -5,17 +5,22 @@ use fuser::FileType;
 use mountpoint_s3::fs::error_metadata::MOUNTPOINT_ERROR_LOOKUP_NONEXISTENT;
 #[cfg(all(feature = "s3_tests", not(feature = "s3express_tests")))]
 use mountpoint_s3::fs::error_metadata::{ErrorMetadata, MOUNTPOINT_ERROR_CLIENT};
-use mountpoint_s3::fs::{CacheConfig, OpenFlags, ToErrno, FUSE_ROOT_INODE};
+use mountpoint_s3::fs::{CacheConfig, OpenFlags, ServerSideEncryption, ToErrno, FUSE_ROOT_INODE};
+use mountpoint_s3::manifest::{Manifest, ManifestFormat};
 use mountpoint_s3::prefix::Prefix;
 use mountpoint_s3::s3::S3Personality;
+use mountpoint_s3::upload::{ContentTypeMap, UploadJournal};
 use mountpoint_s3::S3FilesystemConfig;
 #[cfg(feature = "s3_tests")]
 use mountpoint_s3_client::config::S3ClientConfig;
+use mountpoint_s3_client::error::ObjectClientError;
 #[cfg(all(feature = "s3_tests", not(feature = "s3express_tests")))]
 use mountpoint_s3_client::error_metadata::ClientErrorMetadata;
 use mountpoint_s3_client::failure_client::{countdown_failure_client, CountdownFailureConfig};
 use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockClientError, MockObject, Operation};
-use mountpoint_s3_client::types::{ETag, GetObjectParams, RestoreStatus};
+use mountpoint_s3_client::types::{
+    ETag, GetObjectParams, HeadObjectParams, ObjectLock, ObjectTags, RestoreStatus, SseCustomerKey,
+};
 use mountpoint_s3_client::ObjectClient;
 #[cfg(all(feature = "s3_tests", not(feature = "s3express_tests")))]
 use mountpoint_s3_client::PutObjectRequest;
@@ -31,6 +36,7 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use test_case::test_case;
+use time::OffsetDateTime;
 
 mod common;
 #[cfg(all(feature = "s3_tests", not(feature = "s3express_tests")))]
@@ -561,6 +567,533 @@ async fn test_implicit_directory_shadow(prefix: &str) {
     fs.releasedir(dir_ino, dir_handle, 0).await.unwrap();
 }
 
+#[test_case(""; "unprefixed")]
+#[test_case("test_prefix/"; "prefixed")]
+#[tokio::test]
+async fn test_read_as_of(prefix: &str) {
+    let time = |seconds: i64| OffsetDateTime::from_unix_timestamp(seconds).unwrap();
+    let prefix = Prefix::new(prefix).expect("valid prefix");
+    let config = S3FilesystemConfig {
+        as_of: Some(time(200)),
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem("test_read_as_of", &prefix, config);
+
+    let add_version = |key: &str, version_id: &str, value: u8, last_modified: i64| {
+        let mut object = MockObject::constant(value, 15, ETag::from_str(&format!("etag_{version_id}")).unwrap());
+        object.set_last_modified(time(last_modified));
+        client.add_object_version(&format!("{prefix}{key}"), version_id, object);
+    };
+    // Overwritten after the snapshot time
+    add_version("file1.txt", "v1", 0xa1, 100);
+    add_version("file1.txt", "v2", 0xb1, 300);
+    // Deleted before the snapshot time
+    add_version("file2.txt", "v3", 0xa2, 100);
+    client.add_delete_marker(&format!("{prefix}file2.txt"), "v4", time(150));
+    // Created after the snapshot time
+    add_version("dir1/file3.txt", "v5", 0xa3, 300);
+    add_version("dir2/file4.txt", "v6", 0xa4, 100);
+
+    let dir_handle = fs.opendir(FUSE_ROOT_INODE, 0).await.unwrap().fh;
+    let mut reply = Default::default();
+    let _reply = fs
+        .readdirplus(FUSE_ROOT_INODE, dir_handle, 0, &mut reply)
+        .await
+        .unwrap();
+    let names: Vec<_> = reply.entries.iter().skip(2).map(|entry| entry.name.clone()).collect();
+    assert_eq!(names, vec![OsString::from("dir2"), OsString::from("file1.txt")]);
+    fs.releasedir(FUSE_ROOT_INODE, dir_handle, 0).await.unwrap();
+
+    let entry = fs.lookup(FUSE_ROOT_INODE, "file1.txt".as_ref()).await.unwrap();
+    assert_eq!(entry.attr.kind, FileType::RegularFile);
+    let fh = fs.open(entry.attr.ino, OpenFlags::empty(), 0).await.unwrap().fh;
+    let bytes_read = fs
+        .read(entry.attr.ino, fh, 0, 4096, 0, None)
+        .await
+        .expect("fs read should succeed");
+    assert_eq!(&bytes_read[..], &[0xa1; 15]);
+    fs.release(entry.attr.ino, fh, 0, None, true).await.unwrap();
+
+    for name in ["file2.txt", "dir1"] {
+        let entry = fs.lookup(FUSE_ROOT_INODE, name.as_ref()).await;
+        assert!(
+            matches!(entry, Err(e) if e.to_errno() == libc::ENOENT),
+            "{name} should not exist"
+        );
+    }
+    let entry = fs.lookup(FUSE_ROOT_INODE, "dir2".as_ref()).await.unwrap();
+    assert_eq!(entry.attr.kind, FileType::Directory);
+}
+
+#[test_case(""; "unprefixed")]
+#[test_case("test_prefix/"; "prefixed")]
+#[tokio::test]
+async fn test_read_versions_directory(prefix: &str) {
+    let time = |seconds: i64| OffsetDateTime::from_unix_timestamp(seconds).unwrap();
+    let prefix = Prefix::new(prefix).expect("valid prefix");
+    let config = S3FilesystemConfig {
+        show_versions: true,
+        allow_delete: true,
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem("test_read_versions_directory", &prefix, config);
+
+    let add_version = |key: &str, version_id: &str, value: u8, last_modified: i64| {
+        let mut object = MockObject::constant(value, 15, ETag::from_str(&format!("etag_{version_id}")).unwrap());
+        object.set_last_modified(time(last_modified));
+        client.add_object_version(&format!("{prefix}{key}"), version_id, object);
+    };
+    client.add_object(
+        &format!("{prefix}file1.txt"),
+        MockObject::constant(0xc1, 15, ETag::from_str("etag_v2").unwrap()),
+    );
+    add_version("file1.txt", "v1", 0xa1, 100);
+    add_version("file1.txt", "v2", 0xc1, 200);
+    add_version("dir/file2.txt", "v3", 0xa2, 100);
+    client.add_delete_marker(&format!("{prefix}dir/file2.txt"), "v4", time(150));
+
+    let read_dir = |ino| {
+        let fs = &fs;
+        async move {
+            let dir_handle = fs.opendir(ino, 0).await.unwrap().fh;
+            let mut reply = Default::default();
+            let _reply = fs.readdirplus(ino, dir_handle, 0, &mut reply).await.unwrap();
+            fs.releasedir(ino, dir_handle, 0).await.unwrap();
+            reply
+                .entries
+                .iter()
+                .skip(2)
+                .map(|entry| entry.name.clone())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // Versions directories are hidden from their parent's listing
+    assert_eq!(read_dir(FUSE_ROOT_INODE).await, vec![OsString::from("file1.txt")]);
+
+    let versions_dir = fs.lookup(FUSE_ROOT_INODE, "file1.txt.versions".as_ref()).await.unwrap();
+    assert_eq!(versions_dir.attr.kind, FileType::Directory);
+    assert_eq!(
+        read_dir(versions_dir.attr.ino).await,
+        vec![
+            OsString::from("1970-01-01T00-03-20Z_v2"),
+            OsString::from("1970-01-01T00-01-40Z_v1")
+        ]
+    );
+
+    let entry = fs
+        .lookup(versions_dir.attr.ino, "1970-01-01T00-01-40Z_v1".as_ref())
+        .await
+        .unwrap();
+    assert_eq!(entry.attr.kind, FileType::RegularFile);
+    let fh = fs.open(entry.attr.ino, OpenFlags::empty(), 0).await.unwrap().fh;
+    let bytes_read = fs
+        .read(entry.attr.ino, fh, 0, 4096, 0, None)
+        .await
+        .expect("fs read should succeed");
+    assert_eq!(&bytes_read[..], &[0xa1; 15]);
+    fs.release(entry.attr.ino, fh, 0, None, true).await.unwrap();
+
+    // Versions directories are read-only
+    let err = fs
+        .unlink(versions_dir.attr.ino, "1970-01-01T00-01-40Z_v1".as_ref())
+        .await
+        .expect_err("versions can't be deleted");
+    assert_eq!(err.to_errno(), libc::EPERM);
+    let mode = libc::S_IFREG | libc::S_IRWXU;
+    let err = fs
+        .mknod(versions_dir.attr.ino, "new.txt".as_ref(), mode, 0, 0)
+        .await
+        .expect_err("versions directories can't be written to");
+    assert_eq!(err.to_errno(), libc::EPERM);
+
+    // The versions directory of a directory lists its children, including deleted ones
+    let versions_dir = fs.lookup(FUSE_ROOT_INODE, "dir.versions".as_ref()).await.unwrap();
+    assert_eq!(read_dir(versions_dir.attr.ino).await, vec![OsString::from("file2.txt")]);
+    let file_versions_dir = fs.lookup(versions_dir.attr.ino, "file2.txt".as_ref()).await.unwrap();
+    assert_eq!(
+        read_dir(file_versions_dir.attr.ino).await,
+        vec![OsString::from("1970-01-01T00-01-40Z_v3")]
+    );
+
+    let entry = fs.lookup(FUSE_ROOT_INODE, "missing.versions".as_ref()).await;
+    assert!(matches!(entry, Err(e) if e.to_errno() == libc::ENOENT));
+}
+
+#[test_case(""; "unprefixed")]
+#[test_case("test_prefix/"; "prefixed")]
+#[tokio::test]
+async fn test_escape_names(prefix: &str) {
+    let prefix = Prefix::new(prefix).expect("valid prefix");
+    let config = S3FilesystemConfig {
+        escape_names: true,
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem("test_escape_names", &prefix, config);
+
+    for (key, value) in [("100%.txt", 0xa1), ("a//b", 0xa2), ("a/./c", 0xa3), ("a/../d", 0xa4)] {
+        client.add_object(
+            &format!("{prefix}{key}"),
+            MockObject::constant(value, 15, ETag::for_tests()),
+        );
+    }
+
+    let read_dir = |ino| {
+        let fs = &fs;
+        async move {
+            let dir_handle = fs.opendir(ino, 0).await.unwrap().fh;
+            let mut reply = Default::default();
+            let _reply = fs.readdirplus(ino, dir_handle, 0, &mut reply).await.unwrap();
+            fs.releasedir(ino, dir_handle, 0).await.unwrap();
+            reply
+                .entries
+                .iter()
+                .skip(2)
+                .map(|entry| entry.name.clone())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    assert_eq!(
+        read_dir(FUSE_ROOT_INODE).await,
+        vec![OsString::from("100%25.txt"), OsString::from("a")]
+    );
+    let dir = fs.lookup(FUSE_ROOT_INODE, "a".as_ref()).await.unwrap();
+    assert_eq!(
+        read_dir(dir.attr.ino).await,
+        vec![OsString::from("%"), OsString::from("%2E"), OsString::from("%2E%2E")]
+    );
+
+    for (parent, name, file, value) in [
+        ("a", "%", "b", 0xa2),
+        ("a", "%2E", "c", 0xa3),
+        ("a", "%2E%2E", "d", 0xa4),
+    ] {
+        let parent = fs.lookup(FUSE_ROOT_INODE, parent.as_ref()).await.unwrap();
+        let dir = fs.lookup(parent.attr.ino, name.as_ref()).await.unwrap();
+        assert_eq!(dir.attr.kind, FileType::Directory);
+        let entry = fs.lookup(dir.attr.ino, file.as_ref()).await.unwrap();
+        let fh = fs.open(entry.attr.ino, OpenFlags::empty(), 0).await.unwrap().fh;
+        let bytes_read = fs
+            .read(entry.attr.ino, fh, 0, 4096, 0, None)
+            .await
+            .expect("fs read should succeed");
+        assert_eq!(&bytes_read[..], &[value; 15]);
+        fs.release(entry.attr.ino, fh, 0, None, true).await.unwrap();
+    }
+
+    let entry = fs.lookup(FUSE_ROOT_INODE, "100%25.txt".as_ref()).await.unwrap();
+    assert_eq!(entry.attr.kind, FileType::RegularFile);
+
+    // Names that no key component is escaped to don't exist, and can't be created
+    for name in ["100%.txt", "%2e", "%61"] {
+        let err = fs
+            .lookup(dir.attr.ino, name.as_ref())
+            .await
+            .expect_err("not a valid escaped name");
+        assert_eq!(err.to_errno(), libc::ENOENT);
+    }
+    let mode = libc::S_IFREG | libc::S_IRWXU;
+    let err = fs
+        .mknod(dir.attr.ino, "%61".as_ref(), mode, 0, 0)
+        .await
+        .expect_err("not a valid escaped name");
+    assert_eq!(err.to_errno(), libc::EINVAL);
+
+    // A file can't have an empty name, as its key would be the one of its parent directory
+    let err = fs
+        .mknod(dir.attr.ino, "%".as_ref(), mode, 0, 0)
+        .await
+        .expect_err("files can't have an empty name");
+    assert_eq!(err.to_errno(), libc::EEXIST);
+
+    let new_dir = fs.mkdir(dir.attr.ino, "%2E%2E".as_ref(), 0, 0).await;
+    assert!(matches!(new_dir, Err(e) if e.to_errno() == libc::EEXIST));
+    let new_dir = fs.mkdir(dir.attr.ino, "new%25".as_ref(), 0, 0).await.unwrap();
+    let entry = fs.lookup(dir.attr.ino, "new%25".as_ref()).await.unwrap();
+    assert_eq!(entry.attr.ino, new_dir.attr.ino);
+    assert!(read_dir(dir.attr.ino).await.contains(&OsString::from("new%25")));
+}
+
+#[test_case(""; "unprefixed")]
+#[test_case("test_prefix/"; "prefixed")]
+#[tokio::test]
+async fn test_directory_markers(prefix: &str) {
+    let prefix = Prefix::new(prefix).expect("valid prefix");
+    let config = S3FilesystemConfig {
+        directory_markers: true,
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem("test_directory_markers", &prefix, config);
+
+    let dir = fs
+        .mkdir(FUSE_ROOT_INODE, "dir".as_ref(), libc::S_IFDIR, 0)
+        .await
+        .unwrap();
+    assert!(client.contains_key(&format!("{prefix}dir/")));
+    fs.mkdir(dir.attr.ino, "nested".as_ref(), libc::S_IFDIR, 0)
+        .await
+        .unwrap();
+    assert!(client.contains_key(&format!("{prefix}dir/nested/")));
+
+    // The directory's own marker isn't listed as a file inside it
+    let dir_handle = fs.opendir(dir.attr.ino, 0).await.unwrap().fh;
+    let mut reply = DirectoryReply::default();
+    let _reply = fs.readdirplus(dir.attr.ino, dir_handle, 0, &mut reply).await.unwrap();
+    fs.releasedir(dir.attr.ino, dir_handle, 0).await.unwrap();
+    let names = reply
+        .entries
+        .iter()
+        .skip(2)
+        .map(|entry| entry.name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec![OsString::from("nested")]);
+
+    let err = fs
+        .rmdir(FUSE_ROOT_INODE, "dir".as_ref())
+        .await
+        .expect_err("directory is not empty");
+    assert_eq!(err.to_errno(), libc::ENOTEMPTY);
+    assert!(client.contains_key(&format!("{prefix}dir/")));
+
+    fs.rmdir(dir.attr.ino, "nested".as_ref()).await.unwrap();
+    assert!(!client.contains_key(&format!("{prefix}dir/nested/")));
+    fs.rmdir(FUSE_ROOT_INODE, "dir".as_ref()).await.unwrap();
+    assert!(!client.contains_key(&format!("{prefix}dir/")));
+    let err = fs
+        .lookup(FUSE_ROOT_INODE, "dir".as_ref())
+        .await
+        .expect_err("directory was removed");
+    assert_eq!(err.to_errno(), libc::ENOENT);
+
+    // Directory markers created by other clients can be removed too
+    client.add_object(
+        &format!("{prefix}console/"),
+        MockObject::constant(0, 0, ETag::for_tests()),
+    );
+    fs.rmdir(FUSE_ROOT_INODE, "console".as_ref()).await.unwrap();
+    assert!(!client.contains_key(&format!("{prefix}console/")));
+
+    // Implicit directories have no marker to delete
+    client.add_object(
+        &format!("{prefix}implicit/file.txt"),
+        MockObject::constant(0xa1, 15, ETag::for_tests()),
+    );
+    let err = fs
+        .rmdir(FUSE_ROOT_INODE, "implicit".as_ref())
+        .await
+        .expect_err("directory is not empty");
+    assert_eq!(err.to_errno(), libc::ENOTEMPTY);
+}
+
+#[test_case(""; "unprefixed")]
+#[test_case("test_prefix/"; "prefixed")]
+#[tokio::test]
+async fn test_stable_inode_numbers(prefix: &str) {
+    let prefix = Prefix::new(prefix).expect("valid prefix");
+    let config = S3FilesystemConfig {
+        stable_inode_numbers: true,
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem("test_stable_inode_numbers", &prefix, config.clone());
+    client.add_object(
+        &format!("{prefix}dir/file.txt"),
+        MockObject::constant(0xa1, 15, ETag::for_tests()),
+    );
+    client.add_object(
+        &format!("{prefix}other.txt"),
+        MockObject::constant(0xa1, 15, ETag::for_tests()),
+    );
+
+    let dir_ino = fs.lookup(FUSE_ROOT_INODE, "dir".as_ref()).await.unwrap().attr.ino;
+    let file_ino = fs.lookup(dir_ino, "file.txt".as_ref()).await.unwrap().attr.ino;
+    let other_ino = fs.lookup(FUSE_ROOT_INODE, "other.txt".as_ref()).await.unwrap().attr.ino;
+    assert_ne!(dir_ino, file_ino);
+    assert_ne!(file_ino, other_ino);
+
+    // Another mount discovering the files in another order sees the same numbers
+    let fs = make_test_filesystem_with_client(client, "test_stable_inode_numbers", &prefix, config);
+    let dir_handle = fs.opendir(FUSE_ROOT_INODE, 0).await.unwrap().fh;
+    let mut reply = DirectoryReply::default();
+    let _reply = fs
+        .readdirplus(FUSE_ROOT_INODE, dir_handle, 0, &mut reply)
+        .await
+        .unwrap();
+    fs.releasedir(FUSE_ROOT_INODE, dir_handle, 0).await.unwrap();
+    let entries = reply
+        .entries
+        .iter()
+        .skip(2)
+        .map(|entry| (entry.name.clone(), entry.ino))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        vec![
+            (OsString::from("dir"), dir_ino),
+            (OsString::from("other.txt"), other_ino)
+        ]
+    );
+    let lookup = fs.lookup(dir_ino, "file.txt".as_ref()).await.unwrap();
+    assert_eq!(lookup.attr.ino, file_ino);
+}
+
+#[tokio::test]
+async fn test_nfs_export_forgotten_inodes() {
+    let config = S3FilesystemConfig {
+        nfs_export: true,
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem("test_nfs_export_forgotten_inodes", &Default::default(), config);
+    client.add_object("dir/sub/file.txt", MockObject::constant(0xa1, 15, ETag::for_tests()));
+
+    let dir_ino = fs.lookup(FUSE_ROOT_INODE, "dir".as_ref()).await.unwrap().attr.ino;
+    let sub_ino = fs.lookup(dir_ino, "sub".as_ref()).await.unwrap().attr.ino;
+    let file_ino = fs.lookup(sub_ino, "file.txt".as_ref()).await.unwrap().attr.ino;
+
+    // The kernel evicts the inodes, children first
+    fs.forget(file_ino, 1).await;
+    fs.forget(sub_ino, 1).await;
+    fs.forget(dir_ino, 1).await;
+    let err = fs.getattr(file_ino).await.expect_err("inode was forgotten");
+    assert_eq!(err.to_errno(), libc::ENOENT);
+
+    // An NFS client presents a file handle for the file, along with its forgotten ancestors
+    let entry = fs.lookup(file_ino, ".".as_ref()).await.unwrap();
+    assert_eq!(entry.attr.ino, file_ino);
+    assert_eq!(entry.attr.size, 15);
+    let entry = fs.lookup(sub_ino, "..".as_ref()).await.unwrap();
+    assert_eq!(entry.attr.ino, dir_ino);
+    let entry = fs.lookup(FUSE_ROOT_INODE, "..".as_ref()).await.unwrap();
+    assert_eq!(entry.attr.ino, FUSE_ROOT_INODE);
+    let err = fs
+        .lookup(file_ino, "..".as_ref())
+        .await
+        .expect_err("files have no `..` entry");
+    assert_eq!(err.to_errno(), libc::ENOTDIR);
+
+    // Once the kernel evicts the file and the directory again, the ancestors are released too
+    fs.forget(dir_ino, 1).await;
+    fs.forget(file_ino, 1).await;
+    let err = fs.getattr(sub_ino).await.expect_err("inode was forgotten");
+    assert_eq!(err.to_errno(), libc::ENOENT);
+
+    // File handles for objects that were deleted, or inodes that never existed, are stale
+    client.remove_object("dir/sub/file.txt");
+    let err = fs.lookup(file_ino, ".".as_ref()).await.expect_err("object was deleted");
+    assert_eq!(err.to_errno(), libc::ESTALE);
+    let err = fs
+        .lookup(file_ino + 1, ".".as_ref())
+        .await
+        .expect_err("inode never existed");
+    assert_eq!(err.to_errno(), libc::ESTALE);
+}
+
+#[tokio::test]
+async fn test_metadata_cache_file() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let config = S3FilesystemConfig {
+        cache_config: CacheConfig {
+            serve_lookup_from_cache: true,
+            dir_ttl: Duration::from_secs(600),
+            file_ttl: Duration::from_secs(600),
+            ..Default::default()
+        },
+        metadata_cache_file: Some(cache_dir.path().join("metadata")),
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem("test_metadata_cache_file", &Default::default(), config.clone());
+    client.add_object("dir/file.txt", MockObject::constant(0xa1, 15, ETag::for_tests()));
+
+    let dir_ino = fs.lookup(FUSE_ROOT_INODE, "dir".as_ref()).await.unwrap().attr.ino;
+    let file_attr = fs.lookup(dir_ino, "file.txt".as_ref()).await.unwrap().attr;
+    fs.destroy();
+
+    // The next mount serves the lookups from the saved metadata
+    let head_counter = client.new_counter(Operation::HeadObject);
+    let list_counter = client.new_counter(Operation::ListObjectsV2);
+    let fs = make_test_filesystem_with_client(
+        client.clone(),
+        "test_metadata_cache_file",
+        &Default::default(),
+        config.clone(),
+    );
+    let dir_ino = fs.lookup(FUSE_ROOT_INODE, "dir".as_ref()).await.unwrap().attr.ino;
+    let attr = fs.lookup(dir_ino, "file.txt".as_ref()).await.unwrap().attr;
+    assert_eq!(attr.size, file_attr.size);
+    assert_eq!(attr.mtime, file_attr.mtime);
+    assert_eq!(head_counter.count(), 0);
+    assert_eq!(list_counter.count(), 0);
+
+    // A mount with different options ignores it
+    let config = S3FilesystemConfig {
+        escape_names: true,
+        ..config
+    };
+    let fs = make_test_filesystem_with_client(client, "test_metadata_cache_file", &Default::default(), config);
+    fs.lookup(FUSE_ROOT_INODE, "dir".as_ref()).await.unwrap();
+    assert_eq!(list_counter.count(), 1);
+}
+
+#[tokio::test]
+async fn test_manifest() {
+    let manifest = r#"{"key": "dir/a.txt", "size": 15, "etag": "test_etag"}
+{"key": "dir/sub/b.txt", "size": 15, "etag": "test_etag"}
+{"key": "c.txt", "size": 15, "etag": "test_etag", "last_modified": "2024-11-01T12:00:00Z"}
+"#;
+    let manifest = Manifest::from_reader(manifest.as_bytes(), ManifestFormat::JsonLines).unwrap();
+    let config = S3FilesystemConfig {
+        manifest: Some(Arc::new(manifest)),
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem("test_manifest", &Default::default(), config);
+    client.add_object("dir/a.txt", MockObject::constant(0xa1, 15, ETag::for_tests()));
+    client.add_object("dir/sub/b.txt", MockObject::constant(0xa2, 15, ETag::for_tests()));
+    client.add_object("c.txt", MockObject::constant(0xa3, 15, ETag::for_tests()));
+    // Keys missing from the manifest are not shown
+    client.add_object("dir/unlisted.txt", MockObject::constant(0xa4, 15, ETag::for_tests()));
+    let head_counter = client.new_counter(Operation::HeadObject);
+    let list_counter = client.new_counter(Operation::ListObjectsV2);
+
+    let dir_handle = fs.opendir(FUSE_ROOT_INODE, 0).await.unwrap().fh;
+    let mut reply = DirectoryReply::default();
+    let _reply = fs
+        .readdirplus(FUSE_ROOT_INODE, dir_handle, 0, &mut reply)
+        .await
+        .unwrap();
+    fs.releasedir(FUSE_ROOT_INODE, dir_handle, 0).await.unwrap();
+    let names = reply
+        .entries
+        .iter()
+        .skip(2)
+        .map(|entry| entry.name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec![OsString::from("c.txt"), OsString::from("dir")]);
+    let c_attr = &reply.entries[2].attr;
+    assert_eq!(
+        c_attr.mtime,
+        SystemTime::from(OffsetDateTime::from_unix_timestamp(1730462400).unwrap())
+    );
+
+    let dir_ino = fs.lookup(FUSE_ROOT_INODE, "dir".as_ref()).await.unwrap().attr.ino;
+    let sub_attr = fs.lookup(dir_ino, "sub".as_ref()).await.unwrap().attr;
+    assert_eq!(sub_attr.kind, FileType::Directory);
+    let err = fs
+        .lookup(dir_ino, "unlisted.txt".as_ref())
+        .await
+        .expect_err("key is not in the manifest");
+    assert_eq!(err.to_errno(), libc::ENOENT);
+
+    let file_ino = fs.lookup(dir_ino, "a.txt".as_ref()).await.unwrap().attr.ino;
+    let fh = fs.open(file_ino, OpenFlags::empty(), 0).await.unwrap().fh;
+    let data = fs.read(file_ino, fh, 0, 4096, 0, None).await.unwrap();
+    assert_eq!(&data[..], &[0xa1; 15]);
+    fs.release(file_ino, fh, 0, None, false).await.unwrap();
+
+    // Only object data was read from S3
+    assert_eq!(head_counter.count(), 0);
+    assert_eq!(list_counter.count(), 0);
+}
+
 #[test_case(1024; "small")]
 #[test_case(50 * 1024; "large")]
 #[tokio::test]
@@ -734,6 +1267,695 @@ async fn test_duplicate_write_fails() {
     assert_eq!(err, libc::EPERM);
 }
 
+#[test_case(false; "atomic upload")]
+#[test_case(true; "incremental upload")]
+#[tokio::test]
+async fn test_overwrite_fails_on_concurrent_modification(incremental_upload: bool) {
+    const BUCKET_NAME: &str = "test_overwrite_fails_on_concurrent_modification";
+    const FILE_NAME: &str = "foo.bin";
+
+    let fs_config = S3FilesystemConfig {
+        allow_overwrite: true,
+        incremental_upload,
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem(BUCKET_NAME, &Default::default(), fs_config);
+
+    client.add_object(FILE_NAME, MockObject::constant(0xa1, 15, ETag::from("etag-at-open")));
+
+    let entry = fs.lookup(FUSE_ROOT_INODE, FILE_NAME.as_ref()).await.unwrap();
+    let file_ino = entry.attr.ino;
+    let fh = fs
+        .open(file_ino, OpenFlags::O_WRONLY | OpenFlags::O_TRUNC, 0)
+        .await
+        .unwrap()
+        .fh;
+
+    let written = fs.write(file_ino, fh, 0, &[0xaa; 27], 0, 0, None).await.unwrap();
+    assert_eq!(written, 27);
+
+    // Another writer replaces the object while the file is open
+    client.add_object(FILE_NAME, MockObject::constant(0xb2, 42, ETag::from("concurrent-etag")));
+
+    let err = fs
+        .release(file_ino, fh, 0, None, false)
+        .await
+        .expect_err("overwrite should fail if the object was modified since open")
+        .to_errno();
+    assert_eq!(err, libc::ESTALE);
+
+    // The concurrent modification was not overwritten
+    let head = client
+        .head_object(BUCKET_NAME, FILE_NAME, &HeadObjectParams::new())
+        .await
+        .unwrap();
+    assert_eq!(head.etag, ETag::from("concurrent-etag"));
+    assert_eq!(head.size, 42);
+}
+
+#[test_case(true; "keep page cache")]
+#[test_case(false; "drop page cache")]
+#[tokio::test]
+async fn test_keep_page_cache(keep_page_cache: bool) {
+    const FILE_NAME: &str = "foo.bin";
+
+    let fs_config = S3FilesystemConfig {
+        keep_page_cache,
+        allow_overwrite: true,
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem("test_keep_page_cache", &Default::default(), fs_config);
+    client.add_object(FILE_NAME, MockObject::constant(0xa1, 15, ETag::from("etag-1")));
+
+    async fn open_read(fs: &TestS3Filesystem<Arc<MockClient>>, ino: u64) -> u32 {
+        let opened = fs.open(ino, OpenFlags::empty(), 0).await.unwrap();
+        fs.release(ino, opened.fh, 0, None, false).await.unwrap();
+        opened.flags
+    }
+    let keep_cache = if keep_page_cache {
+        fuser::consts::FOPEN_KEEP_CACHE
+    } else {
+        0
+    };
+
+    // The content cached on the first open can be kept while the object is unchanged
+    let ino = fs.lookup(FUSE_ROOT_INODE, FILE_NAME.as_ref()).await.unwrap().attr.ino;
+    assert_eq!(open_read(&fs, ino).await, 0);
+    assert_eq!(open_read(&fs, ino).await, keep_cache);
+    assert_eq!(open_read(&fs, ino).await, keep_cache);
+
+    // A changed object is a new file
+    client.add_object(FILE_NAME, MockObject::constant(0xa2, 15, ETag::from("etag-2")));
+    let ino = fs.lookup(FUSE_ROOT_INODE, FILE_NAME.as_ref()).await.unwrap().attr.ino;
+    assert_eq!(open_read(&fs, ino).await, 0);
+    assert_eq!(open_read(&fs, ino).await, keep_cache);
+
+    // Overwriting the file drops the content cached before
+    let fh = fs
+        .open(ino, OpenFlags::O_WRONLY | OpenFlags::O_TRUNC, 0)
+        .await
+        .unwrap()
+        .fh;
+    fs.write(ino, fh, 0, &[0xaa; 27], 0, 0, None).await.unwrap();
+    fs.release(ino, fh, 0, None, false).await.unwrap();
+    assert_eq!(open_read(&fs, ino).await, 0);
+    assert_eq!(open_read(&fs, ino).await, keep_cache);
+}
+
+#[tokio::test]
+async fn test_upload_with_journal() {
+    const BUCKET_NAME: &str = "test_upload_with_journal";
+    const FILE_NAME: &str = "foo.bin";
+
+    let journal_dir = tempfile::tempdir().unwrap();
+    let fs_config = S3FilesystemConfig {
+        upload_journal: Some(UploadJournal::new(journal_dir.path()).unwrap()),
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem(BUCKET_NAME, &Default::default(), fs_config);
+    let complete_counter = client.new_counter(Operation::CompleteMultipartUpload);
+
+    let mode = libc::S_IFREG | libc::S_IRWXU;
+    let dentry = fs.mknod(FUSE_ROOT_INODE, FILE_NAME.as_ref(), mode, 0, 0).await.unwrap();
+    let file_ino = dentry.attr.ino;
+    let fh = fs.open(file_ino, OpenFlags::O_WRONLY, 0).await.unwrap().fh;
+
+    let part_size = client.write_part_size().unwrap();
+    let body = vec![0xaa; part_size * 2 + 17];
+    let written = fs.write(file_ino, fh, 0, &body, 0, 0, None).await.unwrap();
+    assert_eq!(written, body.len());
+    assert!(std::fs::read_dir(journal_dir.path()).unwrap().next().is_some());
+
+    fs.release(file_ino, fh, 0, None, false).await.unwrap();
+
+    assert_eq!(complete_counter.count(), 1);
+    let head = client
+        .head_object(BUCKET_NAME, FILE_NAME, &HeadObjectParams::new())
+        .await
+        .unwrap();
+    assert_eq!(head.size, body.len() as u64);
+    assert_eq!(head.etag, ETag::from_object_bytes(&body));
+    // The journal is removed once the upload completes
+    assert!(std::fs::read_dir(journal_dir.path()).unwrap().next().is_none());
+}
+
+#[test_case(false; "atomic upload")]
+#[test_case(true; "incremental upload")]
+#[tokio::test]
+async fn test_upload_with_tags(incremental_upload: bool) {
+    const BUCKET_NAME: &str = "test_upload_with_tags";
+    const FILE_NAME: &str = "foo.bin";
+
+    let object_tags = ObjectTags::from([
+        ("project".to_owned(), "blue".to_owned()),
+        ("owner".to_owned(), "".to_owned()),
+    ]);
+    let fs_config = S3FilesystemConfig {
+        incremental_upload,
+        object_tags: object_tags.clone(),
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem(BUCKET_NAME, &Default::default(), fs_config);
+
+    let mode = libc::S_IFREG | libc::S_IRWXU;
+    let dentry = fs.mknod(FUSE_ROOT_INODE, FILE_NAME.as_ref(), mode, 0, 0).await.unwrap();
+    let file_ino = dentry.attr.ino;
+    let fh = fs.open(file_ino, OpenFlags::O_WRONLY, 0).await.unwrap().fh;
+    fs.write(file_ino, fh, 0, &[0xaa; 27], 0, 0, None).await.unwrap();
+    fs.release(file_ino, fh, 0, None, false).await.unwrap();
+
+    let result = client.get_object_tagging(BUCKET_NAME, FILE_NAME).await.unwrap();
+    assert_eq!(result.tags, object_tags);
+}
+
+#[test_case(false, false; "atomic upload")]
+#[test_case(false, true; "atomic upload with journal")]
+#[test_case(true, false; "incremental upload")]
+#[tokio::test]
+async fn test_upload_with_acl(incremental_upload: bool, use_journal: bool) {
+    const BUCKET_NAME: &str = "test_upload_with_acl";
+    const FILE_NAME: &str = "foo.bin";
+
+    let journal_dir = tempfile::tempdir().unwrap();
+    let fs_config = S3FilesystemConfig {
+        incremental_upload,
+        acl: Some("bucket-owner-full-control".to_owned()),
+        upload_journal: use_journal.then(|| UploadJournal::new(journal_dir.path()).unwrap()),
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem(BUCKET_NAME, &Default::default(), fs_config);
+
+    let mode = libc::S_IFREG | libc::S_IRWXU;
+    let dentry = fs.mknod(FUSE_ROOT_INODE, FILE_NAME.as_ref(), mode, 0, 0).await.unwrap();
+    let file_ino = dentry.attr.ino;
+    let fh = fs.open(file_ino, OpenFlags::O_WRONLY, 0).await.unwrap().fh;
+    fs.write(file_ino, fh, 0, &[0xaa; 27], 0, 0, None).await.unwrap();
+    fs.release(file_ino, fh, 0, None, false).await.unwrap();
+
+    let acl = client.get_object_acl(FILE_NAME).unwrap();
+    assert_eq!(acl.as_deref(), Some("bucket-owner-full-control"));
+}
+
+#[test_case(false, false; "atomic upload")]
+#[test_case(false, true; "atomic upload with journal")]
+#[test_case(true, false; "incremental upload")]
+#[tokio::test]
+async fn test_sse_customer_key(incremental_upload: bool, use_journal: bool) {
+    const BUCKET_NAME: &str = "test_sse_customer_key";
+    const FILE_NAME: &str = "foo.bin";
+
+    let sse_customer_key = SseCustomerKey::new(&[0x42; SseCustomerKey::KEY_LENGTH]).unwrap();
+    let server_side_encryption = ServerSideEncryption::default().with_customer_key(Some(sse_customer_key.clone()));
+    let journal_dir = tempfile::tempdir().unwrap();
+    let fs_config = S3FilesystemConfig {
+        incremental_upload,
+        server_side_encryption: server_side_encryption.clone(),
+        upload_journal: use_journal.then(|| UploadJournal::new(journal_dir.path()).unwrap()),
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem(BUCKET_NAME, &Default::default(), fs_config);
+
+    let mode = libc::S_IFREG | libc::S_IRWXU;
+    let dentry = fs.mknod(FUSE_ROOT_INODE, FILE_NAME.as_ref(), mode, 0, 0).await.unwrap();
+    let file_ino = dentry.attr.ino;
+    let fh = fs.open(file_ino, OpenFlags::O_WRONLY, 0).await.unwrap().fh;
+    fs.write(file_ino, fh, 0, &[0xaa; 27], 0, 0, None).await.unwrap();
+    fs.release(file_ino, fh, 0, None, false).await.unwrap();
+
+    // The object can only be read with the key it was written with
+    let head_params = HeadObjectParams::new().sse_customer_key(Some(sse_customer_key));
+    client.head_object(BUCKET_NAME, FILE_NAME, &head_params).await.unwrap();
+    client
+        .head_object(BUCKET_NAME, FILE_NAME, &HeadObjectParams::new())
+        .await
+        .expect_err("head without the key should fail");
+
+    // A new file system with the same key can look up and read the object
+    let fs_config = S3FilesystemConfig {
+        server_side_encryption,
+        ..Default::default()
+    };
+    let fs = make_test_filesystem_with_client(client.clone(), BUCKET_NAME, &Default::default(), fs_config);
+    let file_ino = fs.lookup(FUSE_ROOT_INODE, FILE_NAME.as_ref()).await.unwrap().attr.ino;
+    let fh = fs.open(file_ino, OpenFlags::empty(), 0).await.unwrap().fh;
+    let data = fs.read(file_ino, fh, 0, 4096, 0, None).await.unwrap();
+    assert_eq!(&data[..], &[0xaa; 27]);
+    fs.release(file_ino, fh, 0, None, false).await.unwrap();
+
+    // A file system without the key cannot
+    let fs = make_test_filesystem_with_client(client, BUCKET_NAME, &Default::default(), Default::default());
+    let err = fs
+        .lookup(FUSE_ROOT_INODE, FILE_NAME.as_ref())
+        .await
+        .expect_err("lookup without the key should fail");
+    assert_eq!(err.to_errno(), libc::EIO);
+}
+
+#[test_case(false, false; "atomic upload")]
+#[test_case(false, true; "atomic upload with journal")]
+#[test_case(true, false; "incremental upload")]
+#[tokio::test]
+async fn test_object_lock(incremental_upload: bool, use_journal: bool) {
+    const BUCKET_NAME: &str = "test_object_lock";
+    const FILE_NAME: &str = "foo.bin";
+
+    let retain_until_date = OffsetDateTime::now_utc() + Duration::from_secs(24 * 60 * 60);
+    let object_lock = ObjectLock::new()
+        .retention("GOVERNANCE".to_owned(), retain_until_date)
+        .legal_hold(true);
+    let journal_dir = tempfile::tempdir().unwrap();
+    let fs_config = S3FilesystemConfig {
+        incremental_upload,
+        allow_delete: true,
+        object_lock: object_lock.clone(),
+        upload_journal: use_journal.then(|| UploadJournal::new(journal_dir.path()).unwrap()),
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem(BUCKET_NAME, &Default::default(), fs_config);
+
+    let mode = libc::S_IFREG | libc::S_IRWXU;
+    let dentry = fs.mknod(FUSE_ROOT_INODE, FILE_NAME.as_ref(), mode, 0, 0).await.unwrap();
+    let file_ino = dentry.attr.ino;
+    let fh = fs.open(file_ino, OpenFlags::O_WRONLY, 0).await.unwrap().fh;
+    fs.write(file_ino, fh, 0, &[0xaa; 27], 0, 0, None).await.unwrap();
+    fs.release(file_ino, fh, 0, None, false).await.unwrap();
+
+    assert_eq!(client.get_object_lock(FILE_NAME).unwrap(), object_lock);
+
+    let err = fs
+        .unlink(FUSE_ROOT_INODE, FILE_NAME.as_ref())
+        .await
+        .expect_err("unlink of a locked object should fail");
+    assert_eq!(err.to_errno(), libc::EPERM);
+    assert!(client.contains_key(FILE_NAME));
+
+    // A file system without Object Lock settings also refuses to unlink the locked object
+    let fs_config = S3FilesystemConfig {
+        allow_delete: true,
+        ..Default::default()
+    };
+    let fs = make_test_filesystem_with_client(client.clone(), BUCKET_NAME, &Default::default(), fs_config);
+    fs.lookup(FUSE_ROOT_INODE, FILE_NAME.as_ref()).await.unwrap();
+    let err = fs
+        .unlink(FUSE_ROOT_INODE, FILE_NAME.as_ref())
+        .await
+        .expect_err("unlink of a locked object should fail");
+    assert_eq!(err.to_errno(), libc::EPERM);
+    assert!(client.contains_key(FILE_NAME));
+}
+
+#[test_case(false, false; "atomic upload")]
+#[test_case(false, true; "atomic upload with journal")]
+#[test_case(true, false; "incremental upload")]
+#[tokio::test]
+async fn test_infer_content_type(incremental_upload: bool, use_journal: bool) {
+    const BUCKET_NAME: &str = "test_infer_content_type";
+
+    let journal_dir = tempfile::tempdir().unwrap();
+    let fs_config = S3FilesystemConfig {
+        incremental_upload,
+        content_types: Some(ContentTypeMap::new()),
+        upload_journal: use_journal.then(|| UploadJournal::new(journal_dir.path()).unwrap()),
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem(BUCKET_NAME, &Default::default(), fs_config);
+
+    let files = [
+        ("index.html", Some("text/html")),
+        ("data.JSON", Some("application/json")),
+        ("README", None),
+    ];
+    for (name, _) in files {
+        let mode = libc::S_IFREG | libc::S_IRWXU;
+        let dentry = fs.mknod(FUSE_ROOT_INODE, name.as_ref(), mode, 0, 0).await.unwrap();
+        let file_ino = dentry.attr.ino;
+        let fh = fs.open(file_ino, OpenFlags::O_WRONLY, 0).await.unwrap().fh;
+        fs.write(file_ino, fh, 0, &[0xaa; 27], 0, 0, None).await.unwrap();
+        fs.release(file_ino, fh, 0, None, false).await.unwrap();
+    }
+
+    for (name, expected) in files {
+        let content_type = client.get_object_content_type(name).unwrap();
+        assert_eq!(content_type.as_deref(), expected, "wrong content type for {name}");
+    }
+}
+
+#[tokio::test]
+async fn test_tag_xattrs() {
+    const BUCKET_NAME: &str = "test_tag_xattrs";
+
+    let fs_config = S3FilesystemConfig {
+        enable_xattrs: true,
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem(BUCKET_NAME, &Default::default(), fs_config);
+    let mut object = MockObject::constant(0xa1, 15, ETag::for_tests());
+    object.set_object_tags(ObjectTags::from([("project".to_owned(), "blue".to_owned())]));
+    client.add_object("remote.bin", object);
+
+    let file_ino = fs
+        .lookup(FUSE_ROOT_INODE, "remote.bin".as_ref())
+        .await
+        .unwrap()
+        .attr
+        .ino;
+
+    let value = fs.getxattr(file_ino, "user.s3.tag.project".as_ref()).await.unwrap();
+    assert_eq!(value, b"blue");
+    let err = fs
+        .getxattr(file_ino, "user.s3.tag.missing".as_ref())
+        .await
+        .expect_err("tag does not exist")
+        .to_errno();
+    assert_eq!(err, libc::ENODATA);
+    let err = fs
+        .getxattr(file_ino, "security.capability".as_ref())
+        .await
+        .expect_err("only tag attributes are supported")
+        .to_errno();
+    assert_eq!(err, libc::ENODATA);
+
+    fs.setxattr(file_ino, "user.s3.tag.env".as_ref(), b"prod", 0)
+        .await
+        .unwrap();
+    let err = fs
+        .setxattr(file_ino, "user.s3.tag.env".as_ref(), b"dev", libc::XATTR_CREATE)
+        .await
+        .expect_err("tag already exists")
+        .to_errno();
+    assert_eq!(err, libc::EEXIST);
+    let err = fs
+        .setxattr(file_ino, "user.other".as_ref(), b"value", 0)
+        .await
+        .expect_err("only tag attributes are supported")
+        .to_errno();
+    assert_eq!(err, libc::ENOTSUP);
+
+    let names = fs.listxattr(file_ino).await.unwrap();
+    assert_eq!(names, b"user.s3.tag.env\0user.s3.tag.project\0");
+
+    fs.removexattr(file_ino, "user.s3.tag.project".as_ref()).await.unwrap();
+    let result = client.get_object_tagging(BUCKET_NAME, "remote.bin").await.unwrap();
+    assert_eq!(result.tags, ObjectTags::from([("env".to_owned(), "prod".to_owned())]));
+
+    // Files that have not been uploaded yet have no tags
+    let mode = libc::S_IFREG | libc::S_IRWXU;
+    let local_ino = fs
+        .mknod(FUSE_ROOT_INODE, "local.bin".as_ref(), mode, 0, 0)
+        .await
+        .unwrap()
+        .attr
+        .ino;
+    assert!(fs.listxattr(local_ino).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_xattrs_disabled() {
+    const BUCKET_NAME: &str = "test_xattrs_disabled";
+
+    let (client, fs) = make_test_filesystem(BUCKET_NAME, &Default::default(), Default::default());
+    client.add_object("remote.bin", MockObject::constant(0xa1, 15, ETag::for_tests()));
+    let get_tagging_counter = client.new_counter(Operation::GetObjectTagging);
+
+    let file_ino = fs
+        .lookup(FUSE_ROOT_INODE, "remote.bin".as_ref())
+        .await
+        .unwrap()
+        .attr
+        .ino;
+
+    // ENOSYS tells the kernel to stop sending extended attribute requests
+    let err = fs
+        .getxattr(file_ino, "security.capability".as_ref())
+        .await
+        .expect_err("xattrs are disabled")
+        .to_errno();
+    assert_eq!(err, libc::ENOSYS);
+    let err = fs
+        .listxattr(file_ino)
+        .await
+        .expect_err("xattrs are disabled")
+        .to_errno();
+    assert_eq!(err, libc::ENOSYS);
+    let err = fs
+        .setxattr(file_ino, "user.s3.tag.env".as_ref(), b"prod", 0)
+        .await
+        .expect_err("xattrs are disabled")
+        .to_errno();
+    assert_eq!(err, libc::ENOSYS);
+    let err = fs
+        .removexattr(file_ino, "user.s3.tag.env".as_ref())
+        .await
+        .expect_err("xattrs are disabled")
+        .to_errno();
+    assert_eq!(err, libc::ENOSYS);
+    assert_eq!(get_tagging_counter.count(), 0);
+}
+
+#[tokio::test]
+async fn test_tag_xattrs_cached() {
+    const BUCKET_NAME: &str = "test_tag_xattrs_cached";
+
+    let fs_config = S3FilesystemConfig {
+        cache_config: CacheConfig {
+            serve_lookup_from_cache: true,
+            dir_ttl: Duration::from_secs(600),
+            file_ttl: Duration::from_secs(600),
+            ..Default::default()
+        },
+        enable_xattrs: true,
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem(BUCKET_NAME, &Default::default(), fs_config);
+    let mut object = MockObject::constant(0xa1, 15, ETag::for_tests());
+    object.set_object_tags(ObjectTags::from([("project".to_owned(), "blue".to_owned())]));
+    client.add_object("remote.bin", object);
+    let get_tagging_counter = client.new_counter(Operation::GetObjectTagging);
+
+    let file_ino = fs
+        .lookup(FUSE_ROOT_INODE, "remote.bin".as_ref())
+        .await
+        .unwrap()
+        .attr
+        .ino;
+
+    // The tags are fetched once, and then served from the stat of the inode
+    assert_eq!(fs.listxattr(file_ino).await.unwrap(), b"user.s3.tag.project\0");
+    let value = fs.getxattr(file_ino, "user.s3.tag.project".as_ref()).await.unwrap();
+    assert_eq!(value, b"blue");
+    assert_eq!(get_tagging_counter.count(), 1);
+
+    // Changing a tag updates the cached tags
+    fs.setxattr(file_ino, "user.s3.tag.env".as_ref(), b"prod", 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        fs.listxattr(file_ino).await.unwrap(),
+        b"user.s3.tag.env\0user.s3.tag.project\0"
+    );
+    assert_eq!(get_tagging_counter.count(), 1);
+}
+
+#[tokio::test]
+async fn test_listxattr_tagging_denied() {
+    const BUCKET_NAME: &str = "test_listxattr_tagging_denied";
+
+    let client = Arc::new(MockClient::new(MockClientConfig {
+        bucket: BUCKET_NAME.to_string(),
+        part_size: 1024 * 1024,
+        ..Default::default()
+    }));
+    let mut object = MockObject::constant(0xa1, 15, ETag::for_tests());
+    object.set_object_tags(ObjectTags::from([("project".to_owned(), "blue".to_owned())]));
+    client.add_object("remote.bin", object);
+
+    let mut get_object_tagging_failures = HashMap::new();
+    get_object_tagging_failures.insert(1, ObjectClientError::ClientError(MockClientError("denied".into())));
+    get_object_tagging_failures.insert(2, ObjectClientError::ClientError(MockClientError("denied".into())));
+    let failure_client = countdown_failure_client(
+        client.clone(),
+        CountdownFailureConfig {
+            get_object_tagging_failures,
+            ..Default::default()
+        },
+    );
+    let fs_config = S3FilesystemConfig {
+        enable_xattrs: true,
+        ..Default::default()
+    };
+    let fs = make_test_filesystem_with_client(Arc::new(failure_client), BUCKET_NAME, &Default::default(), fs_config);
+
+    let file_ino = fs
+        .lookup(FUSE_ROOT_INODE, "remote.bin".as_ref())
+        .await
+        .unwrap()
+        .attr
+        .ino;
+
+    // Copying tools list the attributes of every file, so listing succeeds with no attributes
+    assert!(fs.listxattr(file_ino).await.unwrap().is_empty());
+    let err = fs
+        .getxattr(file_ino, "user.s3.tag.project".as_ref())
+        .await
+        .expect_err("tags cannot be read")
+        .to_errno();
+    assert_eq!(err, libc::EIO);
+
+    // Once the tags can be read again, they are listed
+    assert_eq!(fs.listxattr(file_ino).await.unwrap(), b"user.s3.tag.project\0");
+}
+
+#[tokio::test]
+async fn test_restore_xattr() {
+    const BUCKET_NAME: &str = "test_restore_xattr";
+
+    let fs_config = S3FilesystemConfig {
+        enable_xattrs: true,
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem(BUCKET_NAME, &Default::default(), fs_config);
+    let mut object = MockObject::from(b"hello world");
+    object.set_storage_class(Some("GLACIER".to_owned()));
+    client.add_object("archived.bin", object);
+    client.add_object("standard.bin", MockObject::from(b"hello world"));
+
+    let archived_ino = fs
+        .lookup(FUSE_ROOT_INODE, "archived.bin".as_ref())
+        .await
+        .unwrap()
+        .attr
+        .ino;
+    let value = fs.getxattr(archived_ino, "user.s3.restore".as_ref()).await.unwrap();
+    assert_eq!(value, b"archived");
+    let err = fs
+        .open(archived_ino, OpenFlags::empty(), 0)
+        .await
+        .expect_err("can't open archived objects")
+        .to_errno();
+    assert_eq!(err, libc::EACCES);
+
+    let err = fs
+        .setxattr(archived_ino, "user.s3.restore".as_ref(), b"tier=Bulk", 0)
+        .await
+        .expect_err("number of days is required")
+        .to_errno();
+    assert_eq!(err, libc::EINVAL);
+    fs.setxattr(archived_ino, "user.s3.restore".as_ref(), b"days=7;tier=Bulk", 0)
+        .await
+        .unwrap();
+    assert!(client.is_object_restored("archived.bin").unwrap());
+
+    let value = fs.getxattr(archived_ino, "user.s3.restore".as_ref()).await.unwrap();
+    let value = String::from_utf8(value).unwrap();
+    assert!(
+        value.starts_with("restored; expiry="),
+        "unexpected restore status {value:?}"
+    );
+
+    // Once the object is looked up again, it becomes readable
+    let lookup = fs.lookup(FUSE_ROOT_INODE, "archived.bin".as_ref()).await.unwrap();
+    assert_ne!(lookup.attr.perm, 0);
+    let fh = fs.open(archived_ino, OpenFlags::empty(), 0).await.unwrap().fh;
+    let data = fs.read(archived_ino, fh, 0, 11, 0, None).await.unwrap();
+    assert_eq!(&data[..], b"hello world");
+    fs.release(archived_ino, fh, 0, None, true).await.unwrap();
+
+    // Objects in other storage classes have no restore status and can't be restored
+    let standard_ino = fs
+        .lookup(FUSE_ROOT_INODE, "standard.bin".as_ref())
+        .await
+        .unwrap()
+        .attr
+        .ino;
+    let err = fs
+        .getxattr(standard_ino, "user.s3.restore".as_ref())
+        .await
+        .expect_err("standard objects have no restore status")
+        .to_errno();
+    assert_eq!(err, libc::ENODATA);
+    let err = fs
+        .setxattr(standard_ino, "user.s3.restore".as_ref(), b"days=7", 0)
+        .await
+        .expect_err("standard objects can't be restored")
+        .to_errno();
+    assert_eq!(err, libc::EINVAL);
+}
+
+#[tokio::test]
+async fn test_delete_recursive_xattr() {
+    const BUCKET_NAME: &str = "test_delete_recursive_xattr";
+
+    let fs_config = S3FilesystemConfig {
+        allow_delete: true,
+        enable_xattrs: true,
+        ..Default::default()
+    };
+    let (client, fs) = make_test_filesystem(BUCKET_NAME, &Default::default(), fs_config);
+    for i in 0..2500 {
+        client.add_object(
+            &format!("dir/{}/file{i}", i % 10),
+            MockObject::constant(0xa1, 15, ETag::for_tests()),
+        );
+    }
+    client.add_object("dir2/file", MockObject::constant(0xa1, 15, ETag::for_tests()));
+    client.add_object("other", MockObject::constant(0xa1, 15, ETag::for_tests()));
+
+    let dir_ino = fs.lookup(FUSE_ROOT_INODE, "dir".as_ref()).await.unwrap().attr.ino;
+    let file_ino = fs.lookup(FUSE_ROOT_INODE, "other".as_ref()).await.unwrap().attr.ino;
+
+    let err = fs
+        .setxattr(dir_ino, "user.s3.delete".as_ref(), b"yes", 0)
+        .await
+        .expect_err("only recursive deletes are supported")
+        .to_errno();
+    assert_eq!(err, libc::EINVAL);
+    let err = fs
+        .setxattr(file_ino, "user.s3.delete".as_ref(), b"recursive", 0)
+        .await
+        .expect_err("files can't be deleted recursively")
+        .to_errno();
+    assert_eq!(err, libc::ENOTDIR);
+
+    let delete_counter = client.new_counter(Operation::DeleteObjects);
+    fs.setxattr(dir_ino, "user.s3.delete".as_ref(), b"recursive", 0)
+        .await
+        .unwrap();
+    assert_eq!(delete_counter.count(), 3);
+    assert_eq!(client.object_count(), 2);
+    assert!(client.contains_key("dir2/file"));
+    let err = fs
+        .lookup(FUSE_ROOT_INODE, "dir".as_ref())
+        .await
+        .expect_err("directory was deleted")
+        .to_errno();
+    assert_eq!(err, libc::ENOENT);
+
+    // Objects S3 fails to delete are reported, and don't stop the others from being deleted
+    let retain_until_date = OffsetDateTime::now_utc() + Duration::from_secs(24 * 60 * 60);
+    let mut locked = MockObject::constant(0xa1, 15, ETag::for_tests());
+    locked.set_object_lock(ObjectLock::new().retention("COMPLIANCE".to_owned(), retain_until_date));
+    client.add_object("dir2/locked", locked);
+    let dir_ino = fs.lookup(FUSE_ROOT_INODE, "dir2".as_ref()).await.unwrap().attr.ino;
+    let err = fs
+        .setxattr(dir_ino, "user.s3.delete".as_ref(), b"recursive", 0)
+        .await
+        .expect_err("locked objects can't be deleted")
+        .to_errno();
+    assert_eq!(err, libc::EIO);
+    assert!(!client.contains_key("dir2/file"));
+    assert!(client.contains_key("dir2/locked"));
+
+    // Recursive deletes require --allow-delete
+    let fs = make_test_filesystem_with_client(client, BUCKET_NAME, &Default::default(), Default::default());
+    let dir_ino = fs.lookup(FUSE_ROOT_INODE, "dir2".as_ref()).await.unwrap().attr.ino;
+    let err = fs
+        .setxattr(dir_ino, "user.s3.delete".as_ref(), b"recursive", 0)
+        .await
+        .expect_err("deletes are disabled")
+        .to_errno();
+    assert_eq!(err, libc::EPERM);
+}
+
 #[tokio::test]
 async fn test_upload_aborted_on_write_failure() {
     const BUCKET_NAME: &str = "test_upload_aborted_on_write_failure";
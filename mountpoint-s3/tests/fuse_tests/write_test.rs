@@ -7,6 +7,7 @@ use std::process::Command;
 use std::thread;
 use std::time::Duration;
 
+use mountpoint_s3_client::checksums::{crc64, crc64nvme_to_base64};
 use mountpoint_s3_client::types::{Checksum, ChecksumAlgorithm, PutObjectSingleParams, UploadChecksum};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
@@ -1354,10 +1355,12 @@ fn concurrent_open_for_write_test(max_files: usize) {
 #[derive(Clone, Copy)]
 enum UploadChecksumsMode {
     Enabled,
+    Crc64nvme,
     Disabled,
 }
 
 const CHECKSUMS_ENABLED: UploadChecksumsMode = UploadChecksumsMode::Enabled;
+const CHECKSUMS_CRC64NVME: UploadChecksumsMode = UploadChecksumsMode::Crc64nvme;
 const CHECKSUMS_DISABLED: UploadChecksumsMode = UploadChecksumsMode::Disabled;
 
 fn write_checksums_test(
@@ -1370,7 +1373,11 @@ fn write_checksums_test(
 
     let config = TestSessionConfig {
         filesystem_config: S3FilesystemConfig {
-            use_upload_checksums: matches!(checksums_mode, UploadChecksumsMode::Enabled),
+            upload_checksum_algorithm: match checksums_mode {
+                UploadChecksumsMode::Enabled => Some(ChecksumAlgorithm::Crc32c),
+                UploadChecksumsMode::Crc64nvme => Some(ChecksumAlgorithm::Crc64nvme),
+                UploadChecksumsMode::Disabled => None,
+            },
             ..Default::default()
         }
         .upload_mode(upload_mode),
@@ -1415,6 +1422,14 @@ fn write_checksums_test(
                 });
             assert!(object_crc32c || parts_crc32c, "crc32c is used for trailing checksums");
         }
+        UploadChecksumsMode::Crc64nvme => {
+            // CRC64NVME checksums cover the full object, so they match a checksum of the local data.
+            let expected_checksum = crc64nvme_to_base64(&crc64::checksum(&body));
+            assert_eq!(
+                object_checksum.and_then(|checksum| checksum.checksum_crc64nvme),
+                Some(expected_checksum),
+            );
+        }
         UploadChecksumsMode::Disabled => {
             // For S3 Standard, the list of parts is only present if checksums were used, but for S3
             // Express One Zone the list of parts is always present. The important thing is just that
@@ -1445,7 +1460,7 @@ fn write_checksums_test_s3_incremental_upload(checksums_mode: UploadChecksumsMod
     write_checksums_test(fuse::s3_session::new, checksums_mode, INCREMENTAL_UPLOAD);
 }
 
-#[test_matrix([CHECKSUMS_ENABLED, CHECKSUMS_DISABLED], [ATOMIC_UPLOAD, INCREMENTAL_UPLOAD])]
+#[test_matrix([CHECKSUMS_ENABLED, CHECKSUMS_CRC64NVME, CHECKSUMS_DISABLED], [ATOMIC_UPLOAD, INCREMENTAL_UPLOAD])]
 fn write_checksums_test_mock(checksums_mode: UploadChecksumsMode, upload_mode: UploadMode) {
     write_checksums_test(fuse::mock_session::new, checksums_mode, upload_mode);
 }